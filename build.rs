@@ -7,4 +7,14 @@ fn main() {
         println!("cargo:rustc-link-lib=dylib=stdc++");
         println!("cargo:rustc-link-lib=c++_shared");
     }
+
+    // Secondary windows are only supported on desktop platforms
+    let target_arch = env::var("CARGO_CFG_TARGET_ARCH");
+    println!("cargo:rustc-check-cfg=cfg(multi_window)");
+    if env::var("CARGO_FEATURE_MULTI_WINDOW").is_ok()
+        && !matches!(target_os.as_deref(), Ok("android"))
+        && !matches!(target_arch.as_deref(), Ok("wasm32"))
+    {
+        println!("cargo:rustc-cfg=multi_window");
+    }
 }
@@ -1,22 +1,26 @@
+use shipyard::IntoIter;
 use shura::prelude::*;
 
 #[shura::app]
 fn app(config: AppConfig) {
     App::run(config, || {
         Scene::new()
-            .entity::<Cube>()
             .system(System::update(update))
             .system(System::setup(setup))
             .system(System::render(render))
     });
 }
 
+const LIGHT_HEIGHT: f32 = 3.0;
+const LIGHT_ORBIT: f32 = 8.0;
+const LIGHT_SPEED: f32 = 0.8;
+const MARKER_SIZE: u32 = 32;
+
 fn setup(ctx: &mut Context) {
     const NUM_INSTANCES_PER_ROW: u32 = 10;
     const SPACE_BETWEEN: f32 = 3.0;
-    ctx.entities.get_mut().add_many(
-        ctx.physics,
-        (0..NUM_INSTANCES_PER_ROW).flat_map(|z| {
+    ctx.world
+        .bulk_add_entity((0..NUM_INSTANCES_PER_ROW).flat_map(|z| {
             (0..NUM_INSTANCES_PER_ROW).map(move |x| {
                 let x = SPACE_BETWEEN * (x as f32 - NUM_INSTANCES_PER_ROW as f32 / 2.0);
                 let z = SPACE_BETWEEN * (z as f32 - NUM_INSTANCES_PER_ROW as f32 / 2.0);
@@ -26,9 +30,20 @@ fn setup(ctx: &mut Context) {
                     position: position.into(),
                 }
             })
-        }),
+        }));
+
+    *ctx.lights3d = Lights3D::new(Color::new(0.1, 0.1, 0.12, 1.0));
+    ctx.lights3d.set_directional(
+        DirectionalLight3D::new(Vector3::new(-0.3, -1.0, -0.5), Color::WHITE).intensity(0.4),
+    );
+    ctx.lights3d.add_point(
+        PointLight3D::new(Vector3::new(0.0, LIGHT_HEIGHT, 0.0), 12.0, Color::ORANGE).intensity(3.0),
     );
 
+    ctx.assets.load_sprite(
+        "marker",
+        SpriteBuilder::raw(Vector2::new(MARKER_SIZE, MARKER_SIZE), &marker_pixels()),
+    );
     ctx.assets.load_model(
         "cube",
         ModelBuilder::bytes(
@@ -44,8 +59,36 @@ fn setup(ctx: &mut Context) {
 
 fn update(ctx: &mut Context) {
     const SPEED: f32 = 7.0;
+    const MOUSE_SENSITIVITY: f32 = 0.003;
     let speed = SPEED * ctx.time.delta();
+
+    // Toggle mouse look with M, Escape releases the cursor
+    if ctx.input.is_pressed(Key::KeyM) {
+        ctx.input.set_cursor_grab(match ctx.input.cursor_grab() {
+            CursorGrabMode::None => CursorGrabMode::Locked,
+            _ => CursorGrabMode::None,
+        });
+    }
+    if ctx.input.is_pressed(Key::Escape) {
+        ctx.input.set_cursor_grab(CursorGrabMode::None);
+    }
+
     let camera = ctx.world_camera3d.perspective_mut().unwrap();
+    if ctx.input.cursor_grab() == CursorGrabMode::Locked {
+        let delta = ctx.input.mouse_delta() * MOUSE_SENSITIVITY;
+        let forward = camera.target - camera.eye;
+        let right = UnitVector3::new_normalize(forward.cross(&camera.up));
+        let up = UnitVector3::new_normalize(camera.up);
+        let yaw = Rotation3::from_axis_angle(&up, -delta.x);
+        let pitched = Rotation3::from_axis_angle(&right, -delta.y) * forward;
+        // Stop pitching before looking straight up or down
+        let forward = if pitched.normalize().dot(&up).abs() < 0.99 {
+            yaw * pitched
+        } else {
+            yaw * forward
+        };
+        camera.target = camera.eye + forward;
+    }
 
     let forward = camera.target - camera.eye;
     let forward_norm = forward.normalize();
@@ -70,28 +113,86 @@ fn update(ctx: &mut Context) {
         camera.eye = camera.target - (forward - right * speed).normalize() * forward_mag;
     }
 
-    for cube in ctx.entities.get_mut::<Cube>().iter_mut() {
+    // The point light circles above the grid
+    let angle = ctx.time.total() * LIGHT_SPEED;
+    let light = Vector3::new(
+        angle.cos() * LIGHT_ORBIT,
+        LIGHT_HEIGHT,
+        angle.sin() * LIGHT_ORBIT,
+    );
+    ctx.lights3d.points_mut()[0].position = light;
+
+    let mut cubes = ctx.world.view_mut::<Cube>();
+    for cube in (&mut cubes).iter() {
         cube.position.rotation *= Rotation3::new(Vector3::new(
             1.0 * ctx.time.delta(),
             1.0 * ctx.time.delta(),
             1.0 * ctx.time.delta(),
         ));
     }
+    ctx.assets.write_instances("cubes", false, |data| {
+        for cube in cubes.iter() {
+            data.push(Instance3D::new(cube.position, Vector3::new(1.0, 1.0, 1.0)))
+        }
+    });
+
+    // Upright markers floating over the cubes and a marker facing the camera at the light.
+    // The cutoff discards the transparent corners, so the markers need no sorting.
+    ctx.assets.write_instances("markers", false, |data| {
+        for cube in cubes.iter() {
+            data.push(
+                BillboardInstance3D::new(
+                    cube.position.translation.vector + Vector3::new(0.0, 1.8, 0.0),
+                    Vector2::new(0.6, 0.6),
+                )
+                .cylindrical(true)
+                .color(Color::LIME)
+                .alpha_cutoff(0.5),
+            );
+        }
+        data.push(
+            BillboardInstance3D::new(light, Vector2::new(1.0, 1.0))
+                .color(Color::ORANGE)
+                .alpha_cutoff(0.5),
+        );
+    });
 }
 
 fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
-    encoder.render3d(Some(Color::new_rgba(220, 220, 220, 255)), |renderer| {
-        renderer.draw_model(
-            &ctx.write_instance_entities::<Cube, _>("cubes", |cube, data| {
-                data.push(Instance3D::new(cube.position, Vector3::new(1.0, 1.0, 1.0)))
-            }),
+    encoder.render3d(Some(Color::new_rgba(20, 20, 30, 255)), |renderer| {
+        renderer.draw_model_lit(
+            &ctx.assets.instances("cubes"),
             &ctx.assets.model("cube"),
             &ctx.default_assets.world_camera3d,
+            &ctx.default_assets.lights3d,
+        );
+        renderer.draw_billboards(
+            &ctx.assets.instances("markers"),
+            &ctx.default_assets.world_camera3d,
+            &ctx.assets.sprite("marker"),
         );
     });
 }
 
-#[derive(Entity)]
+#[derive(Component)]
 struct Cube {
     position: Isometry3<f32>,
 }
+
+/// White diamond on a transparent background, tinted per instance
+fn marker_pixels() -> Vec<u8> {
+    let mut pixels = Vec::with_capacity((MARKER_SIZE * MARKER_SIZE * 4) as usize);
+    let center = (MARKER_SIZE as f32 - 1.0) / 2.0;
+    for y in 0..MARKER_SIZE {
+        for x in 0..MARKER_SIZE {
+            let distance = ((x as f32 - center).abs() + (y as f32 - center).abs()) / center;
+            let color = if distance < 1.0 {
+                Color::WHITE
+            } else {
+                Color::TRANSPARENT
+            };
+            pixels.extend_from_slice(&color.to_rgba());
+        }
+    }
+    pixels
+}
@@ -1,11 +1,14 @@
-use rayon::iter::ParallelExtend;
+use rayon::iter::ParallelIterator;
 use shipyard::{IntoIter, IntoWithId, Remove};
 use shura::prelude::*;
 
 #[shura::app]
-fn app(config: AppConfig) {
+fn app(mut config: AppConfig) {
+    config.gpu.profiling = true;
+    config.alt_enter_fullscreen = true;
     App::run(config, || {
         Scene::new()
+            .system(System::fixed_update(fixed_update, 60))
             .system(System::update(update))
             .system(System::setup(setup))
             .system(System::render(render))
@@ -14,21 +17,45 @@ fn app(config: AppConfig) {
 
 fn setup(ctx: &mut Context) {
     ctx.world_camera2d.set_scaling(WorldCameraScaling::Vertical(3.0));
+    ctx.screen_config.set_ui_scaling(UiScaling::FitHeight(1080.0));
     ctx.assets.load_font(
         "font",
         FontBuilder::bytes(include_resource_bytes!("bunnymark/novem.ttf")),
     );
-    ctx.assets.load_text_mesh::<&str>("text", "font", &[]);
+    ctx.assets.load_text::<&str>("text", "font", &[]);
     ctx.assets.load_sprite(
         "bunny_sprite",
         SpriteBuilder::bytes(include_resource_bytes!("bunnymark/wabbit.png")),
     );
     ctx.world.add_entity(Bunny::new(Default::default()));
+    ctx.diagnostics.track_component::<Bunny>();
 }
 
 fn update(ctx: &mut Context) {
     const MODIFY_STEP: usize = 1500;
-    const GRAVITY: f32 = -2.5;
+
+    if ctx.input.is_pressed(Key::F3) {
+        let shown = ctx.diagnostics.is_shown();
+        ctx.diagnostics.show(!shown);
+    }
+
+    if ctx.input.is_pressed(Key::F4) {
+        if ctx.diagnostics.is_recording() {
+            ctx.diagnostics.stop_recording();
+        } else {
+            ctx.diagnostics.record_to(
+                "bunnymark.csv",
+                RecordConfig {
+                    every_n_frames: 1,
+                    columns: vec![
+                        RecordColumn::FrameTime,
+                        RecordColumn::entity_count::<Bunny>(),
+                        RecordColumn::DrawCalls,
+                    ],
+                },
+            );
+        }
+    }
 
     if ctx.input.is_held(MouseButton::Left) || ctx.input.is_held(ScreenTouch) {
         let cursor: Vector2<f32> = ctx.cursor.coords;
@@ -36,8 +63,8 @@ fn update(ctx: &mut Context) {
             .bulk_add_entity((0..MODIFY_STEP).into_iter().map(|_| Bunny::new(cursor)));
     }
 
-    let mut bunnies = ctx.world.view_mut::<Bunny>();
     if ctx.input.is_held(MouseButton::Right) {
+        let mut bunnies = ctx.world.view_mut::<Bunny>();
         let mut to_delete = Vec::new();
         for (i, (entity, _)) in bunnies.iter().with_id().enumerate() {
             if i >= MODIFY_STEP {
@@ -51,51 +78,78 @@ fn update(ctx: &mut Context) {
         }
     }
 
+    let bunnies = ctx.world.view::<Bunny>();
+
+    let anchor = UiAnchor::TopRight.position(
+        &ctx.assets.default_assets().ui_camera.1,
+        vector!(-20.0, -20.0),
+    );
     ctx.assets.write_text(
         "text",
         "font",
-        &[TextSection {
-            color: Color::RED,
-            text: format!("FPS: {}\nBunnies: {}", ctx.time.fps(), bunnies.len()),
-            size: 0.05,
+        &[TextSection::<&str> {
+            color: Color::BLACK,
+            spans: TextSpan::parse_markup(&format!(
+                "FPS: <red>{}</red>\nBunnies: <b><blue>{}</blue></b>",
+                ctx.time.fps(),
+                bunnies.len()
+            )),
+            size: 50.0,
+            offset: Isometry2::new(anchor, 0.0),
             horizontal_alignment: TextAlignment::End,
             vertical_alignment: TextAlignment::End,
             ..Default::default()
         }],
     );
 
-    let delta = ctx.time.delta();
+    let alpha = ctx.time.alpha();
+    let camera = ctx.world_camera2d.aabb();
+    let disabled = ctx.world.view::<DisabledComponent>();
+    ctx.assets.write_instances_par(
+        "bunny_instances",
+        false,
+        &bunnies,
+        &disabled,
+        false,
+        |bunny| {
+            camera
+                .intersects(&bunny.position.aabb(bunny.scaling))
+                .then(|| bunny.position.instance(alpha, bunny.scaling, ()))
+        },
+    );
+}
+
+fn fixed_update(ctx: &mut Context) {
+    const GRAVITY: f32 = -2.5;
+    let delta = ctx.time.fixed_delta();
     let fov = ctx.world_camera2d.fov();
 
-    ctx.assets
-        .write_instances("bunny_instances", false, |data| {
-            data.par_extend((&mut bunnies).par_iter().map(|bunny| {
-                let mut linvel = bunny.linvel;
-                let mut translation = bunny.position.translation.vector;
-
-                linvel.y += GRAVITY * delta;
-                translation += linvel * delta;
-                if translation.x >= fov.x {
-                    linvel.x = -linvel.x;
-                    translation.x = fov.x;
-                } else if translation.x <= -fov.x {
-                    linvel.x = -linvel.x;
-                    translation.x = -fov.x;
-                }
-
-                if translation.y < -fov.y {
-                    linvel.y = gen_range(0.0..15.0);
-                    translation.y = -fov.y;
-                } else if translation.y > fov.y {
-                    linvel.y = -1.0;
-                    translation.y = fov.y;
-                }
-                bunny.linvel = linvel;
-                bunny.position.translation.vector = translation;
-
-                SpriteInstance2D::new(bunny.position, bunny.scaling, ())
-            }));
-        });
+    let mut bunnies = ctx.world.view_mut::<Bunny>();
+    (&mut bunnies).par_iter().for_each(|bunny| {
+        bunny.position.snapshot();
+        let mut linvel = bunny.linvel;
+        let mut translation = bunny.position.translation();
+
+        linvel.y += GRAVITY * delta;
+        translation += linvel * delta;
+        if translation.x >= fov.x {
+            linvel.x = -linvel.x;
+            translation.x = fov.x;
+        } else if translation.x <= -fov.x {
+            linvel.x = -linvel.x;
+            translation.x = -fov.x;
+        }
+
+        if translation.y < -fov.y {
+            linvel.y = gen_range(0.0..15.0);
+            translation.y = -fov.y;
+        } else if translation.y > fov.y {
+            linvel.y = -1.0;
+            translation.y = fov.y;
+        }
+        bunny.linvel = linvel;
+        bunny.position.set_translation(translation);
+    });
 }
 
 fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
@@ -107,9 +161,9 @@ fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
             &ctx.assets.sprite("bunny_sprite"),
         );
 
-        renderer.draw_text_mesh(
-            &ctx.assets.text_mesh("text"),
-            &ctx.default_assets.relative_top_right_camera.0,
+        renderer.draw_text(
+            &ctx.assets.text("text"),
+            &ctx.ui_camera.0,
             &ctx.assets.font("font"),
         );
     });
@@ -117,7 +171,7 @@ fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
 
 #[derive(Component)]
 struct Bunny {
-    position: Isometry2<f32>,
+    position: PositionComponent2D,
     scaling: Vector2<f32>,
     linvel: Vector2<f32>,
 }
@@ -128,7 +182,8 @@ impl Bunny {
         let rotation = gen_range(-1.0..1.0);
         let linvel = vector!(gen_range(-2.5..2.5), gen_range(-7.5..7.5));
         Bunny {
-            position: Isometry2::new(translation, rotation),
+            position: PositionComponent2D::new(Isometry2::new(translation, rotation))
+                .with_interpolate(true),
             linvel,
             scaling,
         }
@@ -0,0 +1,148 @@
+use shipyard::IntoIter;
+use shura::prelude::*;
+
+#[shura::app]
+fn app(config: AppConfig) {
+    App::run(config, || {
+        Scene::new()
+            .plugin(PhysicsDebugRender::colliders_only())
+            .system(System::setup(setup))
+            .system(System::update(update))
+    });
+}
+
+const HALF_HEIGHT: f32 = 0.4;
+const RADIUS: f32 = 0.3;
+const SPEED: f32 = 4.0;
+const JUMP_SPEED: f32 = 8.0;
+const GRAVITY: f32 = -20.0;
+const STEPS: usize = 6;
+const STEP_SIZE: Vector2<f32> = Vector2::new(0.8, 0.2);
+
+#[derive(Component)]
+struct Player {
+    vertical_speed: f32,
+}
+
+fn setup(ctx: &mut Context) {
+    ctx.world_camera2d
+        .set_scaling(WorldCameraScaling::Max(16.0));
+    ctx.physics.set_gravity(Vector2::new(0.0, GRAVITY));
+
+    let mut colliders = vec![
+        ColliderBuilder::cuboid(30.0, 0.5).translation(Vector2::new(0.0, -0.5)),
+        // 25°, walkable
+        ColliderBuilder::triangle(
+            Point2::new(-4.0, 0.0),
+            Point2::new(-12.0, 0.0),
+            Point2::new(-12.0, 8.0 * 25.0_f32.to_radians().tan()),
+        ),
+        // 60°, too steep to climb
+        ColliderBuilder::triangle(
+            Point2::new(12.0, 0.0),
+            Point2::new(14.0, 0.0),
+            Point2::new(14.0, 2.0 * 60.0_f32.to_radians().tan()),
+        ),
+    ];
+    for step in 0..STEPS {
+        let height = (step + 1) as f32 * STEP_SIZE.y;
+        colliders.push(
+            ColliderBuilder::cuboid(STEP_SIZE.x / 2.0, height / 2.0)
+                .translation(Vector2::new(2.0 + step as f32 * STEP_SIZE.x, height / 2.0)),
+        );
+    }
+    let mut level = RigidBodyComponent::new(RigidBodyBuilder::fixed(), colliders);
+    let entity = ctx.world.add_entity(());
+    level.init(ctx.physics, entity);
+    ctx.world.add_component(entity, (level,));
+
+    for i in 0..3 {
+        let mut body = RigidBodyComponent::new(
+            RigidBodyBuilder::dynamic().translation(Vector2::new(-2.0 - i as f32, 0.3)),
+            [ColliderBuilder::cuboid(0.3, 0.3)],
+        );
+        let entity = ctx.world.add_entity(());
+        body.init(ctx.physics, entity);
+        ctx.world.add_component(entity, (body,));
+    }
+
+    let controller = KinematicCharacterController {
+        autostep: Some(CharacterAutostep {
+            max_height: CharacterLength::Absolute(STEP_SIZE.y + 0.05),
+            min_width: CharacterLength::Absolute(0.2),
+            include_dynamic_bodies: false,
+        }),
+        max_slope_climb_angle: 45.0_f32.to_radians(),
+        min_slope_slide_angle: 30.0_f32.to_radians(),
+        ..Default::default()
+    };
+    ctx.world.add_entity((
+        CharacterControllerComponent::new(SharedShape::capsule_y(HALF_HEIGHT, RADIUS))
+            .with_controller(controller)
+            .with_translation(Vector2::new(0.0, 2.0))
+            .with_push_mass(1.0),
+        Player {
+            vertical_speed: 0.0,
+        },
+    ));
+}
+
+fn update(ctx: &mut Context) {
+    let delta = ctx.time.delta();
+    let mut direction = 0.0;
+    if ctx.input.is_held(Key::KeyD) {
+        direction += 1.0;
+    }
+    if ctx.input.is_held(Key::KeyA) {
+        direction -= 1.0;
+    }
+    let jump = ctx.input.is_pressed(Key::Space);
+
+    let mut controllers = ctx.world.view_mut::<CharacterControllerComponent>();
+    let mut players = ctx.world.view_mut::<Player>();
+    for (character, player) in (&mut controllers, &mut players).iter() {
+        if jump {
+            character.request_jump();
+        }
+        if character.is_grounded() && player.vertical_speed < 0.0 {
+            player.vertical_speed = 0.0;
+        }
+        if character.consume_jump() {
+            player.vertical_speed = JUMP_SPEED;
+        }
+        player.vertical_speed += GRAVITY * delta;
+
+        let desired = Vector2::new(direction * SPEED, player.vertical_speed) * delta;
+        character.move_by(ctx.physics, desired, delta, QueryFilter::default());
+        if character.collisions().iter().any(|collision| {
+            collision.hit.normal1.dot(&Vector2::y()) < -0.5 && player.vertical_speed > 0.0
+        }) {
+            // Bumped the head
+            player.vertical_speed = 0.0;
+        }
+
+        let color = if character.is_sliding() {
+            Color::RED
+        } else if character.is_grounded() {
+            Color::GREEN
+        } else {
+            Color::BLUE
+        };
+        let position = character.translation();
+        let offset = Vector2::new(0.0, HALF_HEIGHT);
+        ctx.debug.circle(position + offset, RADIUS, color);
+        ctx.debug.circle(position - offset, RADIUS, color);
+        for side in [-RADIUS, RADIUS] {
+            let side = Vector2::new(side, 0.0);
+            ctx.debug
+                .line(position - offset + side, position + offset + side, color);
+        }
+        if let Some(normal) = character.ground_normal() {
+            let foot = position - offset - Vector2::new(0.0, RADIUS);
+            ctx.debug.line(foot, foot + normal, Color::WHITE);
+        }
+        ctx.world_camera2d.set_translation(position);
+    }
+
+    ctx.physics.step(delta);
+}
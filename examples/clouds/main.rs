@@ -0,0 +1,61 @@
+use shura::prelude::*;
+
+const SIZE: u32 = 128;
+const WIND: Vector2<f32> = Vector2::new(0.4, 0.1);
+
+#[shura::app]
+fn app(config: AppConfig) {
+    App::run(config, || {
+        Scene::new()
+            .plugin_data(Noise::simplex(7).frequency(0.02).fbm(5, 2.0, 0.5))
+            .system(System::setup(setup))
+            .system(System::update(update))
+            .system(System::render(render))
+    });
+}
+
+fn setup(ctx: &mut Context) {
+    let clouds = clouds(ctx.plugin_data::<Noise>(), Vector2::zeros());
+    ctx.assets.load_sprite(
+        "clouds",
+        SpriteBuilder::raw(Vector2::new(SIZE, SIZE), &clouds).filter(wgpu::FilterMode::Linear),
+    );
+}
+
+fn update(ctx: &mut Context) {
+    // The noise is baked again every frame with the offset moving along with the wind
+    let clouds = clouds(ctx.plugin_data::<Noise>(), WIND * ctx.time.total() * 10.0);
+    ctx.assets
+        .sprite_mut("clouds")
+        .write(&ctx.gpu, Vector2::new(SIZE, SIZE), &clouds);
+
+    let fov = ctx.world_camera2d.fov();
+    ctx.assets
+        .write_instances("sky", false, |data: &mut Vec<SpriteInstance2D>| {
+            data.push(SpriteInstance2D::new(Isometry2::default(), fov * 2.0, ()));
+        });
+}
+
+fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
+    encoder.render2d(Some(Color::new_rgba(90, 150, 220, 255)), |renderer| {
+        renderer.draw_sprite(
+            &ctx.assets.instances("sky"),
+            &ctx.default_assets.sprite_mesh,
+            &ctx.default_assets.world_camera2d,
+            &ctx.assets.sprite("clouds"),
+        );
+    });
+}
+
+/// White pixels whose alpha follows the noise, thin noise stays clear sky
+fn clouds(noise: &Noise, offset: Vector2<f32>) -> Vec<u8> {
+    let mut pixels = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let value = noise.sample2(offset.x + x as f32, offset.y + y as f32);
+            let density = ((value - 0.05) * 2.5).clamp(0.0, 1.0);
+            pixels.extend_from_slice(&[255, 255, 255, (density * 255.0) as u8]);
+        }
+    }
+    pixels
+}
@@ -0,0 +1,32 @@
+@group(0) @binding(0)
+var<uniform> u_camera: mat4x4<f32>;
+
+// x: time, y: strength
+@group(1) @binding(0)
+var<uniform> u_wind: vec4<f32>;
+
+struct VertexInput {
+    @location(0) v_position: vec2<f32>,
+    // x: weight, y: phase
+    @location(1) v_sway: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) weight: f32,
+}
+
+@vertex
+fn vs_main(model: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    let offset = sin(u_wind.x * 2.0 + model.v_sway.y) * u_wind.y * model.v_sway.x;
+    let pos = model.v_position + vec2<f32>(offset, 0.0);
+    out.clip_position = u_camera * vec4<f32>(pos, 0.0, 1.0);
+    out.weight = model.v_sway.x;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(mix(vec3<f32>(0.1, 0.3, 0.1), vec3<f32>(0.4, 0.8, 0.3), in.weight), 1.0);
+}
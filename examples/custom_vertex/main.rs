@@ -0,0 +1,109 @@
+use shura::prelude::*;
+
+const BLADES: u32 = 24;
+
+#[shura::app]
+fn app(config: AppConfig) {
+    App::run(config, || {
+        Scene::new()
+            .system(System::setup(setup))
+            .system(System::update(update))
+            .system(System::render(render))
+    });
+}
+
+/// Vertex with an extra attribute next to the position. `sway.x` is how much the vertex bends
+/// with the wind and `sway.y` the phase of the blade it belongs to.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+struct FoliageVertex {
+    pos: Vector2<f32>,
+    sway: Vector2<f32>,
+}
+
+impl Vertex for FoliageVertex {
+    const ATTRIBUTES: &'static [wgpu::VertexFormat] =
+        &[wgpu::VertexFormat::Float32x2, wgpu::VertexFormat::Float32x2];
+}
+
+impl VertexPosition2D for FoliageVertex {
+    fn pos(&self) -> &Vector2<f32> {
+        &self.pos
+    }
+
+    fn pos_mut(&mut self) -> &mut Vector2<f32> {
+        &mut self.pos
+    }
+
+    fn with_pos(&self, pos: Vector2<f32>) -> Self {
+        Self { pos, ..*self }
+    }
+}
+
+impl BaseVertex2D for FoliageVertex {
+    fn create_data(vertices: Vec<Vector2<f32>>) -> Vec<Self> {
+        vertices
+            .into_iter()
+            .map(|pos| Self {
+                pos,
+                sway: Vector2::zeros(),
+            })
+            .collect()
+    }
+}
+
+fn setup(ctx: &mut Context) {
+    ctx.world_camera2d.set_scaling(WorldCameraScaling::Min(6.0));
+    ctx.assets.load_shader(
+        "foliage",
+        ShaderConfig {
+            source: ShaderModuleSource::Single(
+                &ctx.gpu.create_shader_module(include_wgsl!("foliage.wgsl")),
+            ),
+            // The wind uniform is read in the vertex shader like the camera
+            uniforms: &[UniformField::Camera, UniformField::Camera],
+            vertex_buffers: VertexBuffers::vertex::<FoliageVertex>(),
+            ..Default::default()
+        },
+    );
+    ctx.assets.load(
+        "wind",
+        UniformData::new(
+            &ctx.gpu,
+            ctx.gpu.default_layouts().camera_layout.clone(),
+            &[Vector4::new(0.0, 0.15, 0.0, 0.0)],
+        ),
+    );
+
+    let blades = (0..BLADES)
+        .map(|i| {
+            let x = i as f32 / BLADES as f32 * 8.0 - 4.0;
+            let phase = i as f32 * 0.7;
+            MeshBuilder2D::<FoliageVertex>::triangle(
+                Vector2::new(-0.1, 0.0),
+                Vector2::new(0.1, 0.0),
+                Vector2::new(0.0, gen_range(1.0..2.0)),
+            )
+            .apply_vertex_translation(Vector2::new(x, -1.0))
+            .apply_vertices(|v| v.sway = Vector2::new((v.pos.y + 1.0) / 2.0, phase))
+        })
+        .collect::<Vec<_>>();
+    ctx.assets
+        .load_mesh("foliage", &MeshBuilder2D::compound(&blades));
+}
+
+fn update(ctx: &mut Context) {
+    ctx.assets
+        .uniform_mut::<Vector4<f32>>("wind")
+        .write(&ctx.gpu, &[Vector4::new(ctx.time.total(), 0.15, 0.0, 0.0)]);
+}
+
+fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
+    encoder.render2d(Some(Color::new_rgba(170, 200, 230, 255)), |renderer| {
+        renderer.use_shader(&ctx.assets.shader("foliage"));
+        renderer.use_mesh(&ctx.assets.mesh::<FoliageVertex>("foliage"));
+        renderer.use_camera(&ctx.default_assets.world_camera2d);
+        renderer.use_uniform(&*ctx.assets.uniform::<Vector4<f32>>("wind"), 1);
+        renderer.render();
+    });
+}
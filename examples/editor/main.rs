@@ -0,0 +1,134 @@
+use shipyard::{IntoIter, IntoWithId};
+use shura::prelude::*;
+
+#[shura::app]
+fn app(config: AppConfig) {
+    App::run(config, || {
+        Scene::new()
+            .system(System::setup(setup))
+            .system(System::resize(resize))
+            .system(System::update(update))
+            .system(System::render(render))
+    });
+}
+
+fn setup(ctx: &mut Context) {
+    ctx.world_camera2d
+        .set_scaling(WorldCameraScaling::Max(10.0));
+    ctx.assets.load_render_target("viewport", ctx.render_size);
+    ctx.world.add_unique(Editor::default());
+}
+
+fn resize(ctx: &mut Context) {
+    ctx.assets
+        .render_target_mut("viewport")
+        .resize(&ctx.gpu, ctx.render_size);
+}
+
+fn update(ctx: &mut Context) {
+    let mut editor = ctx.world.unique_mut::<Editor>();
+    let shapes = ctx.world.view::<Shape>();
+    for path in ctx.input.dropped_files() {
+        editor.imported.push(path.display().to_string());
+    }
+
+    gui::SidePanel::left("inspector").show(ctx.gui, |ui| {
+        ui.heading("Inspector");
+        ui.label(format!("Shapes: {}", shapes.len()));
+        ui.color_edit_button_rgba_unmultiplied(&mut editor.color);
+        ui.add(gui::Slider::new(&mut editor.size, 0.1..=3.0).text("Size"));
+        if ui.button("Clear").clicked() {
+            for (entity, _) in shapes.iter().with_id() {
+                ctx.commands.despawn(entity);
+            }
+        }
+        if ui.button("Copy color").clicked() {
+            ctx.clipboard.set_text(format!("{:?}", editor.color));
+        }
+
+        ui.separator();
+        ui.heading("Assets");
+        if ctx.input.is_file_hovered() {
+            ui.label("Drop to import");
+        }
+        for path in &editor.imported {
+            ui.label(path);
+        }
+    });
+
+    let viewport = ctx.assets.render_target("viewport");
+    let image = ctx.gui.sprite_image(
+        &ctx.gpu,
+        viewport.sprite(),
+        viewport.sprite().size().cast::<f32>(),
+    );
+    let camera = ctx.world_camera2d.camera();
+    gui::CentralPanel::default().show(ctx.gui, |ui| {
+        let response = ui.add(image.shrink_to_fit().sense(gui::Sense::click()));
+        // Clicks only reach the game while the pointer is above the viewport
+        if let Some(position) =
+            gui::Gui::viewport_cursor(&response, camera).filter(|_| response.clicked())
+        {
+            let [r, g, b, a] = editor.color;
+            ctx.commands.spawn((Shape {
+                position: position.coords,
+                size: editor.size,
+                color: Color::new(r, g, b, a),
+            },));
+        }
+    });
+
+    ctx.assets.write_instances(
+        "shape_instances",
+        false,
+        |data: &mut Vec<ColorInstance2D>| {
+            for shape in shapes.iter() {
+                data.push(ColorInstance2D::new(
+                    Isometry2::new(shape.position, 0.0),
+                    Vector2::new(shape.size, shape.size),
+                    shape.color,
+                ));
+            }
+        },
+    );
+}
+
+fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
+    let viewport = ctx.assets.render_target("viewport");
+    encoder.render2d_to(
+        Some(Color::new_rgba(30, 30, 40, 255)),
+        &*viewport,
+        |renderer| {
+            renderer.draw_color(
+                &ctx.assets.instances("shape_instances"),
+                &ctx.default_assets.position_mesh,
+                &ctx.default_assets.world_camera2d,
+            );
+        },
+    );
+    encoder.render2d(Some(Color::BLACK), |_| {});
+}
+
+#[derive(Component)]
+struct Shape {
+    position: Vector2<f32>,
+    size: f32,
+    color: Color,
+}
+
+#[derive(Unique)]
+struct Editor {
+    color: [f32; 4],
+    size: f32,
+    imported: Vec<String>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self {
+            color: [0.9, 0.5, 0.2, 1.0],
+            size: 1.0,
+            imported: Vec::new(),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 mod plugin;
 
 use plugin::*;
+use shipyard::IntoIter;
 use shura::gui::Widget;
 use shura::prelude::*;
 use std::f32::consts::{PI, TAU};
@@ -11,12 +12,13 @@ const SIZE: Vector2<u32> = vector!(800, 800);
 fn app(config: AppConfig) {
     App::run(config, || {
         Scene::new()
-            .plugin(LightPlugin {})
+            .plugin(LightPlugin::default())
             .system(System::setup(load_assets))
             .system(System::setup(setup))
             .system(System::render(render))
+            .system(System::render(bloom).priority(SystemPriority::LAST))
+            .system(System::resize(resize))
             .system(System::update(update))
-            .entity::<MyLight>()
     })
 }
 
@@ -29,6 +31,25 @@ fn load_assets(ctx: &mut Context) {
         "background_mesh",
         &MeshBuilder2D::<SpriteVertex2D>::cuboid(vector![10.0, 10.0]),
     );
+    ctx.assets.load(
+        "bloom",
+        ctx.gpu.create_bloom(BloomConfig {
+            threshold: 0.6,
+            intensity: 0.8,
+            ..Default::default()
+        }),
+    );
+}
+
+fn resize(ctx: &mut Context) {
+    ctx.assets
+        .bloom_mut("bloom")
+        .resize(&ctx.gpu, ctx.render_size);
+}
+
+fn bloom(ctx: &RenderContext, encoder: &mut RenderEncoder) {
+    let bloom = ctx.assets.bloom("bloom");
+    encoder.apply_bloom(&bloom, ctx.target(), ctx.target());
 }
 
 fn setup(ctx: &mut Context) {
@@ -38,32 +59,37 @@ fn setup(ctx: &mut Context) {
         .window
         .request_inner_size(winit::dpi::PhysicalSize::new(SIZE.x, SIZE.y));
 
-    ctx.entities.get_mut().add(
-        ctx.physics,
+    ctx.world.add_entity((
+        LightComponent {
+            inner_radius: 0.2,
+            outer_radius: 10.0,
+            color: Color::BLUE,
+            ..Default::default()
+        },
         MyLight {
-            light: LightComponent {
-                inner_radius: 0.2,
-                outer_radius: 10.0,
-                color: Color::BLUE,
-                ..Default::default()
-            },
             display: true,
             follow_player: true,
         },
-    );
-    // ctx.entities.get_mut().add(
-    //     ctx.physics,
-    //     MyLight {
-    //         light: LightComponent {
-    //             inner_radius: 0.2,
-    //             outer_radius: 8.0,
-    //             color: Color::RED,
-    //             ..Default::default()
-    //         },
-    //         display: false,
-    //         follow_player: false
-    //     }
-    // );
+    ));
+    ctx.world.add_entity((LightComponent {
+        position: Isometry2::new(vector![-3.0, 3.0], 0.0),
+        inner_radius: 0.2,
+        outer_radius: 8.0,
+        color: Color::RED,
+        cpu_shadows: true,
+        ..Default::default()
+    },));
+
+    for (translation, half_extents) in [
+        (vector![2.0, 1.0], vector![0.5, 0.5]),
+        (vector![-1.5, -2.0], vector![1.0, 0.25]),
+        (vector![0.0, 3.0], vector![0.25, 1.0]),
+    ] {
+        ctx.world.add_entity((ShadowCasterComponent::from_shape(
+            Isometry2::new(translation, 0.0),
+            &Cuboid::new(half_extents),
+        ),));
+    }
 }
 
 fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
@@ -77,8 +103,9 @@ fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
 }
 
 fn update(ctx: &mut Context) {
-    let mut lights = ctx.entities.get_mut::<MyLight>();
-    for my_light in lights.iter_mut() {
+    let mut lights = ctx.world.view_mut::<LightComponent>();
+    let mut my_lights = ctx.world.view_mut::<MyLight>();
+    for (light, my_light) in (&mut lights, &mut my_lights).iter() {
         if my_light.display {
             gui::Window::new("Light")
                 .resizable(false)
@@ -86,31 +113,31 @@ fn update(ctx: &mut Context) {
                     ui.horizontal(|ui| {
                         ui.label(format!(
                             "Position: {} / {}",
-                            my_light.light.position.translation.x,
-                            my_light.light.position.translation.y
+                            light.position.translation.x,
+                            light.position.translation.y
                         ));
                     });
 
-                    let mut rotation = my_light.light.position.rotation.angle();
+                    let mut rotation = light.position.rotation.angle();
                     ui.horizontal(|ui| {
                         ui.label("Rotation:");
                         gui::widgets::Slider::new(&mut rotation, -TAU..=TAU).ui(ui);
                     });
-                    my_light.light.position.rotation = Rotation2::new(rotation);
+                    light.position.rotation = Rotation2::new(rotation);
 
                     ui.horizontal(|ui| {
                         ui.label("Outer Radius:");
-                        gui::widgets::Slider::new(&mut my_light.light.outer_radius, 0.0..=50.0)
+                        gui::widgets::Slider::new(&mut light.outer_radius, 0.0..=50.0)
                             .ui(ui);
                     });
 
                     ui.horizontal(|ui| {
                         ui.label("Inner Radius:");
-                        gui::widgets::Slider::new(&mut my_light.light.inner_radius, 0.0..=1.0)
+                        gui::widgets::Slider::new(&mut light.inner_radius, 0.0..=1.0)
                             .ui(ui);
                     });
 
-                    let mut egui_color = my_light.light.color.into();
+                    let mut egui_color = light.color.into();
                     ui.horizontal(|ui| {
                         ui.label("Color:");
                         gui::widgets::color_picker::color_edit_button_rgba(
@@ -119,12 +146,12 @@ fn update(ctx: &mut Context) {
                             egui::widgets::color_picker::Alpha::OnlyBlend,
                         )
                     });
-                    my_light.light.color = egui_color.into();
+                    light.color = egui_color.into();
 
                     ui.horizontal(|ui| {
                         ui.label("Inner Magnification:");
                         gui::widgets::Slider::new(
-                            &mut my_light.light.inner_magnification,
+                            &mut light.inner_magnification,
                             0.01..=10.0,
                         )
                         .ui(ui);
@@ -133,7 +160,7 @@ fn update(ctx: &mut Context) {
                     ui.horizontal(|ui| {
                         ui.label("Outer Magnification:");
                         gui::widgets::Slider::new(
-                            &mut my_light.light.outer_magnification,
+                            &mut light.outer_magnification,
                             0.01..=10.0,
                         )
                         .ui(ui);
@@ -142,22 +169,22 @@ fn update(ctx: &mut Context) {
                     ui.horizontal(|ui| {
                         ui.label("Side Falloff Magnification:");
                         gui::widgets::Slider::new(
-                            &mut my_light.light.side_falloff_magnification,
+                            &mut light.side_falloff_magnification,
                             0.0..=10.0,
                         )
                         .ui(ui);
                     });
 
-                    let end = my_light.light.sector.y;
+                    let end = light.sector.y;
                     ui.horizontal(|ui| {
                         ui.label("Start:");
-                        gui::widgets::Slider::new(&mut my_light.light.sector.x, -PI..=end).ui(ui);
+                        gui::widgets::Slider::new(&mut light.sector.x, -PI..=end).ui(ui);
                     });
 
-                    let start = my_light.light.sector.x;
+                    let start = light.sector.x;
                     ui.horizontal(|ui| {
                         ui.label("End:");
-                        gui::widgets::Slider::new(&mut my_light.light.sector.y, start..=PI).ui(ui);
+                        gui::widgets::Slider::new(&mut light.sector.y, start..=PI).ui(ui);
                     });
                 });
 
@@ -165,16 +192,14 @@ fn update(ctx: &mut Context) {
                 my_light.follow_player = !my_light.follow_player;
             }
             if my_light.follow_player {
-                my_light.light.position.translation.vector = ctx.cursor.coords;
+                light.position.translation.vector = ctx.cursor.coords;
             }
         }
     }
 }
 
-#[derive(Entity)]
+#[derive(Component)]
 pub struct MyLight {
-    #[shura(component)]
-    light: LightComponent,
     display: bool,
     follow_player: bool,
 }
@@ -1,15 +1,62 @@
+use shipyard::IntoIter;
 use shura::prelude::*;
-pub struct LightPlugin {}
+
+const STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Stencil8;
+
+/// Renders every [LightComponent] into a light map, using the stencil buffer to cut out the
+/// shadows of every [ShadowCasterComponent]. The light map is rendered in its own pass before
+/// the main pass.
+pub struct LightPlugin {
+    pub ambient: Color,
+}
+
+impl Default for LightPlugin {
+    fn default() -> Self {
+        Self {
+            ambient: Color::new(0.007, 0.007, 0.007, 1.0),
+        }
+    }
+}
+
+/// Settings of the [LightPlugin], accessible with `ctx.plugin_data::<LightPluginData>()`
+pub struct LightPluginData {
+    pub ambient: Color,
+}
+
+const ASSETS: [&str; 8] = [
+    "present_shader",
+    "light_shader",
+    "shadow_shader",
+    "shadows",
+    "light_map",
+    "light_stencil",
+    "light_instances",
+    "shadow_edges",
+];
 
 impl Plugin for LightPlugin {
     fn init<S: SceneCreator>(&mut self, scene: S) -> S {
         scene
+            .plugin_data(LightPluginData {
+                ambient: self.ambient,
+            })
             .system(System::resize(resize))
-            .system(System::setup(load_assets))
-            .system(System::render(render))
+            .render_pass(RenderPassConfig::new("light_map").before(RenderPassConfig::MAIN))
+            .system(System::render_in("light_map", render))
             .system(System::render(apply_render).priority(SystemPriority::LAST))
+            .system(System::update(sync_colliders).priority(SystemPriority::AFTER))
             .system(System::update(update).priority(SystemPriority::LAST))
     }
+
+    fn finish(&self, ctx: &mut Context) {
+        load_assets(ctx);
+    }
+
+    fn cleanup(&self, ctx: &mut Context) {
+        for key in ASSETS {
+            ctx.assets.unload(key);
+        }
+    }
 }
 
 fn load_assets(ctx: &mut Context) {
@@ -63,53 +110,136 @@ fn load_assets(ctx: &mut Context) {
             ],
             blend: BlendState::ALPHA_BLENDING,
             vertex_buffers: VertexBuffers::instance::<SpriteVertex2D, LightInstance2D>(),
+            depth_stencil: Some(stencil_state(
+                wgpu::CompareFunction::Equal,
+                wgpu::StencilOperation::Keep,
+            )),
+            ..Default::default()
+        },
+    );
+    ctx.assets.load_shader(
+        "shadow_shader",
+        ShaderConfig {
+            source: ShaderModuleSource::Single(
+                &ctx.gpu
+                    .create_shader_module(include_resource_wgsl!("lighting/shadow.wgsl")),
+            ),
+            uniforms: &[UniformField::Camera],
+            write_mask: ColorWrites::empty(),
+            vertex_buffers: VertexBuffers::instance::<SpriteVertex2D, ShadowEdgeInstance2D>(),
+            depth_stencil: Some(stencil_state(
+                wgpu::CompareFunction::Always,
+                wgpu::StencilOperation::IncrementClamp,
+            )),
             ..Default::default()
         },
     );
     ctx.assets
         .load_uniform_empty::<Shadow>("shadows", bind_group_layout.into(), 10);
     ctx.assets.load_render_target("light_map", ctx.render_size);
+    ctx.assets
+        .load_depth_buffer("light_stencil", ctx.render_size, STENCIL_FORMAT);
+}
+
+fn stencil_state(
+    compare: wgpu::CompareFunction,
+    pass_op: wgpu::StencilOperation,
+) -> wgpu::DepthStencilState {
+    let face = wgpu::StencilFaceState {
+        compare,
+        fail_op: wgpu::StencilOperation::Keep,
+        depth_fail_op: wgpu::StencilOperation::Keep,
+        pass_op,
+    };
+    wgpu::DepthStencilState {
+        format: STENCIL_FORMAT,
+        depth_write_enabled: false,
+        depth_compare: wgpu::CompareFunction::Always,
+        stencil: wgpu::StencilState {
+            front: face,
+            back: face,
+            read_mask: 0xff,
+            write_mask: 0xff,
+        },
+        bias: Default::default(),
+    }
 }
 
 fn resize(ctx: &mut Context) {
     ctx.assets
         .render_target_mut("light_map")
         .resize(&ctx.gpu, ctx.render_size);
+    ctx.assets
+        .depth_buffer_mut("light_stencil")
+        .resize(&ctx.gpu, ctx.render_size);
 }
 
 fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
-    encoder.render2d_to(
-        Some(Color::new(0.007, 0.007, 0.007, 1.0)),
-        &*ctx.assets.render_target("light_map"),
-        |renderer| {
-            renderer.draw(
-                &ctx.assets.shader("light_shader"),
-                &ctx.write_instance_components(
-                    "light_instances",
-                    |light: &LightComponent, data| {
-                        data.push(LightInstance2D(Instance2D::new(
-                            light.position,
-                            vector![light.outer_radius, light.outer_radius],
-                            LightData {
-                                color: light.color,
-                                sector: light.sector,
-                                inner_radius: light.inner_radius,
-                                inner_magnification: light.inner_magnification,
-                                outer_magnification: light.outer_magnification,
-                                side_falloff_magnification: light.side_falloff_magnification,
-                                shadow_range: light.shadow_range,
-                            },
-                        )))
-                    },
-                ),
-                &ctx.default_assets.sprite_mesh,
-                &[
-                    &ctx.default_assets.world_camera2d,
-                    &*ctx.assets.uniform::<Shadow>("shadows"),
-                ],
-            );
+    let lights = ctx.world.view::<LightComponent>();
+    let casters = ctx.world.view::<ShadowCasterComponent>();
+    ctx.assets.write_instances(
+        "light_instances",
+        false,
+        |data: &mut Vec<LightInstance2D>| {
+            data.extend(lights.iter().map(LightComponent::instance));
         },
     );
+
+    let mut ranges = vec![];
+    ctx.assets.write_instances(
+        "shadow_edges",
+        false,
+        |data: &mut Vec<ShadowEdgeInstance2D>| {
+            for light in lights.iter() {
+                let start = data.len() as u32;
+                if !light.cpu_shadows {
+                    let light_aabb = light.aabb();
+                    for caster in casters.iter() {
+                        if caster.aabb().intersects(&light_aabb) {
+                            data.extend(caster.edges().map(|(start, end)| ShadowEdgeInstance2D {
+                                light: light.position.translation.vector,
+                                start,
+                                end,
+                            }));
+                        }
+                    }
+                }
+                ranges.push(start..data.len() as u32);
+            }
+        },
+    );
+
+    let ambient = ctx.plugin_data::<LightPluginData>().ambient;
+    let light_instances = ctx.assets.instances::<LightInstance2D>("light_instances");
+    let shadow_edges = ctx.assets.instances::<ShadowEdgeInstance2D>("shadow_edges");
+    let light_map = ctx.assets.render_target("light_map");
+    let stencil = ctx.assets.depth_buffer("light_stencil");
+    let shadow_shader = ctx.assets.shader("shadow_shader");
+    let light_shader = ctx.assets.shader("light_shader");
+    let shadows = ctx.assets.uniform::<Shadow>("shadows");
+    if ranges.is_empty() {
+        encoder.renderer2d_to(&*light_map, Some(ambient));
+        return;
+    }
+
+    // Every light gets its own pass, so the stencil buffer is cleared in between
+    for (i, range) in ranges.into_iter().enumerate() {
+        let clear = if i == 0 { Some(ambient) } else { None };
+        let mut renderer = encoder.renderer(&*light_map, clear, Some(&stencil));
+        renderer.use_camera(&ctx.default_assets.world_camera2d);
+        renderer.use_mesh(&ctx.default_assets.sprite_mesh);
+        if !range.is_empty() {
+            renderer.use_shader(&shadow_shader);
+            renderer.use_instances_with_range(&shadow_edges, range);
+            renderer.render();
+        }
+
+        let i = i as u32;
+        renderer.use_shader(&light_shader);
+        renderer.use_uniform(&*shadows, 1);
+        renderer.use_instances_with_range(&light_instances, i..i + 1);
+        renderer.render();
+    }
 }
 
 fn apply_render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
@@ -125,49 +255,40 @@ fn apply_render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
     });
 }
 
+fn sync_colliders(ctx: &mut Context) {
+    let mut casters = ctx.world.view_mut::<ShadowCasterComponent>();
+    let colliders = ctx.world.view::<ColliderComponent>();
+    for (caster, collider) in (&mut casters, &colliders).iter() {
+        if collider.handle().is_some() {
+            caster.position = *collider.get(ctx.physics).position();
+        }
+    }
+}
+
+/// CPU fallback for lights with [LightComponent::cpu_shadows]. The light shader tests every
+/// fragment against the edges in its `shadow_range`.
 fn update(ctx: &mut Context) {
     let mut shadows = vec![];
-    ctx.entities
-        .components_each_mut::<LightComponent>(|_, light| {
-            let light_translation = light.position.translation.vector;
-            let light_aabb = AABB::from_center(
-                light_translation,
-                vector![light.outer_radius, light.outer_radius],
-            );
-            let start = shadows.len() as u32;
-            let mut end = start;
-            for mesh in &[] {
-                if !mesh.aabb().intersects(&light_aabb) {
+    let mut lights = ctx.world.view_mut::<LightComponent>();
+    let casters = ctx.world.view::<ShadowCasterComponent>();
+    for light in (&mut lights).iter() {
+        let start = shadows.len() as u32;
+        if light.cpu_shadows {
+            let light_aabb = light.aabb();
+            let light_center = light.position.translation.vector;
+            for caster in casters.iter() {
+                if !caster.aabb().intersects(&light_aabb) {
                     continue;
                 }
-                let vertices = mesh.vertices();
-
-                let mut leftmost = 0;
-                let mut rightmost = 0;
-
-                for (i, v) in vertices[1..].iter().enumerate() {
-                    let ray = v.pos - light_translation;
-                    fn det(v1: Vector2<f32>, v2: Vector2<f32>) -> f32 {
-                        return v1.x * v2.y - v1.y * v2.x;
-                    }
-                    if det(ray, vertices[leftmost].pos - light_translation) < 0.0 {
-                        leftmost = i;
-                    } else if det(ray, vertices[rightmost].pos - light_translation) > 0.0 {
-                        rightmost = i;
-                    }
-                }
-
-                for i in leftmost..rightmost {
-                    shadows.push(Shadow {
-                        light_center: light_translation,
-                        start: vertices[i].pos,
-                        end: vertices[(i + 1) % vertices.len()].pos,
-                    });
-                }
-                end += 1;
+                shadows.extend(caster.edges().map(|(start, end)| Shadow {
+                    light_center,
+                    start,
+                    end,
+                }));
             }
-            light.shadow_range = vector![start, end];
-        });
+        }
+        light.shadow_range = vector![start, shadows.len() as u32];
+    }
     ctx.assets
         .uniform_mut::<Shadow>("shadows")
         .write(&ctx.gpu, &shadows);
@@ -211,7 +332,76 @@ struct Shadow {
     end: Vector2<f32>,
 }
 
-pub struct LightOccluder {}
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+struct ShadowEdgeInstance2D {
+    light: Vector2<f32>,
+    start: Vector2<f32>,
+    end: Vector2<f32>,
+}
+
+impl Instance for ShadowEdgeInstance2D {
+    const ATTRIBUTES: &'static [VertexFormat] = &[
+        wgpu::VertexFormat::Float32x2,
+        wgpu::VertexFormat::Float32x2,
+        wgpu::VertexFormat::Float32x2,
+    ];
+}
+
+/// Closed outline that blocks the light of every [LightComponent]. When the entity also has a
+/// [ColliderComponent], the position follows the collider.
+#[derive(Component)]
+pub struct ShadowCasterComponent {
+    pub position: Isometry2<f32>,
+    pub outline: Vec<Point2<f32>>,
+}
+
+impl ShadowCasterComponent {
+    const BALL_SUBDIVISIONS: u32 = 16;
+
+    pub fn new(position: Isometry2<f32>, outline: Vec<Point2<f32>>) -> Self {
+        Self { position, outline }
+    }
+
+    pub fn from_shape(position: Isometry2<f32>, shape: &dyn Shape) -> Self {
+        let outline = if let Some(cuboid) = shape.as_cuboid() {
+            cuboid.to_polyline()
+        } else if let Some(ball) = shape.as_ball() {
+            ball.to_polyline(Self::BALL_SUBDIVISIONS)
+        } else if let Some(polygon) = shape.as_convex_polygon() {
+            polygon.points().to_vec()
+        } else if let Some(triangle) = shape.as_triangle() {
+            triangle.vertices().to_vec()
+        } else {
+            panic!("Unsupported shadow caster shape!");
+        };
+        Self::new(position, outline)
+    }
+
+    pub fn from_collider(collider: &Collider) -> Self {
+        Self::from_shape(*collider.position(), collider.shape())
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (Vector2<f32>, Vector2<f32>)> + '_ {
+        let points = self
+            .outline
+            .iter()
+            .map(|p| (self.position * p).coords)
+            .collect::<Vec<_>>();
+        (0..points.len()).map(move |i| (points[i], points[(i + 1) % points.len()]))
+    }
+
+    pub fn aabb(&self) -> AABB {
+        let mut min = vector![f32::MAX, f32::MAX];
+        let mut max = vector![f32::MIN, f32::MIN];
+        for p in &self.outline {
+            let p = (self.position * p).coords;
+            min = min.inf(&p);
+            max = max.sup(&p);
+        }
+        AABB::new(min, max)
+    }
+}
 
 #[derive(Component)]
 pub struct LightComponent {
@@ -223,9 +413,36 @@ pub struct LightComponent {
     pub inner_magnification: f32,
     pub outer_magnification: f32,
     pub side_falloff_magnification: f32,
+    /// Uses the CPU fallback instead of the stencil buffer to compute shadows
+    pub cpu_shadows: bool,
     pub shadow_range: Vector2<u32>,
 }
 
+impl LightComponent {
+    pub fn aabb(&self) -> AABB {
+        AABB::from_center(
+            self.position.translation.vector,
+            vector![self.outer_radius, self.outer_radius],
+        )
+    }
+
+    fn instance(&self) -> LightInstance2D {
+        LightInstance2D(Instance2D::new(
+            self.position,
+            vector![self.outer_radius, self.outer_radius],
+            LightData {
+                color: self.color,
+                sector: self.sector,
+                inner_radius: self.inner_radius,
+                inner_magnification: self.inner_magnification,
+                outer_magnification: self.outer_magnification,
+                side_falloff_magnification: self.side_falloff_magnification,
+                shadow_range: self.shadow_range,
+            },
+        ))
+    }
+}
+
 impl Default for LightComponent {
     fn default() -> Self {
         Self {
@@ -237,6 +454,7 @@ impl Default for LightComponent {
             inner_magnification: 1.1,
             outer_magnification: 1.1,
             side_falloff_magnification: 0.2,
+            cpu_shadows: false,
             shadow_range: vector![0, 0],
         }
     }
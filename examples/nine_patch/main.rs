@@ -0,0 +1,105 @@
+use shipyard::IntoIter;
+use shura::prelude::*;
+
+const PANEL_SIZE: u32 = 64;
+const PANEL_BORDER: u32 = 16;
+
+#[shura::app]
+fn app(config: AppConfig) {
+    App::run(config, || {
+        Scene::new()
+            .system(System::setup(setup))
+            .system(System::update(update))
+            .system(System::render(render))
+    });
+}
+
+fn setup(ctx: &mut Context) {
+    ctx.world_camera2d
+        .set_scaling(WorldCameraScaling::Max(300.0));
+    ctx.assets.load_nine_patch_sprite(
+        "panel",
+        SpriteBuilder::raw(Vector2::new(PANEL_SIZE, PANEL_SIZE), &panel_pixels()),
+        NinePatchBorder::uniform(PANEL_BORDER),
+    );
+
+    ctx.world
+        .add_entity(Panel::new(Isometry2::default(), Vector2::default(), true));
+    ctx.world.add_entity(Panel::new(
+        Isometry2::new(Vector2::new(0.0, 120.0), 0.0),
+        Vector2::new(400.0, 150.0),
+        false,
+    ));
+    ctx.world.add_entity(Panel::new(
+        Isometry2::new(Vector2::new(-180.0, -90.0), 0.3),
+        Vector2::new(120.0, 200.0),
+        false,
+    ));
+}
+
+fn update(ctx: &mut Context) {
+    let fov = ctx.world_camera2d.fov();
+    let mut panels = ctx.world.view_mut::<Panel>();
+    for panel in (&mut panels).iter() {
+        if panel.fill_window {
+            panel.size = fov * 2.0 - Vector2::new(20.0, 20.0);
+        }
+    }
+
+    let nine_patch = ctx.assets.nine_patch_sprite("panel").data(1.0);
+    ctx.assets
+        .write_instances("panel_instances", false, |data| {
+            for panel in panels.iter() {
+                data.push(NinePatchInstance2D::new(
+                    panel.position.position,
+                    panel.size,
+                    nine_patch,
+                ));
+            }
+        });
+}
+
+fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
+    encoder.render2d(Some(Color::new_rgba(40, 40, 60, 255)), |renderer| {
+        renderer.draw_nine_patch(
+            &ctx.assets.instances("panel_instances"),
+            &ctx.default_assets.world_camera2d,
+            &ctx.assets.nine_patch_sprite("panel"),
+        );
+    });
+}
+
+fn panel_pixels() -> Vec<u8> {
+    let mut pixels = Vec::with_capacity((PANEL_SIZE * PANEL_SIZE * 4) as usize);
+    for y in 0..PANEL_SIZE {
+        for x in 0..PANEL_SIZE {
+            let edge = x.min(y).min(PANEL_SIZE - 1 - x).min(PANEL_SIZE - 1 - y);
+            let color = if edge < 4 {
+                Color::new_rgba(30, 30, 30, 255)
+            } else if edge < PANEL_BORDER {
+                Color::new_rgba(200, 160, 60, 255)
+            } else {
+                Color::new_rgba(240, 230, 200, 255)
+            };
+            pixels.extend_from_slice(&color.to_rgba());
+        }
+    }
+    pixels
+}
+
+#[derive(Component)]
+struct Panel {
+    position: PositionComponent2D,
+    size: Vector2<f32>,
+    fill_window: bool,
+}
+
+impl Panel {
+    pub fn new(position: Isometry2<f32>, size: Vector2<f32>, fill_window: bool) -> Self {
+        Self {
+            position: PositionComponent2D::new(position),
+            size,
+            fill_window,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use shura::prelude::*;
+
+const SIZE: u32 = 256;
+const BRICK: Vector2<u32> = vector!(64, 32);
+const MORTAR: u32 = 4;
+const BEVEL: f32 = 6.0;
+
+#[shura::app]
+fn app(config: AppConfig) {
+    App::run(config, || {
+        Scene::new()
+            .system(System::setup(setup))
+            .system(System::update(update))
+            .system(System::render(render))
+    });
+}
+
+/// Returns the distance to the closest mortar line and the direction pointing away from it
+fn brick(x: u32, y: u32) -> (f32, Vector2<f32>) {
+    let row = y / BRICK.y;
+    let x = (x + (row % 2) * BRICK.x / 2) % BRICK.x;
+    let y = y % BRICK.y;
+    let edges = [
+        (x as f32, vector![1.0, 0.0]),
+        ((BRICK.x - 1 - x) as f32, vector![-1.0, 0.0]),
+        // Texture rows go down, normals point up
+        (y as f32, vector![0.0, -1.0]),
+        ((BRICK.y - 1 - y) as f32, vector![0.0, 1.0]),
+    ];
+    edges
+        .into_iter()
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(distance, direction)| (distance - MORTAR as f32, direction))
+        .unwrap()
+}
+
+fn bricks() -> (Vec<u8>, Vec<u8>) {
+    let mut diffuse = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    let mut normals = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let (distance, direction) = brick(x, y);
+            let (color, normal) = if distance < 0.0 {
+                ([120, 115, 105, 255], vector![0.0, 0.0, 1.0])
+            } else if distance < BEVEL {
+                let tilt = 1.0 - distance / BEVEL;
+                (
+                    [150, 60, 45, 255],
+                    Vector3::new(direction.x * tilt, direction.y * tilt, 1.0).normalize(),
+                )
+            } else {
+                ([165, 70, 50, 255], vector![0.0, 0.0, 1.0])
+            };
+            diffuse.extend_from_slice(&color);
+            normals.extend(normal.iter().map(|n| ((n * 0.5 + 0.5) * 255.0) as u8));
+            normals.push(255);
+        }
+    }
+    (diffuse, normals)
+}
+
+fn setup(ctx: &mut Context) {
+    ctx.world_camera2d.set_scaling(WorldCameraScaling::Min(4.0));
+    let (diffuse, normals) = bricks();
+    let size = vector![SIZE, SIZE];
+    ctx.assets
+        .load_sprite("bricks", SpriteBuilder::raw(size, &diffuse));
+    // Normals have to be stored linear
+    ctx.assets.load_sprite(
+        "bricks_normal",
+        SpriteBuilder::raw(size, &normals).format(wgpu::TextureFormat::Rgba8Unorm),
+    );
+    ctx.assets.load(
+        "lights",
+        ctx.gpu
+            .create_lights2d(&Lights2D::new(Color::new(0.05, 0.05, 0.08, 1.0))),
+    );
+    ctx.assets
+        .write_instances("walls", false, |data: &mut Vec<SpriteInstance2D>| {
+            data.push(SpriteInstance2D::new(
+                Isometry2::new(vector![-0.9, 0.0], 0.0),
+                vector![1.6, 1.6],
+                (),
+            ));
+            data.push(SpriteInstance2D::new(
+                Isometry2::new(vector![0.9, 0.0], 0.6),
+                vector![1.6, 1.6],
+                (),
+            ));
+        });
+}
+
+fn update(ctx: &mut Context) {
+    let angle = ctx.time.total() * 0.8;
+    let mut lights = Lights2D::new(Color::new(0.05, 0.05, 0.08, 1.0));
+    lights.push(
+        PointLight2D::new(
+            vector![angle.cos(), angle.sin()] * 1.2,
+            3.0,
+            Color::new(1.0, 0.9, 0.7, 1.0),
+        )
+        .height(0.3),
+    );
+    ctx.assets
+        .uniform_mut::<Lights2D>("lights")
+        .write(&ctx.gpu, &[lights]);
+}
+
+fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
+    encoder.render2d(Some(Color::BLACK), |renderer| {
+        renderer.draw_sprite_lit(
+            &ctx.assets.instances("walls"),
+            &ctx.default_assets.sprite_mesh,
+            &ctx.default_assets.world_camera2d,
+            &ctx.assets.sprite("bricks"),
+            &ctx.assets.sprite("bricks_normal"),
+            &ctx.assets.uniform("lights"),
+        );
+    });
+}
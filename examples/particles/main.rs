@@ -0,0 +1,85 @@
+use shipyard::IntoIter;
+use shura::prelude::*;
+
+const SPRITE_SIZE: u32 = 32;
+
+#[shura::app]
+fn app(config: AppConfig) {
+    App::run(config, || {
+        Scene::new()
+            .plugin(ParticlePlugin::new())
+            .system(System::setup(setup))
+            .system(System::update(update))
+            .system(System::render(render))
+    });
+}
+
+fn setup(ctx: &mut Context) {
+    ctx.world_camera2d.set_scaling(WorldCameraScaling::Max(8.0));
+    ctx.assets.load_sprite(
+        "particle",
+        SpriteBuilder::raw(Vector2::new(SPRITE_SIZE, SPRITE_SIZE), &particle_pixels()),
+    );
+
+    // Added first so the smoke is drawn behind the flames
+    ctx.world.add_entity((
+        PositionComponent2D::new(Isometry2::new(Vector2::new(0.0, 0.8), 0.0)),
+        ParticleEmitterComponent::new("particle")
+            .spawn_rate(30.0)
+            .lifetime(2.0, 3.5)
+            .cone(std::f32::consts::FRAC_PI_2, 0.3)
+            .speed(0.6, 1.0)
+            .gravity(Vector2::new(0.3, 0.2))
+            .size(Vector2::new(0.4, 0.4), Vector2::new(1.6, 1.6))
+            .color(Gradient::new(&[
+                (0.0, Color::new(0.3, 0.3, 0.3, 0.0)),
+                (0.2, Color::new(0.3, 0.3, 0.3, 0.5)),
+                (1.0, Color::new(0.5, 0.5, 0.5, 0.0)),
+            ]))
+            .blend(ParticleBlend::Alpha),
+    ));
+    ctx.world.add_entity((
+        PositionComponent2D::new(Isometry2::new(Vector2::new(0.0, -1.0), 0.0)),
+        ParticleEmitterComponent::new("particle")
+            .spawn_rate(120.0)
+            .lifetime(0.5, 1.0)
+            .cone(std::f32::consts::FRAC_PI_2, 0.4)
+            .speed(1.0, 2.0)
+            .gravity(Vector2::new(0.0, 1.0))
+            .size(Vector2::new(0.6, 0.6), Vector2::new(0.1, 0.1))
+            .color(Gradient::new(&[
+                (0.0, Color::new(1.0, 0.9, 0.4, 1.0)),
+                (0.4, Color::new(1.0, 0.4, 0.0, 0.8)),
+                (1.0, Color::new(0.6, 0.0, 0.0, 0.0)),
+            ]))
+            .blend(ParticleBlend::Additive),
+    ));
+}
+
+fn update(ctx: &mut Context) {
+    if ctx.input.is_pressed(Key::Space) {
+        let mut emitters = ctx.world.view_mut::<ParticleEmitterComponent>();
+        for emitter in (&mut emitters).iter() {
+            let emitting = emitter.is_emitting();
+            emitter.set_emitting(!emitting);
+        }
+    }
+}
+
+fn render(_ctx: &RenderContext, encoder: &mut RenderEncoder) {
+    encoder.render2d(Some(Color::new_rgba(20, 20, 30, 255)), |_| {});
+}
+
+/// White circle fading out towards its edge, tinted by the emitter gradient
+fn particle_pixels() -> Vec<u8> {
+    let mut pixels = Vec::with_capacity((SPRITE_SIZE * SPRITE_SIZE * 4) as usize);
+    let center = (SPRITE_SIZE as f32 - 1.0) / 2.0;
+    for y in 0..SPRITE_SIZE {
+        for x in 0..SPRITE_SIZE {
+            let distance = Vector2::new(x as f32 - center, y as f32 - center).norm() / center;
+            let alpha = (1.0 - distance).clamp(0.0, 1.0);
+            pixels.extend_from_slice(&Color::new(1.0, 1.0, 1.0, alpha * alpha).to_rgba());
+        }
+    }
+    pixels
+}
@@ -0,0 +1,81 @@
+use shura::prelude::*;
+
+const SIZE: u32 = 512;
+const RUNS: u32 = 20;
+
+/// Walls every 8 cells with alternating gaps, so the path has to zig-zag through the grid
+fn maze() -> NavGrid {
+    NavGrid::from_fn(
+        vector![SIZE, SIZE],
+        vector![1.0, 1.0],
+        Vector2::zeros(),
+        |position| {
+            let (x, y) = (position.x as u32, position.y as u32);
+            let wall = x % 8 == 4;
+            let gap = if (x / 8) % 2 == 0 {
+                y < 4
+            } else {
+                y >= SIZE - 4
+            };
+            !wall || gap
+        },
+    )
+}
+
+fn bench(name: &str, mut run: impl FnMut() -> Option<usize>) {
+    let mut result = None;
+    let start = instant::Instant::now();
+    for _ in 0..RUNS {
+        result = run();
+    }
+    let elapsed = start.elapsed() / RUNS;
+    match result {
+        Some(points) => println!("{name}: {elapsed:?} ({points} points)"),
+        None => println!("{name}: {elapsed:?} (no path)"),
+    }
+}
+
+fn main() {
+    let start = point![0.5, 0.5];
+    let goal = point![SIZE as f32 - 0.5, SIZE as f32 - 0.5];
+
+    let open = NavGrid::new(vector![SIZE, SIZE], vector![1.0, 1.0]);
+    bench("open diagonal", || {
+        open.find_path(start, goal).map(|path| path.len())
+    });
+
+    let maze = maze().with_max_explored(usize::MAX);
+    bench("maze", || {
+        maze.find_path(start, goal).map(|path| path.len())
+    });
+
+    let raw = maze.clone().with_smoothing(false);
+    bench("maze without smoothing", || {
+        raw.find_path(start, goal).map(|path| path.len())
+    });
+
+    let orthogonal = maze.clone().with_diagonal(false);
+    bench("maze orthogonal", || {
+        orthogonal.find_path(start, goal).map(|path| path.len())
+    });
+
+    let mut walled = open.clone();
+    for y in 0..SIZE {
+        walled.set_walkable(vector![SIZE / 2, y], false);
+    }
+    bench("unreachable, default guard", || {
+        walled.find_path(start, goal).map(|path| path.len())
+    });
+
+    let mut destructible = maze.clone();
+    bench("destroy wall and search", || {
+        for y in 0..SIZE {
+            destructible.set_walkable(vector![4, y], true);
+        }
+        let path = destructible.find_path(start, goal).map(|path| path.len());
+        for y in 4..SIZE {
+            destructible.set_walkable(vector![4, y], false);
+        }
+        path
+    });
+}
@@ -0,0 +1,92 @@
+use shipyard::IntoIter;
+use shura::prelude::*;
+
+#[shura::app]
+fn app(config: AppConfig) {
+    App::run(config, || {
+        Scene::new()
+            .system(System::setup(setup))
+            .system(System::update(update))
+            .system(System::render(render))
+    });
+}
+
+const LINKS: usize = 16;
+const HALF_LINK_SIZE: f32 = 0.2;
+const LINK_SPACING: f32 = 0.5;
+
+struct Rope {
+    links: Vec<EntityId>,
+}
+
+fn setup(ctx: &mut Context) {
+    ctx.world_camera2d
+        .set_scaling(WorldCameraScaling::Max(10.0));
+    ctx.physics.set_gravity(Vector2::new(0.0, -9.81));
+
+    let mut links: Vec<EntityId> = Vec::with_capacity(LINKS);
+    for i in 0..LINKS {
+        // The first link holds the rope in place
+        let builder = if i == 0 {
+            RigidBodyBuilder::fixed()
+        } else {
+            RigidBodyBuilder::dynamic()
+        };
+        let mut body = RigidBodyComponent::new(
+            builder.translation(Vector2::new(i as f32 * LINK_SPACING - 4.0, 3.0)),
+            [ColliderBuilder::cuboid(HALF_LINK_SIZE, HALF_LINK_SIZE)],
+        );
+        let entity = ctx.world.add_entity(());
+        body.init(ctx.physics, entity);
+        ctx.world.add_component(entity, (body,));
+
+        if let Some(previous) = links.last() {
+            let joint = RevoluteJointBuilder::new().anchors(
+                Vector2::new(LINK_SPACING / 2.0, 0.0),
+                Vector2::new(-LINK_SPACING / 2.0, 0.0),
+            );
+            ctx.physics.add_joint(*previous, entity, joint).unwrap();
+        }
+        links.push(entity);
+    }
+    ctx.plugins.insert(Rope { links });
+}
+
+fn update(ctx: &mut Context) {
+    if ctx.input.is_pressed(Key::Space) {
+        let rope = ctx.plugins.get::<Rope>();
+        let middle = rope.links[LINKS / 2];
+        let joints = ctx
+            .physics
+            .joints_of(middle)
+            .map(|(handle, _)| handle)
+            .collect::<Vec<_>>();
+        for joint in joints {
+            ctx.physics.remove_joint(joint);
+        }
+    }
+
+    ctx.physics.step(ctx.time.delta());
+
+    let physics = &*ctx.physics;
+    ctx.assets
+        .write_instances("links", false, |data: &mut Vec<ColorInstance2D>| {
+            for body in ctx.world.view::<RigidBodyComponent>().iter() {
+                data.push(ColorInstance2D::new(
+                    body.position(physics),
+                    Vector2::new(HALF_LINK_SIZE * 2.0, HALF_LINK_SIZE * 2.0),
+                    Color::GREEN,
+                ));
+            }
+        });
+}
+
+fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
+    encoder.render2d(Some(Color::BLACK), |renderer| {
+        renderer.draw_color(
+            &ctx.assets.instances("links"),
+            &ctx.default_assets.position_mesh,
+            &ctx.default_assets.world_camera2d,
+        );
+    });
+}
@@ -14,7 +14,7 @@ fn scene(data: Option<Vec<u8>>) -> SerializedScene {
 
 #[shura::app]
 fn app(config: AppConfig) {
-    let data = config.storage.load_bytes("data.binc").ok();
+    let data = load_save_game(&*config.storage);
     App::run(config, || scene(data));
 }
 
@@ -52,7 +52,7 @@ fn update(ctx: &mut Context) {
     }
 
     if ctx.input.is_pressed(Key::KeyR) {
-        if let Ok(save_game) = ctx.storage.load_bytes("data.binc") {
+        if let Some(save_game) = load_save_game(&*ctx.storage) {
             let active_scene_id = ctx.scenes.active_scene_id();
             ctx.add_scene(
                 active_scene_id.wrapping_add(1),
@@ -74,11 +74,18 @@ fn update(ctx: &mut Context) {
             .set_scaling(WorldCameraScaling::Max(fov.x + scroll / 5.0));
     }
 
-    if ctx.input.is_held(MouseButton::Right)
-        && ctx
-            .world
+    // Taps spawn boxes on touch screens, panning the camera does not
+    let tap = ctx.input.tap(ctx.world_camera2d.camera());
+    let spawn = if ctx.input.is_held(MouseButton::Right) {
+        Some(ctx.cursor)
+    } else {
+        tap
+    };
+    let mut spawned = false;
+    if let Some(spawn) = spawn.filter(|spawn| {
+        ctx.world
             .intersection_with_shape(
-                &ctx.cursor.into(),
+                &(*spawn).into(),
                 &Cuboid::new(Vector2::new(
                     PhysicsBox::HALF_BOX_SIZE,
                     PhysicsBox::HALF_BOX_SIZE,
@@ -86,14 +93,16 @@ fn update(ctx: &mut Context) {
                 Default::default(),
             )
             .is_none()
-    {
-        let b = PhysicsBox::new(ctx.cursor.coords);
+    }) {
+        let b = PhysicsBox::new(spawn.coords);
         boxes.add(ctx.world, b);
+        spawned = true;
     }
 
     let delta = ctx.time.delta();
-    let cursor_world: Point2<f32> = ctx.cursor;
-    let remove = ctx.input.is_held(MouseButton::Left) || ctx.input.is_pressed(ScreenTouch);
+    let cursor_world: Point2<f32> = tap.unwrap_or(ctx.cursor);
+    // Taps on a box remove it
+    let remove = ctx.input.is_held(MouseButton::Left) || (tap.is_some() && !spawned);
     for physics_box in boxes.iter_mut() {
         if physics_box.color == Color::RED {
             physics_box.color = Color::GREEN;
@@ -156,6 +165,20 @@ fn end(ctx: &mut Context, reason: EndReason) {
     }
 }
 
+const SAVE_VERSION: u32 = 1;
+
+fn load_save_game(storage: &dyn StorageLoader) -> Option<Vec<u8>> {
+    let load = |path: &str| {
+        storage.load_versioned::<Vec<u8>>(path, SAVE_VERSION, |version, _| {
+            Err(anyhow::anyhow!("No migration from version {version}"))
+        })
+    };
+    match load("data.binc") {
+        Err(SaveError::Corrupted) => load(&<dyn StorageLoader>::backup_path("data.binc")).ok(),
+        save_game => save_game.ok(),
+    }
+}
+
 fn serialize_scene(ctx: &mut Context) {
     info!("Serializing scene!");
     let ser = ctx
@@ -164,9 +187,14 @@ fn serialize_scene(ctx: &mut Context) {
                 .serialize_entity_single::<Floor>()
                 .serialize_entity_single::<Player>()
                 .serialize_entity::<PhysicsBox>()
+                .serialize_world_camera2d()
+                .serialize_physics_settings()
+                .serialize_screen_config()
         })
         .unwrap();
-    ctx.storage.store("data.binc", &ser).unwrap();
+    ctx.storage
+        .save_versioned("data.binc", SAVE_VERSION, &ser)
+        .unwrap();
 }
 
 fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
@@ -0,0 +1,88 @@
+use shura::prelude::*;
+
+#[shura::app]
+fn app(config: AppConfig) {
+    App::run(config, || {
+        Scene::new()
+            .system(System::setup(setup))
+            .system(System::update(update))
+            .system(System::render(render))
+    });
+}
+
+#[derive(Unique)]
+struct Effects {
+    grayscale: bool,
+    blur: bool,
+    vignette: bool,
+}
+
+fn setup(ctx: &mut Context) {
+    ctx.assets
+        .load("grayscale", PostProcess::grayscale(&ctx.gpu));
+    ctx.assets
+        .load("blur", PostProcess::gaussian_blur(&ctx.gpu, 6.0));
+    ctx.assets
+        .load("vignette", PostProcess::vignette(&ctx.gpu, 0.9, 0.75, 0.45));
+    ctx.world.add_unique(Effects {
+        grayscale: false,
+        blur: false,
+        vignette: true,
+    });
+
+    ctx.assets
+        .write_instances("squares", false, |data: &mut Vec<ColorInstance2D>| {
+            for x in -4..=4 {
+                for y in -4..=4 {
+                    let color = Color::new((x + 4) as f32 / 8.0, (y + 4) as f32 / 8.0, 0.6, 1.0);
+                    data.push(ColorInstance2D::new(
+                        Isometry2::new(Vector2::new(x as f32, y as f32) * 0.35, 0.0),
+                        Vector2::new(0.25, 0.25),
+                        color,
+                    ));
+                }
+            }
+        });
+}
+
+fn update(ctx: &mut Context) {
+    let mut effects = ctx.world.unique_mut::<Effects>();
+    if ctx.input.is_pressed(Key::Digit1) {
+        effects.grayscale = !effects.grayscale;
+    }
+    if ctx.input.is_pressed(Key::Digit2) {
+        effects.blur = !effects.blur;
+    }
+    if ctx.input.is_pressed(Key::Digit3) {
+        effects.vignette = !effects.vignette;
+    }
+}
+
+fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
+    encoder.render2d(Some(Color::WHITE), |renderer| {
+        renderer.draw_color(
+            &ctx.assets.instances("squares"),
+            &ctx.default_assets.position_mesh,
+            &ctx.default_assets.world_camera2d,
+        );
+    });
+
+    let effects = ctx.world.unique::<Effects>();
+    let grayscale = ctx.assets.post_process("grayscale");
+    let blur = ctx.assets.post_process("blur");
+    let vignette = ctx.assets.post_process("vignette");
+    let mut chain: Vec<&PostProcess> = Vec::new();
+    if effects.grayscale {
+        chain.push(&grayscale);
+    }
+    if effects.blur {
+        chain.push(&blur);
+    }
+    if effects.vignette {
+        chain.push(&vignette);
+    }
+    // Fails without the framebuffer feature, where the default target is the surface
+    if let Err(err) = encoder.post_process(&chain) {
+        println!("{err}");
+    }
+}
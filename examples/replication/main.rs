@@ -0,0 +1,116 @@
+use shipyard::IntoIter;
+use shura::prelude::*;
+
+#[shura::app]
+fn app(config: AppConfig) {
+    App::run(config, || {
+        Scene::new()
+            .system(System::setup(setup))
+            .system(System::update(update))
+            .system(System::render(render))
+    });
+}
+
+/// Sender and receiver in one app, the packets would usually be sent through a socket
+struct Loopback {
+    server: Replication,
+    client: Replication,
+    mirror: World,
+    tick: u32,
+}
+
+fn replication() -> Replication {
+    Replication::new().replicate::<Body>()
+}
+
+fn setup(ctx: &mut Context) {
+    ctx.world_camera2d
+        .set_scaling(WorldCameraScaling::Max(10.0));
+    for i in 0..5 {
+        ctx.world.add_entity((
+            Body {
+                position: Vector2::zeros(),
+            },
+            Orbit {
+                radius: 1.0 + i as f32,
+                speed: 1.0 / (1.0 + i as f32),
+            },
+            Replicated,
+        ));
+    }
+    ctx.plugins.insert(Loopback {
+        server: replication(),
+        client: replication(),
+        mirror: World::new(),
+        tick: 0,
+    });
+}
+
+fn update(ctx: &mut Context) {
+    let total = ctx.time.total();
+    for (body, orbit) in (
+        &mut ctx.world.view_mut::<Body>(),
+        &ctx.world.view::<Orbit>(),
+    )
+        .iter()
+    {
+        let angle = total * orbit.speed;
+        body.position = Vector2::new(angle.cos(), angle.sin()) * orbit.radius;
+    }
+
+    let loopback = ctx.plugins.get_mut::<Loopback>();
+    loopback.tick += 1;
+    let packet = loopback.server.snapshot(ctx.world, loopback.tick);
+    // Every fourth packet gets lost
+    if loopback.tick % 4 != 0 {
+        loopback.client.apply(&mut loopback.mirror, &packet);
+        if let Some(tick) = loopback.client.last_received() {
+            loopback.server.acknowledge(tick);
+        }
+    }
+
+    let mirror = &loopback.mirror;
+    ctx.assets
+        .write_instances("bodies", false, |data: &mut Vec<ColorInstance2D>| {
+            for body in ctx.world.view::<Body>().iter() {
+                data.push(body.instance(-5.0, Color::RED));
+            }
+            for body in mirror.view::<Body>().iter() {
+                data.push(body.instance(5.0, Color::BLUE));
+            }
+        });
+}
+
+fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
+    encoder.render2d(Some(Color::BLACK), |renderer| {
+        renderer.draw_color(
+            &ctx.assets.instances("bodies"),
+            &ctx.default_assets.position_mesh,
+            &ctx.default_assets.world_camera2d,
+        );
+    });
+}
+
+#[derive(Component, Replicate)]
+struct Body {
+    #[shura(replicate(quantize = Self::QUANTIZE))]
+    position: Vector2<f32>,
+}
+
+impl Body {
+    const QUANTIZE: Quantize = Quantize::new(-8.0, 8.0, 16);
+
+    fn instance(&self, offset: f32, color: Color) -> ColorInstance2D {
+        ColorInstance2D::new(
+            Isometry2::new(self.position + Vector2::new(offset, 0.0), 0.0),
+            Vector2::new(0.4, 0.4),
+            color,
+        )
+    }
+}
+
+#[derive(Component)]
+struct Orbit {
+    radius: f32,
+    speed: f32,
+}
@@ -0,0 +1,136 @@
+//! Validates the shaders in `static/shader` against the limits of WebGL2, the most restricted
+//! backend shura runs on. Run with `cargo run --example shader_validation`.
+use std::path::{Path, PathBuf};
+
+use naga::{
+    front::wgsl,
+    valid::{Capabilities, ValidationFlags, Validator},
+    AddressSpace, Binding, Module, ShaderStage, TypeInner,
+};
+
+fn shaders(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            shaders(&path, files);
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "wgsl")
+        {
+            files.push(path);
+        }
+    }
+}
+
+fn vertex_attributes(module: &Module, arguments: &[naga::FunctionArgument]) -> u32 {
+    arguments
+        .iter()
+        .map(
+            |argument| match (&argument.binding, &module.types[argument.ty].inner) {
+                (Some(Binding::Location { .. }), _) => 1,
+                (None, TypeInner::Struct { members, .. }) => members
+                    .iter()
+                    .filter(|member| matches!(member.binding, Some(Binding::Location { .. })))
+                    .count() as u32,
+                _ => 0,
+            },
+        )
+        .sum()
+}
+
+fn downlevel_errors(module: &Module) -> Vec<String> {
+    let limits = wgpu::Limits::downlevel_webgl2_defaults();
+    let mut errors = Vec::new();
+    let mut layouter = naga::proc::Layouter::default();
+    layouter.update(module.to_ctx()).unwrap();
+
+    for entry_point in &module.entry_points {
+        match entry_point.stage {
+            ShaderStage::Compute => {
+                errors.push(format!("compute entry point '{}'", entry_point.name))
+            }
+            ShaderStage::Vertex => {
+                let attributes = vertex_attributes(module, &entry_point.function.arguments);
+                if attributes > limits.max_vertex_attributes {
+                    errors.push(format!(
+                        "'{}' has {attributes} vertex attributes, at most {} are allowed",
+                        entry_point.name, limits.max_vertex_attributes
+                    ));
+                }
+            }
+            ShaderStage::Fragment => (),
+        }
+    }
+
+    for (_, global) in module.global_variables.iter() {
+        let name = global.name.as_deref().unwrap_or("<unnamed>");
+        if let Some(binding) = &global.binding {
+            if binding.group >= limits.max_bind_groups {
+                errors.push(format!(
+                    "'{name}' uses bind group {}, at most {} groups are allowed",
+                    binding.group, limits.max_bind_groups
+                ));
+            }
+        }
+        match global.space {
+            AddressSpace::Storage { .. } => errors.push(format!("storage buffer '{name}'")),
+            AddressSpace::Uniform => {
+                let size = layouter[global.ty].size;
+                if size > limits.max_uniform_buffer_binding_size {
+                    errors.push(format!(
+                        "uniform '{name}' has {size} bytes, at most {} are allowed",
+                        limits.max_uniform_buffer_binding_size
+                    ));
+                }
+            }
+            _ => (),
+        }
+    }
+    errors
+}
+
+fn main() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("static/shader");
+    let mut files = Vec::new();
+    shaders(&root, &mut files);
+    files.sort();
+
+    let mut failed = 0;
+    for file in &files {
+        let name = file.strip_prefix(&root).unwrap().display();
+        let source = std::fs::read_to_string(file).unwrap();
+        let module = match wgsl::parse_str(&source) {
+            Ok(module) => module,
+            Err(err) => {
+                println!("{name}: {}", err.emit_to_string(&source));
+                failed += 1;
+                continue;
+            }
+        };
+        if let Err(err) =
+            Validator::new(ValidationFlags::all(), Capabilities::empty()).validate(&module)
+        {
+            println!("{name}: {}", err.emit_to_string(&source));
+            failed += 1;
+            continue;
+        }
+        let errors = downlevel_errors(&module);
+        if errors.is_empty() {
+            println!("{name}: ok");
+        } else {
+            for error in errors {
+                println!("{name}: {error}");
+            }
+            failed += 1;
+        }
+    }
+
+    println!(
+        "{} of {} shaders are valid on WebGL2",
+        files.len() - failed,
+        files.len()
+    );
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
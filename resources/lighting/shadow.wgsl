@@ -0,0 +1,50 @@
+// Extrudes an edge of a shadow caster away from the light. Only writes to the stencil buffer.
+const FAR: f32 = 1000.0;
+
+@group(0) @binding(0)
+var<uniform> u_camera: mat4x4<f32>;
+
+struct VertexInput {
+    @location(0) v_position: vec2<f32>,
+    @location(1) v_tex: vec2<f32>,
+}
+
+struct InstanceInput {
+    @location(2) i_light: vec2<f32>,
+    @location(3) i_start: vec2<f32>,
+    @location(4) i_end: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+fn cross(a: vec2<f32>, b: vec2<f32>) -> f32 {
+    return a.x * b.y - a.y * b.x;
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    var start = instance.i_start;
+    var end = instance.i_end;
+    // Keep the quad counter clockwise so it doesn't get culled
+    if cross(start - instance.i_light, end - instance.i_light) > 0.0 {
+        start = instance.i_end;
+        end = instance.i_start;
+    }
+
+    let corner = model.v_position + vec2<f32>(0.5, 0.5);
+    let point = mix(start, end, corner.x);
+    let pos = point + normalize(point - instance.i_light) * FAR * corner.y;
+    out.position = u_camera * vec4<f32>(pos, 0.0, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.0, 0.0, 0.0, 0.0);
+}
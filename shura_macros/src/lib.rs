@@ -2,6 +2,8 @@ use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 
+mod replicate;
+
 #[proc_macro_attribute]
 /// This macro helps setup a cross plattform main method
 pub fn app(_args: TokenStream, item: TokenStream) -> TokenStream {
@@ -23,3 +25,17 @@ pub fn app(_args: TokenStream, item: TokenStream) -> TokenStream {
     )
     .into()
 }
+
+/// Implements `shura::ecs::Replicate` for a struct. Only the fields marked with
+/// `#[shura(replicate)]` are sent, the others are [Default] on the receiving side. `f32`,
+/// `Vector2<f32>` and `Isometry2<f32>` fields can be quantized with
+/// `#[shura(replicate(quantize(min, max, bits)))]` or `#[shura(replicate(quantize = QUANTIZE))]`.
+/// The component id is a hash of the type name, unless it is set with
+/// `#[shura(replicate(id = 1))]` on the struct.
+#[proc_macro_derive(Replicate, attributes(shura))]
+pub fn derive_replicate(item: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(item as syn::DeriveInput);
+    replicate::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
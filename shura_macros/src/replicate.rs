@@ -0,0 +1,146 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parenthesized, punctuated::Punctuated, spanned::Spanned, Data, DeriveInput, Expr, Index,
+    Member, Token,
+};
+
+struct ReplicatedField {
+    member: Member,
+    replicate: bool,
+    quantize: TokenStream,
+}
+
+/// FNV-1a of the type name, so the id is the same on every peer and compiler version
+fn type_id(name: &str) -> u32 {
+    name.bytes().fold(0x811c_9dc5, |hash: u32, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    })
+}
+
+fn struct_id(input: &DeriveInput) -> syn::Result<u32> {
+    let mut id = None;
+    for attr in &input.attrs {
+        if !attr.path().is_ident("shura") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("replicate") {
+                return Err(meta.error("expected `replicate(id = ..)`"));
+            }
+            meta.parse_nested_meta(|meta| {
+                if !meta.path.is_ident("id") {
+                    return Err(meta.error("expected `id = ..`"));
+                }
+                id = Some(meta.value()?.parse::<syn::LitInt>()?.base10_parse()?);
+                Ok(())
+            })
+        })?;
+    }
+    Ok(id.unwrap_or_else(|| type_id(&input.ident.to_string())))
+}
+
+fn field(index: usize, field: &syn::Field) -> syn::Result<ReplicatedField> {
+    let mut replicate = false;
+    let mut quantize = quote!(::core::option::Option::None);
+    for attr in &field.attrs {
+        if !attr.path().is_ident("shura") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("replicate") {
+                return Err(meta.error("expected `replicate`"));
+            }
+            replicate = true;
+            if !meta.input.peek(syn::token::Paren) {
+                return Ok(());
+            }
+            meta.parse_nested_meta(|meta| {
+                if !meta.path.is_ident("quantize") {
+                    return Err(meta.error("expected `quantize(min, max, bits)`"));
+                }
+                if meta.input.peek(Token![=]) {
+                    let value: Expr = meta.value()?.parse()?;
+                    quantize = quote!(::core::option::Option::Some(#value));
+                    return Ok(());
+                }
+                let content;
+                parenthesized!(content in meta.input);
+                let args: Vec<Expr> = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?
+                    .into_iter()
+                    .collect();
+                let [min, max, bits] = &args[..] else {
+                    return Err(meta.error("expected `quantize(min, max, bits)`"));
+                };
+                quantize = quote!(::core::option::Option::Some(
+                    ::shura::ecs::Quantize::new(#min, #max, #bits)
+                ));
+                Ok(())
+            })
+        })?;
+    }
+
+    Ok(ReplicatedField {
+        member: match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(index)),
+        },
+        replicate,
+        quantize,
+    })
+}
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "Replicate can only be derived for structs",
+        ));
+    };
+    let id = struct_id(&input)?;
+    let fields = data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, f)| field(index, f))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let writes = fields.iter().filter(|field| field.replicate).map(|field| {
+        let ReplicatedField {
+            member, quantize, ..
+        } = field;
+        quote!(::shura::ecs::ReplicateField::write_field(&self.#member, writer, #quantize);)
+    });
+    let reads = fields.iter().map(|field| {
+        let ReplicatedField {
+            member,
+            replicate,
+            quantize,
+        } = field;
+        if *replicate {
+            quote!(#member: ::shura::ecs::ReplicateField::read_field(reader, #quantize)?,)
+        } else {
+            quote!(#member: ::core::default::Default::default(),)
+        }
+    });
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote!(
+        impl #impl_generics ::shura::ecs::Replicate for #name #ty_generics #where_clause {
+            const ID: u32 = #id;
+
+            #[allow(unused_variables)]
+            fn write(&self, writer: &mut ::shura::ecs::ReplicationWriter) {
+                #(#writes)*
+            }
+
+            #[allow(unused_variables)]
+            fn read(
+                reader: &mut ::shura::ecs::ReplicationReader,
+            ) -> ::core::option::Option<Self> {
+                ::core::option::Option::Some(Self { #(#reads)* })
+            }
+        }
+    ))
+}
@@ -8,16 +8,27 @@ fn app(config: AppConfig) {
             .system(System::update(update))
             .system(System::setup(setup))
             .system(System::render(render))
+            .system(System::suspend(suspend))
+            .system(System::resume(resume))
     });
 }
 
+// On Android the app gets suspended when it goes to the background
+fn suspend(ctx: &mut Context) {
+    info!("Suspended after {} seconds", ctx.time.total());
+}
+
+fn resume(ctx: &mut Context) {
+    info!("Resumed with size {:?}", ctx.render_size);
+}
+
 fn setup(ctx: &mut Context) {
     ctx.world_camera2d.set_scaling(WorldCameraScaling::Min(3.0));
     ctx.assets.load_font(
         "font",
         FontBuilder::bytes(include_resource_bytes!("bunnymark/novem.ttf")),
     );
-    ctx.assets.load_text_mesh::<&str>("text", "font", &[]);
+    ctx.assets.load_text::<&str>("text", "font", &[]);
     ctx.assets.load_sprite(
         "bunny_sprite",
         SpriteBuilder::bytes(include_resource_bytes!("bunnymark/wabbit.png")),
@@ -105,8 +116,8 @@ fn render(ctx: &RenderContext, encoder: &mut RenderEncoder) {
             &ctx.assets.sprite("bunny_sprite"),
         );
 
-        renderer.draw_text_mesh(
-            &ctx.assets.text_mesh("text"),
+        renderer.draw_text(
+            &ctx.assets.text("text"),
             &ctx.default_assets.relative_top_right_camera.0,
             &ctx.assets.font("font"),
         );
@@ -1,5 +1,15 @@
 mod ease;
+mod skeleton;
+#[cfg(feature = "gltf")]
+mod skin;
+mod timer;
 mod tween;
+mod tween_manager;
 
 pub use ease::*;
+pub use skeleton::*;
+#[cfg(feature = "gltf")]
+pub use skin::*;
+pub use timer::*;
 pub use tween::*;
+pub use tween_manager::*;
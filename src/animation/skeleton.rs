@@ -0,0 +1,487 @@
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    context::Context,
+    ecs::{Component, DisabledComponent, IterEnabled, System, WorldExt},
+    graphics::{SpriteAtlas, SpriteCropInstance2D},
+    math::{Isometry2, Matrix2, Matrix3, Vector2},
+    scene::{Plugin, SceneCreator},
+};
+
+/// Translation, rotation in radians and scale of a bone relative to its parent
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct BoneTransform {
+    pub translation: Vector2<f32>,
+    pub rotation: f32,
+    pub scale: Vector2<f32>,
+}
+
+impl BoneTransform {
+    pub fn matrix(&self) -> Matrix3<f32> {
+        let (sin, cos) = self.rotation.sin_cos();
+        Matrix3::new(
+            cos * self.scale.x,
+            -sin * self.scale.y,
+            self.translation.x,
+            sin * self.scale.x,
+            cos * self.scale.y,
+            self.translation.y,
+            0.0,
+            0.0,
+            1.0,
+        )
+    }
+
+    /// Interpolates the rotation along the shortest path
+    pub fn lerp(&self, other: &Self, factor: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(&other.translation, factor),
+            rotation: lerp_angle(self.rotation, other.rotation, factor),
+            scale: self.scale.lerp(&other.scale, factor),
+        }
+    }
+}
+
+impl Default for BoneTransform {
+    fn default() -> Self {
+        Self {
+            translation: Vector2::zeros(),
+            rotation: 0.0,
+            scale: Vector2::new(1.0, 1.0),
+        }
+    }
+}
+
+fn lerp_angle(from: f32, to: f32, factor: f32) -> f32 {
+    use std::f32::consts::{PI, TAU};
+    let delta = (to - from + PI).rem_euclid(TAU) - PI;
+    from + delta * factor
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bone {
+    pub name: String,
+    /// Index of the parent bone, which has to come before this bone
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub parent: Option<usize>,
+    /// Local transform when no clip animates the bone
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bind: BoneTransform,
+}
+
+/// Sprite that is attached to a bone. All slots of a skeleton share one sprite and use different
+/// regions of it.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Slot {
+    pub name: String,
+    pub bone: usize,
+    /// Transform relative to the bone
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub offset: BoneTransform,
+    pub size: Vector2<f32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub atlas: SpriteAtlas,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interpolation {
+    #[default]
+    Linear,
+    /// Keeps the value of a keyframe until the next one
+    Stepped,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Keyframe<T> {
+    /// Seconds since the start of the clip
+    pub time: f32,
+    pub value: T,
+}
+
+/// Keyframes of a single bone. Keyframes replace the value of the bind transform and have to be
+/// sorted by time, a missing channel keeps the bind transform.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct BoneTrack {
+    pub bone: usize,
+    pub interpolation: Interpolation,
+    pub translation: Vec<Keyframe<Vector2<f32>>>,
+    pub rotation: Vec<Keyframe<f32>>,
+    pub scale: Vec<Keyframe<Vector2<f32>>>,
+}
+
+impl BoneTrack {
+    fn sample<T: Copy>(
+        keyframes: &[Keyframe<T>],
+        time: f32,
+        interpolation: Interpolation,
+        lerp: impl Fn(&T, &T, f32) -> T,
+    ) -> Option<T> {
+        let first = keyframes.first()?;
+        let next = keyframes.partition_point(|keyframe| keyframe.time <= time);
+        if next == 0 {
+            return Some(first.value);
+        }
+        let previous = &keyframes[next - 1];
+        match (keyframes.get(next), interpolation) {
+            (Some(next), Interpolation::Linear) => {
+                let factor = (time - previous.time) / (next.time - previous.time);
+                Some(lerp(&previous.value, &next.value, factor))
+            }
+            _ => Some(previous.value),
+        }
+    }
+
+    fn apply(&self, time: f32, transform: &mut BoneTransform) {
+        let interpolation = self.interpolation;
+        if let Some(translation) =
+            Self::sample(&self.translation, time, interpolation, |a, b, f| {
+                a.lerp(b, f)
+            })
+        {
+            transform.translation = translation;
+        }
+        if let Some(rotation) = Self::sample(&self.rotation, time, interpolation, |a, b, f| {
+            lerp_angle(*a, *b, f)
+        }) {
+            transform.rotation = rotation;
+        }
+        if let Some(scale) = Self::sample(&self.scale, time, interpolation, |a, b, f| a.lerp(b, f))
+        {
+            transform.scale = scale;
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SkeletonClip {
+    /// Length in seconds
+    pub duration: f32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub looping: bool,
+    pub tracks: Vec<BoneTrack>,
+}
+
+impl SkeletonClip {
+    /// Writes the local transforms of all bones at `time` into `pose`
+    pub fn sample(&self, skeleton: &Skeleton, time: f32, pose: &mut [BoneTransform]) {
+        for (transform, bone) in pose.iter_mut().zip(&skeleton.bones) {
+            *transform = bone.bind;
+        }
+        for track in &self.tracks {
+            if let Some(transform) = pose.get_mut(track.bone) {
+                track.apply(time, transform);
+            }
+        }
+    }
+}
+
+/// Bones, slots and clips of a skeletal animation. Bones are sorted so that every parent comes
+/// before its children.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Skeleton {
+    pub bones: Vec<Bone>,
+    pub slots: Vec<Slot>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub clips: FxHashMap<String, SkeletonClip>,
+}
+
+impl Skeleton {
+    pub fn new(bones: Vec<Bone>, slots: Vec<Slot>) -> Self {
+        for (index, bone) in bones.iter().enumerate() {
+            if let Some(parent) = bone.parent {
+                assert!(
+                    parent < index,
+                    "Parent of bone {} has to come before it!",
+                    bone.name
+                );
+            }
+        }
+        for slot in &slots {
+            assert!(
+                slot.bone < bones.len(),
+                "Bone of slot {} does not exist!",
+                slot.name
+            );
+        }
+        Self {
+            bones,
+            slots,
+            clips: Default::default(),
+        }
+    }
+
+    /// Loads a skeleton from JSON:
+    ///
+    /// ```json
+    /// {
+    ///     "bones": [
+    ///         { "name": "body" },
+    ///         { "name": "arm", "parent": 0, "bind": { "translation": [0.5, 0.2] } }
+    ///     ],
+    ///     "slots": [
+    ///         { "name": "arm", "bone": 1, "size": [0.6, 0.2],
+    ///           "atlas": { "offset": [0.5, 0.0], "scaling": [0.5, 1.0], "alpha": 1.0 } }
+    ///     ],
+    ///     "clips": {
+    ///         "wave": {
+    ///             "duration": 1.0,
+    ///             "looping": true,
+    ///             "tracks": [
+    ///                 { "bone": 1, "interpolation": "Linear",
+    ///                   "rotation": [{ "time": 0.0, "value": 0.0 }, { "time": 0.5, "value": 1.2 },
+    ///                                { "time": 1.0, "value": 0.0 }] }
+    ///             ]
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// Omitted transforms are the identity and omitted channels keep the bind transform.
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &[u8]) -> anyhow::Result<Self> {
+        let skeleton: Skeleton = serde_json::from_slice(json)?;
+        let clips = skeleton.clips;
+        Ok(Self::new(skeleton.bones, skeleton.slots).with_clips(clips))
+    }
+
+    pub fn with_clip(mut self, name: impl Into<String>, clip: SkeletonClip) -> Self {
+        self.clips.insert(name.into(), clip);
+        self
+    }
+
+    fn with_clips(mut self, clips: FxHashMap<String, SkeletonClip>) -> Self {
+        self.clips = clips;
+        self
+    }
+
+    pub fn bone_index(&self, name: &str) -> Option<usize> {
+        self.bones.iter().position(|bone| bone.name == name)
+    }
+
+    pub fn clip(&self, name: &str) -> Option<&SkeletonClip> {
+        self.clips.get(name)
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct PlayingClip {
+    name: String,
+    time: f32,
+}
+
+/// Plays the clips of a [Skeleton] and computes the world transforms of its bones
+#[derive(Component, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SkeletonComponent {
+    skeleton: Arc<Skeleton>,
+    current: Option<PlayingClip>,
+    /// Clip that is faded out during a crossfade
+    previous: Option<PlayingClip>,
+    fade_elapsed: f32,
+    fade_duration: f32,
+    pub speed: f32,
+    paused: bool,
+    pose: Vec<BoneTransform>,
+    world: Vec<Matrix3<f32>>,
+}
+
+impl SkeletonComponent {
+    pub fn new(skeleton: Arc<Skeleton>) -> Self {
+        let mut component = Self {
+            pose: skeleton.bones.iter().map(|bone| bone.bind).collect(),
+            world: vec![Matrix3::identity(); skeleton.bones.len()],
+            skeleton,
+            current: None,
+            previous: None,
+            fade_elapsed: 0.0,
+            fade_duration: 0.0,
+            speed: 1.0,
+            paused: false,
+        };
+        component.compose();
+        component
+    }
+
+    pub fn skeleton(&self) -> &Skeleton {
+        &self.skeleton
+    }
+
+    /// Starts a clip from the beginning without blending
+    pub fn play(&mut self, name: &str) {
+        self.assert_clip(name);
+        self.previous = None;
+        self.current = Some(PlayingClip {
+            name: name.to_owned(),
+            time: 0.0,
+        });
+    }
+
+    /// Blends from the current pose to the clip over `duration` seconds
+    pub fn crossfade(&mut self, name: &str, duration: f32) {
+        self.assert_clip(name);
+        if duration <= 0.0 || self.current.is_none() {
+            return self.play(name);
+        }
+        self.previous = self.current.take();
+        self.current = Some(PlayingClip {
+            name: name.to_owned(),
+            time: 0.0,
+        });
+        self.fade_elapsed = 0.0;
+        self.fade_duration = duration;
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.previous = None;
+        for (transform, bone) in self.pose.iter_mut().zip(&self.skeleton.bones) {
+            *transform = bone.bind;
+        }
+        self.compose();
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn current_clip(&self) -> Option<&str> {
+        self.current.as_ref().map(|clip| clip.name.as_str())
+    }
+
+    pub fn is_crossfading(&self) -> bool {
+        self.previous.is_some()
+    }
+
+    /// Returns true if a non looping clip has reached its end
+    pub fn finished(&self) -> bool {
+        self.current.as_ref().is_some_and(|playing| {
+            let clip = &self.skeleton.clips[&playing.name];
+            !clip.looping && playing.time >= clip.duration
+        })
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        if self.paused || self.current.is_none() {
+            return;
+        }
+        let delta = delta * self.speed;
+        let skeleton = self.skeleton.clone();
+        for playing in self.current.iter_mut().chain(self.previous.iter_mut()) {
+            let clip = &skeleton.clips[&playing.name];
+            playing.time += delta;
+            if clip.looping && clip.duration > 0.0 {
+                playing.time = playing.time.rem_euclid(clip.duration);
+            } else {
+                playing.time = playing.time.min(clip.duration);
+            }
+        }
+
+        let current = self.current.as_ref().unwrap();
+        skeleton.clips[&current.name].sample(&skeleton, current.time, &mut self.pose);
+        if let Some(previous) = &self.previous {
+            self.fade_elapsed += delta;
+            let factor = self.fade_elapsed / self.fade_duration;
+            if factor >= 1.0 {
+                self.previous = None;
+            } else {
+                let mut from = self.pose.clone();
+                skeleton.clips[&previous.name].sample(&skeleton, previous.time, &mut from);
+                for (to, from) in self.pose.iter_mut().zip(&from) {
+                    *to = from.lerp(to, factor);
+                }
+            }
+        }
+        self.compose();
+    }
+
+    /// Transform of a bone relative to the skeleton
+    pub fn bone_matrix(&self, bone: usize) -> Matrix3<f32> {
+        self.world[bone]
+    }
+
+    /// Position of a bone relative to the skeleton, the scale is ignored
+    pub fn bone_position(&self, bone: usize) -> Isometry2<f32> {
+        let matrix = &self.world[bone];
+        Isometry2::new(
+            Vector2::new(matrix[(0, 2)], matrix[(1, 2)]),
+            matrix[(1, 0)].atan2(matrix[(0, 0)]),
+        )
+    }
+
+    /// One sprite instance per slot, positioned relative to `position`
+    pub fn instances(
+        &self,
+        position: Isometry2<f32>,
+    ) -> impl Iterator<Item = SpriteCropInstance2D> + '_ {
+        let root = position.to_homogeneous();
+        self.skeleton.slots.iter().map(move |slot| {
+            let matrix = root
+                * self.world[slot.bone]
+                * slot.offset.matrix()
+                * Matrix3::new_nonuniform_scaling(&slot.size);
+            SpriteCropInstance2D {
+                translation: Vector2::new(matrix[(0, 2)], matrix[(1, 2)]),
+                // Instances store the transposed linear part, see Instance2D::new
+                scale_rotation: Matrix2::new(
+                    matrix[(0, 0)],
+                    matrix[(1, 0)],
+                    matrix[(0, 1)],
+                    matrix[(1, 1)],
+                ),
+                data: slot.atlas,
+            }
+        })
+    }
+
+    fn compose(&mut self) {
+        for (index, bone) in self.skeleton.bones.iter().enumerate() {
+            let local = self.pose[index].matrix();
+            self.world[index] = match bone.parent {
+                Some(parent) => self.world[parent] * local,
+                None => local,
+            };
+        }
+    }
+
+    fn assert_clip(&self, name: &str) {
+        assert!(
+            self.skeleton.clips.contains_key(name),
+            "Skeleton clip '{name}' does not exist!"
+        );
+    }
+}
+
+/// Advances every [SkeletonComponent] in the world each frame.
+pub struct SkeletonPlugin;
+
+impl Plugin for SkeletonPlugin {
+    fn init<S: SceneCreator>(&mut self, scene: S) -> S {
+        scene.system(System::update(update_skeletons))
+    }
+}
+
+fn update_skeletons(ctx: &mut Context) {
+    let delta = ctx.time.delta();
+    let mut skeletons = ctx.world.view_mut::<SkeletonComponent>();
+    let disabled = ctx.world.view::<DisabledComponent>();
+    for (_, skeleton) in (&mut skeletons).iter_enabled(&disabled) {
+        skeleton.update(delta);
+    }
+}
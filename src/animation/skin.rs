@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use crate::{
+    context::Context,
+    ecs::{Component, DisabledComponent, IterEnabled, System, WorldExt},
+    graphics::{Gpu, NodeTransform, Skin, UniformData, MAX_JOINTS},
+    math::Matrix4,
+    scene::{Plugin, SceneCreator},
+};
+
+#[derive(Clone, Debug)]
+struct PlayingSkinClip {
+    name: String,
+    time: f32,
+    looping: bool,
+}
+
+/// Plays the animations of a [SkinnedModel](crate::graphics::SkinnedModel) and holds the joint
+/// matrices for [Renderer::draw_skinned_model](crate::graphics::Renderer::draw_skinned_model)
+#[derive(Component, Debug)]
+pub struct SkinComponent {
+    skin: Arc<Skin>,
+    current: Option<PlayingSkinClip>,
+    /// Clip that is blended out by [SkinComponent::blend_to]
+    previous: Option<PlayingSkinClip>,
+    blend_elapsed: f32,
+    blend_duration: f32,
+    pub speed: f32,
+    paused: bool,
+    pose: Vec<NodeTransform>,
+    matrices: Vec<Matrix4<f32>>,
+    joints: UniformData<Matrix4<f32>>,
+}
+
+impl SkinComponent {
+    /// Panics if the skin has more than [MAX_JOINTS] joints
+    pub fn new(gpu: &Gpu, skin: Arc<Skin>) -> Self {
+        assert!(
+            skin.joints.len() <= MAX_JOINTS,
+            "Skin has {} joints but at most {MAX_JOINTS} are supported!",
+            skin.joints.len()
+        );
+        let matrices = vec![Matrix4::identity(); MAX_JOINTS];
+        let mut component = Self {
+            joints: UniformData::new(gpu, gpu.default_layouts.joints_layout.clone(), &matrices),
+            pose: skin.rest_pose.clone(),
+            matrices,
+            skin,
+            current: None,
+            previous: None,
+            blend_elapsed: 0.0,
+            blend_duration: 0.0,
+            speed: 1.0,
+            paused: false,
+        };
+        component.compose();
+        component.buffer(gpu);
+        component
+    }
+
+    pub fn skin(&self) -> &Skin {
+        &self.skin
+    }
+
+    /// Starts a clip from the beginning without blending
+    pub fn play(&mut self, name: &str, looping: bool) {
+        self.assert_clip(name);
+        self.previous = None;
+        self.current = Some(PlayingSkinClip {
+            name: name.to_owned(),
+            time: 0.0,
+            looping,
+        });
+    }
+
+    /// Blends from the current clip to a looping clip over `duration` seconds
+    pub fn blend_to(&mut self, name: &str, duration: f32) {
+        self.assert_clip(name);
+        if duration <= 0.0 || self.current.is_none() {
+            return self.play(name, true);
+        }
+        self.previous = self.current.take();
+        self.current = Some(PlayingSkinClip {
+            name: name.to_owned(),
+            time: 0.0,
+            looping: true,
+        });
+        self.blend_elapsed = 0.0;
+        self.blend_duration = duration;
+    }
+
+    /// Jumps to `time` seconds of the current clip, the pose is updated on the next
+    /// [SkinComponent::update]
+    pub fn set_time(&mut self, time: f32) {
+        if let Some(current) = &mut self.current {
+            current.time = time;
+        }
+    }
+
+    pub fn time(&self) -> Option<f32> {
+        self.current.as_ref().map(|clip| clip.time)
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.previous = None;
+        self.pose.clone_from(&self.skin.rest_pose);
+        self.compose();
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn current_clip(&self) -> Option<&str> {
+        self.current.as_ref().map(|clip| clip.name.as_str())
+    }
+
+    pub fn is_blending(&self) -> bool {
+        self.previous.is_some()
+    }
+
+    /// Returns true if a non looping clip has reached its end
+    pub fn finished(&self) -> bool {
+        self.current.as_ref().is_some_and(|playing| {
+            !playing.looping && playing.time >= self.skin.clips[&playing.name].duration
+        })
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        if self.current.is_none() {
+            return;
+        }
+        let delta = if self.paused { 0.0 } else { delta * self.speed };
+        let skin = self.skin.clone();
+        for playing in self.current.iter_mut().chain(self.previous.iter_mut()) {
+            let clip = &skin.clips[&playing.name];
+            playing.time += delta;
+            if playing.looping && clip.duration > 0.0 {
+                playing.time = playing.time.rem_euclid(clip.duration);
+            } else {
+                playing.time = playing.time.clamp(0.0, clip.duration);
+            }
+        }
+
+        let current = self.current.as_ref().unwrap();
+        self.pose.clone_from(&skin.rest_pose);
+        skin.clips[&current.name].sample(current.time, &mut self.pose);
+        if let Some(previous) = &self.previous {
+            self.blend_elapsed += delta;
+            let factor = self.blend_elapsed / self.blend_duration;
+            if factor >= 1.0 {
+                self.previous = None;
+            } else {
+                let mut from = skin.rest_pose.clone();
+                skin.clips[&previous.name].sample(previous.time, &mut from);
+                for (to, from) in self.pose.iter_mut().zip(&from) {
+                    *to = from.lerp(to, factor);
+                }
+            }
+        }
+        self.compose();
+    }
+
+    /// Uploads the joint matrices of the current pose
+    pub fn buffer(&mut self, gpu: &Gpu) {
+        self.joints.write(gpu, &self.matrices);
+    }
+
+    /// Matrix per joint that transforms from the bind pose to the current pose
+    pub fn joint_matrix(&self, joint: usize) -> Matrix4<f32> {
+        self.matrices[joint]
+    }
+
+    pub fn joints(&self) -> &UniformData<Matrix4<f32>> {
+        &self.joints
+    }
+
+    fn compose(&mut self) {
+        self.skin.joint_matrices(&self.pose, &mut self.matrices);
+    }
+
+    fn assert_clip(&self, name: &str) {
+        assert!(
+            self.skin.clips.contains_key(name),
+            "Skin clip '{name}' does not exist!"
+        );
+    }
+}
+
+/// Advances every [SkinComponent] and uploads its joint matrices
+pub struct SkinPlugin;
+
+impl Plugin for SkinPlugin {
+    fn init<S: SceneCreator>(&mut self, scene: S) -> S {
+        scene.system(System::update(update_skins))
+    }
+}
+
+fn update_skins(ctx: &mut Context) {
+    let delta = ctx.time.delta();
+    let mut skins = ctx.world.view_mut::<SkinComponent>();
+    let disabled = ctx.world.view::<DisabledComponent>();
+    for (_, skin) in (&mut skins).iter_enabled(&disabled) {
+        skin.update(delta);
+        skin.buffer(&ctx.gpu);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::time::Duration;
+
+/// Counts down a duration that is advanced manually with [Timer::tick]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Timer {
+    duration: Duration,
+    elapsed: Duration,
+    repeating: bool,
+    times_finished: u32,
+}
+
+impl Timer {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            elapsed: Duration::ZERO,
+            repeating: false,
+            times_finished: 0,
+        }
+    }
+
+    /// Timer that starts over every time it finishes
+    pub fn repeating(duration: Duration) -> Self {
+        Self {
+            repeating: true,
+            ..Self::new(duration)
+        }
+    }
+
+    /// Advances the timer and returns true if it finished during this tick. A repeating timer
+    /// can finish multiple times during one tick, see [Timer::times_finished].
+    pub fn tick(&mut self, delta: Duration) -> bool {
+        if self.is_finished() {
+            return false;
+        }
+        self.elapsed += delta;
+        if self.elapsed < self.duration {
+            return false;
+        }
+
+        if self.repeating && !self.duration.is_zero() {
+            let times = (self.elapsed.as_nanos() / self.duration.as_nanos()) as u32;
+            self.times_finished += times;
+            self.elapsed -= self.duration * times;
+        } else {
+            self.times_finished += 1;
+            self.elapsed = self.duration;
+        }
+        true
+    }
+
+    /// True once a non repeating timer is done
+    pub fn is_finished(&self) -> bool {
+        !self.repeating && self.times_finished > 0
+    }
+
+    pub fn times_finished(&self) -> u32 {
+        self.times_finished
+    }
+
+    /// Progress of the current run between `0.0` and `1.0`
+    pub fn percent(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.elapsed)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn is_repeating(&self) -> bool {
+        self.repeating
+    }
+
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.duration = duration;
+    }
+
+    pub fn set_repeating(&mut self, repeating: bool) {
+        self.repeating = repeating;
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.times_finished = 0;
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     animation::EaseMethod,
+    graphics::Color,
     instant::Duration,
     math::{Isometry2, Rotation2, Vector2},
 };
@@ -34,6 +35,12 @@ impl Stepable for Rotation2<f32> {
     }
 }
 
+impl Stepable for Color {
+    fn step(&mut self, end: &Self, factor: f32) -> Self {
+        self.lerp(end, factor)
+    }
+}
+
 pub trait Tweenable {
     type Output: Stepable;
     fn value(&self) -> &Self::Output;
@@ -0,0 +1,114 @@
+use crate::{
+    animation::{TweenState, Tweenable},
+    context::Context,
+    ecs::{Component, EntityId, World, WorldExt},
+    time::Duration,
+};
+
+/// Id of a tween started with [TweenManager::animate]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TweenId(u64);
+
+type TickTween = Box<dyn FnMut(&World, Duration) -> Option<TweenState>>;
+type TweenCallback = Box<dyn FnOnce(&mut Context)>;
+
+struct ActiveTween {
+    id: TweenId,
+    tick: TickTween,
+    on_complete: Vec<TweenCallback>,
+}
+
+/// Tweens of a scene that are applied to components, accessible as `ctx.tweens`. They are
+/// ticked before the update systems of every frame.
+#[derive(Default)]
+pub struct TweenManager {
+    next_id: u64,
+    tweens: Vec<ActiveTween>,
+}
+
+impl TweenManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the value of `tween` to the component `C` of `entity` every frame until the tween
+    /// is completed. Stops without completing when the entity or the component is removed.
+    pub fn animate<C: Component + Send + Sync, T: Tweenable + 'static>(
+        &mut self,
+        entity: EntityId,
+        mut tween: T,
+        apply: impl Fn(&mut C, T::Output) + 'static,
+    ) -> TweenId {
+        let id = TweenId(self.next_id);
+        self.next_id += 1;
+        self.tweens.push(ActiveTween {
+            id,
+            tick: Box::new(move |world, delta| {
+                let mut components = world.view_mut::<C>();
+                if !components.contains(entity) {
+                    return None;
+                }
+                let state = tween.tick(delta);
+                (apply)(&mut components[entity], *tween.value());
+                Some(state)
+            }),
+            on_complete: Vec::new(),
+        });
+        id
+    }
+
+    /// Calls `callback` after the tween completed, for example to start the next tween of a
+    /// sequence. Returns false if the tween is not running.
+    pub fn then(&mut self, id: TweenId, callback: impl FnOnce(&mut Context) + 'static) -> bool {
+        match self.tweens.iter_mut().find(|tween| tween.id == id) {
+            Some(tween) => {
+                tween.on_complete.push(Box::new(callback));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stops the tween without calling its completion callbacks
+    pub fn cancel(&mut self, id: TweenId) -> bool {
+        let len = self.tweens.len();
+        self.tweens.retain(|tween| tween.id != id);
+        self.tweens.len() != len
+    }
+
+    pub fn is_running(&self, id: TweenId) -> bool {
+        self.tweens.iter().any(|tween| tween.id == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tweens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tweens.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.tweens.clear();
+    }
+}
+
+pub(crate) fn update_tweens(ctx: &mut Context) {
+    let delta = ctx.time.delta_duration();
+    let world = &*ctx.world;
+    let mut completed = Vec::new();
+    ctx.tweens
+        .tweens
+        .retain_mut(|tween| match (tween.tick)(world, delta) {
+            Some(TweenState::Active) => true,
+            Some(TweenState::Completed) => {
+                completed.append(&mut tween.on_complete);
+                false
+            }
+            None => false,
+        });
+
+    for callback in completed {
+        (callback)(ctx);
+    }
+}
@@ -1,20 +1,33 @@
 use std::sync::{Arc, OnceLock};
 
+#[cfg(feature = "debug-draw")]
+use crate::graphics::DebugDraw;
+#[cfg(target_arch = "wasm32")]
+use crate::graphics::WebCanvas;
+#[cfg(multi_window)]
+use crate::graphics::WindowManager;
 #[cfg(feature = "gui")]
 use crate::gui::Gui;
 use crate::{
     context::{Context, RenderContext},
-    ecs::{EndReason, GlobalWorld, UpdateOperation},
-    graphics::{AssetManager, Gpu, GpuConfig, RenderEncoder},
-    input::Input,
+    ecs::{
+        fixed_ticks, run_parallel_systems, run_state_systems, EndReason, GlobalWorld,
+        UpdateOperation,
+    },
+    graphics::{
+        AssetManager, Gpu, GpuConfig, MonitorInfo, RedrawMode, RenderEncoder, RenderTarget,
+        SurfaceRenderTarget, TransitionRenderer,
+    },
+    i18n::I18n,
+    input::{Clipboard, Input, Key},
     io::{ResourceLoader, StorageLoader},
     math::Vector2,
     scene::{Scene, SceneManager},
-    time::TimeManager,
+    time::{Diagnostics, Duration, Instant, TimeManager},
 };
 #[cfg(feature = "log")]
 use crate::{
-    log::{info, LoggerBuilder},
+    log::{error, info, LoggerBuilder},
     VERSION,
 };
 #[cfg(target_os = "android")]
@@ -22,7 +35,7 @@ use winit::platform::android::activity::AndroidApp;
 use winit::{
     application::ApplicationHandler,
     event::WindowEvent,
-    event_loop::{ActiveEventLoop, EventLoop},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     window::Window,
 };
 
@@ -61,16 +74,24 @@ pub struct AppConfig {
     pub storage: Arc<dyn StorageLoader>,
     pub resource: Arc<dyn ResourceLoader>,
     pub scene_id: u32,
+    /// Toggles borderless fullscreen when Alt+Enter is pressed
+    pub alt_enter_fullscreen: bool,
     #[cfg(feature = "framebuffer")]
     pub apply_frame_buffer: bool,
     #[cfg(target_os = "android")]
     pub android: AndroidApp,
     #[cfg(feature = "log")]
     pub logger: Option<LoggerBuilder>,
+    /// Only applied to the canvas created by shura, not to one found with `canvas_selector`
     #[cfg(target_arch = "wasm32")]
     pub canvas_attrs: FxHashMap<String, String>,
+    /// Stretches the canvas created by shura over the whole browser window
     #[cfg(target_arch = "wasm32")]
     pub auto_scale_canvas: bool,
+    /// CSS selector of an existing canvas to render to instead of appending a new one to the
+    /// body. Its size is then controlled by the CSS of the page.
+    #[cfg(target_arch = "wasm32")]
+    pub canvas_selector: Option<String>,
 }
 
 impl Default for AppConfig {
@@ -83,10 +104,7 @@ impl AppConfig {
     pub const FIRST_SCENE_ID: u32 = 0;
     pub fn new(#[cfg(target_os = "android")] android: AndroidApp) -> Self {
         #[cfg(target_arch = "wasm32")]
-        let (resource, storage) = (
-            crate::io::WebResourceLoader,
-            crate::io::UnimplementedStorageLoader,
-        );
+        let (resource, storage) = (crate::io::WebResourceLoader, crate::io::WebStorageLoader);
 
         #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
         let (resource, storage) = (
@@ -106,6 +124,7 @@ impl AppConfig {
                 .with_title("App Game"),
             gpu: GpuConfig::default(),
             scene_id: Self::FIRST_SCENE_ID,
+            alt_enter_fullscreen: false,
             storage: Arc::new(storage),
             resource: Arc::new(resource),
             #[cfg(target_os = "android")]
@@ -115,6 +134,8 @@ impl AppConfig {
             #[cfg(target_arch = "wasm32")]
             auto_scale_canvas: true,
             #[cfg(target_arch = "wasm32")]
+            canvas_selector: None,
+            #[cfg(target_arch = "wasm32")]
             canvas_attrs: {
                 let mut map = FxHashMap::default();
                 map.insert("tabindex".into(), "0".into());
@@ -137,11 +158,31 @@ impl AppConfig {
         self
     }
 
+    /// Sets the window and taskbar icon from an image file, e.g. a PNG. Ignored on wasm and
+    /// Android. An invalid image is skipped, use [window_icon] to handle the error yourself.
+    pub fn icon(mut self, bytes: &[u8]) -> Self {
+        match window_icon(bytes) {
+            Ok(icon) => self.window = self.window.with_window_icon(Some(icon)),
+            Err(_err) => {
+                #[cfg(feature = "log")]
+                error!("Cannot set window icon: {_err}");
+            }
+        }
+        self
+    }
+
     pub fn gpu(mut self, gpu: GpuConfig) -> Self {
         self.gpu = gpu;
         self
     }
 
+    /// Restricts the adapter to `backends`, e.g. [wgpu::Backends::GL] to test the WebGL2
+    /// fallback in a browser that supports WebGPU
+    pub fn backends(mut self, backends: wgpu::Backends) -> Self {
+        self.gpu.backends = backends;
+        self
+    }
+
     pub fn scene_id(mut self, scene_id: u32) -> Self {
         self.scene_id = scene_id;
         self
@@ -182,6 +223,12 @@ impl AppConfig {
         self.auto_scale_canvas = auto_scale_canvas;
         self
     }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn canvas_selector(mut self, selector: impl Into<String>) -> Self {
+        self.canvas_selector = Some(selector.into());
+        self
+    }
 }
 
 enum AppState<S: Into<Scene>, I: FnOnce() -> S> {
@@ -201,10 +248,13 @@ impl<S: Into<Scene>, I: FnOnce() -> S> AppState<S, I> {
 }
 
 impl<S: Into<Scene>, I: FnOnce() -> S> ApplicationHandler<()> for AppState<S, I> {
-    fn about_to_wait(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
+    fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         match self {
-            AppState::Initialized(app) => app.window.request_redraw(),
-            AppState::Uninitialized { .. } => (),
+            // Suspended apps idle until they are resumed
+            AppState::Initialized(app) if !app.suspended => app.schedule_frame(event_loop),
+            AppState::Initialized(_) | AppState::Uninitialized { .. } => {
+                event_loop.set_control_flow(ControlFlow::Wait)
+            }
         }
     }
 
@@ -232,12 +282,14 @@ impl<S: Into<Scene>, I: FnOnce() -> S> ApplicationHandler<()> for AppState<S, I>
     }
 
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
-        if cfg!(target_os = "android") {
-            match self {
-                AppState::Initialized(app) => app.gpu.resume(&app.window),
-                AppState::Uninitialized { .. } => self.init(event_loop),
-            };
-        }
+        match self {
+            AppState::Initialized(app) => app.resume(event_loop),
+            AppState::Uninitialized { .. } => {
+                if cfg!(target_os = "android") {
+                    self.init(event_loop)
+                }
+            }
+        };
     }
 
     fn window_event(
@@ -251,8 +303,14 @@ impl<S: Into<Scene>, I: FnOnce() -> S> ApplicationHandler<()> for AppState<S, I>
             AppState::Uninitialized { .. } => return,
         };
 
+        if window_id == app.window.id() && !matches!(event, WindowEvent::RedrawRequested) {
+            app.redraw_pending = true;
+        }
+
         #[cfg(feature = "gui")]
-        app.gui.handle_event(&app.window, &event);
+        if window_id == app.window.id() {
+            app.gui.handle_event(&app.window, &event);
+        }
 
         if !app.window_events.events.is_empty() {
             let scene_id = app.scenes.active_scene_id();
@@ -263,22 +321,26 @@ impl<S: Into<Scene>, I: FnOnce() -> S> ApplicationHandler<()> for AppState<S, I>
             let (window_events, _, mut ctx) = Context::new(&scene_id, app, scene, event_loop);
             for e in &mut window_events.events {
                 e(&mut ctx, &event);
+                ctx.apply_commands();
             }
         }
 
         if window_id == app.window.id() {
             match &event {
                 WindowEvent::RedrawRequested => {
+                    if app.suspended {
+                        return;
+                    }
                     app.process_frame(event_loop);
                     if app.end {
                         event_loop.exit();
-                    } else {
-                        app.window.request_redraw();
                     }
                 }
                 WindowEvent::CloseRequested | WindowEvent::Destroyed => {
                     app.end = true;
                 }
+                // The size of the canvas is tracked by the app itself on wasm
+                #[cfg(not(target_arch = "wasm32"))]
                 WindowEvent::Resized(physical_size) => {
                     let width = physical_size.width.max(1);
                     let height = physical_size.height.max(1);
@@ -286,18 +348,46 @@ impl<S: Into<Scene>, I: FnOnce() -> S> ApplicationHandler<()> for AppState<S, I>
                 }
                 _ => app.input.on_event(&event),
             }
+        } else {
+            #[cfg(multi_window)]
+            if app.windows.contains(window_id) {
+                match &event {
+                    WindowEvent::CloseRequested | WindowEvent::Destroyed => {
+                        app.windows.close(window_id);
+                        app.input
+                            .on_window_event(window_id, &WindowEvent::Destroyed);
+                    }
+                    WindowEvent::Resized(physical_size) => {
+                        let width = physical_size.width.max(1);
+                        let height = physical_size.height.max(1);
+                        app.windows.resize(window_id, Vector2::new(width, height));
+                    }
+                    WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                        app.windows.set_scale_factor(window_id, *scale_factor);
+                    }
+                    _ => app.input.on_window_event(window_id, &event),
+                }
+            }
         }
     }
 
-    fn suspended(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {}
+    fn suspended(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        if let AppState::Initialized(app) = self {
+            app.suspend(event_loop);
+        }
+    }
     fn memory_warning(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {}
     fn user_event(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop, _event: ()) {}
     fn device_event(
         &mut self,
         _event_loop: &winit::event_loop::ActiveEventLoop,
         _device_id: winit::event::DeviceId,
-        _event: winit::event::DeviceEvent,
+        event: winit::event::DeviceEvent,
     ) {
+        if let AppState::Initialized(app) = self {
+            app.redraw_pending |= app.input.is_focused();
+            app.input.on_device_event(&event);
+        }
     }
 }
 
@@ -308,11 +398,18 @@ pub struct App {
     pub(crate) assets: Arc<AssetManager>,
     pub(crate) end: bool,
     pub(crate) time: TimeManager,
+    pub(crate) diagnostics: Diagnostics,
     pub(crate) scenes: SceneManager,
     pub(crate) window: Arc<Window>,
     pub(crate) input: Input,
+    pub(crate) i18n: I18n,
+    pub(crate) clipboard: Clipboard,
     pub(crate) global_world: GlobalWorld,
+    #[cfg(feature = "serde")]
+    pub(crate) prefabs: crate::serde::PrefabRegistry,
     pub(crate) gpu: Arc<Gpu>,
+    #[cfg(multi_window)]
+    pub(crate) windows: WindowManager,
     #[cfg(feature = "gui")]
     pub(crate) gui: Gui,
     #[cfg(feature = "audio")]
@@ -320,9 +417,16 @@ pub struct App {
     #[cfg(feature = "audio")]
     pub(crate) audio: AudioManager,
     #[cfg(target_arch = "wasm32")]
-    pub(crate) auto_scale_canvas: bool,
+    pub(crate) web_canvas: WebCanvas,
     #[cfg(feature = "framebuffer")]
     pub(crate) apply_framebuffer: bool,
+    #[cfg(feature = "debug-draw")]
+    pub(crate) debug: DebugDraw,
+    pub(crate) scene_transition: Option<TransitionRenderer>,
+    pub(crate) alt_enter_fullscreen: bool,
+    pub(crate) suspended: bool,
+    /// An event arrived since the last frame, see [RedrawMode::OnEvent]
+    pub(crate) redraw_pending: bool,
 }
 
 impl App {
@@ -362,26 +466,56 @@ impl App {
         config: AppConfig,
         init: impl FnOnce() -> S,
     ) -> Self {
+        #[cfg(target_arch = "wasm32")]
+        let mut config = config;
+        #[cfg(target_arch = "wasm32")]
+        if let Some(selector) = &config.canvas_selector {
+            use wasm_bindgen_futures::wasm_bindgen::JsCast;
+            use winit::platform::web::WindowAttributesExtWebSys;
+
+            let canvas = web_sys::window()
+                .and_then(|window| window.document())
+                .and_then(|document| document.query_selector(selector).ok().flatten())
+                .and_then(|element| element.dyn_into::<web_sys::HtmlCanvasElement>().ok())
+                .unwrap_or_else(|| panic!("No canvas matches '{selector}'!"));
+            config.window = config.window.with_canvas(Some(canvas));
+            // The CSS of the page decides the size
+            config.window.inner_size = None;
+        }
+
         let (resource, storage) = (config.resource, config.storage);
         let window = event_loop.create_window(config.window).unwrap();
         let window = Arc::new(window);
 
         #[cfg(target_arch = "wasm32")]
-        {
+        let web_canvas = {
             use console_error_panic_hook::hook;
             use winit::platform::web::WindowExtWebSys;
 
             std::panic::set_hook(Box::new(hook));
-            let canvas = &web_sys::Element::from(window.canvas().unwrap());
-            for (attr, value) in config.canvas_attrs {
-                canvas.set_attribute(&attr, &value).unwrap();
-            }
+            let canvas = window.canvas().unwrap();
+            if config.canvas_selector.is_none() {
+                let element = web_sys::Element::from(canvas.clone());
+                for (attr, value) in config.canvas_attrs {
+                    element.set_attribute(&attr, &value).unwrap();
+                }
+                if config.auto_scale_canvas {
+                    let style = element.get_attribute("style").unwrap_or_default();
+                    element
+                        .set_attribute(
+                            "style",
+                            &format!("{style} display: block; width: 100vw; height: 100vh;"),
+                        )
+                        .unwrap();
+                }
 
-            let browser_window = web_sys::window().unwrap();
-            let document = browser_window.document().unwrap();
-            let body = document.body().unwrap();
-            body.append_child(canvas).ok();
-        }
+                let browser_window = web_sys::window().unwrap();
+                let document = browser_window.document().unwrap();
+                let body = document.body().unwrap();
+                body.append_child(&element).ok();
+            }
+            WebCanvas::new(canvas)
+        };
 
         let gpu = pollster::block_on(Gpu::new(window.clone(), config.gpu));
         let gpu = Arc::new(gpu);
@@ -401,6 +535,8 @@ impl App {
 
         let size = gpu.surface_size();
         let scene = (init)();
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.set_adapter(&gpu.adapter_info());
 
         Self {
             window_events: config.window_events,
@@ -411,9 +547,13 @@ impl App {
             #[cfg(feature = "gui")]
             gui: Gui::new(&window, &gpu),
             #[cfg(target_arch = "wasm32")]
-            auto_scale_canvas: config.auto_scale_canvas,
+            web_canvas,
             #[cfg(feature = "framebuffer")]
             apply_framebuffer: config.apply_frame_buffer,
+            #[cfg(feature = "debug-draw")]
+            debug: DebugDraw::new(),
+            #[cfg(multi_window)]
+            windows: WindowManager::new(gpu.clone()),
             window,
             gpu,
             assets,
@@ -422,8 +562,17 @@ impl App {
             end: false,
             scenes: SceneManager::new(scene.into(), config.scene_id),
             time: TimeManager::new(),
+            diagnostics,
             input: Input::new(size.cast::<f32>()),
+            i18n: I18n::new(),
+            clipboard: Clipboard::new(),
             global_world: Default::default(),
+            #[cfg(feature = "serde")]
+            prefabs: Default::default(),
+            scene_transition: None,
+            alt_enter_fullscreen: config.alt_enter_fullscreen,
+            suspended: false,
+            redraw_pending: true,
         }
     }
 
@@ -435,31 +584,105 @@ impl App {
         self.gpu.resize(new_size);
         let mut default_assets = self.assets.default_assets_mut();
         default_assets.resize(&self.gpu, new_size);
+        #[cfg(target_arch = "wasm32")]
+        self.web_canvas.apply(new_size);
         #[cfg(feature = "gui")]
-        self.gui.resize(self.window.scale_factor() as f32, new_size);
+        self.gui.resize(self.scale_factor() as f32, new_size);
+    }
+
+    fn scale_factor(&self) -> f64 {
+        #[cfg(target_arch = "wasm32")]
+        return self.web_canvas.scale_factor();
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.window.scale_factor();
+    }
+
+    /// Runs the suspend systems of the active scene and pauses the audio. On Android the surface
+    /// gets destroyed with the native window, so it is dropped last.
+    fn suspend(&mut self, event_loop: &ActiveEventLoop) {
+        if self.suspended {
+            return;
+        }
+        #[cfg(feature = "log")]
+        info!("Suspending app");
+        self.run_lifecycle_systems(event_loop, true);
+        #[cfg(feature = "audio")]
+        self.audio.set_suspended(true);
+        if cfg!(target_os = "android") {
+            self.gpu.suspend();
+        }
+        self.suspended = true;
+    }
+
+    /// Recreates the surface and everything sized to it before the resume systems run
+    fn resume(&mut self, event_loop: &ActiveEventLoop) {
+        if !self.suspended {
+            return;
+        }
+        #[cfg(feature = "log")]
+        info!("Resuming app");
+        self.suspended = false;
+        self.gpu.resume(&self.window);
+        self.resize(self.gpu.surface_size());
+        #[cfg(feature = "audio")]
+        self.audio.set_suspended(false);
+        self.run_lifecycle_systems(event_loop, false);
+        self.window.request_redraw();
+    }
+
+    fn run_lifecycle_systems(&mut self, event_loop: &ActiveEventLoop, suspend: bool) {
+        let scene_id = self.scenes.active_scene_id();
+        let scene = self.scenes.get_active_scene();
+        let mut scene = scene.borrow_mut();
+        let (_, systems, mut ctx) = Context::new(&scene_id, self, &mut scene, event_loop);
+        let systems = if suspend {
+            &systems.suspend_systems
+        } else {
+            &systems.resume_systems
+        };
+        for (_, system) in systems {
+            (system)(&mut ctx);
+            ctx.apply_commands();
+        }
     }
 
     fn process_frame(&mut self, event_loop: &ActiveEventLoop) {
+        self.end_popped_overlays(event_loop);
+        // The canvas follows its CSS size and the devicePixelRatio. This has to happen before the
+        // active scene is borrowed.
+        #[cfg(target_arch = "wasm32")]
+        {
+            let size = self.web_canvas.physical_size();
+            if size != self.gpu.surface_size() {
+                self.resize(size);
+            }
+        }
         let scene_id = self.scenes.active_scene_id();
         let scene = self.scenes.get_active_scene();
         let mut scene = scene.borrow_mut();
         let scene = &mut scene;
         let surface_size = self.gpu.surface_size();
-        #[cfg(target_arch = "wasm32")]
+        self.redraw_pending = false;
+        scene.screen_config.redraw_requested = false;
+
+        #[cfg(feature = "hot-reload")]
+        self.assets.reload_assets();
+
+        if self.alt_enter_fullscreen
+            && self.input.is_pressed(Key::Enter)
+            && (self.input.is_held(Key::AltLeft) || self.input.is_held(Key::AltRight))
         {
-            if self.auto_scale_canvas {
-                let browser_window = web_sys::window().unwrap();
-                let width: u32 = browser_window.inner_width().unwrap().as_f64().unwrap() as u32;
-                let height: u32 = browser_window.inner_height().unwrap().as_f64().unwrap() as u32;
-                let size = winit::dpi::PhysicalSize::new(width, height);
-                if size != self.window.inner_size().into() {
-                    let _ = self.window.request_inner_size(size);
-                }
-            }
+            scene.screen_config.toggle_fullscreen();
         }
+        if scene.screen_config.fullscreen_changed {
+            scene.screen_config.apply_fullscreen(&self.window);
+            scene.screen_config.fullscreen_changed = false;
+        }
+        self.input.apply_cursor_grab(&self.window);
 
-        let resized = self.scenes.switched().is_some() || scene.screen_config.changed;
+        let resized = self.scenes.scene_changed() || scene.screen_config.changed;
         if resized {
+            scene.screen_config.monitors = MonitorInfo::query(&self.window);
             #[cfg(feature = "framebuffer")]
             let scale = scene.screen_config.render_scale();
 
@@ -475,7 +698,7 @@ impl App {
                     )
                 };
 
-                if self.scenes.switched().is_some() {
+                if self.scenes.scene_changed() {
                     info!("Switched to scene {}!", scene_id);
                 }
 
@@ -494,45 +717,143 @@ impl App {
             scene.world_camera3d.resize(surface_size);
 
             self.gpu.apply_vsync(scene.screen_config.vsync());
-            #[cfg(feature = "framebuffer")]
             {
                 let mut default_assets = self.assets.default_assets_mut();
+                #[cfg(feature = "framebuffer")]
                 default_assets.apply_render_scale(&self.gpu, &scene.screen_config);
+                default_assets.apply_ui_camera(&self.gpu, &scene.screen_config);
             }
             scene.screen_config.changed = false;
         }
 
         self.update(scene_id, scene, event_loop);
-        if scene.render_entities || resized {
+        self.scenes.advance_transition(self.time.delta_duration());
+        if scene.render_entities || resized || self.scenes.is_transitioning() {
             self.render(scene);
         }
         self.input.update();
+        #[cfg(multi_window)]
+        self.windows.update();
     }
 
-    fn update(&mut self, scene_id: u32, scene: &mut Scene, event_loop: &ActiveEventLoop) {
-        if let Some(max_frame_time) = scene.screen_config.max_frame_time() {
-            let last_update = self.time.update();
-            if instant::Instant::now() < last_update + max_frame_time {
-                return;
+    /// Decides when the next frame is rendered. Waiting for the fps limit sleeps until shortly
+    /// before the frame is due and spins for the rest, since sleeping alone is too imprecise.
+    fn schedule_frame(&mut self, event_loop: &ActiveEventLoop) {
+        const SPIN_TIME: Duration = Duration::from_micros(1500);
+
+        let scene = self.scenes.get_active_scene();
+        let scene = scene.borrow();
+        let now = Instant::now();
+        let mut next_frame = match scene.screen_config.redraw_mode() {
+            RedrawMode::Continuous => Some(now),
+            RedrawMode::OnEvent
+                if self.redraw_pending
+                    || scene.screen_config.redraw_requested
+                    || scene.screen_config.changed
+                    || self.scenes.scene_changed() =>
+            {
+                Some(now)
             }
+            RedrawMode::OnEvent => None,
+        };
+        #[cfg(feature = "gui")]
+        if let Some(repaint) = self
+            .gui
+            .repaint_delay()
+            .and_then(|delay| now.checked_add(delay))
+        {
+            next_frame = Some(next_frame.map_or(repaint, |next_frame| next_frame.min(repaint)));
+        }
+
+        let Some(mut next_frame) = next_frame else {
+            event_loop.set_control_flow(ControlFlow::Wait);
+            return;
+        };
+        if let Some(min_frame_time) = scene.screen_config.min_frame_time() {
+            next_frame = next_frame.max(self.time.update() + min_frame_time);
         }
+
+        if next_frame > now + SPIN_TIME {
+            // winit on wasm uses its own clock, so the browser has to poll
+            #[cfg(target_arch = "wasm32")]
+            event_loop.set_control_flow(ControlFlow::Poll);
+            #[cfg(not(target_arch = "wasm32"))]
+            event_loop.set_control_flow(ControlFlow::WaitUntil(next_frame - SPIN_TIME));
+            return;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        while Instant::now() < next_frame {
+            std::hint::spin_loop();
+        }
+        event_loop.set_control_flow(ControlFlow::Wait);
+        self.window.request_redraw();
+    }
+
+    fn update(&mut self, scene_id: u32, scene: &mut Scene, event_loop: &ActiveEventLoop) {
         self.time.tick();
+        self.gpu.poll_profiler();
+        self.diagnostics.frame(
+            self.time.delta_duration(),
+            self.gpu.take_render_stats(),
+            self.gpu.pass_timings(),
+        );
 
         #[cfg(feature = "gamepad")]
         self.input.sync_gamepad();
         #[cfg(feature = "gui")]
         self.gui.begin(&self.time.total_duration(), &self.window);
+        #[cfg(feature = "gui")]
+        self.input
+            .set_keyboard_suppressed(self.gui.wants_keyboard_input());
+
+        for id in self.scenes.updated_underneath() {
+            if let Some(underneath) = self.scenes.get(id) {
+                self.update_scene(id, &mut underneath.borrow_mut(), event_loop, false);
+            }
+        }
+        self.update_scene(scene_id, scene, event_loop, true);
+        self.diagnostics.count_components(&scene.world);
+        self.diagnostics.record(&scene.world);
+        #[cfg(feature = "gui")]
+        self.diagnostics.gui(&self.gui);
+        #[cfg(all(feature = "debug-draw", feature = "text", not(feature = "gui")))]
+        self.diagnostics
+            .debug_draw(&self.debug, scene.world_camera2d.camera());
+
+        #[cfg(feature = "audio")]
+        self.audio
+            .set_listener(*scene.world_camera2d.camera().translation());
+        #[cfg(feature = "audio")]
+        self.audio.update(self.time.delta());
+    }
+
+    /// Runs the systems of a single scene. Scenes underneath an overlay get an [Input] where
+    /// nothing is pressed.
+    fn update_scene(
+        &mut self,
+        scene_id: u32,
+        scene: &mut Scene,
+        event_loop: &ActiveEventLoop,
+        receives_input: bool,
+    ) {
+        let idle_input = (!receives_input).then(|| self.input.idle());
         let (_, systems, mut ctx) = Context::new(&scene_id, self, scene, event_loop);
+        if let Some(idle_input) = &idle_input {
+            ctx.input = idle_input;
+            ctx.cursor = idle_input.cursor(ctx.world_camera2d.camera());
+        }
         let now = ctx.time.update();
 
         for (_, setup) in systems.setup_systems.drain(..) {
-            (setup)(&mut ctx)
+            (setup)(&mut ctx);
+            ctx.apply_commands();
         }
 
         if *ctx.started {
             if let Some(last_id) = ctx.scenes.switched() {
                 for (_, switch) in &systems.switch_systems {
-                    (switch)(&mut ctx, last_id)
+                    (switch)(&mut ctx, last_id);
+                    ctx.apply_commands();
                 }
             }
         }
@@ -540,15 +861,44 @@ impl App {
         if ctx.screen_config.changed {
             for (_, resize) in &systems.resize_systems {
                 (resize)(&mut ctx);
+                ctx.apply_commands();
             }
         }
 
         let receiver = ctx.tasks.receiver();
         while let Ok(callback) = receiver.try_recv() {
             (callback)(&mut ctx);
+            ctx.apply_commands();
+        }
+
+        let receiver = ctx.clipboard.receiver();
+        while let Ok(callback) = receiver.try_recv() {
+            (callback)(&mut ctx);
+            ctx.apply_commands();
+        }
+
+        #[cfg(feature = "net")]
+        {
+            let receiver = ctx.net.receiver();
+            while let Ok(callback) = receiver.try_recv() {
+                (callback)(&mut ctx);
+                ctx.apply_commands();
+            }
         }
 
-        for (_, (update_operation, update)) in &mut systems.update_systems {
+        #[cfg(feature = "animation")]
+        {
+            crate::animation::update_tweens(&mut ctx);
+            ctx.apply_commands();
+        }
+
+        run_state_systems(&systems.state_systems, &mut ctx);
+        let mut parallel_systems = systems.parallel_systems.as_slice();
+        for (priority, (update_operation, update)) in &mut systems.update_systems {
+            let ready = parallel_systems.partition_point(|(p, _)| p <= priority);
+            run_parallel_systems(ctx.world, ctx.diagnostics, &parallel_systems[..ready]);
+            parallel_systems = &parallel_systems[ready..];
+
             match update_operation {
                 UpdateOperation::EveryFrame => (),
                 UpdateOperation::EveryNFrame(frames) => {
@@ -563,15 +913,35 @@ impl App {
                         *last_update = now;
                     }
                 }
+                UpdateOperation::FixedUpdate {
+                    accumulator,
+                    fixed_delta,
+                } => {
+                    for _ in 0..fixed_ticks(ctx.time, accumulator, *fixed_delta) {
+                        (update)(&mut ctx);
+                        ctx.apply_commands();
+                        run_state_systems(&systems.state_systems, &mut ctx);
+                    }
+                    continue;
+                }
             }
 
             (update)(&mut ctx);
+            ctx.apply_commands();
+            run_state_systems(&systems.state_systems, &mut ctx);
         }
+        run_parallel_systems(ctx.world, ctx.diagnostics, parallel_systems);
+        #[cfg(feature = "physics")]
+        systems.collision_handlers.dispatch(&mut ctx);
+        ctx.apply_commands();
         scene.started = true;
+        scene
+            .world_camera2d
+            .update(self.time.delta(), self.time.alpha(), &scene.world);
         // scene.groups.update(&scene.world_camera2d);
     }
 
-    fn buffer(&mut self, scene: &mut Scene) {
+    fn buffer(&self, scene: &Scene) {
         let mut default_assets = self.assets.default_assets_mut();
         default_assets
             .world_camera2d
@@ -581,15 +951,121 @@ impl App {
             .world_camera3d
             .write(&self.gpu, &scene.world_camera3d);
 
+        let mut lights3d = scene.lights3d;
+        lights3d.set_camera_position(scene.world_camera3d.eye().coords);
+        default_assets.lights3d.write(&self.gpu, &[lights3d]);
+
+        default_assets.sky = scene.sky.cubemap();
+        if default_assets.sky.is_some() {
+            default_assets
+                .sky_camera
+                .write(&self.gpu, &[scene.world_camera3d.sky_matrix()]);
+        }
+
         default_assets
             .times
             .write(&self.gpu, &[[self.time.total(), self.time.delta()]]);
     }
 
+    /// Renders the scene that is left behind by a transition into the offscreen target of the
+    /// transition. Returns false if no transition is running.
+    fn render_transition_source(&mut self, surface_target: &SurfaceRenderTarget) -> bool {
+        let Some(transition) = self.scenes.active_transition() else {
+            return false;
+        };
+        if transition.from == self.scenes.active_scene_id() {
+            return false;
+        }
+        let (from, kind, progress) = (
+            transition.from,
+            transition.transition,
+            transition.progress(),
+        );
+        let Some(from) = self.scenes.get(from) else {
+            return false;
+        };
+        let from = from.borrow();
+        self.buffer(&from);
+
+        let default_assets = self.assets.default_assets();
+        let (systems, ctx) = RenderContext::new(
+            self.assets.clone(),
+            self.gpu.clone(),
+            surface_target,
+            &default_assets,
+            &self.time,
+            &self.diagnostics,
+            #[cfg(feature = "debug-draw")]
+            &self.debug,
+            &from,
+        );
+        let size = ctx.target().size();
+        let transition = self
+            .scene_transition
+            .get_or_insert_with(|| TransitionRenderer::new(&self.gpu, size));
+        transition.prepare(&self.gpu, size, kind, progress);
+
+        let mut encoder =
+            RenderEncoder::new(&self.gpu, &self.assets, &default_assets, &transition.from);
+        systems.render(&ctx, &mut encoder);
+        encoder.finish();
+        self.gpu.submit();
+        true
+    }
+
+    /// Renders the scenes underneath the overlays from the bottom up
+    fn render_underneath(&self, surface_target: &SurfaceRenderTarget, transitioning: bool) {
+        for id in self.scenes.underneath() {
+            let Some(scene) = self.scenes.get(id) else {
+                continue;
+            };
+            let scene = scene.borrow();
+            self.buffer(&scene);
+
+            let default_assets = self.assets.default_assets();
+            let (systems, ctx) = RenderContext::new(
+                self.assets.clone(),
+                self.gpu.clone(),
+                surface_target,
+                &default_assets,
+                &self.time,
+                &self.diagnostics,
+                #[cfg(feature = "debug-draw")]
+                &self.debug,
+                &scene,
+            );
+            let target: &dyn RenderTarget =
+                match self.scene_transition.as_ref().filter(|_| transitioning) {
+                    Some(transition) => &transition.to,
+                    None => ctx.target(),
+                };
+            let mut encoder = RenderEncoder::new(&self.gpu, &self.assets, &default_assets, target);
+            systems.render(&ctx, &mut encoder);
+            encoder.finish();
+            self.gpu.submit();
+        }
+    }
+
+    /// Calls the end systems of overlays popped during the last frame and removes them
+    fn end_popped_overlays(&mut self, event_loop: &ActiveEventLoop) {
+        for id in self.scenes.take_popped_overlays() {
+            if let Some(scene) = self.scenes.get(id) {
+                let mut scene = scene.borrow_mut();
+                let (_, systems, mut ctx) = Context::new(&id, self, &mut scene, event_loop);
+                for (_, end) in &systems.end_systems {
+                    (end)(&mut ctx, EndReason::Removed)
+                }
+            }
+            self.scenes.remove(id);
+        }
+    }
+
     fn render(&mut self, scene: &mut Scene) {
+        let surface_target = self.gpu.start_frame(&self.gpu);
+        let transitioning = self.render_transition_source(&surface_target);
+        self.render_underneath(&surface_target, transitioning);
         self.buffer(scene);
 
-        let surface_target = self.gpu.start_frame(&self.gpu);
         let default_assets = self.assets.default_assets();
 
         let (systems, ctx) = RenderContext::new(
@@ -597,13 +1073,35 @@ impl App {
             self.gpu.clone(),
             &surface_target,
             &default_assets,
+            &self.time,
+            &self.diagnostics,
+            #[cfg(feature = "debug-draw")]
+            &self.debug,
             scene,
         );
+        let transition = self.scene_transition.as_ref().filter(|_| transitioning);
+        let default_target: &dyn RenderTarget = match transition {
+            Some(transition) => &transition.to,
+            None => ctx.target(),
+        };
         let mut encoder =
-            RenderEncoder::new(&self.gpu, &self.assets, &default_assets, ctx.target());
+            RenderEncoder::new(&self.gpu, &self.assets, &default_assets, default_target);
+
+        systems.render(&ctx, &mut encoder);
+
+        #[cfg(feature = "debug-draw")]
+        self.debug.render(
+            &self.gpu,
+            &self.assets,
+            &mut encoder,
+            &scene.world_camera2d,
+            &default_assets.world_camera2d,
+            ctx.target().size(),
+        );
 
-        for (_, render) in &systems.render_systems {
-            (render)(&ctx, &mut encoder);
+        if let Some(transition) = transition {
+            transition.render(&mut encoder, ctx.target());
+            encoder.default_target = ctx.target();
         }
 
         #[cfg(feature = "framebuffer")]
@@ -616,13 +1114,66 @@ impl App {
         #[cfg(feature = "gui")]
         self.gui.render(&surface_target, &self.gpu, &mut encoder);
 
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.gpu.is_capturing_frames() {
+            let target: &dyn RenderTarget = if surface_target
+                .surface_texture
+                .texture
+                .usage()
+                .contains(wgpu::TextureUsages::COPY_SRC)
+            {
+                &surface_target
+            } else {
+                ctx.target()
+            };
+            self.gpu.capture_frame(&mut encoder.inner, target);
+        }
+
         encoder.finish();
         self.gpu.submit();
         surface_target.finish();
+        self.gpu.poll_screenshots();
+
+        #[cfg(multi_window)]
+        self.render_windows(scene);
+        self.gpu.finish_profiler_frame();
+        self.gpu.reset_transient_uniforms();
+    }
+
+    #[cfg(multi_window)]
+    fn render_windows(&mut self, scene: &mut Scene) {
+        let default_assets = self.assets.default_assets();
+        for (id, window) in &self.windows.windows {
+            let Some(render) = &window.render else {
+                continue;
+            };
+            let Some(surface_target) = self.windows.start_frame(*id) else {
+                continue;
+            };
+            let (_, mut ctx) = RenderContext::new(
+                self.assets.clone(),
+                self.gpu.clone(),
+                &surface_target,
+                &default_assets,
+                &self.time,
+                &self.diagnostics,
+                #[cfg(feature = "debug-draw")]
+                &self.debug,
+                scene,
+            );
+            ctx.window = Some(*id);
+            let mut encoder =
+                RenderEncoder::new(&self.gpu, &self.assets, &default_assets, ctx.target());
+            (render)(&ctx, &mut encoder);
+            encoder.finish();
+            self.gpu.submit();
+            surface_target.finish();
+        }
     }
 
     fn end(&mut self, event_loop: &ActiveEventLoop) {
         self.end = true;
+        self.diagnostics.stop_recording();
         let scenes = self.scenes.end_scenes();
         for (id, scene) in scenes {
             let mut scene = scene.borrow_mut();
@@ -641,6 +1192,34 @@ pub static GLOBAL_GPU: OnceLock<Arc<Gpu>> = OnceLock::new();
 #[cfg(feature = "audio")]
 pub static GLOBAL_AUDIO: OnceLock<AudioManager> = OnceLock::new();
 
+#[derive(Debug)]
+pub enum IconError {
+    /// The bytes are not a supported image file
+    Decode(image::ImageError),
+    /// The decoded image is rejected by the platform, e.g. because it is empty
+    Invalid(winit::window::BadIcon),
+}
+
+impl std::fmt::Display for IconError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IconError::Decode(error) => write!(f, "Cannot decode window icon: {error}"),
+            IconError::Invalid(error) => write!(f, "Invalid window icon: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for IconError {}
+
+/// Decodes an image file into an icon for [winit::window::WindowAttributes::with_window_icon]
+pub fn window_icon(bytes: &[u8]) -> Result<winit::window::Icon, IconError> {
+    let image = image::load_from_memory(bytes)
+        .map_err(IconError::Decode)?
+        .into_rgba8();
+    let (width, height) = image.dimensions();
+    winit::window::Icon::from_rgba(image.into_raw(), width, height).map_err(IconError::Invalid)
+}
+
 pub fn global_resources() -> Arc<dyn ResourceLoader> {
     GLOBAL_RESOURCE_LOADER.get().unwrap().clone()
 }
@@ -657,3 +1236,25 @@ pub fn global_gpu() -> Arc<Gpu> {
 pub fn global_audio() -> AudioManager {
     GLOBAL_AUDIO.get().unwrap().clone()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_icon_from_png() {
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image::RgbaImage::new(16, 16)
+            .write_to(&mut bytes, image::ImageFormat::Png)
+            .unwrap();
+        assert!(window_icon(bytes.get_ref()).is_ok());
+    }
+
+    #[test]
+    fn invalid_window_icon_is_an_error() {
+        assert!(matches!(
+            window_icon(b"not an image"),
+            Err(IconError::Decode(_))
+        ));
+    }
+}
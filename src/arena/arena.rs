@@ -338,6 +338,77 @@ impl<T> Arena<T> {
 
         (item1, item2)
     }
+
+    /// Mutable access to several entries at once. [None] if an index is stale or appears twice.
+    pub fn get_many_mut<const N: usize>(
+        &mut self,
+        indices: [ArenaIndex; N],
+    ) -> Option<[&mut T; N]> {
+        let end = indices.iter().map(|i| i.index + 1).max().unwrap_or(0);
+        let mut found: [Option<&mut T>; N] = std::array::from_fn(|_| None);
+        for (i, entry) in self.items.iter_mut().take(end).enumerate() {
+            if let ArenaEntry::Occupied { generation, data } = entry {
+                if let Some(slot) = indices
+                    .iter()
+                    .position(|index| index.index == i && index.generation == *generation)
+                {
+                    found[slot] = Some(data);
+                }
+            }
+        }
+        if found.iter().any(Option::is_none) {
+            return None;
+        }
+        Some(found.map(Option::unwrap))
+    }
+
+    /// Splits the slots into chunks of `chunk_size`, e.g. to hand them to different threads.
+    /// Chunks contain fewer entries where slots are free.
+    pub fn chunks_mut(&mut self, chunk_size: usize) -> impl Iterator<Item = ArenaIterMut<'_, T>> {
+        self.items.chunks_mut(chunk_size).map(|chunk| ArenaIterMut {
+            len: chunk
+                .iter()
+                .filter(|entry| matches!(entry, ArenaEntry::Occupied { .. }))
+                .count(),
+            base: chunk.iter_mut(),
+        })
+    }
+
+    /// Indices of all entries in the order of `compare`. The entries themselves stay where
+    /// they are, so every index remains valid.
+    pub fn sorted_indices_by(
+        &self,
+        mut compare: impl FnMut(&T, &T) -> cmp::Ordering,
+    ) -> Vec<ArenaIndex> {
+        let mut indices: Vec<(ArenaIndex, &T)> = self.iter_with_index().collect();
+        indices.sort_by(|(_, a), (_, b)| compare(a, b));
+        indices.into_iter().map(|(index, _)| index).collect()
+    }
+
+    /// Removes and returns every entry for which `predicate` returns true. Their indices become
+    /// invalid, inserting the values again gives them new ones.
+    pub fn drain_filter(
+        &mut self,
+        mut predicate: impl FnMut(ArenaIndex, &mut T) -> bool,
+    ) -> Vec<(ArenaIndex, T)> {
+        let mut drained = Vec::new();
+        for i in 0..self.capacity() {
+            let index = match &mut self.items[i] {
+                ArenaEntry::Occupied { generation, data } => {
+                    let index = ArenaIndex {
+                        index: i,
+                        generation: *generation,
+                    };
+                    predicate(index, data).then_some(index)
+                }
+                _ => None,
+            };
+            if let Some(index) = index {
+                drained.extend(self.remove(index).map(|data| (index, data)));
+            }
+        }
+        drained
+    }
 }
 
 impl<'a, T> IntoIterator for &'a Arena<T> {
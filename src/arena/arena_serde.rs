@@ -115,11 +115,13 @@ where
         let init_cap = access.size_hint().unwrap_or(DEFAULT_CAPACITY);
         let mut items = Vec::with_capacity(init_cap);
 
+        // Start after the newest loaded generation, so reused slots never match an index
+        // that was serialized
         let mut generation = 0;
         while let Some(element) = access.next_element::<Option<(u32, T)>>()? {
             let item = match element {
                 Some((gen, data)) => {
-                    generation = cmp::max(generation, gen);
+                    generation = cmp::max(generation, gen.wrapping_add(1));
                     ArenaEntry::Occupied {
                         generation: gen,
                         data,
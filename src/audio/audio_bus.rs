@@ -0,0 +1,235 @@
+use std::sync::{Arc, Weak};
+
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+
+use crate::audio::AudioSink;
+
+struct AudioBusState {
+    volume: f32,
+    paused: bool,
+    sinks: Vec<Weak<AudioSink>>,
+    detached: Vec<Arc<AudioSink>>,
+}
+
+impl Default for AudioBusState {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            paused: false,
+            sinks: Vec::new(),
+            detached: Vec::new(),
+        }
+    }
+}
+
+impl AudioBusState {
+    fn apply(&mut self, master_volume: f32, master_paused: bool) {
+        self.sinks.retain(|sink| sink.strong_count() > 0);
+        self.detached.retain(|sink| !sink.empty());
+        let volume = self.volume * master_volume;
+        let paused = self.paused || master_paused;
+        for sink in self
+            .sinks
+            .iter()
+            .filter_map(|sink| sink.upgrade())
+            .chain(self.detached.iter().cloned())
+        {
+            sink.set_volume(volume);
+            if paused {
+                sink.pause();
+            } else {
+                sink.play();
+            }
+        }
+    }
+}
+
+struct AudioFade {
+    from: Arc<AudioSink>,
+    to: Arc<AudioSink>,
+    volume: f32,
+    elapsed: f32,
+    duration: f32,
+}
+
+#[derive(Default)]
+pub(crate) struct AudioMixer {
+    master_volume: f32,
+    master_paused: bool,
+    suspended: bool,
+    buses: FxHashMap<String, AudioBusState>,
+    fades: Vec<AudioFade>,
+}
+
+impl AudioMixer {
+    pub(crate) fn new() -> Self {
+        Self {
+            master_volume: 1.0,
+            ..Default::default()
+        }
+    }
+
+    fn apply(&mut self, name: &str) {
+        let paused = self.master_paused || self.suspended;
+        if name == AudioBus::MASTER {
+            for bus in self.buses.values_mut() {
+                bus.apply(self.master_volume, paused);
+            }
+        } else if let Some(bus) = self.buses.get_mut(name) {
+            bus.apply(self.master_volume, paused);
+        }
+    }
+
+    pub(crate) fn set_suspended(&mut self, suspended: bool) {
+        self.suspended = suspended;
+        self.apply(AudioBus::MASTER);
+    }
+
+    /// Name of the bus a sink was registered on
+    pub(crate) fn bus_of(&self, sink: &Arc<AudioSink>) -> Option<String> {
+        self.buses.iter().find_map(|(name, bus)| {
+            bus.sinks
+                .iter()
+                .filter_map(|other| other.upgrade())
+                .chain(bus.detached.iter().cloned())
+                .any(|other| Arc::ptr_eq(&other, sink))
+                .then(|| name.clone())
+        })
+    }
+
+    pub(crate) fn fade(&mut self, from: Arc<AudioSink>, to: Arc<AudioSink>, duration: f32) {
+        let volume = from.volume();
+        to.set_volume(0.0);
+        self.fades.push(AudioFade {
+            from,
+            to,
+            volume,
+            elapsed: 0.0,
+            duration,
+        });
+    }
+
+    pub(crate) fn update(&mut self, delta: f32) {
+        if self.suspended {
+            return;
+        }
+        self.fades.retain_mut(|fade| {
+            fade.elapsed += delta;
+            let t = (fade.elapsed / fade.duration).min(1.0);
+            fade.from.set_volume(fade.volume * (1.0 - t));
+            fade.to.set_volume(fade.volume * t);
+            if t >= 1.0 {
+                fade.from.stop();
+            }
+            t < 1.0
+        });
+    }
+
+    pub(crate) fn register(&mut self, name: &str, sink: &Arc<AudioSink>, detached: bool) {
+        let bus = self.buses.entry(name.to_string()).or_default();
+        if detached {
+            bus.detached.push(sink.clone());
+        } else {
+            bus.sinks.push(Arc::downgrade(sink));
+        }
+        self.apply(name);
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) {
+        if let Some(bus) = self.buses.remove(name) {
+            for sink in bus.sinks.iter().filter_map(|sink| sink.upgrade()) {
+                sink.stop();
+            }
+            for sink in bus.detached {
+                sink.stop();
+            }
+        }
+    }
+}
+
+/// Group of sinks that share a volume and can be paused together. The volume of every sink is
+/// the bus volume times the volume of the [AudioBus::MASTER] bus, so changing the volume of a
+/// sink directly gets overwritten by the next change of its bus.
+#[derive(Clone)]
+pub struct AudioBus {
+    name: String,
+    mixer: Arc<Mutex<AudioMixer>>,
+}
+
+impl AudioBus {
+    /// Scales and pauses all other buses
+    pub const MASTER: &'static str = "master";
+
+    pub(crate) fn new(name: &str, mixer: Arc<Mutex<AudioMixer>>) -> Self {
+        if name != Self::MASTER {
+            mixer.lock().buses.entry(name.to_string()).or_default();
+        }
+        Self {
+            name: name.to_string(),
+            mixer,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        self.modify(|volume_ref, _| *volume_ref = volume);
+    }
+
+    pub fn volume(&self) -> f32 {
+        let mut mixer = self.mixer.lock();
+        if self.name == Self::MASTER {
+            mixer.master_volume
+        } else {
+            mixer.buses.entry(self.name.clone()).or_default().volume
+        }
+    }
+
+    pub fn pause(&self) {
+        self.modify(|_, paused| *paused = true);
+    }
+
+    pub fn resume(&self) {
+        self.modify(|_, paused| *paused = false);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        let mut mixer = self.mixer.lock();
+        if self.name == Self::MASTER {
+            mixer.master_paused
+        } else {
+            mixer.buses.entry(self.name.clone()).or_default().paused
+        }
+    }
+
+    /// Stops all sounds that are currently playing on this bus
+    pub fn stop_all(&self) {
+        let mut mixer = self.mixer.lock();
+        for (name, bus) in mixer.buses.iter_mut() {
+            if self.name == Self::MASTER || *name == self.name {
+                for sink in bus.sinks.iter().filter_map(|sink| sink.upgrade()) {
+                    sink.stop();
+                }
+                bus.sinks.clear();
+                for sink in bus.detached.drain(..) {
+                    sink.stop();
+                }
+            }
+        }
+    }
+
+    fn modify(&self, modify: impl FnOnce(&mut f32, &mut bool)) {
+        let mut mixer = self.mixer.lock();
+        let mixer = &mut *mixer;
+        if self.name == Self::MASTER {
+            modify(&mut mixer.master_volume, &mut mixer.master_paused);
+        } else {
+            let bus = mixer.buses.entry(self.name.clone()).or_default();
+            modify(&mut bus.volume, &mut bus.paused);
+        }
+        mixer.apply(&self.name);
+    }
+}
@@ -1,4 +1,15 @@
-use crate::audio::{AudioSink, Sound, SoundBuilder};
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use parking_lot::Mutex;
+
+use crate::{
+    audio::{
+        AudioBus, AudioMixer, AudioSink, Sound, SoundBuilder, SpatialAudioSink, SpatialConfig,
+        StreamedSound,
+    },
+    math::Vector2,
+};
 
 pub struct AudioDeviceManager {
     pub output_stream: rodio::OutputStream,
@@ -13,7 +24,11 @@ impl AudioDeviceManager {
                 output_stream,
                 output_handle: output_handle.clone(),
             },
-            AudioManager { output_handle },
+            AudioManager {
+                output_handle,
+                mixer: Arc::new(Mutex::new(AudioMixer::new())),
+                listener: Arc::new(Mutex::new(Vector2::zeros())),
+            },
         )
     }
 
@@ -23,6 +38,8 @@ impl AudioDeviceManager {
 #[derive(Clone)]
 pub struct AudioManager {
     pub output_handle: rodio::OutputStreamHandle,
+    mixer: Arc<Mutex<AudioMixer>>,
+    listener: Arc<Mutex<Vector2<f32>>>,
 }
 
 impl AudioManager {
@@ -44,4 +61,104 @@ impl AudioManager {
     pub fn create_sound(&self, builder: SoundBuilder) -> Sound {
         Sound::new(builder)
     }
+
+    /// Returns the bus with the given name and creates it if it doesn't exist yet
+    pub fn bus(&self, name: &str) -> AudioBus {
+        AudioBus::new(name, self.mixer.clone())
+    }
+
+    pub fn master(&self) -> AudioBus {
+        self.bus(AudioBus::MASTER)
+    }
+
+    /// Stops all sounds of a bus and forgets its volume
+    pub fn remove_bus(&self, name: &str) {
+        self.mixer.lock().remove(name);
+    }
+
+    /// Creates a sink that follows the volume and pause state of a bus for as long as it lives
+    pub fn create_sink_on(&self, bus: &str) -> Arc<AudioSink> {
+        let sink = Arc::new(self.create_sink());
+        self.mixer.lock().register(bus, &sink, false);
+        sink
+    }
+
+    pub fn play_once_on(&self, bus: &str, sound: &Sound) {
+        let sink = Arc::new(self.create_sink());
+        sink.append(sound.decode());
+        self.mixer.lock().register(bus, &sink, true);
+    }
+
+    /// Position of the listener for spatial sounds, updated to the translation of the world
+    /// camera of the active scene every frame
+    pub fn listener(&self) -> Vector2<f32> {
+        *self.listener.lock()
+    }
+
+    pub(crate) fn set_listener(&self, listener: Vector2<f32>) {
+        *self.listener.lock() = listener;
+    }
+
+    /// Plays a sound panned and attenuated by its position relative to the listener. Sounds
+    /// out of range are not played at all.
+    pub fn play_spatial(&self, sound: &Sound, position: Vector2<f32>, config: SpatialConfig) {
+        if !config.audible(position - self.listener()) {
+            return;
+        }
+        let sink = self.create_spatial_sink(position, config);
+        sink.append(sound);
+        sink.detach();
+    }
+
+    pub fn create_spatial_sink(
+        &self,
+        position: Vector2<f32>,
+        config: SpatialConfig,
+    ) -> SpatialAudioSink {
+        SpatialAudioSink::new(self, position, config)
+    }
+
+    /// Fails if the sound cannot be opened or decoded
+    pub fn play_stream(&self, sound: &StreamedSound) -> Result<AudioSink> {
+        let source = sound.source()?;
+        let sink = self.create_sink();
+        sink.append(source);
+        Ok(sink)
+    }
+
+    pub fn play_stream_on(&self, bus: &str, sound: &StreamedSound) -> Result<Arc<AudioSink>> {
+        let source = sound.source()?;
+        let sink = self.create_sink_on(bus);
+        sink.append(source);
+        Ok(sink)
+    }
+
+    /// Fades out `old` while fading in `new` at the volume of `old`. The new sink plays on the
+    /// same bus as `old` and `old` gets stopped once the fade is done. If `new` cannot be
+    /// decoded, `old` keeps playing.
+    pub fn crossfade(
+        &self,
+        old: Arc<AudioSink>,
+        new: &StreamedSound,
+        duration: Duration,
+    ) -> Result<Arc<AudioSink>> {
+        let source = new.source()?;
+        let mut mixer = self.mixer.lock();
+        let sink = Arc::new(self.create_sink());
+        if let Some(bus) = mixer.bus_of(&old) {
+            mixer.register(&bus, &sink, false);
+        }
+        sink.append(source);
+        mixer.fade(old, sink.clone(), duration.as_secs_f32());
+        Ok(sink)
+    }
+
+    /// Pauses all buses while the app is suspended
+    pub(crate) fn set_suspended(&self, suspended: bool) {
+        self.mixer.lock().set_suspended(suspended);
+    }
+
+    pub(crate) fn update(&self, delta: f32) {
+        self.mixer.lock().update(delta);
+    }
 }
@@ -1,7 +1,13 @@
+mod audio_bus;
 mod audio_manager;
 mod sound;
+mod spatial;
+mod stream;
 
+pub use audio_bus::*;
 pub use audio_manager::*;
 pub use rodio::Sink as AudioSink;
 pub use rodio::*;
 pub use sound::*;
+pub use spatial::*;
+pub use stream::*;
@@ -0,0 +1,129 @@
+use rodio::SpatialSink;
+
+use crate::{
+    audio::{AudioManager, Sound},
+    math::Vector2,
+};
+
+/// How a sound fades with the distance to the listener, which is the translation of the world
+/// camera
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpatialConfig {
+    /// Sounds further away from the listener are not audible
+    pub max_distance: f32,
+    /// Exponent of the falloff, `1.0` is linear
+    pub rolloff: f32,
+}
+
+impl Default for SpatialConfig {
+    fn default() -> Self {
+        Self {
+            max_distance: 20.0,
+            rolloff: 1.0,
+        }
+    }
+}
+
+impl SpatialConfig {
+    pub fn new(max_distance: f32) -> Self {
+        Self {
+            max_distance,
+            ..Default::default()
+        }
+    }
+
+    pub fn rolloff(mut self, rolloff: f32) -> Self {
+        self.rolloff = rolloff;
+        self
+    }
+
+    pub fn gain(&self, distance: f32) -> f32 {
+        (1.0 - distance / self.max_distance)
+            .clamp(0.0, 1.0)
+            .powf(self.rolloff)
+    }
+
+    /// `-1.0` is fully left, `1.0` fully right
+    pub fn pan(&self, offset: Vector2<f32>) -> f32 {
+        (offset.x / self.max_distance).clamp(-1.0, 1.0)
+    }
+
+    pub fn audible(&self, offset: Vector2<f32>) -> bool {
+        offset.norm() < self.max_distance
+    }
+}
+
+/// Sink whose panning and volume depend on its position relative to the listener. Inaudible
+/// sinks are paused until they get into range again.
+pub struct SpatialAudioSink {
+    sink: SpatialSink,
+    pub config: SpatialConfig,
+    pub position: Vector2<f32>,
+    pub volume: f32,
+    paused: bool,
+}
+
+impl SpatialAudioSink {
+    pub fn new(
+        audio: &AudioManager,
+        position: Vector2<f32>,
+        config: SpatialConfig,
+    ) -> SpatialAudioSink {
+        let sink = SpatialSink::try_new(
+            &audio.output_handle,
+            [0.0, 0.0, 0.0],
+            [-1.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+        )
+        .unwrap();
+        let mut sink = Self {
+            sink,
+            config,
+            position,
+            volume: 1.0,
+            paused: false,
+        };
+        sink.update(audio.listener());
+        sink
+    }
+
+    pub fn append(&self, sound: &Sound) {
+        self.sink.append(sound.decode());
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.sink.pause();
+    }
+
+    pub fn play(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn stop(&self) {
+        self.sink.stop();
+    }
+
+    pub fn empty(&self) -> bool {
+        self.sink.empty()
+    }
+
+    pub fn detach(self) {
+        self.sink.detach();
+    }
+
+    /// Recomputes panning and volume for the listener position
+    pub fn update(&mut self, listener: Vector2<f32>) {
+        let offset = self.position - listener;
+        if self.paused || !self.config.audible(offset) {
+            self.sink.pause();
+            return;
+        }
+        let pan = self.config.pan(offset);
+        self.sink.set_emitter_position([pan, 0.0, 0.0]);
+        self.sink
+            .set_volume(self.volume * self.config.gain(offset.norm()));
+        self.sink.play();
+    }
+}
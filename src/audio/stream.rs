@@ -0,0 +1,263 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use rodio::{Decoder, Source};
+
+#[cfg(feature = "log")]
+use crate::log::error;
+use crate::{
+    audio::SoundBuilder,
+    io::{ResourceLoader, ResourceReader},
+};
+
+type StreamDecoder = Decoder<Box<dyn ResourceReader>>;
+
+/// Sound that is decoded from the resource while it plays instead of being kept in memory,
+/// meant for long music tracks
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreamedSound {
+    pub path: String,
+    /// Restarts from this point after the end of the track, so the part before is an intro
+    /// that only plays once
+    pub loop_start: Option<Duration>,
+}
+
+impl SoundBuilder {
+    /// Fails if the resource cannot be opened or its format is not supported
+    pub fn stream_asset(path: &str) -> Result<StreamedSound> {
+        let sound = StreamedSound::asset(path);
+        sound.decode()?;
+        Ok(sound)
+    }
+}
+
+impl StreamedSound {
+    pub fn asset(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            loop_start: None,
+        }
+    }
+
+    /// Loops the whole track
+    pub fn looped(self) -> Self {
+        self.loop_from(Duration::ZERO)
+    }
+
+    pub fn loop_from(mut self, loop_start: Duration) -> Self {
+        self.loop_start = Some(loop_start);
+        self
+    }
+
+    pub fn decode(&self) -> Result<StreamDecoder> {
+        self.decode_from(&*crate::app::global_resources())
+    }
+
+    pub fn decode_from(&self, resources: &dyn ResourceLoader) -> Result<StreamDecoder> {
+        let reader = resources
+            .open(&self.path)
+            .with_context(|| format!("Cannot open {}", self.path))?;
+        Decoder::new(reader).with_context(|| format!("Cannot decode {}", self.path))
+    }
+
+    pub fn source(&self) -> Result<Box<dyn Source<Item = i16> + Send>> {
+        self.source_from(&*crate::app::global_resources())
+    }
+
+    /// The resource is opened once, loops seek back in the same decoder
+    pub fn source_from(
+        &self,
+        resources: &dyn ResourceLoader,
+    ) -> Result<Box<dyn Source<Item = i16> + Send>> {
+        let decoder = self.decode_from(resources)?;
+        Ok(match self.loop_start {
+            Some(loop_start) => Box::new(LoopedStream {
+                decoder,
+                loop_start,
+            }),
+            None => Box::new(decoder),
+        })
+    }
+}
+
+struct LoopedStream {
+    decoder: StreamDecoder,
+    loop_start: Duration,
+}
+
+impl Iterator for LoopedStream {
+    type Item = i16;
+
+    // Runs on the audio thread, so a failing seek ends the track instead of panicking
+    fn next(&mut self) -> Option<i16> {
+        if let Some(sample) = self.decoder.next() {
+            return Some(sample);
+        }
+        if let Err(_err) = self.decoder.try_seek(self.loop_start) {
+            #[cfg(feature = "log")]
+            error!("Cannot loop streamed sound: {_err}");
+            return None;
+        }
+        self.decoder.next()
+    }
+}
+
+impl Source for LoopedStream {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.decoder.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.decoder.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.decoder.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Seek, SeekFrom},
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use super::*;
+    use crate::io::NativeResourceLoader;
+
+    const TONE: &str = "tests/audio/tone.wav";
+    /// 10 seconds of 8 kHz mono 16 bit PCM
+    const TONE_SAMPLES: usize = 80_000;
+    const TONE_BYTES: usize = 44 + TONE_SAMPLES * 2;
+
+    struct CountingReader {
+        reader: Box<dyn ResourceReader>,
+        read: Arc<AtomicUsize>,
+    }
+
+    impl Read for CountingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let read = self.reader.read(buf)?;
+            self.read.fetch_add(read, Ordering::Relaxed);
+            Ok(read)
+        }
+    }
+
+    impl Seek for CountingReader {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.reader.seek(pos)
+        }
+    }
+
+    /// Counts how often resources are opened and how many bytes are read from them
+    #[derive(Default)]
+    struct CountingLoader {
+        in_memory: bool,
+        opened: AtomicUsize,
+        read: Arc<AtomicUsize>,
+    }
+
+    impl CountingLoader {
+        fn loader() -> NativeResourceLoader {
+            NativeResourceLoader {
+                resource_dir: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources"),
+            }
+        }
+    }
+
+    impl ResourceLoader for CountingLoader {
+        fn load_bytes(&self, path: &str) -> Result<Vec<u8>> {
+            Self::loader().load_bytes(path)
+        }
+
+        fn load_string(&self, path: &str) -> Result<String> {
+            Self::loader().load_string(path)
+        }
+
+        fn open(&self, path: &str) -> Result<Box<dyn ResourceReader>> {
+            self.opened.fetch_add(1, Ordering::Relaxed);
+            if self.in_memory {
+                let data = self.load_bytes(path)?;
+                self.read.fetch_add(data.len(), Ordering::Relaxed);
+                return Ok(Box::new(std::io::Cursor::new(data)));
+            }
+            Ok(Box::new(CountingReader {
+                reader: Self::loader().open(path)?,
+                read: self.read.clone(),
+            }))
+        }
+    }
+
+    /// Largest amount of bytes that were read ahead of the decoded samples
+    fn read_ahead(loader: &CountingLoader) -> usize {
+        let mut decoder = StreamedSound::asset(TONE).decode_from(loader).unwrap();
+        let mut samples = 0;
+        let mut read_ahead = 0;
+        while decoder.next().is_some() {
+            samples += 1;
+            let consumed = 44 + samples * 2;
+            read_ahead =
+                read_ahead.max(loader.read.load(Ordering::Relaxed).saturating_sub(consumed));
+        }
+        assert_eq!(samples, TONE_SAMPLES);
+        read_ahead
+    }
+
+    #[test]
+    fn memory_stays_constant() {
+        // The decoder buffers 64 KiB at most, no matter how long the track is
+        const MAX_READ_AHEAD: usize = 80 * 1024;
+
+        let streamed = CountingLoader::default();
+        assert!(read_ahead(&streamed) <= MAX_READ_AHEAD);
+
+        // Loading the track into memory instead reads all of it up front
+        let in_memory = CountingLoader {
+            in_memory: true,
+            ..Default::default()
+        };
+        assert!(read_ahead(&in_memory) >= TONE_BYTES - 1024);
+    }
+
+    #[test]
+    fn loops_without_opening_again() {
+        let loader = CountingLoader::default();
+        let sound = StreamedSound::asset(TONE).loop_from(Duration::from_secs(5));
+        let mut source = sound.source_from(&loader).unwrap();
+        assert_eq!(source.total_duration(), None);
+        assert_eq!(source.sample_rate(), 8000);
+        assert_eq!(source.channels(), 1);
+
+        let samples: Vec<i16> = source.by_ref().take(2 * TONE_SAMPLES).collect();
+        assert_eq!(samples.len(), 2 * TONE_SAMPLES);
+        // The loop continues 5 seconds into the track
+        let looped = &samples[TONE_SAMPLES..TONE_SAMPLES + 100];
+        let loop_start = TONE_SAMPLES / 2;
+        assert!(
+            (loop_start - 8..=loop_start + 8).any(|start| &samples[start..start + 100] == looped)
+        );
+        assert_eq!(loader.opened.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn missing_and_unsupported_resources_are_errors() {
+        let loader = CountingLoader::default();
+        assert!(StreamedSound::asset("tests/audio/missing.ogg")
+            .source_from(&loader)
+            .is_err());
+        assert!(StreamedSound::asset("tests/aseprite/sheet.json")
+            .looped()
+            .source_from(&loader)
+            .is_err());
+    }
+}
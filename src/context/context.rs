@@ -1,31 +1,39 @@
 use std::{cell::RefCell, sync::Arc};
 
-#[cfg(feature = "serde")]
-use rustc_hash::FxHashMap;
-
+#[cfg(feature = "animation")]
+use crate::animation::TweenManager;
 #[cfg(feature = "physics")]
 use crate::physics::Physics;
 
 #[cfg(feature = "audio")]
 use crate::audio::{AudioDeviceManager, AudioManager};
+#[cfg(feature = "debug-draw")]
+use crate::graphics::DebugDraw;
+#[cfg(multi_window)]
+use crate::graphics::WindowManager;
 #[cfg(feature = "gui")]
 use crate::gui::Gui;
 use crate::{
     app::{App, WindowEventManager},
-    ecs::{EndReason, GlobalWorld, SystemManager, World},
-    graphics::{AssetManager, Gpu, ScreenConfig, WorldCamera2D, WorldCamera3D},
-    input::Input,
+    ecs::{
+        Component, DisabledComponent, EndReason, EntityCommands, EntityId, GlobalWorld,
+        set_entity_enabled, IterEnabled, SceneState, StateExt, SystemManager, World, WorldExt,
+    },
+    graphics::{
+        AssetKey, AssetManager, AssetWrapMut, Gpu, Instance, InstanceBuffer, Lights3D,
+        ScreenConfig, Sky, WorldCamera2D, WorldCamera3D,
+    },
+    i18n::I18n,
+    input::{Clipboard, Input},
     io::{ResourceLoader, StorageLoader},
-    math::{Point2, Vector2},
-    scene::{Scene, SceneManager},
+    math::{BoundingVolume, Point2, Vector2, AABB},
+    random::SceneRandom,
+    scene::{PluginData, Scene, SceneManager},
     tasks::TaskManager,
-    time::TimeManager,
+    time::{Diagnostics, TimeManager},
 };
 #[cfg(feature = "serde")]
-use crate::{
-    entity::{ConstTypeId, EntityGroupHandle},
-    serde::{EntityGroupDeserializer, EntityGroupSerializer, SceneSerializer},
-};
+use crate::serde::{PrefabRegistry, SceneSerializer};
 
 #[non_exhaustive]
 pub struct Context<'a> {
@@ -34,16 +42,28 @@ pub struct Context<'a> {
     pub screen_config: &'a mut ScreenConfig,
     pub world_camera2d: &'a mut WorldCamera2D,
     pub world_camera3d: &'a mut WorldCamera3D,
+    pub lights3d: &'a mut Lights3D,
+    pub sky: &'a mut Sky,
     pub world: &'a mut World,
     // pub groups: &'a mut EntityGroupManager,
     #[cfg(feature = "physics")]
     pub physics: &'a mut Physics,
     pub tasks: &'a mut TaskManager,
+    pub random: &'a mut SceneRandom,
+    #[cfg(feature = "net")]
+    pub net: &'a mut crate::tasks::Net,
+    pub commands: &'a mut EntityCommands,
+    #[cfg(feature = "animation")]
+    pub tweens: &'a mut TweenManager,
+    pub plugins: &'a mut PluginData,
     pub started: &'a bool,
 
     // App
     pub time: &'a TimeManager,
+    pub diagnostics: &'a mut Diagnostics,
     pub input: &'a Input,
+    pub i18n: &'a mut I18n,
+    pub clipboard: &'a mut Clipboard,
     pub gpu: Arc<Gpu>,
     #[cfg(feature = "gui")]
     pub gui: &'a mut Gui,
@@ -54,11 +74,17 @@ pub struct Context<'a> {
     pub end: &'a mut bool,
     pub scenes: &'a mut SceneManager,
     pub window: Arc<winit::window::Window>,
+    #[cfg(multi_window)]
+    pub windows: &'a mut WindowManager,
     pub event_loop: &'a winit::event_loop::ActiveEventLoop,
     pub storage: Arc<dyn StorageLoader>,
     pub resource: Arc<dyn ResourceLoader>,
     pub assets: Arc<AssetManager>,
     pub global_world: &'a mut GlobalWorld,
+    #[cfg(feature = "serde")]
+    pub prefabs: &'a mut PrefabRegistry,
+    #[cfg(feature = "debug-draw")]
+    pub debug: &'a DebugDraw,
 
     // Misc
     pub scene_id: &'a u32,
@@ -91,16 +117,28 @@ impl<'a> Context<'a> {
                 screen_config: &mut scene.screen_config,
                 world_camera2d: &mut scene.world_camera2d,
                 world_camera3d: &mut scene.world_camera3d,
+                lights3d: &mut scene.lights3d,
+                sky: &mut scene.sky,
                 world: &mut scene.world,
                 // groups: &mut scene.groups,
                 #[cfg(feature = "physics")]
                 physics: &mut scene.physics,
                 tasks: &mut scene.tasks,
+                random: &mut scene.random,
+                #[cfg(feature = "net")]
+                net: &mut scene.net,
+                commands: &mut scene.commands,
+                #[cfg(feature = "animation")]
+                tweens: &mut scene.tweens,
+                plugins: &mut scene.plugin_data,
                 started: &scene.started,
                 
                 // App
                 time: &app.time,
+                diagnostics: &mut app.diagnostics,
                 input: &app.input,
+                i18n: &mut app.i18n,
+                clipboard: &mut app.clipboard,
                 gpu: app.gpu.clone(),
                 storage: app.storage_loader.clone(),
                 resource: app.resource_loader.clone(),
@@ -114,7 +152,13 @@ impl<'a> Context<'a> {
                 end: &mut app.end,
                 scenes: &mut app.scenes,
                 global_world: &mut app.global_world,
+                #[cfg(feature = "serde")]
+                prefabs: &mut app.prefabs,
+                #[cfg(feature = "debug-draw")]
+                debug: &app.debug,
                 window: app.window.clone(),
+                #[cfg(multi_window)]
+                windows: &mut app.windows,
                 event_loop,
 
                 // Misc
@@ -131,60 +175,17 @@ impl<'a> Context<'a> {
         &mut self, // Not actually needed, just to ensure there are only unique references to entities
         serialize: impl FnOnce(SceneSerializer) -> SceneSerializer,
     ) -> Result<Vec<u8>, Box<bincode::ErrorKind>> {
-        let entities = &self.entities;
-        let serializer = (serialize)(SceneSerializer::new(entities));
-
-        #[derive(serde::Serialize)]
-        struct Scene<'a> {
-            render_entities: bool,
-            screen_config: &'a ScreenConfig,
-            world_camera2d: &'a WorldCamera2D,
-            world_camera3d: &'a WorldCamera3D,
-            groups: &'a EntityGroupManager,
-            physics: &'a Physics,
-        }
-
-        #[cfg(feature = "physics")]
-        {
-            let ser_entities = serializer.finish();
-            let mut world_cpy = self.physics.clone();
-
-            for ty in self.entities.entities() {
-                if !ser_entities.contains_key(&ty.entity_type_id()) {
-                    for (_, entity) in ty.dyn_iter() {
-                        entity.remove_from_world(&mut world_cpy);
-                    }
-                }
-            }
-
-            let scene = Scene {
-                render_entities: *self.render_entities,
-                screen_config: self.screen_config,
-                world_camera2d: self.world_camera2d,
-                world_camera3d: self.world_camera3d,
-                groups: self.groups,
-                physics: &world_cpy,
-            };
-            let scene: (&Scene, FxHashMap<ConstTypeId, Vec<u8>>) = (&scene, ser_entities);
-
-            bincode::serialize(&scene)
-        }
-
-        #[cfg(not(feature = "physics"))]
-        {
-            let ser_entities = serializer.finish();
-            let scene = Scene {
-                render_entities: *self.render_entities,
-                screen_config: self.screen_config,
-                world_camera2d: self.world_camera2d,
-                world_camera3d: self.world_camera3d,
-                groups: self.groups,
-                physics: &self.physics,
-            };
-            let scene: (&Scene, FxHashMap<ConstTypeId, Vec<u8>>) = (&scene, ser_entities);
-            let result = bincode::serialize(&scene);
-            return result;
-        }
+        let serializer = SceneSerializer::new(
+            self.world,
+            #[cfg(feature = "physics")]
+            self.physics,
+            self.world_camera2d,
+            self.world_camera3d,
+            self.screen_config,
+            *self.render_entities,
+            self.random,
+        );
+        (serialize)(serializer).finish()
     }
 
     pub fn with_scene(
@@ -204,15 +205,27 @@ impl<'a> Context<'a> {
                 screen_config: &mut scene.screen_config,
                 world_camera2d: &mut scene.world_camera2d,
                 world_camera3d: &mut scene.world_camera3d,
+                lights3d: &mut scene.lights3d,
+                sky: &mut scene.sky,
                 world: &mut scene.world,
                 #[cfg(feature = "physics")]
                 physics: &mut scene.physics,
                 tasks: &mut scene.tasks,
+                random: &mut scene.random,
+                #[cfg(feature = "net")]
+                net: &mut scene.net,
+                commands: &mut scene.commands,
+                #[cfg(feature = "animation")]
+                tweens: &mut scene.tweens,
+                plugins: &mut scene.plugin_data,
                 started: &scene.started,
 
                 // App
                 time: self.time,
+                diagnostics: self.diagnostics,
                 input: self.input,
+                i18n: self.i18n,
+                clipboard: self.clipboard,
                 gpu: self.gpu.clone(),
                 storage: self.storage.clone(),
                 resource: self.resource.clone(),
@@ -228,6 +241,8 @@ impl<'a> Context<'a> {
                 window: self.window.clone(),
                 event_loop: self.event_loop,
                 global_world: self.global_world,
+                #[cfg(feature = "serde")]
+                prefabs: self.prefabs,
 
                 // Misc
                 scene_id: &scene_id,
@@ -236,9 +251,60 @@ impl<'a> Context<'a> {
                 cursor,
             };
             (action)(&mut scene.systems, &mut ctx);
+            ctx.apply_commands();
         }
     }
 
+    /// Applies the queued [EntityCommands], done by shura after every system
+    pub fn apply_commands(&mut self) {
+        self.commands.apply(self.world);
+    }
+
+    /// Disables or enables an entity without removing any of its components. The rigid body and
+    /// collider of a disabled entity are disabled in the physics world, velocities are kept.
+    pub fn set_enabled(&mut self, entity: EntityId, enabled: bool) {
+        set_entity_enabled(
+            self.world,
+            #[cfg(feature = "physics")]
+            self.physics,
+            entity,
+            enabled,
+        );
+    }
+
+    pub fn is_enabled(&self, entity: EntityId) -> bool {
+        self.world.is_enabled(entity)
+    }
+
+    /// Renders another frame when the scene uses
+    /// [RedrawMode::OnEvent](crate::graphics::RedrawMode::OnEvent)
+    pub fn request_redraw(&mut self) {
+        self.screen_config.request_redraw();
+    }
+
+    /// Resource of a plugin, see [SceneCreator::plugin_data](crate::scene::SceneCreator::plugin_data)
+    pub fn plugin_data<T: 'static>(&self) -> &T {
+        self.plugins.get()
+    }
+
+    pub fn plugin_data_mut<T: 'static>(&mut self) -> &mut T {
+        self.plugins.get_mut()
+    }
+
+    /// Current value of a [SceneState], panics if it has not been inserted
+    pub fn state<S: SceneState>(&self) -> S {
+        self.world.state()
+    }
+
+    pub fn in_state<S: SceneState>(&self, state: &S) -> bool {
+        self.world.in_state(state)
+    }
+
+    /// Changes a [SceneState], its exit and enter systems run before the next update system
+    pub fn set_state<S: SceneState>(&mut self, state: S) {
+        self.world.set_state(state);
+    }
+
     pub fn add_scene(&mut self, scene_id: u32, scene: impl Into<Scene>) {
         self.scenes.add(scene_id, scene);
     }
@@ -256,23 +322,60 @@ impl<'a> Context<'a> {
         self.scenes.remove(scene_id)
     }
 
-    #[cfg(feature = "serde")]
-    pub fn serialize_group(
-        &mut self,
-        group: &EntityGroupHandle,
-        serialize: impl FnOnce(&mut EntityGroupSerializer),
-    ) -> Option<Result<Vec<u8>, Box<bincode::ErrorKind>>> {
-        if let Some(mut ser) =
-            EntityGroupSerializer::new(self.physics, self.groups, self.entities, group)
-        {
-            serialize(&mut ser);
-            return Some(ser.finish());
-        }
-        None
+    /// Writes the instances of all components of enabled entities
+    pub fn write_instance_entities<C: Component + Send + Sync, I: Instance>(
+        &self,
+        key: AssetKey,
+        instances: impl Fn(&C, &mut Vec<I>),
+    ) -> AssetWrapMut<InstanceBuffer<I>> {
+        let components = self.world.view::<C>();
+        let disabled = self.world.view::<DisabledComponent>();
+        self.assets.write_instances(key, false, |data| {
+            for (_, component) in components.iter_enabled(&disabled) {
+                instances(component, data);
+            }
+        })
     }
 
-    #[cfg(feature = "serde")]
-    pub fn deserialize_group(&mut self, deserialize: EntityGroupDeserializer) -> EntityGroupHandle {
-        deserialize.finish(self)
+    /// Writes the instances of all components of enabled entities whose bounding volume is inside
+    /// the frustum of [WorldCamera3D]
+    pub fn write_instance_entities_culled<C: Component + Send + Sync, I: Instance>(
+        &self,
+        key: AssetKey,
+        bounds: impl Fn(&C) -> BoundingVolume,
+        instance: impl Fn(&C) -> I,
+    ) -> AssetWrapMut<InstanceBuffer<I>> {
+        let frustum = self.world_camera3d.frustum();
+        let components = self.world.view::<C>();
+        let disabled = self.world.view::<DisabledComponent>();
+        self.assets.write_instances(key, false, |data| {
+            data.extend(
+                components
+                    .iter_enabled(&disabled)
+                    .filter(|(_, component)| frustum.intersects(&bounds(*component)))
+                    .map(|(_, component)| instance(component)),
+            );
+        })
+    }
+
+    /// Writes the instances of all components of enabled entities whose [AABB] intersects
+    /// [WorldCamera2D]
+    pub fn write_instance_entities_culled2d<C: Component + Send + Sync, I: Instance>(
+        &self,
+        key: AssetKey,
+        aabb: impl Fn(&C) -> AABB,
+        instance: impl Fn(&C) -> I,
+    ) -> AssetWrapMut<InstanceBuffer<I>> {
+        let camera = self.world_camera2d.aabb();
+        let components = self.world.view::<C>();
+        let disabled = self.world.view::<DisabledComponent>();
+        self.assets.write_instances(key, false, |data| {
+            data.extend(
+                components
+                    .iter_enabled(&disabled)
+                    .filter(|(_, component)| camera.intersects(&aabb(*component)))
+                    .map(|(_, component)| instance(component)),
+            );
+        })
     }
 }
@@ -2,10 +2,16 @@ use std::sync::Arc;
 
 use crate::{
     ecs::{SystemManager, World},
-    graphics::{AssetManager, DefaultAssets, Gpu, RenderTarget, SurfaceRenderTarget},
-    scene::Scene,
+    graphics::{
+        AssetManager, CameraBuffer2D, DefaultAssets, Gpu, RenderTarget, SurfaceRenderTarget,
+        UiCamera, WorldCamera2D,
+    },
+    scene::{PluginData, Scene},
+    time::{Diagnostics, TimeManager},
 };
 
+#[cfg(feature = "debug-draw")]
+use crate::graphics::DebugDraw;
 #[cfg(feature = "physics")]
 use crate::physics::Physics;
 
@@ -14,9 +20,20 @@ pub struct RenderContext<'a> {
     pub gpu: Arc<Gpu>,
     pub surface_target: &'a SurfaceRenderTarget,
     pub default_assets: &'a DefaultAssets,
+    pub time: &'a TimeManager,
+    pub diagnostics: &'a Diagnostics,
+    pub world_camera2d: &'a WorldCamera2D,
+    /// Same as `default_assets.ui_camera`
+    pub ui_camera: &'a (CameraBuffer2D, UiCamera),
+    #[cfg(feature = "debug-draw")]
+    pub debug: &'a DebugDraw,
     #[cfg(feature = "physics")]
     pub physics: &'a Physics,
     pub world: &'a World,
+    pub plugins: &'a PluginData,
+    /// The secondary window that is rendered, [None] for the main window
+    #[cfg(multi_window)]
+    pub window: Option<winit::window::WindowId>,
 }
 
 impl<'a> RenderContext<'a> {
@@ -25,6 +42,9 @@ impl<'a> RenderContext<'a> {
         gpu: Arc<Gpu>,
         surface_target: &'a SurfaceRenderTarget,
         default_assets: &'a DefaultAssets,
+        time: &'a TimeManager,
+        diagnostics: &'a Diagnostics,
+        #[cfg(feature = "debug-draw")] debug: &'a DebugDraw,
         scene: &'a Scene,
     ) -> (&'a SystemManager, Self) {
         (
@@ -33,15 +53,34 @@ impl<'a> RenderContext<'a> {
                 assets,
                 gpu,
                 default_assets,
+                time,
+                diagnostics,
                 surface_target,
+                world_camera2d: &scene.world_camera2d,
+                ui_camera: &default_assets.ui_camera,
+                #[cfg(feature = "debug-draw")]
+                debug,
                 #[cfg(feature = "physics")]
                 physics: &scene.physics,
                 world: &scene.world,
+                plugins: &scene.plugin_data,
+                #[cfg(multi_window)]
+                window: None,
             },
         )
     }
 
+    /// Resource of a plugin, see [SceneCreator::plugin_data](crate::scene::SceneCreator::plugin_data)
+    pub fn plugin_data<T: 'static>(&self) -> &T {
+        self.plugins.get()
+    }
+
     pub fn target(&self) -> &dyn RenderTarget {
+        #[cfg(multi_window)]
+        if self.window.is_some() {
+            return self.surface_target;
+        }
+
         #[cfg(feature = "framebuffer")]
         return &self.default_assets.framebuffer;
 
@@ -0,0 +1,175 @@
+use rustc_hash::FxHashSet;
+use shipyard::{IntoIter, IntoWithId};
+
+use crate::{
+    context::Context,
+    ecs::{Component, EntityId, System, SystemPriority, World, WorldExt},
+    physics::{ActiveCollisionTypes, Collider, ColliderHandle, Physics},
+    scene::{Plugin, SceneCreator},
+};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone)]
+pub enum AreaComponentStatus {
+    Initialized { collider_handle: ColliderHandle },
+    Uninitialized { collider: Collider },
+}
+
+/// Sensor that keeps track of the entities whose colliders are inside of it. The sets are
+/// updated by the [AreaPlugin] after the update systems, so the physics should be stepped
+/// before that.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Component)]
+#[track(Deletion, Removal)]
+pub struct AreaComponent {
+    pub status: AreaComponentStatus,
+    entities: FxHashSet<EntityId>,
+    just_entered: Vec<EntityId>,
+    just_exited: Vec<EntityId>,
+}
+
+impl AreaComponent {
+    /// The collider is always turned into a sensor that also detects fixed and kinematic bodies
+    pub fn new(collider: impl Into<Collider>) -> Self {
+        let mut collider = collider.into();
+        collider.set_sensor(true);
+        collider.set_active_collision_types(ActiveCollisionTypes::all());
+        Self {
+            status: AreaComponentStatus::Uninitialized { collider },
+            entities: Default::default(),
+            just_entered: Vec::new(),
+            just_exited: Vec::new(),
+        }
+    }
+
+    pub fn handle(&self) -> Option<ColliderHandle> {
+        match &self.status {
+            AreaComponentStatus::Initialized { collider_handle } => Some(*collider_handle),
+            AreaComponentStatus::Uninitialized { .. } => None,
+        }
+    }
+
+    pub fn get<'a>(&'a self, physics: &'a Physics) -> &'a Collider {
+        match &self.status {
+            AreaComponentStatus::Initialized { collider_handle } => {
+                physics.collider(*collider_handle).unwrap()
+            }
+            AreaComponentStatus::Uninitialized { collider } => collider,
+        }
+    }
+
+    pub fn get_mut<'a>(&'a mut self, physics: &'a mut Physics) -> &'a mut Collider {
+        match &mut self.status {
+            AreaComponentStatus::Initialized { collider_handle } => {
+                physics.collider_mut(*collider_handle).unwrap()
+            }
+            AreaComponentStatus::Uninitialized { collider } => collider,
+        }
+    }
+
+    /// Entities currently inside the area
+    pub fn entities(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.entities.iter().copied()
+    }
+
+    /// Entities inside the area that have a component of type `C`
+    pub fn entities_of<'a, C: Component>(
+        &'a self,
+        world: &'a World,
+    ) -> impl Iterator<Item = EntityId> + 'a {
+        let view = world.view::<C>();
+        self.entities
+            .iter()
+            .copied()
+            .filter(move |entity| view.contains(*entity))
+    }
+
+    pub fn contains(&self, entity: EntityId) -> bool {
+        self.entities.contains(&entity)
+    }
+
+    pub fn just_entered(&self) -> &[EntityId] {
+        &self.just_entered
+    }
+
+    pub fn just_exited(&self) -> &[EntityId] {
+        &self.just_exited
+    }
+
+    fn update(&mut self, physics: &mut Physics, entity: EntityId) {
+        let collider_handle = match &self.status {
+            AreaComponentStatus::Initialized { collider_handle } => *collider_handle,
+            AreaComponentStatus::Uninitialized { collider } => {
+                let collider_handle = physics.add_collider(&entity, collider.clone());
+                self.status = AreaComponentStatus::Initialized { collider_handle };
+                collider_handle
+            }
+        };
+
+        let inside = physics
+            .narrow_phase()
+            .intersection_pairs_with(collider_handle)
+            .filter(|(_, _, intersecting)| *intersecting)
+            .filter_map(|(collider1, collider2, _)| {
+                let other = if collider1 == collider_handle {
+                    collider2
+                } else {
+                    collider1
+                };
+                physics.entity_from_collider(&other).copied()
+            })
+            .filter(|other| *other != entity)
+            .collect::<FxHashSet<_>>();
+
+        self.just_entered.clear();
+        self.just_entered
+            .extend(inside.difference(&self.entities).copied());
+        self.just_exited.clear();
+        self.just_exited
+            .extend(self.entities.difference(&inside).copied());
+        self.entities = inside;
+    }
+}
+
+/// Adds new [AreaComponent]s to the physics, removes deleted ones and updates the entities
+/// inside of them
+pub struct AreaPlugin {
+    pub priority: SystemPriority,
+}
+
+impl AreaPlugin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn priority(mut self, priority: SystemPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+impl Default for AreaPlugin {
+    fn default() -> Self {
+        Self {
+            priority: SystemPriority::LAST,
+        }
+    }
+}
+
+impl Plugin for AreaPlugin {
+    fn init<S: SceneCreator>(&mut self, scene: S) -> S {
+        scene.system(System::update(update_areas).priority(self.priority))
+    }
+}
+
+fn update_areas(ctx: &mut Context) {
+    let mut areas = ctx.world.view_mut::<AreaComponent>();
+    for (_, area) in areas.take_deleted().into_iter().chain(areas.take_removed()) {
+        if let Some(collider_handle) = area.handle() {
+            ctx.physics.remove_collider(collider_handle);
+        }
+    }
+    for (entity, area) in (&mut areas).iter().with_id() {
+        area.update(ctx.physics, entity);
+    }
+}
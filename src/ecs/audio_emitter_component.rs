@@ -0,0 +1,107 @@
+use shipyard::IntoIter;
+
+use crate::{
+    audio::{AudioManager, Sound, SpatialAudioSink, SpatialConfig},
+    context::Context,
+    ecs::{Component, PositionComponent2D, System, SystemPriority, WorldExt},
+    math::Vector2,
+    scene::{Plugin, SceneCreator},
+};
+
+/// Spatial sound source. With the [AudioEmitterPlugin] its panning and volume follow the
+/// [PositionComponent2D] of the entity, or [AudioEmitterComponent::set_position] if the entity
+/// has none, relative to the world camera.
+#[derive(Component)]
+pub struct AudioEmitterComponent {
+    sink: SpatialAudioSink,
+}
+
+impl AudioEmitterComponent {
+    pub fn new(audio: &AudioManager, config: SpatialConfig) -> Self {
+        Self {
+            sink: audio.create_spatial_sink(Vector2::zeros(), config),
+        }
+    }
+
+    pub fn play(&self, sound: &Sound) {
+        self.sink.append(sound);
+    }
+
+    pub fn pause(&mut self) {
+        self.sink.pause();
+    }
+
+    pub fn resume(&mut self) {
+        self.sink.play();
+    }
+
+    pub fn stop(&self) {
+        self.sink.stop();
+    }
+
+    pub fn set_position(&mut self, position: Vector2<f32>) {
+        self.sink.position = position;
+    }
+
+    pub fn position(&self) -> Vector2<f32> {
+        self.sink.position
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.sink.volume = volume;
+    }
+
+    pub fn set_config(&mut self, config: SpatialConfig) {
+        self.sink.config = config;
+    }
+
+    pub fn config(&self) -> &SpatialConfig {
+        &self.sink.config
+    }
+
+    pub fn sink(&self) -> &SpatialAudioSink {
+        &self.sink
+    }
+}
+
+pub struct AudioEmitterPlugin {
+    pub priority: SystemPriority,
+}
+
+impl AudioEmitterPlugin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn priority(mut self, priority: SystemPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+impl Default for AudioEmitterPlugin {
+    fn default() -> Self {
+        Self {
+            priority: SystemPriority::LAST,
+        }
+    }
+}
+
+impl Plugin for AudioEmitterPlugin {
+    fn init<S: SceneCreator>(&mut self, scene: S) -> S {
+        scene.system(System::update(update_emitters).priority(self.priority))
+    }
+}
+
+fn update_emitters(ctx: &mut Context) {
+    let mut emitters = ctx.world.view_mut::<AudioEmitterComponent>();
+    let positions = ctx.world.view::<PositionComponent2D>();
+    for (emitter, position) in (&mut emitters, &positions).iter() {
+        emitter.sink.position = position.translation();
+    }
+
+    let listener = *ctx.world_camera2d.camera().translation();
+    for emitter in (&mut emitters).iter() {
+        emitter.sink.update(listener);
+    }
+}
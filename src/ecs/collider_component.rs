@@ -5,6 +5,7 @@ use crate::{
 };
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone)]
 pub enum ColliderComponentStatus {
     Initialized { collider_handle: ColliderHandle },
     Uninitialized { collider: Collider },
@@ -31,7 +32,7 @@ impl ColliderComponentStatus {
 }
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Component)]
+#[derive(Clone, Component)]
 #[track(Insertion, Deletion, Removal)]
 pub struct ColliderComponent {
     pub status: ColliderComponentStatus,
@@ -0,0 +1,96 @@
+use crate::ecs::{EntityId, TupleAddComponent, TupleRemove, Unique, World, WorldExt};
+
+type Command = Box<dyn FnOnce(&mut World)>;
+
+/// Changes to the [World] that are queued while its storages are borrowed, for example while
+/// iterating a view. Accessible as `ctx.commands`.
+///
+/// The queue is applied in the order the commands were added after every setup, switch, resize
+/// and update system, task callback and window event handler and after the collision handlers,
+/// so the next system already sees the changes. Entities spawned by a command are not
+/// initialized any differently than entities added directly to the [World]. Commands that
+/// target an entity that has been deleted in the meantime are skipped.
+#[derive(Default)]
+pub struct EntityCommands {
+    commands: Vec<Command>,
+}
+
+impl EntityCommands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn<C: TupleAddComponent + 'static>(&mut self, components: C) {
+        self.commands.push(Box::new(move |world| {
+            world.add_entity(components);
+        }));
+    }
+
+    /// Same as [EntityCommands::spawn] but calls `spawned` with the id of the new entity
+    pub fn spawn_with<C: TupleAddComponent + 'static>(
+        &mut self,
+        components: C,
+        spawned: impl FnOnce(&mut World, EntityId) + 'static,
+    ) {
+        self.commands.push(Box::new(move |world| {
+            let entity = world.add_entity(components);
+            (spawned)(world, entity);
+        }));
+    }
+
+    /// Deleting an entity that does not exist anymore does nothing
+    pub fn despawn(&mut self, entity: EntityId) {
+        self.commands.push(Box::new(move |world| {
+            world.delete_entity(entity);
+        }));
+    }
+
+    pub fn add_component<C: TupleAddComponent + 'static>(
+        &mut self,
+        entity: EntityId,
+        components: C,
+    ) {
+        self.commands.push(Box::new(move |world| {
+            if world.is_alive(entity) {
+                world.add_component(entity, components);
+            }
+        }));
+    }
+
+    pub fn remove_component<C: TupleRemove + 'static>(&mut self, entity: EntityId) {
+        self.commands.push(Box::new(move |world| {
+            world.remove::<C>(entity);
+        }));
+    }
+
+    /// Adds or replaces a unique component of the world
+    pub fn set_single<U: Unique + Send + Sync>(&mut self, unique: U) {
+        self.commands.push(Box::new(move |world| {
+            world.add_unique(unique);
+        }));
+    }
+
+    /// Queues any other change to the world
+    pub fn run(&mut self, command: impl FnOnce(&mut World) + 'static) {
+        self.commands.push(Box::new(command));
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    /// Applies all queued commands in order
+    pub fn apply(&mut self, world: &mut World) {
+        for command in self.commands.drain(..) {
+            (command)(world);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+#[cfg(feature = "physics")]
+use crate::physics::Physics;
+use crate::{
+    context::Context,
+    ecs::{Component, EntityId, World, WorldExt},
+    headless::HeadlessContext,
+};
+
+/// Marks an entity as disabled, added and removed with
+/// [Context::set_enabled](crate::context::Context::set_enabled). Disabled entities keep all of
+/// their components but are skipped by the instance writing of the [Context], the plugins of
+/// shura and their physics is paused. Own systems can skip them with
+/// [IterEnabled::iter_enabled](crate::ecs::IterEnabled::iter_enabled).
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct DisabledComponent;
+
+/// The world and entity toggling of [Context] and [HeadlessContext], so helpers like
+/// [EntityPool](crate::ecs::EntityPool) work with both
+pub trait EntityContext {
+    fn world(&mut self) -> &mut World;
+    fn set_enabled(&mut self, entity: EntityId, enabled: bool);
+}
+
+impl EntityContext for Context<'_> {
+    fn world(&mut self) -> &mut World {
+        self.world
+    }
+
+    fn set_enabled(&mut self, entity: EntityId, enabled: bool) {
+        Context::set_enabled(self, entity, enabled);
+    }
+}
+
+impl EntityContext for HeadlessContext<'_> {
+    fn world(&mut self) -> &mut World {
+        self.world
+    }
+
+    fn set_enabled(&mut self, entity: EntityId, enabled: bool) {
+        HeadlessContext::set_enabled(self, entity, enabled);
+    }
+}
+
+pub(crate) fn set_entity_enabled(
+    world: &World,
+    #[cfg(feature = "physics")] physics: &mut Physics,
+    entity: EntityId,
+    enabled: bool,
+) {
+    if !world.is_alive(entity) {
+        return;
+    }
+    if enabled {
+        world.view_mut::<DisabledComponent>().remove(entity);
+    } else {
+        world.entities().add_component(
+            entity,
+            &mut world.view_mut::<DisabledComponent>(),
+            DisabledComponent,
+        );
+    }
+
+    #[cfg(feature = "physics")]
+    {
+        use crate::ecs::{ColliderComponent, RigidBodyComponent};
+        let mut rigid_bodies = world.view_mut::<RigidBodyComponent>();
+        if rigid_bodies.contains(entity) {
+            rigid_bodies[entity].get_mut(physics).set_enabled(enabled);
+        }
+        let mut colliders = world.view_mut::<ColliderComponent>();
+        if colliders.contains(entity) {
+            colliders[entity].get_mut(physics).set_enabled(enabled);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use rustc_hash::FxHashMap;
+
+use crate::ecs::{EntityContext, EntityId, World, WorldExt};
+
+/// What [EntityPool::acquire] does when every pooled entity is in use
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PoolOverflow {
+    /// Spawns a new entity that stays in the pool
+    #[default]
+    Grow,
+    /// Reuses the entity that has been acquired the longest time ago
+    RecycleOldest,
+}
+
+/// Entities that are spawned once and then reused instead of being deleted and spawned again.
+/// Free entities are disabled with
+/// [Context::set_enabled](crate::context::Context::set_enabled), so they aren't rendered and their
+/// rigid bodies and colliders stay in the physics world. Works with the [Context] as well as the
+/// [HeadlessContext](crate::headless::HeadlessContext).
+pub struct EntityPool {
+    spawn: Box<dyn Fn(&mut World) -> EntityId + Send + Sync>,
+    overflow: PoolOverflow,
+    free: Vec<EntityId>,
+    in_use: FxHashMap<EntityId, u64>,
+    acquired: u64,
+}
+
+impl EntityPool {
+    /// Spawns `capacity` disabled entities with `spawn`
+    pub fn new(
+        ctx: &mut impl EntityContext,
+        capacity: usize,
+        spawn: impl Fn(&mut World) -> EntityId + Send + Sync + 'static,
+    ) -> Self {
+        let mut pool = Self {
+            spawn: Box::new(spawn),
+            overflow: PoolOverflow::default(),
+            free: Vec::with_capacity(capacity),
+            in_use: FxHashMap::default(),
+            acquired: 0,
+        };
+        for _ in 0..capacity {
+            let entity = (pool.spawn)(ctx.world());
+            ctx.set_enabled(entity, false);
+            pool.free.push(entity);
+        }
+        pool
+    }
+
+    pub fn overflow(mut self, overflow: PoolOverflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Enables a free entity and calls `reset` on it before it is handed out
+    pub fn acquire(
+        &mut self,
+        ctx: &mut impl EntityContext,
+        reset: impl FnOnce(&mut World, EntityId),
+    ) -> EntityId {
+        let entity = loop {
+            match self.free.pop() {
+                // Skip entities that were deleted from outside of the pool
+                Some(entity) if ctx.world().is_alive(entity) => break entity,
+                Some(_) => continue,
+                None => {}
+            }
+            match self.overflow {
+                PoolOverflow::RecycleOldest if !self.in_use.is_empty() => {
+                    let (&oldest, _) = self
+                        .in_use
+                        .iter()
+                        .min_by_key(|(_, acquired)| **acquired)
+                        .unwrap();
+                    self.in_use.remove(&oldest);
+                    if ctx.world().is_alive(oldest) {
+                        break oldest;
+                    }
+                }
+                _ => break (self.spawn)(ctx.world()),
+            }
+        };
+
+        ctx.set_enabled(entity, true);
+        (reset)(ctx.world(), entity);
+        self.acquired += 1;
+        self.in_use.insert(entity, self.acquired);
+        entity
+    }
+
+    /// Disables the entity and makes it available again. Returns false if the entity was not
+    /// acquired from this pool.
+    pub fn release(&mut self, ctx: &mut impl EntityContext, entity: EntityId) -> bool {
+        if self.in_use.remove(&entity).is_none() {
+            return false;
+        }
+        if ctx.world().is_alive(entity) {
+            ctx.set_enabled(entity, false);
+            self.free.push(entity);
+        }
+        true
+    }
+
+    pub fn is_in_use(&self, entity: EntityId) -> bool {
+        self.in_use.contains_key(&entity)
+    }
+
+    pub fn in_use(&self) -> usize {
+        self.in_use.len()
+    }
+
+    pub fn available(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.free.len() + self.in_use.len()
+    }
+
+    /// Entities that are currently acquired, in no particular order
+    pub fn iter_in_use(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.in_use.keys().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "physics")]
+    use crate::physics::Physics;
+    use crate::{
+        ecs::{Component, DisabledComponent},
+        headless::HeadlessContext,
+        time::TimeManager,
+    };
+
+    #[derive(Component)]
+    struct Bullet(u32);
+
+    #[test]
+    fn acquire_and_release_does_not_leak() {
+        const CAPACITY: usize = 16;
+        let mut world = World::new();
+        #[cfg(feature = "physics")]
+        let mut physics = Physics::new();
+        let time = TimeManager::new();
+        let mut end = false;
+        let mut ctx = HeadlessContext {
+            world: &mut world,
+            #[cfg(feature = "physics")]
+            physics: &mut physics,
+            time: &time,
+            end: &mut end,
+        };
+
+        let mut pool = EntityPool::new(&mut ctx, CAPACITY, |world| world.add_entity((Bullet(0),)));
+        let mut acquired = Vec::new();
+        for round in 0..1000 {
+            for _ in 0..(round % CAPACITY) + 1 {
+                acquired.push(pool.acquire(&mut ctx, |world, entity| {
+                    world.view_mut::<Bullet>()[entity].0 = round as u32;
+                }));
+            }
+            assert_eq!(pool.in_use(), acquired.len());
+            for entity in &acquired {
+                assert!(ctx.is_enabled(*entity));
+                assert_eq!(ctx.world.view::<Bullet>()[*entity].0, round as u32);
+            }
+            for entity in acquired.drain(..) {
+                assert!(pool.release(&mut ctx, entity));
+                assert!(!pool.release(&mut ctx, entity));
+            }
+        }
+
+        assert_eq!(pool.capacity(), CAPACITY);
+        assert_eq!(pool.available(), CAPACITY);
+        assert_eq!(pool.in_use(), 0);
+        assert_eq!(ctx.world.view::<Bullet>().len(), CAPACITY);
+        assert_eq!(ctx.world.view::<DisabledComponent>().len(), CAPACITY);
+    }
+
+    #[test]
+    fn overflow() {
+        let mut world = World::new();
+        #[cfg(feature = "physics")]
+        let mut physics = Physics::new();
+        let time = TimeManager::new();
+        let mut end = false;
+        let mut ctx = HeadlessContext {
+            world: &mut world,
+            #[cfg(feature = "physics")]
+            physics: &mut physics,
+            time: &time,
+            end: &mut end,
+        };
+
+        let mut grow = EntityPool::new(&mut ctx, 2, |world| world.add_entity((Bullet(0),)));
+        for _ in 0..3 {
+            grow.acquire(&mut ctx, |_, _| {});
+        }
+        assert_eq!(grow.capacity(), 3);
+
+        let mut recycle = EntityPool::new(&mut ctx, 2, |world| world.add_entity((Bullet(0),)))
+            .overflow(PoolOverflow::RecycleOldest);
+        let first = recycle.acquire(&mut ctx, |_, _| {});
+        recycle.acquire(&mut ctx, |_, _| {});
+        assert_eq!(recycle.acquire(&mut ctx, |_, _| {}), first);
+        assert_eq!(recycle.capacity(), 2);
+        assert_eq!(ctx.world.view::<Bullet>().len(), 5);
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture pooled_spawning`
+    #[test]
+    #[ignore]
+    fn bench_pooled_spawning() {
+        use crate::time::Instant;
+
+        const ROUNDS: u32 = 1000;
+        const BULLETS: usize = 256;
+        let mut world = World::new();
+        #[cfg(feature = "physics")]
+        let mut physics = Physics::new();
+        let time = TimeManager::new();
+        let mut end = false;
+        let mut ctx = HeadlessContext {
+            world: &mut world,
+            #[cfg(feature = "physics")]
+            physics: &mut physics,
+            time: &time,
+            end: &mut end,
+        };
+
+        let mut pool = EntityPool::new(&mut ctx, BULLETS, |world| world.add_entity((Bullet(0),)));
+        let mut bullets = Vec::with_capacity(BULLETS);
+        let start = Instant::now();
+        for round in 0..ROUNDS {
+            for _ in 0..BULLETS {
+                bullets.push(pool.acquire(&mut ctx, |world, entity| {
+                    world.view_mut::<Bullet>()[entity].0 = round;
+                }));
+            }
+            for bullet in bullets.drain(..) {
+                pool.release(&mut ctx, bullet);
+            }
+        }
+        let pooled = start.elapsed() / ROUNDS;
+        assert_eq!(pool.capacity(), BULLETS);
+
+        let start = Instant::now();
+        for round in 0..ROUNDS {
+            for _ in 0..BULLETS {
+                bullets.push(ctx.world.add_entity((Bullet(round),)));
+            }
+            for bullet in bullets.drain(..) {
+                assert!(ctx.world.delete_entity(bullet));
+            }
+        }
+        let spawned = start.elapsed() / ROUNDS;
+        assert_eq!(ctx.world.view::<Bullet>().len(), BULLETS);
+        println!(
+            "pooled: {pooled:?}, add_entity / delete_entity: {spawned:?} per {BULLETS} bullets"
+        );
+    }
+}
@@ -0,0 +1,348 @@
+use std::fmt;
+
+use shipyard::{IntoIter, IntoWithId};
+
+use crate::{
+    context::Context,
+    ecs::{
+        AllStoragesViewMut, Component, EntityId, PositionComponent2D, PositionComponent3D, System,
+        SystemPriority, World, WorldExt,
+    },
+    math::{Isometry2, Isometry3},
+    scene::{Plugin, SceneCreator},
+};
+
+/// Isometry of a position component that children inherit from their parent
+pub trait HierarchyTransform: Copy + Default + Send + Sync + 'static {
+    type Position: Component + Send + Sync;
+    /// Current and previous position
+    fn get(position: &Self::Position) -> (Self, Self);
+    fn set(position: &mut Self::Position, current: Self, previous: Self);
+    fn compose(&self, local: &Self) -> Self;
+    fn inverse_transform(&self) -> Self;
+}
+
+macro_rules! impl_hierarchy_transform {
+    ($($isometry:ty => $position:ty),*) => {
+        $(
+            impl HierarchyTransform for $isometry {
+                type Position = $position;
+
+                fn get(position: &Self::Position) -> (Self, Self) {
+                    (position.position, position.previous)
+                }
+
+                fn set(position: &mut Self::Position, current: Self, previous: Self) {
+                    position.position = current;
+                    position.previous = previous;
+                }
+
+                fn compose(&self, local: &Self) -> Self {
+                    self * local
+                }
+
+                fn inverse_transform(&self) -> Self {
+                    self.inverse()
+                }
+            }
+        )*
+    };
+}
+
+impl_hierarchy_transform!(
+    Isometry2<f32> => PositionComponent2D,
+    Isometry3<f32> => PositionComponent3D
+);
+
+/// Parent of an entity, added with [HierarchyExt::set_parent]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ParentComponent {
+    parent: EntityId,
+}
+
+impl ParentComponent {
+    pub fn parent(&self) -> EntityId {
+        self.parent
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Component, Clone, Debug, Default)]
+pub struct ChildrenComponent {
+    children: Vec<EntityId>,
+}
+
+impl ChildrenComponent {
+    pub fn children(&self) -> &[EntityId] {
+        &self.children
+    }
+}
+
+/// Position of a child relative to its parent
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalPosition<T> {
+    pub position: T,
+}
+
+impl<T: HierarchyTransform> Component for LocalPosition<T> {
+    type Tracking = shipyard::track::Untracked;
+}
+
+pub type LocalPosition2D = LocalPosition<Isometry2<f32>>;
+pub type LocalPosition3D = LocalPosition<Isometry3<f32>>;
+
+/// Which transform stays the same when an entity gets a new parent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeepTransform {
+    Local,
+    World,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HierarchyError {
+    /// The parent is the child itself or one of its descendants
+    Cycle,
+}
+
+impl fmt::Display for HierarchyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HierarchyError::Cycle => write!(f, "Entity cannot be its own ancestor"),
+        }
+    }
+}
+
+impl std::error::Error for HierarchyError {}
+
+/// Parent and child relationships between entities. The world positions of children are
+/// computed from their [LocalPosition] by [HierarchyPlugin] or
+/// [HierarchyExt::propagate_transforms].
+pub trait HierarchyExt {
+    /// Attaches `child` to `parent` with a local offset, 2D or 3D depending on the offset
+    fn set_parent<T: HierarchyTransform>(
+        &self,
+        child: EntityId,
+        parent: EntityId,
+        local: T,
+    ) -> Result<(), HierarchyError>;
+    /// Moves `child` to another parent or detaches it with [None]
+    fn reparent<T: HierarchyTransform>(
+        &self,
+        child: EntityId,
+        parent: Option<EntityId>,
+        keep: KeepTransform,
+    ) -> Result<(), HierarchyError>;
+    fn parent(&self, entity: EntityId) -> Option<EntityId>;
+    fn children(&self, entity: EntityId) -> Vec<EntityId>;
+    /// The entity and all of its descendants, parents always come before their children
+    fn descendants(&self, entity: EntityId) -> Vec<EntityId>;
+    /// Deletes the entity together with all of its descendants
+    fn remove_with_children(&self, entity: EntityId);
+    /// Computes the positions of all children from their parents, top down
+    fn propagate_transforms<T: HierarchyTransform>(&self);
+}
+
+impl HierarchyExt for World {
+    fn set_parent<T: HierarchyTransform>(
+        &self,
+        child: EntityId,
+        parent: EntityId,
+        local: T,
+    ) -> Result<(), HierarchyError> {
+        {
+            let parents = self.view::<ParentComponent>();
+            let mut current = Some(parent);
+            while let Some(entity) = current {
+                if entity == child {
+                    return Err(HierarchyError::Cycle);
+                }
+                current = parents.contains(entity).then(|| parents[entity].parent);
+            }
+        }
+
+        detach(self, child);
+        let entities = self.entities();
+        entities.add_component(
+            child,
+            &mut self.view_mut::<ParentComponent>(),
+            ParentComponent { parent },
+        );
+        entities.add_component(
+            child,
+            &mut self.view_mut::<LocalPosition<T>>(),
+            LocalPosition { position: local },
+        );
+        let mut children = self.view_mut::<ChildrenComponent>();
+        if children.contains(parent) {
+            children[parent].children.push(child);
+        } else {
+            entities.add_component(
+                parent,
+                &mut children,
+                ChildrenComponent {
+                    children: vec![child],
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn reparent<T: HierarchyTransform>(
+        &self,
+        child: EntityId,
+        parent: Option<EntityId>,
+        keep: KeepTransform,
+    ) -> Result<(), HierarchyError> {
+        let local = {
+            let positions = self.view::<T::Position>();
+            let locals = self.view::<LocalPosition<T>>();
+            let world = positions
+                .contains(child)
+                .then(|| T::get(&positions[child]).0)
+                .unwrap_or_default();
+            match (keep, parent) {
+                (KeepTransform::Local, _) if locals.contains(child) => locals[child].position,
+                (KeepTransform::World, Some(parent)) if positions.contains(parent) => {
+                    T::get(&positions[parent])
+                        .0
+                        .inverse_transform()
+                        .compose(&world)
+                }
+                _ => world,
+            }
+        };
+
+        match parent {
+            Some(parent) => self.set_parent(child, parent, local),
+            None => {
+                detach(self, child);
+                self.view_mut::<LocalPosition<T>>().remove(child);
+                let mut positions = self.view_mut::<T::Position>();
+                if keep == KeepTransform::Local && positions.contains(child) {
+                    T::set(&mut positions[child], local, local);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn parent(&self, entity: EntityId) -> Option<EntityId> {
+        let parents = self.view::<ParentComponent>();
+        parents.contains(entity).then(|| parents[entity].parent)
+    }
+
+    fn children(&self, entity: EntityId) -> Vec<EntityId> {
+        let children = self.view::<ChildrenComponent>();
+        if children.contains(entity) {
+            children[entity].children.clone()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn descendants(&self, entity: EntityId) -> Vec<EntityId> {
+        let children = self.view::<ChildrenComponent>();
+        let mut descendants = vec![entity];
+        let mut i = 0;
+        while let Some(entity) = descendants.get(i).copied() {
+            if children.contains(entity) {
+                descendants.extend_from_slice(&children[entity].children);
+            }
+            i += 1;
+        }
+        descendants
+    }
+
+    fn remove_with_children(&self, entity: EntityId) {
+        let descendants = self.descendants(entity);
+        detach(self, entity);
+        let mut all_storages = self.borrow::<AllStoragesViewMut>().unwrap();
+        for entity in descendants {
+            all_storages.delete_entity(entity);
+        }
+    }
+
+    fn propagate_transforms<T: HierarchyTransform>(&self) {
+        let parents = self.view::<ParentComponent>();
+        let children = self.view::<ChildrenComponent>();
+        let locals = self.view::<LocalPosition<T>>();
+        let mut positions = self.view_mut::<T::Position>();
+
+        // Roots are parents without a parent or whose parent got deleted
+        let mut stack = children
+            .iter()
+            .with_id()
+            .filter(|(entity, _)| {
+                !parents.contains(*entity) || !children.contains(parents[*entity].parent)
+            })
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        while let Some(entity) = stack.pop() {
+            let parent = positions
+                .contains(entity)
+                .then(|| T::get(&positions[entity]));
+            for child in &children[entity].children {
+                if let (Some((current, previous)), true, true) =
+                    (parent, locals.contains(*child), positions.contains(*child))
+                {
+                    let local = locals[*child].position;
+                    T::set(
+                        &mut positions[*child],
+                        current.compose(&local),
+                        previous.compose(&local),
+                    );
+                }
+                if children.contains(*child) {
+                    stack.push(*child);
+                }
+            }
+        }
+    }
+}
+
+fn detach(world: &World, child: EntityId) {
+    let Some(ParentComponent { parent }) = world.view_mut::<ParentComponent>().remove(child) else {
+        return;
+    };
+    let mut children = world.view_mut::<ChildrenComponent>();
+    if children.contains(parent) {
+        children[parent].children.retain(|entity| *entity != child);
+    }
+}
+
+/// Computes the world positions of all children in 2D and 3D after the update systems
+pub struct HierarchyPlugin {
+    pub priority: SystemPriority,
+}
+
+impl HierarchyPlugin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn priority(mut self, priority: SystemPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+impl Default for HierarchyPlugin {
+    fn default() -> Self {
+        Self {
+            priority: SystemPriority::LAST,
+        }
+    }
+}
+
+impl Plugin for HierarchyPlugin {
+    fn init<S: SceneCreator>(&mut self, scene: S) -> S {
+        scene.system(System::update(propagate_transforms).priority(self.priority))
+    }
+}
+
+fn propagate_transforms(ctx: &mut Context) {
+    ctx.world.propagate_transforms::<Isometry2<f32>>();
+    ctx.world.propagate_transforms::<Isometry3<f32>>();
+}
@@ -1,32 +1,85 @@
 use crate::{
+    ecs::Component,
+    math::{Isometry2, Rotation2, Vector2},
     physics::{
-        Collider, KinematicCharacterController, RigidBody, RigidBodyHandle, Shape, SharedShape,
-        TypedShape, Physics,
+        CharacterCollision, EffectiveCharacterMovement, KinematicCharacterController, Physics,
+        QueryFilter, Shape, SharedShape,
     },
-    BaseComponent, Instance2D, Isometry2, Rotation, Vector2,
 };
 
-// #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+/// Kinematic character that is moved with rapier's [KinematicCharacterController]. Besides
+/// autostepping and snapping to the ground it tracks the ground state, coyote time and buffered
+/// jumps and can push dynamic rigid bodies.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Component)]
 pub struct CharacterControllerComponent {
     pub controller: KinematicCharacterController,
     pub shape: SharedShape,
-    scaling: Vector2<f32>,
-    position: Isometry2<f32>,
-    instance: Instance2D,
-    disabled: bool,
+    pub position: Isometry2<f32>,
+    /// Mass used to push dynamic rigid bodies, `0.0` disables pushing
+    pub push_mass: f32,
+    /// Seconds after leaving the ground in which a jump is still allowed
+    pub coyote_time: f32,
+    /// Seconds a jump requested in the air is remembered until the ground is reached
+    pub jump_buffer_time: f32,
+    grounded: bool,
+    sliding: bool,
+    ground_normal: Option<Vector2<f32>>,
+    time_since_grounded: Option<f32>,
+    time_since_jump_request: Option<f32>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    collisions: Vec<CharacterCollision>,
 }
 
 impl CharacterControllerComponent {
-    pub fn controller(&self) -> &KinematicCharacterController {
-        &self.controller
+    pub const DEFAULT_COYOTE_TIME: f32 = 0.1;
+    pub const DEFAULT_JUMP_BUFFER_TIME: f32 = 0.1;
+
+    pub fn new(shape: impl Into<SharedShape>) -> Self {
+        Self {
+            controller: KinematicCharacterController::default(),
+            shape: shape.into(),
+            position: Isometry2::default(),
+            push_mass: 0.0,
+            coyote_time: Self::DEFAULT_COYOTE_TIME,
+            jump_buffer_time: Self::DEFAULT_JUMP_BUFFER_TIME,
+            grounded: false,
+            sliding: false,
+            ground_normal: None,
+            time_since_grounded: None,
+            time_since_jump_request: None,
+            collisions: Vec::new(),
+        }
     }
 
-    pub fn controller_mut(&mut self) -> &mut KinematicCharacterController {
-        &mut self.controller
+    pub fn with_controller(mut self, controller: KinematicCharacterController) -> Self {
+        self.controller = controller;
+        self
     }
 
-    pub fn set_controller(&mut self, controller: KinematicCharacterController) {
-        self.controller = controller;
+    pub fn with_position(mut self, position: Isometry2<f32>) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_translation(mut self, translation: Vector2<f32>) -> Self {
+        self.set_translation(translation);
+        self
+    }
+
+    pub fn with_push_mass(mut self, push_mass: f32) -> Self {
+        self.push_mass = push_mass;
+        self
+    }
+
+    pub fn with_coyote_time(mut self, coyote_time: f32) -> Self {
+        self.coyote_time = coyote_time;
+        self
+    }
+
+    pub fn with_jump_buffer_time(mut self, jump_buffer_time: f32) -> Self {
+        self.jump_buffer_time = jump_buffer_time;
+        self
     }
 
     pub fn set_shape(&mut self, shape: impl Into<SharedShape>) {
@@ -37,139 +90,280 @@ impl CharacterControllerComponent {
         self.shape.downcast_ref::<S>()
     }
 
-    pub fn try_shape_mut<S: Shape>(&mut self) -> Option<&mut S> {
-        self.shape.downcast_mut::<S>()
+    pub fn set_rotation(&mut self, rotation: Rotation2<f32>) {
+        self.position.rotation = rotation;
     }
 
-    pub fn shape<S: Shape>(&self) -> &S {
-        self.try_shape().unwrap()
+    pub fn set_translation(&mut self, translation: Vector2<f32>) {
+        self.position.translation.vector = translation;
     }
 
-    pub fn shape_mut<S: Shape>(&mut self) -> &mut S {
-        self.try_shape_mut().unwrap()
+    pub fn set_position(&mut self, position: Isometry2<f32>) {
+        self.position = position
     }
 
-    pub fn with_scaling(mut self, scaling: Vector2<f32>) -> Self {
-        self.set_scaling(scaling);
-        self
+    pub fn rotation(&self) -> Rotation2<f32> {
+        self.position.rotation
     }
 
-    pub fn with_rotation(mut self, rotation: Rotation<f32>) -> Self {
-        self.set_rotation(rotation);
-        self
+    pub fn translation(&self) -> Vector2<f32> {
+        self.position.translation.vector
     }
 
-    pub fn with_translation(mut self, translation: Vector2<f32>) -> Self {
-        self.set_translation(translation);
-        self
+    pub fn position(&self) -> &Isometry2<f32> {
+        &self.position
     }
 
-    pub fn with_position(mut self, position: Isometry2<f32>) -> Self {
-        self.set_position(position);
-        self
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
     }
 
-    pub fn with_disabled(mut self, disabled: bool) -> Self {
-        self.set_disabled(disabled);
-        self
+    /// Whether the character slides down a slope that is too steep to climb
+    pub fn is_sliding(&self) -> bool {
+        self.sliding
     }
 
-    pub fn with_shape(mut self, shape: impl Into<SharedShape>) -> Self {
-        self.shape = shape.into();
-        self
+    /// Normal of the ground the character stands on
+    pub fn ground_normal(&self) -> Option<Vector2<f32>> {
+        self.ground_normal
     }
 
-    pub fn with_controller(mut self, controller: KinematicCharacterController) -> Self {
-        self.controller = controller;
-        self
+    /// Collisions of the last [CharacterControllerComponent::move_by]
+    pub fn collisions(&self) -> &[CharacterCollision] {
+        &self.collisions
     }
 
-    pub fn set_disabled(&mut self, disabled: bool) {
-        self.disabled = disabled;
-        self.instance.set_scaling_rotation(
-            if disabled {
-                Vector2::default()
-            } else {
-                self.scaling
-            },
-            self.position.rotation,
-        );
+    /// Remembers a jump for [CharacterControllerComponent::jump_buffer_time] seconds
+    pub fn request_jump(&mut self) {
+        self.time_since_jump_request = Some(0.0);
     }
 
-    pub fn set_rotation(&mut self, rotation: Rotation<f32>) {
-        self.position.rotation = rotation;
-        self.instance.set_scaling_rotation(
-            if self.disabled {
-                Vector2::default()
-            } else {
-                self.scaling
-            },
-            rotation,
-        );
+    /// Whether the character is grounded or left the ground within the coyote time
+    pub fn can_jump(&self) -> bool {
+        self.time_since_grounded
+            .is_some_and(|time| time <= self.coyote_time)
     }
 
-    pub fn set_translation(&mut self, translation: Vector2<f32>) {
-        self.instance.set_translation(translation);
-        self.position.translation.vector = translation;
+    /// Returns true once if a buffered jump request meets a moment where [Self::can_jump].
+    /// The coyote time is used up until the character touches the ground again.
+    pub fn consume_jump(&mut self) -> bool {
+        if self.time_since_jump_request.is_some() && self.can_jump() {
+            self.time_since_jump_request = None;
+            self.time_since_grounded = None;
+            return true;
+        }
+        false
     }
 
-    pub fn set_position(&mut self, position: Isometry2<f32>) {
-        self.position = position;
-        self.instance = Instance2D::new(
-            position,
-            if self.disabled {
-                Vector2::default()
-            } else {
-                self.scaling
-            },
+    /// Moves the character as far as possible along `desired_translation`, applying autostep
+    /// and snap to ground of the controller, and pushes dynamic rigid bodies in the way.
+    /// The colliders of the character itself should be excluded with the `filter`.
+    pub fn move_by(
+        &mut self,
+        physics: &mut Physics,
+        desired_translation: Vector2<f32>,
+        dt: f32,
+        filter: QueryFilter,
+    ) -> EffectiveCharacterMovement {
+        let mut collisions = std::mem::take(&mut self.collisions);
+        collisions.clear();
+        let movement = self.controller.move_shape(
+            dt,
+            physics.rigid_bodies(),
+            physics.colliders(),
+            physics.query_pipeline(),
+            &*self.shape,
+            &self.position,
+            desired_translation,
+            filter,
+            |collision| collisions.push(collision),
         );
+        self.set_translation(self.translation() + movement.translation);
+
+        if self.push_mass > 0.0 {
+            physics.solve_character_collision_impulses(
+                &self.controller,
+                &*self.shape,
+                self.push_mass,
+                &collisions,
+                dt,
+                filter,
+            );
+        }
+
+        self.grounded = movement.grounded;
+        self.sliding = movement.is_sliding_down_slope;
+        let up = self.controller.up.into_inner();
+        self.ground_normal = if movement.grounded {
+            collisions
+                .iter()
+                .map(|collision| collision.hit.normal1.into_inner())
+                .filter(|normal| normal.dot(&up) > 0.0)
+                .max_by(|a, b| a.dot(&up).total_cmp(&b.dot(&up)))
+                .or(Some(up))
+        } else {
+            None
+        };
+        self.collisions = collisions;
+
+        self.time_since_grounded = if movement.grounded {
+            Some(0.0)
+        } else {
+            self.time_since_grounded.map(|time| time + dt)
+        };
+        self.time_since_jump_request = self
+            .time_since_jump_request
+            .map(|time| time + dt)
+            .filter(|time| *time <= self.jump_buffer_time);
+        movement
     }
+}
 
-    pub fn set_scaling(&mut self, scaling: Vector2<f32>) {
-        self.scaling = scaling;
-        self.instance.set_scaling_rotation(
-            if self.disabled {
-                Vector2::default()
-            } else {
-                self.scaling
-            },
-            self.position.rotation,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::World,
+        math::Point2,
+        physics::{ColliderBuilder, SharedShape},
+    };
+
+    const DT: f32 = 1.0 / 60.0;
+    const HALF_HEIGHT: f32 = 0.4;
+    const RADIUS: f32 = 0.3;
+    const SLOPE: f32 = 20.0;
+
+    /// Ground with its top at `y = 0` and a slope rising to the right from `x = 5`
+    fn level() -> Physics {
+        let mut world = World::new();
+        let mut physics = Physics::new();
+        let level = world.add_entity(());
+        physics.add_collider(
+            &level,
+            ColliderBuilder::cuboid(50.0, 0.5)
+                .translation(Vector2::new(0.0, -0.5))
+                .build(),
+        );
+        let rise = 10.0 * SLOPE.to_radians().tan();
+        physics.add_collider(
+            &level,
+            ColliderBuilder::triangle(
+                Point2::new(5.0, 0.0),
+                Point2::new(15.0, 0.0),
+                Point2::new(15.0, rise),
+            )
+            .build(),
         );
+        // Updates the query pipeline
+        physics.step(DT);
+        physics
     }
 
-    pub fn disabled(&self) -> bool {
-        self.disabled
+    fn character(x: f32, y: f32) -> CharacterControllerComponent {
+        CharacterControllerComponent::new(SharedShape::capsule_y(HALF_HEIGHT, RADIUS))
+            .with_translation(Vector2::new(x, y))
     }
 
-    pub fn rotation(&self) -> Rotation<f32> {
-        self.position.rotation
+    fn land(character: &mut CharacterControllerComponent, physics: &mut Physics, dt: f32) {
+        character.move_by(physics, Vector2::new(0.0, -5.0), dt, QueryFilter::default());
+        assert!(character.is_grounded());
     }
 
-    pub fn translation(&self) -> Vector2<f32> {
-        self.position.translation.vector
+    fn fly(character: &mut CharacterControllerComponent, physics: &mut Physics, dt: f32) {
+        character.set_translation(Vector2::new(0.0, 10.0));
+        character.move_by(physics, Vector2::zeros(), dt, QueryFilter::default());
+        assert!(!character.is_grounded());
     }
 
-    pub fn position(&self) -> Isometry2<f32> {
-        self.position
+    #[test]
+    fn grounded_on_flat_ground() {
+        let mut physics = level();
+        let mut character = character(0.0, 3.0);
+        assert!(!character.is_grounded());
+        assert_eq!(character.ground_normal(), None);
+
+        land(&mut character, &mut physics, DT);
+        assert!((character.translation().y - (HALF_HEIGHT + RADIUS)).abs() < 0.05);
+        assert!((character.ground_normal().unwrap() - Vector2::y()).norm() < 1e-3);
+        assert!(!character.is_sliding());
+        assert!(!character.collisions().is_empty());
+
+        character.move_by(
+            &mut physics,
+            Vector2::new(1.0, 0.0),
+            DT,
+            QueryFilter::default(),
+        );
+        assert!((character.translation().x - 1.0).abs() < 1e-3);
+        assert!(character.is_grounded());
     }
 
-    pub const fn scaling(&self) -> &Vector2<f32> {
-        &self.scaling
+    #[test]
+    fn walks_up_slopes() {
+        let mut physics = level();
+        let mut character = character(8.0, 4.0);
+        land(&mut character, &mut physics, DT);
+        let slope = SLOPE.to_radians();
+        let normal = Vector2::new(-slope.sin(), slope.cos());
+        assert!((character.ground_normal().unwrap() - normal).norm() < 0.05);
+
+        let start = character.translation();
+        for _ in 0..20 {
+            character.move_by(
+                &mut physics,
+                Vector2::new(0.1, 0.0),
+                DT,
+                QueryFilter::default(),
+            );
+            assert!(character.is_grounded());
+        }
+        let climbed = character.translation() - start;
+        assert!(climbed.x > 1.5);
+        assert!((climbed.y - climbed.x * slope.tan()).abs() < 0.2);
+        assert!(!character.is_sliding());
     }
 
-    // pub fn move_character(&mut self, physics: &Physics) {}
-
-    // pub fn move_character_no_apply(&self, physics: &Physics) -> EffectiveCharacterMovement {
-
-    // }
-
-    // pub fn solve_collision(&self) {
-
-    // }
-}
+    #[test]
+    fn coyote_time() {
+        let mut physics = level();
+        let mut character = character(0.0, 3.0).with_coyote_time(0.1);
+        assert!(!character.can_jump());
+        land(&mut character, &mut physics, DT);
+        assert!(character.can_jump());
+
+        fly(&mut character, &mut physics, 0.05);
+        assert!(character.can_jump());
+        character.move_by(&mut physics, Vector2::zeros(), 0.06, QueryFilter::default());
+        assert!(!character.can_jump());
+
+        character.set_translation(Vector2::new(0.0, 3.0));
+        land(&mut character, &mut physics, DT);
+        fly(&mut character, &mut physics, 0.05);
+        character.request_jump();
+        assert!(character.consume_jump());
+        // The coyote time is used up by the jump
+        character.request_jump();
+        assert!(!character.consume_jump());
+    }
 
-impl BaseComponent for CharacterControllerComponent {
-    fn instance(&self, physics: &Physics) -> crate::Instance2D {
-        self.instance
+    #[test]
+    fn jump_buffer() {
+        let mut physics = level();
+        let mut character = character(0.0, 10.0).with_jump_buffer_time(0.1);
+        character.request_jump();
+        fly(&mut character, &mut physics, 0.05);
+        assert!(!character.consume_jump());
+        character.set_translation(Vector2::new(0.0, 3.0));
+        land(&mut character, &mut physics, 0.04);
+        assert!(character.consume_jump());
+        assert!(!character.consume_jump());
+
+        // Requests expire in the air
+        character.request_jump();
+        fly(&mut character, &mut physics, 0.06);
+        character.move_by(&mut physics, Vector2::zeros(), 0.06, QueryFilter::default());
+        character.set_translation(Vector2::new(0.0, 3.0));
+        land(&mut character, &mut physics, DT);
+        assert!(!character.consume_jump());
     }
 }
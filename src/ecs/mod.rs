@@ -1,17 +1,53 @@
 #[cfg(feature = "physics")]
+mod area_component;
+#[cfg(feature = "physics")]
 mod collider_component;
 #[cfg(feature = "physics")]
+mod kinematic_character_controller_component;
+#[cfg(feature = "physics")]
 mod rigid_body_component;
 #[cfg(feature = "physics")]
 mod simple_character_controller_component;
+#[cfg(feature = "audio")]
+mod audio_emitter_component;
+mod commands;
+mod disabled_component;
+mod entity_pool;
+mod hierarchy;
+mod parallax_component;
+mod particle_emitter_component;
+mod position_component;
+mod replication;
+mod snapshot;
+mod state;
+mod state_machine;
+mod sprite_sheet_animation_component;
 mod systems;
 mod world;
 
+#[cfg(feature = "physics")]
+pub use area_component::*;
 #[cfg(feature = "physics")]
 pub use collider_component::*;
 #[cfg(feature = "physics")]
+pub use kinematic_character_controller_component::*;
+#[cfg(feature = "physics")]
 pub use rigid_body_component::*;
 #[cfg(feature = "physics")]
 pub use simple_character_controller_component::*;
+#[cfg(feature = "audio")]
+pub use audio_emitter_component::*;
+pub use commands::*;
+pub use disabled_component::*;
+pub use entity_pool::*;
+pub use hierarchy::*;
+pub use parallax_component::*;
+pub use particle_emitter_component::*;
+pub use position_component::*;
+pub use replication::*;
+pub use snapshot::*;
+pub use state::*;
+pub use state_machine::*;
+pub use sprite_sheet_animation_component::*;
 pub use systems::*;
 pub use world::*;
@@ -0,0 +1,199 @@
+use crate::{
+    context::RenderContext,
+    ecs::{Component, DisabledComponent, IterEnabled, System, SystemPriority, WorldExt},
+    graphics::{
+        AssetKey, Camera2D, Gpu, InstanceBuffer, RenderEncoder, SpriteAtlas, SpriteCropInstance2D,
+    },
+    math::{Isometry2, Vector2, AABB},
+    scene::{Plugin, SceneCreator},
+};
+
+/// Axes on which a [ParallaxLayer] repeats infinitely
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParallaxRepeat {
+    #[default]
+    None,
+    Horizontal,
+    Vertical,
+    Both,
+}
+
+impl ParallaxRepeat {
+    pub fn horizontal(&self) -> bool {
+        matches!(self, Self::Horizontal | Self::Both)
+    }
+
+    pub fn vertical(&self) -> bool {
+        matches!(self, Self::Vertical | Self::Both)
+    }
+}
+
+/// Background sprite that scrolls with a fraction of the camera speed. Repeating layers are drawn
+/// as one quad covering the camera with wrapped UVs, so the sprite needs a
+/// [wgpu::AddressMode::Repeat] sampler on the repeating axes.
+#[derive(Component)]
+pub struct ParallaxLayer {
+    pub sprite: AssetKey,
+    /// Position of the layer while the camera is at the origin
+    pub position: Vector2<f32>,
+    /// World size of one repetition of the sprite
+    pub size: Vector2<f32>,
+    /// `0.0` is fixed to the camera, `1.0` moves like the world
+    pub scroll_factor: Vector2<f32>,
+    pub repeat: ParallaxRepeat,
+    /// Layers with a lower order are drawn first
+    pub order: i32,
+    pub alpha: f32,
+    instances: Option<InstanceBuffer<SpriteCropInstance2D>>,
+}
+
+impl ParallaxLayer {
+    pub fn new(sprite: AssetKey, size: Vector2<f32>) -> Self {
+        Self {
+            sprite,
+            position: Vector2::zeros(),
+            size,
+            scroll_factor: Vector2::new(0.5, 0.5),
+            repeat: ParallaxRepeat::None,
+            order: 0,
+            alpha: 1.0,
+            instances: None,
+        }
+    }
+
+    pub fn position(mut self, position: Vector2<f32>) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn scroll_factor(mut self, scroll_factor: Vector2<f32>) -> Self {
+        self.scroll_factor = scroll_factor;
+        self
+    }
+
+    pub fn repeat(mut self, repeat: ParallaxRepeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    pub fn order(mut self, order: i32) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    /// Center of the layer for the given camera position
+    pub fn center(&self, camera: Vector2<f32>) -> Vector2<f32> {
+        self.position + camera.component_mul(&(Vector2::new(1.0, 1.0) - self.scroll_factor))
+    }
+
+    /// Instance that covers the visible part of the layer or [None] if the layer is not visible
+    pub fn instance(&self, camera: &Camera2D) -> Option<SpriteCropInstance2D> {
+        let view = camera.aabb();
+        let center = self.center(*camera.translation());
+        let mut translation = center;
+        let mut scaling = self.size;
+        let mut tiles = Vector2::new(1.0, 1.0);
+        for (axis, repeat) in [self.repeat.horizontal(), self.repeat.vertical()]
+            .into_iter()
+            .enumerate()
+        {
+            if !repeat || self.size[axis] <= 0.0 {
+                continue;
+            }
+            let origin = center[axis] - self.size[axis] / 2.0;
+            let first = ((view.min()[axis] - origin) / self.size[axis]).floor();
+            let last = ((view.max()[axis] - origin) / self.size[axis]).ceil();
+            tiles[axis] = (last - first).max(1.0);
+            scaling[axis] = tiles[axis] * self.size[axis];
+            translation[axis] = origin + first * self.size[axis] + scaling[axis] / 2.0;
+        }
+
+        if !AABB::from_center(translation, scaling / 2.0).intersects(&view) {
+            return None;
+        }
+        Some(SpriteCropInstance2D::new(
+            Isometry2::new(translation, 0.0),
+            scaling,
+            SpriteAtlas {
+                offset: Vector2::zeros(),
+                scaling: tiles,
+                alpha: self.alpha,
+            },
+        ))
+    }
+
+    fn prepare(&mut self, gpu: &Gpu, camera: &Camera2D) {
+        let instance = self.instance(camera);
+        let instances = self
+            .instances
+            .get_or_insert_with(|| InstanceBuffer::empty(gpu, 1));
+        instances.write(gpu, instance.as_slice());
+    }
+}
+
+/// Draws all [ParallaxLayer]s sorted by their order. The default priority draws them before
+/// render systems with [SystemPriority::DURING], so they sit behind the gameplay sprites.
+pub struct ParallaxPlugin {
+    pub priority: SystemPriority,
+}
+
+impl ParallaxPlugin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn priority(mut self, priority: SystemPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+impl Default for ParallaxPlugin {
+    fn default() -> Self {
+        Self {
+            priority: SystemPriority::BEFORE,
+        }
+    }
+}
+
+impl Plugin for ParallaxPlugin {
+    fn init<S: SceneCreator>(&mut self, scene: S) -> S {
+        scene.system(System::render(render_parallax).priority(self.priority))
+    }
+}
+
+fn render_parallax(ctx: &RenderContext, encoder: &mut RenderEncoder) {
+    let mut layers = ctx.world.view_mut::<ParallaxLayer>();
+    let disabled = ctx.world.view::<DisabledComponent>();
+    let mut layers = (&mut layers)
+        .iter_enabled(&disabled)
+        .map(|(_, layer)| layer)
+        .collect::<Vec<_>>();
+    if layers.is_empty() {
+        return;
+    }
+    layers.sort_by_key(|layer| layer.order);
+
+    let camera = ctx.world_camera2d.camera();
+    for layer in layers.iter_mut() {
+        layer.prepare(&ctx.gpu, camera);
+    }
+
+    let mut renderer = encoder.renderer2d(None);
+    for layer in &layers {
+        if let Some(instances) = &layer.instances {
+            renderer.draw_sprite_crop(
+                instances,
+                &ctx.default_assets.sprite_mesh,
+                &ctx.default_assets.world_camera2d,
+                &ctx.assets.sprite(layer.sprite),
+            );
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::VecDeque;
+
+use shipyard::Get;
+
+use crate::{
+    context::{Context, RenderContext},
+    ecs::{
+        Component, DisabledComponent, IterEnabled, PositionComponent2D, System, SystemPriority,
+        WorldExt,
+    },
+    graphics::{
+        AssetKey, BlendState, ColorInstance2D, Gpu, Gradient, InstanceBuffer, RenderEncoder,
+    },
+    math::{Isometry2, Vector2},
+    random::SceneRandom,
+    scene::{Plugin, SceneCreator},
+};
+
+/// How the particles of a [ParticleEmitterComponent] are blended with the scene
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParticleBlend {
+    #[default]
+    Alpha,
+    /// Overlapping particles add up their colors, useful for fire, sparks and magic
+    Additive,
+}
+
+impl ParticleBlend {
+    pub const ADDITIVE_BLENDING: BlendState = BlendState {
+        color: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::SrcAlpha,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        },
+        alpha: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        },
+    };
+
+    pub fn blend_state(&self) -> BlendState {
+        match self {
+            Self::Alpha => BlendState::ALPHA_BLENDING,
+            Self::Additive => Self::ADDITIVE_BLENDING,
+        }
+    }
+}
+
+/// Space in which the particles of a [ParticleEmitterComponent] are simulated
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParticleSpace {
+    /// Particles stay where they were spawned when the emitter moves
+    #[default]
+    World,
+    /// Particles move and rotate with the emitter
+    Local,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Particle {
+    position: Vector2<f32>,
+    velocity: Vector2<f32>,
+    age: f32,
+    lifetime: f32,
+}
+
+/// Spawns, simulates and draws sprite particles. The simulation runs on the CPU in
+/// [ParticlePlugin] and every emitter is drawn with a single draw call. The emitter is placed at
+/// the [PositionComponent2D] of its entity or at the origin if it has none.
+#[derive(Component)]
+pub struct ParticleEmitterComponent {
+    pub sprite: AssetKey,
+    /// Particles spawned per second while emitting
+    pub spawn_rate: f32,
+    /// Minimum and maximum lifetime in seconds
+    pub lifetime: (f32, f32),
+    /// Angle in radians of the emission cone relative to the emitter rotation
+    pub direction: f32,
+    /// Half angle in radians of the emission cone, `PI` emits in every direction
+    pub spread: f32,
+    /// Minimum and maximum initial speed
+    pub speed: (f32, f32),
+    pub gravity: Vector2<f32>,
+    /// Size at spawn and at death, linearly interpolated in between
+    pub size: (Vector2<f32>, Vector2<f32>),
+    pub color: Gradient,
+    pub blend: ParticleBlend,
+    pub space: ParticleSpace,
+    /// When full the oldest particles are recycled for new ones
+    pub max_particles: usize,
+    emitting: bool,
+    spawn_accumulator: f32,
+    pending_burst: usize,
+    origin: Isometry2<f32>,
+    particles: VecDeque<Particle>,
+    instances: Option<InstanceBuffer<ColorInstance2D>>,
+}
+
+impl ParticleEmitterComponent {
+    pub fn new(sprite: AssetKey) -> Self {
+        Self {
+            sprite,
+            spawn_rate: 10.0,
+            lifetime: (1.0, 1.0),
+            direction: std::f32::consts::FRAC_PI_2,
+            spread: std::f32::consts::PI,
+            speed: (1.0, 1.0),
+            gravity: Vector2::zeros(),
+            size: (Vector2::new(0.1, 0.1), Vector2::new(0.1, 0.1)),
+            color: Gradient::default(),
+            blend: ParticleBlend::Alpha,
+            space: ParticleSpace::World,
+            max_particles: 1000,
+            emitting: true,
+            spawn_accumulator: 0.0,
+            pending_burst: 0,
+            origin: Isometry2::default(),
+            particles: VecDeque::new(),
+            instances: None,
+        }
+    }
+
+    pub fn spawn_rate(mut self, spawn_rate: f32) -> Self {
+        self.spawn_rate = spawn_rate;
+        self
+    }
+
+    pub fn lifetime(mut self, min: f32, max: f32) -> Self {
+        self.lifetime = (min, max);
+        self
+    }
+
+    pub fn cone(mut self, direction: f32, spread: f32) -> Self {
+        self.direction = direction;
+        self.spread = spread;
+        self
+    }
+
+    pub fn speed(mut self, min: f32, max: f32) -> Self {
+        self.speed = (min, max);
+        self
+    }
+
+    pub fn gravity(mut self, gravity: Vector2<f32>) -> Self {
+        self.gravity = gravity;
+        self
+    }
+
+    pub fn size(mut self, start: Vector2<f32>, end: Vector2<f32>) -> Self {
+        self.size = (start, end);
+        self
+    }
+
+    pub fn color(mut self, color: Gradient) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn blend(mut self, blend: ParticleBlend) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    pub fn space(mut self, space: ParticleSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    pub fn max_particles(mut self, max_particles: usize) -> Self {
+        self.max_particles = max_particles;
+        self
+    }
+
+    pub fn emitting(mut self, emitting: bool) -> Self {
+        self.emitting = emitting;
+        self
+    }
+
+    /// Continuous emission, bursts are spawned regardless
+    pub fn set_emitting(&mut self, emitting: bool) {
+        self.emitting = emitting;
+        self.spawn_accumulator = 0.0;
+    }
+
+    pub fn is_emitting(&self) -> bool {
+        self.emitting
+    }
+
+    /// Spawns `amount` particles on the next [ParticleEmitterComponent::update]
+    pub fn burst(&mut self, amount: usize) {
+        self.pending_burst += amount;
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.pending_burst = 0;
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// Advances the particles and spawns new ones at `origin`. The particles are sampled from
+    /// `random`, so a seeded [SceneRandom] always spawns the same particles.
+    pub fn update(&mut self, delta: f32, origin: Isometry2<f32>, random: &mut SceneRandom) {
+        self.origin = origin;
+        let gravity = self.gravity * delta;
+        for particle in self.particles.iter_mut() {
+            particle.age += delta;
+            particle.velocity += gravity;
+            particle.position += particle.velocity * delta;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+
+        let mut amount = std::mem::take(&mut self.pending_burst);
+        if self.emitting && self.spawn_rate > 0.0 {
+            self.spawn_accumulator += delta * self.spawn_rate;
+            let spawned = self.spawn_accumulator.floor();
+            self.spawn_accumulator -= spawned;
+            amount += spawned as usize;
+        }
+        for _ in 0..amount.min(self.max_particles) {
+            self.spawn(random);
+        }
+    }
+
+    fn spawn(&mut self, random: &mut SceneRandom) {
+        if self.max_particles == 0 {
+            return;
+        }
+        while self.particles.len() >= self.max_particles {
+            self.particles.pop_front();
+        }
+
+        let angle = self.direction + sample(random, -self.spread, self.spread);
+        let velocity =
+            Vector2::new(angle.cos(), angle.sin()) * sample(random, self.speed.0, self.speed.1);
+        let (position, velocity) = match self.space {
+            ParticleSpace::World => (
+                self.origin.translation.vector,
+                self.origin.rotation * velocity,
+            ),
+            ParticleSpace::Local => (Vector2::zeros(), velocity),
+        };
+        self.particles.push_back(Particle {
+            position,
+            velocity,
+            age: 0.0,
+            lifetime: sample(random, self.lifetime.0, self.lifetime.1),
+        });
+    }
+
+    pub fn instances(&self) -> impl Iterator<Item = ColorInstance2D> + '_ {
+        let transform = match self.space {
+            ParticleSpace::World => Isometry2::default(),
+            ParticleSpace::Local => self.origin,
+        };
+        self.particles.iter().map(move |particle| {
+            let t = if particle.lifetime > 0.0 {
+                (particle.age / particle.lifetime).clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+            ColorInstance2D::new(
+                Isometry2::new(
+                    transform.transform_point(&particle.position.into()).coords,
+                    transform.rotation.angle(),
+                ),
+                self.size.0.lerp(&self.size.1, t),
+                self.color.sample(t),
+            )
+        })
+    }
+
+    pub fn buffer(&mut self, gpu: &Gpu) {
+        let instances = self.instances().collect::<Vec<_>>();
+        let buffer = self
+            .instances
+            .get_or_insert_with(|| InstanceBuffer::empty(gpu, self.max_particles as u64));
+        buffer.write(gpu, &instances);
+    }
+}
+
+fn sample(random: &mut SceneRandom, min: f32, max: f32) -> f32 {
+    if min < max {
+        random.gen_range(min..max)
+    } else {
+        min
+    }
+}
+
+/// Simulates and draws all [ParticleEmitterComponent]s. The default priority draws the particles
+/// after render systems with [SystemPriority::DURING], so they appear above the gameplay sprites.
+pub struct ParticlePlugin {
+    pub priority: SystemPriority,
+}
+
+impl ParticlePlugin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn priority(mut self, priority: SystemPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+impl Default for ParticlePlugin {
+    fn default() -> Self {
+        Self {
+            priority: SystemPriority::AFTER,
+        }
+    }
+}
+
+impl Plugin for ParticlePlugin {
+    fn init<S: SceneCreator>(&mut self, scene: S) -> S {
+        scene
+            .system(System::update(update_particles))
+            .system(System::render(render_particles).priority(self.priority))
+    }
+}
+
+fn update_particles(ctx: &mut Context) {
+    let delta = ctx.time.delta();
+    let mut emitters = ctx.world.view_mut::<ParticleEmitterComponent>();
+    let positions = ctx.world.view::<PositionComponent2D>();
+    let disabled = ctx.world.view::<DisabledComponent>();
+    for (entity, emitter) in (&mut emitters).iter_enabled(&disabled) {
+        let origin = positions
+            .get(entity)
+            .map(|position| position.position)
+            .unwrap_or_default();
+        emitter.update(delta, origin, ctx.random);
+        emitter.buffer(&ctx.gpu);
+    }
+}
+
+fn render_particles(ctx: &RenderContext, encoder: &mut RenderEncoder) {
+    let emitters = ctx.world.view::<ParticleEmitterComponent>();
+    let disabled = ctx.world.view::<DisabledComponent>();
+    let mut renderer = encoder.renderer2d(None);
+    for (_, emitter) in emitters.iter_enabled(&disabled) {
+        if let Some(instances) = &emitter.instances {
+            renderer.draw_particles(
+                instances,
+                &ctx.default_assets.sprite_mesh,
+                &ctx.default_assets.world_camera2d,
+                &ctx.assets.sprite(emitter.sprite),
+                emitter.blend,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn emitter() -> ParticleEmitterComponent {
+        ParticleEmitterComponent::new("particle")
+            .spawn_rate(100.0)
+            .lifetime(0.5, 2.0)
+            .speed(1.0, 3.0)
+            .cone(0.0, 1.0)
+    }
+
+    fn simulate(seed: u64) -> Vec<(Vector2<f32>, Vector2<f32>, f32)> {
+        let mut random = SceneRandom::new(seed);
+        let mut emitter = emitter();
+        emitter.burst(10);
+        for _ in 0..60 {
+            emitter.update(1.0 / 60.0, Isometry2::default(), &mut random);
+        }
+        emitter
+            .particles
+            .iter()
+            .map(|particle| (particle.position, particle.velocity, particle.lifetime))
+            .collect()
+    }
+
+    #[test]
+    fn seeded_random_spawns_the_same_particles() {
+        let particles = simulate(69);
+        assert!(particles.len() > 10);
+        assert_eq!(particles, simulate(69));
+        assert_ne!(particles, simulate(70));
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    ecs::Component,
+    graphics::{Instance2D, Instance3D},
+    math::{Isometry2, Isometry3, Rotation2, Rotation3, Vector2, Vector3, AABB},
+};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct PositionComponent2D {
+    pub position: Isometry2<f32>,
+    pub previous: Isometry2<f32>,
+    pub interpolate: bool,
+}
+
+impl PositionComponent2D {
+    pub fn new(position: Isometry2<f32>) -> Self {
+        Self {
+            position,
+            previous: position,
+            interpolate: false,
+        }
+    }
+
+    pub fn with_interpolate(mut self, interpolate: bool) -> Self {
+        self.interpolate = interpolate;
+        self
+    }
+
+    pub fn set_interpolate(&mut self, interpolate: bool) {
+        self.interpolate = interpolate;
+    }
+
+    pub fn set_position(&mut self, position: Isometry2<f32>) {
+        self.position = position;
+    }
+
+    pub fn set_translation(&mut self, translation: Vector2<f32>) {
+        self.position.translation.vector = translation;
+    }
+
+    pub fn set_rotation(&mut self, rotation: Rotation2<f32>) {
+        self.position.rotation = rotation;
+    }
+
+    pub fn translation(&self) -> Vector2<f32> {
+        self.position.translation.vector
+    }
+
+    pub fn rotation(&self) -> Rotation2<f32> {
+        self.position.rotation
+    }
+
+    /// Store the current position as previous position. Call this before every fixed step.
+    pub fn snapshot(&mut self) {
+        self.previous = self.position;
+    }
+
+    pub fn lerp_to(&self, other: &Self, t: f32) -> Isometry2<f32> {
+        self.position.lerp_slerp(&other.position, t)
+    }
+
+    pub fn interpolated(&self, alpha: f32) -> Isometry2<f32> {
+        self.previous.lerp_slerp(&self.position, alpha)
+    }
+
+    pub fn render_position(&self, alpha: f32) -> Isometry2<f32> {
+        if self.interpolate {
+            self.interpolated(alpha)
+        } else {
+            self.position
+        }
+    }
+
+    pub fn instance<D: bytemuck::Pod>(
+        &self,
+        alpha: f32,
+        scaling: Vector2<f32>,
+        data: D,
+    ) -> Instance2D<D> {
+        Instance2D::new(self.render_position(alpha), scaling, data)
+    }
+
+    /// Bounds of an instance with the given scaling on the unit sized default meshes
+    pub fn aabb(&self, scaling: Vector2<f32>) -> AABB {
+        AABB::from_position(self.position, scaling / 2.0)
+    }
+}
+
+impl From<Isometry2<f32>> for PositionComponent2D {
+    fn from(position: Isometry2<f32>) -> Self {
+        Self::new(position)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct PositionComponent3D {
+    pub position: Isometry3<f32>,
+    pub previous: Isometry3<f32>,
+    pub interpolate: bool,
+}
+
+impl PositionComponent3D {
+    pub fn new(position: Isometry3<f32>) -> Self {
+        Self {
+            position,
+            previous: position,
+            interpolate: false,
+        }
+    }
+
+    pub fn with_interpolate(mut self, interpolate: bool) -> Self {
+        self.interpolate = interpolate;
+        self
+    }
+
+    pub fn set_interpolate(&mut self, interpolate: bool) {
+        self.interpolate = interpolate;
+    }
+
+    pub fn set_position(&mut self, position: Isometry3<f32>) {
+        self.position = position;
+    }
+
+    pub fn set_translation(&mut self, translation: Vector3<f32>) {
+        self.position.translation.vector = translation;
+    }
+
+    pub fn set_rotation(&mut self, rotation: Rotation3<f32>) {
+        self.position.rotation = rotation;
+    }
+
+    pub fn translation(&self) -> Vector3<f32> {
+        self.position.translation.vector
+    }
+
+    pub fn rotation(&self) -> Rotation3<f32> {
+        self.position.rotation
+    }
+
+    /// Store the current position as previous position. Call this before every fixed step.
+    pub fn snapshot(&mut self) {
+        self.previous = self.position;
+    }
+
+    pub fn lerp_to(&self, other: &Self, t: f32) -> Isometry3<f32> {
+        self.position.lerp_slerp(&other.position, t)
+    }
+
+    pub fn interpolated(&self, alpha: f32) -> Isometry3<f32> {
+        self.previous.lerp_slerp(&self.position, alpha)
+    }
+
+    pub fn render_position(&self, alpha: f32) -> Isometry3<f32> {
+        if self.interpolate {
+            self.interpolated(alpha)
+        } else {
+            self.position
+        }
+    }
+
+    pub fn instance(&self, alpha: f32, scaling: Vector3<f32>) -> Instance3D {
+        Instance3D::new(self.render_position(alpha), scaling)
+    }
+}
+
+impl From<Isometry3<f32>> for PositionComponent3D {
+    fn from(position: Isometry3<f32>) -> Self {
+        Self::new(position)
+    }
+}
@@ -0,0 +1,708 @@
+use std::{any::Any, collections::VecDeque};
+
+use rustc_hash::FxHashMap;
+use shipyard::{IntoIter, IntoWithId};
+
+use crate::{
+    ecs::{Component, EntityId, World, WorldExt},
+    math::{Isometry2, Vector2},
+};
+
+/// Marks an entity whose [Replicate] components are included in [Replication::snapshot]
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Replicated;
+
+pub use shura_macros::Replicate;
+
+/// Component that can be sent to another peer with [Replication]. Only the components that
+/// changed since the last acknowledged tick are sent, so [Replicate::write] should be
+/// deterministic.
+///
+/// Can be derived for structs, see [macro@Replicate].
+pub trait Replicate: Component + Sized {
+    /// Identifies the component type, must be the same on every peer
+    const ID: u32;
+    fn write(&self, writer: &mut ReplicationWriter);
+    fn read(reader: &mut ReplicationReader) -> Option<Self>;
+}
+
+/// Fixed point encoding of a float within a range
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quantize {
+    pub min: f32,
+    pub max: f32,
+    /// 8, 16 or 32
+    pub bits: u8,
+}
+
+impl Quantize {
+    pub const fn new(min: f32, max: f32, bits: u8) -> Self {
+        assert!(bits == 8 || bits == 16 || bits == 32);
+        Self { min, max, bits }
+    }
+
+    fn steps(&self) -> f64 {
+        ((1u64 << self.bits) - 1) as f64
+    }
+}
+
+#[derive(Default)]
+pub struct ReplicationWriter {
+    data: Vec<u8>,
+}
+
+impl ReplicationWriter {
+    pub fn u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn f32(&mut self, value: f32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn bytes(&mut self, value: &[u8]) {
+        self.u32(value.len() as u32);
+        self.data.extend_from_slice(value);
+    }
+
+    /// Clamps the value to the range of `quantize` and writes it with `quantize.bits` bits
+    pub fn quantized(&mut self, value: f32, quantize: Quantize) {
+        let normalized =
+            ((value - quantize.min) / (quantize.max - quantize.min)).clamp(0.0, 1.0) as f64;
+        let value = (normalized * quantize.steps()).round() as u32;
+        match quantize.bits {
+            8 => self.u8(value as u8),
+            16 => self.u16(value as u16),
+            _ => self.u32(value),
+        }
+    }
+
+    pub fn vector2(&mut self, value: Vector2<f32>, quantize: Option<Quantize>) {
+        for value in [value.x, value.y] {
+            match quantize {
+                Some(quantize) => self.quantized(value, quantize),
+                None => self.f32(value),
+            }
+        }
+    }
+
+    /// The rotation is always quantized to 16 bits
+    pub fn isometry2(&mut self, value: Isometry2<f32>, translation: Option<Quantize>) {
+        self.vector2(value.translation.vector, translation);
+        self.quantized(
+            value.rotation.angle(),
+            Quantize::new(-std::f32::consts::PI, std::f32::consts::PI, 16),
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn serde<T: serde::Serialize>(&mut self, value: &T) {
+        self.bytes(&bincode::serialize(value).unwrap());
+    }
+}
+
+pub struct ReplicationReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ReplicationReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (value, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(value)
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|bytes| bytes[0])
+    }
+
+    pub fn u16(&mut self) -> Option<u16> {
+        self.take(2)
+            .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        self.take(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn f32(&mut self) -> Option<f32> {
+        self.take(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    pub fn bool(&mut self) -> Option<bool> {
+        self.u8().map(|value| value != 0)
+    }
+
+    pub fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    pub fn quantized(&mut self, quantize: Quantize) -> Option<f32> {
+        let value = match quantize.bits {
+            8 => self.u8()? as u32,
+            16 => self.u16()? as u32,
+            _ => self.u32()?,
+        };
+        let normalized = (value as f64 / quantize.steps()) as f32;
+        Some(quantize.min + normalized * (quantize.max - quantize.min))
+    }
+
+    pub fn vector2(&mut self, quantize: Option<Quantize>) -> Option<Vector2<f32>> {
+        let mut read = || match quantize {
+            Some(quantize) => self.quantized(quantize),
+            None => self.f32(),
+        };
+        Some(Vector2::new(read()?, read()?))
+    }
+
+    pub fn isometry2(&mut self, translation: Option<Quantize>) -> Option<Isometry2<f32>> {
+        let translation = self.vector2(translation)?;
+        let rotation = self.quantized(Quantize::new(
+            -std::f32::consts::PI,
+            std::f32::consts::PI,
+            16,
+        ))?;
+        Some(Isometry2::new(translation, rotation))
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn serde<T: serde::de::DeserializeOwned>(&mut self) -> Option<T> {
+        bincode::deserialize(self.bytes()?).ok()
+    }
+}
+
+/// Field of a derived [Replicate]. The quantization is ignored by fields that are not floats.
+pub trait ReplicateField: Sized {
+    fn write_field(&self, writer: &mut ReplicationWriter, quantize: Option<Quantize>);
+    fn read_field(reader: &mut ReplicationReader, quantize: Option<Quantize>) -> Option<Self>;
+}
+
+macro_rules! replicate_field {
+    ($($ty: ty => $method: ident),*) => {
+        $(
+            impl ReplicateField for $ty {
+                fn write_field(
+                    &self,
+                    writer: &mut ReplicationWriter,
+                    _quantize: Option<Quantize>,
+                ) {
+                    writer.$method(*self);
+                }
+
+                fn read_field(
+                    reader: &mut ReplicationReader,
+                    _quantize: Option<Quantize>,
+                ) -> Option<Self> {
+                    reader.$method()
+                }
+            }
+        )*
+    };
+}
+
+replicate_field!(u8 => u8, u16 => u16, u32 => u32, u64 => u64, bool => bool);
+
+impl ReplicateField for i32 {
+    fn write_field(&self, writer: &mut ReplicationWriter, _quantize: Option<Quantize>) {
+        writer.u32(*self as u32);
+    }
+
+    fn read_field(reader: &mut ReplicationReader, _quantize: Option<Quantize>) -> Option<Self> {
+        reader.u32().map(|value| value as i32)
+    }
+}
+
+impl ReplicateField for f32 {
+    fn write_field(&self, writer: &mut ReplicationWriter, quantize: Option<Quantize>) {
+        match quantize {
+            Some(quantize) => writer.quantized(*self, quantize),
+            None => writer.f32(*self),
+        }
+    }
+
+    fn read_field(reader: &mut ReplicationReader, quantize: Option<Quantize>) -> Option<Self> {
+        match quantize {
+            Some(quantize) => reader.quantized(quantize),
+            None => reader.f32(),
+        }
+    }
+}
+
+impl ReplicateField for Vector2<f32> {
+    fn write_field(&self, writer: &mut ReplicationWriter, quantize: Option<Quantize>) {
+        writer.vector2(*self, quantize);
+    }
+
+    fn read_field(reader: &mut ReplicationReader, quantize: Option<Quantize>) -> Option<Self> {
+        reader.vector2(quantize)
+    }
+}
+
+/// The quantization applies to the translation
+impl ReplicateField for Isometry2<f32> {
+    fn write_field(&self, writer: &mut ReplicationWriter, quantize: Option<Quantize>) {
+        writer.isometry2(*self, quantize);
+    }
+
+    fn read_field(reader: &mut ReplicationReader, quantize: Option<Quantize>) -> Option<Self> {
+        reader.isometry2(quantize)
+    }
+}
+
+/// Encoded components of every replicated entity
+type ReplicationState = FxHashMap<u64, FxHashMap<u32, Vec<u8>>>;
+
+struct ReplicatedType {
+    collect: fn(&World, &mut ReplicationState),
+    decode: fn(&[u8]) -> Option<Box<dyn Any>>,
+    insert: fn(&mut World, EntityId, Box<dyn Any>),
+    remove: fn(&mut World, EntityId),
+}
+
+/// Change of a single entity within a snapshot
+struct EntityDelta {
+    remote: u64,
+    /// Decoded components by their [Replicate::ID]
+    updated: Vec<(u32, Box<dyn Any>)>,
+    removed: Vec<u32>,
+}
+
+/// Replicates entities marked with [Replicated] to another [Replication] with the same
+/// components registered, for example through a socket. The transport is up to the user.
+///
+/// A snapshot only contains the components that changed since the last tick the other side
+/// [acknowledged](Replication::acknowledge), or every component if no tick was acknowledged.
+/// The receiving side spawns, updates and despawns its own entities accordingly.
+#[derive(Default)]
+pub struct Replication {
+    types: FxHashMap<u32, ReplicatedType>,
+    sent: VecDeque<(u32, ReplicationState)>,
+    baseline: Option<(u32, ReplicationState)>,
+    received: Option<u32>,
+    entities: FxHashMap<u64, EntityId>,
+}
+
+impl Replication {
+    /// Snapshots of unacknowledged ticks that are kept to serve as baselines
+    pub const MAX_PENDING: usize = 64;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn replicate<C: Replicate>(mut self) -> Self {
+        let previous = self.types.insert(
+            C::ID,
+            ReplicatedType {
+                collect: |world, state| {
+                    let replicated = world.view::<Replicated>();
+                    for (entity, component) in world.view::<C>().iter().with_id() {
+                        if !replicated.contains(entity) {
+                            continue;
+                        }
+                        let mut writer = ReplicationWriter::default();
+                        component.write(&mut writer);
+                        state
+                            .entry(entity.inner())
+                            .or_default()
+                            .insert(C::ID, writer.data);
+                    }
+                },
+                decode: |data| {
+                    let mut reader = ReplicationReader { data };
+                    let component = C::read(&mut reader)?;
+                    // Leftover bytes mean that the layout of the component differs
+                    reader
+                        .data
+                        .is_empty()
+                        .then(|| Box::new(component) as Box<dyn Any>)
+                },
+                insert: |world, entity, component| {
+                    let component = *component
+                        .downcast::<C>()
+                        .expect("Decoded component has the wrong type!");
+                    world.add_component(entity, (component,));
+                },
+                remove: |world, entity| {
+                    world.remove::<(C,)>(entity);
+                },
+            },
+        );
+        assert!(
+            previous.is_none(),
+            "Replicated component id {} is already used!",
+            C::ID
+        );
+        self
+    }
+
+    /// Encodes the replicated entities relative to the last acknowledged tick
+    pub fn snapshot(&mut self, world: &World, tick: u32) -> Vec<u8> {
+        let mut state = ReplicationState::default();
+        // Entities without replicated components are spawned on the other side as well
+        for (entity, _) in world.view::<Replicated>().iter().with_id() {
+            state.entry(entity.inner()).or_default();
+        }
+        for ty in self.types.values() {
+            (ty.collect)(world, &mut state);
+        }
+
+        let empty = ReplicationState::default();
+        let (baseline_tick, baseline) = match &self.baseline {
+            Some((tick, baseline)) => (*tick, baseline),
+            None => (u32::MAX, &empty),
+        };
+
+        let mut writer = ReplicationWriter::default();
+        writer.u32(tick);
+        writer.u32(baseline_tick);
+
+        let mut changed = Vec::new();
+        for (entity, components) in &state {
+            let previous = baseline.get(entity);
+            let updated: Vec<_> = components
+                .iter()
+                .filter(|(id, data)| previous.and_then(|previous| previous.get(*id)) != Some(*data))
+                .collect();
+            let removed: Vec<_> = previous
+                .map(|previous| {
+                    previous
+                        .keys()
+                        .filter(|id| !components.contains_key(*id))
+                        .collect()
+                })
+                .unwrap_or_default();
+            if previous.is_none() || !updated.is_empty() || !removed.is_empty() {
+                changed.push((entity, updated, removed));
+            }
+        }
+
+        writer.u32(changed.len() as u32);
+        for (entity, updated, removed) in changed {
+            writer.u64(*entity);
+            writer.u16(updated.len() as u16);
+            for (id, data) in updated {
+                writer.u32(*id);
+                writer.bytes(data);
+            }
+            writer.u16(removed.len() as u16);
+            for id in removed {
+                writer.u32(*id);
+            }
+        }
+
+        let despawned: Vec<_> = baseline
+            .keys()
+            .filter(|entity| !state.contains_key(*entity))
+            .collect();
+        writer.u32(despawned.len() as u32);
+        for entity in despawned {
+            writer.u64(*entity);
+        }
+
+        if self.sent.len() == Self::MAX_PENDING {
+            self.sent.pop_front();
+        }
+        self.sent.push_back((tick, state));
+        writer.data
+    }
+
+    /// The other side received the snapshot of `tick`, following snapshots only contain the
+    /// changes since then
+    pub fn acknowledge(&mut self, tick: u32) {
+        if let Some(index) = self.sent.iter().position(|(sent, _)| *sent == tick) {
+            self.baseline = self.sent.drain(..=index).last();
+        }
+    }
+
+    /// Tick of the last snapshot that was applied, which should be acknowledged on the sending
+    /// side
+    pub fn last_received(&self) -> Option<u32> {
+        self.received
+    }
+
+    /// Local entity of an entity of the sending side
+    pub fn local_entity(&self, remote: EntityId) -> Option<EntityId> {
+        self.entities.get(&remote.inner()).copied()
+    }
+
+    /// Applies a snapshot of the other side. Snapshots older than the last applied one are
+    /// ignored. Returns false if the snapshot is malformed or contains unknown components, in
+    /// which case the world is left untouched.
+    pub fn apply(&mut self, world: &mut World, data: &[u8]) -> bool {
+        self.try_apply(world, data).is_some()
+    }
+
+    fn try_apply(&mut self, world: &mut World, data: &[u8]) -> Option<()> {
+        let mut reader = ReplicationReader { data };
+        let tick = reader.u32()?;
+        let _baseline = reader.u32()?;
+
+        // The whole snapshot is decoded before the world is touched
+        let mut changed = Vec::new();
+        for _ in 0..reader.u32()? {
+            let remote = reader.u64()?;
+            let mut updated = Vec::new();
+            for _ in 0..reader.u16()? {
+                let id = reader.u32()?;
+                let data = reader.bytes()?;
+                updated.push((id, (self.types.get(&id)?.decode)(data)?));
+            }
+            let mut removed = Vec::new();
+            for _ in 0..reader.u16()? {
+                let id = reader.u32()?;
+                self.types.get(&id)?;
+                removed.push(id);
+            }
+            changed.push(EntityDelta {
+                remote,
+                updated,
+                removed,
+            });
+        }
+        let mut despawned = Vec::new();
+        for _ in 0..reader.u32()? {
+            despawned.push(reader.u64()?);
+        }
+        if !reader.data.is_empty() {
+            return None;
+        }
+
+        if self.received.is_some_and(|received| tick <= received) {
+            return Some(());
+        }
+        for delta in changed {
+            let entity = match self.entities.get(&delta.remote) {
+                Some(entity) if world.is_alive(*entity) => *entity,
+                _ => {
+                    let entity = world.add_entity(());
+                    self.entities.insert(delta.remote, entity);
+                    entity
+                }
+            };
+            for (id, component) in delta.updated {
+                (self.types[&id].insert)(world, entity, component);
+            }
+            for id in delta.removed {
+                (self.types[&id].remove)(world, entity);
+            }
+        }
+        for remote in despawned {
+            if let Some(entity) = self.entities.remove(&remote) {
+                world.delete_entity(entity);
+            }
+        }
+
+        self.received = Some(tick);
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shipyard::Get;
+
+    use super::*;
+
+    const QUANTIZE: Quantize = Quantize::new(-100.0, 100.0, 16);
+    /// Half a step of [QUANTIZE]
+    const TOLERANCE: f32 = 200.0 / 65535.0 / 2.0 + 1e-4;
+
+    #[derive(Component, Replicate, Clone, Debug, PartialEq)]
+    struct Body {
+        #[shura(replicate(quantize = QUANTIZE))]
+        position: Vector2<f32>,
+        #[shura(replicate)]
+        health: u32,
+        selected: bool,
+    }
+
+    #[derive(Component, Replicate, Clone, Debug, PartialEq)]
+    #[shura(replicate(id = 7))]
+    struct Team(#[shura(replicate)] u8);
+
+    fn body(x: f32, y: f32) -> Body {
+        Body {
+            position: Vector2::new(x, y),
+            health: 100,
+            selected: true,
+        }
+    }
+
+    fn replication() -> Replication {
+        Replication::new().replicate::<Body>().replicate::<Team>()
+    }
+
+    fn get<C: Component + Clone>(world: &World, entity: EntityId) -> Option<C> {
+        (&world.view::<C>()).get(entity).ok().cloned()
+    }
+
+    fn assert_close(a: Vector2<f32>, b: Vector2<f32>) {
+        assert!((a - b).abs().max() <= TOLERANCE, "{a} != {b}");
+    }
+
+    /// Entities that changed in a snapshot
+    fn changed_entities(snapshot: &[u8]) -> Vec<u64> {
+        let mut reader = ReplicationReader { data: snapshot };
+        reader.u32().unwrap();
+        reader.u32().unwrap();
+        let mut entities = Vec::new();
+        for _ in 0..reader.u32().unwrap() {
+            entities.push(reader.u64().unwrap());
+            for _ in 0..reader.u16().unwrap() {
+                reader.u32().unwrap();
+                reader.bytes().unwrap();
+            }
+            for _ in 0..reader.u16().unwrap() {
+                reader.u32().unwrap();
+            }
+        }
+        entities
+    }
+
+    #[test]
+    fn derived_fields() {
+        assert_eq!(Team::ID, 7);
+        assert_ne!(Body::ID, Team::ID);
+
+        let mut writer = ReplicationWriter::default();
+        body(12.345, -67.89).write(&mut writer);
+        // Two 16 bit coordinates and the health
+        assert_eq!(writer.data.len(), 2 + 2 + 4);
+        let read = Body::read(&mut ReplicationReader { data: &writer.data }).unwrap();
+        assert_close(read.position, Vector2::new(12.345, -67.89));
+        assert_eq!(read.health, 100);
+        assert!(
+            !read.selected,
+            "Fields without #[shura(replicate)] are not sent"
+        );
+
+        let mut writer = ReplicationWriter::default();
+        body(500.0, -500.0).write(&mut writer);
+        let read = Body::read(&mut ReplicationReader { data: &writer.data }).unwrap();
+        assert_eq!(read.position, Vector2::new(100.0, -100.0));
+
+        assert!(Body::read(&mut ReplicationReader { data: &[1, 2, 3] }).is_none());
+    }
+
+    #[test]
+    fn loopback() {
+        let mut server_world = World::new();
+        let mut client_world = World::new();
+        let mut server = replication();
+        let mut client = replication();
+
+        let moving = server_world.add_entity((Replicated, body(0.0, 0.0), Team(1)));
+        let standing = server_world.add_entity((Replicated, body(-20.5, 33.3)));
+        server_world.add_entity((body(1.0, 1.0),));
+
+        let snapshot = server.snapshot(&server_world, 1);
+        assert!(client.apply(&mut client_world, &snapshot));
+        assert_eq!(client.last_received(), Some(1));
+        assert_eq!(client_world.view::<Body>().iter().count(), 2);
+        let local_moving = client.local_entity(moving).unwrap();
+        let local_standing = client.local_entity(standing).unwrap();
+        assert_eq!(get(&client_world, local_moving), Some(Team(1)));
+        assert_close(
+            get::<Body>(&client_world, local_standing).unwrap().position,
+            Vector2::new(-20.5, 33.3),
+        );
+        server.acknowledge(client.last_received().unwrap());
+
+        for tick in 2..20 {
+            server_world.query_components_mut::<Body>(|entity, body| {
+                if entity == moving {
+                    body.position += Vector2::new(1.25, -0.5);
+                }
+            });
+            let snapshot = server.snapshot(&server_world, tick);
+            assert_eq!(changed_entities(&snapshot), vec![moving.inner()]);
+            assert!(client.apply(&mut client_world, &snapshot));
+            server.acknowledge(client.last_received().unwrap());
+
+            let expected = get::<Body>(&server_world, moving).unwrap().position;
+            assert_close(
+                get::<Body>(&client_world, local_moving).unwrap().position,
+                expected,
+            );
+        }
+
+        let snapshot = server.snapshot(&server_world, 20);
+        assert!(changed_entities(&snapshot).is_empty());
+
+        server_world.delete_entity(moving);
+        let snapshot = server.snapshot(&server_world, 21);
+        assert!(client.apply(&mut client_world, &snapshot));
+        assert!(!client_world.is_alive(local_moving));
+        assert!(client_world.is_alive(local_standing));
+        assert_eq!(client.local_entity(moving), None);
+    }
+
+    #[test]
+    fn malformed_snapshot_leaves_the_world_untouched() {
+        let mut server_world = World::new();
+        let mut server = replication();
+        for i in 0..4 {
+            server_world.add_entity((Replicated, body(i as f32, 0.0)));
+        }
+        let snapshot = server.snapshot(&server_world, 1);
+
+        let mut client_world = World::new();
+        let mut client = replication();
+        for len in [0, 8, snapshot.len() / 2, snapshot.len() - 1] {
+            assert!(!client.apply(&mut client_world, &snapshot[..len]));
+        }
+        let mut trailing = snapshot.clone();
+        trailing.push(0);
+        assert!(!client.apply(&mut client_world, &trailing));
+
+        // The second entity has a component that is not registered
+        let mut writer = ReplicationWriter::default();
+        writer.u32(1);
+        writer.u32(u32::MAX);
+        writer.u32(2);
+        let mut data = ReplicationWriter::default();
+        body(1.0, 2.0).write(&mut data);
+        for (entity, id) in [(1, Body::ID), (2, 999)] {
+            writer.u64(entity);
+            writer.u16(1);
+            writer.u32(id);
+            writer.bytes(&data.data);
+            writer.u16(0);
+        }
+        writer.u32(0);
+        assert!(!client.apply(&mut client_world, &writer.data));
+
+        assert_eq!(client_world.view::<Body>().iter().count(), 0);
+        assert_eq!(client.last_received(), None);
+        assert!(client.apply(&mut client_world, &snapshot));
+        assert_eq!(client_world.view::<Body>().iter().count(), 4);
+    }
+}
@@ -1,10 +1,13 @@
+use rustc_hash::FxHashMap;
+
 use crate::{
-    ecs::Component,
+    ecs::{Component, EntityId},
     math::Isometry2,
     physics::{Collider, ColliderHandle, Physics, RigidBody, RigidBodyHandle},
 };
 
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone)]
 pub enum RigidBodyComponentStatus {
     Initialized {
         rigid_body_handle: RigidBodyHandle,
@@ -65,11 +68,21 @@ impl RigidBodyComponentStatus {
     }
 }
 
+/// Where a named collider of a [RigidBodyComponent] lives
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Component)]
+pub enum NamedCollider {
+    /// Index into the colliders of an uninitialized component
+    Pending(usize),
+    Attached(ColliderHandle),
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Component)]
 #[track(Insertion, Deletion, Removal)]
 pub struct RigidBodyComponent {
     pub status: RigidBodyComponentStatus,
+    named_colliders: FxHashMap<String, NamedCollider>,
 }
 
 impl RigidBodyComponent {
@@ -82,6 +95,36 @@ impl RigidBodyComponent {
                 rigid_body: Box::new(rigid_body.into()),
                 colliders: colliders.into_iter().map(|c| c.into()).collect(),
             },
+            named_colliders: Default::default(),
+        }
+    }
+
+    /// Adds the rigid body and its colliders to the physics world. Named colliders keep their
+    /// names.
+    pub fn init(&mut self, physics: &mut Physics, entity: EntityId) {
+        let status = std::mem::replace(
+            &mut self.status,
+            RigidBodyComponentStatus::Initialized {
+                rigid_body_handle: RigidBodyHandle::invalid(),
+            },
+        );
+        let rigid_body_handle = match status {
+            RigidBodyComponentStatus::Initialized { rigid_body_handle } => rigid_body_handle,
+            RigidBodyComponentStatus::Uninitialized {
+                rigid_body,
+                colliders,
+            } => physics.add_rigid_body(*rigid_body, colliders, &entity),
+        };
+        self.status = RigidBodyComponentStatus::Initialized { rigid_body_handle };
+        let handles = physics
+            .rigid_body(rigid_body_handle)
+            .unwrap()
+            .colliders()
+            .to_vec();
+        for named in self.named_colliders.values_mut() {
+            if let NamedCollider::Pending(index) = *named {
+                *named = NamedCollider::Attached(handles[index]);
+            }
         }
     }
 
@@ -119,4 +162,91 @@ impl RigidBodyComponent {
     ) -> Option<Collider> {
         self.status.detach_collider(physics, collider)
     }
+
+    /// Attaches a collider that can later be accessed by its name. A collider with the same
+    /// name gets replaced.
+    pub fn add_collider(
+        &mut self,
+        physics: &mut Physics,
+        name: impl Into<String>,
+        collider: impl Into<Collider>,
+    ) {
+        let name = name.into();
+        self.remove_collider(physics, &name);
+        let named = match self.status.attach_collider(physics, collider) {
+            Some(handle) => NamedCollider::Attached(handle),
+            None => match &self.status {
+                RigidBodyComponentStatus::Uninitialized { colliders, .. } => {
+                    NamedCollider::Pending(colliders.len() - 1)
+                }
+                RigidBodyComponentStatus::Initialized { .. } => return,
+            },
+        };
+        self.named_colliders.insert(name, named);
+    }
+
+    pub fn remove_collider(&mut self, physics: &mut Physics, name: &str) -> Option<Collider> {
+        match self.named_colliders.remove(name)? {
+            NamedCollider::Attached(handle) => self.status.detach_collider(physics, handle),
+            NamedCollider::Pending(index) => {
+                for named in self.named_colliders.values_mut() {
+                    if let NamedCollider::Pending(other) = named {
+                        if *other > index {
+                            *other -= 1;
+                        }
+                    }
+                }
+                match &mut self.status {
+                    RigidBodyComponentStatus::Uninitialized { colliders, .. } => {
+                        Some(colliders.remove(index))
+                    }
+                    RigidBodyComponentStatus::Initialized { .. } => None,
+                }
+            }
+        }
+    }
+
+    pub fn collider_handle(&self, name: &str) -> Option<ColliderHandle> {
+        match self.named_colliders.get(name)? {
+            NamedCollider::Attached(handle) => Some(*handle),
+            NamedCollider::Pending(_) => None,
+        }
+    }
+
+    pub fn collider_names(&self) -> impl Iterator<Item = &str> {
+        self.named_colliders.keys().map(|name| name.as_str())
+    }
+
+    pub fn collider<'a>(&'a self, physics: &'a Physics, name: &str) -> Option<&'a Collider> {
+        match (*self.named_colliders.get(name)?, &self.status) {
+            (NamedCollider::Attached(handle), _) => physics.collider(handle),
+            (
+                NamedCollider::Pending(index),
+                RigidBodyComponentStatus::Uninitialized { colliders, .. },
+            ) => colliders.get(index),
+            (NamedCollider::Pending(_), RigidBodyComponentStatus::Initialized { .. }) => None,
+        }
+    }
+
+    pub fn collider_mut<'a>(
+        &'a mut self,
+        physics: &'a mut Physics,
+        name: &str,
+    ) -> Option<&'a mut Collider> {
+        match (*self.named_colliders.get(name)?, &mut self.status) {
+            (NamedCollider::Attached(handle), _) => physics.collider_mut(handle),
+            (
+                NamedCollider::Pending(index),
+                RigidBodyComponentStatus::Uninitialized { colliders, .. },
+            ) => colliders.get_mut(index),
+            (NamedCollider::Pending(_), RigidBodyComponentStatus::Initialized { .. }) => None,
+        }
+    }
+
+    /// Disabled colliders neither collide nor show up in scene queries
+    pub fn set_collider_enabled(&mut self, physics: &mut Physics, name: &str, enabled: bool) {
+        if let Some(collider) = self.collider_mut(physics, name) {
+            collider.set_enabled(enabled);
+        }
+    }
 }
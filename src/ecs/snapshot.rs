@@ -0,0 +1,94 @@
+use shipyard::{IntoIter, IntoWithId};
+
+use crate::ecs::{Component, EntityId, World, WorldExt};
+#[cfg(feature = "physics")]
+use crate::physics::{Physics, PhysicsSnapshot};
+
+trait StorageSnapshot {
+    fn restore(&self, world: &World);
+}
+
+struct ComponentSnapshot<C> {
+    components: Vec<(EntityId, C)>,
+}
+
+impl<C: Component + Clone> StorageSnapshot for ComponentSnapshot<C> {
+    fn restore(&self, world: &World) {
+        let entities = world.entities();
+        let mut view = world.view_mut::<C>();
+        view.clear();
+        for (entity, component) in &self.components {
+            entities.add_component(*entity, &mut view, component.clone());
+        }
+    }
+}
+
+/// Copy of the physics simulation and the components of the types added with
+/// [WorldSnapshot::component]. Restoring it rewinds the scene to the moment it was taken.
+/// Components of entities that were deleted in the meantime are not restored.
+pub struct WorldSnapshot {
+    #[cfg(feature = "physics")]
+    physics: PhysicsSnapshot,
+    components: Vec<Box<dyn StorageSnapshot>>,
+}
+
+#[cfg(not(feature = "physics"))]
+impl Default for WorldSnapshot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WorldSnapshot {
+    #[cfg(feature = "physics")]
+    pub fn new(physics: &Physics) -> Self {
+        Self {
+            physics: physics.snapshot(),
+            components: Vec::new(),
+        }
+    }
+
+    #[cfg(not(feature = "physics"))]
+    pub fn new() -> Self {
+        Self {
+            components: Vec::new(),
+        }
+    }
+
+    /// Copies all components of type `C`. Components that hold physics handles like the
+    /// [RigidBodyComponent](crate::ecs::RigidBodyComponent) stay valid after restoring, since
+    /// the handles are restored together with the physics state.
+    pub fn component<C: Component + Clone>(mut self, world: &World) -> Self {
+        let view = world.view::<C>();
+        let components = view
+            .iter()
+            .with_id()
+            .map(|(entity, component)| (entity, component.clone()))
+            .collect();
+        self.components
+            .push(Box::new(ComponentSnapshot::<C> { components }));
+        self
+    }
+
+    #[cfg(feature = "physics")]
+    pub fn physics(&self) -> &PhysicsSnapshot {
+        &self.physics
+    }
+
+    #[cfg(feature = "physics")]
+    pub fn restore(&self, world: &World, physics: &mut Physics) {
+        physics.restore(&self.physics);
+        self.restore_components(world);
+    }
+
+    #[cfg(not(feature = "physics"))]
+    pub fn restore(&self, world: &World) {
+        self.restore_components(world);
+    }
+
+    fn restore_components(&self, world: &World) {
+        for snapshot in &self.components {
+            snapshot.restore(world);
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use std::{collections::VecDeque, ops::Range};
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    context::Context,
+    ecs::{Component, DisabledComponent, IterEnabled, System, WorldExt},
+    graphics::SpriteArrayIndex,
+    scene::{Plugin, SceneCreator},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnimationClip {
+    pub frames: Range<SpriteArrayIndex>,
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_fps"))]
+    fps: f32,
+    pub looping: bool,
+}
+
+impl AnimationClip {
+    pub fn new(frames: Range<SpriteArrayIndex>, fps: f32) -> Self {
+        assert!(!frames.is_empty(), "Animation clip must contain frames!");
+        assert_valid_fps(fps);
+        Self {
+            frames,
+            fps,
+            looping: true,
+        }
+    }
+
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+
+    pub fn set_fps(&mut self, fps: f32) {
+        assert_valid_fps(fps);
+        self.fps = fps;
+    }
+
+    pub fn len(&self) -> u32 {
+        self.frames.end - self.frames.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+fn is_valid_fps(fps: f32) -> bool {
+    fps.is_finite() && fps > 0.0
+}
+
+fn assert_valid_fps(fps: f32) {
+    assert!(
+        is_valid_fps(fps),
+        "Animation clip fps must be greater than 0, got {fps}!"
+    );
+}
+
+#[cfg(feature = "serde")]
+fn deserialize_fps<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    let fps = <f32 as serde::Deserialize>::deserialize(deserializer)?;
+    if is_valid_fps(fps) {
+        Ok(fps)
+    } else {
+        Err(serde::de::Error::custom(format!(
+            "Animation clip fps must be greater than 0, got {fps}!"
+        )))
+    }
+}
+
+#[derive(Component, Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpriteSheetAnimation {
+    clips: FxHashMap<String, AnimationClip>,
+    current: Option<String>,
+    queue: VecDeque<String>,
+    elapsed: f32,
+    frame: u32,
+    paused: bool,
+    finished: bool,
+}
+
+impl SpriteSheetAnimation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_clip(mut self, name: impl Into<String>, clip: AnimationClip) -> Self {
+        self.add_clip(name, clip);
+        self
+    }
+
+    pub fn add_clip(&mut self, name: impl Into<String>, clip: AnimationClip) {
+        self.clips.insert(name.into(), clip);
+    }
+
+    pub fn remove_clip(&mut self, name: &str) -> Option<AnimationClip> {
+        if self.current.as_deref() == Some(name) {
+            self.stop();
+        }
+        self.queue.retain(|queued| queued != name);
+        self.clips.remove(name)
+    }
+
+    pub fn clip(&self, name: &str) -> Option<&AnimationClip> {
+        self.clips.get(name)
+    }
+
+    /// Starts the given clip from the beginning and clears the queue.
+    pub fn play(&mut self, name: &str) -> &mut Self {
+        assert!(
+            self.clips.contains_key(name),
+            "Animation clip '{name}' does not exist!"
+        );
+        self.queue.clear();
+        self.start(name.to_string());
+        self
+    }
+
+    /// Queues a clip that is played once the current clip has finished.
+    pub fn then(&mut self, name: &str) -> &mut Self {
+        assert!(
+            self.clips.contains_key(name),
+            "Animation clip '{name}' does not exist!"
+        );
+        if self.current.is_none() {
+            self.start(name.to_string());
+        } else {
+            self.queue.push_back(name.to_string());
+        }
+        self
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.queue.clear();
+        self.elapsed = 0.0;
+        self.frame = 0;
+        self.finished = false;
+    }
+
+    pub fn update(&mut self, delta: f32) {
+        if self.paused || self.finished {
+            return;
+        }
+        let Some(mut clip) = self.current.as_ref().map(|name| &self.clips[name]) else {
+            return;
+        };
+        // Unreachable through the API, but a clip without a valid fps would never advance
+        if !is_valid_fps(clip.fps) {
+            return;
+        }
+
+        self.elapsed += delta;
+        let mut frame_time = 1.0 / clip.fps;
+        while self.elapsed >= frame_time {
+            self.elapsed -= frame_time;
+            self.frame += 1;
+            if self.frame >= clip.len() {
+                if let Some(next) = self.queue.pop_front() {
+                    clip = &self.clips[&next];
+                    if !is_valid_fps(clip.fps) {
+                        self.current = Some(next);
+                        self.frame = 0;
+                        self.elapsed = 0.0;
+                        return;
+                    }
+                    frame_time = 1.0 / clip.fps;
+                    self.current = Some(next);
+                    self.frame = 0;
+                } else if clip.looping {
+                    self.frame = 0;
+                } else {
+                    self.frame = clip.len() - 1;
+                    self.elapsed = 0.0;
+                    self.finished = true;
+                    return;
+                }
+            }
+        }
+    }
+
+    pub fn current_index(&self) -> SpriteArrayIndex {
+        self.current
+            .as_ref()
+            .map(|name| self.clips[name].frames.start + self.frame)
+            .unwrap_or(0)
+    }
+
+    pub fn current_clip(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    pub fn is_playing(&self, name: &str) -> bool {
+        self.current.as_deref() == Some(name)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Returns true if a non looping clip has reached its last frame and no clip is queued.
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    fn start(&mut self, name: String) {
+        self.current = Some(name);
+        self.elapsed = 0.0;
+        self.frame = 0;
+        self.finished = false;
+    }
+}
+
+/// Advances every [SpriteSheetAnimation] in the world each frame.
+pub struct SpriteSheetAnimationPlugin;
+
+impl Plugin for SpriteSheetAnimationPlugin {
+    fn init<S: SceneCreator>(&mut self, scene: S) -> S {
+        scene.system(System::update(update_animations))
+    }
+}
+
+fn update_animations(ctx: &mut Context) {
+    let delta = ctx.time.delta();
+    let mut animations = ctx.world.view_mut::<SpriteSheetAnimation>();
+    let disabled = ctx.world.view::<DisabledComponent>();
+    for (_, animation) in (&mut animations).iter_enabled(&disabled) {
+        animation.update(delta);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advances_with_fps() {
+        let mut animation =
+            SpriteSheetAnimation::new().with_clip("walk", AnimationClip::new(4..8, 4.0));
+        animation.play("walk");
+        animation.update(0.3);
+        assert_eq!(animation.current_index(), 5);
+        animation.update(0.75);
+        assert_eq!(animation.current_index(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "fps must be greater than 0")]
+    fn zero_fps_is_rejected() {
+        AnimationClip::new(0..4, 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "fps must be greater than 0")]
+    fn modified_clip_is_rejected() {
+        AnimationClip::new(0..4, 10.0).set_fps(f32::NAN);
+    }
+
+    #[test]
+    fn invalid_clip_does_not_advance() {
+        let mut animation =
+            SpriteSheetAnimation::new().with_clip("walk", AnimationClip::new(4..8, 4.0));
+        animation.clips.get_mut("walk").unwrap().fps = -1.0;
+        animation.play("walk");
+        animation.update(10.0);
+        assert_eq!(animation.current_index(), 4);
+        animation.clips.get_mut("walk").unwrap().fps = f32::NAN;
+        animation.update(10.0);
+        assert_eq!(animation.current_index(), 4);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialized_fps_is_validated() {
+        let clip = AnimationClip::new(0..4, 10.0);
+        let json = serde_json::to_string(&clip).unwrap();
+        assert_eq!(serde_json::from_str::<AnimationClip>(&json).unwrap(), clip);
+
+        let zero = json.replace("10.0", "0.0");
+        assert_ne!(zero, json);
+        let error = serde_json::from_str::<AnimationClip>(&zero).unwrap_err();
+        assert!(error.to_string().contains("fps must be greater than 0"));
+        assert!(serde_json::from_str::<AnimationClip>(&json.replace("10.0", "-5.0")).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use std::any::{Any, TypeId};
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    context::Context,
+    ecs::{SystemPriority, Unique, UniqueView, UniqueViewMut, UpdateSystem, World, WorldExt},
+};
+
+/// Value that describes in which state a scene is, usually an enum like
+/// `GameState::{Menu, Playing, Paused}`
+pub trait SceneState: Clone + PartialEq + Send + Sync + 'static {}
+impl<T: Clone + PartialEq + Send + Sync + 'static> SceneState for T {}
+
+type StateValue = Box<dyn Any + Send + Sync>;
+
+struct StateTransition {
+    type_id: TypeId,
+    from: Option<StateValue>,
+    to: StateValue,
+}
+
+/// Current value of every [SceneState] of a world and the transitions that still have to run
+/// their [System::on_exit](crate::ecs::System::on_exit) and
+/// [System::on_enter](crate::ecs::System::on_enter) systems
+#[derive(Unique, Default)]
+pub struct SceneStates {
+    states: FxHashMap<TypeId, StateValue>,
+    pending: Vec<StateTransition>,
+}
+
+pub trait StateExt {
+    /// Adds a state and queues its [System::on_enter](crate::ecs::System::on_enter) systems.
+    /// Replaces the state if it already exists.
+    fn insert_state<S: SceneState>(&self, state: S);
+    fn has_state<S: SceneState>(&self) -> bool;
+    /// Panics if the state has not been inserted
+    fn state<S: SceneState>(&self) -> S;
+    fn in_state<S: SceneState>(&self, state: &S) -> bool;
+    /// Changes the state immediately, the exit and enter systems run before the next update
+    /// system. Setting the current state again does nothing.
+    fn set_state<S: SceneState>(&self, state: S);
+}
+
+impl StateExt for World {
+    fn insert_state<S: SceneState>(&self, state: S) {
+        if self.borrow::<UniqueViewMut<SceneStates>>().is_err() {
+            self.add_unique(SceneStates::default());
+        }
+        let mut states = self.unique_mut::<SceneStates>();
+        let from = states.states.remove(&TypeId::of::<S>());
+        states
+            .states
+            .insert(TypeId::of::<S>(), Box::new(state.clone()));
+        states.pending.push(StateTransition {
+            type_id: TypeId::of::<S>(),
+            from,
+            to: Box::new(state),
+        });
+    }
+
+    fn has_state<S: SceneState>(&self) -> bool {
+        self.borrow::<UniqueView<SceneStates>>()
+            .is_ok_and(|states| states.states.contains_key(&TypeId::of::<S>()))
+    }
+
+    fn state<S: SceneState>(&self) -> S {
+        self.borrow::<UniqueView<SceneStates>>()
+            .ok()
+            .and_then(|states| {
+                states
+                    .states
+                    .get(&TypeId::of::<S>())
+                    .and_then(|state| state.downcast_ref::<S>())
+                    .cloned()
+            })
+            .unwrap_or_else(|| panic!("State {} does not exist!", std::any::type_name::<S>()))
+    }
+
+    fn in_state<S: SceneState>(&self, state: &S) -> bool {
+        self.borrow::<UniqueView<SceneStates>>()
+            .is_ok_and(|states| {
+                states
+                    .states
+                    .get(&TypeId::of::<S>())
+                    .and_then(|current| current.downcast_ref::<S>())
+                    .is_some_and(|current| current == state)
+            })
+    }
+
+    fn set_state<S: SceneState>(&self, state: S) {
+        if self.in_state(&state) {
+            return;
+        }
+        self.insert_state(state);
+    }
+}
+
+/// System that runs once when a [SceneState] changes, created with
+/// [System::on_enter](crate::ecs::System::on_enter) and
+/// [System::on_exit](crate::ecs::System::on_exit)
+pub struct StateSystem {
+    pub(crate) type_id: TypeId,
+    pub(crate) enter: bool,
+    pub(crate) matches: Box<dyn Fn(&dyn Any) -> bool>,
+    pub(crate) system: UpdateSystem,
+}
+
+impl StateSystem {
+    pub(crate) fn new<S: SceneState>(
+        state: S,
+        enter: bool,
+        system: impl Fn(&mut Context) + 'static,
+    ) -> Self {
+        Self {
+            type_id: TypeId::of::<S>(),
+            enter,
+            matches: Box::new(move |value| value.downcast_ref::<S>() == Some(&state)),
+            system: Box::new(system),
+        }
+    }
+}
+
+/// Runs the exit and enter systems of all pending state transitions, including the transitions
+/// caused by these systems
+pub(crate) fn run_state_systems(systems: &[(SystemPriority, StateSystem)], ctx: &mut Context) {
+    loop {
+        let pending = match ctx.world.borrow::<UniqueViewMut<SceneStates>>() {
+            Ok(mut states) if !states.pending.is_empty() => std::mem::take(&mut states.pending),
+            _ => return,
+        };
+
+        for transition in pending {
+            for (_, system) in systems
+                .iter()
+                .filter(|(_, system)| !system.enter && system.type_id == transition.type_id)
+            {
+                if let Some(from) = &transition.from {
+                    if (system.matches)(from.as_ref()) {
+                        (system.system)(ctx);
+                        ctx.apply_commands();
+                    }
+                }
+            }
+            for (_, system) in systems
+                .iter()
+                .filter(|(_, system)| system.enter && system.type_id == transition.type_id)
+            {
+                if (system.matches)(transition.to.as_ref()) {
+                    (system.system)(ctx);
+                    ctx.apply_commands();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use rustc_hash::FxHashMap;
+use shipyard::{Get, IntoIter, IntoWithId};
+
+use crate::{
+    context::Context,
+    ecs::{Component, EntityId, SceneState, System, WorldExt},
+    math::Vector2,
+    scene::{Plugin, SceneCreator},
+};
+
+/// Value of a [Blackboard] entry
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlackboardValue {
+    Bool(bool),
+    Int(i64),
+    Float(f32),
+    Vector(Vector2<f32>),
+    Entity(EntityId),
+    Text(String),
+}
+
+/// Type that can be stored in a [Blackboard]
+pub trait BlackboardType: Sized {
+    fn into_value(self) -> BlackboardValue;
+    fn from_value(value: &BlackboardValue) -> Option<Self>;
+}
+
+macro_rules! blackboard_type {
+    ($ty:ty, $variant:ident) => {
+        impl BlackboardType for $ty {
+            fn into_value(self) -> BlackboardValue {
+                BlackboardValue::$variant(self)
+            }
+
+            fn from_value(value: &BlackboardValue) -> Option<Self> {
+                match value {
+                    BlackboardValue::$variant(value) => Some(value.clone()),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+blackboard_type!(bool, Bool);
+blackboard_type!(i64, Int);
+blackboard_type!(f32, Float);
+blackboard_type!(Vector2<f32>, Vector);
+blackboard_type!(EntityId, Entity);
+blackboard_type!(String, Text);
+
+/// Typed key-value store of a [StateMachine] that its states use to share data
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Blackboard {
+    values: FxHashMap<String, BlackboardValue>,
+}
+
+impl Blackboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set<T: BlackboardType>(&mut self, key: impl Into<String>, value: T) {
+        self.values.insert(key.into(), value.into_value());
+    }
+
+    /// None if the key does not exist or holds a different type
+    pub fn get<T: BlackboardType>(&self, key: &str) -> Option<T> {
+        self.values.get(key).and_then(T::from_value)
+    }
+
+    pub fn get_or<T: BlackboardType>(&self, key: &str, default: T) -> T {
+        self.get(key).unwrap_or(default)
+    }
+
+    pub fn value(&self, key: &str) -> Option<&BlackboardValue> {
+        self.values.get(key)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<BlackboardValue> {
+        self.values.remove(key)
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+/// Current state of an entity whose behavior is defined by a [StateMachinePlugin] of the same
+/// state type. Only data is stored in the component, so a serialized state machine resumes in
+/// its state without running the enter callbacks again.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateMachine<S: SceneState> {
+    state: S,
+    previous: Option<S>,
+    requested: Option<S>,
+    entered: bool,
+    elapsed: f32,
+    pub blackboard: Blackboard,
+}
+
+impl<S: SceneState> Component for StateMachine<S> {
+    type Tracking = shipyard::track::Untracked;
+}
+
+impl<S: SceneState> StateMachine<S> {
+    /// The enter callbacks of `state` run on the first tick
+    pub fn new(state: S) -> Self {
+        Self {
+            state,
+            previous: None,
+            requested: None,
+            entered: false,
+            elapsed: 0.0,
+            blackboard: Blackboard::default(),
+        }
+    }
+
+    pub fn with_blackboard(mut self, blackboard: Blackboard) -> Self {
+        self.blackboard = blackboard;
+        self
+    }
+
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    pub fn previous(&self) -> Option<&S> {
+        self.previous.as_ref()
+    }
+
+    pub fn in_state(&self, state: &S) -> bool {
+        self.state == *state
+    }
+
+    /// Seconds since the current state was entered
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// Changes the state on the next tick, before any guarded transition is evaluated. Setting
+    /// the current state does nothing.
+    pub fn set_state(&mut self, state: S) {
+        self.requested = Some(state);
+    }
+}
+
+type StateCallback = Box<dyn Fn(&mut Context, EntityId, &mut Blackboard)>;
+type TransitionGuard = Box<dyn Fn(&Context, EntityId, &Blackboard) -> bool>;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StateHook {
+    Enter,
+    Update,
+    Exit,
+}
+
+struct GuardedTransition<S> {
+    from: Option<S>,
+    to: S,
+    guard: TransitionGuard,
+}
+
+/// Callbacks and transitions of every [StateMachine] with the state type `S`, ticked each
+/// frame by an update system. The callbacks get the blackboard of the entity, which is taken
+/// out of the component while they run.
+pub struct StateMachinePlugin<S: SceneState> {
+    hooks: Vec<(StateHook, S, StateCallback)>,
+    transitions: Vec<GuardedTransition<S>>,
+}
+
+impl<S: SceneState> Default for StateMachinePlugin<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: SceneState> StateMachinePlugin<S> {
+    pub fn new() -> Self {
+        Self {
+            hooks: Vec::new(),
+            transitions: Vec::new(),
+        }
+    }
+
+    pub fn on_enter(
+        mut self,
+        state: S,
+        callback: impl Fn(&mut Context, EntityId, &mut Blackboard) + 'static,
+    ) -> Self {
+        self.hooks
+            .push((StateHook::Enter, state, Box::new(callback)));
+        self
+    }
+
+    /// Runs every frame while the entity is in `state`
+    pub fn on_update(
+        mut self,
+        state: S,
+        callback: impl Fn(&mut Context, EntityId, &mut Blackboard) + 'static,
+    ) -> Self {
+        self.hooks
+            .push((StateHook::Update, state, Box::new(callback)));
+        self
+    }
+
+    pub fn on_exit(
+        mut self,
+        state: S,
+        callback: impl Fn(&mut Context, EntityId, &mut Blackboard) + 'static,
+    ) -> Self {
+        self.hooks
+            .push((StateHook::Exit, state, Box::new(callback)));
+        self
+    }
+
+    /// Changes from `from`, or from any state if `None`, to `to` once `guard` returns true.
+    /// Guards are evaluated after the update callbacks in the order they were added, the first
+    /// one that passes wins.
+    pub fn transition(
+        mut self,
+        from: impl Into<Option<S>>,
+        to: S,
+        guard: impl Fn(&Context, EntityId, &Blackboard) -> bool + 'static,
+    ) -> Self {
+        self.transitions.push(GuardedTransition {
+            from: from.into(),
+            to,
+            guard: Box::new(guard),
+        });
+        self
+    }
+
+    fn run(
+        &self,
+        hook: StateHook,
+        state: &S,
+        ctx: &mut Context,
+        entity: EntityId,
+        blackboard: &mut Blackboard,
+    ) {
+        for (_, _, callback) in self
+            .hooks
+            .iter()
+            .filter(|(h, s, _)| *h == hook && s == state)
+        {
+            (callback)(ctx, entity, blackboard);
+            ctx.apply_commands();
+        }
+    }
+
+    fn with_machine<R>(
+        ctx: &Context,
+        entity: EntityId,
+        f: impl FnOnce(&mut StateMachine<S>) -> R,
+    ) -> Option<R> {
+        let mut machines = ctx.world.view_mut::<StateMachine<S>>();
+        (&mut machines).get(entity).ok().map(f)
+    }
+
+    fn tick(&self, ctx: &mut Context) {
+        let delta = ctx.time.delta();
+        let entities: Vec<EntityId> = ctx
+            .world
+            .view::<StateMachine<S>>()
+            .iter()
+            .with_id()
+            .map(|(entity, _)| entity)
+            .collect();
+
+        for entity in entities {
+            if !ctx.world.is_enabled(entity) {
+                continue;
+            }
+            let Some((state, entered, mut blackboard)) =
+                Self::with_machine(ctx, entity, |machine| {
+                    (
+                        machine.state.clone(),
+                        machine.entered,
+                        std::mem::take(&mut machine.blackboard),
+                    )
+                })
+            else {
+                continue;
+            };
+
+            if !entered {
+                self.run(StateHook::Enter, &state, ctx, entity, &mut blackboard);
+            }
+            self.run(StateHook::Update, &state, ctx, entity, &mut blackboard);
+
+            let requested = Self::with_machine(ctx, entity, |machine| machine.requested.take())
+                .flatten()
+                .filter(|requested| *requested != state);
+            let next = requested.or_else(|| {
+                self.transitions
+                    .iter()
+                    .filter(|transition| {
+                        transition.to != state
+                            && transition.from.as_ref().map_or(true, |from| *from == state)
+                    })
+                    .find(|transition| (transition.guard)(&*ctx, entity, &blackboard))
+                    .map(|transition| transition.to.clone())
+            });
+            if let Some(next) = &next {
+                self.run(StateHook::Exit, &state, ctx, entity, &mut blackboard);
+                self.run(StateHook::Enter, next, ctx, entity, &mut blackboard);
+            }
+
+            Self::with_machine(ctx, entity, |machine| {
+                machine.blackboard = blackboard;
+                machine.entered = true;
+                match next {
+                    Some(next) => {
+                        machine.previous = Some(std::mem::replace(&mut machine.state, next));
+                        machine.elapsed = 0.0;
+                    }
+                    None => machine.elapsed += delta,
+                }
+            });
+        }
+    }
+}
+
+impl<S: SceneState> Plugin for StateMachinePlugin<S> {
+    fn init<C: SceneCreator>(&mut self, scene: C) -> C {
+        let machine = Self {
+            hooks: std::mem::take(&mut self.hooks),
+            transitions: std::mem::take(&mut self.transitions),
+        };
+        scene.system(System::update(move |ctx| machine.tick(ctx)))
+    }
+}
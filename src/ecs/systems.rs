@@ -1,15 +1,22 @@
+#[cfg(feature = "physics")]
+use crate::physics::CollisionHandlers;
 use crate::{
     context::{Context, RenderContext},
-    graphics::RenderEncoder,
-    time::{Duration, Instant},
+    ecs::{SceneState, StateSystem, World},
+    graphics::{AssetKey, Color, RenderEncoder},
+    scene::PluginId,
+    time::{Diagnostics, Duration, Instant, TimeManager},
 };
+use std::any::TypeId;
 
 pub type SetupSystem = Box<dyn FnOnce(&mut Context)>;
 pub type ResizeSystem = Box<dyn Fn(&mut Context)>;
 pub type UpdateSystem = Box<dyn Fn(&mut Context)>;
+pub type ParallelSystem = Box<dyn Fn(&World) + Send + Sync>;
 pub type SwitchSystem = Box<dyn Fn(&mut Context, u32)>;
 pub type RenderSystem = Box<dyn Fn(&RenderContext, &mut RenderEncoder)>;
 pub type EndSystem = Box<dyn Fn(&mut Context, EndReason)>;
+pub type LifecycleSystem = Box<dyn Fn(&mut Context)>;
 
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -36,83 +43,368 @@ impl Default for SystemPriority {
     }
 }
 
+/// Named group of render systems. Passes run in the order resolved from their [RenderPassConfig::before]
+/// and [RenderPassConfig::after] constraints, passes without constraints run in the order they
+/// were registered. [System::render] systems run in [RenderPassConfig::MAIN], which exists in
+/// every scene.
+#[derive(Clone, Debug)]
+pub struct RenderPassConfig {
+    pub name: &'static str,
+    pub before: Vec<&'static str>,
+    pub after: Vec<&'static str>,
+    /// Key of a render target loaded with
+    /// [AssetManager::load_render_target](crate::graphics::AssetManager::load_render_target)
+    /// that becomes the default target of the pass. The target stays borrowed while the pass
+    /// runs.
+    pub target: Option<AssetKey>,
+    /// Clears the target of the pass before its first system
+    pub clear: Option<Color>,
+}
+
+impl RenderPassConfig {
+    pub const MAIN: &'static str = "main";
+
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            before: Vec::new(),
+            after: Vec::new(),
+            target: None,
+            clear: None,
+        }
+    }
+
+    pub fn before(mut self, pass: &'static str) -> Self {
+        self.before.push(pass);
+        self
+    }
+
+    pub fn after(mut self, pass: &'static str) -> Self {
+        self.after.push(pass);
+        self
+    }
+
+    pub fn target(mut self, target: AssetKey) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    pub fn clear(mut self, clear: Color) -> Self {
+        self.clear = Some(clear);
+        self
+    }
+}
+
+/// Components a [ParallelSystem] reads and writes
+#[derive(Clone, Debug, Default)]
+pub struct SystemAccess {
+    reads: Vec<TypeId>,
+    writes: Vec<TypeId>,
+}
+
+impl SystemAccess {
+    /// True if one of the systems writes a component the other one reads or writes
+    pub fn conflicts(&self, other: &SystemAccess) -> bool {
+        self.writes
+            .iter()
+            .any(|id| other.reads.contains(id) || other.writes.contains(id))
+            || other.writes.iter().any(|id| self.reads.contains(id))
+    }
+}
+
 enum SystemType {
     Setup(SetupSystem),
     Update(UpdateSystem),
+    Parallel(SystemAccess, ParallelSystem),
+    State(StateSystem),
     UpdateNFrame(u64, UpdateSystem),
     UpdateAfter(Duration, UpdateSystem),
+    FixedUpdate(Duration, UpdateSystem),
     Resize(ResizeSystem),
     Switch(SwitchSystem),
-    Render(RenderSystem),
+    Render(&'static str, RenderSystem),
     End(EndSystem),
+    Suspend(LifecycleSystem),
+    Resume(LifecycleSystem),
     // TODO: Custom callable event
 }
 
 pub struct System {
     pub priority: SystemPriority,
+    pub name: &'static str,
     system_type: SystemType,
 }
 
 impl System {
     pub fn setup(system: impl FnOnce(&mut Context) + 'static) -> Self {
         Self {
+            name: std::any::type_name_of_val(&system),
             system_type: SystemType::Setup(Box::new(system)),
             priority: SystemPriority::default(),
         }
     }
     pub fn update(system: impl Fn(&mut Context) + 'static) -> Self {
         Self {
+            name: std::any::type_name_of_val(&system),
             system_type: SystemType::Update(Box::new(system)),
             priority: SystemPriority::default(),
         }
     }
+    /// Update system that only gets the [World]. Consecutive parallel systems that don't
+    /// conflict by their declared [System::reads] and [System::writes] run at the same time with
+    /// the `rayon` feature. Update systems that take the [Context] are barriers, parallel systems
+    /// run before the update systems with the same priority. Borrowing a storage that is not
+    /// declared panics if another system uses it at the same time.
+    pub fn parallel(system: impl Fn(&World) + Send + Sync + 'static) -> Self {
+        Self {
+            name: std::any::type_name_of_val(&system),
+            system_type: SystemType::Parallel(SystemAccess::default(), Box::new(system)),
+            priority: SystemPriority::default(),
+        }
+    }
+    /// Runs once when the [SceneState] `S` changes to `state`
+    pub fn on_enter<S: SceneState>(state: S, system: impl Fn(&mut Context) + 'static) -> Self {
+        Self {
+            name: std::any::type_name_of_val(&system),
+            system_type: SystemType::State(StateSystem::new(state, true, system)),
+            priority: SystemPriority::default(),
+        }
+    }
+    /// Runs once when the [SceneState] `S` changes from `state` to another value
+    pub fn on_exit<S: SceneState>(state: S, system: impl Fn(&mut Context) + 'static) -> Self {
+        Self {
+            name: std::any::type_name_of_val(&system),
+            system_type: SystemType::State(StateSystem::new(state, false, system)),
+            priority: SystemPriority::default(),
+        }
+    }
     pub fn switch(system: impl Fn(&mut Context, u32) + 'static) -> Self {
         Self {
+            name: std::any::type_name_of_val(&system),
             system_type: SystemType::Switch(Box::new(system)),
             priority: SystemPriority::default(),
         }
     }
     pub fn resize(system: impl Fn(&mut Context) + 'static) -> Self {
         Self {
+            name: std::any::type_name_of_val(&system),
             system_type: SystemType::Resize(Box::new(system)),
             priority: SystemPriority::default(),
         }
     }
     pub fn update_nframe(frame: u64, system: impl Fn(&mut Context) + 'static) -> Self {
         Self {
+            name: std::any::type_name_of_val(&system),
             system_type: SystemType::UpdateNFrame(frame, Box::new(system)),
             priority: SystemPriority::default(),
         }
     }
     pub fn update_after(duration: Duration, system: impl Fn(&mut Context) + 'static) -> Self {
         Self {
+            name: std::any::type_name_of_val(&system),
             system_type: SystemType::UpdateAfter(duration, Box::new(system)),
             priority: SystemPriority::default(),
         }
     }
+    /// Runs `tick_rate` times per second. Panics if `tick_rate` is 0.
+    pub fn fixed_update(system: impl Fn(&mut Context) + 'static, tick_rate: u32) -> Self {
+        Self {
+            name: std::any::type_name_of_val(&system),
+            system_type: SystemType::FixedUpdate(tick_interval(tick_rate), Box::new(system)),
+            priority: SystemPriority::default(),
+        }
+    }
     pub fn render(system: impl Fn(&RenderContext, &mut RenderEncoder) + 'static) -> Self {
+        Self::render_in(RenderPassConfig::MAIN, system)
+    }
+    /// Render system that runs in the pass registered with
+    /// [SceneCreator::render_pass](crate::scene::SceneCreator::render_pass)
+    pub fn render_in(
+        pass: &'static str,
+        system: impl Fn(&RenderContext, &mut RenderEncoder) + 'static,
+    ) -> Self {
         Self {
-            system_type: SystemType::Render(Box::new(system)),
+            name: std::any::type_name_of_val(&system),
+            system_type: SystemType::Render(pass, Box::new(system)),
             priority: SystemPriority::default(),
         }
     }
     pub fn end(system: impl Fn(&mut Context, EndReason) + 'static) -> Self {
         Self {
+            name: std::any::type_name_of_val(&system),
             system_type: SystemType::End(Box::new(system)),
             priority: SystemPriority::default(),
         }
     }
+    /// Runs when the app goes to the background, e.g. to pause audio and save the game. On
+    /// Android the surface is destroyed afterwards and no systems run until the app resumes.
+    pub fn suspend(system: impl Fn(&mut Context) + 'static) -> Self {
+        Self {
+            name: std::any::type_name_of_val(&system),
+            system_type: SystemType::Suspend(Box::new(system)),
+            priority: SystemPriority::default(),
+        }
+    }
+    /// Runs when a suspended app comes back, after the surface and the window sized render
+    /// targets are recreated
+    pub fn resume(system: impl Fn(&mut Context) + 'static) -> Self {
+        Self {
+            name: std::any::type_name_of_val(&system),
+            system_type: SystemType::Resume(Box::new(system)),
+            priority: SystemPriority::default(),
+        }
+    }
+
+    /// Name shown in the [Diagnostics] overlay, defaults to the name of the function
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
 
     pub fn priority(mut self, priority: SystemPriority) -> Self {
         self.priority = priority;
         self
     }
+
+    /// Skips the system when `condition` is false, checked before every run. Multiple conditions
+    /// all have to be true.
+    pub fn run_if(mut self, condition: impl Fn(&Context) -> bool + 'static) -> Self {
+        self.system_type = match self.system_type {
+            SystemType::Setup(system) => SystemType::Setup(Box::new(move |ctx| {
+                if condition(ctx) {
+                    (system)(ctx)
+                }
+            })),
+            SystemType::Update(system) => SystemType::Update(conditional(condition, system)),
+            SystemType::UpdateNFrame(frame, system) => {
+                SystemType::UpdateNFrame(frame, conditional(condition, system))
+            }
+            SystemType::UpdateAfter(duration, system) => {
+                SystemType::UpdateAfter(duration, conditional(condition, system))
+            }
+            SystemType::FixedUpdate(duration, system) => {
+                SystemType::FixedUpdate(duration, conditional(condition, system))
+            }
+            SystemType::Resize(system) => SystemType::Resize(conditional(condition, system)),
+            SystemType::State(mut state) => {
+                state.system = conditional(condition, state.system);
+                SystemType::State(state)
+            }
+            SystemType::Switch(system) => SystemType::Switch(Box::new(move |ctx, last_id| {
+                if condition(ctx) {
+                    (system)(ctx, last_id)
+                }
+            })),
+            SystemType::End(system) => SystemType::End(Box::new(move |ctx, reason| {
+                if condition(ctx) {
+                    (system)(ctx, reason)
+                }
+            })),
+            SystemType::Suspend(system) => SystemType::Suspend(conditional(condition, system)),
+            SystemType::Resume(system) => SystemType::Resume(conditional(condition, system)),
+            SystemType::Render(_) | SystemType::Parallel(..) => {
+                panic!("Only systems that take the Context can have run conditions!")
+            }
+        };
+        self
+    }
+
+    /// Declares that a [System::parallel] system reads `C`. Other systems never run at the same
+    /// time, so this does nothing for them.
+    pub fn reads<C: 'static>(mut self) -> Self {
+        if let Some(access) = self.access_mut() {
+            access.reads.push(TypeId::of::<C>());
+        }
+        self
+    }
+
+    /// Declares that a [System::parallel] system writes `C`, does nothing for other systems
+    pub fn writes<C: 'static>(mut self) -> Self {
+        if let Some(access) = self.access_mut() {
+            access.writes.push(TypeId::of::<C>());
+        }
+        self
+    }
+
+    /// Components the system reads and writes, [None] if it is not a [System::parallel] system
+    pub fn access(&self) -> Option<&SystemAccess> {
+        match &self.system_type {
+            SystemType::Parallel(access, _) => Some(access),
+            _ => None,
+        }
+    }
+
+    fn access_mut(&mut self) -> Option<&mut SystemAccess> {
+        match &mut self.system_type {
+            SystemType::Parallel(access, _) => Some(access),
+            _ => None,
+        }
+    }
+}
+
+/// Time between two ticks of a fixed update running `tick_rate` times per second
+pub(crate) fn tick_interval(tick_rate: u32) -> Duration {
+    assert!(
+        tick_rate > 0,
+        "Fixed update tick rate must be greater than 0"
+    );
+    Duration::from_secs_f64(1.0 / tick_rate as f64)
+}
+
+/// Adds the frame time to the `accumulator` of a fixed update system and returns how many
+/// ticks are due, at most [TimeManager::MAX_FIXED_TICKS]. The time of the ticks beyond that is
+/// dropped to avoid spiraling after a long hitch. Updates the fixed delta and alpha of `time`.
+pub(crate) fn fixed_ticks(
+    time: &TimeManager,
+    accumulator: &mut Duration,
+    fixed_delta: Duration,
+) -> u32 {
+    *accumulator += time.delta_duration();
+    let mut ticks = 0;
+    while *accumulator >= fixed_delta && ticks < TimeManager::MAX_FIXED_TICKS {
+        *accumulator -= fixed_delta;
+        ticks += 1;
+    }
+    if *accumulator >= fixed_delta {
+        *accumulator =
+            Duration::from_secs_f64(accumulator.as_secs_f64() % fixed_delta.as_secs_f64());
+    }
+    time.set_fixed_delta(fixed_delta);
+    time.set_alpha(
+        fixed_delta,
+        accumulator.as_secs_f32() / fixed_delta.as_secs_f32(),
+    );
+    ticks
+}
+
+/// Measures the system while the [Diagnostics] overlay is shown
+fn timed(name: &'static str, system: UpdateSystem) -> UpdateSystem {
+    Box::new(move |ctx| {
+        let start = ctx.diagnostics.start();
+        (system)(ctx);
+        ctx.diagnostics.stop(name, start);
+    })
+}
+
+fn conditional(
+    condition: impl Fn(&Context) -> bool + 'static,
+    system: UpdateSystem,
+) -> UpdateSystem {
+    Box::new(move |ctx| {
+        if condition(ctx) {
+            (system)(ctx)
+        }
+    })
 }
 
 pub enum UpdateOperation {
     EveryFrame,
     EveryNFrame(u64),
     UpdaterAfter(Instant, Duration),
+    FixedUpdate {
+        accumulator: Duration,
+        fixed_delta: Duration,
+    },
 }
 
 #[derive(Default)]
@@ -121,8 +413,18 @@ pub struct SystemManager {
     pub switch_systems: Vec<(SystemPriority, SwitchSystem)>,
     pub resize_systems: Vec<(SystemPriority, ResizeSystem)>,
     pub update_systems: Vec<(SystemPriority, (UpdateOperation, UpdateSystem))>,
+    pub parallel_systems: Vec<(SystemPriority, (SystemAccess, &'static str, ParallelSystem))>,
+    pub state_systems: Vec<(SystemPriority, StateSystem)>,
     pub end_systems: Vec<(SystemPriority, EndSystem)>,
-    pub render_systems: Vec<(SystemPriority, RenderSystem)>,
+    pub suspend_systems: Vec<(SystemPriority, LifecycleSystem)>,
+    pub resume_systems: Vec<(SystemPriority, LifecycleSystem)>,
+    pub render_passes: Vec<RenderPassConfig>,
+    pub render_systems: Vec<(SystemPriority, &'static str, RenderSystem)>,
+    plugins: Vec<(PluginId, Vec<PluginId>)>,
+    plugin_finish_systems: Vec<SetupSystem>,
+    plugin_cleanup_systems: Vec<EndSystem>,
+    #[cfg(feature = "physics")]
+    pub collision_handlers: CollisionHandlers,
 }
 
 impl SystemManager {
@@ -135,31 +437,457 @@ impl SystemManager {
         self.switch_systems.sort_by_key(|e| e.0);
         self.resize_systems.sort_by_key(|e| e.0);
         self.update_systems.sort_by_key(|e| e.0);
+        self.parallel_systems.sort_by_key(|e| e.0);
+        self.state_systems.sort_by_key(|e| e.0);
         self.end_systems.sort_by_key(|e| e.0);
-        self.render_systems.sort_by_key(|e| e.0);
+        self.suspend_systems.sort_by_key(|e| e.0);
+        self.resume_systems.sort_by_key(|e| e.0);
+        // Plugin hooks run after all other setup and end systems
+        self.setup_systems.extend(
+            self.plugin_finish_systems
+                .drain(..)
+                .map(|finish| (SystemPriority::LAST, finish)),
+        );
+        self.end_systems.extend(
+            self.plugin_cleanup_systems
+                .drain(..)
+                .map(|cleanup| (SystemPriority::LAST, cleanup)),
+        );
+        for (plugin, dependencies) in &self.plugins {
+            for dependency in dependencies {
+                assert!(
+                    self.plugins.iter().any(|(id, _)| id == dependency),
+                    "Plugin {} requires the plugin {} which is not added to the scene!",
+                    plugin.name(),
+                    dependency.name()
+                );
+            }
+        }
+
+        if !self
+            .render_passes
+            .iter()
+            .any(|pass| pass.name == RenderPassConfig::MAIN)
+        {
+            self.render_passes
+                .insert(0, RenderPassConfig::new(RenderPassConfig::MAIN));
+        }
+        self.render_passes = resolve_render_passes(std::mem::take(&mut self.render_passes));
+        let passes = &self.render_passes;
+        self.render_systems
+            .sort_by_cached_key(|(priority, name, _)| {
+                let pass = passes
+                    .iter()
+                    .position(|pass| pass.name == *name)
+                    .unwrap_or_else(|| panic!("Render pass '{name}' is not registered!"));
+                (pass, *priority)
+            });
+    }
+
+    pub(crate) fn register_plugin(&mut self, plugin: PluginId, dependencies: Vec<PluginId>) {
+        assert!(
+            !self.plugins.iter().any(|(id, _)| *id == plugin),
+            "Plugin {} is already added to the scene!",
+            plugin.name()
+        );
+        self.plugins.push((plugin, dependencies));
+    }
+
+    pub(crate) fn register_plugin_finish(&mut self, finish: SetupSystem) {
+        self.plugin_finish_systems.push(finish);
+    }
+
+    pub(crate) fn register_plugin_cleanup(&mut self, cleanup: EndSystem) {
+        self.plugin_cleanup_systems.push(cleanup);
+    }
+
+    pub fn has_plugin(&self, plugin: PluginId) -> bool {
+        self.plugins.iter().any(|(id, _)| *id == plugin)
+    }
+
+    pub fn register_render_pass(&mut self, pass: RenderPassConfig) {
+        assert!(
+            !self.render_passes.iter().any(|p| p.name == pass.name),
+            "Render pass '{}' is already registered!",
+            pass.name
+        );
+        self.render_passes.push(pass);
+    }
+
+    /// Runs the render systems pass by pass
+    pub(crate) fn render(&self, ctx: &RenderContext, encoder: &mut RenderEncoder) {
+        let mut systems = self.render_systems.iter().peekable();
+        for pass in &self.render_passes {
+            let mut run = |encoder: &mut RenderEncoder<'_>| {
+                let label = std::mem::replace(&mut encoder.profiler_label, pass.name);
+                if let Some(clear) = pass.clear {
+                    encoder.renderer2d(Some(clear));
+                }
+                while let Some((_, _, render)) = systems.next_if(|(_, name, _)| *name == pass.name)
+                {
+                    (render)(ctx, encoder);
+                }
+                encoder.profiler_label = label;
+            };
+
+            match pass.target {
+                Some(target) => {
+                    let target = ctx.assets.render_target(target);
+                    encoder.flush();
+                    let mut pass_encoder = RenderEncoder::new(
+                        encoder.gpu,
+                        encoder.assets,
+                        encoder.default_assets,
+                        &*target,
+                    );
+                    run(&mut pass_encoder);
+                    pass_encoder.finish();
+                }
+                None => run(encoder),
+            }
+        }
     }
 
     pub fn register_system(&mut self, system: System) {
+        let name = system.name;
         match system.system_type {
-            SystemType::Update(update) => self
-                .update_systems
-                .push((system.priority, (UpdateOperation::EveryFrame, update))),
+            SystemType::Update(update) => self.update_systems.push((
+                system.priority,
+                (UpdateOperation::EveryFrame, timed(name, update)),
+            )),
             SystemType::UpdateNFrame(frame, update) => self.update_systems.push((
                 system.priority,
-                (UpdateOperation::EveryNFrame(frame), update),
+                (UpdateOperation::EveryNFrame(frame), timed(name, update)),
             )),
             SystemType::UpdateAfter(duration, update) => self.update_systems.push((
                 system.priority,
                 (
                     UpdateOperation::UpdaterAfter(Instant::now(), duration),
-                    update,
+                    timed(name, update),
                 ),
             )),
-            SystemType::Render(render) => self.render_systems.push((system.priority, render)),
+            SystemType::FixedUpdate(fixed_delta, update) => self.update_systems.push((
+                system.priority,
+                (
+                    UpdateOperation::FixedUpdate {
+                        accumulator: Duration::ZERO,
+                        fixed_delta,
+                    },
+                    timed(name, update),
+                ),
+            )),
+            SystemType::Parallel(access, parallel) => self
+                .parallel_systems
+                .push((system.priority, (access, name, parallel))),
+            SystemType::State(mut state) => {
+                state.system = timed(name, state.system);
+                self.state_systems.push((system.priority, state))
+            }
+            SystemType::Render(pass, render) => self.render_systems.push((
+                system.priority,
+                pass,
+                Box::new(move |ctx, encoder| {
+                    let start = ctx.diagnostics.start();
+                    (render)(ctx, encoder);
+                    ctx.diagnostics.stop(name, start);
+                }),
+            )),
             SystemType::End(end) => self.end_systems.push((system.priority, end)),
-            SystemType::Resize(resize) => self.resize_systems.push((system.priority, resize)),
+            SystemType::Suspend(suspend) => self.suspend_systems.push((system.priority, suspend)),
+            SystemType::Resume(resume) => self.resume_systems.push((system.priority, resume)),
+            SystemType::Resize(resize) => self
+                .resize_systems
+                .push((system.priority, timed(name, resize))),
             SystemType::Setup(setup) => self.setup_systems.push((system.priority, setup)),
             SystemType::Switch(switch) => self.switch_systems.push((system.priority, switch)),
         }
     }
 }
+
+/// Orders the passes so every pass runs after the passes it has to follow, otherwise the
+/// registration order is kept
+fn resolve_render_passes(passes: Vec<RenderPassConfig>) -> Vec<RenderPassConfig> {
+    let index = |name: &str, by: &str| {
+        passes
+            .iter()
+            .position(|pass| pass.name == name)
+            .unwrap_or_else(|| {
+                panic!("Render pass '{by}' is ordered relative to the unknown pass '{name}'!")
+            })
+    };
+    let mut dependencies: Vec<Vec<usize>> = vec![Vec::new(); passes.len()];
+    for (i, pass) in passes.iter().enumerate() {
+        for after in &pass.after {
+            dependencies[i].push(index(after, pass.name));
+        }
+        for before in &pass.before {
+            dependencies[index(before, pass.name)].push(i);
+        }
+    }
+
+    let mut order = Vec::with_capacity(passes.len());
+    let mut done = vec![false; passes.len()];
+    while order.len() < passes.len() {
+        let next = (0..passes.len())
+            .find(|&i| !done[i] && dependencies[i].iter().all(|&d| done[d]))
+            .unwrap_or_else(|| {
+                let cycle: Vec<&str> = (0..passes.len())
+                    .filter(|&i| !done[i])
+                    .map(|i| passes[i].name)
+                    .collect();
+                panic!(
+                    "The ordering of the render passes {} forms a cycle!",
+                    cycle.join(", ")
+                )
+            });
+        done[next] = true;
+        order.push(next);
+    }
+
+    let mut passes: Vec<Option<RenderPassConfig>> = passes.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .map(|i| passes[i].take().unwrap())
+        .collect()
+}
+
+/// Runs the systems in order, consecutive systems without conflicts are run at the same time.
+/// A panic in one of the systems is resumed once all systems of its batch are done.
+pub(crate) fn run_parallel_systems(
+    world: &World,
+    diagnostics: &Diagnostics,
+    systems: &[(SystemPriority, (SystemAccess, &'static str, ParallelSystem))],
+) {
+    let mut batch: Vec<&(SystemAccess, &'static str, ParallelSystem)> = Vec::new();
+    for (_, system) in systems {
+        if batch.iter().any(|(access, ..)| access.conflicts(&system.0)) {
+            run_batch(world, diagnostics, &batch);
+            batch.clear();
+        }
+        batch.push(system);
+    }
+    run_batch(world, diagnostics, &batch);
+}
+
+fn run_batch(
+    world: &World,
+    diagnostics: &Diagnostics,
+    batch: &[&(SystemAccess, &'static str, ParallelSystem)],
+) {
+    let run = |(_, name, system): &(SystemAccess, &'static str, ParallelSystem)| {
+        let start = diagnostics.start();
+        (system)(world);
+        diagnostics.stop(*name, start);
+    };
+
+    #[cfg(feature = "rayon")]
+    if batch.len() > 1 {
+        rayon::scope(|scope| {
+            for system in batch {
+                scope.spawn(move |_| run(system));
+            }
+        });
+        return;
+    }
+
+    for system in batch {
+        run(system);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shipyard::IntoIter;
+
+    use super::*;
+    use crate::ecs::{Component, WorldExt};
+
+    #[test]
+    fn fixed_ticks_are_capped() {
+        let fixed_delta = tick_interval(50);
+        let mut time = TimeManager::new();
+        let mut accumulator = Duration::ZERO;
+
+        time.tick_by(Duration::from_millis(50));
+        assert_eq!(fixed_ticks(&time, &mut accumulator, fixed_delta), 2);
+        assert_eq!(accumulator, Duration::from_millis(10));
+        assert!((time.alpha() - 0.5).abs() < 1e-4);
+
+        // A one second hitch only runs the maximum amount of ticks and drops the rest
+        time.tick_by(Duration::from_secs(1));
+        assert_eq!(
+            fixed_ticks(&time, &mut accumulator, fixed_delta),
+            TimeManager::MAX_FIXED_TICKS
+        );
+        assert!(accumulator < fixed_delta);
+        assert!((0.0..1.0).contains(&time.alpha()));
+
+        time.tick_by(Duration::ZERO);
+        assert_eq!(fixed_ticks(&time, &mut accumulator, fixed_delta), 0);
+    }
+
+    #[test]
+    fn tick_interval_of_rate() {
+        assert_eq!(tick_interval(1), Duration::from_secs(1));
+        assert_eq!(tick_interval(50), Duration::from_millis(20));
+    }
+
+    #[test]
+    #[should_panic(expected = "tick rate must be greater than 0")]
+    fn zero_tick_rate_is_rejected() {
+        System::fixed_update(|_| {}, 0);
+    }
+
+    #[test]
+    fn access_of_other_systems_is_ignored() {
+        let update = System::update(|_| {}).reads::<A>().writes::<A>();
+        assert!(update.access().is_none());
+
+        let parallel = System::parallel(|_| {}).reads::<A>();
+        let writer = System::parallel(|_| {}).writes::<A>();
+        assert!(parallel
+            .access()
+            .unwrap()
+            .conflicts(writer.access().unwrap()));
+    }
+
+    macro_rules! values {
+        ($($name:ident),*) => {
+            $(
+                #[derive(Component)]
+                struct $name(f32);
+            )*
+        };
+    }
+
+    values!(A, B, C, D);
+
+    const ENTITIES: usize = 100_000;
+    const RUNS: u32 = 20;
+
+    fn work<T: Component>(world: &World, value: impl Fn(&mut T) -> &mut f32 + Send + Sync) {
+        for component in (&mut world.view_mut::<T>()).iter() {
+            let value = value(component);
+            *value = (*value * 1.0001 + 0.5).sqrt();
+        }
+    }
+
+    fn scheduled(
+        system: System,
+        conflicting: bool,
+    ) -> (SystemPriority, (SystemAccess, &'static str, ParallelSystem)) {
+        // Every system also writing `A` puts each one in its own batch
+        let system = if conflicting {
+            system.writes::<A>()
+        } else {
+            system
+        };
+        match system.system_type {
+            SystemType::Parallel(access, run) => (system.priority, (access, system.name, run)),
+            _ => unreachable!(),
+        }
+    }
+
+    fn time_systems(world: &World, conflicting: bool) -> Duration {
+        let systems = [
+            System::parallel(|world| work::<A>(world, |a| &mut a.0)).writes::<A>(),
+            System::parallel(|world| work::<B>(world, |b| &mut b.0)).writes::<B>(),
+            System::parallel(|world| work::<C>(world, |c| &mut c.0)).writes::<C>(),
+            System::parallel(|world| work::<D>(world, |d| &mut d.0)).writes::<D>(),
+        ]
+        .into_iter()
+        .map(|system| scheduled(system, conflicting))
+        .collect::<Vec<_>>();
+        let diagnostics = Diagnostics::new();
+
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            run_parallel_systems(world, &diagnostics, &systems);
+        }
+        start.elapsed() / RUNS
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture parallel_scheduling`
+    #[test]
+    #[ignore]
+    fn bench_parallel_scheduling() {
+        let world = || {
+            let mut world = World::new();
+            world.bulk_add_entity((0..ENTITIES).map(|i| {
+                let value = i as f32;
+                (A(value), B(value), C(value), D(value))
+            }));
+            world
+        };
+        let values = |world: &World| {
+            let (a, b, c, d) = (
+                world.view::<A>(),
+                world.view::<B>(),
+                world.view::<C>(),
+                world.view::<D>(),
+            );
+            (&a, &b, &c, &d)
+                .iter()
+                .map(|(a, b, c, d)| [a.0, b.0, c.0, d.0].map(f32::to_bits))
+                .collect::<Vec<_>>()
+        };
+
+        let (sequential_world, parallel_world) = (world(), world());
+        let sequential = time_systems(&sequential_world, true);
+        let parallel = time_systems(&parallel_world, false);
+        println!("sequential: {sequential:?}, parallel: {parallel:?}");
+
+        assert!(values(&sequential_world) == values(&parallel_world));
+        #[cfg(feature = "rayon")]
+        if rayon::current_num_threads() > 1 {
+            assert!(
+                parallel < sequential,
+                "Batching the systems did not make them faster"
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "System C failed")]
+    fn panic_in_parallel_batch_propagates() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            mpsc, Arc,
+        };
+
+        let finished = Arc::new(AtomicUsize::new(0));
+        let counting = |finished: &Arc<AtomicUsize>| {
+            let finished = finished.clone();
+            System::parallel(move |_| {
+                finished.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        // No conflicts, so all of them run in one batch
+        let systems = [
+            counting(&finished).writes::<A>(),
+            counting(&finished).writes::<B>(),
+            System::parallel(|_| panic!("System C failed")).writes::<C>(),
+            counting(&finished).writes::<D>(),
+        ]
+        .into_iter()
+        .map(|system| scheduled(system, false))
+        .collect::<Vec<_>>();
+
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                run_parallel_systems(&World::new(), &Diagnostics::new(), &systems)
+            }));
+            let _ = sender.send(result);
+        });
+        let result = receiver
+            .recv_timeout(Duration::from_secs(10))
+            .expect("Parallel batch deadlocked after a system panicked");
+        if let Err(panic) = result {
+            // Without rayon the systems after the panicking one don't run
+            #[cfg(feature = "rayon")]
+            assert_eq!(finished.load(Ordering::SeqCst), 3);
+            std::panic::resume_unwind(panic);
+        }
+    }
+}
@@ -1,5 +1,8 @@
 pub use shipyard;
 use parking_lot::Mutex;
+use shipyard::{IntoIter, IntoWithId};
+
+use crate::ecs::DisabledComponent;
 
 pub use shipyard::{
     error, ARef, ARefMut, AllStorages, AllStoragesView, AllStoragesViewMut, AsLabel, BulkAddEntity,
@@ -20,6 +23,13 @@ pub trait WorldExt {
     fn entities_mut(&self) -> EntitiesViewMut;
     fn unique<C: Unique>(&self) -> UniqueView<C>;
     fn unique_mut<C: Unique>(&self) -> UniqueViewMut<C>;
+    /// False if the entity has been deleted, even if its index is reused by a new entity
+    fn is_alive(&self, entity: EntityId) -> bool;
+    /// False if the entity has a [DisabledComponent]
+    fn is_enabled(&self, entity: EntityId) -> bool;
+    /// Calls `f` for every entity with the component `C`, independent of its other components
+    fn query_components<C: Component>(&self, f: impl FnMut(EntityId, &C));
+    fn query_components_mut<C: Component>(&self, f: impl FnMut(EntityId, &mut C));
 }
 
 impl WorldExt for World {
@@ -46,4 +56,61 @@ impl WorldExt for World {
     fn unique_mut<C: Unique>(&self) -> UniqueViewMut<C> {
         self.borrow::<UniqueViewMut<C>>().unwrap()
     }
+
+    fn is_alive(&self, entity: EntityId) -> bool {
+        self.entities().is_alive(entity)
+    }
+
+    fn is_enabled(&self, entity: EntityId) -> bool {
+        !self.view::<DisabledComponent>().contains(entity)
+    }
+
+    fn query_components<C: Component>(&self, mut f: impl FnMut(EntityId, &C)) {
+        for (entity, component) in self.view::<C>().iter().with_id() {
+            (f)(entity, component);
+        }
+    }
+
+    fn query_components_mut<C: Component>(&self, mut f: impl FnMut(EntityId, &mut C)) {
+        let mut components = self.view_mut::<C>();
+        let entities = components
+            .iter()
+            .with_id()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in entities {
+            (f)(entity, &mut components[entity]);
+        }
+    }
+}
+
+/// Iterates the components of all entities without a [DisabledComponent], which is what every
+/// instance writer and plugin of shura uses
+pub trait IterEnabled<'a, T> {
+    fn iter_enabled(
+        self,
+        disabled: &'a View<DisabledComponent>,
+    ) -> impl Iterator<Item = (EntityId, T)> + 'a;
+}
+
+impl<'a, 'v, C: Component> IterEnabled<'a, &'a C> for &'a View<'v, C> {
+    fn iter_enabled(
+        self,
+        disabled: &'a View<DisabledComponent>,
+    ) -> impl Iterator<Item = (EntityId, &'a C)> + 'a {
+        self.iter()
+            .with_id()
+            .filter(move |(entity, _)| !disabled.contains(*entity))
+    }
+}
+
+impl<'a, 'v, C: Component> IterEnabled<'a, &'a mut C> for &'a mut ViewMut<'v, C> {
+    fn iter_enabled(
+        self,
+        disabled: &'a View<DisabledComponent>,
+    ) -> impl Iterator<Item = (EntityId, &'a mut C)> + 'a {
+        self.iter()
+            .with_id()
+            .filter(move |(entity, _)| !disabled.contains(*entity))
+    }
 }
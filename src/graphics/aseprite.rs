@@ -0,0 +1,248 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use crate::{
+    graphics::{AnimationFrame, SpriteArrayBuilder, SpriteArrayIndex},
+    math::Vector2,
+    time::Duration,
+};
+
+#[derive(Deserialize)]
+struct AsepriteRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct AsepriteSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteFrame {
+    frame: AsepriteRect,
+    #[serde(default)]
+    rotated: bool,
+    #[serde(default)]
+    trimmed: bool,
+    sprite_source_size: AsepriteRect,
+    source_size: AsepriteSize,
+    duration: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AsepriteFrames {
+    Array(Vec<AsepriteFrame>),
+    Hash(serde_json::Map<String, serde_json::Value>),
+}
+
+#[derive(Deserialize)]
+struct AsepriteTag {
+    name: String,
+    from: u32,
+    to: u32,
+    #[serde(default)]
+    direction: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteMeta {
+    #[serde(default)]
+    frame_tags: Vec<AsepriteTag>,
+}
+
+#[derive(Deserialize)]
+struct AsepriteSheet {
+    frames: AsepriteFrames,
+    meta: AsepriteMeta,
+}
+
+impl<'a> SpriteArrayBuilder<'a, image::RgbaImage> {
+    pub fn aseprite_resource(json_path: &str, image_path: &str) -> Result<Self> {
+        let resources = crate::app::global_resources();
+        let json = resources.load_bytes(json_path)?;
+        let image = resources.load_bytes(image_path)?;
+        Self::aseprite(&json, &image)
+    }
+
+    /// Load a sprite sheet exported by Aseprite. Both the "array" and "hash" layouts are supported.
+    /// Every tag is added as animation with the frame durations from the export.
+    pub fn aseprite(json: &[u8], image: &[u8]) -> Result<Self> {
+        let sheet: AsepriteSheet =
+            serde_json::from_slice(json).context("Invalid Aseprite json!")?;
+        let frames = match sheet.frames {
+            AsepriteFrames::Array(frames) => frames,
+            AsepriteFrames::Hash(frames) => frames
+                .into_iter()
+                .map(|(name, frame)| {
+                    serde_json::from_value(frame)
+                        .with_context(|| format!("Invalid Aseprite frame '{name}'!"))
+                })
+                .collect::<Result<Vec<AsepriteFrame>>>()?,
+        };
+        if frames.is_empty() {
+            bail!("Aseprite sheet does not contain any frames!");
+        }
+
+        let mut image = image::load_from_memory(image)
+            .context("Invalid Aseprite image!")?
+            .to_rgba8();
+        let sprite_size = Vector2::new(
+            frames.iter().map(|f| f.source_size.w).max().unwrap(),
+            frames.iter().map(|f| f.source_size.h).max().unwrap(),
+        );
+
+        let mut data = Vec::with_capacity(frames.len());
+        for (i, frame) in frames.iter().enumerate() {
+            let rect = &frame.frame;
+            // Rotated frames are stored rotated by 90 degrees clockwise with swapped dimensions
+            let (width, height) = if frame.rotated {
+                (rect.h, rect.w)
+            } else {
+                (rect.w, rect.h)
+            };
+            if rect.x + width > image.width() || rect.y + height > image.height() {
+                bail!("Aseprite frame {i} is outside of the image!");
+            }
+
+            let mut cropped =
+                image::imageops::crop(&mut image, rect.x, rect.y, width, height).to_image();
+            if frame.rotated {
+                cropped = image::imageops::rotate270(&cropped);
+            }
+
+            let mut sprite = image::RgbaImage::new(sprite_size.x, sprite_size.y);
+            let (x, y) = if frame.trimmed {
+                (frame.sprite_source_size.x, frame.sprite_source_size.y)
+            } else {
+                (0, 0)
+            };
+            image::imageops::overlay(&mut sprite, &cropped, x as i64, y as i64);
+            data.push(sprite);
+        }
+
+        let mut builder = Self {
+            label: None,
+            sprite_size,
+            sprite_amount: Vector2::new(frames.len() as u32, 1),
+            sampler: Self::DEFAULT_SAMPLER,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            data,
+            animations: Default::default(),
+        };
+
+        for tag in sheet.meta.frame_tags {
+            if tag.from > tag.to || tag.to as usize >= frames.len() {
+                return Err(anyhow!(
+                    "Aseprite tag '{}' has an invalid frame range {}..={}!",
+                    tag.name,
+                    tag.from,
+                    tag.to
+                ));
+            }
+            let forward = tag.from..=tag.to;
+            let indices: Vec<SpriteArrayIndex> = match tag.direction.as_str() {
+                "reverse" => forward.rev().collect(),
+                "pingpong" => forward
+                    .clone()
+                    .chain(
+                        forward
+                            .rev()
+                            .skip(1)
+                            .take((tag.to - tag.from).saturating_sub(1) as usize),
+                    )
+                    .collect(),
+                "forward" | "" => forward.collect(),
+                direction => bail!(
+                    "Aseprite tag '{}' has unknown direction '{direction}'!",
+                    tag.name
+                ),
+            };
+            let animation = indices
+                .into_iter()
+                .map(|index| AnimationFrame {
+                    index,
+                    duration: Duration::from_millis(frames[index as usize].duration),
+                })
+                .collect();
+            builder.animations.insert(tag.name, animation);
+        }
+
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSON: &[u8] = crate::include_resource_bytes!("tests/aseprite/sheet.json");
+    const IMAGE: &[u8] = crate::include_resource_bytes!("tests/aseprite/sheet.png");
+
+    fn frames(durations: &[(SpriteArrayIndex, u64)]) -> Vec<AnimationFrame> {
+        durations
+            .iter()
+            .map(|(index, duration)| AnimationFrame {
+                index: *index,
+                duration: Duration::from_millis(*duration),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn array_sheet() {
+        let sheet = SpriteArrayBuilder::aseprite(JSON, IMAGE).unwrap();
+        assert_eq!(sheet.sprite_size, Vector2::new(4, 4));
+        assert_eq!(sheet.sprite_amount, Vector2::new(3, 1));
+        assert_eq!(sheet.data.len(), 3);
+
+        assert_eq!(sheet.data[0].get_pixel(3, 3).0, [255, 0, 0, 255]);
+        assert_eq!(sheet.data[1].get_pixel(0, 0).0, [0, 255, 0, 255]);
+        // The trimmed frame is placed at its source offset
+        assert_eq!(sheet.data[2].get_pixel(0, 0).0, [0, 0, 0, 0]);
+        assert_eq!(sheet.data[2].get_pixel(1, 1).0, [0, 0, 255, 255]);
+        assert_eq!(sheet.data[2].get_pixel(2, 2).0, [0, 0, 255, 255]);
+        assert_eq!(sheet.data[2].get_pixel(3, 3).0, [0, 0, 0, 0]);
+
+        assert_eq!(sheet.animations.len(), 3);
+        assert_eq!(
+            sheet.animations["walk"],
+            frames(&[(0, 100), (1, 150), (2, 200)])
+        );
+        assert_eq!(
+            sheet.animations["idle"],
+            frames(&[(0, 100), (1, 150), (2, 200), (1, 150)])
+        );
+        assert_eq!(sheet.animations["hurt"], frames(&[(2, 200), (1, 150)]));
+    }
+
+    #[test]
+    fn hash_sheet() {
+        let mut json: serde_json::Value = serde_json::from_slice(JSON).unwrap();
+        let frames = json["frames"].as_array().unwrap().clone();
+        json["frames"] = frames
+            .into_iter()
+            .map(|frame| (frame["filename"].as_str().unwrap().to_owned(), frame))
+            .collect::<serde_json::Map<_, _>>()
+            .into();
+        let json = serde_json::to_vec(&json).unwrap();
+
+        let sheet = SpriteArrayBuilder::aseprite(&json, IMAGE).unwrap();
+        assert_eq!(sheet.data.len(), 3);
+        assert_eq!(sheet.animations["walk"].len(), 3);
+    }
+
+    #[test]
+    fn invalid_tag() {
+        let mut json: serde_json::Value = serde_json::from_slice(JSON).unwrap();
+        json["meta"]["frameTags"][0]["to"] = 3.into();
+        let json = serde_json::to_vec(&json).unwrap();
+        assert!(SpriteArrayBuilder::aseprite(&json, IMAGE).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
+
+#[cfg(feature = "audio")]
+use crate::audio::SoundBuilder;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::graphics::{ModelBuilder, Shader, ShaderConfig};
+#[cfg(feature = "text")]
+use crate::text::FontBuilder;
+use crate::{
+    context::Context,
+    graphics::{AssetKey, AssetManager, Gpu, SpriteBuilder},
+    io::ResourceLoader,
+    tasks::TaskManager,
+};
+
+enum BatchEntry {
+    Sprite(String),
+    #[cfg(not(target_arch = "wasm32"))]
+    Model(String),
+    #[cfg(not(target_arch = "wasm32"))]
+    Shader(ShaderConfig<'static>),
+    #[cfg(feature = "audio")]
+    Sound(String),
+    #[cfg(feature = "text")]
+    Font(String),
+}
+
+enum LoadedAsset {
+    Sprite(image::DynamicImage, String),
+    #[cfg(not(target_arch = "wasm32"))]
+    Model(ModelBuilder),
+    #[cfg(not(target_arch = "wasm32"))]
+    Shader(Shader),
+    #[cfg(feature = "audio")]
+    Sound(SoundBuilder),
+    #[cfg(feature = "text")]
+    Font(FontBuilder),
+}
+
+/// Queue of assets that are loaded and decoded in the background with [AssetBatch::start].
+/// Created with [AssetManager::load_batch].
+pub struct AssetBatch {
+    loader: Arc<dyn ResourceLoader>,
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    gpu: Arc<Gpu>,
+    entries: Vec<(AssetKey, BatchEntry)>,
+}
+
+impl AssetBatch {
+    pub fn new(loader: Arc<dyn ResourceLoader>, gpu: Arc<Gpu>) -> Self {
+        Self {
+            loader,
+            gpu,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn sprite(mut self, key: AssetKey, path: &str) -> Self {
+        self.entries
+            .push((key, BatchEntry::Sprite(path.to_owned())));
+        self
+    }
+
+    /// Models are loaded through the global resource loader, which can't block on the web
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn model(mut self, key: AssetKey, path: &str) -> Self {
+        self.entries.push((key, BatchEntry::Model(path.to_owned())));
+        self
+    }
+
+    /// Compiles the shader with the other assets, so loading screens hide the compilation
+    /// instead of the first frame that draws with it
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn shader(mut self, key: AssetKey, mut config: ShaderConfig<'static>) -> Self {
+        if config.name.is_none() {
+            config.name = Some(key);
+        }
+        self.entries.push((key, BatchEntry::Shader(config)));
+        self
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn sound(mut self, key: AssetKey, path: &str) -> Self {
+        self.entries.push((key, BatchEntry::Sound(path.to_owned())));
+        self
+    }
+
+    #[cfg(feature = "text")]
+    pub fn font(mut self, key: AssetKey, path: &str) -> Self {
+        self.entries.push((key, BatchEntry::Font(path.to_owned())));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Reads and decodes every asset as its own task. The GPU resources are created on the main
+    /// thread once a task is done, so the assets show up one by one in the [AssetManager].
+    pub fn start(self, tasks: &TaskManager) -> AssetBatchHandle {
+        let state = Arc::new(BatchState {
+            total: self.entries.len(),
+            finished: AtomicUsize::new(0),
+            failures: Mutex::new(Vec::new()),
+        });
+
+        for (key, entry) in self.entries {
+            let loader = self.loader.clone();
+            let state = state.clone();
+            let callback = move |ctx: &mut Context, result: Result<LoadedAsset>| {
+                if let Err(err) = result.and_then(|asset| insert(&ctx.assets, key, asset)) {
+                    #[cfg(feature = "log")]
+                    crate::log::error!("Cannot load asset {key}: {err}");
+                    state.failures.lock().push((key, err.to_string()));
+                }
+                state.finished.fetch_add(1, Ordering::Relaxed);
+            };
+
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let gpu = self.gpu.clone();
+                tasks.spawn(
+                    move || pollster::block_on(load(&*loader, &gpu, entry)),
+                    callback,
+                );
+            }
+
+            #[cfg(target_arch = "wasm32")]
+            tasks.spawn_async(async move { load(&*loader, entry).await }, callback);
+        }
+
+        AssetBatchHandle { state }
+    }
+}
+
+async fn load(
+    loader: &dyn ResourceLoader,
+    #[cfg(not(target_arch = "wasm32"))] gpu: &Gpu,
+    entry: BatchEntry,
+) -> Result<LoadedAsset> {
+    Ok(match entry {
+        BatchEntry::Sprite(path) => {
+            let bytes = loader.async_load_bytes(&path).await?;
+            LoadedAsset::Sprite(image::load_from_memory(&bytes)?, path)
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        BatchEntry::Model(path) => LoadedAsset::Model(
+            ModelBuilder::try_resource(&path)
+                .map_err(|err| anyhow!("Cannot load model {path}: {err}"))?,
+        ),
+        #[cfg(not(target_arch = "wasm32"))]
+        BatchEntry::Shader(config) => LoadedAsset::Shader(Shader::new(gpu, config)),
+        #[cfg(feature = "audio")]
+        BatchEntry::Sound(path) => {
+            LoadedAsset::Sound(SoundBuilder::bytes(&loader.async_load_bytes(&path).await?))
+        }
+        #[cfg(feature = "text")]
+        BatchEntry::Font(path) => {
+            LoadedAsset::Font(FontBuilder::owned(loader.async_load_bytes(&path).await?))
+        }
+    })
+}
+
+fn insert(assets: &AssetManager, key: AssetKey, asset: LoadedAsset) -> Result<()> {
+    if assets.exists(key) {
+        return Err(anyhow!("Asset {key} already exists!"));
+    }
+    match asset {
+        LoadedAsset::Sprite(image, path) => assets.load_sprite(
+            key,
+            SpriteBuilder {
+                resource_path: Some(path),
+                ..SpriteBuilder::image(image)
+            },
+        ),
+        #[cfg(not(target_arch = "wasm32"))]
+        LoadedAsset::Model(builder) => assets.load_model(key, builder),
+        #[cfg(not(target_arch = "wasm32"))]
+        LoadedAsset::Shader(shader) => assets.load(key, shader),
+        #[cfg(feature = "audio")]
+        LoadedAsset::Sound(builder) => assets.load_sound(key, builder),
+        #[cfg(feature = "text")]
+        LoadedAsset::Font(builder) => assets.load_font(key, builder),
+    }
+    Ok(())
+}
+
+struct BatchState {
+    total: usize,
+    finished: AtomicUsize,
+    failures: Mutex<Vec<(AssetKey, String)>>,
+}
+
+/// Progress of a started [AssetBatch]. Failed assets count as finished.
+#[derive(Clone)]
+pub struct AssetBatchHandle {
+    state: Arc<BatchState>,
+}
+
+impl AssetBatchHandle {
+    /// Between `0.0` and `1.0`, an empty batch is immediately done
+    pub fn progress(&self) -> f32 {
+        if self.state.total == 0 {
+            return 1.0;
+        }
+        self.finished() as f32 / self.state.total as f32
+    }
+
+    pub fn finished(&self) -> usize {
+        self.state.finished.load(Ordering::Relaxed)
+    }
+
+    pub fn total(&self) -> usize {
+        self.state.total
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished() >= self.state.total
+    }
+
+    pub fn has_failures(&self) -> bool {
+        !self.state.failures.lock().is_empty()
+    }
+
+    /// Keys of the assets that could not be loaded together with the reason
+    pub fn failures(&self) -> Vec<(AssetKey, String)> {
+        self.state.failures.lock().clone()
+    }
+}
@@ -14,18 +14,27 @@ use rustc_hash::FxBuildHasher;
 #[cfg(feature = "audio")]
 use crate::audio::{Sound, SoundBuilder};
 
+#[cfg(feature = "rayon")]
+use crate::ecs::{Component, DisabledComponent, View};
+#[cfg(feature = "hot-reload")]
+use crate::graphics::HotReloader;
+#[cfg(feature = "gltf")]
+use crate::graphics::{SkinnedModel, SkinnedModelBuilder};
 #[cfg(feature = "text")]
-use crate::text::{Font, FontBuilder, TextMesh, TextSection};
+use crate::text::{Font, FontBuilder, Text, TextSection};
 
 use crate::{
     graphics::{
-        Camera, CameraBuffer, DefaultAssets, DepthBuffer, Gpu, Index, Instance, InstanceBuffer,
-        Mesh, MeshBuilder, Model, ModelBuilder, RenderTarget, Shader, ShaderConfig, ShaderModule,
-        ShaderModuleDescriptor, Sprite, SpriteArray, SpriteArrayBuilder, SpriteBuilder,
-        SpriteRenderTarget, UniformData, Vertex,
+        AssetBatch, Bloom, Camera, CameraBuffer, Cubemap, CubemapBuilder, DefaultAssets,
+        DepthBuffer, Gpu, Index, Instance, Instance2D, InstanceBuffer, InstanceSort,
+        KeyedInstances, Mesh, MeshBuilder, Model, ModelBuilder, NinePatchBorder, NinePatchSprite,
+        PostProcess, RenderTarget, Shader, ShaderConfig, ShaderHandle, ShaderModule,
+        ShaderModuleDescriptor, ShaderModuleSource, ShaderSource, Sprite, SpriteArray,
+        SpriteArrayBuilder, SpriteBuilder, SpriteRenderTarget, Terrain, UniformData, Vertex,
     },
     io::ResourceLoader,
     math::Vector2,
+    tilemap::TileMap,
 };
 
 pub trait Asset: Send + Sync + Downcast {}
@@ -41,8 +50,10 @@ pub type AssetWrapMut<'a, A> = dashmap::mapref::one::MappedRefMut<'a, AssetKey,
 pub struct AssetManager {
     pub loader: Arc<dyn ResourceLoader>,
     default_assets: RwLock<DefaultAssets>,
-    gpu: Arc<Gpu>,
+    pub(crate) gpu: Arc<Gpu>,
     assets: DashMap<AssetKey, Box<dyn Asset>, FxBuildHasher>,
+    #[cfg(feature = "hot-reload")]
+    hot_reloader: parking_lot::Mutex<HotReloader>,
 }
 
 impl AssetManager {
@@ -50,6 +61,8 @@ impl AssetManager {
         Self {
             default_assets: RwLock::new(DefaultAssets::new(&gpu)),
             assets: DashMap::with_hasher(FxBuildHasher),
+            #[cfg(feature = "hot-reload")]
+            hot_reloader: parking_lot::Mutex::new(HotReloader::new()),
             loader,
             gpu,
         }
@@ -63,6 +76,11 @@ impl AssetManager {
         self.default_assets.write()
     }
 
+    /// Builder for assets that are loaded in the background, see [AssetBatch::start]
+    pub fn load_batch(&self) -> AssetBatch {
+        AssetBatch::new(self.loader.clone(), self.gpu.clone())
+    }
+
     pub fn exists(&self, key: AssetKey) -> bool {
         self.assets.contains_key(key)
     }
@@ -104,6 +122,8 @@ impl AssetManager {
     }
 
     pub fn unload(&self, key: &'static str) -> Option<Box<dyn Asset>> {
+        #[cfg(feature = "hot-reload")]
+        self.hot_reloader.lock().unwatch(key);
         self.assets.remove(key).map(|a| a.1)
     }
 
@@ -111,6 +131,22 @@ impl AssetManager {
         self.get(key)
     }
 
+    pub fn nine_patch_sprite(&self, key: AssetKey) -> AssetWrap<NinePatchSprite> {
+        self.get(key)
+    }
+
+    pub fn post_process(&self, key: AssetKey) -> AssetWrap<PostProcess> {
+        self.get(key)
+    }
+
+    pub fn bloom(&self, key: AssetKey) -> AssetWrap<Bloom> {
+        self.get(key)
+    }
+
+    pub fn tilemap(&self, key: AssetKey) -> AssetWrap<TileMap> {
+        self.get(key)
+    }
+
     pub fn instances<I: Instance>(&self, key: AssetKey) -> AssetWrap<InstanceBuffer<I>> {
         self.get(key)
     }
@@ -124,11 +160,11 @@ impl AssetManager {
         key: AssetKey,
         font: AssetKey,
         sections: &[TextSection<S>],
-    ) -> AssetWrapMut<TextMesh> {
-        let mut mesh = self.get_mut::<TextMesh>(key);
-        let font = self.get(font);
-        mesh.write(&self.gpu, &font, sections);
-        mesh
+    ) -> AssetWrapMut<Text> {
+        let mut text = self.get_mut::<Text>(key);
+        let mut font = self.get_mut::<Font>(font);
+        text.write(&self.gpu, &mut font, sections);
+        text
     }
 
     pub fn uniform<D: bytemuck::Pod + Send + Sync>(
@@ -150,7 +186,7 @@ impl AssetManager {
         self.get(key)
     }
 
-    pub fn text_mesh(&self, key: AssetKey) -> AssetWrap<TextMesh> {
+    pub fn text(&self, key: AssetKey) -> AssetWrap<Text> {
         self.get(key)
     }
 
@@ -158,10 +194,23 @@ impl AssetManager {
         self.get(key)
     }
 
+    pub fn cubemap(&self, key: AssetKey) -> AssetWrap<Cubemap> {
+        self.get(key)
+    }
+
+    #[cfg(feature = "gltf")]
+    pub fn skinned_model(&self, key: AssetKey) -> AssetWrap<SkinnedModel> {
+        self.get(key)
+    }
+
     pub fn shader(&self, key: AssetKey) -> AssetWrap<Shader> {
         self.get(key)
     }
 
+    pub fn shader_handle(&self, key: AssetKey) -> AssetWrap<ShaderHandle> {
+        self.get(key)
+    }
+
     pub fn shader_module(&self, key: AssetKey) -> AssetWrap<ShaderModule> {
         self.get(key)
     }
@@ -184,6 +233,22 @@ impl AssetManager {
         self.get_mut(key)
     }
 
+    pub fn nine_patch_sprite_mut(&self, key: AssetKey) -> AssetWrapMut<NinePatchSprite> {
+        self.get_mut(key)
+    }
+
+    pub fn post_process_mut(&self, key: AssetKey) -> AssetWrapMut<PostProcess> {
+        self.get_mut(key)
+    }
+
+    pub fn bloom_mut(&self, key: AssetKey) -> AssetWrapMut<Bloom> {
+        self.get_mut(key)
+    }
+
+    pub fn tilemap_mut(&self, key: AssetKey) -> AssetWrapMut<TileMap> {
+        self.get_mut(key)
+    }
+
     pub fn instances_mut<I: Instance>(&self, key: AssetKey) -> AssetWrapMut<InstanceBuffer<I>> {
         self.get_mut(key)
     }
@@ -211,7 +276,7 @@ impl AssetManager {
         self.get_mut(key)
     }
 
-    pub fn text_mesh_mut(&self, key: AssetKey) -> AssetWrapMut<TextMesh> {
+    pub fn text_mut(&self, key: AssetKey) -> AssetWrapMut<Text> {
         self.get_mut(key)
     }
 
@@ -250,9 +315,24 @@ impl AssetManager {
     }
 
     pub fn load_sprite<D: Deref<Target = [u8]>>(&self, key: AssetKey, desc: SpriteBuilder<D>) {
+        #[cfg(feature = "hot-reload")]
+        if let Some(path) = &desc.resource_path {
+            self.hot_reloader
+                .lock()
+                .watch_sprite(key, path, &desc, &*self.loader);
+        }
         self.load(key, self.gpu.create_sprite(desc));
     }
 
+    pub fn load_nine_patch_sprite<D: Deref<Target = [u8]>>(
+        &self,
+        key: AssetKey,
+        desc: SpriteBuilder<D>,
+        border: NinePatchBorder,
+    ) {
+        self.load(key, NinePatchSprite::new(&self.gpu, desc, border));
+    }
+
     pub fn load_render_target(&self, key: AssetKey, size: Vector2<u32>) {
         self.load(key, SpriteRenderTarget::new(&self.gpu, size));
     }
@@ -287,9 +367,24 @@ impl AssetManager {
     }
 
     pub fn load_model(&self, key: AssetKey, builder: ModelBuilder) {
+        #[cfg(feature = "hot-reload")]
+        if let Some(path) = &builder.resource_path {
+            self.hot_reloader
+                .lock()
+                .watch_model(key, path, &*self.loader);
+        }
         self.load(key, Model::new(&self.gpu, builder));
     }
 
+    pub fn load_cubemap(&self, key: AssetKey, desc: CubemapBuilder) {
+        self.load(key, self.gpu.create_cubemap(desc));
+    }
+
+    #[cfg(feature = "gltf")]
+    pub fn load_skinned_model(&self, key: AssetKey, builder: SkinnedModelBuilder) {
+        self.load(key, SkinnedModel::new(&self.gpu, builder));
+    }
+
     pub fn load_sprite_array<D: Deref<Target = [u8]>>(
         &self,
         key: AssetKey,
@@ -321,9 +416,49 @@ impl AssetManager {
         if config.name.is_none() {
             config.name = Some(key);
         }
+        if let ShaderModuleSource::Hot(path) = config.source {
+            let source = self
+                .loader
+                .load_string(path)
+                .unwrap_or_else(|err| panic!("Cannot load shader {path}: {err}"));
+            let module = self.gpu.create_shader_module(ShaderModuleDescriptor {
+                label: Some(path),
+                source: ShaderSource::Wgsl(source.into()),
+            });
+            #[cfg(feature = "hot-reload")]
+            self.hot_reloader
+                .lock()
+                .watch_shader(key, path, &config, &*self.loader);
+            let config = ShaderConfig {
+                source: ShaderModuleSource::Single(&module),
+                ..config
+            };
+            self.load(key, Shader::new(&self.gpu, config));
+            return;
+        }
         self.load(key, Shader::new(&self.gpu, config));
     }
 
+    /// Swaps shaders, sprites and models whose resource changed since the last poll
+    #[cfg(feature = "hot-reload")]
+    pub(crate) fn reload_assets(&self) {
+        self.hot_reloader.lock().poll(self);
+    }
+
+    #[cfg(feature = "hot-reload")]
+    pub(crate) fn replace<A: Asset>(&self, key: AssetKey, asset: A) {
+        self.assets.insert(key, Box::new(asset));
+    }
+
+    /// Compiles the shader in the background, see [Gpu::create_shader_async]. The shader is
+    /// accessed with [AssetManager::shader_handle].
+    pub fn load_shader_async(&self, key: AssetKey, mut config: ShaderConfig<'static>) {
+        if config.name.is_none() {
+            config.name = Some(key);
+        }
+        self.load(key, self.gpu.create_shader_async(config));
+    }
+
     pub fn load_shader_module(&self, key: AssetKey, desc: ShaderModuleDescriptor<'_>) {
         self.load(key, self.gpu.device.create_shader_module(desc))
     }
@@ -339,15 +474,19 @@ impl AssetManager {
     }
 
     #[cfg(feature = "text")]
-    pub fn load_text_mesh<S: AsRef<str>>(
+    pub fn load_text<S: AsRef<str>>(
         &self,
         key: AssetKey,
         font: AssetKey,
         sections: &[TextSection<S>],
     ) {
-        self.load(key, TextMesh::new(&self.gpu, &self.font(font), sections));
+        let text = Text::new(&self.gpu, &mut self.font_mut(font), sections);
+        self.load(key, text);
     }
 
+    /// Writes whatever `data` pushes. Entities are not filtered here, see
+    /// [Context::write_instance_entities](crate::context::Context::write_instance_entities) and
+    /// [IterEnabled](crate::ecs::IterEnabled) to skip disabled ones.
     pub fn write_instances<I: Instance>(
         &self,
         key: AssetKey,
@@ -375,6 +514,92 @@ impl AssetManager {
         instance_buffer
     }
 
+    /// Like [AssetManager::write_instances] but every instance is pushed with a key. The instances
+    /// are uploaded in one buffer sorted by key and
+    /// [InstanceBuffer::key_range] returns the instances of a key, e.g. to draw each with another
+    /// sprite through [Renderer::draw_sprite_keyed](crate::graphics::Renderer::draw_sprite_keyed).
+    pub fn write_keyed_instances<I: Instance>(
+        &self,
+        key: AssetKey,
+        manual: bool,
+        data: impl FnOnce(&mut KeyedInstances<I>),
+    ) -> AssetWrapMut<InstanceBuffer<I>> {
+        if !self.exists(key) {
+            self.load_instance_buffer::<I>(key, &[]);
+        }
+        let mut instance_buffer = self.get_mut::<InstanceBuffer<I>>(key);
+        if manual && !instance_buffer.force_update {
+            return instance_buffer;
+        }
+        instance_buffer.force_update = false;
+
+        let mut keyed = KeyedInstances {
+            instances: Vec::new(),
+        };
+        data(&mut keyed);
+        instance_buffer.write_keyed(&self.gpu, &mut keyed);
+        instance_buffer
+    }
+
+    /// Like [AssetManager::write_instances] but maps the component of every enabled entity to an
+    /// instance in parallel. The instances keep the order of the storage, which changes when
+    /// entities are removed.
+    /// With `sort_by_entity` they are ordered by entity index instead, so the draw order only
+    /// depends on the entities at the cost of collecting their ids first.
+    #[cfg(feature = "rayon")]
+    pub fn write_instances_par<C: Component + Sync, I: Instance>(
+        &self,
+        key: AssetKey,
+        manual: bool,
+        components: &View<C>,
+        disabled: &View<DisabledComponent>,
+        sort_by_entity: bool,
+        instance: impl Fn(&C) -> Option<I> + Send + Sync,
+    ) -> AssetWrapMut<InstanceBuffer<I>> {
+        use rayon::iter::{IntoParallelRefIterator, ParallelExtend, ParallelIterator};
+        use shipyard::{Get, IntoIter, IntoWithId};
+
+        self.write_instances(key, manual, |data| {
+            if sort_by_entity {
+                let mut entities = components
+                    .iter()
+                    .with_id()
+                    .map(|(entity, _)| entity)
+                    .filter(|entity| !disabled.contains(*entity))
+                    .collect::<Vec<_>>();
+                entities.sort_unstable_by_key(|entity| entity.index());
+                data.par_extend(
+                    entities
+                        .par_iter()
+                        .filter_map(|entity| instance(components.get(*entity).ok()?)),
+                );
+            } else {
+                data.par_extend(
+                    (components, !disabled)
+                        .par_iter()
+                        .filter_map(|(component, _)| instance(component)),
+                );
+            }
+        })
+    }
+
+    /// Like [AssetManager::write_instances] but sorts the instances before they are uploaded
+    pub fn write_sorted_instances<D: bytemuck::Pod + Send + Sync>(
+        &self,
+        key: AssetKey,
+        manual: bool,
+        sort: InstanceSort<D>,
+        data: impl FnOnce(&mut Vec<Instance2D<D>>),
+    ) -> AssetWrapMut<InstanceBuffer<Instance2D<D>>>
+    where
+        Instance2D<D>: Instance,
+    {
+        self.write_instances(key, manual, |instances| {
+            data(instances);
+            sort.sort(instances);
+        })
+    }
+
     pub fn write_mesh<V: Vertex>(
         &self,
         key: AssetKey,
@@ -425,10 +650,19 @@ impl<R: RenderTarget> Asset for R {}
 impl<V: Vertex> Asset for Mesh<V> {}
 impl<I: Instance> Asset for InstanceBuffer<I> {}
 impl Asset for Sprite {}
+impl Asset for NinePatchSprite {}
+impl Asset for PostProcess {}
+impl Asset for Bloom {}
+impl Asset for TileMap {}
 impl Asset for SpriteArray {}
-impl Asset for TextMesh {}
+impl Asset for Text {}
 impl Asset for Model {}
+impl Asset for Cubemap {}
+impl Asset for Terrain {}
+#[cfg(feature = "gltf")]
+impl Asset for SkinnedModel {}
 impl Asset for Shader {}
+impl Asset for ShaderHandle {}
 impl Asset for DepthBuffer {}
 #[cfg(feature = "audio")]
 impl Asset for Sound {}
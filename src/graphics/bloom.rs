@@ -0,0 +1,165 @@
+use crate::{
+    graphics::{
+        Gpu, RenderTarget, Shader, ShaderConfig, ShaderModuleSource, Sprite, SpriteBuilder,
+        SpriteRenderTarget, SpriteVertex2D, UniformData, UniformField, VertexBuffers,
+    },
+    math::{Vector2, Vector4},
+};
+use wgpu::{include_wgsl, BlendComponent, BlendFactor, BlendOperation, BlendState};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct BloomConfig {
+    /// Brightness above which pixels start to glow
+    pub threshold: f32,
+    /// Smooths the transition at the threshold in the range [0, 1]
+    pub knee: f32,
+    pub intensity: f32,
+    /// Amount of downsampled targets. More mips make the glow spread further.
+    pub mip_count: u32,
+}
+
+impl Default for BloomConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 0.8,
+            knee: 0.5,
+            intensity: 1.0,
+            mip_count: 5,
+        }
+    }
+}
+
+/// Extracts bright pixels, blurs them through a chain of downsampled targets and adds the result
+/// back onto the image. See [RenderEncoder::apply_bloom](crate::graphics::RenderEncoder::apply_bloom).
+pub struct Bloom {
+    config: BloomConfig,
+    size: Vector2<u32>,
+    pub(crate) mips: Vec<SpriteRenderTarget>,
+    pub(crate) threshold_shader: Shader,
+    pub(crate) downsample_shader: Shader,
+    pub(crate) upsample_shader: Shader,
+    pub(crate) threshold_params: UniformData<Vector4<f32>>,
+    pub(crate) upsample_params: UniformData<Vector4<f32>>,
+    pub(crate) composite_params: UniformData<Vector4<f32>>,
+}
+
+impl Bloom {
+    const ADDITIVE: BlendState = BlendState {
+        color: BlendComponent {
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        },
+        alpha: BlendComponent {
+            src_factor: BlendFactor::Zero,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        },
+    };
+
+    pub fn new(gpu: &Gpu, config: BloomConfig, size: Vector2<u32>) -> Self {
+        let shader = |name, source, blend| {
+            gpu.create_shader(ShaderConfig {
+                name: Some(name),
+                source: ShaderModuleSource::Single(&gpu.create_shader_module(source)),
+                uniforms: &[
+                    UniformField::Camera,
+                    UniformField::Sprite,
+                    UniformField::SingleUniform,
+                ],
+                vertex_buffers: VertexBuffers::vertex::<SpriteVertex2D>(),
+                blend,
+                ..Default::default()
+            })
+        };
+        let params = |params| {
+            UniformData::new(
+                gpu,
+                gpu.default_layouts().single_uniform_layout.clone(),
+                &[params],
+            )
+        };
+
+        let mut bloom = Self {
+            threshold_shader: shader(
+                "bloom_threshold",
+                include_wgsl!("../../static/shader/2d/bloom/threshold.wgsl"),
+                BlendState::REPLACE,
+            ),
+            downsample_shader: shader(
+                "bloom_downsample",
+                include_wgsl!("../../static/shader/2d/bloom/downsample.wgsl"),
+                BlendState::REPLACE,
+            ),
+            upsample_shader: shader(
+                "bloom_upsample",
+                include_wgsl!("../../static/shader/2d/bloom/upsample.wgsl"),
+                Self::ADDITIVE,
+            ),
+            threshold_params: params(Vector4::new(config.threshold, config.knee, 0.0, 0.0)),
+            upsample_params: params(Vector4::new(1.0, 0.0, 0.0, 0.0)),
+            composite_params: params(Vector4::new(config.intensity, 0.0, 0.0, 0.0)),
+            mips: Vec::new(),
+            size,
+            config,
+        };
+        bloom.create_mips(gpu);
+        bloom
+    }
+
+    fn create_mips(&mut self, gpu: &Gpu) {
+        let mip_count = self.config.mip_count.max(1);
+        self.mips = (1..=mip_count)
+            .map(|i| {
+                let size = Vector2::new((self.size.x >> i).max(1), (self.size.y >> i).max(1));
+                SpriteRenderTarget::custom(
+                    gpu,
+                    SpriteBuilder::empty(size).sampler(wgpu::SamplerDescriptor {
+                        mag_filter: wgpu::FilterMode::Linear,
+                        min_filter: wgpu::FilterMode::Linear,
+                        ..Sprite::DEFAULT_SAMPLER
+                    }),
+                )
+            })
+            .collect();
+    }
+
+    /// Recreates the mip chain if the size changed. Should match the size of the source target.
+    pub fn resize(&mut self, gpu: &Gpu, size: Vector2<u32>) {
+        if self.size != size {
+            self.size = size;
+            self.create_mips(gpu);
+        }
+    }
+
+    pub fn set_config(&mut self, gpu: &Gpu, config: BloomConfig) {
+        let recreate = config.mip_count != self.config.mip_count;
+        self.config = config;
+        self.threshold_params.write(
+            gpu,
+            &[Vector4::new(config.threshold, config.knee, 0.0, 0.0)],
+        );
+        self.composite_params
+            .write(gpu, &[Vector4::new(config.intensity, 0.0, 0.0, 0.0)]);
+        if recreate {
+            self.create_mips(gpu);
+        }
+    }
+
+    pub fn config(&self) -> &BloomConfig {
+        &self.config
+    }
+
+    pub fn size(&self) -> Vector2<u32> {
+        self.size
+    }
+
+    pub fn mips(&self) -> &[SpriteRenderTarget] {
+        &self.mips
+    }
+
+    pub(crate) fn same_target(a: &dyn RenderTarget, b: &dyn RenderTarget) -> bool {
+        a.texture().global_id() == b.texture().global_id()
+    }
+}
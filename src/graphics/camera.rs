@@ -3,9 +3,10 @@ use std::marker::PhantomData;
 use std::ops::Deref;
 
 use crate::{
+    ecs::{EntityId, PositionComponent2D, World},
     graphics::Gpu,
     graphics::{Uniform, UniformData},
-    math::{Isometry2, Isometry3, Matrix4, Point3, Rotation2, Vector2, Vector3, AABB},
+    math::{Frustum, Isometry2, Isometry3, Matrix4, Point3, Rotation2, Vector2, Vector3, AABB},
 };
 
 const MINIMAL_FOV: f32 = 0.0001;
@@ -153,12 +154,76 @@ impl Camera for Camera2D {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct CameraFollow {
+    pub target: EntityId,
+    /// Fraction of the distance to the target that is left after one second. `0.0` snaps to the
+    /// target immediately.
+    pub smoothing: f32,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy)]
+pub struct CameraShake {
+    pub amplitude: f32,
+    pub frequency: f32,
+    pub duration: f32,
+    elapsed: f32,
+}
+
+impl CameraShake {
+    pub fn new(amplitude: f32, frequency: f32, duration: f32) -> Self {
+        Self {
+            amplitude,
+            frequency,
+            duration,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    pub fn offset(&self) -> Vector2<f32> {
+        if self.finished() {
+            return Vector2::zeros();
+        }
+        let decay = 1.0 - self.elapsed / self.duration;
+        let t = self.elapsed * self.frequency;
+        Vector2::new(Self::noise(t, 0), Self::noise(t, 1)) * self.amplitude * decay * decay
+    }
+
+    /// Smooth value noise in the range [-1, 1]
+    fn noise(t: f32, seed: u32) -> f32 {
+        fn hash(x: i32, seed: u32) -> f32 {
+            let mut h = (x as u32).wrapping_mul(0x27d4_eb2d) ^ seed.wrapping_mul(0x1656_67b1);
+            h ^= h >> 15;
+            h = h.wrapping_mul(0x85eb_ca6b);
+            h ^= h >> 13;
+            (h & 0xffff) as f32 / 0xffff as f32 * 2.0 - 1.0
+        }
+        let i = t.floor();
+        let f = t - i;
+        let f = f * f * (3.0 - 2.0 * f);
+        let a = hash(i as i32, seed);
+        let b = hash(i as i32 + 1, seed);
+        a + (b - a) * f
+    }
+}
+
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone)]
 pub struct WorldCamera2D {
+    /// The view that gets rendered, including shake and bounds
     pub(crate) camera: Camera2D,
+    position: Isometry2<f32>,
     scale: WorldCameraScaling,
     window_size: Vector2<f32>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    follow: Option<CameraFollow>,
+    shake: Option<CameraShake>,
+    bounds: Option<AABB>,
 }
 
 impl WorldCamera2D {
@@ -172,14 +237,19 @@ impl WorldCamera2D {
         let fov = scale.fov(window_size);
         Self {
             camera: Camera2D::new(position, fov),
+            position,
             window_size,
             scale,
+            follow: None,
+            shake: None,
+            bounds: None,
         }
     }
 
     pub(crate) fn compute_fov(&mut self) {
         let fov = self.scale.fov(self.window_size);
         self.camera.set_fov(fov);
+        self.apply();
     }
 
     pub(crate) fn resize(&mut self, window_size: Vector2<u32>) {
@@ -187,6 +257,52 @@ impl WorldCamera2D {
         self.compute_fov();
     }
 
+    pub(crate) fn update(&mut self, delta: f32, alpha: f32, world: &World) {
+        if let Some(follow) = self.follow {
+            if let Ok(target) = world.get::<&PositionComponent2D>(follow.target) {
+                let target = target.render_position(alpha).translation.vector;
+                let t = 1.0 - follow.smoothing.clamp(0.0, 1.0).powf(delta);
+                let translation = self.position.translation.vector.lerp(&target, t);
+                self.position.translation.vector = translation;
+            }
+        }
+
+        if let Some(shake) = &mut self.shake {
+            shake.elapsed += delta;
+            if shake.finished() {
+                self.shake = None;
+            }
+        }
+        self.apply();
+    }
+
+    fn apply(&mut self) {
+        let mut position = self.position;
+        if let Some(shake) = &self.shake {
+            position.translation.vector += shake.offset();
+        }
+
+        if let Some(bounds) = &self.bounds {
+            let fov = self.camera.fov();
+            let translation = &mut position.translation.vector;
+            for i in 0..2 {
+                let min = bounds.min()[i] + fov[i];
+                let max = bounds.max()[i] - fov[i];
+                translation[i] = if min > max {
+                    bounds.center()[i]
+                } else {
+                    translation[i].clamp(min, max)
+                };
+            }
+        }
+        self.camera.set_position(position);
+    }
+
+    /// The visible area including shake and bounds
+    pub fn aabb(&self) -> AABB {
+        self.camera.aabb()
+    }
+
     pub fn intersects(&self, aabb: &AABB) -> bool {
         self.aabb().intersects(aabb)
     }
@@ -200,16 +316,73 @@ impl WorldCamera2D {
         self.compute_fov();
     }
 
+    pub const fn position(&self) -> &Isometry2<f32> {
+        &self.position
+    }
+
+    pub const fn translation(&self) -> &Vector2<f32> {
+        &self.position.translation.vector
+    }
+
+    pub fn rotation(&self) -> &Rotation2<f32> {
+        &self.position.rotation
+    }
+
     pub fn set_rotation(&mut self, rotation: Rotation2<f32>) {
-        self.camera.set_rotation(rotation);
+        self.position.rotation = rotation;
+        self.apply();
     }
 
     pub fn set_position(&mut self, position: Isometry2<f32>) {
-        self.camera.set_position(position);
+        self.position = position;
+        self.apply();
     }
 
     pub fn set_translation(&mut self, translation: Vector2<f32>) {
-        self.camera.set_translation(translation);
+        self.position.translation.vector = translation;
+        self.apply();
+    }
+
+    /// Moves the camera towards the [PositionComponent2D] of the entity every frame
+    pub fn set_follow(&mut self, target: EntityId, smoothing: f32) {
+        self.follow = Some(CameraFollow { target, smoothing });
+    }
+
+    pub fn clear_follow(&mut self) {
+        self.follow = None;
+    }
+
+    pub fn follow(&self) -> Option<&CameraFollow> {
+        self.follow.as_ref()
+    }
+
+    /// Shakes the rendered view without changing the position of the camera
+    pub fn shake(&mut self, amplitude: f32, frequency: f32, duration: f32) {
+        self.shake = Some(CameraShake::new(amplitude, frequency, duration));
+        self.apply();
+    }
+
+    pub fn stop_shake(&mut self) {
+        self.shake = None;
+        self.apply();
+    }
+
+    pub fn shaking(&self) -> Option<&CameraShake> {
+        self.shake.as_ref()
+    }
+
+    pub fn set_bounds(&mut self, bounds: AABB) {
+        self.bounds = Some(bounds);
+        self.apply();
+    }
+
+    pub fn clear_bounds(&mut self) {
+        self.bounds = None;
+        self.apply();
+    }
+
+    pub fn bounds(&self) -> Option<&AABB> {
+        self.bounds.as_ref()
     }
 
     pub fn camera(&self) -> &Camera2D {
@@ -408,6 +581,106 @@ impl CameraProjection3D {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub struct OrthographicCamera3D {
+    pub left: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub top: f32,
+    pub znear: f32,
+    pub zfar: f32,
+}
+
+impl Default for OrthographicCamera3D {
+    fn default() -> Self {
+        Self::from_size(5.0, 1.0)
+    }
+}
+
+impl OrthographicCamera3D {
+    pub fn new(left: f32, right: f32, bottom: f32, top: f32, znear: f32, zfar: f32) -> Self {
+        Self {
+            left,
+            right,
+            bottom,
+            top,
+            znear,
+            zfar,
+        }
+    }
+
+    /// Creates a centered view that is `size` units high
+    pub fn from_size(size: f32, aspect: f32) -> Self {
+        let half_height = size / 2.0;
+        let half_width = half_height * aspect;
+        Self::new(
+            -half_width,
+            half_width,
+            -half_height,
+            half_height,
+            0.1,
+            1000.0,
+        )
+    }
+
+    /// Keeps the height and center of the view and adjusts the width to the aspect ratio
+    pub fn resize(&mut self, aspect: f32) {
+        let center = (self.left + self.right) / 2.0;
+        let half_width = (self.top - self.bottom) / 2.0 * aspect;
+        self.left = center - half_width;
+        self.right = center + half_width;
+    }
+
+    /// Maps `znear` to a depth of 0 and `zfar` to 1, the depth range of wgpu
+    pub fn matrix(&self) -> Matrix4<f32> {
+        gl_to_wgpu_depth()
+            * Orthographic3::new(
+                self.left,
+                self.right,
+                self.bottom,
+                self.top,
+                self.znear,
+                self.zfar,
+            )
+            .to_homogeneous()
+    }
+}
+
+/// nalgebra projects depth to [-1, 1] like OpenGL, everything outside of [0, 1] is clipped by wgpu
+#[rustfmt::skip]
+fn gl_to_wgpu_depth() -> Matrix4<f32> {
+    Matrix4::new(
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 0.5, 0.5,
+        0.0, 0.0, 0.0, 1.0,
+    )
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone)]
+pub enum Projection3D {
+    Perspective(CameraProjection3D),
+    Orthographic(OrthographicCamera3D),
+}
+
+impl Projection3D {
+    pub fn resize(&mut self, aspect: f32) {
+        match self {
+            Projection3D::Perspective(proj) => proj.resize(aspect),
+            Projection3D::Orthographic(proj) => proj.resize(aspect),
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        match self {
+            Projection3D::Perspective(proj) => proj.matrix().to_homogeneous(),
+            Projection3D::Orthographic(proj) => proj.matrix(),
+        }
+    }
+}
+
 pub trait CameraView3D: Send + Sync + 'static {
     fn matrix(&self) -> Matrix4<f32>;
 }
@@ -454,20 +727,65 @@ impl CameraViewSelection {
 #[derive(Debug, Clone)]
 pub struct WorldCamera3D {
     pub view: CameraViewSelection,
-    proj: CameraProjection3D,
+    proj: Projection3D,
+    aspect: f32,
 }
 
 impl WorldCamera3D {
     pub fn new(window_size: Vector2<u32>, view: CameraViewSelection) -> Self {
+        let aspect = window_size.x as f32 / window_size.y as f32;
         Self {
             view,
-            proj: CameraProjection3D::new(window_size.x as f32 / window_size.y as f32),
+            proj: Projection3D::Perspective(CameraProjection3D::new(aspect)),
+            aspect,
         }
     }
 
     pub(crate) fn resize(&mut self, window_size: Vector2<u32>) {
-        self.proj
-            .resize(window_size.x as f32 / window_size.y as f32)
+        self.aspect = window_size.x as f32 / window_size.y as f32;
+        self.proj.resize(self.aspect)
+    }
+
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&self.matrix())
+    }
+
+    pub fn projection(&self) -> &Projection3D {
+        &self.proj
+    }
+
+    /// The projection is resized to the aspect ratio of the window
+    pub fn set_projection(&mut self, mut proj: Projection3D) {
+        proj.resize(self.aspect);
+        self.proj = proj;
+    }
+
+    pub fn perspective_projection(&self) -> Option<&CameraProjection3D> {
+        match &self.proj {
+            Projection3D::Perspective(proj) => Some(proj),
+            _ => None,
+        }
+    }
+
+    pub fn perspective_projection_mut(&mut self) -> Option<&mut CameraProjection3D> {
+        match &mut self.proj {
+            Projection3D::Perspective(proj) => Some(proj),
+            _ => None,
+        }
+    }
+
+    pub fn orthographic(&self) -> Option<&OrthographicCamera3D> {
+        match &self.proj {
+            Projection3D::Orthographic(proj) => Some(proj),
+            _ => None,
+        }
+    }
+
+    pub fn orthographic_mut(&mut self) -> Option<&mut OrthographicCamera3D> {
+        match &mut self.proj {
+            Projection3D::Orthographic(proj) => Some(proj),
+            _ => None,
+        }
     }
 
     pub fn perspective(&self) -> Option<&PerspectiveCamera3D> {
@@ -505,10 +823,111 @@ impl WorldCamera3D {
     pub fn set_perspective(&mut self, cam: PerspectiveCamera3D) {
         self.view = CameraViewSelection::PerspectiveCamera3D(cam)
     }
+
+    /// Inverse of the view projection without the translation of the view, maps clip space
+    /// positions to view directions
+    pub fn sky_matrix(&self) -> Matrix4<f32> {
+        let mut view = self.view.matrix();
+        view.fixed_view_mut::<3, 1>(0, 3).fill(0.0);
+        (self.proj.matrix() * view)
+            .try_inverse()
+            .unwrap_or_else(Matrix4::identity)
+    }
+
+    /// Position of the camera in the world
+    pub fn eye(&self) -> Point3<f32> {
+        let inverse = self
+            .view
+            .matrix()
+            .try_inverse()
+            .unwrap_or_else(Matrix4::identity);
+        Point3::new(inverse[(0, 3)], inverse[(1, 3)], inverse[(2, 3)])
+    }
 }
 
 impl Camera for WorldCamera3D {
     fn matrix(&self) -> Matrix4<f32> {
-        self.proj.matrix().to_homogeneous() * self.view.matrix()
+        self.proj.matrix() * self.view.matrix()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Point3<f32>, b: Point3<f32>) {
+        assert!((a - b).norm() < 1e-5, "{a} != {b}");
+    }
+
+    /// nalgebra's depth of [-1, 1] in the [0, 1] of wgpu
+    fn to_wgpu(point: Point3<f32>) -> Point3<f32> {
+        Point3::new(point.x, point.y, point.z * 0.5 + 0.5)
+    }
+
+    #[test]
+    fn orthographic_depth_is_in_wgpu_range() {
+        let camera = OrthographicCamera3D::new(-4.0, 6.0, -2.0, 3.0, 0.5, 50.0);
+        let expected = Orthographic3::new(-4.0, 6.0, -2.0, 3.0, 0.5, 50.0);
+        let matrix = Projection3D::Orthographic(camera).matrix();
+
+        for point in [
+            Point3::new(1.0, 0.5, -10.0),
+            Point3::new(2.5, -1.0, -25.0),
+            Point3::new(-7.0, 8.0, -60.0),
+        ] {
+            assert_close(
+                matrix.transform_point(&point),
+                to_wgpu(expected.project_point(&point)),
+            );
+        }
+        assert_close(
+            matrix.transform_point(&Point3::new(-4.0, -2.0, -0.5)),
+            Point3::new(-1.0, -1.0, 0.0),
+        );
+        assert_close(
+            matrix.transform_point(&Point3::new(6.0, 3.0, -50.0)),
+            Point3::new(1.0, 1.0, 1.0),
+        );
+        // Orthographic depth is linear, halfway between near and far is halfway in depth
+        assert_close(
+            matrix.transform_point(&Point3::new(1.0, 0.5, -25.25)),
+            Point3::new(0.0, 0.0, 0.5),
+        );
+
+        let default = OrthographicCamera3D::default().matrix();
+        assert!(
+            default
+                .transform_point(&Point3::new(0.0, 0.0, -0.1))
+                .z
+                .abs()
+                < 1e-5
+        );
+        assert!((default.transform_point(&Point3::new(0.0, 0.0, -1000.0)).z - 1.0).abs() < 1e-5);
+        assert!(default.transform_point(&Point3::new(0.0, 0.0, -10.0)).z > 0.0);
+    }
+
+    #[test]
+    fn orthographic_resize_keeps_height_and_center() {
+        let mut camera = WorldCamera3D::new(
+            Vector2::new(800, 600),
+            CameraViewSelection::PerspectiveCamera3D(PerspectiveCamera3D::new()),
+        );
+        camera.set_projection(Projection3D::Orthographic(OrthographicCamera3D::from_size(
+            4.0, 1.0,
+        )));
+        camera.resize(Vector2::new(1920, 1080));
+
+        let half_width = 2.0 * 16.0 / 9.0;
+        let expected = Orthographic3::new(-half_width, half_width, -2.0, 2.0, 0.1, 1000.0);
+        let view = camera.view.matrix();
+        let point = Point3::new(1.5, -0.5, 0.25);
+        assert_close(
+            Camera::matrix(&camera).transform_point(&point),
+            to_wgpu(expected.project_point(&view.transform_point(&point))),
+        );
+        assert!(camera.perspective_projection().is_none());
+        let orthographic = camera.orthographic().unwrap();
+        assert!((orthographic.top - orthographic.bottom - 4.0).abs() < 1e-6);
+        assert!((orthographic.right - orthographic.left - 2.0 * half_width).abs() < 1e-5);
     }
 }
@@ -1,3 +1,9 @@
+use std::fmt;
+
+/// RGBA color with components from `0.0` to `1.0`. Colors are passed to the shaders unchanged,
+/// which treat them as linear. The surface converts them to sRGB when its format is sRGB, which
+/// is the case on most native platforms. Colors picked in an image editor or written as hex
+/// codes are sRGB encoded and need [Color::to_linear] to look the same on screen.
 #[repr(C)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Copy, Clone, Debug, PartialEq, Default, bytemuck::Pod, bytemuck::Zeroable)]
@@ -178,6 +184,289 @@ impl Color {
         self.g = 1.0 - self.g;
         self.b = 1.0 - self.b;
     }
+
+    pub const fn with_alpha(mut self, a: f32) -> Self {
+        self.a = a;
+        self
+    }
+
+    /// Parses `#RGB`, `#RGBA`, `#RRGGBB` or `#RRGGBBAA`, the `#` is optional. The components
+    /// are taken as they are like with [Color::new_rgba], so the result is still sRGB encoded.
+    pub fn from_hex(hex: &str) -> Result<Self, ColorParseError> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if let Some(invalid) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(ColorParseError::InvalidDigit(invalid));
+        }
+        let digit = |i: usize| u8::from_str_radix(&digits[i..i + 1], 16).unwrap();
+        let byte = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).unwrap();
+        match digits.len() {
+            3 | 4 => Ok(Self::new_rgba(
+                digit(0) * 17,
+                digit(1) * 17,
+                digit(2) * 17,
+                if digits.len() == 4 {
+                    digit(3) * 17
+                } else {
+                    255
+                },
+            )),
+            6 | 8 => Ok(Self::new_rgba(
+                byte(0),
+                byte(2),
+                byte(4),
+                if digits.len() == 8 { byte(6) } else { 255 },
+            )),
+            len => Err(ColorParseError::InvalidLength(len)),
+        }
+    }
+
+    /// `#RRGGBBAA` with the components rounded to bytes
+    pub fn to_hex(&self) -> String {
+        let [r, g, b, a] =
+            [self.r, self.g, self.b, self.a].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+        format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+    }
+
+    /// Hue in degrees, saturation and value from `0.0` to `1.0`
+    pub fn from_hsv(h: f32, s: f32, v: f32) -> Self {
+        let c = v * s;
+        let (r, g, b) = Self::hue_to_rgb(h, c);
+        let m = v - c;
+        Self::new(r + m, g + m, b + m, 1.0)
+    }
+
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let s = if max > 0.0 { (max - min) / max } else { 0.0 };
+        (self.hue(max, min), s, max)
+    }
+
+    /// Hue in degrees, saturation and lightness from `0.0` to `1.0`
+    pub fn from_hsl(h: f32, s: f32, l: f32) -> Self {
+        let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let (r, g, b) = Self::hue_to_rgb(h, c);
+        let m = l - c / 2.0;
+        Self::new(r + m, g + m, b + m, 1.0)
+    }
+
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let l = (max + min) / 2.0;
+        let s = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        (self.hue(max, min), s, l)
+    }
+
+    fn hue_to_rgb(h: f32, c: f32) -> (f32, f32, f32) {
+        let h = h.rem_euclid(360.0) / 60.0;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        }
+    }
+
+    fn hue(&self, max: f32, min: f32) -> f32 {
+        let delta = max - min;
+        let h = if delta == 0.0 {
+            0.0
+        } else if max == self.r {
+            ((self.g - self.b) / delta).rem_euclid(6.0)
+        } else if max == self.g {
+            (self.b - self.r) / delta + 2.0
+        } else {
+            (self.r - self.g) / delta + 4.0
+        };
+        h * 60.0
+    }
+
+    /// Decodes an sRGB color, e.g. from a color picker, to the linear color the shaders expect
+    pub fn to_linear(&self) -> Self {
+        let decode = |c: f32| {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        Self::new(decode(self.r), decode(self.g), decode(self.b), self.a)
+    }
+
+    pub fn to_srgb(&self) -> Self {
+        let encode = |c: f32| {
+            if c <= 0.0031308 {
+                c * 12.92
+            } else {
+                1.055 * c.powf(1.0 / 2.4) - 0.055
+            }
+        };
+        Self::new(encode(self.r), encode(self.g), encode(self.b), self.a)
+    }
+
+    /// Interpolates every component, which is physically correct since colors are linear
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t,
+        )
+    }
+
+    /// Interpolates in the Oklab color space. The colors in between look evenly spaced to the
+    /// eye and don't get muddy, e.g. from red to blue.
+    pub fn lerp_oklab(&self, other: &Self, t: f32) -> Self {
+        let from = self.to_oklab();
+        let to = other.to_oklab();
+        Self::from_oklab(
+            [
+                from[0] + (to[0] - from[0]) * t,
+                from[1] + (to[1] - from[1]) * t,
+                from[2] + (to[2] - from[2]) * t,
+            ],
+            self.a + (other.a - self.a) * t,
+        )
+    }
+
+    pub fn lerp_in(&self, other: &Self, t: f32, interpolation: ColorInterpolation) -> Self {
+        match interpolation {
+            ColorInterpolation::Linear => self.lerp(other, t),
+            ColorInterpolation::Oklab => self.lerp_oklab(other, t),
+        }
+    }
+
+    /// Lightness, green-red and blue-yellow axis of the linear color
+    pub fn to_oklab(&self) -> [f32; 3] {
+        let l = 0.41222147 * self.r + 0.53633254 * self.g + 0.05144599 * self.b;
+        let m = 0.21190350 * self.r + 0.68069955 * self.g + 0.10739696 * self.b;
+        let s = 0.08830246 * self.r + 0.28171884 * self.g + 0.62997878 * self.b;
+        let (l, m, s) = (l.cbrt(), m.cbrt(), s.cbrt());
+        [
+            0.21045426 * l + 0.79361778 * m - 0.00407205 * s,
+            1.97799850 * l - 2.42859221 * m + 0.45059371 * s,
+            0.02590404 * l + 0.78277177 * m - 0.80867577 * s,
+        ]
+    }
+
+    pub fn from_oklab([lightness, a, b]: [f32; 3], alpha: f32) -> Self {
+        let l = lightness + 0.39633778 * a + 0.21580376 * b;
+        let m = lightness - 0.10556135 * a - 0.06385417 * b;
+        let s = lightness - 0.08948418 * a - 1.29148555 * b;
+        let (l, m, s) = (l * l * l, m * m * m, s * s * s);
+        Self::new(
+            4.07674166 * l - 3.30771159 * m + 0.23096993 * s,
+            -1.26843800 * l + 2.60975740 * m - 0.34131940 * s,
+            -0.00419609 * l - 0.70341861 * m + 1.70761470 * s,
+            alpha,
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ColorParseError {
+    /// Only 3, 4, 6 or 8 digits are allowed
+    InvalidLength(usize),
+    InvalidDigit(char),
+}
+
+impl fmt::Display for ColorParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorParseError::InvalidLength(len) => {
+                write!(f, "Hex color has {len} digits, expected 3, 4, 6 or 8")
+            }
+            ColorParseError::InvalidDigit(digit) => {
+                write!(f, "'{digit}' is not a hexadecimal digit")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ColorParseError {}
+
+impl std::str::FromStr for Color {
+    type Err = ColorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(s)
+    }
+}
+
+/// Color space colors are blended in by [Gradient] and [Color::lerp_in]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorInterpolation {
+    #[default]
+    Linear,
+    Oklab,
+}
+
+/// Colors at positions, e.g. the color over the lifetime of a particle where `0.0` is the spawn
+/// and `1.0` the death. Positions before the first or after the last stop get its color.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gradient {
+    stops: Vec<(f32, Color)>,
+    interpolation: ColorInterpolation,
+}
+
+impl Gradient {
+    /// Panics if there are no stops
+    pub fn new(stops: &[(f32, Color)]) -> Self {
+        assert!(!stops.is_empty(), "Gradient needs at least one stop!");
+        let mut stops = stops.to_vec();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            stops,
+            interpolation: ColorInterpolation::default(),
+        }
+    }
+
+    pub fn constant(color: Color) -> Self {
+        Self::new(&[(0.0, color)])
+    }
+
+    /// Fades from `from` to `to`
+    pub fn fade(from: Color, to: Color) -> Self {
+        Self::new(&[(0.0, from), (1.0, to)])
+    }
+
+    pub fn interpolation(mut self, interpolation: ColorInterpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    pub fn sample(&self, t: f32) -> Color {
+        let next = self.stops.partition_point(|(stop, _)| *stop <= t);
+        if next == 0 {
+            return self.stops[0].1;
+        }
+        if next == self.stops.len() {
+            return self.stops[next - 1].1;
+        }
+        let (start, from) = self.stops[next - 1];
+        let (end, to) = self.stops[next];
+        from.lerp_in(&to, (t - start) / (end - start), self.interpolation)
+    }
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self::constant(Color::WHITE)
+    }
 }
 
 impl From<Color> for wgpu::Color {
@@ -196,3 +485,169 @@ impl From<Color> for [f32; 4] {
         [val.r, val.g, val.b, val.a]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Color, b: Color, epsilon: f32) {
+        let difference = [a.r - b.r, a.g - b.g, a.b - b.b, a.a - b.a];
+        assert!(
+            difference.iter().all(|d| d.abs() <= epsilon),
+            "{a:?} != {b:?}"
+        );
+    }
+
+    fn assert_components(a: [f32; 3], b: [f32; 3], epsilon: f32) {
+        assert!(
+            a.iter().zip(b).all(|(a, b)| (a - b).abs() <= epsilon),
+            "{a:?} != {b:?}"
+        );
+    }
+
+    /// Every component from 0 to 1 in steps of 0.125
+    fn samples() -> impl Iterator<Item = Color> {
+        let steps = (0..=8).map(|i| i as f32 / 8.0);
+        steps.clone().flat_map(move |r| {
+            let steps = steps.clone();
+            steps
+                .clone()
+                .flat_map(move |g| steps.clone().map(move |b| Color::new(r, g, b, 1.0)))
+        })
+    }
+
+    #[test]
+    fn hex_reference_colors() {
+        assert_close(
+            Color::from_hex("#ff8000").unwrap(),
+            Color::new(1.0, 128.0 / 255.0, 0.0, 1.0),
+            1e-6,
+        );
+        assert_close(
+            "0f08".parse().unwrap(),
+            Color::new(0.0, 1.0, 0.0, 136.0 / 255.0),
+            1e-6,
+        );
+        assert_eq!(Color::new_rgba(18, 52, 86, 255).to_hex(), "#123456ff");
+        assert_eq!(Color::WHITE.to_hex(), "#ffffffff");
+        assert_eq!(Color::TRANSPARENT.to_hex(), "#00000000");
+        assert_eq!(
+            Color::from_hex("#12345"),
+            Err(ColorParseError::InvalidLength(5))
+        );
+        assert_eq!(
+            Color::from_hex("#12g"),
+            Err(ColorParseError::InvalidDigit('g'))
+        );
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        for color in samples() {
+            let parsed = Color::from_hex(&color.to_hex()).unwrap();
+            assert_close(parsed, color, 0.5 / 255.0 + 1e-6);
+        }
+    }
+
+    #[test]
+    fn hsv_and_hsl_reference_colors() {
+        assert_close(
+            Color::from_hsv(0.0, 1.0, 1.0),
+            Color::new(1.0, 0.0, 0.0, 1.0),
+            1e-6,
+        );
+        assert_close(
+            Color::from_hsv(30.0, 1.0, 1.0),
+            Color::new(1.0, 0.5, 0.0, 1.0),
+            1e-6,
+        );
+        assert_close(
+            Color::from_hsv(240.0, 0.5, 0.5),
+            Color::new(0.25, 0.25, 0.5, 1.0),
+            1e-6,
+        );
+        assert_close(
+            Color::from_hsv(-120.0, 1.0, 1.0),
+            Color::new(0.0, 0.0, 1.0, 1.0),
+            1e-6,
+        );
+        assert_close(
+            Color::from_hsl(120.0, 1.0, 0.5),
+            Color::new(0.0, 1.0, 0.0, 1.0),
+            1e-6,
+        );
+        assert_close(
+            Color::from_hsl(0.0, 0.0, 0.75),
+            Color::new(0.75, 0.75, 0.75, 1.0),
+            1e-6,
+        );
+
+        let color = Color::new(0.2, 0.4, 0.6, 1.0);
+        let (h, s, v) = color.to_hsv();
+        assert_components([h, s, v], [210.0, 2.0 / 3.0, 0.6], 1e-4);
+        let (h, s, l) = color.to_hsl();
+        assert_components([h, s, l], [210.0, 0.5, 0.4], 1e-4);
+    }
+
+    #[test]
+    fn hsv_and_hsl_round_trip() {
+        for color in samples() {
+            let (h, s, v) = color.to_hsv();
+            assert_close(Color::from_hsv(h, s, v), color, 1e-5);
+            let (h, s, l) = color.to_hsl();
+            assert_close(Color::from_hsl(h, s, l), color, 1e-5);
+        }
+    }
+
+    #[test]
+    fn srgb_reference_values() {
+        assert_close(
+            Color::new(0.5, 0.0, 1.0, 0.5).to_linear(),
+            Color::new(0.21404114, 0.0, 1.0, 0.5),
+            1e-6,
+        );
+        assert_close(
+            Color::new(0.5, 0.002, 1.0, 1.0).to_srgb(),
+            Color::new(0.7353569, 0.002 * 12.92, 1.0, 1.0),
+            1e-6,
+        );
+        for color in samples() {
+            assert_close(color.to_linear().to_srgb(), color, 1e-5);
+            assert_close(color.to_srgb().to_linear(), color, 1e-5);
+        }
+    }
+
+    #[test]
+    fn oklab_reference_colors() {
+        assert_components(Color::WHITE.to_oklab(), [1.0, 0.0, 0.0], 1e-4);
+        assert_components(Color::BLACK.to_oklab(), [0.0, 0.0, 0.0], 1e-6);
+        let primaries = [
+            (
+                Color::new(1.0, 0.0, 0.0, 1.0),
+                [0.6279554, 0.2248631, 0.1258463],
+            ),
+            (
+                Color::new(0.0, 1.0, 0.0, 1.0),
+                [0.8664396, -0.2338876, 0.1794985],
+            ),
+            (
+                Color::new(0.0, 0.0, 1.0, 1.0),
+                [0.4520137, -0.0324570, -0.3115281],
+            ),
+        ];
+        for (color, oklab) in primaries {
+            assert_components(color.to_oklab(), oklab, 1e-4);
+        }
+    }
+
+    #[test]
+    fn oklab_round_trip() {
+        for color in samples() {
+            let alpha = color.r;
+            let color = color.with_alpha(alpha);
+            assert_close(Color::from_oklab(color.to_oklab(), alpha), color, 1e-4);
+        }
+        let middle = Color::BLACK.lerp_oklab(&Color::WHITE, 0.5);
+        assert_components(middle.to_oklab(), [0.5, 0.0, 0.0], 1e-4);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::graphics::{AssetKey, Gpu, Uniform};
+
+/// Six square images of the same size in the order +X, -X, +Y, -Y, +Z, -Z
+pub struct CubemapBuilder<'a> {
+    pub label: Option<&'a str>,
+    pub size: u32,
+    pub faces: [image::RgbaImage; 6],
+    pub format: wgpu::TextureFormat,
+    pub sampler: wgpu::SamplerDescriptor<'a>,
+}
+
+impl<'a> CubemapBuilder<'a> {
+    pub fn faces(faces: [&[u8]; 6]) -> Self {
+        Self::images(faces.map(|bytes| image::load_from_memory(bytes).unwrap()))
+    }
+
+    pub fn resources(paths: [&str; 6]) -> Self {
+        let resources = crate::app::global_resources();
+        Self::faces(
+            paths
+                .map(|path| resources.load_bytes(path).unwrap())
+                .each_ref()
+                .map(|bytes| &bytes[..]),
+        )
+    }
+
+    /// Panics if the faces are not square or differ in size
+    pub fn images(faces: [image::DynamicImage; 6]) -> Self {
+        let size = faces[0].width();
+        for (index, face) in faces.iter().enumerate() {
+            assert!(
+                face.width() == size && face.height() == size,
+                "Cubemap face {index} is {}x{} but all faces must be {size}x{size}!",
+                face.width(),
+                face.height()
+            );
+        }
+        Self {
+            label: None,
+            size,
+            faces: faces.map(|face| face.to_rgba8()),
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            sampler: Cubemap::DEFAULT_SAMPLER,
+        }
+    }
+
+    pub fn label(mut self, label: Option<&'a str>) -> Self {
+        self.label = label;
+        self
+    }
+
+    pub fn format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn sampler(mut self, sampler: wgpu::SamplerDescriptor<'a>) -> Self {
+        self.sampler = sampler;
+        self
+    }
+}
+
+/// Cube texture, used as the background of 3D scenes through [Sky]
+#[derive(Debug)]
+pub struct Cubemap {
+    _texture: wgpu::Texture,
+    _view: wgpu::TextureView,
+    _sampler: wgpu::Sampler,
+    bind_group: wgpu::BindGroup,
+    size: u32,
+}
+
+impl Cubemap {
+    /// Cube sampling is seamless across faces on every backend, the edges only have to be
+    /// clamped
+    pub const DEFAULT_SAMPLER: wgpu::SamplerDescriptor<'static> = wgpu::SamplerDescriptor {
+        label: None,
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        lod_min_clamp: 0.0,
+        lod_max_clamp: 32.0,
+        compare: None,
+        anisotropy_clamp: 1,
+        border_color: None,
+    };
+
+    /// Panics if the faces are larger than the texture size limit of the device, which is
+    /// only 2048 on WebGL
+    pub fn new(gpu: &Gpu, desc: CubemapBuilder) -> Self {
+        let max_size = gpu.device.limits().max_texture_dimension_2d;
+        assert!(
+            desc.size != 0 && desc.size <= max_size,
+            "Cubemap faces are {0}x{0} but the device supports at most {max_size}x{max_size}!",
+            desc.size
+        );
+
+        let extent = wgpu::Extent3d {
+            width: desc.size,
+            height: desc.size,
+            depth_or_array_layers: 6,
+        };
+        let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: desc.label.or(Some("cubemap_texture")),
+            size: extent,
+            format: desc.format,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        for (layer, face) in desc.faces.iter().enumerate() {
+            gpu.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                face,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(desc.format.block_copy_size(None).unwrap() * desc.size),
+                    rows_per_image: Some(desc.size),
+                },
+                wgpu::Extent3d {
+                    depth_or_array_layers: 1,
+                    ..extent
+                },
+            );
+        }
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        let sampler = gpu.device.create_sampler(&desc.sampler);
+        let bind_group = gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &gpu.default_layouts().cubemap_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("cubemap_bind_group"),
+        });
+
+        Self {
+            _texture: texture,
+            _view: view,
+            _sampler: sampler,
+            bind_group,
+            size: desc.size,
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+}
+
+impl Uniform for Cubemap {
+    fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
+
+/// Background of the 3D scene, accessible as `ctx.sky`. When a cubemap is set it is drawn by
+/// [RenderEncoder::render3d](crate::graphics::RenderEncoder::render3d) before anything else.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sky {
+    cubemap: Option<AssetKey>,
+}
+
+impl Sky {
+    /// `cubemap` is the key of a [Cubemap] loaded with
+    /// [AssetManager::load_cubemap](crate::graphics::AssetManager::load_cubemap)
+    pub fn set_cubemap(&mut self, cubemap: AssetKey) {
+        self.cubemap = Some(cubemap);
+    }
+
+    pub fn clear(&mut self) {
+        self.cubemap = None;
+    }
+
+    pub fn cubemap(&self) -> Option<AssetKey> {
+        self.cubemap
+    }
+}
@@ -0,0 +1,263 @@
+use parking_lot::Mutex;
+
+#[cfg(feature = "text")]
+use crate::{
+    graphics::AssetKey,
+    math::Isometry2,
+    text::{Text, TextSection},
+};
+use crate::{
+    graphics::{
+        AssetManager, CameraBuffer2D, Color, ColorMesh2D, ColorVertex2D, Gpu, Index, RenderEncoder,
+        WorldCamera2D,
+    },
+    math::{Vector2, AABB},
+};
+
+struct DebugLine {
+    start: Vector2<f32>,
+    end: Vector2<f32>,
+    color: Color,
+}
+
+#[cfg(feature = "text")]
+struct DebugText {
+    position: Vector2<f32>,
+    text: String,
+    color: Color,
+}
+
+struct DebugState {
+    lines: Vec<DebugLine>,
+    #[cfg(feature = "text")]
+    texts: Vec<DebugText>,
+    line_width: f32,
+    circle_resolution: u32,
+    #[cfg(feature = "text")]
+    font: Option<AssetKey>,
+    #[cfg(feature = "text")]
+    text_size: f32,
+    vertices: Vec<ColorVertex2D>,
+    indices: Vec<Index>,
+    mesh: Option<ColorMesh2D>,
+    #[cfg(feature = "text")]
+    text: Option<Text>,
+}
+
+/// Immediate mode shapes in world space that are drawn on top of everything else and cleared
+/// after every frame. Nothing gets allocated until the first shape is drawn.
+pub struct DebugDraw {
+    state: Mutex<DebugState>,
+}
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DebugDraw {
+    pub const DEFAULT_LINE_WIDTH: f32 = 2.0;
+    pub const DEFAULT_CIRCLE_RESOLUTION: u32 = 32;
+
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(DebugState {
+                lines: Vec::new(),
+                #[cfg(feature = "text")]
+                texts: Vec::new(),
+                line_width: Self::DEFAULT_LINE_WIDTH,
+                circle_resolution: Self::DEFAULT_CIRCLE_RESOLUTION,
+                #[cfg(feature = "text")]
+                font: None,
+                #[cfg(feature = "text")]
+                text_size: 0.2,
+                vertices: Vec::new(),
+                indices: Vec::new(),
+                mesh: None,
+                #[cfg(feature = "text")]
+                text: None,
+            }),
+        }
+    }
+
+    pub fn line(&self, start: Vector2<f32>, end: Vector2<f32>, color: Color) {
+        self.state
+            .lock()
+            .lines
+            .push(DebugLine { start, end, color });
+    }
+
+    pub fn circle(&self, center: Vector2<f32>, radius: f32, color: Color) {
+        let mut state = self.state.lock();
+        let resolution = state.circle_resolution;
+        let point = |i: u32| {
+            let angle = i as f32 / resolution as f32 * std::f32::consts::TAU;
+            center + Vector2::new(angle.cos(), angle.sin()) * radius
+        };
+        for i in 0..resolution {
+            state.lines.push(DebugLine {
+                start: point(i),
+                end: point(i + 1),
+                color,
+            });
+        }
+    }
+
+    pub fn aabb(&self, aabb: AABB, color: Color) {
+        let min = *aabb.min();
+        let max = *aabb.max();
+        self.polygon(
+            &[
+                min,
+                Vector2::new(max.x, min.y),
+                max,
+                Vector2::new(min.x, max.y),
+            ],
+            color,
+        );
+    }
+
+    /// Outline of a closed polygon
+    pub fn polygon(&self, points: &[Vector2<f32>], color: Color) {
+        let mut state = self.state.lock();
+        for (i, start) in points.iter().enumerate() {
+            state.lines.push(DebugLine {
+                start: *start,
+                end: points[(i + 1) % points.len()],
+                color,
+            });
+        }
+    }
+
+    /// Only drawn once a font is set with [DebugDraw::set_font]
+    #[cfg(feature = "text")]
+    pub fn text(&self, position: Vector2<f32>, text: impl Into<String>) {
+        self.text_colored(position, text, Color::WHITE);
+    }
+
+    #[cfg(feature = "text")]
+    pub fn text_colored(&self, position: Vector2<f32>, text: impl Into<String>, color: Color) {
+        self.state.lock().texts.push(DebugText {
+            position,
+            text: text.into(),
+            color,
+        });
+    }
+
+    /// Width of all lines in pixels
+    pub fn set_line_width(&self, line_width: f32) {
+        self.state.lock().line_width = line_width;
+    }
+
+    pub fn set_circle_resolution(&self, resolution: u32) {
+        self.state.lock().circle_resolution = resolution.max(3);
+    }
+
+    #[cfg(feature = "text")]
+    pub fn set_font(&self, font: Option<AssetKey>) {
+        self.state.lock().font = font;
+    }
+
+    #[cfg(feature = "text")]
+    pub fn set_text_size(&self, text_size: f32) {
+        self.state.lock().text_size = text_size;
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.state.lock();
+        state.lines.clear();
+        #[cfg(feature = "text")]
+        state.texts.clear();
+    }
+
+    pub(crate) fn render(
+        &self,
+        gpu: &Gpu,
+        assets: &AssetManager,
+        encoder: &mut RenderEncoder,
+        camera: &WorldCamera2D,
+        camera_buffer: &CameraBuffer2D,
+        target_size: Vector2<u32>,
+    ) {
+        let mut state = self.state.lock();
+        let state = &mut *state;
+        #[cfg(feature = "text")]
+        let has_text = !state.texts.is_empty() && state.font.is_some();
+        #[cfg(not(feature = "text"))]
+        let has_text = false;
+        if state.lines.is_empty() && !has_text {
+            return;
+        }
+
+        if !state.lines.is_empty() {
+            let half_width = state.line_width * camera.fov().y / target_size.y.max(1) as f32;
+            state.vertices.clear();
+            state.indices.clear();
+            for line in &state.lines {
+                let direction = line.end - line.start;
+                let length = direction.norm();
+                if length <= f32::EPSILON {
+                    continue;
+                }
+                let normal = Vector2::new(-direction.y, direction.x) / length * half_width;
+                let offset = state.vertices.len() as Index;
+                state.vertices.extend(
+                    [
+                        line.start - normal,
+                        line.end - normal,
+                        line.end + normal,
+                        line.start + normal,
+                    ]
+                    .map(|pos| ColorVertex2D {
+                        pos,
+                        data: line.color,
+                    }),
+                );
+                state
+                    .indices
+                    .extend([0, 1, 2, 0, 2, 3].map(|index| offset + index));
+            }
+            let builder = (&state.vertices[..], &state.indices[..]);
+            match &mut state.mesh {
+                Some(mesh) => mesh.write(gpu, &builder),
+                None => state.mesh = Some(ColorMesh2D::new(gpu, &builder)),
+            }
+        }
+
+        #[cfg(feature = "text")]
+        if let (true, Some(font)) = (has_text, state.font) {
+            let sections = state
+                .texts
+                .iter()
+                .map(|text| TextSection {
+                    color: text.color,
+                    text: text.text.as_str(),
+                    size: state.text_size,
+                    offset: Isometry2::new(text.position, 0.0),
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>();
+            let mut font = assets.font_mut(font);
+            match &mut state.text {
+                Some(text) => text.write(gpu, &mut font, &sections),
+                None => state.text = Some(Text::new(gpu, &mut font, &sections)),
+            }
+        }
+
+        let mut renderer = encoder.renderer2d(None);
+        if let (false, Some(mesh)) = (state.lines.is_empty(), &state.mesh) {
+            renderer.draw_color_mesh(mesh, camera_buffer);
+        }
+        #[cfg(feature = "text")]
+        if let (true, Some(font), Some(text)) = (has_text, state.font, &state.text) {
+            renderer.draw_text(text, camera_buffer, &assets.font(font));
+        }
+        #[cfg(not(feature = "text"))]
+        let _ = assets;
+
+        state.lines.clear();
+        #[cfg(feature = "text")]
+        state.texts.clear();
+    }
+}
@@ -44,4 +44,8 @@ impl DepthBuffer {
     pub fn view(&self) -> &wgpu::TextureView {
         &self.view
     }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
 }
@@ -0,0 +1,268 @@
+use std::io::Cursor;
+
+use anyhow::{anyhow, bail, Result};
+use base64::Engine;
+
+use crate::{
+    graphics::ModelBuilder,
+    math::{Matrix3, Matrix4, Point3, Vector3},
+};
+
+const UNSUPPORTED_EXTENSIONS: &[&str] = &["KHR_draco_mesh_compression", "EXT_meshopt_compression"];
+
+impl ModelBuilder {
+    /// Loads a binary `.glb` or a `.gltf` whose buffers and images are embedded as base64 data
+    /// URIs. Every primitive becomes a mesh and every material a sprite, the node transforms are
+    /// baked into the vertices.
+    pub fn gltf(bytes: &[u8]) -> Result<Self> {
+        load_gltf(GltfData::embedded(bytes)?)
+    }
+
+    /// Loads a `.glb` or `.gltf` from the resources, external buffers and images are resolved
+    /// relative to `path`
+    pub fn gltf_resource(path: &str) -> Result<Self> {
+        let mut builder = load_gltf(GltfData::resource(path)?)?;
+        builder.resource_path = Some(path.to_owned());
+        Ok(builder)
+    }
+}
+
+/// Parsed glTF document with its loaded buffers and one encoded sprite per material
+pub(crate) struct GltfData {
+    pub document: gltf::Document,
+    pub buffers: Vec<Vec<u8>>,
+    pub sprites: Vec<Vec<u8>>,
+}
+
+impl GltfData {
+    pub fn embedded(bytes: &[u8]) -> Result<Self> {
+        Self::load(bytes, |uri| {
+            Err(anyhow!(
+                "External file {uri} requires loading the glTF from the resources"
+            ))
+        })
+    }
+
+    pub fn resource(path: &str) -> Result<Self> {
+        let resources = crate::app::global_resources();
+        let bytes = resources.load_bytes(path)?;
+        let mut dir: std::path::PathBuf = path.into();
+        dir.pop();
+        Self::load(&bytes, |uri| {
+            resources.load_bytes(dir.join(uri).to_str().unwrap())
+        })
+    }
+
+    fn load(bytes: &[u8], resolve: impl Fn(&str) -> Result<Vec<u8>>) -> Result<Self> {
+        let gltf::Gltf { document, blob } = gltf::Gltf::from_slice(bytes)?;
+        if let Some(extension) = document
+            .extensions_required()
+            .find(|extension| UNSUPPORTED_EXTENSIONS.contains(extension))
+        {
+            bail!("glTF extension {extension} is not supported");
+        }
+
+        let load_uri = |uri: &str| -> Result<Vec<u8>> {
+            match uri.strip_prefix("data:") {
+                Some(data) => {
+                    let (_, data) = data
+                        .split_once(";base64,")
+                        .ok_or_else(|| anyhow!("Only base64 data URIs are supported"))?;
+                    Ok(base64::engine::general_purpose::STANDARD.decode(data)?)
+                }
+                None => resolve(uri),
+            }
+        };
+
+        let mut buffers = Vec::new();
+        for buffer in document.buffers() {
+            buffers.push(match buffer.source() {
+                gltf::buffer::Source::Bin => blob
+                    .clone()
+                    .ok_or_else(|| anyhow!("glTF binary chunk is missing"))?,
+                gltf::buffer::Source::Uri(uri) => load_uri(uri)?,
+            });
+        }
+
+        let mut sprites = Vec::new();
+        for material in document.materials() {
+            let pbr = material.pbr_metallic_roughness();
+            let image = match pbr.base_color_texture() {
+                Some(info) => Some(match info.texture().source().source() {
+                    gltf::image::Source::View { view, .. } => {
+                        let buffer = &buffers[view.buffer().index()];
+                        buffer[view.offset()..view.offset() + view.length()].to_vec()
+                    }
+                    gltf::image::Source::Uri { uri, .. } => load_uri(uri)?,
+                }),
+                None => None,
+            };
+            sprites.push(material_sprite(image, pbr.base_color_factor())?);
+        }
+
+        Ok(Self {
+            document,
+            buffers,
+            sprites,
+        })
+    }
+
+    pub fn scene(&self) -> Result<gltf::Scene> {
+        self.document
+            .default_scene()
+            .or_else(|| self.document.scenes().next())
+            .ok_or_else(|| anyhow!("glTF file contains no scene"))
+    }
+}
+
+fn load_gltf(data: GltfData) -> Result<ModelBuilder> {
+    let mut meshes = Vec::new();
+    let scene = data.scene()?;
+    let mut nodes = scene
+        .nodes()
+        .map(|node| (node, Matrix4::identity()))
+        .collect::<Vec<_>>();
+    while let Some((node, parent)) = nodes.pop() {
+        let transform = parent * Matrix4::from(node.transform().matrix());
+        nodes.extend(node.children().map(|child| (child, transform)));
+        let Some(mesh) = node.mesh() else {
+            continue;
+        };
+
+        let normal_matrix = transform
+            .fixed_view::<3, 3>(0, 0)
+            .into_owned()
+            .try_inverse()
+            .unwrap_or_else(Matrix3::identity)
+            .transpose();
+        for (index, primitive) in mesh.primitives().enumerate() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                bail!("Only triangle primitives are supported");
+            }
+            let reader = primitive.reader(|buffer| Some(&data.buffers[buffer.index()]));
+            let positions = reader
+                .read_positions()
+                .ok_or_else(|| anyhow!("glTF primitive has no positions"))?
+                .flat_map(|p| {
+                    let p = transform.transform_point(&Point3::new(p[0], p[1], p[2]));
+                    [p.x, p.y, p.z]
+                })
+                .collect::<Vec<_>>();
+            let vertex_count = positions.len() / 3;
+            let normals = reader
+                .read_normals()
+                .map(|normals| {
+                    normals
+                        .flat_map(|n| {
+                            let n = (normal_matrix * Vector3::new(n[0], n[1], n[2]))
+                                .try_normalize(f32::EPSILON)
+                                .unwrap_or_default();
+                            [n.x, n.y, n.z]
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            // Flipped to the bottom left origin of OBJ texture coordinates
+            let texcoords = reader
+                .read_tex_coords(0)
+                .map(|uvs| uvs.into_f32().flat_map(|uv| [uv[0], 1.0 - uv[1]]).collect())
+                .unwrap_or_default();
+            let vertex_color = reader
+                .read_colors(0)
+                .map(|colors| colors.into_rgb_f32().flat_map(|color| color).collect())
+                .unwrap_or_default();
+            let indices = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..vertex_count as u32).collect(),
+            };
+
+            meshes.push(tobj::Model::new(
+                tobj::Mesh {
+                    positions,
+                    vertex_color,
+                    normals,
+                    texcoords,
+                    indices,
+                    material_id: primitive.material().index(),
+                    ..Default::default()
+                },
+                format!("{}_{index}", mesh.name().unwrap_or("mesh")),
+            ));
+        }
+    }
+
+    Ok(ModelBuilder {
+        meshes,
+        sprites: data.sprites,
+        resource_path: None,
+    })
+}
+
+/// Encoded image of a material with the base color factor applied
+fn material_sprite(image: Option<Vec<u8>>, factor: [f32; 4]) -> Result<Vec<u8>> {
+    if factor == [1.0; 4] {
+        if let Some(image) = image {
+            return Ok(image);
+        }
+    }
+
+    let mut image = match image {
+        Some(image) => image::load_from_memory(&image)?.into_rgba8(),
+        None => image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4])),
+    };
+    for pixel in image.pixels_mut() {
+        for (channel, factor) in pixel.0.iter_mut().zip(factor) {
+            *channel = (*channel as f32 * factor).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, image::ImageFormat::Png)?;
+    Ok(bytes.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unit cube with a normal per face, translated by 2 on the y axis and a red material
+    const CUBE: &[u8] = crate::include_resource_bytes!("tests/gltf/cube.glb");
+
+    #[test]
+    fn glb_cube() {
+        let model = ModelBuilder::gltf(CUBE).unwrap();
+        assert_eq!(model.meshes.len(), 1);
+        let mesh = &model.meshes[0].mesh;
+        assert_eq!(model.meshes[0].name, "cube_0");
+        assert_eq!(mesh.positions.len(), 24 * 3);
+        assert_eq!(mesh.normals.len(), 24 * 3);
+        assert!(mesh.texcoords.is_empty());
+        assert_eq!(mesh.indices.len(), 36);
+        assert!(mesh.indices.iter().all(|index| *index < 24));
+        assert_eq!(mesh.material_id, Some(0));
+
+        // The node translation is baked into the vertices
+        for position in mesh.positions.chunks(3) {
+            assert!(position[0].abs() == 0.5 && position[2].abs() == 0.5);
+            assert!(position[1] == 1.5 || position[1] == 2.5);
+        }
+        for normal in mesh.normals.chunks(3) {
+            assert_eq!(normal.iter().map(|n| n.abs()).sum::<f32>(), 1.0);
+        }
+
+        assert_eq!(model.sprites.len(), 1);
+        let sprite = image::load_from_memory(&model.sprites[0])
+            .unwrap()
+            .into_rgba8();
+        assert_eq!(sprite.dimensions(), (1, 1));
+        assert_eq!(sprite.get_pixel(0, 0).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn external_buffers_need_the_resources() {
+        let gltf = br#"{
+            "asset": { "version": "2.0" },
+            "buffers": [{ "uri": "cube.bin", "byteLength": 4 }]
+        }"#;
+        assert!(ModelBuilder::gltf(gltf).is_err());
+    }
+}
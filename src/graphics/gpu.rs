@@ -1,100 +1,350 @@
-use std::{ops::Deref, sync::Arc};
+use std::{
+    ops::Deref,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+};
 
-use parking_lot::Mutex;
+use parking_lot::{MappedMutexGuard, Mutex, MutexGuard};
 use wgpu::include_wgsl;
 use winit::window::Window;
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::graphics::FrameCapture;
+#[cfg(feature = "gltf")]
+use crate::graphics::SkinnedVertex3D;
+#[cfg(all(feature = "log", not(target_arch = "wasm32")))]
+use crate::log::error;
 #[cfg(feature = "log")]
-use crate::log::info;
+use crate::log::{info, warn};
 #[cfg(feature = "text")]
-use crate::text::{Font, FontBuilder, TextMesh, TextSection, TextVertex2D};
+use crate::text::{Font, FontBuilder, Text, TextInstance2D, TextSection};
 use crate::{
+    ecs::ParticleBlend,
     graphics::{
-        Camera, Camera2D, CameraBuffer, CameraBuffer2D, ColorInstance2D, ColorVertex2D,
-        DepthBuffer, Instance, Instance3D, InstanceBuffer, Mesh, MeshBuilder, MeshBuilder2D, Model,
-        ModelBuilder, PositionMesh2D, PositionVertex2D, RenderEncoder, Shader, ShaderConfig,
-        ShaderModule, ShaderModuleDescriptor, ShaderModuleSource, Sprite, SpriteArray,
-        SpriteArrayBuilder, SpriteArrayCropInstance2D, SpriteArrayInstance2D, SpriteArrayVertex2D,
-        SpriteBuilder, SpriteCropInstance2D, SpriteInstance2D, SpriteMesh2D, SpriteRenderTarget,
-        SpriteVertex2D, SurfaceRenderTarget, UniformData, UniformField, Vertex, Vertex3D,
-        VertexBuffers, WorldCamera3D,
+        AssetKey, BillboardInstance3D, BlendState, Bloom, BloomConfig, Camera, Camera2D,
+        CameraBuffer, CameraBuffer2D, ColorInstance2D, ColorVertex2D, Cubemap, CubemapBuilder,
+        DepthBuffer, GpuCapabilities, GpuProfiler, Instance, Instance3D, InstanceBuffer, Lights2D,
+        Lights3D, Mesh, MeshBuilder, MeshBuilder2D, MipmapGenerator, Model, ModelBuilder,
+        NinePatchBorder, NinePatchInstance2D, NinePatchSprite, PendingScreenshot, PipelineCache,
+        PipelineCacheStats, PositionMesh2D, PositionVertex2D, RenderEncoder, RenderTarget,
+        ScreenshotCallback, Shader, ShaderConfig, ShaderHandle, ShaderModule,
+        ShaderModuleDescriptor, ShaderModuleSource, Sprite, SpriteArray, SpriteArrayBuilder,
+        SpriteArrayCropInstance2D, SpriteArrayInstance2D, SpriteArrayVertex2D, SpriteBuilder,
+        SpriteCropInstance2D, SpriteInstance2D, SpriteMesh2D, SpriteRenderTarget, SpriteVertex2D,
+        SurfaceRenderTarget, TransientUniforms, UiCamera, UniformData, UniformField, UniformSlice,
+        Vertex, Vertex3D, VertexBuffers, WorldCamera3D,
     },
-    math::{Isometry2, Vector2},
+    math::{Isometry2, Matrix4, Vector2},
+    tilemap::{TileMap, TileMapBuilder},
+    time::Duration,
 };
 
 pub(crate) const RELATIVE_CAMERA_SIZE: f32 = 0.5;
 
 #[derive(Clone)]
 pub struct GpuConfig {
+    /// Backends to choose the adapter from. On wasm WebGPU is preferred and WebGL2 is used in
+    /// browsers without it. Defaults to the `WGPU_BACKEND` environment variable natively and the
+    /// `backend` query parameter of the page on wasm, e.g. `?backend=gl` to test WebGL2.
     pub backends: wgpu::Backends,
+    pub power_preference: wgpu::PowerPreference,
+    /// Only adapters whose name contains this text are used, ignoring case, e.g. `"NVIDIA"` to
+    /// pick the dedicated GPU on a machine with several
+    pub adapter_name_filter: Option<String>,
+    /// Features that the adapter doesn't support are left out with a warning
     pub device_features: wgpu::Features,
+    /// Downgraded to the limits of WebGL2 or of downlevel hardware if the adapter can't satisfy
+    /// them, see [Gpu::capabilities]
     pub device_limits: wgpu::Limits,
     pub max_samples: u8,
+    /// Additional sample counts every shader gets compiled for, so that render targets created
+    /// with [Gpu::create_render_target_msaa] can use them.
+    pub render_target_samples: Vec<u8>,
+    /// Measures the gpu time of every render pass, see [Gpu::pass_timings]. Needs
+    /// [wgpu::Features::TIMESTAMP_QUERY], adapters without it report no timings.
+    pub profiling: bool,
 }
 
 impl Default for GpuConfig {
     fn default() -> Self {
         Self {
-            backends: wgpu::Backends::all(),
+            backends: Self::backends_from_env().unwrap_or(wgpu::Backends::all()),
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            adapter_name_filter: None,
             device_features: wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
-            device_limits: if cfg!(target_arch = "wasm32") {
-                wgpu::Limits::downlevel_webgl2_defaults()
-            } else {
-                wgpu::Limits::default()
-            },
+            device_limits: wgpu::Limits::default(),
             max_samples: 4,
+            render_target_samples: Vec::new(),
+            profiling: false,
         }
     }
 }
 
+impl GpuConfig {
+    pub fn backends(mut self, backends: wgpu::Backends) -> Self {
+        self.backends = backends;
+        self
+    }
+
+    pub fn power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    pub fn required_features(mut self, features: wgpu::Features) -> Self {
+        self.device_features = features;
+        self
+    }
+
+    pub fn required_limits(mut self, limits: wgpu::Limits) -> Self {
+        self.device_limits = limits;
+        self
+    }
+
+    pub fn adapter_name_filter(mut self, filter: impl Into<String>) -> Self {
+        self.adapter_name_filter = Some(filter.into());
+        self
+    }
+
+    fn matches_adapter(&self, info: &wgpu::AdapterInfo) -> bool {
+        self.adapter_name_filter.as_ref().map_or(true, |filter| {
+            info.name.to_lowercase().contains(&filter.to_lowercase())
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn backends_from_env() -> Option<wgpu::Backends> {
+        wgpu::util::backend_bits_from_env()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn backends_from_env() -> Option<wgpu::Backends> {
+        let search = web_sys::window()?.location().search().ok()?;
+        let value = search
+            .trim_start_matches('?')
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("backend="))?;
+        Some(wgpu::util::parse_backends_from_comma_list(value))
+    }
+}
+
 pub struct Gpu {
     pub instance: wgpu::Instance,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub adapter: wgpu::Adapter,
-    pub surface: wgpu::Surface<'static>,
+    /// [None] while the app is suspended, e.g. in the background on Android
+    surface: Mutex<Option<wgpu::Surface<'static>>>,
     pub command_buffers: Mutex<Vec<wgpu::CommandBuffer>>,
     pub config: Mutex<wgpu::SurfaceConfiguration>,
 
     format: wgpu::TextureFormat,
+    capabilities: GpuCapabilities,
     default_layouts: DefaultLayouts,
     surface_size: Mutex<Vector2<u32>>,
     target_msaa: Mutex<Option<wgpu::Texture>>,
 
     samples: u32,
     sample_state: wgpu::MultisampleState,
+    pipeline_samples: Vec<u32>,
+    pipeline_cache: Mutex<PipelineCache>,
+    transient_uniforms: Mutex<TransientUniforms>,
+    profiler: Option<Mutex<GpuProfiler>>,
+    draw_calls: AtomicU32,
+    drawn_instances: AtomicU32,
+    mipmaps: MipmapGenerator,
+    screenshots: Mutex<Vec<PendingScreenshot>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    frame_capture: Mutex<Option<FrameCapture>>,
 }
 
 impl Gpu {
-    pub(crate) async fn new(window: Arc<Window>, gpu_config: GpuConfig) -> Self {
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: gpu_config.backends,
+    async fn request_adapter(
+        window: &Arc<Window>,
+        gpu_config: &GpuConfig,
+        backends: wgpu::Backends,
+    ) -> Option<(wgpu::Instance, wgpu::Surface<'static>, wgpu::Adapter)> {
+        let descriptor = wgpu::InstanceDescriptor {
+            backends,
             dx12_shader_compiler: wgpu::Dx12Compiler::Fxc,
             ..Default::default()
-        });
+        };
+        // Browsers can expose WebGPU without having an adapter for it. The canvas can't get a
+        // WebGL2 context anymore once a WebGPU surface was created, so it has to be checked first.
+        #[cfg(target_arch = "wasm32")]
+        let instance = wgpu::util::new_instance_with_webgpu_detection(descriptor).await;
+        #[cfg(not(target_arch = "wasm32"))]
+        let instance = wgpu::Instance::new(descriptor);
+
         // Important: Request surface before adapter!
-        let surface = instance.create_surface(window.clone()).unwrap();
+        let surface = instance.create_surface(window.clone()).ok()?;
+
+        // Browsers only hand out a single adapter, so the filter can only reject it there
+        #[cfg(not(target_arch = "wasm32"))]
+        if gpu_config.adapter_name_filter.is_some() {
+            let adapter = instance
+                .enumerate_adapters(backends)
+                .into_iter()
+                .find(|adapter| {
+                    gpu_config.matches_adapter(&adapter.get_info())
+                        && adapter.is_surface_supported(&surface)
+                })?;
+            return Some((instance, surface, adapter));
+        }
+
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
+                power_preference: gpu_config.power_preference,
                 compatible_surface: Some(&surface),
                 force_fallback_adapter: false,
             })
-            .await
-            .expect("Invalid Graphics Backend!");
+            .await?;
+        if !gpu_config.matches_adapter(&adapter.get_info()) {
+            return None;
+        }
+        Some((instance, surface, adapter))
+    }
+
+    /// Panic message when no adapter could be found, listing the adapters of this machine
+    fn no_adapter_message(gpu_config: &GpuConfig) -> String {
+        let mut message = format!(
+            "No graphics adapter found for the backends {:?}",
+            gpu_config.backends
+        );
+        if let Some(filter) = &gpu_config.adapter_name_filter {
+            message += &format!(" matching '{filter}'");
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+                backends: wgpu::Backends::all(),
+                ..Default::default()
+            });
+            let adapters = instance.enumerate_adapters(wgpu::Backends::all());
+            if adapters.is_empty() {
+                message += "! There are no adapters available.";
+            } else {
+                message += "! Available adapters:";
+                for adapter in adapters {
+                    let info = adapter.get_info();
+                    message += &format!(
+                        "\n  - {} ({:?}, {:?})",
+                        info.name, info.backend, info.device_type
+                    );
+                }
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            message += "! The browser supports neither WebGPU nor WebGL2.";
+        }
+        message
+    }
+
+    pub(crate) async fn new(window: Arc<Window>, gpu_config: GpuConfig) -> Self {
+        let (instance, surface, adapter) =
+            match Self::request_adapter(&window, &gpu_config, gpu_config.backends).await {
+                Some(adapter) => adapter,
+                None if gpu_config.backends.contains(wgpu::Backends::GL)
+                    && gpu_config.backends != wgpu::Backends::GL =>
+                {
+                    #[cfg(feature = "log")]
+                    warn!("No adapter found, falling back to WebGL2 / OpenGL");
+                    Self::request_adapter(&window, &gpu_config, wgpu::Backends::GL)
+                        .await
+                        .unwrap_or_else(|| panic!("{}", Self::no_adapter_message(&gpu_config)))
+                }
+                None => panic!("{}", Self::no_adapter_message(&gpu_config)),
+            };
+
+        let config = Self::default_config(&surface, &adapter, &window);
+        let gpu = Self::from_adapter(instance, adapter, Some(surface), config, gpu_config).await;
+        gpu.resume(&window);
+        gpu
+    }
+
+    /// Gpu without a window or surface, rendering only into render targets. `None` if there is
+    /// no adapter on this machine.
+    #[cfg(test)]
+    pub(crate) fn headless() -> Option<Self> {
+        let instance = wgpu::Instance::default();
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))?;
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width: 1,
+            height: 1,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: Vec::new(),
+            desired_maximum_frame_latency: 2,
+        };
+        let gpu_config = GpuConfig {
+            max_samples: 1,
+            ..Default::default()
+        };
+        Some(pollster::block_on(Self::from_adapter(
+            instance, adapter, None, config, gpu_config,
+        )))
+    }
+
+    async fn from_adapter(
+        instance: wgpu::Instance,
+        adapter: wgpu::Adapter,
+        surface: Option<wgpu::Surface<'static>>,
+        config: wgpu::SurfaceConfiguration,
+        gpu_config: GpuConfig,
+    ) -> Self {
+        let profiling =
+            gpu_config.profiling && adapter.features().contains(wgpu::Features::TIMESTAMP_QUERY);
+        #[cfg(feature = "log")]
+        {
+            if gpu_config.profiling && !profiling {
+                warn!("GPU profiling is not supported by the adapter!");
+            }
+        }
+        let mut required_features = gpu_config.device_features;
+        if profiling {
+            required_features |= wgpu::Features::TIMESTAMP_QUERY;
+        }
+        if !adapter.features().contains(required_features) {
+            #[cfg(feature = "log")]
+            warn!(
+                "The adapter doesn't support the features {:?}",
+                required_features - adapter.features()
+            );
+            required_features &= adapter.features();
+        }
+
+        let mut required_limits = gpu_config.device_limits.clone();
+        if !required_limits.check_limits(&adapter.limits()) {
+            required_limits = if adapter.get_info().backend == wgpu::Backend::Gl {
+                wgpu::Limits::downlevel_webgl2_defaults()
+            } else {
+                wgpu::Limits::downlevel_defaults()
+            };
+            #[cfg(feature = "log")]
+            warn!("The adapter doesn't support the device limits, using downlevel limits");
+        }
+        let required_limits = required_limits.using_resolution(adapter.limits());
 
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: None,
-                    required_features: gpu_config.device_features,
-                    required_limits: gpu_config.device_limits.using_resolution(adapter.limits()),
+                    required_features,
+                    required_limits: required_limits.clone(),
                     memory_hints: wgpu::MemoryHints::Performance,
                 },
                 None,
             )
             .await
             .expect("Cannot find device!");
+        let capabilities = GpuCapabilities::new(&adapter, required_features, required_limits);
 
         #[cfg(feature = "log")]
         {
@@ -103,31 +353,15 @@ impl Gpu {
             info!("Using WGPU backend: {:?}", adapter_info.backend);
         }
 
-        let config = Self::default_config(&surface, &adapter, &window);
         let format = config.format;
-        let max_samples = gpu_config.max_samples;
-        let sample_flags = adapter.get_texture_format_features(config.format).flags;
-        let samples: u32 = {
-            if max_samples >= 16
-                && sample_flags.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_X16)
-            {
-                16
-            } else if max_samples >= 8
-                && sample_flags.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_X8)
-            {
-                8
-            } else if max_samples >= 4
-                && sample_flags.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_X4)
-            {
-                4
-            } else if max_samples >= 2
-                && sample_flags.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_X2)
-            {
-                2
-            } else {
-                1
+        let samples = Self::supported_samples(&adapter, format, gpu_config.max_samples as u32);
+        let mut pipeline_samples = vec![samples];
+        for extra in &gpu_config.render_target_samples {
+            let extra = Self::supported_samples(&adapter, format, *extra as u32);
+            if !pipeline_samples.contains(&extra) {
+                pipeline_samples.push(extra);
             }
-        };
+        }
         let sample_state = wgpu::MultisampleState {
             count: samples,
             mask: !0,
@@ -136,14 +370,21 @@ impl Gpu {
         #[cfg(feature = "log")]
         {
             info!("Using multisample X{samples}");
+            info!("Compiling shaders for multisample {pipeline_samples:?}");
             info!("Using texture format: {:?}", config.format);
             info!("Using Present mode: {:?}", config.present_mode);
         }
 
-        let gpu = Self {
-            default_layouts: DefaultLayouts::new(&device),
+        let default_layouts = DefaultLayouts::new(&device);
+        let mipmaps = MipmapGenerator::new(&device, &default_layouts);
+        let profiler = profiling.then(|| Mutex::new(GpuProfiler::new(&device, &queue)));
+        Self {
+            profiler,
+            default_layouts,
+            mipmaps,
             config: Mutex::new(config),
-            surface,
+            surface: Mutex::new(surface),
+            capabilities,
             instance,
             queue,
             device,
@@ -152,15 +393,19 @@ impl Gpu {
             format,
             samples,
             sample_state,
+            pipeline_samples,
+            pipeline_cache: Default::default(),
+            transient_uniforms: Default::default(),
+            draw_calls: Default::default(),
+            drawn_instances: Default::default(),
 
-            // These get initialized below
+            // Initialized by Gpu::resume
             surface_size: Default::default(),
             target_msaa: Default::default(),
-        };
-
-        gpu.resume(&window);
-
-        gpu
+            screenshots: Default::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            frame_capture: Default::default(),
+        }
     }
 
     pub(crate) fn compute_surface_size(window: &Window) -> Vector2<u32> {
@@ -176,22 +421,60 @@ impl Gpu {
         window: &Window,
     ) -> wgpu::SurfaceConfiguration {
         let surface_size = Self::compute_surface_size(window);
-        surface
+        let mut config = surface
             .get_default_config(adapter, surface_size.x, surface_size.y)
-            .expect("Surface isn't supported by the adapter.")
+            .expect("Surface isn't supported by the adapter.");
+        // Allows screenshots of the window
+        if surface
+            .get_capabilities(adapter)
+            .usages
+            .contains(wgpu::TextureUsages::COPY_SRC)
+        {
+            config.usage |= wgpu::TextureUsages::COPY_SRC;
+        }
+        config
     }
 
-    pub(crate) fn resume(&self, window: &Window) {
+    /// Configures the surface for the current size of the window. A surface that was destroyed
+    /// by [Gpu::suspend] gets created again with the texture format of the pipelines.
+    pub(crate) fn resume(&self, window: &Arc<Window>) {
         #[cfg(feature = "log")]
         log::info!("Surface resume");
 
-        let config = Self::default_config(&self.surface, &self.adapter, window);
+        let mut surface = self.surface.lock();
+        let surface = surface.get_or_insert_with(|| {
+            self.instance
+                .create_surface(window.clone())
+                .expect("Cannot recreate the surface!")
+        });
+        let mut config = Self::default_config(surface, &self.adapter, window);
+        assert!(
+            surface
+                .get_capabilities(&self.adapter)
+                .formats
+                .contains(&self.format),
+            "The recreated surface doesn't support the texture format {:?}!",
+            self.format
+        );
+        config.format = self.format;
         self.update_msaa(Vector2::new(config.width, config.height));
-        self.surface.configure(&self.device, &config);
+        surface.configure(&self.device, &config);
         *self.surface_size.lock() = Vector2::new(config.width, config.height);
         *self.config.lock() = config;
     }
 
+    /// Destroys the surface, the native window it belongs to is gone until the app resumes
+    pub(crate) fn suspend(&self) {
+        #[cfg(feature = "log")]
+        log::info!("Surface suspend");
+
+        *self.surface.lock() = None;
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.surface.lock().is_none()
+    }
+
     /// Resize the surface, making sure to not resize to zero.
     pub(crate) fn resize(&self, size: Vector2<u32>) {
         self.update_msaa(size);
@@ -200,15 +483,18 @@ impl Gpu {
         config.width = size.x.max(1);
         config.height = size.y.max(1);
         *self.surface_size.lock() = Vector2::new(config.width, config.height);
-        self.surface.configure(&self.device, &config);
+        if let Some(surface) = &*self.surface.lock() {
+            surface.configure(&self.device, &config);
+        }
     }
 
     pub(crate) fn start_frame(&self, gpu: &Gpu) -> SurfaceRenderTarget {
         let config = self.config.lock();
-        let surface_texture = match self.surface.get_current_texture() {
+        let surface = self.surface();
+        let surface_texture = match surface.get_current_texture() {
             Ok(frame) => frame,
             // If we timed out, just try again
-            Err(wgpu::SurfaceError::Timeout) => self.surface
+            Err(wgpu::SurfaceError::Timeout) => surface
                 .get_current_texture()
                 .expect("Failed to acquire next surface texture!"),
             Err(
@@ -218,8 +504,8 @@ impl Gpu {
                 // If OutOfMemory happens, reconfiguring may not help, but we might as well try
                 | wgpu::SurfaceError::OutOfMemory,
             ) => {
-                self.surface.configure(&gpu.device, &config);
-                self.surface
+                surface.configure(&gpu.device, &config);
+                surface
                     .get_current_texture()
                     .expect("Failed to acquire next surface texture!")
             }
@@ -232,6 +518,7 @@ impl Gpu {
                 .lock()
                 .as_ref()
                 .map(|msaa| msaa.create_view(&Default::default())),
+            samples: self.samples(),
             surface_texture,
         };
     }
@@ -244,16 +531,119 @@ impl Gpu {
             wgpu::PresentMode::AutoNoVsync
         };
         config.present_mode = new_mode;
-        self.surface.configure(&self.device, &config);
+        if let Some(surface) = &*self.surface.lock() {
+            surface.configure(&self.device, &config);
+        }
     }
 
     pub(crate) fn update_msaa(&self, size: Vector2<u32>) {
         let mut target_msaa = self.target_msaa.lock();
         if self.samples() != 1 && (size != self.surface_size() || target_msaa.is_none()) {
-            *target_msaa = Some(SpriteRenderTarget::create_msaa(self, size));
+            *target_msaa = Some(SpriteRenderTarget::create_msaa(self, size, self.samples()));
         }
     }
 
+    /// Copies the target with the encoder and calls `callback` once the copy got read back,
+    /// usually one frame later
+    pub fn request_screenshot(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &dyn RenderTarget,
+        callback: ScreenshotCallback,
+    ) {
+        let screenshot = PendingScreenshot::new(self, encoder, target, callback);
+        self.screenshots.lock().push(screenshot);
+    }
+
+    /// Must be called after the encoders of the requested screenshots got submitted
+    pub(crate) fn poll_screenshots(&self) {
+        let mut screenshots = self.screenshots.lock();
+        if screenshots.is_empty() {
+            return;
+        }
+        self.device.poll(wgpu::Maintain::Poll);
+        *screenshots = std::mem::take(&mut *screenshots)
+            .into_iter()
+            .filter_map(|screenshot| screenshot.poll())
+            .collect();
+    }
+
+    /// Saves the window as numbered PNGs into `directory` at most `fps` times per second until
+    /// [Gpu::end_frame_capture] is called
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn begin_frame_capture(&self, directory: impl Into<std::path::PathBuf>, fps: u32) {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory).expect("Cannot create the frame capture directory!");
+        *self.frame_capture.lock() = Some(FrameCapture::new(directory, fps));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn end_frame_capture(&self) {
+        *self.frame_capture.lock() = None;
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn is_capturing_frames(&self) -> bool {
+        self.frame_capture.lock().is_some()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn capture_frame(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &dyn RenderTarget,
+    ) {
+        let Some(path) = self
+            .frame_capture
+            .lock()
+            .as_mut()
+            .and_then(|capture| capture.next_frame())
+        else {
+            return;
+        };
+        self.request_screenshot(
+            encoder,
+            target,
+            Box::new(move |image| {
+                std::thread::spawn(move || {
+                    if let Err(_err) = image.save(&path) {
+                        #[cfg(feature = "log")]
+                        error!("Failed to save frame {}: {_err}", path.display());
+                    }
+                });
+            }),
+        );
+    }
+
+    fn supported_samples(adapter: &wgpu::Adapter, format: wgpu::TextureFormat, max: u32) -> u32 {
+        let flags = adapter.get_texture_format_features(format).flags;
+        [16, 8, 4, 2]
+            .into_iter()
+            .find(|samples| *samples <= max && flags.sample_count_supported(*samples))
+            .unwrap_or(1)
+    }
+
+    /// Returns `samples` if render targets can use it, otherwise the closest lower sample count
+    /// that is supported by the adapter and that the shaders got compiled for.
+    pub fn validate_samples(&self, samples: u32) -> u32 {
+        let supported = Self::supported_samples(&self.adapter, self.format, samples);
+        let validated = self
+            .pipeline_samples
+            .iter()
+            .copied()
+            .filter(|s| *s <= supported)
+            .max()
+            .unwrap_or(1);
+        #[cfg(feature = "log")]
+        if validated != samples {
+            warn!(
+                "Multisample X{samples} is not available for render targets, using X{validated} instead. \
+                Add it to GpuConfig::render_target_samples if the adapter supports it."
+            );
+        }
+        validated
+    }
+
     pub fn block(&self, handle: wgpu::SubmissionIndex) {
         self.device
             .poll(wgpu::MaintainBase::WaitForSubmissionIndex(handle));
@@ -265,10 +655,45 @@ impl Gpu {
         self.queue.submit(command_buffers)
     }
 
+    /// Fills every mip level of the texture from level 0
+    pub fn generate_mipmaps(&self, texture: &wgpu::Texture) {
+        if texture.mip_level_count() <= 1 {
+            return;
+        }
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("mipmap_encoder"),
+            });
+        self.mipmaps
+            .generate(&self.device, &self.default_layouts, &mut encoder, texture);
+        self.queue.submit(Some(encoder.finish()));
+    }
+
     pub fn create_render_target(&self, size: Vector2<u32>) -> SpriteRenderTarget {
         SpriteRenderTarget::new(self, size)
     }
 
+    pub fn create_render_target_msaa(
+        &self,
+        size: Vector2<u32>,
+        samples: u32,
+    ) -> SpriteRenderTarget {
+        SpriteRenderTarget::new_msaa(self, size, samples)
+    }
+
+    pub fn create_lights2d(&self, lights: &Lights2D) -> UniformData<Lights2D> {
+        lights.uniform(self)
+    }
+
+    pub fn create_bloom(&self, config: BloomConfig) -> Bloom {
+        Bloom::new(self, config, self.surface_size())
+    }
+
+    pub fn create_tilemap(&self, builder: TileMapBuilder) -> TileMap {
+        TileMap::new(self, builder)
+    }
+
     pub fn create_depth_buffer(
         &self,
         size: Vector2<u32>,
@@ -304,6 +729,18 @@ impl Gpu {
         Sprite::new(self, desc)
     }
 
+    pub fn create_cubemap(&self, desc: CubemapBuilder) -> Cubemap {
+        Cubemap::new(self, desc)
+    }
+
+    pub fn create_nine_patch_sprite<D: Deref<Target = [u8]>>(
+        &self,
+        desc: SpriteBuilder<D>,
+        border: NinePatchBorder,
+    ) -> NinePatchSprite {
+        NinePatchSprite::new(self, desc, border)
+    }
+
     pub fn create_sprite_array<D: Deref<Target = [u8]>>(
         &self,
         desc: SpriteArrayBuilder<D>,
@@ -319,10 +756,128 @@ impl Gpu {
         UniformData::new(self, layout, &[data])
     }
 
+    /// Shaders with the same config share their pipelines, see [Gpu::pipeline_cache_stats]
     pub fn create_shader(&self, config: ShaderConfig) -> Shader {
         Shader::new(self, config)
     }
 
+    /// Compiles the shader on another thread, so the frame that first uses it does not wait for
+    /// the driver. Renderers skip draws with the shader until [ShaderHandle::is_ready]. If
+    /// compiling fails the reason is stored in [ShaderHandle::error]. On the web the shader is
+    /// compiled immediately.
+    pub fn create_shader_async(self: &Arc<Self>, config: ShaderConfig<'static>) -> ShaderHandle {
+        let handle = ShaderHandle::default();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let gpu = self.clone();
+            let shader = handle.clone();
+            std::thread::spawn(move || {
+                match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    Shader::new(&gpu, config)
+                })) {
+                    Ok(compiled) => shader.set(compiled),
+                    Err(panic) => {
+                        let error = panic
+                            .downcast_ref::<&str>()
+                            .map(|message| message.to_string())
+                            .or_else(|| panic.downcast_ref::<String>().cloned())
+                            .unwrap_or_else(|| "Unknown error".to_owned());
+                        #[cfg(feature = "log")]
+                        error!("Cannot compile shader: {error}");
+                        shader.fail(error);
+                    }
+                }
+            });
+        }
+        #[cfg(target_arch = "wasm32")]
+        handle.set(Shader::new(self, config));
+        handle
+    }
+
+    pub fn pipeline_cache_stats(&self) -> PipelineCacheStats {
+        self.pipeline_cache.lock().stats()
+    }
+
+    /// Forgets the cached pipelines, existing shaders keep theirs. Reloaded shader modules are
+    /// new modules, so without clearing the pipelines of previous versions stay alive.
+    pub fn clear_pipeline_cache(&self) {
+        self.pipeline_cache.lock().clear();
+    }
+
+    pub(crate) fn pipeline_cache(&self) -> parking_lot::MutexGuard<PipelineCache> {
+        self.pipeline_cache.lock()
+    }
+
+    /// Writes a uniform that is only valid until the end of the current frame. All transient
+    /// uniforms of a frame are packed into a few shared buffers that are reused every frame, so
+    /// this is cheap enough to call per draw. Bind the slice with
+    /// [Renderer::use_uniform_slice](crate::graphics::Renderer::use_uniform_slice).
+    pub fn transient_uniform<T: bytemuck::Pod>(&self, value: &T) -> UniformSlice {
+        self.transient_uniforms
+            .lock()
+            .push(self, bytemuck::bytes_of(value))
+    }
+
+    /// Amount of buffers that back the transient uniforms
+    pub fn transient_uniform_buffers(&self) -> usize {
+        self.transient_uniforms.lock().buffers()
+    }
+
+    /// Draw calls and instances since the start of the current frame
+    pub fn render_stats(&self) -> RenderStats {
+        RenderStats {
+            draw_calls: self.draw_calls.load(Ordering::Relaxed),
+            instances: self.drawn_instances.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn count_draw(&self, instances: u32) {
+        self.draw_calls.fetch_add(1, Ordering::Relaxed);
+        self.drawn_instances.fetch_add(instances, Ordering::Relaxed);
+    }
+
+    pub(crate) fn take_render_stats(&self) -> RenderStats {
+        RenderStats {
+            draw_calls: self.draw_calls.swap(0, Ordering::Relaxed),
+            instances: self.drawn_instances.swap(0, Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn reset_transient_uniforms(&self) {
+        self.transient_uniforms.lock().reset();
+    }
+
+    /// GPU time of the render passes of a recent frame, passes with the same label are summed
+    /// up. Render systems are labeled with the name of their
+    /// [RenderPassConfig](crate::ecs::RenderPassConfig). Empty unless [GpuConfig::profiling] is
+    /// enabled and supported.
+    pub fn pass_timings(&self) -> Vec<(String, Duration)> {
+        self.profiler
+            .as_ref()
+            .map(|profiler| profiler.lock().timings().to_vec())
+            .unwrap_or_default()
+    }
+
+    pub fn is_profiling(&self) -> bool {
+        self.profiler.is_some()
+    }
+
+    pub(crate) fn profiler(&self) -> Option<parking_lot::MutexGuard<GpuProfiler>> {
+        self.profiler.as_ref().map(|profiler| profiler.lock())
+    }
+
+    pub(crate) fn poll_profiler(&self) {
+        if let Some(profiler) = &self.profiler {
+            profiler.lock().poll(&self.device);
+        }
+    }
+
+    pub(crate) fn finish_profiler_frame(&self) {
+        if let Some(profiler) = &self.profiler {
+            profiler.lock().finish_frame(&self.device, &self.queue);
+        }
+    }
+
     pub fn create_shader_module(&self, desc: ShaderModuleDescriptor<'_>) -> ShaderModule {
         self.device.create_shader_module(desc)
     }
@@ -333,12 +888,8 @@ impl Gpu {
     }
 
     #[cfg(feature = "text")]
-    pub fn create_text_mesh<S: AsRef<str>>(
-        &self,
-        font: &Font,
-        sections: &[TextSection<S>],
-    ) -> TextMesh {
-        TextMesh::new(self, font, sections)
+    pub fn create_text<S: AsRef<str>>(&self, font: &mut Font, sections: &[TextSection<S>]) -> Text {
+        Text::new(self, font, sections)
     }
 
     pub fn create_computed_target<D: Deref<Target = [u8]>>(
@@ -353,6 +904,11 @@ impl Gpu {
         self.samples
     }
 
+    /// Name, vendor, backend and driver of the adapter in use
+    pub fn adapter_info(&self) -> wgpu::AdapterInfo {
+        self.adapter.get_info()
+    }
+
     pub fn format(&self) -> wgpu::TextureFormat {
         self.format
     }
@@ -361,6 +917,15 @@ impl Gpu {
         self.sample_state
     }
 
+    /// Sample counts every [Shader] gets compiled for. The first one is [Gpu::samples].
+    pub fn pipeline_samples(&self) -> &[u32] {
+        &self.pipeline_samples
+    }
+
+    pub fn capabilities(&self) -> &GpuCapabilities {
+        &self.capabilities
+    }
+
     pub fn default_layouts(&self) -> &DefaultLayouts {
         &self.default_layouts
     }
@@ -369,8 +934,11 @@ impl Gpu {
         *self.surface_size.lock()
     }
 
-    pub fn surface(&self) -> &wgpu::Surface {
-        &self.surface
+    /// Panics while the app is suspended
+    pub fn surface(&self) -> MappedMutexGuard<wgpu::Surface<'static>> {
+        MutexGuard::map(self.surface.lock(), |surface| {
+            surface.as_mut().expect("The surface is suspended!")
+        })
     }
 
     pub fn surface_config(&self) -> wgpu::SurfaceConfiguration {
@@ -378,12 +946,25 @@ impl Gpu {
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub draw_calls: u32,
+    pub instances: u32,
+}
+
 #[derive(Debug)]
 pub struct DefaultLayouts {
     pub sprite_array_layout: Arc<wgpu::BindGroupLayout>,
     pub sprite_layout: Arc<wgpu::BindGroupLayout>,
     pub camera_layout: Arc<wgpu::BindGroupLayout>,
     pub single_uniform_layout: Arc<wgpu::BindGroupLayout>,
+    pub cubemap_layout: Arc<wgpu::BindGroupLayout>,
+    /// Uniform with a dynamic offset, used by [UniformSlice]
+    pub dynamic_uniform_layout: Arc<wgpu::BindGroupLayout>,
+    /// Joint matrices of a [SkinComponent](crate::animation::SkinComponent), visible to the
+    /// vertex shader
+    #[cfg(feature = "gltf")]
+    pub joints_layout: Arc<wgpu::BindGroupLayout>,
 }
 
 impl DefaultLayouts {
@@ -425,6 +1006,36 @@ impl DefaultLayouts {
                 label: Some("uniform_bind_group_layout"),
             });
 
+        let dynamic_uniform_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+                label: Some("dynamic_uniform_bind_group_layout"),
+            });
+
+        #[cfg(feature = "gltf")]
+        let joints_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+            label: Some("joints_bind_group_layout"),
+        });
+
         let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[wgpu::BindGroupLayoutEntry {
                 binding: 0,
@@ -462,11 +1073,37 @@ impl DefaultLayouts {
                 ],
             });
 
+        let cubemap_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("cubemap_layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::Cube,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+
         Self {
             sprite_array_layout: sprite_array_layout.into(),
             sprite_layout: sprite_layout.into(),
             camera_layout: camera_layout.into(),
             single_uniform_layout: single_uniform_layout.into(),
+            cubemap_layout: cubemap_layout.into(),
+            dynamic_uniform_layout: dynamic_uniform_layout.into(),
+            #[cfg(feature = "gltf")]
+            joints_layout: joints_layout.into(),
         }
     }
 }
@@ -475,33 +1112,52 @@ impl DefaultLayouts {
 pub struct DefaultAssets {
     // 2D
     pub sprite_shader: Shader,
+    pub sprite_lit_shader: Shader,
     pub color_shader: Shader,
     pub sprite_array_shader: Shader,
     pub sprite_crop_shader: Shader,
+    pub particle_shader: Shader,
+    pub particle_additive_shader: Shader,
     pub sprite_array_crop_shader: Shader,
+    pub nine_patch_shader: Shader,
 
     pub mesh_color_shader: Shader,
     pub mesh_sprite_shader: Shader,
     pub mesh_sprite_array_shader: Shader,
-    pub mesh_text_shader: Shader,
+    #[cfg(feature = "text")]
+    pub text_shader: Shader,
 
     pub missing_sprite: Sprite,
 
     // 3D
     pub model_shader: Shader,
+    pub model_lit_shader: Shader,
+    pub skybox_shader: Shader,
+    pub billboard_shader: Shader,
+    #[cfg(feature = "gltf")]
+    pub skinned_model_shader: Shader,
     pub depth_buffer: DepthBuffer,
 
     pub sprite_mesh: SpriteMesh2D,
     pub position_mesh: PositionMesh2D,
+    pub nine_patch_mesh: PositionMesh2D,
     pub times: UniformData<[f32; 2]>,
     pub world_camera2d: CameraBuffer2D,
     pub world_camera3d: CameraBuffer<WorldCamera3D>,
+    /// Lights of the current scene, see [Context::lights3d](crate::context::Context::lights3d)
+    pub lights3d: UniformData<Lights3D>,
+    /// Cubemap of the current scene, see [Context::sky](crate::context::Context::sky)
+    pub sky: Option<AssetKey>,
+    /// See [WorldCamera3D::sky_matrix]
+    pub sky_camera: UniformData<Matrix4<f32>>,
     pub relative_camera: (CameraBuffer2D, Camera2D),
     pub relative_bottom_left_camera: (CameraBuffer2D, Camera2D),
     pub relative_bottom_right_camera: (CameraBuffer2D, Camera2D),
     pub relative_top_left_camera: (CameraBuffer2D, Camera2D),
     pub relative_top_right_camera: (CameraBuffer2D, Camera2D),
     pub unit_camera: (CameraBuffer2D, Camera2D),
+    /// See [ScreenConfig::set_ui_scaling](crate::graphics::ScreenConfig::set_ui_scaling)
+    pub ui_camera: (CameraBuffer2D, UiCamera),
     #[cfg(feature = "framebuffer")]
     pub framebuffer: SpriteRenderTarget,
 }
@@ -525,6 +1181,85 @@ impl DefaultAssets {
             ..Default::default()
         });
 
+        let model_lit_shader = gpu.create_shader(ShaderConfig {
+            name: Some("model_lit"),
+            uniforms: &[
+                UniformField::Camera,
+                UniformField::Sprite,
+                UniformField::SingleUniform,
+            ],
+            source: ShaderModuleSource::Single(
+                &gpu.create_shader_module(include_wgsl!("../../static/shader/3d/model_lit.wgsl")),
+            ),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DepthBuffer::DEPTH_FORMAT_3D,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            vertex_buffers: VertexBuffers::instance::<Vertex3D, Instance3D>(),
+            ..Default::default()
+        });
+
+        let billboard_shader = gpu.create_shader(ShaderConfig {
+            name: Some("billboard"),
+            uniforms: &[UniformField::Camera, UniformField::Sprite],
+            source: ShaderModuleSource::Single(
+                &gpu.create_shader_module(include_wgsl!("../../static/shader/3d/billboard.wgsl")),
+            ),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DepthBuffer::DEPTH_FORMAT_3D,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            vertex_buffers: VertexBuffers::instance::<SpriteVertex2D, BillboardInstance3D>(),
+            ..Default::default()
+        });
+
+        let skybox_shader = gpu.create_shader(ShaderConfig {
+            name: Some("skybox"),
+            uniforms: &[UniformField::SingleUniform, UniformField::Cubemap],
+            source: ShaderModuleSource::Single(
+                &gpu.create_shader_module(include_wgsl!("../../static/shader/3d/skybox.wgsl")),
+            ),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DepthBuffer::DEPTH_FORMAT_3D,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            vertex_buffers: VertexBuffers::vertex::<SpriteVertex2D>(),
+            blend: BlendState::REPLACE,
+            ..Default::default()
+        });
+
+        #[cfg(feature = "gltf")]
+        let skinned_model_shader =
+            gpu.create_shader(ShaderConfig {
+                name: Some("skinned_model"),
+                uniforms: &[
+                    UniformField::Camera,
+                    UniformField::Sprite,
+                    UniformField::Custom(&gpu.default_layouts.joints_layout),
+                ],
+                source: ShaderModuleSource::Single(&gpu.create_shader_module(include_wgsl!(
+                    "../../static/shader/3d/skinned_model.wgsl"
+                ))),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DepthBuffer::DEPTH_FORMAT_3D,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                vertex_buffers: VertexBuffers::instance::<SkinnedVertex3D, Instance3D>(),
+                ..Default::default()
+            });
+
         let color_shader = gpu.create_shader(ShaderConfig {
             name: Some("color"),
             source: ShaderModuleSource::Single(
@@ -545,6 +1280,21 @@ impl DefaultAssets {
             ..Default::default()
         });
 
+        let sprite_lit_shader = gpu.create_shader(ShaderConfig {
+            name: Some("sprite_lit"),
+            source: ShaderModuleSource::Single(
+                &gpu.create_shader_module(include_wgsl!("../../static/shader/2d/sprite_lit.wgsl")),
+            ),
+            uniforms: &[
+                UniformField::Camera,
+                UniformField::Sprite,
+                UniformField::Sprite,
+                UniformField::SingleUniform,
+            ],
+            vertex_buffers: VertexBuffers::instance::<SpriteVertex2D, SpriteInstance2D>(),
+            ..Default::default()
+        });
+
         let sprite_crop_shader = gpu.create_shader(ShaderConfig {
             name: Some("sprite_crop"),
             source: ShaderModuleSource::Single(
@@ -555,6 +1305,23 @@ impl DefaultAssets {
             ..Default::default()
         });
 
+        let particle_shader =
+            |name, blend| {
+                gpu.create_shader(ShaderConfig {
+                    name: Some(name),
+                    source: ShaderModuleSource::Single(&gpu.create_shader_module(include_wgsl!(
+                        "../../static/shader/2d/particle.wgsl"
+                    ))),
+                    uniforms: &[UniformField::Camera, UniformField::Sprite],
+                    vertex_buffers: VertexBuffers::instance::<SpriteVertex2D, ColorInstance2D>(),
+                    blend,
+                    ..Default::default()
+                })
+            };
+        let particle_additive_shader =
+            particle_shader("particle_additive", ParticleBlend::Additive.blend_state());
+        let particle_shader = particle_shader("particle", ParticleBlend::Alpha.blend_state());
+
         let sprite_array_shader =
             gpu.create_shader(ShaderConfig {
                 name: Some("sprite_array"),
@@ -576,6 +1343,16 @@ impl DefaultAssets {
             ..Default::default()
         });
 
+        let nine_patch_shader = gpu.create_shader(ShaderConfig {
+            name: Some("nine_patch"),
+            source: ShaderModuleSource::Single(
+                &gpu.create_shader_module(include_wgsl!("../../static/shader/2d/nine_patch.wgsl")),
+            ),
+            uniforms: &[UniformField::Camera, UniformField::Sprite],
+            vertex_buffers: VertexBuffers::instance::<PositionVertex2D, NinePatchInstance2D>(),
+            ..Default::default()
+        });
+
         let mesh_color_shader = gpu.create_shader(ShaderConfig {
             name: Some("mesh_color"),
             source: ShaderModuleSource::Single(
@@ -607,12 +1384,12 @@ impl DefaultAssets {
         });
 
         #[cfg(feature = "text")]
-        let mesh_text_shader = gpu.create_shader(ShaderConfig {
-            name: Some("mesh_text"),
-            vertex_buffers: VertexBuffers::vertex::<TextVertex2D>(),
+        let text_shader = gpu.create_shader(ShaderConfig {
+            name: Some("text"),
+            vertex_buffers: VertexBuffers::instance::<SpriteVertex2D, TextInstance2D>(),
             uniforms: &[UniformField::Camera, UniformField::SpriteArray],
             source: ShaderModuleSource::Single(
-                &gpu.create_shader_module(include_wgsl!("../../static/shader/2d/mesh_text.wgsl")),
+                &gpu.create_shader_module(include_wgsl!("../../static/shader/2d/text.wgsl")),
             ),
             ..Default::default()
         });
@@ -676,11 +1453,20 @@ impl DefaultAssets {
             gpu,
             Camera2D::new(Default::default(), Vector2::new(0.5, 0.5)),
         );
+        let ui_camera = UiCamera::new(Default::default(), Default::default(), size);
+        let ui_camera = (CameraBuffer2D::new(gpu, ui_camera.camera()), ui_camera);
         let world_camera2d = CameraBuffer2D::empty(gpu);
         let world_camera3d = CameraBuffer::empty(gpu);
+        let lights3d = Lights3D::default().uniform(gpu);
+        let sky_camera = UniformData::new(
+            gpu,
+            gpu.default_layouts.single_uniform_layout.clone(),
+            &[Matrix4::identity()],
+        );
 
         let sprite_mesh = gpu.create_mesh(&MeshBuilder2D::cuboid(Vector2::new(0.5, 0.5)));
         let position_mesh = gpu.create_mesh(&MeshBuilder2D::cuboid(Vector2::new(0.5, 0.5)));
+        let nine_patch_mesh = gpu.create_mesh(&NinePatchSprite::mesh());
 
         #[cfg(feature = "framebuffer")]
         let framebuffer = SpriteRenderTarget::new(gpu, size);
@@ -699,23 +1485,34 @@ impl DefaultAssets {
 
         Self {
             sprite_shader,
+            sprite_lit_shader,
             color_shader,
             sprite_array_shader,
             sprite_crop_shader,
+            particle_shader,
+            particle_additive_shader,
             sprite_array_crop_shader,
+            nine_patch_shader,
             mesh_sprite_array_shader,
             mesh_color_shader,
             mesh_sprite_shader,
             #[cfg(feature = "text")]
-            mesh_text_shader,
+            text_shader,
             model_shader,
+            model_lit_shader,
+            skybox_shader,
+            billboard_shader,
+            #[cfg(feature = "gltf")]
+            skinned_model_shader,
             sprite_mesh,
             depth_buffer,
             position_mesh,
+            nine_patch_mesh,
             missing_sprite,
 
             times,
             unit_camera,
+            ui_camera,
             relative_camera,
             relative_bottom_left_camera,
             relative_bottom_right_camera,
@@ -723,6 +1520,9 @@ impl DefaultAssets {
             relative_top_right_camera,
             world_camera2d,
             world_camera3d,
+            lights3d,
+            sky: None,
+            sky_camera,
 
             #[cfg(feature = "framebuffer")]
             framebuffer,
@@ -742,6 +1542,20 @@ impl DefaultAssets {
         }
     }
 
+    pub(crate) fn apply_ui_camera(
+        &mut self,
+        gpu: &Gpu,
+        screen_config: &crate::graphics::ScreenConfig,
+    ) {
+        let camera = UiCamera::new(
+            screen_config.ui_scaling(),
+            screen_config.safe_area(),
+            gpu.surface_size(),
+        );
+        self.ui_camera.0.write(gpu, camera.camera());
+        self.ui_camera.1 = camera;
+    }
+
     pub(crate) fn resize(&mut self, gpu: &Gpu, window_size: Vector2<u32>) {
         #[cfg(feature = "framebuffer")]
         self.framebuffer.resize(gpu, window_size);
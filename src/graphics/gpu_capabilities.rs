@@ -0,0 +1,75 @@
+/// What the adapter the [Gpu](crate::graphics::Gpu) was created with can do, to adapt to
+/// downlevel backends like WebGL2. Accessible with
+/// [Gpu::capabilities](crate::graphics::Gpu::capabilities).
+#[derive(Clone, Debug)]
+pub struct GpuCapabilities {
+    pub backend: wgpu::Backend,
+    pub features: wgpu::Features,
+    pub limits: wgpu::Limits,
+    pub downlevel: wgpu::DownlevelFlags,
+    /// The texture format can be rendered to and blended, e.g. for HDR targets
+    pub float_render_targets: bool,
+}
+
+impl GpuCapabilities {
+    pub(crate) fn new(
+        adapter: &wgpu::Adapter,
+        features: wgpu::Features,
+        limits: wgpu::Limits,
+    ) -> Self {
+        let float_render_targets = adapter
+            .get_texture_format_features(wgpu::TextureFormat::Rgba16Float)
+            .allowed_usages
+            .contains(wgpu::TextureUsages::RENDER_ATTACHMENT)
+            && adapter
+                .get_texture_format_features(wgpu::TextureFormat::Rgba16Float)
+                .flags
+                .contains(wgpu::TextureFormatFeatureFlags::BLENDABLE);
+        Self {
+            backend: adapter.get_info().backend,
+            features,
+            limits,
+            downlevel: adapter.get_downlevel_capabilities().flags,
+            float_render_targets,
+        }
+    }
+
+    /// Running on WebGL2 in the browser or on OpenGL ES natively
+    pub fn is_gl(&self) -> bool {
+        self.backend == wgpu::Backend::Gl
+    }
+
+    pub fn is_webgpu(&self) -> bool {
+        self.backend == wgpu::Backend::BrowserWebGpu
+    }
+
+    pub fn compute_shaders(&self) -> bool {
+        self.downlevel
+            .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+            && self.limits.max_compute_workgroups_per_dimension > 0
+    }
+
+    /// Storage buffers in vertex and fragment shaders
+    pub fn storage_buffers(&self) -> bool {
+        self.limits.max_storage_buffers_per_shader_stage > 0
+            && self
+                .downlevel
+                .contains(wgpu::DownlevelFlags::VERTEX_STORAGE)
+    }
+
+    pub fn max_texture_size(&self) -> u32 {
+        self.limits.max_texture_dimension_2d
+    }
+
+    pub fn max_texture_array_layers(&self) -> u32 {
+        self.limits.max_texture_array_layers
+    }
+
+    pub fn max_uniform_buffer_size(&self) -> u32 {
+        self.limits.max_uniform_buffer_binding_size
+    }
+
+    pub fn timestamp_queries(&self) -> bool {
+        self.features.contains(wgpu::Features::TIMESTAMP_QUERY)
+    }
+}
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::time::Duration;
+
+/// Timestamps of one frame, read back once the gpu is done with them
+struct ProfilerFrame {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    read_buffer: wgpu::Buffer,
+    labels: Vec<&'static str>,
+    pending: bool,
+    mapped: Arc<Mutex<Option<bool>>>,
+}
+
+/// Measures the gpu time of every render pass with timestamp queries. Results are available a
+/// few frames later through [Gpu::pass_timings](crate::graphics::Gpu::pass_timings).
+pub(crate) struct GpuProfiler {
+    frames: Vec<ProfilerFrame>,
+    current: Option<usize>,
+    skipped: bool,
+    period: f32,
+    timings: Vec<(String, Duration)>,
+}
+
+impl GpuProfiler {
+    pub const MAX_PASSES: u32 = 256;
+    pub const FRAMES_IN_FLIGHT: usize = 3;
+
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let size = Self::MAX_PASSES as u64 * 2 * wgpu::QUERY_SIZE as u64;
+        let frames = (0..Self::FRAMES_IN_FLIGHT)
+            .map(|_| ProfilerFrame {
+                query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                    label: Some("profiler_query_set"),
+                    ty: wgpu::QueryType::Timestamp,
+                    count: Self::MAX_PASSES * 2,
+                }),
+                resolve_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("profiler_resolve_buffer"),
+                    size,
+                    usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                }),
+                read_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("profiler_read_buffer"),
+                    size,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                labels: Vec::new(),
+                pending: false,
+                mapped: Default::default(),
+            })
+            .collect();
+
+        Self {
+            frames,
+            current: None,
+            skipped: false,
+            period: queue.get_timestamp_period(),
+            timings: Vec::new(),
+        }
+    }
+
+    /// Queries for the next pass, [None] if all frames are still in flight or the frame has too
+    /// many passes
+    pub fn timestamp_writes(
+        &mut self,
+        label: &'static str,
+    ) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        if self.skipped {
+            return None;
+        }
+        let index = match self.current {
+            Some(index) => index,
+            None => {
+                let Some(index) = self.frames.iter().position(|frame| !frame.pending) else {
+                    self.skipped = true;
+                    return None;
+                };
+                self.current = Some(index);
+                index
+            }
+        };
+
+        let frame = &mut self.frames[index];
+        let pass = frame.labels.len() as u32;
+        if pass == Self::MAX_PASSES {
+            return None;
+        }
+        frame.labels.push(label);
+        Some(wgpu::RenderPassTimestampWrites {
+            query_set: &frame.query_set,
+            beginning_of_pass_write_index: Some(pass * 2),
+            end_of_pass_write_index: Some(pass * 2 + 1),
+        })
+    }
+
+    /// Resolves the queries of the current frame and starts reading them back
+    pub fn finish_frame(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.skipped = false;
+        let Some(index) = self.current.take() else {
+            return;
+        };
+        let frame = &mut self.frames[index];
+        let count = frame.labels.len() as u32 * 2;
+        let size = count as u64 * wgpu::QUERY_SIZE as u64;
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("profiler_encoder"),
+        });
+        encoder.resolve_query_set(&frame.query_set, 0..count, &frame.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(&frame.resolve_buffer, 0, &frame.read_buffer, 0, size);
+        queue.submit(Some(encoder.finish()));
+
+        let mapped = frame.mapped.clone();
+        frame.pending = true;
+        frame
+            .read_buffer
+            .slice(..size)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                *mapped.lock() = Some(result.is_ok());
+            });
+    }
+
+    /// Reads back every frame the gpu is done with, the latest one becomes the result
+    pub fn poll(&mut self, device: &wgpu::Device) {
+        if !self.frames.iter().any(|frame| frame.pending) {
+            return;
+        }
+        device.poll(wgpu::Maintain::Poll);
+        for frame in &mut self.frames {
+            let Some(ok) = frame.mapped.lock().take() else {
+                continue;
+            };
+            if ok {
+                let size = frame.labels.len() as u64 * 2 * wgpu::QUERY_SIZE as u64;
+                let data = frame.read_buffer.slice(..size).get_mapped_range();
+                let timestamps: &[u64] = bytemuck::cast_slice(&data);
+                self.timings.clear();
+                for (label, pass) in frame.labels.iter().zip(timestamps.chunks_exact(2)) {
+                    let nanos = pass[1].saturating_sub(pass[0]) as f64 * self.period as f64;
+                    let duration = Duration::from_nanos(nanos as u64);
+                    // Passes with the same label are summed up
+                    match self.timings.iter_mut().find(|(l, _)| l == label) {
+                        Some((_, total)) => *total += duration,
+                        None => self.timings.push((label.to_string(), duration)),
+                    }
+                }
+                drop(data);
+            }
+            frame.read_buffer.unmap();
+            frame.labels.clear();
+            frame.pending = false;
+        }
+    }
+
+    pub fn timings(&self) -> &[(String, Duration)] {
+        &self.timings
+    }
+}
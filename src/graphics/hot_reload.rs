@@ -0,0 +1,391 @@
+use std::time::SystemTime;
+
+#[cfg(feature = "log")]
+use crate::log::{error, info, warn};
+use crate::{
+    graphics::{
+        AssetKey, AssetManager, BlendState, ColorWrites, Gpu, Model, ModelBuilder, Shader,
+        ShaderConfig, ShaderModuleDescriptor, ShaderModuleSource, ShaderSource, Sprite,
+        SpriteBuilder, UniformField, VertexBuffers,
+    },
+    io::ResourceLoader,
+    math::Vector2,
+    time::{Duration, Instant},
+};
+
+#[derive(Clone, Copy)]
+enum HotUniform {
+    Sprite,
+    SingleUniform,
+    SpriteArray,
+    Cubemap,
+    Camera,
+    DynamicUniform,
+}
+
+struct HotVertexBuffer {
+    array_stride: wgpu::BufferAddress,
+    step_mode: wgpu::VertexStepMode,
+    attributes: Vec<wgpu::VertexAttribute>,
+}
+
+/// Owned copy of a [ShaderConfig] with a [ShaderModuleSource::Hot] source, so the shader can be
+/// recompiled later
+struct HotShader {
+    key: AssetKey,
+    path: String,
+    modified: Option<SystemTime>,
+    uniforms: Vec<HotUniform>,
+    vertex_buffers: Vec<HotVertexBuffer>,
+    blend: BlendState,
+    write_mask: ColorWrites,
+    vertex_entry: &'static str,
+    fragment_entry: &'static str,
+    depth_stencil: Option<wgpu::DepthStencilState>,
+}
+
+impl HotShader {
+    fn new(
+        key: AssetKey,
+        path: &str,
+        config: &ShaderConfig,
+        loader: &dyn ResourceLoader,
+    ) -> Option<Self> {
+        let uniforms = config
+            .uniforms
+            .iter()
+            .map(|uniform| match uniform {
+                UniformField::Sprite => Some(HotUniform::Sprite),
+                UniformField::SingleUniform => Some(HotUniform::SingleUniform),
+                UniformField::SpriteArray => Some(HotUniform::SpriteArray),
+                UniformField::Cubemap => Some(HotUniform::Cubemap),
+                UniformField::Camera => Some(HotUniform::Camera),
+                UniformField::DynamicUniform => Some(HotUniform::DynamicUniform),
+                UniformField::Custom(_) => None,
+            })
+            .collect::<Option<Vec<_>>>();
+        let Some(uniforms) = uniforms else {
+            #[cfg(feature = "log")]
+            warn!("Shader {key} uses custom uniforms and can not be hot reloaded!");
+            return None;
+        };
+
+        let owned = |formats: &[wgpu::VertexFormat],
+                     location: &mut u32,
+                     step_mode: wgpu::VertexStepMode| {
+            let mut array_stride = 0;
+            let attributes = formats
+                .iter()
+                .map(|format| {
+                    let attribute = wgpu::VertexAttribute {
+                        format: *format,
+                        offset: array_stride,
+                        shader_location: *location,
+                    };
+                    array_stride += format.size();
+                    *location += 1;
+                    attribute
+                })
+                .collect();
+            HotVertexBuffer {
+                array_stride,
+                step_mode,
+                attributes,
+            }
+        };
+        let mut location = 0;
+        let vertex_buffers = match &config.vertex_buffers {
+            VertexBuffers::Vertex(vertex) => {
+                vec![owned(vertex, &mut location, wgpu::VertexStepMode::Vertex)]
+            }
+            VertexBuffers::VertexInstance(vertex, instance) => vec![
+                owned(vertex, &mut location, wgpu::VertexStepMode::Vertex),
+                owned(instance, &mut location, wgpu::VertexStepMode::Instance),
+            ],
+            VertexBuffers::Custom(custom) => custom
+                .iter()
+                .map(|layout| HotVertexBuffer {
+                    array_stride: layout.array_stride,
+                    step_mode: layout.step_mode,
+                    attributes: layout.attributes.to_vec(),
+                })
+                .collect(),
+        };
+
+        Some(Self {
+            key,
+            path: path.to_owned(),
+            modified: loader.modified(path),
+            uniforms,
+            vertex_buffers,
+            blend: config.blend,
+            write_mask: config.write_mask,
+            vertex_entry: config.vertex_entry,
+            fragment_entry: config.fragment_entry,
+            depth_stencil: config.depth_stencil.clone(),
+        })
+    }
+
+    /// Recompiles the shader, returns [None] and logs the error if the new source is invalid
+    fn compile(&self, gpu: &Gpu, source: String) -> Option<Shader> {
+        let uniforms = self
+            .uniforms
+            .iter()
+            .map(|uniform| match uniform {
+                HotUniform::Sprite => UniformField::Sprite,
+                HotUniform::SingleUniform => UniformField::SingleUniform,
+                HotUniform::SpriteArray => UniformField::SpriteArray,
+                HotUniform::Cubemap => UniformField::Cubemap,
+                HotUniform::Camera => UniformField::Camera,
+                HotUniform::DynamicUniform => UniformField::DynamicUniform,
+            })
+            .collect::<Vec<_>>();
+        let vertex_buffers = self
+            .vertex_buffers
+            .iter()
+            .map(|buffer| wgpu::VertexBufferLayout {
+                array_stride: buffer.array_stride,
+                step_mode: buffer.step_mode,
+                attributes: &buffer.attributes,
+            })
+            .collect();
+
+        gpu.clear_pipeline_cache();
+        gpu.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let module = gpu.create_shader_module(ShaderModuleDescriptor {
+            label: Some(&self.path),
+            source: ShaderSource::Wgsl(source.into()),
+        });
+        let shader = Shader::new(
+            gpu,
+            ShaderConfig {
+                name: Some(self.key),
+                source: ShaderModuleSource::Single(&module),
+                uniforms: &uniforms,
+                vertex_buffers: VertexBuffers::Custom(vertex_buffers),
+                blend: self.blend,
+                write_mask: self.write_mask,
+                vertex_entry: self.vertex_entry,
+                fragment_entry: self.fragment_entry,
+                depth_stencil: self.depth_stencil.clone(),
+            },
+        );
+        match pollster::block_on(gpu.device.pop_error_scope()) {
+            None => Some(shader),
+            Some(_err) => {
+                #[cfg(feature = "log")]
+                error!("Failed to reload shader {}: {_err}", self.key);
+                None
+            }
+        }
+    }
+}
+
+/// Sprite loaded from a resource, recreated with the same settings if its size changes
+struct HotSprite {
+    key: AssetKey,
+    path: String,
+    modified: Option<SystemTime>,
+    sampler: wgpu::SamplerDescriptor<'static>,
+    format: wgpu::TextureFormat,
+    mipmaps: bool,
+}
+
+impl HotSprite {
+    fn new<D: std::ops::Deref<Target = [u8]>>(
+        key: AssetKey,
+        path: &str,
+        desc: &SpriteBuilder<D>,
+        loader: &dyn ResourceLoader,
+    ) -> Self {
+        let sampler = &desc.sampler;
+        Self {
+            key,
+            path: path.to_owned(),
+            modified: loader.modified(path),
+            sampler: wgpu::SamplerDescriptor {
+                label: None,
+                address_mode_u: sampler.address_mode_u,
+                address_mode_v: sampler.address_mode_v,
+                address_mode_w: sampler.address_mode_w,
+                mag_filter: sampler.mag_filter,
+                min_filter: sampler.min_filter,
+                mipmap_filter: sampler.mipmap_filter,
+                lod_min_clamp: sampler.lod_min_clamp,
+                lod_max_clamp: sampler.lod_max_clamp,
+                compare: sampler.compare,
+                anisotropy_clamp: sampler.anisotropy_clamp,
+                border_color: sampler.border_color,
+            },
+            format: desc.format,
+            mipmaps: desc.mipmaps,
+        }
+    }
+
+    fn reload(&self, assets: &AssetManager, bytes: &[u8]) {
+        let image = match image::load_from_memory(bytes) {
+            Ok(image) => image.to_rgba8(),
+            Err(_err) => {
+                #[cfg(feature = "log")]
+                error!("Failed to decode sprite {}: {_err}", self.path);
+                return;
+            }
+        };
+        let size = Vector2::new(image.width(), image.height());
+        if !assets.exists(self.key) {
+            return;
+        }
+        let mut sprite = assets.sprite_mut(self.key);
+        if sprite.size() == size {
+            sprite.write(&assets.gpu, size, &image);
+        } else {
+            *sprite = Sprite::new(
+                &assets.gpu,
+                SpriteBuilder {
+                    label: Some(self.key),
+                    size,
+                    sampler: self.sampler.clone(),
+                    data: image,
+                    format: self.format,
+                    mipmaps: self.mipmaps,
+                    resource_path: None,
+                },
+            );
+        }
+        #[cfg(feature = "log")]
+        info!("Reloaded sprite {} ({} x {})", self.key, size.x, size.y);
+    }
+}
+
+struct HotModel {
+    key: AssetKey,
+    path: String,
+    modified: Option<SystemTime>,
+}
+
+/// Polls the modification times of hot assets and swaps the changed ones in place, so every
+/// [AssetKey] that refers to them sees the new version
+pub(crate) struct HotReloader {
+    shaders: Vec<HotShader>,
+    sprites: Vec<HotSprite>,
+    models: Vec<HotModel>,
+    last_poll: Instant,
+}
+
+impl HotReloader {
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    pub(crate) fn new() -> Self {
+        Self {
+            shaders: Vec::new(),
+            sprites: Vec::new(),
+            models: Vec::new(),
+            last_poll: Instant::now(),
+        }
+    }
+
+    pub(crate) fn watch_shader(
+        &mut self,
+        key: AssetKey,
+        path: &str,
+        config: &ShaderConfig,
+        loader: &dyn ResourceLoader,
+    ) {
+        if let Some(shader) = HotShader::new(key, path, config, loader) {
+            self.shaders.retain(|shader| shader.key != key);
+            self.shaders.push(shader);
+        }
+    }
+
+    pub(crate) fn watch_sprite<D: std::ops::Deref<Target = [u8]>>(
+        &mut self,
+        key: AssetKey,
+        path: &str,
+        desc: &SpriteBuilder<D>,
+        loader: &dyn ResourceLoader,
+    ) {
+        self.sprites.retain(|sprite| sprite.key != key);
+        self.sprites.push(HotSprite::new(key, path, desc, loader));
+    }
+
+    /// Only changes of the obj file itself trigger a reload
+    pub(crate) fn watch_model(&mut self, key: AssetKey, path: &str, loader: &dyn ResourceLoader) {
+        self.models.retain(|model| model.key != key);
+        self.models.push(HotModel {
+            key,
+            path: path.to_owned(),
+            modified: loader.modified(path),
+        });
+    }
+
+    pub(crate) fn unwatch(&mut self, key: AssetKey) {
+        self.shaders.retain(|shader| shader.key != key);
+        self.sprites.retain(|sprite| sprite.key != key);
+        self.models.retain(|model| model.key != key);
+    }
+
+    pub(crate) fn poll(&mut self, assets: &AssetManager) {
+        if self.last_poll.elapsed() < Self::POLL_INTERVAL {
+            return;
+        }
+        self.last_poll = Instant::now();
+        let loader = &*assets.loader;
+        let changed = |path: &str, last: &mut Option<SystemTime>| {
+            let modified = loader.modified(path);
+            if modified.is_none() || modified == *last {
+                return false;
+            }
+            *last = modified;
+            true
+        };
+
+        for shader in &mut self.shaders {
+            if !changed(&shader.path, &mut shader.modified) {
+                continue;
+            }
+            let source = match loader.load_string(&shader.path) {
+                Ok(source) => source,
+                Err(_err) => {
+                    #[cfg(feature = "log")]
+                    error!("Failed to read shader {}: {_err}", shader.path);
+                    continue;
+                }
+            };
+            if let Some(new) = shader.compile(&assets.gpu, source) {
+                #[cfg(feature = "log")]
+                info!("Reloaded shader {}", shader.key);
+                assets.replace(shader.key, new);
+            }
+        }
+
+        for sprite in &mut self.sprites {
+            if !changed(&sprite.path, &mut sprite.modified) {
+                continue;
+            }
+            match loader.load_bytes(&sprite.path) {
+                Ok(bytes) => sprite.reload(assets, &bytes),
+                Err(_err) => {
+                    #[cfg(feature = "log")]
+                    error!("Failed to read sprite {}: {_err}", sprite.path);
+                }
+            }
+        }
+
+        for model in &mut self.models {
+            if !changed(&model.path, &mut model.modified) {
+                continue;
+            }
+            match ModelBuilder::try_resource(&model.path) {
+                Ok(builder) => {
+                    assets.replace(model.key, Model::new(&assets.gpu, builder));
+                    #[cfg(feature = "log")]
+                    info!("Reloaded model {}", model.key);
+                }
+                Err(_err) => {
+                    #[cfg(feature = "log")]
+                    error!("Failed to reload model {}: {_err}", model.path);
+                }
+            }
+        }
+    }
+}
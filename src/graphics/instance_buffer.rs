@@ -189,6 +189,43 @@ impl<D: bytemuck::Pod + Default> Default for Instance2D<D> {
     }
 }
 
+/// Order in which 2D instances are drawn. Later instances are drawn on top.
+///
+/// Sorting costs O(n log n) every time the instances are written. The sort is stable so instances
+/// with the same key keep the order they were written in. With the `rayon` feature a parallel sort
+/// is used once there are more than [InstanceSort::PARALLEL_THRESHOLD] instances.
+#[derive(Clone, Copy)]
+pub enum InstanceSort<D: bytemuck::Pod> {
+    YAscending,
+    /// Instances further down are drawn on top, which is what most top-down games need.
+    YDescending,
+    /// Sorts ascending by the returned key.
+    Custom(fn(&Instance2D<D>) -> f32),
+}
+
+impl<D: bytemuck::Pod + Send + Sync> InstanceSort<D> {
+    pub const PARALLEL_THRESHOLD: usize = 4096;
+
+    pub fn key(&self, instance: &Instance2D<D>) -> f32 {
+        match self {
+            InstanceSort::YAscending => instance.translation.y,
+            InstanceSort::YDescending => -instance.translation.y,
+            InstanceSort::Custom(key) => key(instance),
+        }
+    }
+
+    pub fn sort(&self, instances: &mut [Instance2D<D>]) {
+        let compare = |a: &Instance2D<D>, b: &Instance2D<D>| self.key(a).total_cmp(&self.key(b));
+        #[cfg(feature = "rayon")]
+        if instances.len() > Self::PARALLEL_THRESHOLD {
+            use rayon::slice::ParallelSliceMut;
+            instances.par_sort_by(compare);
+            return;
+        }
+        instances.sort_by(compare);
+    }
+}
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -219,6 +256,84 @@ impl Default for Instance3D {
     }
 }
 
+/// Quad in 3D space that always faces the camera, drawn with
+/// [Renderer::draw_billboards](crate::graphics::Renderer::draw_billboards)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BillboardInstance3D {
+    pub position: Vector3<f32>,
+    /// 1 rotates only around the y axis, 0 faces the camera completely
+    cylindrical: u32,
+    pub size: Vector2<f32>,
+    /// Top left of the sprite region in texture coordinates
+    pub uv_offset: Vector2<f32>,
+    pub uv_scale: Vector2<f32>,
+    /// Multiplied with the sprite
+    pub color: Color,
+    /// Pixels with an alpha below are discarded, which avoids sorting cutout sprites like
+    /// foliage
+    pub alpha_cutoff: f32,
+}
+
+impl Instance for BillboardInstance3D {
+    const ATTRIBUTES: &'static [wgpu::VertexFormat] = &[
+        wgpu::VertexFormat::Float32x3,
+        wgpu::VertexFormat::Uint32,
+        wgpu::VertexFormat::Float32x2,
+        wgpu::VertexFormat::Float32x2,
+        wgpu::VertexFormat::Float32x2,
+        wgpu::VertexFormat::Float32x4,
+        wgpu::VertexFormat::Float32,
+    ];
+}
+
+impl BillboardInstance3D {
+    pub fn new(position: Vector3<f32>, size: Vector2<f32>) -> Self {
+        Self {
+            position,
+            cylindrical: 0,
+            size,
+            uv_offset: Vector2::new(0.0, 0.0),
+            uv_scale: Vector2::new(1.0, 1.0),
+            color: Color::WHITE,
+            alpha_cutoff: 0.0,
+        }
+    }
+
+    /// Keeps the billboard upright, like trees or health bars
+    pub fn cylindrical(mut self, cylindrical: bool) -> Self {
+        self.cylindrical = cylindrical as u32;
+        self
+    }
+
+    pub fn is_cylindrical(&self) -> bool {
+        self.cylindrical != 0
+    }
+
+    pub fn uv_rect(mut self, top_left: Vector2<f32>, size: Vector2<f32>) -> Self {
+        self.uv_offset = top_left;
+        self.uv_scale = size;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn alpha_cutoff(mut self, alpha_cutoff: f32) -> Self {
+        self.alpha_cutoff = alpha_cutoff;
+        self
+    }
+}
+
+impl Default for BillboardInstance3D {
+    fn default() -> Self {
+        Self::new(Vector3::default(), Vector2::new(1.0, 1.0))
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BufferCall {
@@ -226,15 +341,48 @@ pub enum BufferCall {
     EveryFrame,
 }
 
+/// Instances grouped by a key, filled in
+/// [AssetManager::write_keyed_instances](crate::graphics::AssetManager::write_keyed_instances).
+/// This allows drawing instances that share one buffer with a different sprite per key.
+#[derive(Debug)]
+pub struct KeyedInstances<I> {
+    pub(crate) instances: Vec<(u32, I)>,
+}
+
+impl<I> KeyedInstances<I> {
+    pub fn push(&mut self, key: u32, instance: I) {
+        self.instances.push((key, instance));
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+}
+
+impl<I> Extend<(u32, I)> for KeyedInstances<I> {
+    fn extend<T: IntoIterator<Item = (u32, I)>>(&mut self, iter: T) {
+        self.instances.extend(iter);
+    }
+}
+
 #[derive(Debug)]
 pub struct InstanceBuffer<I: Instance> {
     buffer: wgpu::Buffer,
     instances: u64,
+    key_ranges: Vec<(u32, Range<u32>)>,
     pub(crate) data: Vec<I>,
     pub(crate) force_update: bool,
 }
 
 impl<I: Instance> InstanceBuffer<I> {
+    const USAGE: wgpu::BufferUsages = wgpu::BufferUsages::VERTEX
+        .union(wgpu::BufferUsages::COPY_DST)
+        .union(wgpu::BufferUsages::COPY_SRC);
+
     pub fn new(gpu: &Gpu, data: &[I]) -> Self {
         let instance_size = size_of::<I>() as u64;
         let data = bytemuck::cast_slice(data);
@@ -245,13 +393,14 @@ impl<I: Instance> InstanceBuffer<I> {
             .device
             .create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("instance_buffer"),
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                usage: Self::USAGE,
                 contents: data,
             });
 
         Self {
             buffer,
             instances: buffer_size / instance_size,
+            key_ranges: Vec::new(),
             data: Vec::new(),
             force_update: true,
         }
@@ -262,7 +411,7 @@ impl<I: Instance> InstanceBuffer<I> {
         let instance_size = size_of::<I>() as u64;
         let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("instance_buffer"),
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            usage: Self::USAGE,
             size: instance_size * amount,
             mapped_at_creation: false,
         });
@@ -270,6 +419,7 @@ impl<I: Instance> InstanceBuffer<I> {
         Self {
             buffer,
             instances: 0,
+            key_ranges: Vec::new(),
             data: Vec::new(),
             force_update: true,
         }
@@ -279,25 +429,59 @@ impl<I: Instance> InstanceBuffer<I> {
         self.write_offset(gpu, 0, data);
     }
 
+    /// Grows the buffer by doubling its capacity if the data does not fit. The data is copied
+    /// into staging memory of the queue, so the write never waits for the GPU to finish reading
+    /// the previous frame.
     pub fn write_offset(&mut self, gpu: &Gpu, instance_offset: u64, data: &[I]) {
-        let instance_size: u64 = I::SIZE;
-        let data = bytemuck::cast_slice(data);
-        let new_size = instance_offset * instance_size + data.len() as u64;
+        self.key_ranges.clear();
+        let offset = instance_offset * I::SIZE;
+        let data: &[u8] = bytemuck::cast_slice(data);
+        let new_size = offset + data.len() as u64;
+
+        if new_size > self.buffer_capacity() {
+            let mut capacity = self.buffer_capacity().max(I::SIZE);
+            while capacity < new_size {
+                capacity *= 2;
+            }
+            self.resize(gpu, capacity, offset);
+        }
+        if let Some(size) = wgpu::BufferSize::new(data.len() as u64) {
+            if let Some(mut view) = gpu.queue.write_buffer_with(&self.buffer, offset, size) {
+                view.copy_from_slice(data);
+            }
+        }
+
+        self.instances = new_size / I::SIZE;
+    }
+
+    /// Releases the capacity that is not used by the current instances
+    pub fn shrink_to_fit(&mut self, gpu: &Gpu) {
+        let size = self.buffer_size();
+        if size < self.buffer_capacity() {
+            self.resize(gpu, size, size);
+        }
+    }
 
-        if new_size > self.buffer_size() {
-            self.buffer = gpu
+    /// Replaces the buffer with one of `capacity` bytes and copies the first `keep` bytes over
+    fn resize(&mut self, gpu: &Gpu, capacity: u64, keep: u64) {
+        let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("instance_buffer"),
+            usage: Self::USAGE,
+            size: capacity,
+            mapped_at_creation: false,
+        });
+        let keep = keep.min(self.buffer_capacity()).min(capacity);
+        let keep = keep - keep % wgpu::COPY_BUFFER_ALIGNMENT;
+        if keep != 0 {
+            let mut encoder = gpu
                 .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("instance_buffer"),
-                    usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                    contents: data,
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("instance_buffer_resize"),
                 });
-        } else if !data.is_empty() {
-            gpu.queue
-                .write_buffer(&self.buffer, instance_offset * instance_size, data);
+            encoder.copy_buffer_to_buffer(&self.buffer, 0, &buffer, 0, keep);
+            gpu.queue.submit(Some(encoder.finish()));
         }
-
-        self.instances = new_size / instance_size;
+        self.buffer = buffer;
     }
 
     pub fn slice(&self) -> wgpu::BufferSlice {
@@ -308,6 +492,11 @@ impl<I: Instance> InstanceBuffer<I> {
         self.buffer.size()
     }
 
+    /// Amount of instances that fit into the buffer without growing it
+    pub fn capacity(&self) -> u64 {
+        self.buffer_capacity() / I::SIZE
+    }
+
     pub fn instances(&self) -> Range<u32> {
         0..self.instance_amount() as u32
     }
@@ -316,6 +505,40 @@ impl<I: Instance> InstanceBuffer<I> {
         I::SIZE * self.instance_amount()
     }
 
+    /// Sorts the instances by their key and uploads them, so the instances of every key form one
+    /// contiguous range
+    pub fn write_keyed(&mut self, gpu: &Gpu, keyed: &mut KeyedInstances<I>) {
+        keyed.instances.sort_by_key(|(key, _)| *key);
+        let mut instances = std::mem::take(&mut self.data);
+        instances.clear();
+        let mut key_ranges = std::mem::take(&mut self.key_ranges);
+        key_ranges.clear();
+        for (index, (key, instance)) in keyed.instances.drain(..).enumerate() {
+            let index = index as u32;
+            match key_ranges.last_mut() {
+                Some((last, range)) if *last == key => range.end = index + 1,
+                _ => key_ranges.push((key, index..index + 1)),
+            }
+            instances.push(instance);
+        }
+        self.write(gpu, &instances);
+        self.data = instances;
+        self.key_ranges = key_ranges;
+    }
+
+    /// Ranges of the keys of the last [InstanceBuffer::write_keyed], sorted by key. Keys without
+    /// instances are skipped.
+    pub fn key_ranges(&self) -> &[(u32, Range<u32>)] {
+        &self.key_ranges
+    }
+
+    pub fn key_range(&self, key: u32) -> Option<Range<u32>> {
+        self.key_ranges
+            .binary_search_by_key(&key, |(key, _)| *key)
+            .ok()
+            .map(|index| self.key_ranges[index].1.clone())
+    }
+
     pub fn instance_amount(&self) -> wgpu::BufferAddress {
         self.instances
     }
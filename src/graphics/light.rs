@@ -0,0 +1,274 @@
+use crate::{
+    graphics::{Color, Gpu, UniformData},
+    math::{Vector2, Vector3},
+};
+
+/// Point light used by [Renderer::draw_sprite_lit](crate::graphics::Renderer::draw_sprite_lit)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointLight2D {
+    pub position: Vector2<f32>,
+    /// Distance of the light above the sprites. Lower values make the light more grazing.
+    pub height: f32,
+    pub radius: f32,
+    pub color: Color,
+    /// Exponent of the attenuation towards the radius
+    pub falloff: f32,
+    _padding: [f32; 3],
+}
+
+impl PointLight2D {
+    pub fn new(position: Vector2<f32>, radius: f32, color: Color) -> Self {
+        Self {
+            position,
+            height: 0.5,
+            radius,
+            color,
+            falloff: 2.0,
+            _padding: Default::default(),
+        }
+    }
+
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    pub fn falloff(mut self, falloff: f32) -> Self {
+        self.falloff = falloff;
+        self
+    }
+}
+
+impl Default for PointLight2D {
+    fn default() -> Self {
+        Self::new(Vector2::default(), 1.0, Color::WHITE)
+    }
+}
+
+/// Lights passed as a uniform to the lit sprite shader
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Lights2D {
+    lights: [PointLight2D; Lights2D::MAX_LIGHTS],
+    pub ambient: Color,
+    count: u32,
+    _padding: [u32; 3],
+}
+
+impl Lights2D {
+    pub const MAX_LIGHTS: usize = 16;
+
+    pub fn new(ambient: Color) -> Self {
+        Self {
+            lights: Default::default(),
+            ambient,
+            count: 0,
+            _padding: Default::default(),
+        }
+    }
+
+    /// Adds a light. Lights beyond [Lights2D::MAX_LIGHTS] are ignored.
+    pub fn push(&mut self, light: PointLight2D) {
+        if (self.count as usize) < Self::MAX_LIGHTS {
+            self.lights[self.count as usize] = light;
+            self.count += 1;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.count = 0;
+    }
+
+    pub fn lights(&self) -> &[PointLight2D] {
+        &self.lights[..self.count as usize]
+    }
+
+    pub fn lights_mut(&mut self) -> &mut [PointLight2D] {
+        &mut self.lights[..self.count as usize]
+    }
+
+    pub fn uniform(&self, gpu: &Gpu) -> UniformData<Lights2D> {
+        UniformData::new(
+            gpu,
+            gpu.default_layouts().single_uniform_layout.clone(),
+            &[*self],
+        )
+    }
+}
+
+impl Default for Lights2D {
+    fn default() -> Self {
+        Self::new(Color::new(0.1, 0.1, 0.1, 1.0))
+    }
+}
+
+impl FromIterator<PointLight2D> for Lights2D {
+    fn from_iter<T: IntoIterator<Item = PointLight2D>>(iter: T) -> Self {
+        let mut lights = Self::default();
+        for light in iter {
+            lights.push(light);
+        }
+        lights
+    }
+}
+
+/// Light that shines in one direction from infinitely far away, like the sun
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DirectionalLight3D {
+    /// Direction the light travels in, does not have to be normalized
+    pub direction: Vector3<f32>,
+    /// An intensity of 0 turns the light off
+    pub intensity: f32,
+    pub color: Color,
+}
+
+impl DirectionalLight3D {
+    pub fn new(direction: Vector3<f32>, color: Color) -> Self {
+        Self {
+            direction,
+            intensity: 1.0,
+            color,
+        }
+    }
+
+    pub fn intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+}
+
+impl Default for DirectionalLight3D {
+    fn default() -> Self {
+        Self::new(Vector3::new(-0.3, -1.0, -0.5), Color::WHITE)
+    }
+}
+
+/// Point light used by [Renderer::draw_model_lit](crate::graphics::Renderer::draw_model_lit)
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PointLight3D {
+    pub position: Vector3<f32>,
+    /// Distance at which the light has faded out completely
+    pub range: f32,
+    pub color: Color,
+    pub intensity: f32,
+    _padding: [f32; 3],
+}
+
+impl PointLight3D {
+    pub fn new(position: Vector3<f32>, range: f32, color: Color) -> Self {
+        Self {
+            position,
+            range,
+            color,
+            intensity: 1.0,
+            _padding: Default::default(),
+        }
+    }
+
+    pub fn intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+}
+
+impl Default for PointLight3D {
+    fn default() -> Self {
+        Self::new(Vector3::default(), 10.0, Color::WHITE)
+    }
+}
+
+/// Lights of a scene, accessible as `ctx.lights3d`. They are uploaded every frame to
+/// `default_assets.lights3d` together with the position of the world camera.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lights3D {
+    directional: DirectionalLight3D,
+    points: [PointLight3D; Lights3D::MAX_POINT_LIGHTS],
+    pub ambient: Color,
+    camera_position: Vector3<f32>,
+    count: u32,
+}
+
+impl Lights3D {
+    pub const MAX_POINT_LIGHTS: usize = 16;
+
+    pub fn new(ambient: Color) -> Self {
+        Self {
+            directional: Default::default(),
+            points: Default::default(),
+            ambient,
+            camera_position: Default::default(),
+            count: 0,
+        }
+    }
+
+    pub fn set_directional(&mut self, light: DirectionalLight3D) {
+        self.directional = light;
+    }
+
+    pub fn directional(&self) -> &DirectionalLight3D {
+        &self.directional
+    }
+
+    pub fn directional_mut(&mut self) -> &mut DirectionalLight3D {
+        &mut self.directional
+    }
+
+    /// Adds a point light and returns its index. Lights beyond
+    /// [Lights3D::MAX_POINT_LIGHTS] are ignored.
+    pub fn add_point(&mut self, light: PointLight3D) -> Option<usize> {
+        let index = self.count as usize;
+        if index < Self::MAX_POINT_LIGHTS {
+            self.points[index] = light;
+            self.count += 1;
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    pub fn remove_point(&mut self, index: usize) -> PointLight3D {
+        let light = self.points()[index];
+        self.points
+            .copy_within(index + 1..self.count as usize, index);
+        self.count -= 1;
+        light
+    }
+
+    pub fn clear_points(&mut self) {
+        self.count = 0;
+    }
+
+    pub fn points(&self) -> &[PointLight3D] {
+        &self.points[..self.count as usize]
+    }
+
+    pub fn points_mut(&mut self) -> &mut [PointLight3D] {
+        &mut self.points[..self.count as usize]
+    }
+
+    pub(crate) fn set_camera_position(&mut self, position: Vector3<f32>) {
+        self.camera_position = position;
+    }
+
+    pub fn uniform(&self, gpu: &Gpu) -> UniformData<Lights3D> {
+        UniformData::new(
+            gpu,
+            gpu.default_layouts().single_uniform_layout.clone(),
+            &[*self],
+        )
+    }
+}
+
+impl Default for Lights3D {
+    fn default() -> Self {
+        Self::new(Color::new(0.1, 0.1, 0.1, 1.0))
+    }
+}
@@ -45,6 +45,11 @@ pub trait MeshBuilder {
     fn vertices(&self) -> &[Self::Vertex];
 }
 
+/// Layout of a vertex buffer. Custom vertices are `#[repr(C)]` structs that derive
+/// [bytemuck::Pod] and list the format of every field in declaration order. The formats are
+/// bound to consecutive shader locations starting at `0`, followed by the attributes of the
+/// [Instance](crate::graphics::Instance) if the shader uses one. Fields must be tightly packed,
+/// the sizes of the formats have to add up to the size of the struct.
 pub trait Vertex: bytemuck::Pod + bytemuck::Zeroable + Send + Sync + Debug {
     const ATTRIBUTES: &'static [wgpu::VertexFormat];
     const SIZE: u64 = std::mem::size_of::<Self>() as u64;
@@ -99,6 +104,7 @@ impl<D: bytemuck::Pod + Default> Vertex2D<D> {
     }
 }
 
+/// Gives [MeshBuilder2D] access to the position of a vertex
 pub trait VertexPosition2D {
     fn pos(&self) -> &Vector2<f32>;
     fn pos_mut(&mut self) -> &mut Vector2<f32>;
@@ -122,7 +128,9 @@ impl<D: bytemuck::Pod + Default> VertexPosition2D for Vertex2D<D> {
     }
 }
 
+/// Vertex that can be used with the shapes of [MeshBuilder2D]
 pub trait BaseVertex2D: bytemuck::Pod + VertexPosition2D + VertexPosition2D {
+    /// Creates the vertices of a generated shape from their positions
     fn create_data(vertices: Vec<Vector2<f32>>) -> Vec<Self>;
 }
 
@@ -639,6 +647,14 @@ impl<V: BaseVertex2D> MeshBuilder2D<V> {
         }
         self
     }
+
+    /// Modifies every vertex, useful to fill in custom per vertex data
+    pub fn apply_vertices(mut self, mut apply: impl FnMut(&mut V)) -> Self {
+        for v in &mut self.vertices {
+            apply(v);
+        }
+        self
+    }
 }
 
 impl<D: bytemuck::Pod + Default> MeshBuilder2D<Vertex2D<D>>
@@ -0,0 +1,118 @@
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use wgpu::include_wgsl;
+
+use crate::graphics::DefaultLayouts;
+
+/// Generates the mip chain of a texture by repeatedly rendering the previous level into the next
+/// one with linear filtering.
+pub(crate) struct MipmapGenerator {
+    shader: wgpu::ShaderModule,
+    layout: wgpu::PipelineLayout,
+    sampler: wgpu::Sampler,
+    pipelines: Mutex<FxHashMap<wgpu::TextureFormat, wgpu::RenderPipeline>>,
+}
+
+impl MipmapGenerator {
+    pub fn new(device: &wgpu::Device, layouts: &DefaultLayouts) -> Self {
+        Self {
+            shader: device
+                .create_shader_module(include_wgsl!("../../static/shader/2d/mipmap.wgsl")),
+            layout: device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("mipmap_pipeline_layout"),
+                bind_group_layouts: &[&layouts.sprite_layout],
+                push_constant_ranges: &[],
+            }),
+            sampler: device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("mipmap_sampler"),
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            }),
+            pipelines: Default::default(),
+        }
+    }
+
+    pub fn mip_level_count(size: crate::math::Vector2<u32>) -> u32 {
+        32 - size.x.max(size.y).max(1).leading_zeros()
+    }
+
+    pub fn generate(
+        &self,
+        device: &wgpu::Device,
+        layouts: &DefaultLayouts,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) {
+        let format = texture.format();
+        let mut pipelines = self.pipelines.lock();
+        let pipeline = pipelines.entry(format).or_insert_with(|| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("mipmap_pipeline"),
+                layout: Some(&self.layout),
+                vertex: wgpu::VertexState {
+                    module: &self.shader,
+                    entry_point: "vs_main",
+                    buffers: &[],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &self.shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(format.into())],
+                    compilation_options: Default::default(),
+                }),
+                primitive: Default::default(),
+                depth_stencil: None,
+                multisample: Default::default(),
+                multiview: None,
+                cache: None,
+            })
+        });
+
+        let views = (0..texture.mip_level_count())
+            .map(|mip| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("mipmap_view"),
+                    base_mip_level: mip,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for mip in 1..views.len() {
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("mipmap_bind_group"),
+                layout: &layouts.sprite_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&views[mip - 1]),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("mipmap_pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &views[mip],
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+    }
+}
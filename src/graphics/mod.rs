@@ -1,33 +1,87 @@
+#[cfg(feature = "aseprite")]
+mod aseprite;
+mod asset_batch;
 mod assets;
+mod bloom;
 mod camera;
 mod color;
+mod cubemap;
+#[cfg(feature = "debug-draw")]
+mod debug_draw;
 mod depth_buffer;
 mod gpu;
+mod gpu_capabilities;
+mod gpu_profiler;
+#[cfg(feature = "hot-reload")]
+mod hot_reload;
 mod instance_buffer;
+mod light;
 mod mesh;
+mod mipmap;
+#[cfg(feature = "gltf")]
+mod gltf_loader;
 mod model;
+mod nine_patch;
+mod pipeline_cache;
+mod post_process;
 mod render_encoder;
 mod render_target;
 mod renderer;
+mod scene_transition;
 mod screen_config;
+mod screenshot;
 mod shader;
+#[cfg(feature = "gltf")]
+mod skinned_model;
 mod sprite;
+mod ui_camera;
 mod sprite_array;
+mod terrain;
 mod uniform;
+mod uniform_pool;
+#[cfg(multi_window)]
+mod window_manager;
+#[cfg(target_arch = "wasm32")]
+mod web_canvas;
 
+pub use asset_batch::*;
 pub use assets::*;
+pub use bloom::*;
 pub use camera::*;
 pub use color::*;
+pub use cubemap::*;
+#[cfg(feature = "debug-draw")]
+pub use debug_draw::*;
 pub use depth_buffer::*;
 pub use gpu::*;
+pub use gpu_capabilities::*;
+pub(crate) use gpu_profiler::*;
+#[cfg(feature = "hot-reload")]
+pub(crate) use hot_reload::*;
 pub use instance_buffer::*;
+pub use light::*;
 pub use mesh::*;
+pub(crate) use mipmap::*;
 pub use model::*;
+pub use nine_patch::*;
+pub use pipeline_cache::*;
+pub use post_process::*;
 pub use render_encoder::*;
 pub use render_target::*;
 pub use renderer::*;
+pub(crate) use scene_transition::*;
 pub use screen_config::*;
+pub use screenshot::*;
 pub use shader::*;
+#[cfg(feature = "gltf")]
+pub use skinned_model::*;
 pub use sprite::*;
+pub use ui_camera::*;
 pub use sprite_array::*;
+pub use terrain::*;
 pub use uniform::*;
+pub use uniform_pool::*;
+#[cfg(multi_window)]
+pub use window_manager::*;
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_canvas::*;
@@ -1,5 +1,7 @@
 use std::io::{BufReader, Cursor};
 
+use anyhow::{anyhow, Context, Result};
+
 use crate::{
     graphics::{Gpu, Mesh3D, MeshBuilder3D, Sprite, SpriteBuilder, Vertex3D},
     math::{Vector2, Vector3},
@@ -8,12 +10,25 @@ use crate::{
 pub struct ModelBuilder {
     pub meshes: Vec<tobj::Model>,
     pub sprites: Vec<Vec<u8>>,
+    /// Obj file the model was loaded from, used for hot reloading
+    pub resource_path: Option<String>,
 }
 
 impl ModelBuilder {
+    /// Loads an OBJ file with its materials, or a glTF file with the `gltf` feature
     pub fn resource(path: &str) -> Self {
+        Self::try_resource(path).unwrap_or_else(|err| panic!("Cannot load model {path}: {err}"))
+    }
+
+    /// Like [ModelBuilder::resource] but returns an error when a file is missing or invalid
+    pub fn try_resource(path: &str) -> Result<Self> {
+        #[cfg(feature = "gltf")]
+        if path.ends_with(".gltf") || path.ends_with(".glb") {
+            return Self::gltf_resource(path);
+        }
+
         let resources = crate::app::global_resources();
-        let obj_text = resources.load_string(path).unwrap();
+        let obj_text = resources.load_string(path)?;
         let obj_cursor = Cursor::new(&obj_text);
         let mut obj_reader = BufReader::new(obj_cursor);
         let mut path_buf: std::path::PathBuf = path.into();
@@ -28,27 +43,33 @@ impl ModelBuilder {
                 ..Default::default()
             },
             |p| {
-                let mat_text = resources
-                    .load_string(path_buf.join(p).to_str().unwrap())
-                    .unwrap();
+                let mat_text = path_buf
+                    .join(p)
+                    .to_str()
+                    .and_then(|p| resources.load_string(p).ok())
+                    .ok_or(tobj::LoadError::OpenFileFailed)?;
                 tobj::load_mtl_buf(&mut BufReader::new(Cursor::new(mat_text)))
             },
         )
-        .unwrap();
+        .with_context(|| format!("Cannot parse {path}"))?;
 
         let mut sprites = Vec::new();
-        for m in obj_materials.unwrap() {
-            sprites.push(
-                resources
-                    .load_bytes(path_buf.join(m.diffuse_texture.unwrap()).to_str().unwrap())
-                    .unwrap(),
-            );
+        for m in obj_materials.with_context(|| format!("Cannot load materials of {path}"))? {
+            let texture = m
+                .diffuse_texture
+                .ok_or_else(|| anyhow!("Material {} has no diffuse texture", m.name))?;
+            let texture_path = path_buf.join(texture);
+            let texture_path = texture_path
+                .to_str()
+                .ok_or_else(|| anyhow!("Invalid texture path {}", texture_path.display()))?;
+            sprites.push(resources.load_bytes(texture_path)?);
         }
 
-        Self {
+        Ok(Self {
             meshes: obj_meshes,
             sprites,
-        }
+            resource_path: Some(path.to_owned()),
+        })
     }
 
     pub fn bytes(obj: &str, mtl: &[(&str, &str)], materials: &[(&str, &[u8])]) -> Self {
@@ -84,6 +105,7 @@ impl ModelBuilder {
         Self {
             meshes: obj_meshes,
             sprites,
+            resource_path: None,
         }
     }
 }
@@ -0,0 +1,151 @@
+use std::ops::Deref;
+
+use crate::{
+    graphics::{
+        Gpu, Instance, Instance2D, MeshBuilder2D, PositionVertex2D, Sprite, SpriteBuilder, Uniform,
+        Vertex2D,
+    },
+    math::{Isometry2, Vector2, Vector4},
+};
+
+pub type NinePatchInstance2D = Instance2D<NinePatchData>;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NinePatchData {
+    /// Left, right, bottom and top border in world units
+    pub border: Vector4<f32>,
+    /// Left, right, bottom and top border in texture coordinates
+    pub tex_border: Vector4<f32>,
+}
+
+impl Instance for NinePatchInstance2D {
+    const ATTRIBUTES: &'static [wgpu::VertexFormat] = &[
+        wgpu::VertexFormat::Float32x2,
+        wgpu::VertexFormat::Float32x4,
+        wgpu::VertexFormat::Float32x4,
+        wgpu::VertexFormat::Float32x4,
+    ];
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NinePatchBorder {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+}
+
+impl NinePatchBorder {
+    pub const fn new(left: u32, right: u32, top: u32, bottom: u32) -> Self {
+        Self {
+            left,
+            right,
+            top,
+            bottom,
+        }
+    }
+
+    pub const fn uniform(border: u32) -> Self {
+        Self::new(border, border, border, border)
+    }
+}
+
+#[derive(Debug)]
+pub struct NinePatchSprite {
+    sprite: Sprite,
+    border: NinePatchBorder,
+}
+
+impl NinePatchSprite {
+    pub fn new<D: Deref<Target = [u8]>>(
+        gpu: &Gpu,
+        desc: SpriteBuilder<D>,
+        border: NinePatchBorder,
+    ) -> Self {
+        let sprite = Sprite::new(gpu, desc);
+        let size = sprite.size();
+        assert!(
+            border.left + border.right <= size.x && border.top + border.bottom <= size.y,
+            "Nine patch border exceeds the sprite size!"
+        );
+        Self { sprite, border }
+    }
+
+    /// Mesh of 3x3 cells where every vertex position is the cell coordinate from 0 to 3. The
+    /// nine patch shader resolves the real position from the instance.
+    pub fn mesh() -> MeshBuilder2D<PositionVertex2D> {
+        let mut vertices = Vec::with_capacity(16);
+        for y in 0..4 {
+            for x in 0..4 {
+                vertices.push(Vertex2D {
+                    pos: Vector2::new(x as f32, y as f32),
+                    data: (),
+                });
+            }
+        }
+
+        let mut indices = Vec::with_capacity(54);
+        for y in 0..3 {
+            for x in 0..3 {
+                let i = y * 4 + x;
+                indices.extend_from_slice(&[i, i + 1, i + 5, i, i + 5, i + 4]);
+            }
+        }
+        MeshBuilder2D::custom(vertices, indices)
+    }
+
+    /// `pixel_size` is the size of one source pixel in world units and determines how big the
+    /// unscaled corners are.
+    pub fn data(&self, pixel_size: f32) -> NinePatchData {
+        let size = self.sprite.size().cast::<f32>();
+        let border = self.border;
+        NinePatchData {
+            border: Vector4::new(
+                border.left as f32,
+                border.right as f32,
+                border.bottom as f32,
+                border.top as f32,
+            ) * pixel_size,
+            tex_border: Vector4::new(
+                border.left as f32 / size.x,
+                border.right as f32 / size.x,
+                border.bottom as f32 / size.y,
+                border.top as f32 / size.y,
+            ),
+        }
+    }
+
+    pub fn instance(
+        &self,
+        position: Isometry2<f32>,
+        size: Vector2<f32>,
+        pixel_size: f32,
+    ) -> NinePatchInstance2D {
+        NinePatchInstance2D::new(position, size, self.data(pixel_size))
+    }
+
+    pub fn set_border(&mut self, border: NinePatchBorder) {
+        self.border = border;
+    }
+
+    pub const fn border(&self) -> NinePatchBorder {
+        self.border
+    }
+
+    pub const fn sprite(&self) -> &Sprite {
+        &self.sprite
+    }
+
+    pub fn sprite_mut(&mut self) -> &mut Sprite {
+        &mut self.sprite
+    }
+}
+
+impl Uniform for NinePatchSprite {
+    fn bind_group(&self) -> &wgpu::BindGroup {
+        self.sprite.bind_group()
+    }
+}
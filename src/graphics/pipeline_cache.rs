@@ -0,0 +1,187 @@
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+
+/// Everything that makes two pipelines created by [Shader::new](crate::graphics::Shader::new)
+/// differ, except for the label
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) struct PipelineKey {
+    pub layouts: Vec<wgpu::Id<wgpu::BindGroupLayout>>,
+    pub vertex_module: wgpu::Id<wgpu::ShaderModule>,
+    pub fragment_module: wgpu::Id<wgpu::ShaderModule>,
+    pub vertex_entry: &'static str,
+    pub fragment_entry: &'static str,
+    pub buffers: Vec<(
+        wgpu::BufferAddress,
+        wgpu::VertexStepMode,
+        Vec<wgpu::VertexAttribute>,
+    )>,
+    pub blend: wgpu::BlendState,
+    pub write_mask: wgpu::ColorWrites,
+    pub depth_stencil: Option<wgpu::DepthStencilState>,
+}
+
+pub(crate) type CachedPipelines = Vec<(u32, Arc<wgpu::RenderPipeline>)>;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PipelineCacheStats {
+    /// Distinct shader configurations that were compiled
+    pub pipelines: usize,
+    /// Distinct combinations of bind group layouts
+    pub layouts: usize,
+    /// Shaders that reused a cached pipeline
+    pub hits: usize,
+    /// Shaders that compiled a new pipeline
+    pub misses: usize,
+}
+
+impl PipelineCacheStats {
+    /// Share of created shaders that reused a cached pipeline, 0 if none were created yet
+    pub fn hit_rate(&self) -> f32 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f32 / total as f32
+        }
+    }
+}
+
+impl std::fmt::Display for PipelineCacheStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} pipelines, {} layouts, {} hits, {} misses, hit rate: {:.1}%",
+            self.pipelines,
+            self.layouts,
+            self.hits,
+            self.misses,
+            self.hit_rate() * 100.0
+        )
+    }
+}
+
+/// Shares the pipelines of shaders with identical configs, so creating the same shader again,
+/// e.g. in an entity constructor, does not compile it again
+#[derive(Default)]
+pub(crate) struct PipelineCache {
+    pipelines: FxHashMap<PipelineKey, CachedPipelines>,
+    layouts: FxHashMap<Vec<wgpu::Id<wgpu::BindGroupLayout>>, Arc<wgpu::PipelineLayout>>,
+    hits: usize,
+    misses: usize,
+}
+
+impl PipelineCache {
+    pub fn get(&mut self, key: &PipelineKey) -> Option<CachedPipelines> {
+        let pipelines = self.pipelines.get(key).cloned();
+        if pipelines.is_some() {
+            self.hits += 1;
+        }
+        pipelines
+    }
+
+    pub fn insert(&mut self, key: PipelineKey, pipelines: CachedPipelines) {
+        self.misses += 1;
+        self.pipelines.insert(key, pipelines);
+    }
+
+    pub fn layout(
+        &mut self,
+        device: &wgpu::Device,
+        layouts: &[&wgpu::BindGroupLayout],
+    ) -> Arc<wgpu::PipelineLayout> {
+        self.layouts
+            .entry(layouts.iter().map(|layout| layout.global_id()).collect())
+            .or_insert_with(|| {
+                Arc::new(
+                    device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                        label: Some("pipeline_layout"),
+                        bind_group_layouts: layouts,
+                        push_constant_ranges: &[],
+                    }),
+                )
+            })
+            .clone()
+    }
+
+    pub fn stats(&self) -> PipelineCacheStats {
+        PipelineCacheStats {
+            pipelines: self.pipelines.len(),
+            layouts: self.layouts.len(),
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pipelines.clear();
+        self.layouts.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{BlendState, Gpu, Shader, ShaderConfig, ShaderModuleSource};
+
+    #[test]
+    fn stats_format() {
+        let stats = PipelineCacheStats {
+            pipelines: 2,
+            layouts: 1,
+            hits: 98,
+            misses: 2,
+        };
+        assert_eq!(stats.hit_rate(), 0.98);
+        assert_eq!(
+            stats.to_string(),
+            "2 pipelines, 1 layouts, 98 hits, 2 misses, hit rate: 98.0%"
+        );
+        assert_eq!(PipelineCacheStats::default().hit_rate(), 0.0);
+        assert_eq!(
+            PipelineCacheStats::default().to_string(),
+            "0 pipelines, 0 layouts, 0 hits, 0 misses, hit rate: 0.0%"
+        );
+    }
+
+    #[test]
+    #[ignore = "needs a graphics adapter"]
+    fn identical_configs_share_one_pipeline() {
+        let gpu = Gpu::headless().expect("No graphics adapter available");
+        let module = gpu.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(
+                "@vertex fn vs_main() -> @builtin(position) vec4<f32> { return vec4<f32>(0.0); }
+                @fragment fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(1.0); }"
+                    .into(),
+            ),
+        });
+        let config = |blend| ShaderConfig {
+            source: ShaderModuleSource::Single(&module),
+            blend,
+            ..Default::default()
+        };
+
+        let shaders: Vec<Shader> = (0..100)
+            .map(|_| Shader::new(&gpu, config(BlendState::ALPHA_BLENDING)))
+            .collect();
+        assert_eq!(shaders.len(), 100);
+        assert_eq!(
+            gpu.pipeline_cache_stats(),
+            PipelineCacheStats {
+                pipelines: 1,
+                layouts: 1,
+                hits: 99,
+                misses: 1,
+            }
+        );
+
+        Shader::new(&gpu, config(BlendState::REPLACE));
+        assert_eq!(gpu.pipeline_cache_stats().pipelines, 2);
+
+        gpu.clear_pipeline_cache();
+        assert_eq!(gpu.pipeline_cache_stats().pipelines, 0);
+        Shader::new(&gpu, config(BlendState::REPLACE));
+        assert_eq!(gpu.pipeline_cache_stats().misses, 3);
+    }
+}
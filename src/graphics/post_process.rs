@@ -0,0 +1,198 @@
+use std::fmt;
+
+use parking_lot::{Mutex, MutexGuard};
+
+use crate::{
+    graphics::{
+        Gpu, Shader, ShaderConfig, ShaderModuleSource, SpriteRenderTarget, SpriteVertex2D,
+        UniformData, UniformField, VertexBuffers,
+    },
+    math::{Vector2, Vector4},
+};
+use wgpu::{include_wgsl, BlendState};
+
+pub struct PostProcessPass {
+    pub shader: Shader,
+    pub params: Option<UniformData<Vector4<f32>>>,
+}
+
+/// A fullscreen effect of one or more passes. Every pass samples the output of the previous pass
+/// from slot 1 and can read optional parameters from slot 2.
+pub struct PostProcess {
+    passes: Vec<PostProcessPass>,
+    pub(crate) targets: Mutex<Vec<SpriteRenderTarget>>,
+}
+
+impl PostProcess {
+    pub fn new(shader: Shader, params: Option<UniformData<Vector4<f32>>>) -> Self {
+        Self {
+            passes: vec![PostProcessPass { shader, params }],
+            targets: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn pass(mut self, shader: Shader, params: Option<UniformData<Vector4<f32>>>) -> Self {
+        self.passes.push(PostProcessPass { shader, params });
+        self
+    }
+
+    /// Creates a shader with the layout that is expected for a pass
+    pub fn create_shader(gpu: &Gpu, name: &str, source: wgpu::ShaderModuleDescriptor) -> Shader {
+        let uniforms = [
+            UniformField::Camera,
+            UniformField::Sprite,
+            UniformField::SingleUniform,
+        ];
+        gpu.create_shader(ShaderConfig {
+            name: Some(name),
+            source: ShaderModuleSource::Single(&gpu.create_shader_module(source)),
+            uniforms: &uniforms,
+            vertex_buffers: VertexBuffers::vertex::<SpriteVertex2D>(),
+            blend: BlendState::REPLACE,
+            ..Default::default()
+        })
+    }
+
+    pub fn create_params(gpu: &Gpu, params: Vector4<f32>) -> UniformData<Vector4<f32>> {
+        UniformData::new(
+            gpu,
+            gpu.default_layouts().single_uniform_layout.clone(),
+            &[params],
+        )
+    }
+
+    pub fn grayscale(gpu: &Gpu) -> Self {
+        let shader = gpu.create_shader(ShaderConfig {
+            name: Some("grayscale"),
+            source: ShaderModuleSource::Single(&gpu.create_shader_module(include_wgsl!(
+                "../../static/shader/2d/post_process/grayscale.wgsl"
+            ))),
+            uniforms: &[UniformField::Camera, UniformField::Sprite],
+            vertex_buffers: VertexBuffers::vertex::<SpriteVertex2D>(),
+            blend: BlendState::REPLACE,
+            ..Default::default()
+        });
+        Self::new(shader, None)
+    }
+
+    /// Darkens the screen towards the edges. `radius` is the distance from the center in texture
+    /// coordinates where the darkening ends.
+    pub fn vignette(gpu: &Gpu, intensity: f32, radius: f32, softness: f32) -> Self {
+        let shader = Self::create_shader(
+            gpu,
+            "vignette",
+            include_wgsl!("../../static/shader/2d/post_process/vignette.wgsl"),
+        );
+        let params = Self::create_params(gpu, Vector4::new(intensity, radius, softness, 0.0));
+        Self::new(shader, Some(params))
+    }
+
+    /// Separable blur with a horizontal and a vertical pass. `radius` is in pixels.
+    pub fn gaussian_blur(gpu: &Gpu, radius: f32) -> Self {
+        let source = || include_wgsl!("../../static/shader/2d/post_process/blur.wgsl");
+        let horizontal = Self::create_params(gpu, Vector4::new(1.0, 0.0, radius, 0.0));
+        let vertical = Self::create_params(gpu, Vector4::new(0.0, 1.0, radius, 0.0));
+        Self::new(
+            Self::create_shader(gpu, "gaussian_blur_horizontal", source()),
+            Some(horizontal),
+        )
+        .pass(
+            Self::create_shader(gpu, "gaussian_blur_vertical", source()),
+            Some(vertical),
+        )
+    }
+
+    pub fn passes(&self) -> &[PostProcessPass] {
+        &self.passes
+    }
+
+    pub fn passes_mut(&mut self) -> &mut [PostProcessPass] {
+        &mut self.passes
+    }
+
+    /// Updates the parameters of a pass
+    pub fn set_params(&mut self, gpu: &Gpu, pass: usize, params: Vector4<f32>) {
+        if let Some(uniform) = &mut self.passes[pass].params {
+            uniform.write(gpu, &[params]);
+        }
+    }
+
+    /// Locks the intermediate targets of every effect in the chain
+    pub(crate) fn lock_targets<'e>(
+        effects: &[&'e PostProcess],
+    ) -> Result<Vec<MutexGuard<'e, Vec<SpriteRenderTarget>>>, PostProcessError> {
+        effects
+            .iter()
+            .enumerate()
+            .map(|(index, effect)| {
+                effect
+                    .targets
+                    .try_lock()
+                    .ok_or(PostProcessError::InUse(index))
+            })
+            .collect()
+    }
+
+    pub(crate) fn resize_targets(
+        targets: &mut Vec<SpriteRenderTarget>,
+        gpu: &Gpu,
+        size: Vector2<u32>,
+        amount: usize,
+    ) {
+        for target in targets.iter_mut() {
+            target.resize(gpu, size);
+        }
+        while targets.len() < amount {
+            targets.push(SpriteRenderTarget::new(gpu, size));
+        }
+    }
+}
+
+/// Returned by [RenderEncoder::post_process_to](crate::graphics::RenderEncoder::post_process_to)
+/// before anything is drawn
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PostProcessError {
+    /// The source is not a [SpriteRenderTarget]
+    UnsupportedSource,
+    /// The effect at this index of the chain is already in use, usually because it appears
+    /// twice in the chain
+    InUse(usize),
+}
+
+impl fmt::Display for PostProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PostProcessError::UnsupportedSource => {
+                write!(f, "Only a SpriteRenderTarget can be post processed")
+            }
+            PostProcessError::InUse(index) => {
+                write!(
+                    f,
+                    "PostProcess at index {index} of the chain is already in use"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PostProcessError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore = "needs a graphics adapter"]
+    fn effect_twice_in_chain_is_rejected() {
+        let gpu = Gpu::headless().expect("No graphics adapter");
+        let grayscale = PostProcess::grayscale(&gpu);
+        let vignette = PostProcess::vignette(&gpu, 1.0, 0.5, 0.2);
+        assert!(PostProcess::lock_targets(&[&grayscale, &vignette]).is_ok());
+        assert_eq!(
+            PostProcess::lock_targets(&[&grayscale, &vignette, &grayscale]).err(),
+            Some(PostProcessError::InUse(2))
+        );
+        // The locks of the failed chain are released again
+        assert!(PostProcess::lock_targets(&[&grayscale]).is_ok());
+    }
+}
@@ -1,7 +1,8 @@
 use crate::graphics::{
-    AssetManager, Color, DefaultAssets, DepthBuffer, Gpu, RenderTarget, Renderer,
-    SpriteRenderTarget,
+    AssetManager, Bloom, Color, DefaultAssets, DepthBuffer, Gpu, PostProcess, PostProcessError,
+    RenderTarget, Renderer, Shader, Sprite, SpriteRenderTarget, UniformData,
 };
+use crate::math::Vector4;
 
 pub struct RenderEncoder<'a> {
     pub inner: wgpu::CommandEncoder,
@@ -9,6 +10,8 @@ pub struct RenderEncoder<'a> {
     pub default_assets: &'a DefaultAssets,
     pub gpu: &'a Gpu,
     pub default_target: &'a dyn RenderTarget,
+    /// Label of the following passes in [Gpu::pass_timings]
+    pub(crate) profiler_label: &'static str,
 }
 
 impl<'a> Clone for RenderEncoder<'a> {
@@ -23,6 +26,8 @@ impl<'a> Clone for RenderEncoder<'a> {
 }
 
 impl<'a> RenderEncoder<'a> {
+    pub const PROFILER_LABEL: &'static str = "encoder";
+
     pub fn new(
         gpu: &'a Gpu,
         assets: &'a AssetManager,
@@ -41,6 +46,7 @@ impl<'a> RenderEncoder<'a> {
             default_assets,
             default_target,
             gpu,
+            profiler_label: Self::PROFILER_LABEL,
         }
     }
 
@@ -68,12 +74,17 @@ impl<'a> RenderEncoder<'a> {
         clear: Option<Color>,
         render: impl FnOnce(&mut Renderer<'b>),
     ) {
+        let assets = self.assets;
+        let sky = self.default_assets.sky;
         let mut renderer = self.renderer(
             self.default_target,
             clear,
             Some(&self.default_assets.depth_buffer),
         );
 
+        if let Some(sky) = sky {
+            renderer.draw_skybox(&assets.cubemap(sky));
+        }
         (render)(&mut renderer);
     }
 
@@ -94,7 +105,11 @@ impl<'a> RenderEncoder<'a> {
         clear: Option<Color>,
         depth: Option<&'b DepthBuffer>,
     ) -> Renderer<'b> {
-        Renderer::new(
+        let mut profiler = self.gpu.profiler();
+        let timestamp_writes = profiler
+            .as_mut()
+            .and_then(|profiler| profiler.timestamp_writes(self.profiler_label));
+        Renderer::with_timestamps(
             &mut self.inner,
             self.assets,
             self.default_assets,
@@ -102,9 +117,15 @@ impl<'a> RenderEncoder<'a> {
             target,
             clear,
             depth,
+            timestamp_writes,
         )
     }
 
+    /// Labels the following passes in [Gpu::pass_timings] until the label is changed again
+    pub fn set_profiler_label(&mut self, label: &'static str) {
+        self.profiler_label = label;
+    }
+
     pub fn renderer2d(&mut self, clear: Option<Color>) -> Renderer<'_> {
         self.renderer2d_to(self.default_target, clear)
     }
@@ -129,6 +150,166 @@ impl<'a> RenderEncoder<'a> {
         );
     }
 
+    /// Calls `callback` with everything that has been drawn to the default target so far. The
+    /// image is read back asynchronously and is usually available one frame later.
+    pub fn request_screenshot(&mut self, callback: impl FnOnce(image::RgbaImage) + Send + 'static) {
+        self.request_screenshot_of(self.default_target, callback);
+    }
+
+    pub fn request_screenshot_of(
+        &mut self,
+        target: &dyn RenderTarget,
+        callback: impl FnOnce(image::RgbaImage) + Send + 'static,
+    ) {
+        self.gpu
+            .request_screenshot(&mut self.inner, target, Box::new(callback));
+    }
+
+    /// Applies the effects in order to the default target
+    pub fn post_process(&mut self, effects: &[&PostProcess]) -> Result<(), PostProcessError> {
+        self.post_process_to(self.default_target, self.default_target, effects)
+    }
+
+    /// Applies the effects in order to `src` and writes the result into `target`. `src` has to be
+    /// a [SpriteRenderTarget] and an effect can only appear once per chain, otherwise nothing is
+    /// drawn and an error is returned.
+    pub fn post_process_to(
+        &mut self,
+        src: &dyn RenderTarget,
+        target: &dyn RenderTarget,
+        effects: &[&PostProcess],
+    ) -> Result<(), PostProcessError> {
+        let src = src
+            .downcast_ref::<SpriteRenderTarget>()
+            .ok_or(PostProcessError::UnsupportedSource)?;
+        let size = src.size();
+        let mut effect_targets = PostProcess::lock_targets(effects)?;
+        for (effect, targets) in effects.iter().zip(&mut effect_targets) {
+            PostProcess::resize_targets(targets, self.gpu, size, effect.passes().len().min(2));
+        }
+
+        let same_target = src.texture().global_id() == target.texture().global_id();
+        let total_passes: usize = effects.iter().map(|effect| effect.passes().len()).sum();
+        if total_passes == 0 {
+            return Ok(());
+        }
+
+        let mut current: &Sprite = src.sprite();
+        let mut pass_index = 0;
+        for (effect, targets) in effects.iter().zip(&effect_targets) {
+            for (i, pass) in effect.passes().iter().enumerate() {
+                pass_index += 1;
+                let last = pass_index == total_passes && !same_target;
+                let dst: &dyn RenderTarget = if last { target } else { &targets[i % 2] };
+                let mut renderer = self.renderer(dst, Some(Color::TRANSPARENT), None);
+                renderer.use_shader(&pass.shader);
+                renderer.use_mesh(&renderer.default_assets.sprite_mesh);
+                renderer.use_camera(&renderer.default_assets.unit_camera.0);
+                renderer.use_sprite(current, 1);
+                if let Some(params) = &pass.params {
+                    renderer.use_uniform_data(params, 2);
+                }
+                renderer.render();
+                if !last {
+                    current = targets[i % 2].sprite();
+                }
+            }
+        }
+
+        if same_target {
+            let mut renderer = self.renderer(target, Some(Color::TRANSPARENT), None);
+            renderer.draw_sprite_mesh(
+                &renderer.default_assets.sprite_mesh,
+                &renderer.default_assets.unit_camera.0,
+                current,
+            );
+        }
+        Ok(())
+    }
+
+    fn fullscreen_pass(
+        &mut self,
+        target: &dyn RenderTarget,
+        clear: Option<Color>,
+        shader: &Shader,
+        sprite: &Sprite,
+        params: &UniformData<Vector4<f32>>,
+    ) {
+        let mut renderer = self.renderer(target, clear, None);
+        renderer.use_shader(shader);
+        renderer.use_mesh(&renderer.default_assets.sprite_mesh);
+        renderer.use_camera(&renderer.default_assets.unit_camera.0);
+        renderer.use_sprite(sprite, 1);
+        renderer.use_uniform_data(params, 2);
+        renderer.render();
+    }
+
+    /// Adds a bloom of the bright parts of `src` to `target`. `src` has to be a
+    /// [SpriteRenderTarget], `target` can be any target including `src` itself.
+    pub fn apply_bloom(
+        &mut self,
+        bloom: &Bloom,
+        src: &dyn RenderTarget,
+        target: &dyn RenderTarget,
+    ) {
+        let src = src
+            .downcast_ref::<SpriteRenderTarget>()
+            .expect("Cannot apply bloom to this texture!");
+        let Some(first) = bloom.mips.first() else {
+            return;
+        };
+
+        self.fullscreen_pass(
+            first,
+            Some(Color::TRANSPARENT),
+            &bloom.threshold_shader,
+            src.sprite(),
+            &bloom.threshold_params,
+        );
+        for window in bloom.mips.windows(2) {
+            self.fullscreen_pass(
+                &window[1],
+                Some(Color::TRANSPARENT),
+                &bloom.downsample_shader,
+                window[0].sprite(),
+                &bloom.upsample_params,
+            );
+        }
+        for window in bloom.mips.windows(2).rev() {
+            self.fullscreen_pass(
+                &window[0],
+                None,
+                &bloom.upsample_shader,
+                window[1].sprite(),
+                &bloom.upsample_params,
+            );
+        }
+
+        if !Bloom::same_target(src, target) {
+            self.copy_target(src, target);
+        }
+        self.fullscreen_pass(
+            target,
+            None,
+            &bloom.upsample_shader,
+            first.sprite(),
+            &bloom.composite_params,
+        );
+    }
+
+    /// Queues the commands recorded so far for the next [Gpu::submit] and continues with a new
+    /// encoder, so commands of other encoders finished afterwards run after them
+    pub fn flush(&mut self) {
+        let encoder = self
+            .gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("render_encoder"),
+            });
+        let inner = std::mem::replace(&mut self.inner, encoder);
+        self.gpu.command_buffers.lock().push(inner.finish());
+    }
+
     pub fn finish_get(self) -> wgpu::CommandBuffer {
         self.inner.finish()
     }
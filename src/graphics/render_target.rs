@@ -10,6 +10,7 @@ pub trait RenderTarget: Downcast + Send + Sync {
     fn msaa(&self) -> Option<&wgpu::TextureView>;
     fn view(&self) -> &wgpu::TextureView;
     fn texture(&self) -> &wgpu::Texture;
+    fn samples(&self) -> u32;
     fn size(&self) -> Vector2<u32> {
         Vector2::new(self.texture().width(), self.texture().height())
     }
@@ -46,6 +47,7 @@ pub struct SurfaceRenderTarget {
     pub surface_texture: wgpu::SurfaceTexture,
     pub target_view: wgpu::TextureView,
     pub msaa_view: Option<wgpu::TextureView>,
+    pub samples: u32,
 }
 
 impl SurfaceRenderTarget {
@@ -66,6 +68,10 @@ impl RenderTarget for SurfaceRenderTarget {
     fn msaa(&self) -> Option<&wgpu::TextureView> {
         self.msaa_view.as_ref()
     }
+
+    fn samples(&self) -> u32 {
+        self.samples
+    }
 }
 
 impl SurfaceRenderTarget {}
@@ -82,6 +88,10 @@ impl RenderTarget for SpriteRenderTarget {
     fn texture(&self) -> &wgpu::Texture {
         self.sprite().texture()
     }
+
+    fn samples(&self) -> u32 {
+        self.samples
+    }
 }
 
 #[derive(Debug)]
@@ -89,6 +99,7 @@ pub struct SpriteRenderTarget {
     target_msaa: Option<wgpu::TextureView>,
     target_view: wgpu::TextureView,
     target: Sprite,
+    samples: u32,
 }
 
 impl SpriteRenderTarget {
@@ -96,22 +107,48 @@ impl SpriteRenderTarget {
         Self::custom(gpu, SpriteBuilder::empty(size).format(gpu.format()))
     }
 
+    /// Creates a target with its own sample count. Everything rendered to it gets resolved into
+    /// [SpriteRenderTarget::sprite] at the end of each render pass.
+    pub fn new_msaa(gpu: &Gpu, size: Vector2<u32>, samples: u32) -> Self {
+        Self::custom_msaa(gpu, SpriteBuilder::empty(size), samples)
+    }
+
     pub fn custom<D: Deref<Target = [u8]>>(gpu: &Gpu, sprite: SpriteBuilder<D>) -> Self {
+        Self::custom_with_samples(gpu, sprite, gpu.samples())
+    }
+
+    pub fn custom_msaa<D: Deref<Target = [u8]>>(
+        gpu: &Gpu,
+        sprite: SpriteBuilder<D>,
+        samples: u32,
+    ) -> Self {
+        Self::custom_with_samples(gpu, sprite, gpu.validate_samples(samples))
+    }
+
+    fn custom_with_samples<D: Deref<Target = [u8]>>(
+        gpu: &Gpu,
+        sprite: SpriteBuilder<D>,
+        samples: u32,
+    ) -> Self {
         let size = sprite.size;
         let target = Sprite::new(gpu, sprite.format(gpu.format()));
         let target_view = target
             .texture()
             .create_view(&wgpu::TextureViewDescriptor::default());
-        let target_msaa = if gpu.samples() == 1 {
+        let target_msaa = if samples == 1 {
             None
         } else {
-            Some(SpriteRenderTarget::create_msaa(gpu, size).create_view(&Default::default()))
+            Some(
+                SpriteRenderTarget::create_msaa(gpu, size, samples)
+                    .create_view(&Default::default()),
+            )
         };
 
         Self {
             target_msaa,
             target,
             target_view,
+            samples,
         }
     }
 
@@ -125,7 +162,7 @@ impl SpriteRenderTarget {
         target
     }
 
-    pub fn create_msaa(gpu: &Gpu, size: Vector2<u32>) -> wgpu::Texture {
+    pub fn create_msaa(gpu: &Gpu, size: Vector2<u32>, samples: u32) -> wgpu::Texture {
         let multisampled_frame_descriptor = &wgpu::TextureDescriptor {
             size: wgpu::Extent3d {
                 width: size.x,
@@ -133,7 +170,7 @@ impl SpriteRenderTarget {
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
-            sample_count: gpu.samples(),
+            sample_count: samples,
             dimension: wgpu::TextureDimension::D2,
             format: gpu.format(),
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -146,7 +183,11 @@ impl SpriteRenderTarget {
 
     pub fn resize(&mut self, gpu: &Gpu, size: Vector2<u32>) {
         if self.size() != size {
-            *self = Self::new(gpu, size);
+            *self = Self::custom_with_samples(
+                gpu,
+                SpriteBuilder::empty(size).format(gpu.format()),
+                self.samples,
+            );
         }
     }
 
@@ -1,15 +1,23 @@
+#[cfg(feature = "gltf")]
+use crate::graphics::SkinnedModel;
 #[cfg(feature = "text")]
-use crate::text::{Font, TextMesh};
+use crate::text::{Font, Text};
 
 use crate::graphics::{
-    AssetManager, Camera, CameraBuffer, CameraBuffer2D, Color, ColorInstance2D, ColorMesh2D,
-    DefaultAssets, DepthBuffer, Gpu, GpuId, Instance, Instance3D, InstanceBuffer, Mesh, Model,
-    PositionInstance2D, PositionMesh2D, RenderTarget, Shader, Sprite, SpriteArray,
-    SpriteArrayCropInstance2D, SpriteArrayMesh2D, SpriteCropInstance2D, SpriteMesh2D, Uniform,
-    UniformData, Vertex,
+    AssetManager, BillboardInstance3D, Camera, CameraBuffer, CameraBuffer2D, Color,
+    ColorInstance2D, ColorMesh2D, Cubemap, DefaultAssets, DepthBuffer, Gpu, GpuId, Instance,
+    Instance3D, InstanceBuffer, Lights2D, Lights3D, Mesh, Model, NinePatchInstance2D,
+    NinePatchSprite, PositionInstance2D, PositionMesh2D, RenderTarget, Shader, ShaderHandle,
+    Sprite, SpriteArray, SpriteArrayCropInstance2D, SpriteArrayInstance2D, SpriteArrayMesh2D,
+    SpriteCropInstance2D, SpriteInstance2D, SpriteMesh2D, Terrain, UiCamera, Uniform, UniformData,
+    UniformSlice, Vertex,
 };
+use crate::{ecs::ParticleBlend, math::AABB, tilemap::TileMap};
 use std::ops::Range;
 
+#[cfg(feature = "log")]
+use crate::log::warn;
+
 #[derive(Default)]
 struct RenderCache {
     pub bound_shader: Option<GpuId<wgpu::RenderPipeline>>,
@@ -27,6 +35,8 @@ pub struct Renderer<'a> {
     render_pass: wgpu::RenderPass<'a>,
     cache: RenderCache,
     shader_uses_instancing: bool,
+    /// The bound shader has no pipeline for the samples of the target, draws are skipped
+    shader_missing: bool,
 }
 
 impl<'a> Renderer<'a> {
@@ -41,19 +51,51 @@ impl<'a> Renderer<'a> {
         target: &'a dyn RenderTarget,
         clear: Option<Color>,
         depth: Option<&'a DepthBuffer>,
+    ) -> Renderer<'a> {
+        Self::with_timestamps(
+            render_encoder,
+            assets,
+            default_assets,
+            gpu,
+            target,
+            clear,
+            depth,
+            None,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn with_timestamps(
+        render_encoder: &'a mut wgpu::CommandEncoder,
+        assets: &'a AssetManager,
+        default_assets: &'a DefaultAssets,
+        gpu: &'a Gpu,
+        target: &'a dyn RenderTarget,
+        clear: Option<Color>,
+        depth: Option<&'a DepthBuffer>,
+        timestamp_writes: Option<wgpu::RenderPassTimestampWrites<'_>>,
     ) -> Renderer<'a> {
         let render_pass = render_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("render_pass"),
             color_attachments: &[Some(target.attachment(clear))],
             depth_stencil_attachment: depth.map(|depth| wgpu::RenderPassDepthStencilAttachment {
                 view: depth.view(),
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: wgpu::StoreOp::Store,
-                }),
-                stencil_ops: None,
+                depth_ops: depth
+                    .format()
+                    .has_depth_aspect()
+                    .then_some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                stencil_ops: depth
+                    .format()
+                    .has_stencil_aspect()
+                    .then_some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(0),
+                        store: wgpu::StoreOp::Store,
+                    }),
             }),
-            timestamp_writes: None,
+            timestamp_writes,
             occlusion_query_set: None,
         });
 
@@ -67,6 +109,7 @@ impl<'a> Renderer<'a> {
             cache: RenderCache::default(),
             instances: 0..0,
             shader_uses_instancing: false,
+            shader_missing: false,
         }
     }
 
@@ -87,6 +130,22 @@ impl<'a> Renderer<'a> {
             .set_viewport(x, y, w, h, min_depth, max_depth)
     }
 
+    /// Restricts drawing to the visible area of a [UiScaling::Letterbox](crate::graphics::UiScaling::Letterbox)
+    /// camera, so the bars keep the clear color. Does nothing for other scalings.
+    pub fn use_letterbox(&mut self, camera: &UiCamera) {
+        if let Some((position, size)) = camera.letterbox() {
+            let target = self.target.size().cast::<f32>();
+            let position = position.component_mul(&target);
+            let size = size.component_mul(&target);
+            self.set_scissor_rect(
+                position.x as u32,
+                position.y as u32,
+                (size.x as u32).max(1),
+                (size.y as u32).max(1),
+            );
+        }
+    }
+
     pub fn set_stencil_reference(&mut self, reference: u32) {
         self.render_pass.set_stencil_reference(reference)
     }
@@ -114,14 +173,34 @@ impl<'a> Renderer<'a> {
         self.use_uniform(camera.uniform(), Self::CAMERA_SLOT)
     }
 
-    pub fn use_shader(&mut self, shader: &Shader) {
-        let pipeline = shader.pipeline();
+    /// Binds the shader once it is compiled, returns false while it is not ready so the draw can
+    /// be skipped
+    pub fn use_shader_handle(&mut self, shader: &ShaderHandle) -> bool {
+        match shader.get() {
+            Some(shader) => self.use_shader(shader),
+            None => false,
+        }
+    }
+
+    /// Binds the pipeline of the shader for the samples of the target. If the shader was not
+    /// compiled for them, a warning is logged, false is returned and the draws are skipped until
+    /// another shader is bound.
+    pub fn use_shader(&mut self, shader: &Shader) -> bool {
+        let samples = self.target.samples();
+        let Some(pipeline) = shader.pipeline_for(samples) else {
+            #[cfg(feature = "log")]
+            warn!("Shader is not compiled for multisample X{samples}, skipping its draws! See GpuConfig::render_target_samples.");
+            self.shader_missing = true;
+            return false;
+        };
+        self.shader_missing = false;
         let pipeline_id = pipeline.global_id();
         if self.cache.bound_shader.map_or(true, |id| id != pipeline_id) {
             self.cache.bound_shader = Some(pipeline_id);
-            self.render_pass.set_pipeline(shader.pipeline());
+            self.render_pass.set_pipeline(pipeline);
             self.shader_uses_instancing = shader.instance_size() != 0;
         }
+        true
     }
 
     pub fn use_mesh<T: Vertex>(&mut self, mesh: &Mesh<T>) {
@@ -151,6 +230,15 @@ impl<'a> Renderer<'a> {
         }
     }
 
+    /// The slot has to be declared as [UniformField::DynamicUniform](crate::graphics::UniformField::DynamicUniform)
+    pub fn use_uniform_slice(&mut self, uniform: &UniformSlice, slot: u32) {
+        if let Some(cache_slot) = self.cache.bound_uniforms.get_mut(slot as usize) {
+            *cache_slot = None;
+        }
+        self.render_pass
+            .set_bind_group(slot, uniform.bind_group(), &[uniform.offset()]);
+    }
+
     pub fn use_sprite(&mut self, sprite: &Sprite, slot: u32) {
         self.use_uniform(sprite, slot);
     }
@@ -172,6 +260,10 @@ impl<'a> Renderer<'a> {
     }
 
     pub fn render_custom(&mut self, indices: Range<u32>, base_vertex: i32, instances: Range<u32>) {
+        if self.shader_missing {
+            return;
+        }
+        self.gpu.count_draw(instances.len() as u32);
         self.render_pass
             .draw_indexed(indices, base_vertex, instances)
     }
@@ -233,22 +325,107 @@ impl<'a> Renderer<'a> {
         }
     }
 
-    pub fn draw_sprite_array(
+    /// Draws the instances of `key` written with
+    /// [AssetManager::write_keyed_instances](crate::graphics::AssetManager::write_keyed_instances)
+    pub fn draw_sprite_keyed(
         &mut self,
-        instances: &InstanceBuffer<SpriteArrayCropInstance2D>,
+        instances: &InstanceBuffer<PositionInstance2D>,
         mesh: &SpriteMesh2D,
         camera: &CameraBuffer2D,
+        key: u32,
         sprite: &Sprite,
+    ) {
+        let Some(range) = instances.key_range(key) else {
+            return;
+        };
+        if mesh.vertex_buffer_size() != 0 && mesh.index_buffer_size() != 0 {
+            self.use_shader(&self.default_assets.sprite_shader);
+            self.use_instances_with_range(instances, range);
+            self.use_mesh(mesh);
+            self.use_camera(camera);
+            self.use_sprite(sprite, 1);
+            self.render();
+        }
+    }
+
+    /// Draws sprites lit by `lights`, using the normals of `normal_map`. The normal map has to
+    /// match the layout of `sprite`.
+    pub fn draw_sprite_lit(
+        &mut self,
+        instances: &InstanceBuffer<SpriteInstance2D>,
+        mesh: &SpriteMesh2D,
+        camera: &CameraBuffer2D,
+        sprite: &Sprite,
+        normal_map: &Sprite,
+        lights: &UniformData<Lights2D>,
     ) {
         if instances.buffer_size() != 0
             && mesh.vertex_buffer_size() != 0
             && mesh.index_buffer_size() != 0
         {
-            self.use_shader(&self.default_assets.sprite_shader);
+            self.use_shader(&self.default_assets.sprite_lit_shader);
             self.use_instances(instances);
             self.use_mesh(mesh);
             self.use_camera(camera);
             self.use_sprite(sprite, 1);
+            self.use_sprite(normal_map, 2);
+            self.use_uniform_data(lights, 3);
+            self.render();
+        }
+    }
+
+    /// Draws every instance with the layer of its index, e.g. a whole tilemap in one call
+    pub fn draw_sprite_array(
+        &mut self,
+        instances: &InstanceBuffer<SpriteArrayInstance2D>,
+        mesh: &SpriteMesh2D,
+        camera: &CameraBuffer2D,
+        sprite_array: &SpriteArray,
+    ) {
+        if instances.buffer_size() != 0
+            && mesh.vertex_buffer_size() != 0
+            && mesh.index_buffer_size() != 0
+        {
+            self.use_shader(&self.default_assets.sprite_array_shader);
+            self.use_instances(instances);
+            self.use_mesh(mesh);
+            self.use_camera(camera);
+            self.use_sprite_array(sprite_array, 1);
+            self.render();
+        }
+    }
+
+    /// Draws all chunks of the [TileMap] that intersect `view`, e.g. the AABB of the camera
+    pub fn draw_tilemap(&mut self, tilemap: &TileMap, camera: &CameraBuffer2D, view: &AABB) {
+        let assets = self.assets;
+        let default_assets = self.default_assets;
+        let sprite_array = assets.sprite_array(tilemap.sprite_array());
+        for instances in tilemap.visible_chunks(view) {
+            self.draw_sprite_array(
+                instances,
+                &default_assets.sprite_mesh,
+                camera,
+                &sprite_array,
+            );
+        }
+    }
+
+    pub fn draw_sprite_array_crop(
+        &mut self,
+        instances: &InstanceBuffer<SpriteArrayCropInstance2D>,
+        mesh: &SpriteMesh2D,
+        camera: &CameraBuffer2D,
+        sprite_array: &SpriteArray,
+    ) {
+        if instances.buffer_size() != 0
+            && mesh.vertex_buffer_size() != 0
+            && mesh.index_buffer_size() != 0
+        {
+            self.use_shader(&self.default_assets.sprite_array_crop_shader);
+            self.use_instances(instances);
+            self.use_mesh(mesh);
+            self.use_camera(camera);
+            self.use_sprite_array(sprite_array, 1);
             self.render();
         }
     }
@@ -299,10 +476,34 @@ impl<'a> Renderer<'a> {
         &mut self,
         mesh: &SpriteArrayMesh2D,
         camera: &CameraBuffer2D,
-        sprite: &Sprite,
+        sprite_array: &SpriteArray,
     ) {
         if mesh.vertex_buffer_size() != 0 && mesh.index_buffer_size() != 0 {
-            self.use_shader(&self.default_assets.mesh_sprite_shader);
+            self.use_shader(&self.default_assets.mesh_sprite_array_shader);
+            self.use_mesh(mesh);
+            self.use_camera(camera);
+            self.use_sprite_array(sprite_array, 1);
+            self.render();
+        }
+    }
+
+    pub fn draw_particles(
+        &mut self,
+        instances: &InstanceBuffer<ColorInstance2D>,
+        mesh: &SpriteMesh2D,
+        camera: &CameraBuffer2D,
+        sprite: &Sprite,
+        blend: ParticleBlend,
+    ) {
+        if instances.buffer_size() != 0
+            && mesh.vertex_buffer_size() != 0
+            && mesh.index_buffer_size() != 0
+        {
+            self.use_shader(match blend {
+                ParticleBlend::Alpha => &self.default_assets.particle_shader,
+                ParticleBlend::Additive => &self.default_assets.particle_additive_shader,
+            });
+            self.use_instances(instances);
             self.use_mesh(mesh);
             self.use_camera(camera);
             self.use_sprite(sprite, 1);
@@ -330,12 +531,29 @@ impl<'a> Renderer<'a> {
         }
     }
 
+    pub fn draw_nine_patch(
+        &mut self,
+        instances: &InstanceBuffer<NinePatchInstance2D>,
+        camera: &CameraBuffer2D,
+        nine_patch: &NinePatchSprite,
+    ) {
+        if instances.buffer_size() != 0 {
+            self.use_shader(&self.default_assets.nine_patch_shader);
+            self.use_instances(instances);
+            self.use_mesh(&self.default_assets.nine_patch_mesh);
+            self.use_camera(camera);
+            self.use_sprite(nine_patch.sprite(), 1);
+            self.render();
+        }
+    }
+
     #[cfg(feature = "text")]
-    pub fn draw_text_mesh(&mut self, text: &TextMesh, camera: &CameraBuffer2D, font: &Font) {
-        if text.mesh().vertex_buffer_size() != 0 {
-            self.use_shader(&self.default_assets.mesh_text_shader);
+    pub fn draw_text(&mut self, text: &Text, camera: &CameraBuffer2D, font: &Font) {
+        if text.instances().buffer_size() != 0 {
+            self.use_shader(&self.default_assets.text_shader);
+            self.use_instances(text.instances());
+            self.use_mesh(&self.default_assets.sprite_mesh);
             self.use_camera(camera);
-            self.use_mesh(text.mesh());
             self.use_sprite_array(font.sprite_array(), 1);
             self.render();
         }
@@ -365,4 +583,110 @@ impl<'a> Renderer<'a> {
             }
         }
     }
+
+    pub fn draw_billboards<C: Camera>(
+        &mut self,
+        instances: &InstanceBuffer<BillboardInstance3D>,
+        camera: &CameraBuffer<C>,
+        sprite: &Sprite,
+    ) {
+        if instances.buffer_size() != 0 {
+            self.use_shader(&self.default_assets.billboard_shader);
+            self.use_instances(instances);
+            self.use_mesh(&self.default_assets.sprite_mesh);
+            self.use_camera(camera);
+            self.use_sprite(sprite, 1);
+            self.render();
+        }
+    }
+
+    /// Draws the chunks of the terrain that were visible in the last [Terrain::update]
+    pub fn draw_terrain<C: Camera>(
+        &mut self,
+        instances: &InstanceBuffer<Instance3D>,
+        terrain: &Terrain,
+        sprite: &Sprite,
+        camera: &CameraBuffer<C>,
+    ) {
+        if instances.buffer_size() != 0 {
+            self.use_shader(&self.default_assets.model_shader);
+            self.use_instances(instances);
+            self.use_camera(camera);
+            self.use_sprite(sprite, 1);
+            for chunk in terrain.chunks() {
+                if let Some(mesh) = chunk.mesh() {
+                    self.use_mesh(mesh);
+                    self.render();
+                }
+            }
+        }
+    }
+
+    /// Draws the cubemap behind everything that has been or will be drawn in this pass, using
+    /// `default_assets.sky_camera` as orientation
+    pub fn draw_skybox(&mut self, cubemap: &Cubemap) {
+        self.use_shader(&self.default_assets.skybox_shader);
+        self.use_mesh(&self.default_assets.sprite_mesh);
+        self.use_uniform_data(&self.default_assets.sky_camera, 0);
+        self.use_uniform(cubemap, 1);
+        self.render();
+    }
+
+    /// Draws the model with Blinn-Phong shading, usually with
+    /// `ctx.default_assets.lights3d` as `lights`
+    pub fn draw_model_lit<C: Camera>(
+        &mut self,
+        instances: &InstanceBuffer<Instance3D>,
+        model: &Model,
+        camera: &CameraBuffer<C>,
+        lights: &UniformData<Lights3D>,
+    ) {
+        if instances.buffer_size() != 0 {
+            self.use_shader(&self.default_assets.model_lit_shader);
+            self.use_instances(instances);
+            self.use_camera(camera);
+            self.use_uniform_data(lights, 2);
+            for mesh in &model.meshes {
+                if mesh.1.vertex_buffer_size() != 0 {
+                    let sprite = if let Some(index) = mesh.0 {
+                        &model.sprites[index]
+                    } else {
+                        &self.default_assets.missing_sprite
+                    };
+                    self.use_sprite(sprite, 1);
+                    self.use_mesh(&mesh.1);
+                    self.render();
+                }
+            }
+        }
+    }
+
+    /// Draws the model posed by `joints`, see [SkinComponent::joints](crate::animation::SkinComponent::joints)
+    #[cfg(feature = "gltf")]
+    pub fn draw_skinned_model<C: Camera>(
+        &mut self,
+        instances: &InstanceBuffer<Instance3D>,
+        model: &SkinnedModel,
+        joints: &UniformData<crate::math::Matrix4<f32>>,
+        camera: &CameraBuffer<C>,
+    ) {
+        if instances.buffer_size() != 0 {
+            self.use_shader(&self.default_assets.skinned_model_shader);
+            self.use_instances(instances);
+            self.use_camera(camera);
+            self.use_uniform_data(joints, 2);
+            for mesh in &model.meshes {
+                if mesh.1.vertex_buffer_size() != 0 {
+                    let sprite = if let Some(index) = mesh.0 {
+                        &model.sprites[index]
+                    } else {
+                        &self.default_assets.missing_sprite
+                    };
+                    self.use_sprite(sprite, 1);
+                    self.use_mesh(&mesh.1);
+                    self.render();
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,80 @@
+use wgpu::{include_wgsl, BlendState};
+
+use crate::{
+    graphics::{
+        Color, Gpu, RenderEncoder, RenderTarget, Shader, ShaderConfig, ShaderModuleSource,
+        SpriteRenderTarget, SpriteVertex2D, UniformData, UniformField, VertexBuffers,
+    },
+    math::{Vector2, Vector4},
+    scene::Transition,
+};
+
+/// Offscreen targets of both scenes during a transition and the shader that blends them
+pub(crate) struct TransitionRenderer {
+    shader: Shader,
+    params: UniformData<Vector4<f32>>,
+    pub from: SpriteRenderTarget,
+    pub to: SpriteRenderTarget,
+}
+
+impl TransitionRenderer {
+    pub fn new(gpu: &Gpu, size: Vector2<u32>) -> Self {
+        Self {
+            shader: gpu.create_shader(ShaderConfig {
+                name: Some("scene_transition"),
+                source: ShaderModuleSource::Single(
+                    &gpu.create_shader_module(include_wgsl!(
+                        "../../static/shader/2d/transition.wgsl"
+                    )),
+                ),
+                uniforms: &[
+                    UniformField::Camera,
+                    UniformField::Sprite,
+                    UniformField::Sprite,
+                    UniformField::SingleUniform,
+                ],
+                vertex_buffers: VertexBuffers::vertex::<SpriteVertex2D>(),
+                blend: BlendState::REPLACE,
+                ..Default::default()
+            }),
+            params: UniformData::new(
+                gpu,
+                gpu.default_layouts().single_uniform_layout.clone(),
+                &[Vector4::zeros(), Vector4::zeros()],
+            ),
+            from: gpu.create_render_target(size),
+            to: gpu.create_render_target(size),
+        }
+    }
+
+    pub fn prepare(
+        &mut self,
+        gpu: &Gpu,
+        size: Vector2<u32>,
+        transition: Transition,
+        progress: f32,
+    ) {
+        self.from.resize(gpu, size);
+        self.to.resize(gpu, size);
+        let Color { r, g, b, a } = transition.color();
+        self.params.write(
+            gpu,
+            &[
+                Vector4::new(progress, transition.mode(), 0.0, 0.0),
+                Vector4::new(r, g, b, a),
+            ],
+        );
+    }
+
+    /// Replaces the content of `target` with the blend of both scenes
+    pub fn render(&self, encoder: &mut RenderEncoder, target: &dyn RenderTarget) {
+        let mut renderer = encoder.renderer(target, Some(Color::TRANSPARENT), None);
+        renderer.use_shader(&self.shader);
+        renderer.use_mesh(&renderer.default_assets.sprite_mesh);
+        renderer.use_camera(&renderer.default_assets.unit_camera.0);
+        renderer.use_sprite(self.from.sprite(), 1);
+        renderer.use_sprite(self.to.sprite(), 2);
+        renderer.use_uniform_data(&self.params, 3);
+        renderer.render();
+    }
+}
@@ -1,20 +1,108 @@
+#[cfg(feature = "log")]
+use crate::log::warn;
 use crate::{
-    graphics::{Color, Gpu},
+    graphics::{Color, Gpu, SafeArea, UiScaling},
     math::Vector2,
 };
 use instant::Duration;
+use winit::window::{Fullscreen, Window};
+
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum FullscreenMode {
+    #[default]
+    Windowed,
+    Borderless,
+    /// Changes the video mode of the monitor. Falls back to [FullscreenMode::Borderless] if the
+    /// platform or the monitor does not support the mode.
+    Exclusive {
+        width: u32,
+        height: u32,
+        /// Refresh rate in millihertz, the highest available one if [None]
+        refresh: Option<u32>,
+    },
+}
+
+/// When the app renders a new frame
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum RedrawMode {
+    /// Renders as fast as the fps limit and vsync allow
+    #[default]
+    Continuous,
+    /// Only renders after input or window events, when a system calls
+    /// [Context::request_redraw](crate::context::Context::request_redraw) or when an egui
+    /// animation needs a repaint. Lets GUI-only scenes idle without using the CPU.
+    OnEvent,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VideoModeInfo {
+    pub size: Vector2<u32>,
+    pub bit_depth: u16,
+    /// Refresh rate in millihertz
+    pub refresh: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitorInfo {
+    pub name: Option<String>,
+    pub size: Vector2<u32>,
+    pub position: Vector2<i32>,
+    pub scale_factor: f64,
+    /// Refresh rate in millihertz
+    pub refresh: Option<u32>,
+    pub video_modes: Vec<VideoModeInfo>,
+}
+
+impl MonitorInfo {
+    pub(crate) fn query(window: &Window) -> Vec<Self> {
+        window
+            .available_monitors()
+            .map(|monitor| Self {
+                name: monitor.name(),
+                size: Vector2::new(monitor.size().width, monitor.size().height),
+                position: Vector2::new(monitor.position().x, monitor.position().y),
+                scale_factor: monitor.scale_factor(),
+                refresh: monitor.refresh_rate_millihertz(),
+                video_modes: monitor
+                    .video_modes()
+                    .map(|mode| VideoModeInfo {
+                        size: Vector2::new(mode.size().width, mode.size().height),
+                        bit_depth: mode.bit_depth(),
+                        refresh: mode.refresh_rate_millihertz(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Clone, Debug)]
 pub struct ScreenConfig {
     pub clear_color: Option<Color>,
-    pub max_fps: Option<u32>,
+    #[cfg_attr(feature = "serde", serde(alias = "max_fps"))]
+    fps_limit: Option<u32>,
+    redraw_mode: RedrawMode,
     #[cfg(feature = "framebuffer")]
     render_scale: f32,
     vsync: bool,
+    fullscreen: FullscreenMode,
+    monitor: Option<usize>,
+    ui_scaling: UiScaling,
+    safe_area: SafeArea,
     #[cfg_attr(feature = "serde", serde(skip))]
     #[cfg_attr(feature = "serde", serde(default = "default_true"))]
     pub(crate) changed: bool,
+    /// A loaded config applies its fullscreen mode
+    #[cfg_attr(feature = "serde", serde(skip))]
+    #[cfg_attr(feature = "serde", serde(default = "default_true"))]
+    pub(crate) fullscreen_changed: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) monitors: Vec<MonitorInfo>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) redraw_requested: bool,
 }
 
 #[cfg(feature = "serde")]
@@ -26,9 +114,17 @@ impl Default for ScreenConfig {
     fn default() -> Self {
         Self {
             clear_color: Some(Color::BLACK),
-            max_fps: None,
+            fps_limit: None,
+            redraw_mode: RedrawMode::Continuous,
             vsync: false,
+            fullscreen: FullscreenMode::Windowed,
+            monitor: None,
+            ui_scaling: UiScaling::default(),
+            safe_area: SafeArea::default(),
             changed: true,
+            fullscreen_changed: false,
+            monitors: Vec::new(),
+            redraw_requested: false,
             #[cfg(feature = "framebuffer")]
             render_scale: 1.0,
         }
@@ -44,8 +140,17 @@ impl ScreenConfig {
         self.clear_color
     }
 
+    pub fn fps_limit(&self) -> Option<u32> {
+        self.fps_limit
+    }
+
+    #[deprecated(note = "Use ScreenConfig::fps_limit")]
     pub fn max_fps(&self) -> Option<u32> {
-        self.max_fps
+        self.fps_limit()
+    }
+
+    pub fn redraw_mode(&self) -> RedrawMode {
+        self.redraw_mode
     }
 
     pub fn vsync(&self) -> bool {
@@ -68,19 +173,157 @@ impl ScreenConfig {
         self.render_scale = render_scale;
     }
 
+    pub fn fullscreen(&self) -> FullscreenMode {
+        self.fullscreen
+    }
+
+    /// Changes the fullscreen mode of the window, the resize systems run once the window has
+    /// its new size
+    pub fn set_fullscreen(&mut self, fullscreen: FullscreenMode) {
+        self.fullscreen_changed = true;
+        self.fullscreen = fullscreen;
+    }
+
+    /// Switches between [FullscreenMode::Windowed] and [FullscreenMode::Borderless]
+    pub fn toggle_fullscreen(&mut self) {
+        self.set_fullscreen(match self.fullscreen {
+            FullscreenMode::Windowed => FullscreenMode::Borderless,
+            _ => FullscreenMode::Windowed,
+        });
+    }
+
+    /// Index into [ScreenConfig::monitors], the monitor of the window if [None]
+    pub fn monitor(&self) -> Option<usize> {
+        self.monitor
+    }
+
+    pub fn set_monitor(&mut self, monitor: Option<usize>) {
+        self.fullscreen_changed = true;
+        self.monitor = monitor;
+    }
+
+    /// Monitors and their video modes, updated whenever the window is resized
+    pub fn monitors(&self) -> &[MonitorInfo] {
+        &self.monitors
+    }
+
+    pub(crate) fn apply_fullscreen(&self, window: &Window) {
+        let monitor = self
+            .monitor
+            .and_then(|index| window.available_monitors().nth(index))
+            .or_else(|| window.current_monitor());
+        let fullscreen = match self.fullscreen {
+            FullscreenMode::Windowed => None,
+            FullscreenMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+            FullscreenMode::Exclusive {
+                width,
+                height,
+                refresh,
+            } => {
+                let mode = monitor.as_ref().and_then(|monitor| {
+                    monitor
+                        .video_modes()
+                        .filter(|mode| {
+                            mode.size().width == width
+                                && mode.size().height == height
+                                && refresh.map_or(true, |refresh| {
+                                    mode.refresh_rate_millihertz() == refresh
+                                })
+                        })
+                        .max_by_key(|mode| (mode.refresh_rate_millihertz(), mode.bit_depth()))
+                });
+                match mode {
+                    Some(mode) if cfg!(not(any(target_arch = "wasm32", target_os = "android"))) => {
+                        Some(Fullscreen::Exclusive(mode))
+                    }
+                    _ => {
+                        #[cfg(feature = "log")]
+                        warn!("Exclusive fullscreen {width} x {height} is not supported, using borderless fullscreen!");
+                        Some(Fullscreen::Borderless(monitor))
+                    }
+                }
+            }
+        };
+        window.set_fullscreen(fullscreen);
+    }
+
+    /// Stores the fullscreen mode, monitor and vsync, e.g. to restore them with
+    /// [ScreenConfig::load_display] on the next start
+    #[cfg(feature = "serde")]
+    pub fn save_display(
+        &self,
+        storage: &dyn crate::io::StorageLoader,
+        path: &str,
+    ) -> anyhow::Result<()> {
+        let data = bincode::serialize(&(self.fullscreen, self.monitor, self.vsync))?;
+        storage.store(path, &data)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn load_display(
+        &mut self,
+        storage: &dyn crate::io::StorageLoader,
+        path: &str,
+    ) -> anyhow::Result<()> {
+        let (fullscreen, monitor, vsync) = bincode::deserialize(&storage.load_bytes(path)?)?;
+        self.monitor = monitor;
+        self.set_fullscreen(fullscreen);
+        self.set_vsync(vsync);
+        Ok(())
+    }
+
+    pub fn ui_scaling(&self) -> UiScaling {
+        self.ui_scaling
+    }
+
+    pub fn set_ui_scaling(&mut self, ui_scaling: UiScaling) {
+        self.changed = true;
+        self.ui_scaling = ui_scaling;
+    }
+
+    pub fn safe_area(&self) -> SafeArea {
+        self.safe_area
+    }
+
+    /// Insets that the [UiAnchor](crate::graphics::UiAnchor)s of the UI camera avoid. winit does
+    /// not report the notches of phones yet, so they have to be queried from the platform.
+    pub fn set_safe_area(&mut self, safe_area: SafeArea) {
+        self.changed = true;
+        self.safe_area = safe_area;
+    }
+
     pub fn set_clear_color(&mut self, clear_color: Option<Color>) {
         self.clear_color = clear_color;
     }
 
+    /// Caps the frame rate, also when vsync is disabled. Frames are paced by sleeping and
+    /// spinning for the last moment, since sleeping alone oversleeps by up to a millisecond.
+    pub fn set_fps_limit(&mut self, fps_limit: Option<u32>) {
+        self.fps_limit = fps_limit.filter(|fps_limit| *fps_limit > 0);
+    }
+
+    #[deprecated(note = "Use ScreenConfig::set_fps_limit")]
     pub fn set_max_fps(&mut self, max_fps: Option<u32>) {
-        self.max_fps = max_fps;
+        self.set_fps_limit(max_fps);
+    }
+
+    pub fn set_redraw_mode(&mut self, redraw_mode: RedrawMode) {
+        self.redraw_mode = redraw_mode;
+    }
+
+    /// Renders another frame in [RedrawMode::OnEvent]
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+    }
+
+    pub fn min_frame_time(&self) -> Option<Duration> {
+        self.fps_limit
+            .map(|fps_limit| Duration::from_secs_f64(1.0 / fps_limit as f64))
     }
 
+    #[deprecated(note = "Use ScreenConfig::min_frame_time")]
     pub fn max_frame_time(&self) -> Option<Duration> {
-        if let Some(max_fps) = self.max_fps {
-            return Some(Duration::from_secs_f32(1.0 / max_fps as f32));
-        }
-        None
+        self.min_frame_time()
     }
 
     pub fn render_size(&self, gpu: &Gpu) -> Vector2<u32> {
@@ -97,3 +340,25 @@ impl ScreenConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_max_fps_forwards_to_fps_limit() {
+        let mut config = ScreenConfig::new();
+        assert_eq!(config.max_fps(), None);
+        assert_eq!(config.max_frame_time(), None);
+
+        config.set_max_fps(Some(50));
+        assert_eq!(config.fps_limit(), Some(50));
+        assert_eq!(config.max_fps(), Some(50));
+        assert_eq!(config.max_frame_time(), Some(Duration::from_millis(20)));
+        assert_eq!(config.max_frame_time(), config.min_frame_time());
+
+        config.set_max_fps(Some(0));
+        assert_eq!(config.fps_limit(), None);
+    }
+}
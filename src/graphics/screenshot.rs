@@ -0,0 +1,195 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+#[cfg(feature = "log")]
+use crate::log::error;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::time::{Duration, Instant};
+use crate::{
+    graphics::{Gpu, RenderTarget},
+    math::Vector2,
+};
+
+pub type ScreenshotCallback = Box<dyn FnOnce(image::RgbaImage) + Send>;
+
+pub(crate) struct PendingScreenshot {
+    buffer: wgpu::Buffer,
+    size: Vector2<u32>,
+    padded_bytes_per_row: u32,
+    format: wgpu::TextureFormat,
+    callback: ScreenshotCallback,
+    mapped: Option<Arc<AtomicBool>>,
+}
+
+impl PendingScreenshot {
+    /// Records the copy of the target into a staging buffer. The size and format are stored,
+    /// so the readback is not affected by a resize in the meantime.
+    pub(crate) fn new(
+        gpu: &Gpu,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &dyn RenderTarget,
+        callback: ScreenshotCallback,
+    ) -> Self {
+        let texture = target.texture();
+        assert!(
+            texture.usage().contains(wgpu::TextureUsages::COPY_SRC),
+            "Cannot take a screenshot of a target without COPY_SRC usage!"
+        );
+        let size = target.size();
+        let format = texture.format();
+        let bytes_per_pixel = format
+            .block_copy_size(None)
+            .expect("Cannot take a screenshot of a depth or compressed target!");
+        let padded_bytes_per_row = (size.x * bytes_per_pixel)
+            .div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = gpu.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("screenshot_buffer"),
+            size: (padded_bytes_per_row * size.y) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(size.y),
+                },
+            },
+            wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+        );
+        Self {
+            buffer,
+            size,
+            padded_bytes_per_row,
+            format,
+            callback,
+            mapped: None,
+        }
+    }
+
+    /// Returns the screenshot back if it is not ready yet
+    pub(crate) fn poll(mut self) -> Option<Self> {
+        let Some(mapped) = &self.mapped else {
+            // The copy was submitted since the last poll
+            let mapped = Arc::new(AtomicBool::new(false));
+            let done = mapped.clone();
+            self.buffer
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| match result {
+                    Ok(_) => done.store(true, Ordering::Release),
+                    #[cfg(feature = "log")]
+                    Err(err) => error!("Failed to read back screenshot: {err}"),
+                    #[cfg(not(feature = "log"))]
+                    Err(_) => (),
+                });
+            self.mapped = Some(mapped);
+            return Some(self);
+        };
+        if !mapped.load(Ordering::Acquire) {
+            return Some(self);
+        }
+
+        let bytes_per_pixel = self.format.block_copy_size(None).unwrap() as usize;
+        let row_size = self.size.x as usize * bytes_per_pixel;
+        let mut raw = Vec::with_capacity(row_size * self.size.y as usize);
+        {
+            let data = self.buffer.slice(..).get_mapped_range();
+            for row in data.chunks(self.padded_bytes_per_row as usize) {
+                raw.extend_from_slice(&row[..row_size]);
+            }
+        }
+        self.buffer.unmap();
+        let rgba = to_rgba8(self.format, raw);
+        let image = image::RgbaImage::from_raw(self.size.x, self.size.y, rgba).unwrap();
+        (self.callback)(image);
+        None
+    }
+}
+
+fn to_rgba8(format: wgpu::TextureFormat, mut raw: Vec<u8>) -> Vec<u8> {
+    match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => raw,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+            for chunk in raw.chunks_mut(4) {
+                chunk.swap(0, 2);
+            }
+            raw
+        }
+        wgpu::TextureFormat::Rgb10a2Unorm => raw
+            .chunks(4)
+            .flat_map(|chunk| {
+                let value = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+                let channel = |shift: u32| ((value >> shift) & 0x3ff) as f32 / 1023.0;
+                [
+                    (channel(0) * 255.0).round() as u8,
+                    (channel(10) * 255.0).round() as u8,
+                    (channel(20) * 255.0).round() as u8,
+                    ((value >> 30) as f32 / 3.0 * 255.0).round() as u8,
+                ]
+            })
+            .collect(),
+        wgpu::TextureFormat::Rgba16Float => raw
+            .chunks(2)
+            .map(|chunk| {
+                let value = f16_to_f32(u16::from_le_bytes([chunk[0], chunk[1]]));
+                (value.clamp(0.0, 1.0) * 255.0).round() as u8
+            })
+            .collect(),
+        _ => panic!("Screenshots of {format:?} targets are not supported!"),
+    }
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+/// Saves numbered PNGs of the window, see [Gpu::begin_frame_capture]
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct FrameCapture {
+    directory: std::path::PathBuf,
+    interval: Duration,
+    last: Option<Instant>,
+    frame: u32,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FrameCapture {
+    pub(crate) fn new(directory: std::path::PathBuf, fps: u32) -> Self {
+        Self {
+            directory,
+            interval: Duration::from_secs_f64(1.0 / fps.max(1) as f64),
+            last: None,
+            frame: 0,
+        }
+    }
+
+    /// Returns the path of the next frame if it is time to capture it
+    pub(crate) fn next_frame(&mut self) -> Option<std::path::PathBuf> {
+        let now = Instant::now();
+        if self.last.is_some_and(|last| now < last + self.interval) {
+            return None;
+        }
+        self.last = Some(now);
+        let path = self.directory.join(format!("frame_{:05}.png", self.frame));
+        self.frame += 1;
+        Some(path)
+    }
+}
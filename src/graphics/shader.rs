@@ -1,4 +1,8 @@
-use crate::graphics::{Gpu, Instance, PositionInstance2D, SpriteVertex2D, Vertex};
+use std::sync::{Arc, OnceLock};
+
+use crate::graphics::{
+    CachedPipelines, Gpu, Instance, PipelineKey, PositionInstance2D, SpriteVertex2D, Vertex,
+};
 pub use wgpu::{
     include_spirv, include_wgsl, vertex_attr_array, BlendComponent, BlendFactor, BlendOperation,
     BlendState, ColorWrites, Id as GpuId, ShaderModule, ShaderModuleDescriptor, ShaderSource,
@@ -14,9 +18,47 @@ pub enum ShaderModuleSource<'a> {
         vertex: &'a ShaderModule,
         fragment: &'a ShaderModule,
     },
+    /// WGSL file loaded through the [ResourceLoader](crate::io::ResourceLoader). Shaders loaded
+    /// with [AssetManager::load_shader](crate::graphics::AssetManager::load_shader) are
+    /// recompiled when the file changes if the `hot-reload` feature is enabled.
+    Hot(&'a str),
+    /// Compiled together with the shader, e.g. the output of [include_wgsl]. Used by
+    /// [Gpu::create_shader_async] to compile the module in the background as well.
+    Descriptor(ShaderModuleDescriptor<'a>),
     Dummy,
 }
 
+/// Shader that is compiled in the background by [Gpu::create_shader_async]
+#[derive(Clone, Default)]
+pub struct ShaderHandle {
+    shader: Arc<OnceLock<Shader>>,
+    error: Arc<OnceLock<String>>,
+}
+
+impl ShaderHandle {
+    pub(crate) fn set(&self, shader: Shader) {
+        let _ = self.shader.set(shader);
+    }
+
+    pub(crate) fn fail(&self, error: String) {
+        let _ = self.error.set(error);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.shader.get().is_some()
+    }
+
+    /// [None] while the shader is still compiling or if compiling failed
+    pub fn get(&self) -> Option<&Shader> {
+        self.shader.get()
+    }
+
+    /// Why the shader could not be compiled. A failed shader never becomes ready.
+    pub fn error(&self) -> Option<&str> {
+        self.error.get().map(|error| error.as_str())
+    }
+}
+
 pub enum VertexBuffers<'a> {
     Vertex(&'a [wgpu::VertexFormat]),
     VertexInstance(&'a [wgpu::VertexFormat], &'a [wgpu::VertexFormat]),
@@ -25,14 +67,27 @@ pub enum VertexBuffers<'a> {
 
 impl<'a> VertexBuffers<'a> {
     pub fn vertex<V: Vertex>() -> Self {
+        debug_assert_attributes::<V>(V::ATTRIBUTES);
         Self::Vertex(V::ATTRIBUTES)
     }
 
     pub fn instance<V: Vertex, I: Instance>() -> Self {
+        debug_assert_attributes::<V>(V::ATTRIBUTES);
+        debug_assert_attributes::<I>(I::ATTRIBUTES);
         Self::VertexInstance(V::ATTRIBUTES, I::ATTRIBUTES)
     }
 }
 
+/// Attributes are laid out without padding, so a struct with padding would shift them
+fn debug_assert_attributes<T>(attributes: &[wgpu::VertexFormat]) {
+    debug_assert_eq!(
+        attributes.iter().map(|format| format.size()).sum::<u64>(),
+        std::mem::size_of::<T>() as u64,
+        "The attributes of {} do not cover its size!",
+        std::any::type_name::<T>()
+    );
+}
+
 pub struct ShaderConfig<'a> {
     pub name: Option<&'a str>,
     pub source: ShaderModuleSource<'a>,
@@ -65,13 +120,16 @@ pub enum UniformField<'a> {
     Sprite,
     SingleUniform,
     SpriteArray,
+    Cubemap,
     Camera,
+    /// Bound with [Renderer::use_uniform_slice](crate::graphics::Renderer::use_uniform_slice)
+    DynamicUniform,
     Custom(&'a wgpu::BindGroupLayout),
 }
 
 #[derive(Debug)]
 pub struct Shader {
-    pipeline: wgpu::RenderPipeline,
+    pipelines: CachedPipelines,
     instance_size: wgpu::BufferAddress,
     vertex_size: wgpu::BufferAddress,
 }
@@ -85,20 +143,14 @@ impl Shader {
                 UniformField::SingleUniform => &*default_layouts.single_uniform_layout,
                 UniformField::Sprite => &*default_layouts.sprite_layout,
                 UniformField::SpriteArray => &*default_layouts.sprite_array_layout,
+                UniformField::Cubemap => &*default_layouts.cubemap_layout,
                 UniformField::Camera => &*default_layouts.camera_layout,
+                UniformField::DynamicUniform => &*default_layouts.dynamic_uniform_layout,
                 UniformField::Custom(c) => c,
             };
             layouts.push(layout);
         }
 
-        let render_pipeline_layout =
-            gpu.device
-                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: config.name,
-                    bind_group_layouts: &layouts[..],
-                    push_constant_ranges: &[],
-                });
-
         let va;
         let ia;
         let buffers = match config.vertex_buffers {
@@ -172,62 +224,125 @@ impl Shader {
             VertexBuffers::Custom(custom) => custom,
         };
 
+        // Modules compiled here are new for every shader, so their pipelines are never shared
+        let cacheable = !matches!(
+            config.source,
+            ShaderModuleSource::Hot(_) | ShaderModuleSource::Descriptor(_)
+        );
+        let owned_module;
+        let (vertex_module, fragment_module) = match config.source {
+            ShaderModuleSource::Single(s) => (s, s),
+            ShaderModuleSource::Separate { vertex, fragment } => (vertex, fragment),
+            ShaderModuleSource::Hot(path) => {
+                let source = crate::app::GLOBAL_RESOURCE_LOADER
+                    .get()
+                    .expect("Hot shaders need a resource loader!")
+                    .load_string(path)
+                    .unwrap_or_else(|err| panic!("Cannot load shader {path}: {err}"));
+                owned_module = gpu.create_shader_module(ShaderModuleDescriptor {
+                    label: Some(path),
+                    source: ShaderSource::Wgsl(source.into()),
+                });
+                (&owned_module, &owned_module)
+            }
+            ShaderModuleSource::Descriptor(descriptor) => {
+                owned_module = gpu.create_shader_module(descriptor);
+                (&owned_module, &owned_module)
+            }
+            ShaderModuleSource::Dummy => panic!("Dummy not allowed!"),
+        };
+
+        let key = PipelineKey {
+            layouts: layouts.iter().map(|layout| layout.global_id()).collect(),
+            vertex_module: vertex_module.global_id(),
+            fragment_module: fragment_module.global_id(),
+            vertex_entry: config.vertex_entry,
+            fragment_entry: config.fragment_entry,
+            buffers: buffers
+                .iter()
+                .map(|buffer| {
+                    (
+                        buffer.array_stride,
+                        buffer.step_mode,
+                        buffer.attributes.to_vec(),
+                    )
+                })
+                .collect(),
+            blend: config.blend,
+            write_mask: config.write_mask,
+            depth_stencil: config.depth_stencil.clone(),
+        };
+        if cacheable {
+            if let Some(pipelines) = gpu.pipeline_cache().get(&key) {
+                return Self::from_pipelines(pipelines, &buffers);
+            }
+        }
+
+        let render_pipeline_layout = gpu.pipeline_cache().layout(&gpu.device, &layouts);
         // let cache = unsafe { gpu.device.create_pipeline_cache(&wgpu::PipelineCacheDescriptor { label: None, data: None, fallback: true }) };
 
         // Default Shader Configuration
-        let pipeline = gpu
-            .device
-            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: config.name,
-                layout: Some(&render_pipeline_layout),
-                vertex: wgpu::VertexState {
-                    module: match config.source {
-                        ShaderModuleSource::Single(s) => s,
-                        ShaderModuleSource::Separate { vertex, .. } => vertex,
-                        ShaderModuleSource::Dummy => panic!("Dummy not allowed!"),
+        let create_pipeline = |samples: u32| {
+            gpu.device
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: config.name,
+                    layout: Some(&*render_pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: vertex_module,
+                        entry_point: config.vertex_entry,
+                        buffers: &buffers,
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: fragment_module,
+                        entry_point: config.fragment_entry,
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: gpu.format(),
+                            blend: Some(config.blend),
+                            write_mask: config.write_mask,
+                        })],
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    }),
+                    primitive: wgpu::PrimitiveState {
+                        topology: wgpu::PrimitiveTopology::TriangleList,
+                        strip_index_format: None,
+                        front_face: wgpu::FrontFace::Ccw,
+                        cull_mode: Some(wgpu::Face::Back),
+                        polygon_mode: wgpu::PolygonMode::Fill,
+                        unclipped_depth: false,
+                        conservative: false,
                     },
-                    entry_point: config.vertex_entry,
-                    buffers: &buffers,
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                },
-                fragment: Some(wgpu::FragmentState {
-                    module: match config.source {
-                        ShaderModuleSource::Single(s) => s,
-                        ShaderModuleSource::Separate { fragment, .. } => fragment,
-                        ShaderModuleSource::Dummy => panic!("Dummy not allowed!"),
+                    depth_stencil: config.depth_stencil.clone(),
+                    multisample: wgpu::MultisampleState {
+                        count: samples,
+                        ..gpu.sample_state()
                     },
-                    entry_point: config.fragment_entry,
-                    targets: &[Some(wgpu::ColorTargetState {
-                        format: gpu.format(),
-                        blend: Some(config.blend),
-                        write_mask: config.write_mask,
-                    })],
-                    compilation_options: wgpu::PipelineCompilationOptions::default(),
-                }),
-                primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
-                    polygon_mode: wgpu::PolygonMode::Fill,
-                    unclipped_depth: false,
-                    conservative: false,
-                },
-                depth_stencil: config.depth_stencil,
-                multisample: gpu.sample_state(),
-                multiview: None,
-                cache: None, // cache: Some(&cache)
-            });
+                    multiview: None,
+                    cache: None, // cache: Some(&cache)
+                })
+        };
+        let pipelines: CachedPipelines = gpu
+            .pipeline_samples()
+            .iter()
+            .map(|samples| (*samples, Arc::new(create_pipeline(*samples))))
+            .collect();
+        if cacheable {
+            gpu.pipeline_cache().insert(key, pipelines.clone());
+        }
 
         #[cfg(feature = "log")]
         if let Some(name) = config.name {
             info!("Successfully compiled shader {name}");
         }
 
+        Self::from_pipelines(pipelines, &buffers)
+    }
+
+    fn from_pipelines(pipelines: CachedPipelines, buffers: &[wgpu::VertexBufferLayout]) -> Self {
         Shader {
-            pipeline,
-            instance_size: Self::size_of_step_mode(&buffers, wgpu::VertexStepMode::Instance),
-            vertex_size: Self::size_of_step_mode(&buffers, wgpu::VertexStepMode::Vertex),
+            pipelines,
+            instance_size: Self::size_of_step_mode(buffers, wgpu::VertexStepMode::Instance),
+            vertex_size: Self::size_of_step_mode(buffers, wgpu::VertexStepMode::Vertex),
         }
     }
 
@@ -246,7 +361,7 @@ impl Shader {
     pub fn custom(gpu: &Gpu, descriptor: &wgpu::RenderPipelineDescriptor) -> Self {
         let pipeline = gpu.device.create_render_pipeline(descriptor);
         Self {
-            pipeline,
+            pipelines: vec![(descriptor.multisample.count, Arc::new(pipeline))],
             instance_size: Self::size_of_step_mode(
                 descriptor.vertex.buffers,
                 wgpu::VertexStepMode::Instance,
@@ -259,7 +374,20 @@ impl Shader {
     }
 
     pub fn pipeline(&self) -> &wgpu::RenderPipeline {
-        &self.pipeline
+        &self.pipelines[0].1
+    }
+
+    /// Returns the pipeline compiled for `samples`, see [Gpu::pipeline_samples].
+    pub fn pipeline_for(&self, samples: u32) -> Option<&wgpu::RenderPipeline> {
+        self.pipelines
+            .iter()
+            .find(|(s, _)| *s == samples)
+            .map(|(_, pipeline)| &**pipeline)
+    }
+
+    /// Sample count of [Shader::pipeline]
+    pub fn samples(&self) -> u32 {
+        self.pipelines[0].0
     }
 
     pub fn instance_size(&self) -> wgpu::BufferAddress {
@@ -270,3 +398,18 @@ impl Shader {
         self.vertex_size
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_handle_keeps_error() {
+        let handle = ShaderHandle::default();
+        let clone = handle.clone();
+        assert_eq!(handle.error(), None);
+        clone.fail("invalid wgsl".to_owned());
+        assert!(!handle.is_ready());
+        assert_eq!(handle.error(), Some("invalid wgsl"));
+    }
+}
@@ -0,0 +1,456 @@
+use std::{mem, sync::Arc};
+
+use anyhow::{anyhow, bail, Result};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    graphics::{
+        gltf_loader::GltfData, Gpu, Index, Mesh, MeshBuilder, Sprite, SpriteBuilder, Vertex,
+    },
+    math::{Matrix4, Quaternion, UnitQuaternion, Vector2, Vector3, Vector4},
+};
+
+/// Maximum number of joints of a [Skin]. The joint matrices are uploaded as a uniform array of
+/// this length, see `static/shader/3d/skinned_model.wgsl`. Loading a skin with more joints fails.
+pub const MAX_JOINTS: usize = 128;
+
+pub type SkinnedMesh3D = Mesh<SkinnedVertex3D>;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SkinnedVertex3D {
+    pub pos: Vector3<f32>,
+    pub tex: Vector2<f32>,
+    pub normal: Vector3<f32>,
+    /// Indices into [Skin::joints]
+    pub joints: [u32; 4],
+    pub weights: [f32; 4],
+}
+
+impl Vertex for SkinnedVertex3D {
+    const SIZE: u64 = mem::size_of::<Self>() as u64;
+    const ATTRIBUTES: &'static [wgpu::VertexFormat] = &[
+        wgpu::VertexFormat::Float32x3,
+        wgpu::VertexFormat::Float32x2,
+        wgpu::VertexFormat::Float32x3,
+        wgpu::VertexFormat::Uint32x4,
+        wgpu::VertexFormat::Float32x4,
+    ];
+}
+
+pub struct SkinnedMeshBuilder3D {
+    pub vertices: Vec<SkinnedVertex3D>,
+    pub indices: Vec<Index>,
+}
+
+impl MeshBuilder for SkinnedMeshBuilder3D {
+    type Vertex = SkinnedVertex3D;
+
+    fn indices(&self) -> &[Index] {
+        &self.indices
+    }
+
+    fn vertices(&self) -> &[Self::Vertex] {
+        &self.vertices
+    }
+}
+
+/// Local transform of a node relative to its parent
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NodeTransform {
+    pub translation: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Default for NodeTransform {
+    fn default() -> Self {
+        Self {
+            translation: Vector3::zeros(),
+            rotation: UnitQuaternion::identity(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl NodeTransform {
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::new_translation(&self.translation)
+            * self.rotation.to_homogeneous()
+            * Matrix4::new_nonuniform_scaling(&self.scale)
+    }
+
+    pub fn lerp(&self, other: &Self, factor: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(&other.translation, factor),
+            rotation: self.rotation.slerp(&other.rotation, factor),
+            scale: self.scale.lerp(&other.scale, factor),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelProperty {
+    Translation,
+    Rotation,
+    Scale,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelInterpolation {
+    Linear,
+    Step,
+    /// Every keyframe stores an in tangent, the value and an out tangent
+    CubicSpline,
+}
+
+/// Keyframes of one property of one node. Translations and scales only use the first three
+/// components of the values, rotations are quaternions in `x, y, z, w` order.
+#[derive(Clone, Debug)]
+pub struct SkinChannel {
+    pub node: usize,
+    pub property: ChannelProperty,
+    pub interpolation: ChannelInterpolation,
+    pub times: Vec<f32>,
+    pub values: Vec<Vector4<f32>>,
+}
+
+impl SkinChannel {
+    fn value(&self, key: usize) -> Vector4<f32> {
+        match self.interpolation {
+            ChannelInterpolation::CubicSpline => self.values[key * 3 + 1],
+            _ => self.values[key],
+        }
+    }
+
+    pub fn sample(&self, time: f32) -> Vector4<f32> {
+        let last = self.times.len() - 1;
+        let next = self.times.partition_point(|&t| t <= time);
+        if next == 0 {
+            return self.value(0);
+        } else if next > last {
+            return self.value(last);
+        }
+
+        let key = next - 1;
+        let delta = self.times[next] - self.times[key];
+        let factor = (time - self.times[key]) / delta;
+        match self.interpolation {
+            ChannelInterpolation::Step => self.value(key),
+            ChannelInterpolation::Linear if self.property == ChannelProperty::Rotation => {
+                let from = quaternion(self.value(key));
+                let to = quaternion(self.value(next));
+                from.slerp(&to, factor).coords
+            }
+            ChannelInterpolation::Linear => self.value(key).lerp(&self.value(next), factor),
+            ChannelInterpolation::CubicSpline => {
+                let t2 = factor * factor;
+                let t3 = t2 * factor;
+                let out_tangent = self.values[key * 3 + 2] * delta;
+                let in_tangent = self.values[next * 3] * delta;
+                self.value(key) * (2.0 * t3 - 3.0 * t2 + 1.0)
+                    + out_tangent * (t3 - 2.0 * t2 + factor)
+                    + self.value(next) * (-2.0 * t3 + 3.0 * t2)
+                    + in_tangent * (t3 - t2)
+            }
+        }
+    }
+}
+
+/// Animation of a glTF file
+#[derive(Clone, Debug)]
+pub struct SkinClip {
+    pub duration: f32,
+    pub channels: Vec<SkinChannel>,
+}
+
+impl SkinClip {
+    /// Overwrites the animated properties of `pose`, the others are left untouched
+    pub fn sample(&self, time: f32, pose: &mut [NodeTransform]) {
+        for channel in &self.channels {
+            let value = channel.sample(time);
+            let transform = &mut pose[channel.node];
+            match channel.property {
+                ChannelProperty::Translation => transform.translation = value.xyz(),
+                ChannelProperty::Rotation => transform.rotation = quaternion(value),
+                ChannelProperty::Scale => transform.scale = value.xyz(),
+            }
+        }
+    }
+}
+
+/// Node hierarchy, joints and animations of a skinned glTF model. Nodes are indexed like in the
+/// glTF file.
+#[derive(Clone, Debug)]
+pub struct Skin {
+    pub parents: Vec<Option<usize>>,
+    pub rest_pose: Vec<NodeTransform>,
+    /// Node index of every joint
+    pub joints: Vec<usize>,
+    pub inverse_bind_matrices: Vec<Matrix4<f32>>,
+    pub clips: FxHashMap<String, SkinClip>,
+    /// Nodes ordered so that parents come before their children
+    order: Vec<usize>,
+}
+
+impl Skin {
+    pub fn clip(&self, name: &str) -> Option<&SkinClip> {
+        self.clips.get(name)
+    }
+
+    /// Writes one matrix per joint that transforms from the bind pose to `pose`
+    pub fn joint_matrices(&self, pose: &[NodeTransform], matrices: &mut [Matrix4<f32>]) {
+        let mut world = vec![Matrix4::identity(); pose.len()];
+        for &node in &self.order {
+            let local = pose[node].matrix();
+            world[node] = match self.parents[node] {
+                Some(parent) => world[parent] * local,
+                None => local,
+            };
+        }
+        for ((matrix, &joint), inverse_bind) in matrices
+            .iter_mut()
+            .zip(&self.joints)
+            .zip(&self.inverse_bind_matrices)
+        {
+            *matrix = world[joint] * inverse_bind;
+        }
+    }
+}
+
+/// Meshes, materials and the first skin of a glTF file. Only meshes that are bound to that skin
+/// are loaded, their node transforms are ignored as the joints already position them.
+pub struct SkinnedModelBuilder {
+    pub meshes: Vec<(Option<usize>, SkinnedMeshBuilder3D)>,
+    pub sprites: Vec<Vec<u8>>,
+    pub skin: Skin,
+}
+
+impl SkinnedModelBuilder {
+    /// Loads a `.glb` or a `.gltf` with embedded buffers, see [ModelBuilder::gltf](crate::graphics::ModelBuilder::gltf)
+    pub fn gltf(bytes: &[u8]) -> Result<Self> {
+        load_skinned_gltf(GltfData::embedded(bytes)?)
+    }
+
+    /// Loads a `.glb` or `.gltf` from the resources, external buffers and images are resolved
+    /// relative to `path`
+    pub fn gltf_resource(path: &str) -> Result<Self> {
+        load_skinned_gltf(GltfData::resource(path)?)
+    }
+}
+
+fn load_skinned_gltf(data: GltfData) -> Result<SkinnedModelBuilder> {
+    let document = &data.document;
+    let gltf_skin = document
+        .skins()
+        .next()
+        .ok_or_else(|| anyhow!("glTF file contains no skin"))?;
+    let joints = gltf_skin
+        .joints()
+        .map(|joint| joint.index())
+        .collect::<Vec<_>>();
+    if joints.len() > MAX_JOINTS {
+        bail!(
+            "glTF skin has {} joints but at most {MAX_JOINTS} are supported",
+            joints.len()
+        );
+    }
+    let inverse_bind_matrices = match gltf_skin
+        .reader(|buffer| Some(&data.buffers[buffer.index()]))
+        .read_inverse_bind_matrices()
+    {
+        Some(matrices) => matrices.map(Matrix4::from).collect(),
+        None => vec![Matrix4::identity(); joints.len()],
+    };
+
+    let nodes = document.nodes().collect::<Vec<_>>();
+    let mut parents = vec![None; nodes.len()];
+    let mut rest_pose = Vec::with_capacity(parents.len());
+    for node in &nodes {
+        for child in node.children() {
+            parents[child.index()] = Some(node.index());
+        }
+        let (translation, rotation, scale) = node.transform().decomposed();
+        rest_pose.push(NodeTransform {
+            translation: translation.into(),
+            rotation: quaternion(rotation.into()),
+            scale: scale.into(),
+        });
+    }
+    let mut order = Vec::with_capacity(parents.len());
+    let mut pending = (0..parents.len())
+        .filter(|node| parents[*node].is_none())
+        .collect::<Vec<_>>();
+    while let Some(node) = pending.pop() {
+        order.push(node);
+        pending.extend(nodes[node].children().map(|child| child.index()));
+    }
+
+    let mut clips = FxHashMap::default();
+    for (index, animation) in document.animations().enumerate() {
+        let mut duration = 0.0f32;
+        let mut channels = Vec::new();
+        for channel in animation.channels() {
+            let reader = channel.reader(|buffer| Some(&data.buffers[buffer.index()]));
+            let times = reader
+                .read_inputs()
+                .ok_or_else(|| anyhow!("glTF animation channel has no keyframes"))?
+                .collect::<Vec<_>>();
+            let (property, values): (_, Vec<Vector4<f32>>) = match reader
+                .read_outputs()
+                .ok_or_else(|| anyhow!("glTF animation channel has no values"))?
+            {
+                gltf::animation::util::ReadOutputs::Translations(values) => (
+                    ChannelProperty::Translation,
+                    values
+                        .map(|v| Vector4::new(v[0], v[1], v[2], 0.0))
+                        .collect(),
+                ),
+                gltf::animation::util::ReadOutputs::Rotations(values) => (
+                    ChannelProperty::Rotation,
+                    values.into_f32().map(Vector4::from).collect(),
+                ),
+                gltf::animation::util::ReadOutputs::Scales(values) => (
+                    ChannelProperty::Scale,
+                    values
+                        .map(|v| Vector4::new(v[0], v[1], v[2], 0.0))
+                        .collect(),
+                ),
+                // Morph targets are not supported
+                gltf::animation::util::ReadOutputs::MorphTargetWeights(_) => continue,
+            };
+            let interpolation = match channel.sampler().interpolation() {
+                gltf::animation::Interpolation::Linear => ChannelInterpolation::Linear,
+                gltf::animation::Interpolation::Step => ChannelInterpolation::Step,
+                gltf::animation::Interpolation::CubicSpline => ChannelInterpolation::CubicSpline,
+            };
+            let keys = match interpolation {
+                ChannelInterpolation::CubicSpline => values.len() / 3,
+                _ => values.len(),
+            };
+            if times.is_empty() || keys != times.len() {
+                bail!("glTF animation channel has mismatching keyframes and values");
+            }
+            duration = duration.max(*times.last().unwrap());
+            channels.push(SkinChannel {
+                node: channel.target().node().index(),
+                property,
+                interpolation,
+                times,
+                values,
+            });
+        }
+        let name = animation
+            .name()
+            .map(str::to_owned)
+            .unwrap_or_else(|| format!("animation_{index}"));
+        clips.insert(name, SkinClip { duration, channels });
+    }
+
+    let mut meshes = Vec::new();
+    for node in data.scene()?.nodes().flat_map(descendants) {
+        let (Some(mesh), Some(skin)) = (node.mesh(), node.skin()) else {
+            continue;
+        };
+        if skin.index() != gltf_skin.index() {
+            continue;
+        }
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                bail!("Only triangle primitives are supported");
+            }
+            let reader = primitive.reader(|buffer| Some(&data.buffers[buffer.index()]));
+            let mut vertices = reader
+                .read_positions()
+                .ok_or_else(|| anyhow!("glTF primitive has no positions"))?
+                .map(|pos| SkinnedVertex3D {
+                    pos: pos.into(),
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>();
+            if let Some(normals) = reader.read_normals() {
+                for (vertex, normal) in vertices.iter_mut().zip(normals) {
+                    vertex.normal = normal.into();
+                }
+            }
+            // Flipped to the bottom left origin of OBJ texture coordinates
+            if let Some(uvs) = reader.read_tex_coords(0) {
+                for (vertex, uv) in vertices.iter_mut().zip(uvs.into_f32()) {
+                    vertex.tex = Vector2::new(uv[0], 1.0 - uv[1]);
+                }
+            }
+            let joint_indices = reader
+                .read_joints(0)
+                .ok_or_else(|| anyhow!("Skinned glTF primitive has no joints"))?;
+            for (vertex, joints) in vertices.iter_mut().zip(joint_indices.into_u16()) {
+                vertex.joints = joints.map(u32::from);
+            }
+            let weights = reader
+                .read_weights(0)
+                .ok_or_else(|| anyhow!("Skinned glTF primitive has no weights"))?;
+            for (vertex, weights) in vertices.iter_mut().zip(weights.into_f32()) {
+                vertex.weights = weights;
+            }
+            let indices = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..vertices.len() as u32).collect(),
+            };
+            meshes.push((
+                primitive.material().index(),
+                SkinnedMeshBuilder3D { vertices, indices },
+            ));
+        }
+    }
+    if meshes.is_empty() {
+        bail!("glTF file contains no skinned mesh");
+    }
+
+    Ok(SkinnedModelBuilder {
+        meshes,
+        sprites: data.sprites,
+        skin: Skin {
+            parents,
+            rest_pose,
+            joints,
+            inverse_bind_matrices,
+            clips,
+            order,
+        },
+    })
+}
+
+fn descendants(node: gltf::Node) -> Vec<gltf::Node> {
+    let mut nodes = vec![node.clone()];
+    nodes.extend(node.children().flat_map(descendants));
+    nodes
+}
+
+fn quaternion(xyzw: Vector4<f32>) -> UnitQuaternion<f32> {
+    UnitQuaternion::new_normalize(Quaternion::from(xyzw))
+}
+
+pub struct SkinnedModel {
+    pub meshes: Vec<(Option<usize>, SkinnedMesh3D)>,
+    pub sprites: Vec<Sprite>,
+    pub skin: Arc<Skin>,
+}
+
+impl SkinnedModel {
+    pub fn new(gpu: &Gpu, builder: SkinnedModelBuilder) -> Self {
+        let sprites = builder
+            .sprites
+            .into_iter()
+            .map(|m| gpu.create_sprite(SpriteBuilder::bytes(&m)))
+            .collect::<Vec<_>>();
+        let meshes = builder
+            .meshes
+            .into_iter()
+            .map(|(material, mesh)| (material, gpu.create_mesh(&mesh)))
+            .collect::<Vec<_>>();
+        Self {
+            meshes,
+            sprites,
+            skin: Arc::new(builder.skin),
+        }
+    }
+}
@@ -1,7 +1,5 @@
-use wgpu::util::DeviceExt;
-
 use crate::{
-    graphics::{Color, Gpu, Uniform},
+    graphics::{Color, Gpu, MipmapGenerator, Uniform},
     math::Vector2,
 };
 use std::ops::Deref;
@@ -12,13 +10,20 @@ pub struct SpriteBuilder<'a, D: Deref<Target = [u8]>> {
     pub sampler: wgpu::SamplerDescriptor<'a>,
     pub data: D,
     pub format: wgpu::TextureFormat,
+    /// Generates the full mip chain on the GPU when the sprite gets created
+    pub mipmaps: bool,
+    /// Resource the data was loaded from, used for hot reloading
+    pub resource_path: Option<String>,
 }
 
 impl<'a> SpriteBuilder<'a, image::RgbaImage> {
     pub fn resource(path: &str) -> SpriteBuilder<'a, image::ImageBuffer<image::Rgba<u8>, Vec<u8>>> {
         let resources = crate::app::global_resources();
         let bytes = resources.load_bytes(path).unwrap();
-        Self::bytes(&bytes)
+        Self {
+            resource_path: Some(path.to_owned()),
+            ..Self::bytes(&bytes)
+        }
     }
 
     pub fn bytes(bytes: &[u8]) -> Self {
@@ -30,6 +35,8 @@ impl<'a> SpriteBuilder<'a, image::RgbaImage> {
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
             sampler: Sprite::DEFAULT_SAMPLER,
             data: image.to_rgba8(),
+            mipmaps: false,
+            resource_path: None,
         }
     }
 
@@ -41,6 +48,8 @@ impl<'a> SpriteBuilder<'a, image::RgbaImage> {
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
             sampler: Sprite::DEFAULT_SAMPLER,
             data: image.to_rgba8(),
+            mipmaps: false,
+            resource_path: None,
         }
     }
 }
@@ -53,6 +62,8 @@ impl<'a> SpriteBuilder<'a, &'static [u8]> {
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
             sampler: Sprite::DEFAULT_SAMPLER,
             data: &[],
+            mipmaps: false,
+            resource_path: None,
         }
     }
 }
@@ -65,6 +76,8 @@ impl<'a> SpriteBuilder<'a, Vec<u8>> {
             sampler: Sprite::DEFAULT_SAMPLER,
             data: color.to_rgba().into(),
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            mipmaps: false,
+            resource_path: None,
         }
     }
 }
@@ -77,6 +90,8 @@ impl<'a> SpriteBuilder<'a, &'a [u8]> {
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
             sampler: Sprite::DEFAULT_SAMPLER,
             data,
+            mipmaps: false,
+            resource_path: None,
         }
     }
 }
@@ -96,6 +111,66 @@ impl<'a, D: Deref<Target = [u8]>> SpriteBuilder<'a, D> {
         self.format = format;
         self
     }
+
+    pub fn mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
+
+    pub fn mag_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.sampler.mag_filter = filter;
+        self
+    }
+
+    pub fn min_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.sampler.min_filter = filter;
+        self
+    }
+
+    /// Filter between mip levels, only has an effect with [SpriteBuilder::mipmaps]
+    pub fn mipmap_filter(mut self, filter: wgpu::FilterMode) -> Self {
+        self.sampler.mipmap_filter = filter;
+        self
+    }
+
+    /// Sets the min, mag and mipmap filter at once
+    pub fn filter(self, filter: wgpu::FilterMode) -> Self {
+        self.mag_filter(filter)
+            .min_filter(filter)
+            .mipmap_filter(filter)
+    }
+
+    /// Anisotropic filtering requires linear filtering, so all filters are set to linear when
+    /// `level` is above 1. Valid levels are 1, 2, 4, 8 and 16.
+    pub fn anisotropy(mut self, level: u16) -> Self {
+        self.sampler.anisotropy_clamp = level.clamp(1, 16);
+        if level > 1 {
+            self = self.filter(wgpu::FilterMode::Linear);
+        }
+        self
+    }
+
+    /// Sets the address mode for all directions, e.g. to repeat or mirror the sprite
+    pub fn address_mode(mut self, mode: wgpu::AddressMode) -> Self {
+        self.sampler.address_mode_u = mode;
+        self.sampler.address_mode_v = mode;
+        self.sampler.address_mode_w = mode;
+        self
+    }
+
+    pub fn address_mode_uv(mut self, u: wgpu::AddressMode, v: wgpu::AddressMode) -> Self {
+        self.sampler.address_mode_u = u;
+        self.sampler.address_mode_v = v;
+        self
+    }
+
+    pub fn mip_level_count(&self) -> u32 {
+        if self.mipmaps {
+            MipmapGenerator::mip_level_count(self.size)
+        } else {
+            1
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -126,7 +201,14 @@ impl Sprite {
     };
 
     pub fn new<D: Deref<Target = [u8]>>(gpu: &Gpu, desc: SpriteBuilder<D>) -> Self {
-        let texture = Self::create_texture(gpu, desc.label, desc.format, desc.size, &desc.data);
+        let texture = Self::create_texture(
+            gpu,
+            desc.label,
+            desc.format,
+            desc.size,
+            desc.mip_level_count(),
+            &desc.data,
+        );
         let (view, bind_group, sampler) = Self::create_bind_group(gpu, &texture, &desc.sampler);
         Self {
             _sampler: sampler,
@@ -143,52 +225,56 @@ impl Sprite {
         label: Option<&str>,
         format: wgpu::TextureFormat,
         size: Vector2<u32>,
+        mip_level_count: u32,
         data: &[u8],
     ) -> wgpu::Texture {
         assert!(size.x != 0 && size.y != 0);
 
-        if data.is_empty() {
-            gpu.device.create_texture(&wgpu::TextureDescriptor {
-                label,
-                size: wgpu::Extent3d {
-                    width: size.x,
-                    height: size.y,
-                    depth_or_array_layers: 1,
-                },
-                format,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING
-                    | wgpu::TextureUsages::COPY_DST
-                    | wgpu::TextureUsages::COPY_SRC
-                    | wgpu::TextureUsages::RENDER_ATTACHMENT,
-                view_formats: &[],
-            })
-        } else {
-            gpu.device.create_texture_with_data(
-                &gpu.queue,
-                &wgpu::TextureDescriptor {
-                    label: Some("sprite_texture"),
-                    size: wgpu::Extent3d {
-                        width: size.x,
-                        height: size.y,
-                        depth_or_array_layers: 1,
-                    },
-                    format,
-                    mip_level_count: 1,
-                    sample_count: 1,
-                    dimension: wgpu::TextureDimension::D2,
-                    usage: wgpu::TextureUsages::TEXTURE_BINDING
-                        | wgpu::TextureUsages::COPY_DST
-                        | wgpu::TextureUsages::COPY_SRC
-                        | wgpu::TextureUsages::RENDER_ATTACHMENT,
-                    view_formats: &[],
-                },
-                Default::default(),
-                data,
-            )
+        let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: label.or(Some("sprite_texture")),
+            size: wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            format,
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        if !data.is_empty() {
+            Self::write_texture(gpu, &texture, format, size, data);
+            gpu.generate_mipmaps(&texture);
         }
+        texture
+    }
+
+    fn write_texture(
+        gpu: &Gpu,
+        texture: &wgpu::Texture,
+        format: wgpu::TextureFormat,
+        size: Vector2<u32>,
+        data: &[u8],
+    ) {
+        gpu.queue.write_texture(
+            texture.as_image_copy(),
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(format.block_copy_size(None).unwrap() * size.x),
+                rows_per_image: Some(size.y),
+            },
+            wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+        );
     }
 
     fn create_bind_group(
@@ -221,21 +307,10 @@ impl Sprite {
         Self::write(self, gpu, Vector2::new(rgba.width(), rgba.height()), rgba)
     }
 
+    /// Writes into mip level 0 and regenerates the remaining levels
     pub fn write(&mut self, gpu: &Gpu, size: Vector2<u32>, data: &[u8]) {
-        gpu.queue.write_texture(
-            self.texture.as_image_copy(),
-            data,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: Some(self.format.block_copy_size(None).unwrap() * size.x),
-                rows_per_image: Some(size.y),
-            },
-            wgpu::Extent3d {
-                width: size.x,
-                height: size.y,
-                depth_or_array_layers: 1,
-            },
-        );
+        Self::write_texture(gpu, &self.texture, self.format, size, data);
+        gpu.generate_mipmaps(&self.texture);
     }
 
     pub fn to_bytes(&self, gpu: &Gpu) -> Vec<u8> {
@@ -246,6 +321,7 @@ impl Sprite {
         result.into_inner()
     }
 
+    /// Reads back mip level 0
     pub fn to_image(&self, gpu: &Gpu) -> image::DynamicImage {
         let o_texture_width = self.size.x;
         let texture_width = (o_texture_width as f64 / 64.0).ceil() as u32 * 64;
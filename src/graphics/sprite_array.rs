@@ -1,17 +1,36 @@
 use crate::{
     graphics::{Color, Gpu, Uniform},
     math::Vector2,
+    time::Duration,
 };
+use anyhow::{anyhow, bail, Context, Result};
+use rustc_hash::FxHashMap;
 use std::ops::Deref;
 use wgpu::ImageCopyTexture;
 
 pub type SpriteArrayIndex = u32;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnimationFrame {
+    pub index: SpriteArrayIndex,
+    pub duration: Duration,
+}
+
 pub enum TileSize {
     Amount(Vector2<u32>),
     Size(Vector2<u32>),
 }
 
+/// What to do when the layers of a [SpriteArray] don't share the same dimensions
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LayerMismatch {
+    /// Resizes every layer to the largest width and height
+    #[default]
+    Resize,
+    Error,
+}
+
 pub struct SpriteArrayBuilder<'a, D: Deref<Target = [u8]>> {
     pub label: Option<&'a str>,
     pub sprite_size: Vector2<u32>,
@@ -19,6 +38,7 @@ pub struct SpriteArrayBuilder<'a, D: Deref<Target = [u8]>> {
     pub sampler: wgpu::SamplerDescriptor<'a>,
     pub data: Vec<D>,
     pub format: wgpu::TextureFormat,
+    pub animations: FxHashMap<String, Vec<AnimationFrame>>,
 }
 
 impl<'a> SpriteArrayBuilder<'a, image::RgbaImage> {
@@ -50,6 +70,23 @@ impl<'a> SpriteArrayBuilder<'a, image::RgbaImage> {
         Self::images(&images)
     }
 
+    /// Creates one layer per file in the given order. Panics if the files don't share the same
+    /// dimensions, see [SpriteArrayBuilder::files_with] to resize them instead.
+    pub fn files(files: &[&[u8]]) -> Self {
+        Self::files_with(files, LayerMismatch::Error).unwrap()
+    }
+
+    pub fn files_with(files: &[&[u8]], mismatch: LayerMismatch) -> Result<Self> {
+        let images = files
+            .iter()
+            .enumerate()
+            .map(|(i, bytes)| {
+                image::load_from_memory(bytes).with_context(|| format!("Cannot decode layer {i}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::images_with(&images, mismatch)
+    }
+
     pub fn image_sheet(mut image: image::DynamicImage, size: TileSize) -> Self {
         let array_size = Vector2::new(image.width(), image.height());
         let (sprite_size, sprite_amount) = match size {
@@ -77,25 +114,57 @@ impl<'a> SpriteArrayBuilder<'a, image::RgbaImage> {
             sampler: Self::DEFAULT_SAMPLER,
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
             data,
+            animations: Default::default(),
         }
     }
 
     pub fn images(images: &[image::DynamicImage]) -> Self {
-        assert!(images.len() > 1, "Images cannot be empty!");
+        Self::images_with(images, LayerMismatch::Resize).unwrap()
+    }
+
+    pub fn images_with(images: &[image::DynamicImage], mismatch: LayerMismatch) -> Result<Self> {
+        if images.is_empty() {
+            bail!("Images cannot be empty!");
+        }
         let sprite_size = Vector2::new(
-            images.iter().max_by_key(|i| i.width()).unwrap().width(),
-            images.iter().max_by_key(|i| i.height()).unwrap().height(),
+            images.iter().map(|i| i.width()).max().unwrap(),
+            images.iter().map(|i| i.height()).max().unwrap(),
         );
+        let data = images
+            .iter()
+            .enumerate()
+            .map(|(i, image)| {
+                if image.width() == sprite_size.x && image.height() == sprite_size.y {
+                    return Ok(image.to_rgba8());
+                }
+                match mismatch {
+                    LayerMismatch::Resize => Ok(image
+                        .resize_exact(
+                            sprite_size.x,
+                            sprite_size.y,
+                            image::imageops::FilterType::Triangle,
+                        )
+                        .to_rgba8()),
+                    LayerMismatch::Error => Err(anyhow!(
+                        "Layer {i} is {}x{} but expected {}x{}",
+                        image.width(),
+                        image.height(),
+                        sprite_size.x,
+                        sprite_size.y
+                    )),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
         let sprite_amount = Vector2::new(images.len() as u32, 1);
-        let data = images.iter().map(|image| image.to_rgba8()).collect();
-        Self {
+        Ok(Self {
             label: None,
             sprite_size,
             sprite_amount,
             sampler: Self::DEFAULT_SAMPLER,
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
             data,
-        }
+            animations: Default::default(),
+        })
     }
 }
 
@@ -113,6 +182,7 @@ impl<'a> SpriteArrayBuilder<'a, Vec<u8>> {
             sampler: Self::DEFAULT_SAMPLER,
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
             data,
+            animations: Default::default(),
         }
     }
 }
@@ -130,6 +200,7 @@ impl<'a> SpriteArrayBuilder<'a, &'a [u8]> {
             sampler: Self::DEFAULT_SAMPLER,
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
             data,
+            animations: Default::default(),
         }
     }
 
@@ -141,6 +212,7 @@ impl<'a> SpriteArrayBuilder<'a, &'a [u8]> {
             sampler: Self::DEFAULT_SAMPLER,
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
             data: vec![],
+            animations: Default::default(),
         }
     }
 }
@@ -176,6 +248,11 @@ impl<'a, D: Deref<Target = [u8]>> SpriteArrayBuilder<'a, D> {
         self.label = label;
         self
     }
+
+    pub fn animation(mut self, name: impl Into<String>, frames: Vec<AnimationFrame>) -> Self {
+        self.animations.insert(name.into(), frames);
+        self
+    }
 }
 
 pub struct SpriteArray {
@@ -184,6 +261,7 @@ pub struct SpriteArray {
     bind_group: wgpu::BindGroup,
     sprite_size: Vector2<u32>,
     sprite_amount: Vector2<u32>,
+    animations: FxHashMap<String, Vec<AnimationFrame>>,
 }
 
 impl SpriteArray {
@@ -262,6 +340,7 @@ impl SpriteArray {
             bind_group,
             sprite_size: desc.sprite_size,
             sprite_amount: desc.sprite_amount,
+            animations: desc.animations,
         }
     }
 
@@ -298,6 +377,10 @@ impl SpriteArray {
         );
     }
 
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
     pub fn len(&self) -> u32 {
         self.sprite_amount.x * self.sprite_amount.y
     }
@@ -324,6 +407,16 @@ impl SpriteArray {
     pub fn sprite_size(&self) -> &Vector2<u32> {
         &self.sprite_size
     }
+
+    pub fn animation(&self, name: &str) -> Option<&[AnimationFrame]> {
+        self.animations.get(name).map(|frames| frames.as_slice())
+    }
+
+    pub fn animations(&self) -> impl Iterator<Item = (&str, &[AnimationFrame])> {
+        self.animations
+            .iter()
+            .map(|(name, frames)| (name.as_str(), frames.as_slice()))
+    }
 }
 
 impl Uniform for SpriteArray {
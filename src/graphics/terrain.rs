@@ -0,0 +1,351 @@
+use crate::{
+    graphics::{Gpu, Mesh3D, MeshBuilder3D, ModelBuilder, Vertex3D, WorldCamera3D},
+    math::{BoundingVolume, Vector2, Vector3},
+};
+
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeightmapConfig {
+    /// Size of the terrain on the x and z axis, it starts at the origin
+    pub world_size: Vector2<f32>,
+    /// Height of a white pixel, black pixels are at 0
+    pub max_height: f32,
+    /// Quads per chunk side at full resolution
+    pub chunk_resolution: u32,
+    /// World units covered by one repetition of the texture. The sprite needs a repeating
+    /// address mode.
+    pub texture_size: f32,
+    /// Chunks further away from the camera are drawn at half resolution, [None] disables the
+    /// level of detail
+    pub lod_distance: Option<f32>,
+}
+
+impl Default for HeightmapConfig {
+    fn default() -> Self {
+        Self {
+            world_size: Vector2::new(100.0, 100.0),
+            max_height: 10.0,
+            chunk_resolution: 32,
+            texture_size: 1.0,
+            lod_distance: None,
+        }
+    }
+}
+
+/// Grid of height samples, the first sample is at the origin and the last one at
+/// [HeightmapConfig::world_size]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Heightmap {
+    config: HeightmapConfig,
+    size: Vector2<u32>,
+    heights: Vec<f32>,
+}
+
+impl Heightmap {
+    /// Converts a grayscale image, 16 bit images keep their precision
+    pub fn new(image_bytes: &[u8], config: HeightmapConfig) -> Self {
+        let image = image::load_from_memory(image_bytes).unwrap().to_luma16();
+        let heights = image
+            .pixels()
+            .map(|pixel| pixel.0[0] as f32 / u16::MAX as f32 * config.max_height)
+            .collect();
+        Self::from_heights(Vector2::new(image.width(), image.height()), heights, config)
+    }
+
+    /// `heights` are in rows along the x axis
+    pub fn from_heights(size: Vector2<u32>, heights: Vec<f32>, config: HeightmapConfig) -> Self {
+        assert!(
+            size.x >= 2 && size.y >= 2,
+            "A heightmap needs at least 2x2 samples!"
+        );
+        assert_eq!(heights.len(), (size.x * size.y) as usize);
+        assert!(config.chunk_resolution > 0);
+        Self {
+            config,
+            size,
+            heights,
+        }
+    }
+
+    pub fn config(&self) -> &HeightmapConfig {
+        &self.config
+    }
+
+    /// Number of samples on the x and z axis
+    pub fn size(&self) -> Vector2<u32> {
+        self.size
+    }
+
+    pub fn heights(&self) -> &[f32] {
+        &self.heights
+    }
+
+    fn sample(&self, x: u32, z: u32) -> f32 {
+        let x = x.min(self.size.x - 1);
+        let z = z.min(self.size.y - 1);
+        self.heights[(z * self.size.x + x) as usize]
+    }
+
+    fn cell_size(&self) -> Vector2<f32> {
+        Vector2::new(
+            self.config.world_size.x / (self.size.x - 1) as f32,
+            self.config.world_size.y / (self.size.y - 1) as f32,
+        )
+    }
+
+    /// Bilinearly interpolated height, positions outside of the terrain are clamped to its edge
+    pub fn height_at(&self, x: f32, z: f32) -> f32 {
+        let cell = self.cell_size();
+        let gx = (x / cell.x).clamp(0.0, (self.size.x - 1) as f32);
+        let gz = (z / cell.y).clamp(0.0, (self.size.y - 1) as f32);
+        let (x0, z0) = (gx.floor() as u32, gz.floor() as u32);
+        let (fx, fz) = (gx - x0 as f32, gz - z0 as f32);
+        let top = self.sample(x0, z0) * (1.0 - fx) + self.sample(x0 + 1, z0) * fx;
+        let bottom = self.sample(x0, z0 + 1) * (1.0 - fx) + self.sample(x0 + 1, z0 + 1) * fx;
+        top * (1.0 - fz) + bottom * fz
+    }
+
+    /// Normal of a sample, averaged over its neighbours
+    pub fn normal(&self, x: u32, z: u32) -> Vector3<f32> {
+        let cell = self.cell_size();
+        let dx = (self.sample(x + 1, z) - self.sample(x.saturating_sub(1), z)) / (2.0 * cell.x);
+        let dz = (self.sample(x, z + 1) - self.sample(x, z.saturating_sub(1))) / (2.0 * cell.y);
+        Vector3::new(-dx, 1.0, -dz).normalize()
+    }
+
+    /// Number of chunks on the x and z axis
+    pub fn chunks(&self) -> Vector2<u32> {
+        let resolution = self.config.chunk_resolution;
+        Vector2::new(
+            (self.size.x - 1).div_ceil(resolution),
+            (self.size.y - 1).div_ceil(resolution),
+        )
+    }
+
+    /// Sample indices of a chunk along one axis, every `step`th sample and always the last one
+    fn chunk_samples(&self, chunk: u32, samples: u32, step: u32) -> Vec<u32> {
+        let start = chunk * self.config.chunk_resolution;
+        let end = (start + self.config.chunk_resolution).min(samples - 1);
+        let mut indices = (start..end).step_by(step as usize).collect::<Vec<_>>();
+        indices.push(end);
+        indices
+    }
+
+    pub fn chunk_bounds(&self, chunk: Vector2<u32>) -> BoundingVolume {
+        let cell = self.cell_size();
+        let xs = self.chunk_samples(chunk.x, self.size.x, 1);
+        let zs = self.chunk_samples(chunk.y, self.size.y, 1);
+        let (mut min_height, mut max_height) = (f32::MAX, f32::MIN);
+        for &z in &zs {
+            for &x in &xs {
+                let height = self.sample(x, z);
+                min_height = min_height.min(height);
+                max_height = max_height.max(height);
+            }
+        }
+        BoundingVolume::aabb(
+            Vector3::new(xs[0] as f32 * cell.x, min_height, zs[0] as f32 * cell.y),
+            Vector3::new(
+                *xs.last().unwrap() as f32 * cell.x,
+                max_height,
+                *zs.last().unwrap() as f32 * cell.y,
+            ),
+        )
+    }
+
+    /// Mesh of a chunk that uses every `step`th sample. A skirt hangs down from the borders to
+    /// hide the cracks between chunks of a different resolution.
+    pub fn chunk_mesh(&self, chunk: Vector2<u32>, step: u32) -> MeshBuilder3D {
+        let cell = self.cell_size();
+        let xs = self.chunk_samples(chunk.x, self.size.x, step);
+        let zs = self.chunk_samples(chunk.y, self.size.y, step);
+        let vertex = |x: u32, z: u32, drop: f32| {
+            let pos = Vector3::new(
+                x as f32 * cell.x,
+                self.sample(x, z) - drop,
+                z as f32 * cell.y,
+            );
+            Vertex3D::new(
+                pos,
+                Vector2::new(pos.x, pos.z) / self.config.texture_size,
+                self.normal(x, z),
+            )
+        };
+
+        let columns = xs.len() as u32;
+        let mut vertices = Vec::with_capacity(xs.len() * zs.len());
+        let mut indices = Vec::new();
+        for &z in &zs {
+            for &x in &xs {
+                vertices.push(vertex(x, z, 0.0));
+            }
+        }
+        for row in 0..zs.len() as u32 - 1 {
+            for column in 0..columns - 1 {
+                let a = row * columns + column;
+                let b = a + columns;
+                indices.extend([a, b, b + 1, a, b + 1, a + 1]);
+            }
+        }
+
+        let skirt = (self.config.max_height * 0.05).max(cell.x.max(cell.y));
+        let last_row = (zs.len() - 1) as u32 * columns;
+        let edges = [
+            (0..columns).collect::<Vec<_>>(),
+            (0..columns).map(|column| last_row + column).collect(),
+            (0..zs.len() as u32).map(|row| row * columns).collect(),
+            (0..zs.len() as u32)
+                .map(|row| row * columns + columns - 1)
+                .collect(),
+        ];
+        for edge in edges {
+            for pair in edge.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                let lowered_a = vertices.len() as u32;
+                for index in [a, b] {
+                    let mut lowered = vertices[index as usize];
+                    lowered.pos.y -= skirt;
+                    vertices.push(lowered);
+                }
+                let lowered_b = lowered_a + 1;
+                // Both windings, the skirt is visible from both sides
+                indices.extend([a, lowered_a, lowered_b, a, lowered_b, b]);
+                indices.extend([a, lowered_b, lowered_a, a, b, lowered_b]);
+            }
+        }
+
+        MeshBuilder3D { vertices, indices }
+    }
+}
+
+impl ModelBuilder {
+    /// Terrain with one full resolution mesh per chunk and no materials. Use [Terrain] for
+    /// culling, level of detail and height queries.
+    pub fn heightmap(image_bytes: &[u8], config: HeightmapConfig) -> Self {
+        let heightmap = Heightmap::new(image_bytes, config);
+        let chunks = heightmap.chunks();
+        let mut meshes = Vec::new();
+        for z in 0..chunks.y {
+            for x in 0..chunks.x {
+                let mesh = heightmap.chunk_mesh(Vector2::new(x, z), 1);
+                meshes.push(tobj::Model::new(
+                    tobj::Mesh {
+                        positions: mesh.vertices.iter().flat_map(|v| v.pos.data.0[0]).collect(),
+                        normals: mesh
+                            .vertices
+                            .iter()
+                            .flat_map(|v| v.normal.data.0[0])
+                            .collect(),
+                        texcoords: mesh.vertices.iter().flat_map(|v| v.tex.data.0[0]).collect(),
+                        indices: mesh.indices,
+                        ..Default::default()
+                    },
+                    format!("chunk_{x}_{z}"),
+                ));
+            }
+        }
+        Self {
+            meshes,
+            sprites: Vec::new(),
+            resource_path: None,
+        }
+    }
+}
+
+pub struct TerrainChunk {
+    pub position: Vector2<u32>,
+    pub bounds: BoundingVolume,
+    full: Mesh3D,
+    half: Option<Mesh3D>,
+    visible: bool,
+    use_half: bool,
+}
+
+impl TerrainChunk {
+    /// Mesh that is drawn after the last [Terrain::update]
+    pub fn mesh(&self) -> Option<&Mesh3D> {
+        if !self.visible {
+            None
+        } else if self.use_half {
+            self.half.as_ref()
+        } else {
+            Some(&self.full)
+        }
+    }
+}
+
+/// Chunked terrain that is drawn with
+/// [Renderer::draw_terrain](crate::graphics::Renderer::draw_terrain)
+pub struct Terrain {
+    heightmap: Heightmap,
+    chunks: Vec<TerrainChunk>,
+}
+
+impl Terrain {
+    pub fn new(gpu: &Gpu, heightmap: Heightmap) -> Self {
+        let counts = heightmap.chunks();
+        let lod = heightmap.config.lod_distance.is_some();
+        let mut chunks = Vec::with_capacity((counts.x * counts.y) as usize);
+        for z in 0..counts.y {
+            for x in 0..counts.x {
+                let position = Vector2::new(x, z);
+                chunks.push(TerrainChunk {
+                    position,
+                    bounds: heightmap.chunk_bounds(position),
+                    full: gpu.create_mesh(&heightmap.chunk_mesh(position, 1)),
+                    half: lod.then(|| gpu.create_mesh(&heightmap.chunk_mesh(position, 2))),
+                    visible: true,
+                    use_half: false,
+                });
+            }
+        }
+        Self { heightmap, chunks }
+    }
+
+    pub fn heightmap(&self) -> &Heightmap {
+        &self.heightmap
+    }
+
+    pub fn height_at(&self, x: f32, z: f32) -> f32 {
+        self.heightmap.height_at(x, z)
+    }
+
+    pub fn chunks(&self) -> &[TerrainChunk] {
+        &self.chunks
+    }
+
+    /// Culls the chunks against the frustum of the camera and selects their level of detail.
+    /// Assumes the terrain is drawn with an identity instance.
+    pub fn update(&mut self, camera: &WorldCamera3D) {
+        let frustum = camera.frustum();
+        let eye = camera.eye().coords;
+        let lod_distance = self.heightmap.config.lod_distance;
+        for chunk in &mut self.chunks {
+            chunk.visible = frustum.intersects(&chunk.bounds);
+            chunk.use_half = match (lod_distance, chunk.bounds) {
+                (Some(distance), BoundingVolume::Aabb { min, max }) => {
+                    let closest = eye.zip_zip_map(&min, &max, |e, min, max| e.clamp(min, max));
+                    (closest - eye).norm() > distance
+                }
+                _ => false,
+            };
+        }
+    }
+
+    /// Height profile along the x axis at `z` as a 2D heightfield that spans the width of the
+    /// terrain. The physics world is 2D, so this only fits side views of the terrain.
+    #[cfg(feature = "physics")]
+    pub fn heightfield_collider(&self, z: f32) -> crate::physics::ColliderBuilder {
+        let width = self.heightmap.config.world_size.x;
+        let cell = self.heightmap.cell_size();
+        let heights = (0..self.heightmap.size.x)
+            .map(|x| self.height_at(x as f32 * cell.x, z))
+            .collect::<Vec<_>>();
+        crate::physics::ColliderBuilder::heightfield(
+            nalgebra::DVector::from_vec(heights),
+            Vector2::new(width, 1.0),
+        )
+        .translation(Vector2::new(width / 2.0, 0.0))
+    }
+}
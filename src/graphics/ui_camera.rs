@@ -0,0 +1,170 @@
+use crate::{
+    graphics::Camera2D,
+    math::{Isometry2, Vector2, AABB},
+};
+
+/// How the [UiCamera] maps the design resolution of the UI to the window
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UiScaling {
+    /// The visible height is always the design height, the width depends on the aspect ratio
+    FitHeight(f32),
+    /// The visible width is always the design width, the height depends on the aspect ratio
+    FitWidth(f32),
+    /// Exactly the design size is visible, the rest of the window is cut off by
+    /// [Renderer::use_letterbox](crate::graphics::Renderer::use_letterbox)
+    Letterbox(Vector2<f32>),
+    /// At least the design size is visible, the longer side of the window shows more
+    Expand(Vector2<f32>),
+}
+
+impl Default for UiScaling {
+    fn default() -> Self {
+        Self::FitHeight(1080.0)
+    }
+}
+
+/// Insets in pixels of the window areas that are covered, e.g. by the notch of a phone
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SafeArea {
+    pub top: f32,
+    pub bottom: f32,
+    pub left: f32,
+    pub right: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UiAnchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl UiAnchor {
+    fn direction(&self) -> Vector2<f32> {
+        match self {
+            UiAnchor::TopLeft => Vector2::new(-1.0, 1.0),
+            UiAnchor::Top => Vector2::new(0.0, 1.0),
+            UiAnchor::TopRight => Vector2::new(1.0, 1.0),
+            UiAnchor::Left => Vector2::new(-1.0, 0.0),
+            UiAnchor::Center => Vector2::new(0.0, 0.0),
+            UiAnchor::Right => Vector2::new(1.0, 0.0),
+            UiAnchor::BottomLeft => Vector2::new(-1.0, -1.0),
+            UiAnchor::Bottom => Vector2::new(0.0, -1.0),
+            UiAnchor::BottomRight => Vector2::new(1.0, -1.0),
+        }
+    }
+
+    /// Point of the safe area of `camera` plus `offset`, in the coordinates of the camera
+    pub fn position(&self, camera: &UiCamera, offset: Vector2<f32>) -> Vector2<f32> {
+        let area = camera.safe_area();
+        area.center() + self.direction().component_mul(&area.half_extents()) + offset
+    }
+}
+
+/// Camera for HUDs in design units, centered at the origin. Configured through
+/// [ScreenConfig::set_ui_scaling](crate::graphics::ScreenConfig::set_ui_scaling) and available
+/// as [DefaultAssets::ui_camera](crate::graphics::DefaultAssets::ui_camera).
+#[derive(Clone, Debug)]
+pub struct UiCamera {
+    scaling: UiScaling,
+    insets: SafeArea,
+    window_size: Vector2<u32>,
+    camera: Camera2D,
+}
+
+impl UiCamera {
+    pub fn new(scaling: UiScaling, insets: SafeArea, window_size: Vector2<u32>) -> Self {
+        let window_size = Vector2::new(window_size.x.max(1), window_size.y.max(1));
+        let aspect = window_size.x as f32 / window_size.y as f32;
+        let fov = match scaling {
+            UiScaling::FitHeight(height) => Vector2::new(height * aspect, height) / 2.0,
+            UiScaling::FitWidth(width) => Vector2::new(width, width / aspect) / 2.0,
+            UiScaling::Letterbox(size) | UiScaling::Expand(size) => {
+                if aspect > size.x / size.y {
+                    Vector2::new(size.y * aspect, size.y) / 2.0
+                } else {
+                    Vector2::new(size.x, size.x / aspect) / 2.0
+                }
+            }
+        };
+        Self {
+            scaling,
+            insets,
+            window_size,
+            camera: Camera2D::new(Isometry2::default(), fov),
+        }
+    }
+
+    pub fn camera(&self) -> &Camera2D {
+        &self.camera
+    }
+
+    pub fn scaling(&self) -> UiScaling {
+        self.scaling
+    }
+
+    pub fn insets(&self) -> SafeArea {
+        self.insets
+    }
+
+    /// Half of the size of the whole window in design units
+    pub fn fov(&self) -> Vector2<f32> {
+        self.camera.fov()
+    }
+
+    /// Design units per pixel
+    pub fn pixel_scale(&self) -> f32 {
+        self.fov().y * 2.0 / self.window_size.y as f32
+    }
+
+    /// Area that is drawn to, the design area with [UiScaling::Letterbox] and the whole window
+    /// otherwise
+    pub fn visible_area(&self) -> AABB {
+        match self.scaling {
+            UiScaling::Letterbox(size) => AABB::from_center(Vector2::zeros(), size / 2.0),
+            _ => AABB::from_center(Vector2::zeros(), self.fov()),
+        }
+    }
+
+    /// Visible area without the insets
+    pub fn safe_area(&self) -> AABB {
+        let fov = self.fov();
+        let scale = self.pixel_scale();
+        let visible = self.visible_area();
+        let min = Vector2::new(
+            (-fov.x + self.insets.left * scale).max(visible.min().x),
+            (-fov.y + self.insets.bottom * scale).max(visible.min().y),
+        );
+        let max = Vector2::new(
+            (fov.x - self.insets.right * scale).min(visible.max().x),
+            (fov.y - self.insets.top * scale).min(visible.max().y),
+        );
+        AABB::new(min, max)
+    }
+
+    /// Position and size of the visible area relative to the render target, [None] without
+    /// [UiScaling::Letterbox]
+    pub fn letterbox(&self) -> Option<(Vector2<f32>, Vector2<f32>)> {
+        let UiScaling::Letterbox(size) = self.scaling else {
+            return None;
+        };
+        let fraction = (size / 2.0).component_div(&self.fov());
+        let fraction = Vector2::new(fraction.x.min(1.0), fraction.y.min(1.0));
+        Some(((Vector2::new(1.0, 1.0) - fraction) / 2.0, fraction))
+    }
+
+    /// Converts a position in pixels from the top left of the window to design units
+    pub fn to_ui(&self, pixel: Vector2<f32>) -> Vector2<f32> {
+        let fov = self.fov();
+        let scale = self.pixel_scale();
+        Vector2::new(pixel.x * scale - fov.x, fov.y - pixel.y * scale)
+    }
+}
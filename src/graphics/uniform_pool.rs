@@ -0,0 +1,329 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use rustc_hash::FxHashMap;
+
+use crate::graphics::Gpu;
+
+/// Part of a shared uniform buffer, bound with [Renderer::use_uniform_slice](crate::graphics::Renderer::use_uniform_slice)
+/// to a slot declared as [UniformField::DynamicUniform](crate::graphics::UniformField::DynamicUniform).
+///
+/// Slices start at multiples of `min_uniform_buffer_offset_alignment`, which is 256 bytes on
+/// most devices, so every uniform takes at least 256 bytes. The binding size is the size of the
+/// value rounded up to 16 bytes and must not exceed `max_uniform_buffer_binding_size`.
+#[derive(Clone, Debug)]
+pub struct UniformSlice {
+    bind_group: Arc<wgpu::BindGroup>,
+    offset: u32,
+}
+
+impl UniformSlice {
+    pub fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+}
+
+/// Buffer that is split into slots of the same stride
+#[derive(Debug)]
+struct UniformChunk {
+    buffer: wgpu::Buffer,
+    /// One bind group per binding size
+    bind_groups: FxHashMap<u64, Arc<wgpu::BindGroup>>,
+}
+
+impl UniformChunk {
+    fn new(gpu: &Gpu, size: u64) -> Self {
+        Self {
+            buffer: gpu.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("uniform_pool_buffer"),
+                size,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            bind_groups: FxHashMap::default(),
+        }
+    }
+
+    fn slice(&mut self, gpu: &Gpu, binding_size: u64, offset: u64) -> UniformSlice {
+        let buffer = &self.buffer;
+        let bind_group = self
+            .bind_groups
+            .entry(binding_size)
+            .or_insert_with(|| {
+                Arc::new(gpu.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("uniform_pool_bind_group"),
+                    layout: &gpu.default_layouts().dynamic_uniform_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer,
+                            offset: 0,
+                            size: wgpu::BufferSize::new(binding_size),
+                        }),
+                    }],
+                }))
+            })
+            .clone();
+        UniformSlice {
+            bind_group,
+            offset: offset as u32,
+        }
+    }
+}
+
+/// Binding size and offset stride of a value
+fn layout_of(limits: &wgpu::Limits, size: u64) -> (u64, u64) {
+    let binding_size = wgpu::util::align_to(size.max(1), 16);
+    assert!(
+        binding_size <= limits.max_uniform_buffer_binding_size as u64,
+        "Uniform of {binding_size} bytes exceeds the binding size limit of {} bytes!",
+        limits.max_uniform_buffer_binding_size
+    );
+    let stride = wgpu::util::align_to(
+        binding_size,
+        limits.min_uniform_buffer_offset_alignment as u64,
+    );
+    (binding_size, stride)
+}
+
+/// Offsets of the transient uniforms within their chunks
+#[derive(Debug, Default)]
+struct TransientCursor {
+    chunk_sizes: Vec<u64>,
+    chunk: usize,
+    cursor: u64,
+}
+
+impl TransientCursor {
+    /// Chunk and offset of the next value. A chunk of `chunk_size` bytes is added when all
+    /// chunks are full.
+    fn allocate(&mut self, binding_size: u64, stride: u64, chunk_size: u64) -> (usize, u64) {
+        if self.chunk < self.chunk_sizes.len()
+            && self.cursor + binding_size > self.chunk_sizes[self.chunk]
+        {
+            self.chunk += 1;
+            self.cursor = 0;
+        }
+        if self.chunk == self.chunk_sizes.len() {
+            self.chunk_sizes.push(chunk_size);
+        }
+
+        let offset = self.cursor;
+        self.cursor += stride;
+        (self.chunk, offset)
+    }
+
+    fn reset(&mut self) {
+        self.chunk = 0;
+        self.cursor = 0;
+    }
+}
+
+/// Uniforms that live for one frame, see [Gpu::transient_uniform]
+#[derive(Debug, Default)]
+pub(crate) struct TransientUniforms {
+    chunks: Vec<UniformChunk>,
+    cursor: TransientCursor,
+}
+
+impl TransientUniforms {
+    pub const CHUNK_SIZE: u64 = 1 << 20;
+
+    pub fn push(&mut self, gpu: &Gpu, data: &[u8]) -> UniformSlice {
+        let (binding_size, stride) = layout_of(&gpu.device.limits(), data.len() as u64);
+        let chunk_size = Self::CHUNK_SIZE.max(stride);
+        let (chunk, offset) = self.cursor.allocate(binding_size, stride, chunk_size);
+        if chunk == self.chunks.len() {
+            self.chunks.push(UniformChunk::new(gpu, chunk_size));
+        }
+
+        let chunk = &mut self.chunks[chunk];
+        gpu.queue.write_buffer(&chunk.buffer, offset, data);
+        chunk.slice(gpu, binding_size, offset)
+    }
+
+    pub fn reset(&mut self) {
+        self.cursor.reset();
+    }
+
+    pub fn buffers(&self) -> usize {
+        self.chunks.len()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UniformPoolKey(u32);
+
+/// Persistent uniforms of the same type that share a few large buffers instead of allocating a
+/// buffer and bind group each
+#[derive(Debug)]
+pub struct UniformPool<T: bytemuck::Pod> {
+    chunks: Vec<UniformChunk>,
+    binding_size: u64,
+    stride: u64,
+    slots_per_chunk: u32,
+    slots: u32,
+    free: Vec<u32>,
+    marker: PhantomData<T>,
+}
+
+impl<T: bytemuck::Pod> UniformPool<T> {
+    pub const CHUNK_SIZE: u64 = 1 << 16;
+
+    pub fn new(gpu: &Gpu) -> Self {
+        let (binding_size, stride) =
+            layout_of(&gpu.device.limits(), std::mem::size_of::<T>() as u64);
+        Self {
+            chunks: Vec::new(),
+            binding_size,
+            stride,
+            slots_per_chunk: (Self::CHUNK_SIZE / stride).max(1) as u32,
+            slots: 0,
+            free: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+
+    pub fn insert(&mut self, gpu: &Gpu, value: &T) -> UniformPoolKey {
+        let slot = self.free.pop().unwrap_or_else(|| {
+            self.slots += 1;
+            self.slots - 1
+        });
+        let chunk = (slot / self.slots_per_chunk) as usize;
+        while self.chunks.len() <= chunk {
+            self.chunks.push(UniformChunk::new(
+                gpu,
+                self.stride * self.slots_per_chunk as u64,
+            ));
+        }
+        let key = UniformPoolKey(slot);
+        self.write(gpu, key, value);
+        key
+    }
+
+    pub fn write(&self, gpu: &Gpu, key: UniformPoolKey, value: &T) {
+        let (chunk, offset) = self.location(key);
+        gpu.queue.write_buffer(
+            &self.chunks[chunk].buffer,
+            offset,
+            bytemuck::bytes_of(value),
+        );
+    }
+
+    /// The slot is reused by the next [UniformPool::insert]
+    pub fn remove(&mut self, key: UniformPoolKey) {
+        debug_assert!(!self.free.contains(&key.0), "Uniform is already removed!");
+        self.free.push(key.0);
+    }
+
+    pub fn get(&mut self, gpu: &Gpu, key: UniformPoolKey) -> UniformSlice {
+        let (chunk, offset) = self.location(key);
+        self.chunks[chunk].slice(gpu, self.binding_size, offset)
+    }
+
+    pub fn len(&self) -> usize {
+        (self.slots as usize) - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn buffers(&self) -> usize {
+        self.chunks.len()
+    }
+
+    fn location(&self, key: UniformPoolKey) -> (usize, u64) {
+        let chunk = key.0 / self.slots_per_chunk;
+        let slot = key.0 % self.slots_per_chunk;
+        (chunk as usize, slot as u64 * self.stride)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOCATIONS: usize = 10_000;
+
+    fn frame(cursor: &mut TransientCursor, limits: &wgpu::Limits) -> Vec<(usize, u64, u64)> {
+        (0..ALLOCATIONS)
+            .map(|i| {
+                let (binding_size, stride) = layout_of(limits, (i % 300) as u64);
+                let chunk_size = TransientUniforms::CHUNK_SIZE.max(stride);
+                let (chunk, offset) = cursor.allocate(binding_size, stride, chunk_size);
+                (chunk, offset, binding_size)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn layout() {
+        let limits = wgpu::Limits::default();
+        assert_eq!(layout_of(&limits, 0), (16, 256));
+        assert_eq!(layout_of(&limits, 4), (16, 256));
+        assert_eq!(layout_of(&limits, 64), (64, 256));
+        assert_eq!(layout_of(&limits, 257), (272, 512));
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the binding size limit")]
+    fn oversized_uniform() {
+        let limits = wgpu::Limits::default();
+        layout_of(&limits, limits.max_uniform_buffer_binding_size as u64 + 1);
+    }
+
+    #[test]
+    fn transient_offsets() {
+        let limits = wgpu::Limits::default();
+        let alignment = limits.min_uniform_buffer_offset_alignment as u64;
+        let mut cursor = TransientCursor::default();
+        let allocations = frame(&mut cursor, &limits);
+
+        let mut previous: Option<(usize, u64, u64)> = None;
+        for &(chunk, offset, binding_size) in &allocations {
+            assert_eq!(offset % alignment, 0, "Offset {offset} is not aligned!");
+            assert_eq!(binding_size % 16, 0);
+            assert!(offset + binding_size <= cursor.chunk_sizes[chunk]);
+            if let Some((previous_chunk, previous_offset, previous_size)) = previous {
+                if chunk == previous_chunk {
+                    assert!(offset >= previous_offset + previous_size, "Slices overlap!");
+                } else {
+                    assert_eq!(chunk, previous_chunk + 1);
+                    assert_eq!(offset, 0);
+                }
+            }
+            previous = Some((chunk, offset, binding_size));
+        }
+
+        // Only the end of a chunk that can't fit the next slice is wasted, so the chunks are
+        // no more than needed for the strides of all slices
+        let used: u64 = (0..ALLOCATIONS)
+            .map(|i| layout_of(&limits, (i % 300) as u64).1)
+            .sum();
+        assert!(cursor.chunk_sizes.len() <= used.div_ceil(TransientUniforms::CHUNK_SIZE) as usize);
+    }
+
+    #[test]
+    fn transient_reuse_across_frames() {
+        let limits = wgpu::Limits::default();
+        let mut cursor = TransientCursor::default();
+        let first = frame(&mut cursor, &limits);
+        let chunks = cursor.chunk_sizes.len();
+        for _ in 0..3 {
+            cursor.reset();
+            assert_eq!(frame(&mut cursor, &limits), first);
+            assert_eq!(cursor.chunk_sizes.len(), chunks);
+        }
+
+        // A bigger frame only adds chunks
+        cursor.reset();
+        frame(&mut cursor, &limits);
+        frame(&mut cursor, &limits);
+        assert!(cursor.chunk_sizes.len() > chunks);
+    }
+}
@@ -0,0 +1,77 @@
+use std::{cell::Cell, rc::Rc};
+
+use wasm_bindgen_futures::{
+    js_sys,
+    wasm_bindgen::{closure::Closure, JsCast},
+};
+use web_sys::{HtmlCanvasElement, ResizeObserver, ResizeObserverEntry};
+
+use crate::math::Vector2;
+
+/// Follows the CSS size of the canvas with a `ResizeObserver`. The surface has to match the size
+/// in physical pixels, which is the CSS size times the `devicePixelRatio`. The ratio is read
+/// every frame since zooming or moving the browser to another display can change it without
+/// changing the CSS size.
+pub(crate) struct WebCanvas {
+    canvas: HtmlCanvasElement,
+    css_size: Rc<Cell<Vector2<f64>>>,
+    observer: ResizeObserver,
+    _callback: Closure<dyn FnMut(js_sys::Array)>,
+}
+
+impl WebCanvas {
+    pub fn new(canvas: HtmlCanvasElement) -> Self {
+        let css_size = Rc::new(Cell::new(Vector2::new(
+            canvas.client_width() as f64,
+            canvas.client_height() as f64,
+        )));
+        let observed = css_size.clone();
+        let callback = Closure::<dyn FnMut(js_sys::Array)>::new(move |entries: js_sys::Array| {
+            if let Some(entry) = entries
+                .iter()
+                .last()
+                .and_then(|entry| entry.dyn_into::<ResizeObserverEntry>().ok())
+            {
+                let rect = entry.content_rect();
+                observed.set(Vector2::new(rect.width(), rect.height()));
+            }
+        });
+        let observer = ResizeObserver::new(callback.as_ref().unchecked_ref())
+            .expect("ResizeObserver is not supported!");
+        observer.observe(&canvas);
+        Self {
+            canvas,
+            css_size,
+            observer,
+            _callback: callback,
+        }
+    }
+
+    pub fn scale_factor(&self) -> f64 {
+        web_sys::window()
+            .map(|window| window.device_pixel_ratio())
+            .filter(|ratio| *ratio > 0.0)
+            .unwrap_or(1.0)
+    }
+
+    /// Size the surface needs to be crisp, never zero
+    pub fn physical_size(&self) -> Vector2<u32> {
+        let size = self.css_size.get() * self.scale_factor();
+        Vector2::new(
+            (size.x.round() as u32).max(1),
+            (size.y.round() as u32).max(1),
+        )
+    }
+
+    /// Sets the drawing buffer of the canvas, its CSS size stays untouched
+    pub fn apply(&self, size: Vector2<u32>) {
+        self.canvas.set_width(size.x);
+        self.canvas.set_height(size.y);
+    }
+}
+
+impl Drop for WebCanvas {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+use winit::{
+    event_loop::ActiveEventLoop,
+    window::{Window, WindowAttributes, WindowId},
+};
+
+#[cfg(feature = "log")]
+use crate::log::info;
+use crate::{
+    context::RenderContext,
+    graphics::{Gpu, RenderEncoder, SpriteRenderTarget, SurfaceRenderTarget},
+    math::Vector2,
+};
+
+pub type WindowRenderCallback = Box<dyn Fn(&RenderContext, &mut RenderEncoder)>;
+
+pub struct WindowConfig {
+    pub attributes: WindowAttributes,
+    pub vsync: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WindowConfig {
+    pub fn new() -> Self {
+        Self {
+            attributes: WindowAttributes::default()
+                .with_inner_size(winit::dpi::PhysicalSize::new(800, 600))
+                .with_title("Window"),
+            vsync: true,
+        }
+    }
+
+    pub fn attributes(mut self, attributes: WindowAttributes) -> Self {
+        self.attributes = attributes;
+        self
+    }
+
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.vsync = vsync;
+        self
+    }
+}
+
+pub(crate) struct WindowSurface {
+    // The surface must be dropped before its window
+    surface: wgpu::Surface<'static>,
+    config: wgpu::SurfaceConfiguration,
+    target_msaa: Option<wgpu::Texture>,
+    scale_factor: f64,
+    pub(crate) render: Option<WindowRenderCallback>,
+    window: Arc<Window>,
+}
+
+/// Additional windows that share the [Gpu] of the main window. Every window has its own surface
+/// and gets drawn after the main window by the callback set with [WindowManager::on_render].
+/// Closing one of them only closes that window.
+pub struct WindowManager {
+    gpu: Arc<Gpu>,
+    pub(crate) windows: FxHashMap<WindowId, WindowSurface>,
+    closed: Vec<WindowId>,
+}
+
+impl WindowManager {
+    pub(crate) fn new(gpu: Arc<Gpu>) -> Self {
+        Self {
+            gpu,
+            windows: Default::default(),
+            closed: Vec::new(),
+        }
+    }
+
+    pub fn create(&mut self, event_loop: &ActiveEventLoop, config: WindowConfig) -> WindowId {
+        let window = Arc::new(event_loop.create_window(config.attributes).unwrap());
+        let surface = self.gpu.instance.create_surface(window.clone()).unwrap();
+        let capabilities = surface.get_capabilities(&self.gpu.adapter);
+        assert!(
+            capabilities.formats.contains(&self.gpu.format()),
+            "The window doesn't support the texture format of the main window!"
+        );
+
+        let size = Gpu::compute_surface_size(&window);
+        let mut surface_config = surface
+            .get_default_config(&self.gpu.adapter, size.x, size.y)
+            .expect("Surface isn't supported by the adapter.");
+        surface_config.format = self.gpu.format();
+        surface_config.present_mode = if config.vsync {
+            wgpu::PresentMode::AutoVsync
+        } else {
+            wgpu::PresentMode::AutoNoVsync
+        };
+        surface.configure(&self.gpu.device, &surface_config);
+
+        let id = window.id();
+        #[cfg(feature = "log")]
+        info!("Created window {:?} with size: {} x {}", id, size.x, size.y);
+        self.windows.insert(
+            id,
+            WindowSurface {
+                target_msaa: self.create_msaa(size),
+                scale_factor: window.scale_factor(),
+                config: surface_config,
+                surface,
+                render: None,
+                window,
+            },
+        );
+        id
+    }
+
+    /// Closes the window, returns false if it was already closed
+    pub fn close(&mut self, id: WindowId) -> bool {
+        if self.windows.remove(&id).is_some() {
+            #[cfg(feature = "log")]
+            info!("Closed window {:?}", id);
+            self.closed.push(id);
+            return true;
+        }
+        false
+    }
+
+    /// Called every frame with the [RenderContext] of the active scene and an encoder that
+    /// targets the window.
+    pub fn on_render(
+        &mut self,
+        id: WindowId,
+        render: impl Fn(&RenderContext, &mut RenderEncoder) + 'static,
+    ) {
+        if let Some(window) = self.windows.get_mut(&id) {
+            window.render = Some(Box::new(render));
+        }
+    }
+
+    pub fn contains(&self, id: WindowId) -> bool {
+        self.windows.contains_key(&id)
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = WindowId> + '_ {
+        self.windows.keys().copied()
+    }
+
+    pub fn window(&self, id: WindowId) -> Option<&Arc<Window>> {
+        self.windows.get(&id).map(|window| &window.window)
+    }
+
+    pub fn size(&self, id: WindowId) -> Option<Vector2<u32>> {
+        self.windows
+            .get(&id)
+            .map(|window| Vector2::new(window.config.width, window.config.height))
+    }
+
+    pub fn scale_factor(&self, id: WindowId) -> Option<f64> {
+        self.windows.get(&id).map(|window| window.scale_factor)
+    }
+
+    /// Windows closed since the last frame, either by the user or with [WindowManager::close]
+    pub fn just_closed(&self) -> &[WindowId] {
+        &self.closed
+    }
+
+    pub fn set_vsync(&mut self, id: WindowId, vsync: bool) {
+        if let Some(window) = self.windows.get_mut(&id) {
+            window.config.present_mode = if vsync {
+                wgpu::PresentMode::AutoVsync
+            } else {
+                wgpu::PresentMode::AutoNoVsync
+            };
+            window.surface.configure(&self.gpu.device, &window.config);
+        }
+    }
+
+    pub(crate) fn resize(&mut self, id: WindowId, size: Vector2<u32>) {
+        let target_msaa = self.create_msaa(size);
+        if let Some(window) = self.windows.get_mut(&id) {
+            window.config.width = size.x.max(1);
+            window.config.height = size.y.max(1);
+            window.target_msaa = target_msaa;
+            window.surface.configure(&self.gpu.device, &window.config);
+        }
+    }
+
+    pub(crate) fn set_scale_factor(&mut self, id: WindowId, scale_factor: f64) {
+        if let Some(window) = self.windows.get_mut(&id) {
+            window.scale_factor = scale_factor;
+        }
+    }
+
+    pub(crate) fn update(&mut self) {
+        self.closed.clear();
+    }
+
+    pub(crate) fn start_frame(&self, id: WindowId) -> Option<SurfaceRenderTarget> {
+        let window = self.windows.get(&id)?;
+        let surface_texture = match window.surface.get_current_texture() {
+            Ok(frame) => frame,
+            Err(wgpu::SurfaceError::Timeout) => window.surface.get_current_texture().ok()?,
+            Err(
+                wgpu::SurfaceError::Outdated
+                | wgpu::SurfaceError::Lost
+                | wgpu::SurfaceError::OutOfMemory,
+            ) => {
+                window.surface.configure(&self.gpu.device, &window.config);
+                window.surface.get_current_texture().ok()?
+            }
+        };
+
+        Some(SurfaceRenderTarget {
+            target_view: surface_texture.texture.create_view(&Default::default()),
+            msaa_view: window
+                .target_msaa
+                .as_ref()
+                .map(|msaa| msaa.create_view(&Default::default())),
+            samples: self.gpu.samples(),
+            surface_texture,
+        })
+    }
+
+    fn create_msaa(&self, size: Vector2<u32>) -> Option<wgpu::Texture> {
+        let samples = self.gpu.samples();
+        (samples != 1).then(|| {
+            SpriteRenderTarget::create_msaa(
+                &self.gpu,
+                Vector2::new(size.x.max(1), size.y.max(1)),
+                samples,
+            )
+        })
+    }
+}
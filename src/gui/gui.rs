@@ -1,21 +1,29 @@
 use std::ops::{Deref, DerefMut};
 
 use crate::{
-    graphics::{Gpu, RenderEncoder, RenderTarget, SurfaceRenderTarget},
+    graphics::{Camera2D, Gpu, GpuId, RenderEncoder, RenderTarget, Sprite, SurfaceRenderTarget},
     gui::GuiContext,
-    math::Vector2,
+    math::{Point2, Vector2},
 };
 use egui::mutex::Mutex;
 use egui_wgpu::{Renderer, ScreenDescriptor};
 use egui_winit::State;
 use instant::Duration;
+use rustc_hash::FxHashMap;
 use winit::window::Window;
 
+struct SpriteTexture {
+    id: egui::TextureId,
+    used: bool,
+}
+
 pub struct Gui {
     state: State,
     context: GuiContext,
     renderer: Renderer,
     screen_descriptor: Mutex<ScreenDescriptor>,
+    sprites: FxHashMap<GpuId<wgpu::TextureView>, SpriteTexture>,
+    repaint_delay: Duration,
 }
 
 impl Gui {
@@ -39,6 +47,8 @@ impl Gui {
             state,
             context,
             screen_descriptor: Mutex::new(screen_descriptor),
+            sprites: FxHashMap::default(),
+            repaint_delay: Duration::ZERO,
         }
     }
 
@@ -66,6 +76,10 @@ impl Gui {
         encoder: &mut RenderEncoder,
     ) {
         let output = self.context.end_frame();
+        self.repaint_delay = output
+            .viewport_output
+            .get(&egui::ViewportId::ROOT)
+            .map_or(Duration::MAX, |viewport| viewport.repaint_delay);
         let paint_jobs = self.context.tessellate(output.shapes, 1.0);
 
         for add in &output.textures_delta.set {
@@ -100,6 +114,96 @@ impl Gui {
         for free in &output.textures_delta.free {
             self.renderer.free_texture(free);
         }
+
+        let renderer = &mut self.renderer;
+        self.sprites.retain(|_, sprite| {
+            if !sprite.used {
+                renderer.free_texture(&sprite.id);
+            }
+            std::mem::take(&mut sprite.used)
+        });
+    }
+
+    /// Time until egui wants to be drawn again, e.g. for an animation. [None] if it doesn't.
+    pub(crate) fn repaint_delay(&self) -> Option<Duration> {
+        (self.repaint_delay != Duration::MAX).then_some(self.repaint_delay)
+    }
+
+    /// Makes the sprite usable as an egui image, e.g. `ui.image((id, size))`. Textures that are
+    /// not registered during a frame are freed after it, so register the sprite every frame it
+    /// is shown. Resizing a [SpriteRenderTarget](crate::graphics::SpriteRenderTarget) creates a
+    /// new texture and therefore a new id.
+    pub fn register_sprite(&mut self, gpu: &Gpu, sprite: &Sprite) -> egui::TextureId {
+        let renderer = &mut self.renderer;
+        let texture = self
+            .sprites
+            .entry(sprite.view().global_id())
+            .or_insert_with(|| SpriteTexture {
+                id: renderer.register_native_texture(
+                    &gpu.device,
+                    sprite.view(),
+                    wgpu::FilterMode::Linear,
+                ),
+                used: false,
+            });
+        texture.used = true;
+        texture.id
+    }
+
+    pub fn unregister_sprite(&mut self, sprite: &Sprite) {
+        if let Some(texture) = self.sprites.remove(&sprite.view().global_id()) {
+            self.renderer.free_texture(&texture.id);
+        }
+    }
+
+    /// Image of the sprite with the given size in points
+    pub fn sprite_image(
+        &mut self,
+        gpu: &Gpu,
+        sprite: &Sprite,
+        size: Vector2<f32>,
+    ) -> egui::Image<'static> {
+        let id = self.register_sprite(gpu, sprite);
+        egui::Image::new((id, egui::vec2(size.x, size.y)))
+    }
+
+    /// Image of a part of the sprite, `offset` and `crop` are in pixels
+    pub fn sprite_crop_image(
+        &mut self,
+        gpu: &Gpu,
+        sprite: &Sprite,
+        offset: Vector2<u32>,
+        crop: Vector2<u32>,
+        size: Vector2<f32>,
+    ) -> egui::Image<'static> {
+        self.sprite_image(gpu, sprite, size)
+            .uv(Self::sprite_uv(sprite, offset, crop))
+    }
+
+    /// Normalized texture coordinates of a part of the sprite in pixels
+    pub fn sprite_uv(sprite: &Sprite, offset: Vector2<u32>, crop: Vector2<u32>) -> egui::Rect {
+        let size = sprite.size().cast::<f32>();
+        egui::Rect::from_min_size(
+            egui::pos2(offset.x as f32 / size.x, offset.y as f32 / size.y),
+            egui::vec2(crop.x as f32 / size.x, crop.y as f32 / size.y),
+        )
+    }
+
+    /// World position of the pointer above a viewport image that shows what `camera` sees,
+    /// [None] if the pointer is not above the image
+    pub fn viewport_cursor(response: &egui::Response, camera: &Camera2D) -> Option<Point2<f32>> {
+        let pos = response.hover_pos()?;
+        let rect = response.rect;
+        let relative = (pos - rect.min) / rect.size();
+        let fov = camera.fov() * 2.0;
+        Some(
+            (camera.translation()
+                + Vector2::new(
+                    relative.x * fov.x - fov.x / 2.0,
+                    relative.y * -fov.y + fov.y / 2.0,
+                ))
+            .into(),
+        )
     }
 
     // pub fn pixels_per_point(&self) -> f32 {
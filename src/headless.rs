@@ -0,0 +1,252 @@
+#[cfg(feature = "physics")]
+use crate::physics::{Physics, PhysicsConfig};
+use crate::{
+    ecs::{
+        fixed_ticks, set_entity_enabled, tick_interval, EntityId, SystemPriority, UpdateOperation,
+        World, WorldExt,
+    },
+    time::{Duration, TimeManager},
+};
+
+pub type HeadlessSetupSystem = Box<dyn FnOnce(&mut HeadlessContext)>;
+pub type HeadlessUpdateSystem = Box<dyn Fn(&mut HeadlessContext)>;
+
+/// The part of [Context](crate::context::Context) that doesn't need a window, gpu or audio
+/// device. There is deliberately no way to reach graphics from here.
+#[non_exhaustive]
+pub struct HeadlessContext<'a> {
+    pub world: &'a mut World,
+    #[cfg(feature = "physics")]
+    pub physics: &'a mut Physics,
+    pub time: &'a TimeManager,
+    pub end: &'a mut bool,
+}
+
+impl HeadlessContext<'_> {
+    /// Same as [Context::set_enabled](crate::context::Context::set_enabled)
+    pub fn set_enabled(&mut self, entity: EntityId, enabled: bool) {
+        set_entity_enabled(
+            self.world,
+            #[cfg(feature = "physics")]
+            self.physics,
+            entity,
+            enabled,
+        );
+    }
+
+    pub fn is_enabled(&self, entity: EntityId) -> bool {
+        self.world.is_enabled(entity)
+    }
+}
+
+/// Runs setup, update and fixed update systems on a [World] without creating a window or
+/// touching the gpu. Meant for servers, simulations and tests. Either drive it manually with
+/// [HeadlessApp::tick] or in real time with [HeadlessApp::run].
+///
+/// Like in a normal scene the physics are only stepped by the systems themselves, for example
+/// with `ctx.physics.update(ctx.time.delta())`.
+pub struct HeadlessApp {
+    world: World,
+    #[cfg(feature = "physics")]
+    physics: Physics,
+    time: TimeManager,
+    end: bool,
+    setup_systems: Vec<(SystemPriority, HeadlessSetupSystem)>,
+    update_systems: Vec<(SystemPriority, (UpdateOperation, HeadlessUpdateSystem))>,
+}
+
+impl Default for HeadlessApp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeadlessApp {
+    pub fn new() -> Self {
+        Self {
+            world: World::new(),
+            #[cfg(feature = "physics")]
+            physics: Physics::new(),
+            time: TimeManager::new(),
+            end: false,
+            setup_systems: Vec::new(),
+            update_systems: Vec::new(),
+        }
+    }
+
+    pub fn setup(
+        mut self,
+        priority: SystemPriority,
+        system: impl FnOnce(&mut HeadlessContext) + 'static,
+    ) -> Self {
+        self.setup_systems.push((priority, Box::new(system)));
+        self.setup_systems.sort_by_key(|e| e.0);
+        self
+    }
+
+    pub fn update(
+        mut self,
+        priority: SystemPriority,
+        system: impl Fn(&mut HeadlessContext) + 'static,
+    ) -> Self {
+        self.update_systems
+            .push((priority, (UpdateOperation::EveryFrame, Box::new(system))));
+        self.update_systems.sort_by_key(|e| e.0);
+        self
+    }
+
+    /// Runs `system` `tick_rate` times per second. Panics if `tick_rate` is 0.
+    pub fn fixed_update(
+        mut self,
+        priority: SystemPriority,
+        system: impl Fn(&mut HeadlessContext) + 'static,
+        tick_rate: u32,
+    ) -> Self {
+        self.update_systems.push((
+            priority,
+            (
+                UpdateOperation::FixedUpdate {
+                    accumulator: Duration::ZERO,
+                    fixed_delta: tick_interval(tick_rate),
+                },
+                Box::new(system),
+            ),
+        ));
+        self.update_systems.sort_by_key(|e| e.0);
+        self
+    }
+
+    #[cfg(feature = "physics")]
+    pub fn physics_config(mut self, config: PhysicsConfig) -> Self {
+        self.physics.set_config(config);
+        self
+    }
+
+    /// Advances the time by exactly `delta` and runs all systems once. The same sequence of
+    /// deltas always runs the same sequence of systems.
+    pub fn tick(&mut self, delta: Duration) {
+        self.time.tick_by(delta);
+        let mut ctx = HeadlessContext {
+            world: &mut self.world,
+            #[cfg(feature = "physics")]
+            physics: &mut self.physics,
+            time: &self.time,
+            end: &mut self.end,
+        };
+
+        for (_, setup) in self.setup_systems.drain(..) {
+            (setup)(&mut ctx)
+        }
+
+        for (_, (update_operation, update)) in &mut self.update_systems {
+            match update_operation {
+                UpdateOperation::FixedUpdate {
+                    accumulator,
+                    fixed_delta,
+                } => {
+                    for _ in 0..fixed_ticks(ctx.time, accumulator, *fixed_delta) {
+                        (update)(&mut ctx);
+                    }
+                }
+                _ => (update)(&mut ctx),
+            }
+        }
+    }
+
+    /// Ticks `ticks` times with the same `delta`
+    pub fn run_for(&mut self, ticks: u64, delta: Duration) {
+        for _ in 0..ticks {
+            if self.end {
+                break;
+            }
+            self.tick(delta);
+        }
+    }
+
+    /// Ticks with the measured frame time until a system sets `end`. `tick_rate` limits how
+    /// often the systems run per second, without it the loop never sleeps. Panics if
+    /// `tick_rate` is 0.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run(&mut self, tick_rate: Option<u32>) {
+        use crate::time::Instant;
+
+        let interval = tick_rate.map(tick_interval);
+        let mut last = Instant::now();
+        while !self.end {
+            if let Some(interval) = interval {
+                let next = last + interval;
+                let now = Instant::now();
+                if now < next {
+                    std::thread::sleep(next - now);
+                }
+            }
+            let now = Instant::now();
+            self.tick((now - last).min(TimeManager::MAX_FRAME_TIME));
+            last = now;
+        }
+    }
+
+    pub fn end(&mut self) {
+        self.end = true;
+    }
+
+    pub fn ended(&self) -> bool {
+        self.end
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    #[cfg(feature = "physics")]
+    pub fn physics(&self) -> &Physics {
+        &self.physics
+    }
+
+    #[cfg(feature = "physics")]
+    pub fn physics_mut(&mut self) -> &mut Physics {
+        &mut self.physics
+    }
+
+    pub fn time(&self) -> &TimeManager {
+        &self.time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+
+    #[test]
+    fn fixed_update_runs_at_tick_rate() {
+        let ticks = Rc::new(Cell::new(0));
+        let counter = ticks.clone();
+        let mut app = HeadlessApp::new().fixed_update(
+            SystemPriority::default(),
+            move |_| counter.set(counter.get() + 1),
+            10,
+        );
+        for _ in 0..20 {
+            app.tick(Duration::from_millis(50));
+        }
+        assert_eq!(ticks.get(), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "tick rate must be greater than 0")]
+    fn zero_tick_rate_is_rejected() {
+        HeadlessApp::new().fixed_update(SystemPriority::default(), |_| {}, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "tick rate must be greater than 0")]
+    fn zero_run_rate_is_rejected() {
+        HeadlessApp::new().run(Some(0));
+    }
+}
@@ -0,0 +1,293 @@
+use std::fmt::Display;
+
+use parking_lot::Mutex;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::io::ResourceLoader;
+#[cfg(feature = "log")]
+use crate::log::warn;
+
+/// Strings of one language, parsed from lines of `key = value`. Lines starting with `#` are
+/// comments, `\n` in a value is a line break.
+#[derive(Clone, Debug, Default)]
+pub struct StringTable {
+    entries: FxHashMap<String, String>,
+}
+
+impl StringTable {
+    pub fn parse(source: &str) -> Self {
+        let mut entries = FxHashMap::default();
+        for line in source.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().replace("\\n", "\n");
+            entries.insert(key.trim().to_owned(), value);
+        }
+        Self { entries }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.entries.insert(key.into(), value.into());
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// String tables of all loaded languages, accessible as `ctx.i18n`
+pub struct I18n {
+    languages: FxHashMap<String, StringTable>,
+    language: String,
+    fallback: String,
+    pseudo: Option<StringTable>,
+    missing: Mutex<FxHashSet<String>>,
+}
+
+impl Default for I18n {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl I18n {
+    pub const DEFAULT_LANGUAGE: &'static str = "en";
+
+    pub fn new() -> Self {
+        Self {
+            languages: FxHashMap::default(),
+            language: Self::DEFAULT_LANGUAGE.to_owned(),
+            fallback: Self::DEFAULT_LANGUAGE.to_owned(),
+            pseudo: None,
+            missing: Default::default(),
+        }
+    }
+
+    /// Parses a [StringTable] and adds it to the strings of `language` that are already loaded
+    pub fn load_language(&mut self, language: &str, bytes: &[u8]) {
+        let table = StringTable::parse(&String::from_utf8_lossy(bytes));
+        let entries = &mut self
+            .languages
+            .entry(language.to_owned())
+            .or_default()
+            .entries;
+        entries.extend(table.entries);
+        self.missing.lock().clear();
+        self.update_pseudo();
+    }
+
+    pub fn load_language_file(
+        &mut self,
+        resource: &dyn ResourceLoader,
+        language: &str,
+        path: &str,
+    ) -> anyhow::Result<()> {
+        let bytes = resource.load_bytes(path)?;
+        self.load_language(language, &bytes);
+        Ok(())
+    }
+
+    pub fn set_language(&mut self, language: &str) {
+        assert!(
+            self.languages.contains_key(language),
+            "Language '{language}' is not loaded!"
+        );
+        self.language = language.to_owned();
+        self.missing.lock().clear();
+        self.update_pseudo();
+    }
+
+    /// Language whose strings are used when the current language is missing a key,
+    /// [I18n::DEFAULT_LANGUAGE] by default
+    pub fn set_fallback(&mut self, language: &str) {
+        self.fallback = language.to_owned();
+        self.update_pseudo();
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    pub fn fallback(&self) -> &str {
+        &self.fallback
+    }
+
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.languages.keys().map(String::as_str)
+    }
+
+    pub fn table(&self, language: &str) -> Option<&StringTable> {
+        self.languages.get(language)
+    }
+
+    /// Replaces all strings with accented and lengthened versions, e.g. `Start` becomes
+    /// `[Śţàŕţ ~~]`, to find text that is not localized or does not fit into the layout
+    pub fn set_pseudo_localization(&mut self, enabled: bool) {
+        if enabled {
+            self.pseudo = Some(StringTable::default());
+            self.update_pseudo();
+        } else {
+            self.pseudo = None;
+        }
+    }
+
+    pub fn pseudo_localization(&self) -> bool {
+        self.pseudo.is_some()
+    }
+
+    fn update_pseudo(&mut self) {
+        if self.pseudo.is_none() {
+            return;
+        }
+        let mut pseudo = StringTable::default();
+        for language in [&self.fallback, &self.language] {
+            if let Some(table) = self.languages.get(language) {
+                for (key, value) in &table.entries {
+                    pseudo.insert(key.clone(), pseudo_localize(value));
+                }
+            }
+        }
+        self.pseudo = Some(pseudo);
+    }
+
+    fn lookup(&self, key: &str) -> Option<&str> {
+        if let Some(pseudo) = &self.pseudo {
+            return pseudo.get(key);
+        }
+        [&self.language, &self.fallback]
+            .into_iter()
+            .find_map(|language| self.languages.get(language)?.get(key))
+    }
+
+    /// String of `key` in the current language or the fallback language. Missing keys are
+    /// logged once and returned as they are.
+    pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+        match self.lookup(key) {
+            Some(text) => text,
+            None => {
+                #[cfg(feature = "log")]
+                if self.missing.lock().insert(key.to_owned()) {
+                    warn!(
+                        "Missing translation of '{key}' for language '{}'",
+                        self.language
+                    );
+                }
+                key
+            }
+        }
+    }
+
+    /// Like [I18n::text], with every `{name}` replaced by the argument of the same name. `{{`
+    /// and `}}` are literal braces.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        interpolate(self.text(key), args)
+    }
+
+    pub fn has(&self, key: &str) -> bool {
+        self.lookup(key).is_some()
+    }
+}
+
+fn interpolate(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(['{', '}']) {
+        result.push_str(&rest[..start]);
+        let brace = rest.as_bytes()[start];
+        rest = &rest[start..];
+        if rest[1..].starts_with(brace as char) {
+            result.push(brace as char);
+            rest = &rest[2..];
+            continue;
+        }
+        match (brace, rest.find('}')) {
+            (b'{', Some(end)) => {
+                let name = &rest[1..end];
+                match args.iter().find(|(arg, _)| *arg == name) {
+                    Some((_, value)) => result.push_str(&value.to_string()),
+                    None => result.push_str(&rest[..=end]),
+                }
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                result.push(brace as char);
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn pseudo_localize(text: &str) -> String {
+    const ACCENTS: [(char, char); 20] = [
+        ('a', 'à'),
+        ('c', 'ç'),
+        ('e', 'é'),
+        ('i', 'î'),
+        ('n', 'ñ'),
+        ('o', 'ö'),
+        ('r', 'ŕ'),
+        ('s', 'ś'),
+        ('t', 'ţ'),
+        ('u', 'ü'),
+        ('y', 'ý'),
+        ('A', 'Å'),
+        ('C', 'Ç'),
+        ('E', 'É'),
+        ('I', 'Î'),
+        ('N', 'Ñ'),
+        ('O', 'Ö'),
+        ('S', 'Ś'),
+        ('T', 'Ţ'),
+        ('U', 'Ü'),
+    ];
+
+    let mut result = String::from("[");
+    let mut placeholder = false;
+    let mut letters = 0;
+    for char in text.chars() {
+        match char {
+            '{' => placeholder = true,
+            '}' => placeholder = false,
+            _ => (),
+        }
+        if placeholder || char == '}' {
+            result.push(char);
+            continue;
+        }
+        if char.is_alphanumeric() {
+            letters += 1;
+        }
+        let accented = ACCENTS
+            .iter()
+            .find(|(plain, _)| *plain == char)
+            .map_or(char, |(_, accented)| *accented);
+        result.push(accented);
+    }
+    // Translations are often about 40% longer than english text
+    let padding = (letters * 2).div_ceil(5);
+    if padding > 0 {
+        result.push(' ');
+        result.extend(std::iter::repeat('~').take(padding));
+    }
+    result.push(']');
+    result
+}
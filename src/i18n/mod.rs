@@ -0,0 +1,3 @@
+mod i18n;
+
+pub use i18n::*;
@@ -0,0 +1,109 @@
+use std::rc::Rc;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+
+use crate::context::Context;
+#[cfg(feature = "log")]
+use crate::log::warn;
+
+type ClipboardCallback = Box<dyn FnOnce(&mut Context) + Send + 'static>;
+
+/// Text clipboard of the system, accessible as `ctx.clipboard`. Browsers only allow reading the
+/// clipboard asynchronously, so on wasm [Clipboard::get_text] returns the last text set by the
+/// app and [Clipboard::get_text_async] has to be used for text copied elsewhere. Platforms
+/// without clipboard support only share text within the app.
+pub struct Clipboard {
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+    native: Option<arboard::Clipboard>,
+    text: Option<String>,
+    receiver: Rc<Receiver<ClipboardCallback>>,
+    sender: Sender<ClipboardCallback>,
+}
+
+impl Clipboard {
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = unbounded();
+        Self {
+            #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+            native: match arboard::Clipboard::new() {
+                Ok(clipboard) => Some(clipboard),
+                Err(_err) => {
+                    #[cfg(feature = "log")]
+                    warn!("Clipboard is not available: {_err}");
+                    None
+                }
+            },
+            text: None,
+            receiver: Rc::new(receiver),
+            sender,
+        }
+    }
+
+    pub fn get_text(&mut self) -> Option<String> {
+        #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+        if let Some(native) = &mut self.native {
+            return native.get_text().ok();
+        }
+        self.text.clone()
+    }
+
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        let text = text.into();
+
+        #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+        if let Some(native) = &mut self.native {
+            if let Err(_err) = native.set_text(text.clone()) {
+                #[cfg(feature = "log")]
+                warn!("Cannot write to the clipboard: {_err}");
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        if let Some(window) = web_sys::window() {
+            let promise = window.navigator().clipboard().write_text(&text);
+            wasm_bindgen_futures::spawn_local(async move {
+                let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+            });
+        }
+
+        self.text = Some(text);
+    }
+
+    /// Reads the clipboard and calls `callback` with the text before the next update systems.
+    /// Works on every platform, but is only required on wasm.
+    pub fn get_text_async(
+        &mut self,
+        callback: impl FnOnce(&mut Context, Option<String>) + Send + 'static,
+    ) {
+        let sender = self.sender.clone();
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let Some(window) = web_sys::window() else {
+                let text = self.text.clone();
+                let _ = sender.send(Box::new(move |ctx| (callback)(ctx, text)));
+                return;
+            };
+            let promise = window.navigator().clipboard().read_text();
+            let fallback = self.text.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let text = wasm_bindgen_futures::JsFuture::from(promise)
+                    .await
+                    .ok()
+                    .and_then(|text| text.as_string())
+                    .or(fallback);
+                let _ = sender.send(Box::new(move |ctx| (callback)(ctx, text)));
+            });
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let text = self.get_text();
+            let _ = sender.send(Box::new(move |ctx| (callback)(ctx, text)));
+        }
+    }
+
+    pub(crate) fn receiver(&self) -> Rc<Receiver<ClipboardCallback>> {
+        self.receiver.clone()
+    }
+}
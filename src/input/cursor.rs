@@ -0,0 +1,83 @@
+use std::{
+    cell::RefCell,
+    hash::{Hash, Hasher},
+    ops::Deref,
+};
+
+use rustc_hash::{FxHashMap, FxHasher};
+use winit::{
+    event_loop::ActiveEventLoop,
+    window::{CustomCursor, Window},
+};
+
+use crate::{graphics::SpriteBuilder, math::Vector2};
+
+pub use winit::window::CursorGrabMode;
+
+thread_local! {
+    // Cursors are created by the event loop, which only lives on the main thread
+    static CUSTOM_CURSORS: RefCell<FxHashMap<u64, CustomCursor>> = Default::default();
+}
+
+pub trait WindowExt {
+    /// Uses an RGBA sprite as hardware cursor with the hotspot in pixels from the top left.
+    /// Cursors are cached by their pixels and hotspot, so switching between a few cursors
+    /// only creates each of them once.
+    fn set_custom_cursor<D: Deref<Target = [u8]>>(
+        &self,
+        event_loop: &ActiveEventLoop,
+        sprite: SpriteBuilder<D>,
+        hotspot: Vector2<u16>,
+    );
+    /// Switches back to the default cursor of the system
+    fn reset_cursor(&self);
+}
+
+impl WindowExt for Window {
+    fn set_custom_cursor<D: Deref<Target = [u8]>>(
+        &self,
+        event_loop: &ActiveEventLoop,
+        sprite: SpriteBuilder<D>,
+        hotspot: Vector2<u16>,
+    ) {
+        assert!(
+            matches!(
+                sprite.format,
+                wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb
+            ),
+            "Cursors must be RGBA8 images!"
+        );
+        let size: Vector2<u16> = Vector2::new(
+            sprite.size.x.try_into().expect("Cursor is too large!"),
+            sprite.size.y.try_into().expect("Cursor is too large!"),
+        );
+
+        let mut hasher = FxHasher::default();
+        sprite.data.hash(&mut hasher);
+        size.hash(&mut hasher);
+        hotspot.hash(&mut hasher);
+        let key = hasher.finish();
+
+        let cursor = CUSTOM_CURSORS.with_borrow_mut(|cursors| {
+            cursors
+                .entry(key)
+                .or_insert_with(|| {
+                    let source = CustomCursor::from_rgba(
+                        sprite.data.to_vec(),
+                        size.x,
+                        size.y,
+                        hotspot.x,
+                        hotspot.y,
+                    )
+                    .expect("Invalid cursor image!");
+                    event_loop.create_custom_cursor(source)
+                })
+                .clone()
+        });
+        self.set_cursor(cursor);
+    }
+
+    fn reset_cursor(&self) {
+        self.set_cursor(winit::window::CursorIcon::Default);
+    }
+}
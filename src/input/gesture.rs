@@ -0,0 +1,122 @@
+use instant::{Duration, Instant};
+use rustc_hash::FxHashMap;
+
+use crate::math::{Point2, Vector2};
+
+/// Turns the raw screen touches into taps, pinches and two finger pans. All values are in
+/// pixels and only valid for the frame they were recognized in.
+pub struct GestureRecognizer {
+    /// Touches that move further than this amount of pixels are drags, not taps
+    pub tap_max_distance: f32,
+    /// Touches that last longer than this are not taps
+    pub tap_max_duration: Duration,
+    starts: FxHashMap<u64, (Point2<f32>, Instant, bool)>,
+    positions: FxHashMap<u64, Point2<f32>>,
+    pinch_delta: Option<f32>,
+    pan_delta: Option<Vector2<f32>>,
+    tap: Option<Point2<u32>>,
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GestureRecognizer {
+    pub const DEFAULT_TAP_MAX_DISTANCE: f32 = 20.0;
+    pub const DEFAULT_TAP_MAX_DURATION: Duration = Duration::from_millis(300);
+
+    pub fn new() -> Self {
+        Self {
+            tap_max_distance: Self::DEFAULT_TAP_MAX_DISTANCE,
+            tap_max_duration: Self::DEFAULT_TAP_MAX_DURATION,
+            starts: Default::default(),
+            positions: Default::default(),
+            pinch_delta: None,
+            pan_delta: None,
+            tap: None,
+        }
+    }
+
+    pub(crate) fn start(&mut self, id: u64, position: Point2<u32>) {
+        let position = position.cast::<f32>();
+        self.starts.insert(id, (position, Instant::now(), true));
+        self.positions.insert(id, position);
+        // A second finger turns a potential tap into a gesture
+        if self.positions.len() > 1 {
+            self.starts.values_mut().for_each(|start| start.2 = false);
+        }
+    }
+
+    pub(crate) fn moved(&mut self, id: u64, position: Point2<u32>) {
+        let position = position.cast::<f32>();
+        let before = self.two_finger_state();
+        if let Some(old) = self.positions.get_mut(&id) {
+            *old = position;
+        }
+        if let Some((start, _, tap)) = self.starts.get_mut(&id) {
+            if (position - *start).norm() > self.tap_max_distance {
+                *tap = false;
+            }
+        }
+
+        if let (Some((distance_before, center_before)), Some((distance, center))) =
+            (before, self.two_finger_state())
+        {
+            if distance_before > f32::EPSILON {
+                let scale = distance / distance_before;
+                self.pinch_delta = Some(self.pinch_delta.unwrap_or(1.0) * scale);
+            }
+            self.pan_delta = Some(self.pan_delta.unwrap_or_default() + (center - center_before));
+        }
+    }
+
+    pub(crate) fn end(&mut self, id: u64, position: Point2<u32>) {
+        self.positions.remove(&id);
+        if let Some((start, time, tap)) = self.starts.remove(&id) {
+            if tap
+                && time.elapsed() <= self.tap_max_duration
+                && (position.cast::<f32>() - start).norm() <= self.tap_max_distance
+            {
+                self.tap = Some(position);
+            }
+        }
+    }
+
+    pub(crate) fn cancel(&mut self, id: u64) {
+        self.positions.remove(&id);
+        self.starts.remove(&id);
+    }
+
+    pub(crate) fn update(&mut self) {
+        self.pinch_delta = None;
+        self.pan_delta = None;
+        self.tap = None;
+    }
+
+    fn two_finger_state(&self) -> Option<(f32, Point2<f32>)> {
+        if self.positions.len() != 2 {
+            return None;
+        }
+        let mut positions = self.positions.values();
+        let a = positions.next()?;
+        let b = positions.next()?;
+        Some(((b - a).norm(), Point2::from((a.coords + b.coords) / 2.0)))
+    }
+
+    /// Factor by which the distance between two fingers changed this frame
+    pub fn pinch_delta(&self) -> Option<f32> {
+        self.pinch_delta
+    }
+
+    /// Movement of the center of two fingers this frame
+    pub fn pan_delta(&self) -> Option<Vector2<f32>> {
+        self.pan_delta
+    }
+
+    /// Position of a short touch that barely moved and ended this frame
+    pub fn tap(&self) -> Option<Point2<u32>> {
+        self.tap
+    }
+}
@@ -2,12 +2,14 @@
 use crate::log::info;
 use crate::{
     graphics::Camera2D,
+    input::{AxisBinding, CursorGrabMode, GestureRecognizer, InputMap},
     math::{Point2, Vector2},
 };
 #[cfg(feature = "gamepad")]
 use gilrs::*;
 use instant::{Duration, Instant};
 use rustc_hash::FxHashMap;
+use std::{cell::Cell, path::PathBuf};
 use winit::{event::*, keyboard::SmolStr};
 
 pub use winit::{
@@ -49,6 +51,14 @@ impl From<GamepadButton> for InputTrigger {
     }
 }
 
+/// A [Button] on its own triggers for every connected gamepad
+#[cfg(feature = "gamepad")]
+impl From<Button> for InputTrigger {
+    fn from(b: Button) -> Self {
+        Self::AnyGamepadButton(b)
+    }
+}
+
 impl From<Key> for InputTrigger {
     fn from(k: Key) -> Self {
         Self::Key(k)
@@ -76,6 +86,8 @@ pub enum InputTrigger {
     ScreenTouch(ScreenTouch),
     #[cfg(feature = "gamepad")]
     GamepadButton(GamepadButton),
+    #[cfg(feature = "gamepad")]
+    AnyGamepadButton(Button),
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
@@ -161,8 +173,21 @@ pub struct Input {
     modifiers: Modifiers,
     wheel_delta: f32,
     window_size: Vector2<f32>,
+    map: InputMap,
+    keyboard_suppressed: bool,
+    gestures: GestureRecognizer,
+    mouse_delta: Vector2<f32>,
+    dropped_files: Vec<PathBuf>,
+    hovered_files: Vec<PathBuf>,
+    focused: bool,
+    cursor_grab: Cell<CursorGrabMode>,
+    /// Mode that the window actually uses, [None] if the grab has to be (re-)acquired
+    applied_grab: Cell<Option<CursorGrabMode>>,
+    #[cfg(multi_window)]
+    window_cursors: FxHashMap<winit::window::WindowId, Point2<u32>>,
+    /// [None] if gamepads are not supported on this platform
     #[cfg(feature = "gamepad")]
-    game_pad_manager: Gilrs,
+    game_pad_manager: Option<Gilrs>,
     #[cfg(feature = "gamepad")]
     active_gamepad: Option<GamepadId>,
     #[cfg(feature = "gamepad")]
@@ -182,13 +207,27 @@ impl Input {
             last_keys: Default::default(),
             wheel_delta: 0.0,
             window_size,
+            map: InputMap::new(),
+            keyboard_suppressed: false,
+            gestures: GestureRecognizer::new(),
+            mouse_delta: Vector2::zeros(),
+            dropped_files: Vec::new(),
+            hovered_files: Vec::new(),
+            focused: true,
+            cursor_grab: Cell::new(CursorGrabMode::None),
+            applied_grab: Cell::new(Some(CursorGrabMode::None)),
+            #[cfg(multi_window)]
+            window_cursors: Default::default(),
             #[cfg(feature = "gamepad")]
             game_pad_manager: match Gilrs::new() {
-                Ok(ok) => ok,
+                Ok(ok) => Some(ok),
                 Err(err) => match err {
-                    Error::NotImplemented(gilrs) => gilrs,
-                    Error::InvalidAxisToBtn => panic!("Gamepad Error: Invalid Axis To Button!"),
-                    Error::Other(err) => panic!("Gamepad Error: {}", err),
+                    Error::NotImplemented(gilrs) => Some(gilrs),
+                    Error::InvalidAxisToBtn | Error::Other(_) => {
+                        #[cfg(feature = "log")]
+                        crate::log::error!("Gamepads are not available: {err}");
+                        None
+                    }
                 },
             },
             #[cfg(feature = "gamepad")]
@@ -198,6 +237,36 @@ impl Input {
         }
     }
 
+    /// Input where nothing is pressed, given to scenes that are updated underneath an overlay
+    pub(crate) fn idle(&self) -> Self {
+        Self {
+            cursor_raw: Point2::new(0, 0),
+            touches: Default::default(),
+            events: Default::default(),
+            modifiers: Default::default(),
+            last_keys: Default::default(),
+            wheel_delta: 0.0,
+            window_size: self.window_size,
+            map: self.map.clone(),
+            keyboard_suppressed: true,
+            gestures: GestureRecognizer::new(),
+            mouse_delta: Vector2::zeros(),
+            dropped_files: Vec::new(),
+            hovered_files: Vec::new(),
+            focused: self.focused,
+            cursor_grab: self.cursor_grab.clone(),
+            applied_grab: self.applied_grab.clone(),
+            #[cfg(multi_window)]
+            window_cursors: Default::default(),
+            #[cfg(feature = "gamepad")]
+            game_pad_manager: None,
+            #[cfg(feature = "gamepad")]
+            active_gamepad: None,
+            #[cfg(feature = "gamepad")]
+            dead_zone: self.dead_zone,
+        }
+    }
+
     pub(crate) fn resize(&mut self, window_size: Vector2<u32>) {
         self.window_size = window_size.cast()
     }
@@ -214,12 +283,18 @@ impl Input {
                     TouchPhase::Started => {
                         let trigger = ScreenTouch.into();
                         self.touches.insert(touch.id, pos);
+                        self.gestures.start(touch.id, pos);
                         self.events
                             .insert(trigger, InputEvent::new(None, trigger, 1.0));
                     }
                     TouchPhase::Ended | TouchPhase::Cancelled => {
                         let trigger = ScreenTouch.into();
                         self.touches.remove(&touch.id);
+                        if touch.phase == TouchPhase::Ended {
+                            self.gestures.end(touch.id, pos);
+                        } else {
+                            self.gestures.cancel(touch.id);
+                        }
                         if let Some(event) = self.events.get_mut(&trigger) {
                             event.state = InputEventState::JustReleased;
                         }
@@ -228,6 +303,7 @@ impl Input {
                         if let Some(touch) = self.touches.get_mut(&touch.id) {
                             *touch = pos;
                         }
+                        self.gestures.moved(touch.id, pos);
                     }
                 }
             }
@@ -254,6 +330,12 @@ impl Input {
                 let trigger = (*button).into();
                 match state {
                     ElementState::Pressed => {
+                        // Browsers only allow pointer locks after a click
+                        if self.cursor_grab.get() != CursorGrabMode::None
+                            && self.applied_grab.get() == Some(CursorGrabMode::None)
+                        {
+                            self.applied_grab.set(None);
+                        }
                         let display = match button {
                             MouseButton::Left => "MouseLeft".to_string(),
                             MouseButton::Right => "MouseRight".to_string(),
@@ -285,12 +367,93 @@ impl Input {
             WindowEvent::ModifiersChanged(state) => {
                 self.modifiers = *state;
             }
+            WindowEvent::HoveredFile(path) => {
+                self.hovered_files.push(path.clone());
+            }
+            WindowEvent::HoveredFileCancelled => {
+                self.hovered_files.clear();
+            }
+            WindowEvent::DroppedFile(path) => {
+                self.hovered_files.clear();
+                self.dropped_files.push(path.clone());
+            }
+            WindowEvent::Focused(focused) => {
+                self.focused = *focused;
+                // Most platforms release the grab when the window loses focus
+                self.applied_grab.set(None);
+            }
             _ => {}
         }
     }
 
+    pub(crate) fn on_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta } = event {
+            if self.focused {
+                self.mouse_delta += Vector2::new(delta.0 as f32, delta.1 as f32);
+            }
+        }
+    }
+
+    /// Grabs the cursor of the main window once it is focused. [CursorGrabMode::Locked] falls
+    /// back to a confined, centered cursor on platforms without pointer locks (Windows, X11)
+    /// and [CursorGrabMode::Confined] to a locked cursor on macOS.
+    pub(crate) fn apply_cursor_grab(&self, window: &winit::window::Window) {
+        if !self.focused {
+            return;
+        }
+        if self.applied_grab.get().is_none() {
+            let mode = self.cursor_grab.get();
+            let fallback = match mode {
+                CursorGrabMode::None => CursorGrabMode::None,
+                CursorGrabMode::Confined => CursorGrabMode::Locked,
+                CursorGrabMode::Locked => CursorGrabMode::Confined,
+            };
+            let applied = match window.set_cursor_grab(mode) {
+                Ok(()) => Some(mode),
+                Err(_) => match window.set_cursor_grab(fallback) {
+                    Ok(()) => Some(fallback),
+                    Err(_err) => {
+                        #[cfg(feature = "log")]
+                        crate::log::warn!("Cannot grab the cursor: {_err}");
+                        Some(CursorGrabMode::None)
+                    }
+                },
+            };
+            window.set_cursor_visible(mode != CursorGrabMode::Locked);
+            self.applied_grab.set(applied);
+        }
+        if self.cursor_grab.get() == CursorGrabMode::Locked
+            && self.applied_grab.get() == Some(CursorGrabMode::Confined)
+        {
+            let size = window.inner_size();
+            let center = winit::dpi::PhysicalPosition::new(size.width / 2, size.height / 2);
+            let _ = window.set_cursor_position(center);
+        }
+    }
+
+    /// Events of secondary windows. The cursor is tracked per window, everything else is shared
+    /// with the main window.
+    #[cfg(multi_window)]
+    pub(crate) fn on_window_event(&mut self, window: winit::window::WindowId, event: &WindowEvent) {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.window_cursors
+                    .insert(window, Point2::new(position.x as u32, position.y as u32));
+            }
+            WindowEvent::CursorLeft { .. } | WindowEvent::Destroyed => {
+                self.window_cursors.remove(&window);
+            }
+            // The main window gets its own focus events
+            WindowEvent::Focused(_) => {}
+            _ => self.on_event(event),
+        }
+    }
+
     pub(crate) fn update(&mut self) {
         self.wheel_delta = 0.0;
+        self.mouse_delta = Vector2::zeros();
+        self.dropped_files.clear();
+        self.gestures.update();
         self.last_keys.clear();
         self.events
             .retain(|_, event| event.state != InputEventState::JustReleased);
@@ -299,6 +462,84 @@ impl Input {
         }
     }
 
+    /// Trigger that was pressed this frame, e.g. to rebind an action
+    pub fn last_input_event(&self) -> Option<(&InputTrigger, &InputEvent)> {
+        self.events.iter().find(|(_, event)| event.is_pressed())
+    }
+
+    pub fn map(&self) -> &InputMap {
+        &self.map
+    }
+
+    pub fn map_mut(&mut self) -> &mut InputMap {
+        &mut self.map
+    }
+
+    pub fn set_map(&mut self, map: InputMap) {
+        self.map = map;
+    }
+
+    /// Keyboard triggers are ignored by the actions and axes while suppressed. This is set
+    /// every frame when the gui wants keyboard input, e.g. while a text field has focus.
+    pub fn set_keyboard_suppressed(&mut self, suppressed: bool) {
+        self.keyboard_suppressed = suppressed;
+    }
+
+    pub fn keyboard_suppressed(&self) -> bool {
+        self.keyboard_suppressed
+    }
+
+    fn action_event(&self, trigger: &InputTrigger) -> Option<&InputEvent> {
+        if self.keyboard_suppressed && matches!(trigger, InputTrigger::Key(_)) {
+            return None;
+        }
+        self.events.get(trigger)
+    }
+
+    pub fn action_pressed(&self, action: &str) -> bool {
+        self.map
+            .bindings(action)
+            .iter()
+            .any(|trigger| self.action_event(trigger).is_some_and(|e| e.is_pressed()))
+    }
+
+    pub fn action_held(&self, action: &str) -> bool {
+        self.map
+            .bindings(action)
+            .iter()
+            .any(|trigger| self.action_event(trigger).is_some_and(|e| e.is_held()))
+    }
+
+    pub fn action_just_released(&self, action: &str) -> bool {
+        self.map.bindings(action).iter().any(|trigger| {
+            self.action_event(trigger)
+                .is_some_and(|e| e.is_just_released())
+        })
+    }
+
+    /// Combined value of all bindings of an axis, clamped between -1 and 1
+    pub fn action_axis(&self, axis: &str) -> f32 {
+        let pressure = |trigger: &InputTrigger| match self.action_event(trigger) {
+            Some(event) if !event.is_just_released() => event.pressure(),
+            _ => 0.0,
+        };
+        self.map
+            .axis_bindings(axis)
+            .iter()
+            .map(|binding| match binding {
+                AxisBinding::Triggers { negative, positive } => {
+                    pressure(positive) - pressure(negative)
+                }
+                #[cfg(feature = "gamepad")]
+                AxisBinding::GamepadAxis(axis) => self
+                    .active_gamepad
+                    .map(|gamepad| self.gamepad_axis(gamepad, *axis))
+                    .unwrap_or(0.0),
+            })
+            .sum::<f32>()
+            .clamp(-1.0, 1.0)
+    }
+
     pub fn last_keys(&self) -> &Vec<Key> {
         &self.last_keys
     }
@@ -382,51 +623,32 @@ impl Input {
     #[cfg(feature = "gamepad")]
     // Syncs the gamepad inputs to the inputs of the gamepad. This is automatically done once every update cycle.
     pub fn sync_gamepad(&mut self) {
-        while let Some(event) = self.game_pad_manager.next_event() {
+        let Some(game_pad_manager) = &mut self.game_pad_manager else {
+            return;
+        };
+        while let Some(event) = game_pad_manager.next_event() {
             let gamepad = event.id;
             self.active_gamepad = Some(gamepad);
             match event.event {
                 EventType::ButtonPressed(button, _) => {
-                    let trigger = GamepadButton { gamepad, button };
-                    self.events.insert(
-                        trigger.into(),
-                        InputEvent::new(
-                            Some(SmolStr::new(format!("{button:?}"))),
-                            trigger.into(),
-                            1.0,
-                        ),
-                    );
+                    Self::press_gamepad_button(&mut self.events, gamepad, button, 1.0);
                 }
                 EventType::ButtonChanged(button, pressure, _) => {
-                    let trigger = GamepadButton { gamepad, button };
                     if pressure == 0.0 {
-                        if let Some(event) = self.events.get_mut(&trigger.into()) {
-                            event.state = InputEventState::JustReleased;
-                        }
+                        Self::release_gamepad_button(&mut self.events, gamepad, button);
                     } else {
-                        self.events.insert(
-                            trigger.into(),
-                            InputEvent::new(
-                                Some(SmolStr::new(format!("{button:?}"))),
-                                trigger.into(),
-                                pressure,
-                            ),
-                        );
+                        Self::press_gamepad_button(&mut self.events, gamepad, button, pressure);
                     }
                 }
                 EventType::ButtonReleased(button, _) => {
-                    let trigger = GamepadButton { gamepad, button };
-                    if let Some(event) = self.events.get_mut(&trigger.into()) {
-                        event.state = InputEventState::JustReleased;
-                    }
+                    Self::release_gamepad_button(&mut self.events, gamepad, button);
                 }
                 EventType::AxisChanged(_, _, _) => {}
                 EventType::ButtonRepeated(_, _) => {}
                 EventType::Dropped => {}
                 EventType::Connected => {
                     #[cfg(feature = "log")]
-                    {
-                        let gamepad_ref = self.gamepad(gamepad).unwrap();
+                    if let Some(gamepad_ref) = game_pad_manager.connected_gamepad(gamepad) {
                         info!(
                             "Connected gamepad: {} with power {:?} and id {}",
                             gamepad_ref.name(),
@@ -440,21 +662,83 @@ impl Input {
                     {
                         info!("Dropped gamepad: {}", gamepad);
                     }
-                    self.events.retain(|trigger, _| match trigger {
-                        InputTrigger::GamepadButton(c) => c.gamepad != gamepad,
-                        _ => true,
-                    })
+                    for (trigger, event) in self.events.iter_mut() {
+                        if let InputTrigger::GamepadButton(c) = trigger {
+                            if c.gamepad == gamepad {
+                                event.state = InputEventState::JustReleased;
+                            }
+                        }
+                    }
+                    Self::sync_any_gamepad_buttons(&mut self.events);
                 }
             }
         }
         if let Some(active) = self.active_gamepad {
-            let exists = self.game_pad_manager.gamepad(active).is_connected();
-            if !exists {
+            if game_pad_manager.connected_gamepad(active).is_none() {
                 self.active_gamepad = None;
             }
         }
     }
 
+    #[cfg(feature = "gamepad")]
+    fn press_gamepad_button(
+        events: &mut FxHashMap<InputTrigger, InputEvent>,
+        gamepad: GamepadId,
+        button: Button,
+        pressure: f32,
+    ) {
+        let display = SmolStr::new(format!("{button:?}"));
+        for trigger in [
+            GamepadButton { gamepad, button }.into(),
+            InputTrigger::AnyGamepadButton(button),
+        ] {
+            match events.get_mut(&trigger) {
+                Some(event) if event.state != InputEventState::JustReleased => {
+                    event.pressure = pressure;
+                }
+                _ => {
+                    events.insert(
+                        trigger,
+                        InputEvent::new(Some(display.clone()), trigger, pressure),
+                    );
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn release_gamepad_button(
+        events: &mut FxHashMap<InputTrigger, InputEvent>,
+        gamepad: GamepadId,
+        button: Button,
+    ) {
+        if let Some(event) = events.get_mut(&GamepadButton { gamepad, button }.into()) {
+            event.state = InputEventState::JustReleased;
+        }
+        Self::sync_any_gamepad_buttons(events);
+    }
+
+    /// Releases the buttons of any gamepad that are not held on a single gamepad anymore
+    #[cfg(feature = "gamepad")]
+    fn sync_any_gamepad_buttons(events: &mut FxHashMap<InputTrigger, InputEvent>) {
+        let held = events
+            .iter()
+            .filter_map(|(trigger, event)| match trigger {
+                InputTrigger::GamepadButton(c) if event.state != InputEventState::JustReleased => {
+                    Some(c.button)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        for (trigger, event) in events.iter_mut() {
+            if let InputTrigger::AnyGamepadButton(button) = trigger {
+                if !held.contains(button) {
+                    event.state = InputEventState::JustReleased;
+                }
+            }
+        }
+    }
+
     #[cfg(feature = "gamepad")]
     pub fn gamepad_stick_deadzone(
         &self,
@@ -496,6 +780,25 @@ impl Input {
         Self::gamepad_stick_deadzone(self, gamepad_id, stick, self.dead_zone)
     }
 
+    /// Value of a single axis between -1 and 1, values inside the dead zone are 0
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_axis_deadzone(&self, gamepad_id: GamepadId, axis: Axis, dead_zone: f32) -> f32 {
+        let value = self
+            .gamepad(gamepad_id)
+            .and_then(|gamepad| gamepad.axis_data(axis).map(|a| a.value()))
+            .unwrap_or(0.0);
+        if value.abs() >= dead_zone {
+            value
+        } else {
+            0.0
+        }
+    }
+
+    #[cfg(feature = "gamepad")]
+    pub fn gamepad_axis(&self, gamepad_id: GamepadId, axis: Axis) -> f32 {
+        self.gamepad_axis_deadzone(gamepad_id, axis, self.dead_zone)
+    }
+
     #[cfg(feature = "gamepad")]
     pub fn dead_zone(&self) -> f32 {
         self.dead_zone
@@ -513,23 +816,68 @@ impl Input {
 
     #[cfg(feature = "gamepad")]
     pub fn first_gamepad(&self) -> Option<(GamepadId, Gamepad)> {
-        return self.game_pad_manager.gamepads().next();
+        self.game_pad_manager.as_ref()?.gamepads().next()
     }
 
+    /// Ids of all connected gamepads
     #[cfg(feature = "gamepad")]
-    pub fn gamepads(&self) -> ConnectedGamepadsIterator {
-        return self.game_pad_manager.gamepads();
+    pub fn gamepads(&self) -> impl Iterator<Item = GamepadId> + '_ {
+        self.game_pad_manager
+            .iter()
+            .flat_map(|game_pad_manager| game_pad_manager.gamepads())
+            .map(|(id, _)| id)
     }
 
     #[cfg(feature = "gamepad")]
     pub fn gamepad(&self, gamepad_id: GamepadId) -> Option<Gamepad> {
-        return self.game_pad_manager.connected_gamepad(gamepad_id);
+        self.game_pad_manager
+            .as_ref()?
+            .connected_gamepad(gamepad_id)
     }
 
     pub const fn modifiers(&self) -> Modifiers {
         self.modifiers
     }
 
+    /// Grabs the cursor of the main window, e.g. [CursorGrabMode::Locked] for mouse look with
+    /// [Input::mouse_delta]. The cursor is hidden while it is locked and the grab is acquired
+    /// again when the window regains focus.
+    pub fn set_cursor_grab(&self, mode: CursorGrabMode) {
+        if self.cursor_grab.get() != mode {
+            self.cursor_grab.set(mode);
+            self.applied_grab.set(None);
+        }
+    }
+
+    pub fn cursor_grab(&self) -> CursorGrabMode {
+        self.cursor_grab.get()
+    }
+
+    /// Relative mouse movement of this frame in device units with y pointing down. Unlike the
+    /// cursor position it keeps changing when the cursor is grabbed or at the edge of the
+    /// screen. Zero while the window is not focused.
+    pub fn mouse_delta(&self) -> Vector2<f32> {
+        self.mouse_delta
+    }
+
+    /// Files that were dropped on a window this frame. Not supported on wasm.
+    pub fn dropped_files(&self) -> &[PathBuf] {
+        &self.dropped_files
+    }
+
+    /// Files that are currently dragged over a window
+    pub fn hovered_files(&self) -> &[PathBuf] {
+        &self.hovered_files
+    }
+
+    pub fn is_file_hovered(&self) -> bool {
+        !self.hovered_files.is_empty()
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
     pub const fn wheel_delta(&self) -> f32 {
         self.wheel_delta
     }
@@ -542,6 +890,12 @@ impl Input {
         self.touches.iter()
     }
 
+    /// Cursor position in pixels inside of a secondary window, [None] if the cursor is outside
+    #[cfg(multi_window)]
+    pub fn window_cursor_raw(&self, window: winit::window::WindowId) -> Option<Point2<u32>> {
+        self.window_cursors.get(&window).copied()
+    }
+
     pub fn cursor_from_pixel(&self, cursor: Point2<u32>, camera: &Camera2D) -> Point2<f32> {
         let fov = camera.fov() * 2.0;
         let camera_translation = camera.translation();
@@ -554,6 +908,37 @@ impl Input {
         .into()
     }
 
+    pub fn gestures(&self) -> &GestureRecognizer {
+        &self.gestures
+    }
+
+    pub fn gestures_mut(&mut self) -> &mut GestureRecognizer {
+        &mut self.gestures
+    }
+
+    pub fn pinch_delta(&self) -> Option<f32> {
+        self.gestures.pinch_delta()
+    }
+
+    /// Two finger pan of this frame converted to world units
+    pub fn pan_delta(&self, camera: &Camera2D) -> Option<Vector2<f32>> {
+        let fov = camera.fov() * 2.0;
+        self.gestures.pan_delta().map(|delta| {
+            Vector2::new(
+                delta.x / self.window_size.x * fov.x,
+                -delta.y / self.window_size.y * fov.y,
+            )
+        })
+    }
+
+    /// World position of a tap that ended this frame. Touches that move or last too long are
+    /// drags and don't count as taps.
+    pub fn tap(&self, camera: &Camera2D) -> Option<Point2<f32>> {
+        self.gestures
+            .tap()
+            .map(|tap| self.cursor_from_pixel(tap, camera))
+    }
+
     pub fn cursor(&self, camera: &Camera2D) -> Point2<f32> {
         self.cursor_from_pixel(self.cursor_raw, camera)
     }
@@ -574,6 +959,8 @@ impl Input {
         name: Option<&str>,
     ) -> Result<String, MappingError> {
         self.game_pad_manager
+            .as_mut()
+            .ok_or(MappingError::NotConnected)?
             .set_mapping(gamepad_id.into(), mapping, name)
     }
 
@@ -585,6 +972,8 @@ impl Input {
         name: Option<&str>,
     ) -> Result<String, MappingError> {
         self.game_pad_manager
+            .as_mut()
+            .ok_or(MappingError::NotConnected)?
             .set_mapping_strict(gamepad_id.into(), mapping, name)
     }
 }
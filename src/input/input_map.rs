@@ -0,0 +1,100 @@
+use rustc_hash::FxHashMap;
+
+#[cfg(feature = "gamepad")]
+use crate::input::Axis;
+use crate::input::InputTrigger;
+
+/// Source of an axis value between -1 and 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum AxisBinding {
+    Triggers {
+        negative: InputTrigger,
+        positive: InputTrigger,
+    },
+    /// Axis of the active gamepad
+    #[cfg(feature = "gamepad")]
+    GamepadAxis(Axis),
+}
+
+impl AxisBinding {
+    pub fn triggers(negative: impl Into<InputTrigger>, positive: impl Into<InputTrigger>) -> Self {
+        Self::Triggers {
+            negative: negative.into(),
+            positive: positive.into(),
+        }
+    }
+}
+
+/// Named actions and axes bound to any amount of [InputTrigger]s, queried through
+/// [Input::action_pressed](crate::input::Input::action_pressed) and friends. The same trigger
+/// can be bound to multiple actions.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct InputMap {
+    actions: FxHashMap<String, Vec<InputTrigger>>,
+    axes: FxHashMap<String, Vec<AxisBinding>>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the bindings of an action
+    pub fn bind(&mut self, action: &str, triggers: &[InputTrigger]) -> &mut Self {
+        self.actions.insert(action.to_string(), triggers.to_vec());
+        self
+    }
+
+    /// Adds a binding to an action without removing the existing ones
+    pub fn add_binding(&mut self, action: &str, trigger: impl Into<InputTrigger>) -> &mut Self {
+        let trigger = trigger.into();
+        let triggers = self.actions.entry(action.to_string()).or_default();
+        if !triggers.contains(&trigger) {
+            triggers.push(trigger);
+        }
+        self
+    }
+
+    pub fn unbind(&mut self, action: &str) -> Option<Vec<InputTrigger>> {
+        self.actions.remove(action)
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, bindings: &[AxisBinding]) -> &mut Self {
+        self.axes.insert(axis.to_string(), bindings.to_vec());
+        self
+    }
+
+    pub fn unbind_axis(&mut self, axis: &str) -> Option<Vec<AxisBinding>> {
+        self.axes.remove(axis)
+    }
+
+    pub fn bindings(&self, action: &str) -> &[InputTrigger] {
+        self.actions.get(action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// All actions the trigger is bound to
+    pub fn actions_of(&self, trigger: InputTrigger) -> impl Iterator<Item = &str> {
+        self.actions
+            .iter()
+            .filter(move |(_, triggers)| triggers.contains(&trigger))
+            .map(|(action, _)| action.as_str())
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = (&str, &[InputTrigger])> {
+        self.actions
+            .iter()
+            .map(|(action, triggers)| (action.as_str(), triggers.as_slice()))
+    }
+
+    pub fn axes(&self) -> impl Iterator<Item = (&str, &[AxisBinding])> {
+        self.axes
+            .iter()
+            .map(|(axis, bindings)| (axis.as_str(), bindings.as_slice()))
+    }
+}
@@ -1,8 +1,16 @@
+mod clipboard;
+mod cursor;
+mod gesture;
 mod input;
+mod input_map;
 
 #[cfg(feature = "gamepad")]
 pub use gilrs::{
     ev, ff, Axis, Button, ConnectedGamepadsIterator, Gamepad, GamepadId, Mapping, MappingError,
     MappingSource, PowerInfo,
 };
+pub use clipboard::*;
+pub use cursor::*;
+pub use gesture::*;
 pub use input::*;
+pub use input_map::*;
@@ -1,10 +1,14 @@
 use anyhow::Result;
 use downcast_rs::{impl_downcast, Downcast};
 
-use std::{env, fs, path::PathBuf};
+use std::{
+    env, fs,
+    io::{Read, Seek},
+    path::PathBuf,
+};
 
 #[cfg(target_os = "android")]
-use std::{ffi::CString, io::Read};
+use std::ffi::CString;
 
 #[macro_export]
 macro_rules! include_resource_bytes {
@@ -27,10 +31,28 @@ macro_rules! include_resource_wgsl {
     };
 }
 
+pub trait ResourceReader: Read + Seek + Send + Sync {}
+impl<R: Read + Seek + Send + Sync> ResourceReader for R {}
+
 #[async_trait::async_trait(?Send)]
 pub trait ResourceLoader: Send + Sync + Downcast {
     fn load_bytes(&self, path: &str) -> Result<Vec<u8>>;
     fn load_string(&self, path: &str) -> Result<String>;
+    /// Reader for streaming a resource. Loads the whole resource into memory unless the
+    /// loader overrides it.
+    fn open(&self, path: &str) -> Result<Box<dyn ResourceReader>> {
+        Ok(Box::new(std::io::Cursor::new(self.load_bytes(path)?)))
+    }
+    /// Last modification of a resource, [None] if the loader can't tell. Used for hot reloading.
+    fn modified(&self, _path: &str) -> Option<std::time::SystemTime> {
+        None
+    }
+    /// Paths of the files in a resource directory, relative to the resource root
+    fn list(&self, dir: &str) -> Result<Vec<String>> {
+        Err(anyhow::anyhow!(
+            "Cannot list {dir} with this resource loader"
+        ))
+    }
     async fn async_load_bytes(&self, path: &str) -> Result<Vec<u8>> {
         self.load_bytes(path)
     }
@@ -82,6 +104,28 @@ impl ResourceLoader for NativeResourceLoader {
         let data = std::fs::read_to_string(path)?;
         Ok(data)
     }
+
+    fn open(&self, path: &str) -> Result<Box<dyn ResourceReader>> {
+        let file = fs::File::open(self.resource_path(path))?;
+        Ok(Box::new(std::io::BufReader::new(file)))
+    }
+
+    fn modified(&self, path: &str) -> Option<std::time::SystemTime> {
+        fs::metadata(self.resource_path(path)).ok()?.modified().ok()
+    }
+
+    fn list(&self, dir: &str) -> Result<Vec<String>> {
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(self.resource_path(dir))? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                paths.push(format!("{}/{name}", dir.trim_end_matches('/')));
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
 }
 
 #[non_exhaustive]
@@ -189,6 +233,79 @@ impl ResourceLoader for WebResourceLoader {
     }
 }
 
+/// Stores data hex encoded in the local storage of the browser
+#[cfg(target_arch = "wasm32")]
+#[non_exhaustive]
+pub struct WebStorageLoader;
+
+#[cfg(target_arch = "wasm32")]
+impl WebStorageLoader {
+    const PREFIX: &'static str = "shura/";
+
+    fn storage(&self) -> Result<web_sys::Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| anyhow::anyhow!("Local storage is not available"))
+    }
+
+    fn key(path: &str) -> String {
+        format!("{}{path}", Self::PREFIX)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl StorageLoader for WebStorageLoader {
+    fn store(&self, path: &str, data: &dyn AsRef<[u8]>) -> Result<()> {
+        let encoded: String = data
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        self.storage()?
+            .set_item(&Self::key(path), &encoded)
+            .map_err(|_| anyhow::anyhow!("Cannot store {path}"))
+    }
+
+    fn load_string(&self, path: &str) -> Result<String> {
+        Ok(String::from_utf8(self.load_bytes(path)?)?)
+    }
+
+    fn load_bytes(&self, path: &str) -> Result<Vec<u8>> {
+        let encoded = self
+            .storage()?
+            .get_item(&Self::key(path))
+            .ok()
+            .flatten()
+            .ok_or_else(|| anyhow::anyhow!("{path} does not exist"))?;
+        (0..encoded.len())
+            .step_by(2)
+            .map(|i| {
+                encoded
+                    .get(i..i + 2)
+                    .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                    .ok_or_else(|| anyhow::anyhow!("{path} is not hex encoded"))
+            })
+            .collect()
+    }
+
+    fn delete(&self, path: &str) -> Result<()> {
+        self.storage()?
+            .remove_item(&Self::key(path))
+            .map_err(|_| anyhow::anyhow!("Cannot delete {path}"))
+    }
+
+    fn list(&self) -> Vec<String> {
+        let Ok(storage) = self.storage() else {
+            return Vec::new();
+        };
+        let len = storage.length().unwrap_or(0);
+        (0..len)
+            .filter_map(|i| storage.key(i).ok().flatten())
+            .filter_map(|key| key.strip_prefix(Self::PREFIX).map(str::to_owned))
+            .collect()
+    }
+}
+
 #[non_exhaustive]
 pub struct UnimplementedStorageLoader;
 
@@ -247,4 +364,38 @@ impl ResourceLoader for AndroidResourceLoader {
         asset.read_to_string(&mut data).unwrap();
         return Ok(data);
     }
+
+    fn open(&self, path: &str) -> Result<Box<dyn ResourceReader>> {
+        let path = CString::new(path)?;
+        let asset = self
+            .manager
+            .open(&path)
+            .ok_or_else(|| anyhow::anyhow!("Cannot open asset {path:?}"))?;
+        Ok(Box::new(AndroidAsset(asset)))
+    }
+}
+
+/// Streams an asset instead of loading it into memory
+#[cfg(target_os = "android")]
+struct AndroidAsset(ndk::asset::Asset);
+
+// SAFETY: An AAsset may be used from any thread as long as it is not used from two threads at
+// the same time, which `&mut self` of Read and Seek already guarantees.
+#[cfg(target_os = "android")]
+unsafe impl Send for AndroidAsset {}
+#[cfg(target_os = "android")]
+unsafe impl Sync for AndroidAsset {}
+
+#[cfg(target_os = "android")]
+impl Read for AndroidAsset {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+#[cfg(target_os = "android")]
+impl Seek for AndroidAsset {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.0.seek(pos)
+    }
 }
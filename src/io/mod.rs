@@ -1,4 +1,8 @@
 mod io;
+#[cfg(feature = "serde")]
+mod save;
 
 pub use crate::{include_resource_bytes, include_resource_str, include_resource_wgsl};
 pub use io::*;
+#[cfg(feature = "serde")]
+pub use save::*;
@@ -0,0 +1,309 @@
+use std::fmt;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::io::StorageLoader;
+
+#[derive(Debug)]
+pub enum SaveError {
+    /// The file could not be read or written
+    Storage(anyhow::Error),
+    /// The file is not a versioned save or the checksum does not match the data
+    Corrupted,
+    /// The save was written by a newer version than the one loading it
+    UnsupportedVersion {
+        found: u32,
+        current: u32,
+    },
+    /// A migration closure rejected the data
+    Migration {
+        from: u32,
+        error: anyhow::Error,
+    },
+    Serialize(bincode::Error),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Storage(error) => write!(f, "Cannot access save: {error}"),
+            SaveError::Corrupted => write!(f, "Save is corrupted"),
+            SaveError::UnsupportedVersion { found, current } => write!(
+                f,
+                "Save has version {found}, but the current version is {current}"
+            ),
+            SaveError::Migration { from, error } => {
+                write!(f, "Cannot migrate save from version {from}: {error}")
+            }
+            SaveError::Serialize(error) => write!(f, "Cannot (de)serialize save: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+/// `magic | version | checksum` in little endian, followed by the bincode payload
+struct SaveHeader;
+
+impl SaveHeader {
+    const MAGIC: [u8; 4] = *b"SHRS";
+    const SIZE: usize = 12;
+
+    fn write(version: u32, payload: &[u8]) -> Vec<u8> {
+        let mut data = Vec::with_capacity(Self::SIZE + payload.len());
+        data.extend_from_slice(&Self::MAGIC);
+        data.extend_from_slice(&version.to_le_bytes());
+        data.extend_from_slice(&crc32(payload).to_le_bytes());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    /// Version and payload of a save with a valid checksum
+    fn read(data: &[u8]) -> Option<(u32, &[u8])> {
+        if data.len() < Self::SIZE || data[0..4] != Self::MAGIC {
+            return None;
+        }
+        let version = u32::from_le_bytes(data[4..8].try_into().unwrap());
+        let checksum = u32::from_le_bytes(data[8..12].try_into().unwrap());
+        let payload = &data[Self::SIZE..];
+        (crc32(payload) == checksum).then_some((version, payload))
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+impl dyn StorageLoader {
+    /// Path of the backup that [save_versioned](Self::save_versioned) keeps of the previous save
+    pub fn backup_path(path: &str) -> String {
+        format!("{path}.bak")
+    }
+
+    /// Serializes `data` with a header that holds the `version` and a checksum. A valid save
+    /// that already exists at `path` is kept as [backup_path](Self::backup_path), so a game can
+    /// fall back to it when the new save gets corrupted.
+    pub fn save_versioned<T: Serialize>(
+        &self,
+        path: &str,
+        version: u32,
+        data: &T,
+    ) -> Result<(), SaveError> {
+        let payload = bincode::serialize(data).map_err(SaveError::Serialize)?;
+        if let Ok(previous) = self.load_bytes(path) {
+            if SaveHeader::read(&previous).is_some() {
+                self.store(&Self::backup_path(path), &previous)
+                    .map_err(SaveError::Storage)?;
+            }
+        }
+        self.store(path, &SaveHeader::write(version, &payload))
+            .map_err(SaveError::Storage)
+    }
+
+    /// Loads a save written by [save_versioned](Self::save_versioned). Saves of an older version
+    /// are passed to `migrate` with their version, which returns the payload of the next
+    /// version, until the payload reaches the `current` version. A bad checksum results in
+    /// [SaveError::Corrupted].
+    pub fn load_versioned<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        current: u32,
+        mut migrate: impl FnMut(u32, Vec<u8>) -> anyhow::Result<Vec<u8>>,
+    ) -> Result<T, SaveError> {
+        let data = self.load_bytes(path).map_err(SaveError::Storage)?;
+        let (mut version, payload) = SaveHeader::read(&data).ok_or(SaveError::Corrupted)?;
+        if version > current {
+            return Err(SaveError::UnsupportedVersion {
+                found: version,
+                current,
+            });
+        }
+
+        let mut payload = payload.to_vec();
+        while version < current {
+            payload = migrate(version, payload).map_err(|error| SaveError::Migration {
+                from: version,
+                error,
+            })?;
+            version += 1;
+        }
+        bincode::deserialize(&payload).map_err(SaveError::Serialize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::Mutex;
+    use rustc_hash::FxHashMap;
+    use serde::Deserialize;
+
+    #[derive(Default)]
+    struct MemoryStorage {
+        files: Mutex<FxHashMap<String, Vec<u8>>>,
+    }
+
+    impl StorageLoader for MemoryStorage {
+        fn store(&self, path: &str, data: &dyn AsRef<[u8]>) -> anyhow::Result<()> {
+            self.files
+                .lock()
+                .insert(path.to_owned(), data.as_ref().to_vec());
+            Ok(())
+        }
+
+        fn load_string(&self, path: &str) -> anyhow::Result<String> {
+            Ok(String::from_utf8(self.load_bytes(path)?)?)
+        }
+
+        fn delete(&self, path: &str) -> anyhow::Result<()> {
+            self.files.lock().remove(path);
+            Ok(())
+        }
+
+        fn load_bytes(&self, path: &str) -> anyhow::Result<Vec<u8>> {
+            self.files
+                .lock()
+                .get(path)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("{path} does not exist"))
+        }
+
+        fn list(&self) -> Vec<String> {
+            self.files.lock().keys().cloned().collect()
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct SaveV1 {
+        gold: u32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct SaveV2 {
+        gold: u32,
+        level: u32,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct SaveV3 {
+        gold: u64,
+        level: u32,
+        name: String,
+    }
+
+    fn migrate(version: u32, payload: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        Ok(match version {
+            1 => {
+                let save: SaveV1 = bincode::deserialize(&payload)?;
+                bincode::serialize(&SaveV2 {
+                    gold: save.gold,
+                    level: 1,
+                })?
+            }
+            2 => {
+                let save: SaveV2 = bincode::deserialize(&payload)?;
+                bincode::serialize(&SaveV3 {
+                    gold: save.gold as u64,
+                    level: save.level,
+                    name: "Player".to_owned(),
+                })?
+            }
+            _ => anyhow::bail!("No migration from version {version}"),
+        })
+    }
+
+    fn no_migration(version: u32, _: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        anyhow::bail!("Unexpected migration from version {version}")
+    }
+
+    fn save() -> SaveV3 {
+        SaveV3 {
+            gold: 250,
+            level: 7,
+            name: "Andri".to_owned(),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let storage: &dyn StorageLoader = &MemoryStorage::default();
+        storage.save_versioned("save", 3, &save()).unwrap();
+        let loaded: SaveV3 = storage.load_versioned("save", 3, no_migration).unwrap();
+        assert_eq!(loaded, save());
+    }
+
+    #[test]
+    fn flipped_byte_is_corrupted() {
+        let storage: &dyn StorageLoader = &MemoryStorage::default();
+        storage.save_versioned("save", 3, &save()).unwrap();
+        let mut data = storage.load_bytes("save").unwrap();
+        *data.last_mut().unwrap() ^= 1;
+        storage.store("save", &data).unwrap();
+        assert!(matches!(
+            storage.load_versioned::<SaveV3>("save", 3, no_migration),
+            Err(SaveError::Corrupted)
+        ));
+    }
+
+    #[test]
+    fn migrates_from_v1_to_v3() {
+        let storage: &dyn StorageLoader = &MemoryStorage::default();
+        storage
+            .save_versioned("save", 1, &SaveV1 { gold: 42 })
+            .unwrap();
+        let mut migrated = vec![];
+        let loaded: SaveV3 = storage
+            .load_versioned("save", 3, |version, payload| {
+                migrated.push(version);
+                migrate(version, payload)
+            })
+            .unwrap();
+        assert_eq!(migrated, [1, 2]);
+        assert_eq!(
+            loaded,
+            SaveV3 {
+                gold: 42,
+                level: 1,
+                name: "Player".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn newer_version_is_unsupported() {
+        let storage: &dyn StorageLoader = &MemoryStorage::default();
+        storage.save_versioned("save", 4, &save()).unwrap();
+        assert!(matches!(
+            storage.load_versioned::<SaveV3>("save", 3, migrate),
+            Err(SaveError::UnsupportedVersion {
+                found: 4,
+                current: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn second_save_keeps_a_backup() {
+        let storage: &dyn StorageLoader = &MemoryStorage::default();
+        let backup = <dyn StorageLoader>::backup_path("save");
+        storage
+            .save_versioned("save", 1, &SaveV1 { gold: 1 })
+            .unwrap();
+        assert!(storage.load_bytes(&backup).is_err());
+
+        let first = storage.load_bytes("save").unwrap();
+        storage.save_versioned("save", 3, &save()).unwrap();
+        assert_eq!(storage.load_bytes(&backup).unwrap(), first);
+        let loaded: SaveV3 = storage.load_versioned(&backup, 3, migrate).unwrap();
+        assert_eq!(loaded.gold, 1);
+        let loaded: SaveV3 = storage.load_versioned("save", 3, no_migration).unwrap();
+        assert_eq!(loaded, save());
+    }
+}
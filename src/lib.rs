@@ -1,3 +1,6 @@
+// Lets the derive macros refer to `::shura` inside of this crate
+extern crate self as shura;
+
 /// Shura version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -12,20 +15,25 @@ pub mod ecs;
 pub mod graphics;
 #[cfg(feature = "gui")]
 pub mod gui;
+pub mod headless;
+pub mod i18n;
 pub mod input;
 pub mod io;
 #[cfg(feature = "log")]
 pub mod log;
 pub mod math;
+pub mod noise;
 #[cfg(feature = "physics")]
 pub mod physics;
 pub mod random;
 pub mod scene;
 #[cfg(feature = "serde")]
 pub mod serde;
+pub mod steering;
 pub mod tasks;
 #[cfg(feature = "text")]
 pub mod text;
+pub mod tilemap;
 pub mod time;
 
 pub use bytemuck;
@@ -66,21 +74,26 @@ pub mod prelude {
     pub use crate::graphics::*;
     #[cfg(feature = "gui")]
     pub use crate::gui;
+    pub use crate::headless::*;
+    pub use crate::i18n::*;
     pub use crate::input::*;
     pub use crate::io::*;
     #[cfg(feature = "log")]
     pub use crate::log::*;
     pub use crate::macros::*;
     pub use crate::math::*;
+    pub use crate::noise::*;
     #[cfg(feature = "physics")]
     pub use crate::physics::*;
     pub use crate::random::*;
     pub use crate::scene::*;
     #[cfg(feature = "serde")]
     pub use crate::serde::*;
+    pub use crate::steering::*;
     pub use crate::tasks::*;
     #[cfg(feature = "text")]
     pub use crate::text::*;
+    pub use crate::tilemap::*;
     pub use crate::time::*;
 
     pub use bytemuck;
@@ -136,6 +136,55 @@ impl AABB {
         self.max.x = self.max.x.max(other.max.x);
         self.max.y = self.max.y.max(other.max.y);
     }
+
+    /// Smallest box containing all points, an empty box at the origin if there are none
+    pub fn from_points(points: impl IntoIterator<Item = Vector2<f32>>) -> Self {
+        let mut points = points.into_iter();
+        let Some(first) = points.next() else {
+            return Default::default();
+        };
+        points.fold(Self::new(first, first), |aabb, point| Self {
+            min: aabb.min.inf(&point),
+            max: aabb.max.sup(&point),
+        })
+    }
+
+    /// Grows the box by `margin` on every side. A negative margin shrinks it, but never past
+    /// its center.
+    pub fn expand(mut self, margin: f32) -> Self {
+        let center = self.center();
+        self.min = (self.min - Vector2::repeat(margin)).inf(&center);
+        self.max = (self.max + Vector2::repeat(margin)).sup(&center);
+        self
+    }
+
+    pub fn union(&self, other: &AABB) -> AABB {
+        let mut union = *self;
+        union.combine(*other);
+        union
+    }
+
+    /// Overlapping area, [None] if the boxes don't intersect
+    pub fn intersection(&self, other: &AABB) -> Option<AABB> {
+        self.intersects(other).then(|| Self {
+            min: self.min.sup(&other.min),
+            max: self.max.inf(&other.max),
+        })
+    }
+
+    pub fn contains_aabb(&self, other: &AABB) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+
+    /// The point itself if it is inside the box
+    pub fn closest_point(&self, point: &Vector2<f32>) -> Vector2<f32> {
+        point.sup(&self.min).inf(&self.max)
+    }
+
+    /// `0.0` if the point is inside the box
+    pub fn distance_to_point(&self, point: &Vector2<f32>) -> f32 {
+        (self.closest_point(point) - point).norm()
+    }
 }
 
 #[cfg(feature = "physics")]
@@ -151,3 +200,104 @@ impl From<rapier2d::prelude::Aabb> for AABB {
         Self::new(value.mins.coords, value.maxs.coords)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::fixtures::{aabb, vector, CASES},
+        random::SceneRandom,
+    };
+
+    #[test]
+    fn union_contains_both() {
+        let mut random = SceneRandom::new(1);
+        for _ in 0..CASES {
+            let (a, b) = (aabb(&mut random), aabb(&mut random));
+            let union = a.union(&b);
+            assert!(union.contains_aabb(&a) && union.contains_aabb(&b));
+            assert_eq!(union, b.union(&a));
+            assert_eq!(a.union(&a), a);
+
+            let point = vector(&mut random);
+            if a.contains_point(&point) || b.contains_point(&point) {
+                assert!(union.contains_point(&point));
+            }
+        }
+    }
+
+    #[test]
+    fn intersection_is_symmetric() {
+        let mut random = SceneRandom::new(2);
+        for _ in 0..CASES {
+            let (a, b) = (aabb(&mut random), aabb(&mut random));
+            assert_eq!(a.intersects(&b), b.intersects(&a));
+            assert_eq!(a.intersection(&b), b.intersection(&a));
+            assert_eq!(a.intersection(&b).is_some(), a.intersects(&b));
+
+            let point = vector(&mut random);
+            let in_both = a.contains_point(&point) && b.contains_point(&point);
+            match a.intersection(&b) {
+                Some(intersection) => {
+                    assert!(a.contains_aabb(&intersection) && b.contains_aabb(&intersection));
+                    assert_eq!(intersection.contains_point(&point), in_both);
+                }
+                None => assert!(!in_both),
+            }
+        }
+    }
+
+    #[test]
+    fn closest_point_is_inside() {
+        let mut random = SceneRandom::new(3);
+        for _ in 0..CASES {
+            let (aabb, point) = (aabb(&mut random), vector(&mut random));
+            let closest = aabb.closest_point(&point);
+            assert!(aabb.contains_point(&closest));
+            if aabb.contains_point(&point) {
+                assert_eq!(closest, point);
+                assert_eq!(aabb.distance_to_point(&point), 0.0);
+            } else {
+                assert!(aabb.distance_to_point(&point) > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn expand_never_inverts() {
+        let mut random = SceneRandom::new(4);
+        for _ in 0..CASES {
+            let aabb = aabb(&mut random);
+            let margin = random.gen_range(-20.0..20.0);
+            let expanded = aabb.expand(margin);
+            assert!(expanded.min().x <= expanded.max().x && expanded.min().y <= expanded.max().y);
+            assert!(expanded.contains_point(&aabb.center()));
+            if margin >= 0.0 {
+                assert!(expanded.contains_aabb(&aabb));
+            }
+        }
+    }
+
+    #[cfg(feature = "physics")]
+    #[test]
+    fn matches_parry() {
+        use rapier2d::parry::bounding_volume::{Aabb, BoundingVolume};
+
+        let mut random = SceneRandom::new(5);
+        for _ in 0..CASES {
+            let (a, b) = (aabb(&mut random), aabb(&mut random));
+            let (parry_a, parry_b) = (Aabb::from(a), Aabb::from(b));
+            assert_eq!(a.intersects(&b), parry_a.intersects(&parry_b));
+            assert_eq!(
+                a.intersection(&b),
+                parry_a.intersection(&parry_b).map(AABB::from)
+            );
+            assert_eq!(a.union(&b), AABB::from(parry_a.merged(&parry_b)));
+            let point = vector(&mut random);
+            assert_eq!(
+                a.contains_point(&point),
+                parry_a.contains_local_point(&point.into())
+            );
+        }
+    }
+}
@@ -0,0 +1,15 @@
+//! Random inputs shared by the tests of the math types
+use crate::{
+    math::{Vector2, AABB},
+    random::SceneRandom,
+};
+
+pub(crate) const CASES: usize = 1000;
+
+pub(crate) fn vector(random: &mut SceneRandom) -> Vector2<f32> {
+    Vector2::new(random.gen_range(-10.0..10.0), random.gen_range(-10.0..10.0))
+}
+
+pub(crate) fn aabb(random: &mut SceneRandom) -> AABB {
+    AABB::new(vector(random), vector(random))
+}
@@ -0,0 +1,86 @@
+use crate::math::{Matrix4, Vector3, Vector4};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoundingVolume {
+    Sphere {
+        center: Vector3<f32>,
+        radius: f32,
+    },
+    Aabb {
+        min: Vector3<f32>,
+        max: Vector3<f32>,
+    },
+}
+
+impl BoundingVolume {
+    pub fn sphere(center: Vector3<f32>, radius: f32) -> Self {
+        Self::Sphere { center, radius }
+    }
+
+    pub fn aabb(min: Vector3<f32>, max: Vector3<f32>) -> Self {
+        Self::Aabb { min, max }
+    }
+}
+
+/// The six planes of a view projection. Plane normals point inwards.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Frustum {
+    planes: [Vector4<f32>; 6],
+}
+
+impl Frustum {
+    pub fn from_matrix(view_proj: &Matrix4<f32>) -> Self {
+        let row = |i: usize| -> Vector4<f32> { view_proj.row(i).transpose() };
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2].map(|plane| {
+            let length = plane.xyz().norm();
+            if length > 0.0 {
+                plane / length
+            } else {
+                plane
+            }
+        });
+        Self { planes }
+    }
+
+    pub fn planes(&self) -> &[Vector4<f32>; 6] {
+        &self.planes
+    }
+
+    fn distance(plane: &Vector4<f32>, point: &Vector3<f32>) -> f32 {
+        plane.xyz().dot(point) + plane.w
+    }
+
+    pub fn contains_point(&self, point: &Vector3<f32>) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| Self::distance(plane, point) >= 0.0)
+    }
+
+    /// Returns true if any part of the sphere is inside
+    pub fn contains_sphere(&self, center: &Vector3<f32>, radius: f32) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| Self::distance(plane, center) >= -radius)
+    }
+
+    pub fn intersects_aabb(&self, min: &Vector3<f32>, max: &Vector3<f32>) -> bool {
+        self.planes.iter().all(|plane| {
+            let positive = Vector3::new(
+                if plane.x >= 0.0 { max.x } else { min.x },
+                if plane.y >= 0.0 { max.y } else { min.y },
+                if plane.z >= 0.0 { max.z } else { min.z },
+            );
+            Self::distance(plane, &positive) >= 0.0
+        })
+    }
+
+    pub fn intersects(&self, volume: &BoundingVolume) -> bool {
+        match volume {
+            BoundingVolume::Sphere { center, radius } => self.contains_sphere(center, *radius),
+            BoundingVolume::Aabb { min, max } => self.intersects_aabb(min, max),
+        }
+    }
+}
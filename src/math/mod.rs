@@ -1,8 +1,14 @@
 mod aabb;
+#[cfg(test)]
+mod fixtures;
+mod frustum;
+mod ray;
 
 pub use aabb::*;
+pub use frustum::*;
 pub use nalgebra::{
     matrix, point, vector, Isometry2, Isometry3, Matrix2, Matrix3, Matrix4, Point2, Point3,
     Quaternion, Translation2, Translation3, UnitComplex as Rotation2, UnitQuaternion as Rotation3,
     UnitQuaternion, UnitVector2, UnitVector3, Vector2, Vector3, Vector4,
 };
+pub use ray::*;
@@ -0,0 +1,292 @@
+use crate::math::{Vector2, AABB};
+
+/// Ray for picking and simple queries that don't need the physics world. Distances `t` are
+/// measured in multiples of `dir`, which doesn't have to be normalized.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ray2D {
+    pub origin: Vector2<f32>,
+    pub dir: Vector2<f32>,
+}
+
+impl Ray2D {
+    pub fn new(origin: Vector2<f32>, dir: Vector2<f32>) -> Self {
+        Self { origin, dir }
+    }
+
+    pub fn point_at(&self, t: f32) -> Vector2<f32> {
+        self.origin + self.dir * t
+    }
+
+    /// Where the ray enters and leaves the box. The entry is `0.0` if the origin is inside.
+    pub fn intersect_aabb(&self, aabb: &AABB) -> Option<(f32, f32)> {
+        let mut t_min = 0.0_f32;
+        let mut t_max = f32::INFINITY;
+        for axis in 0..2 {
+            let (origin, dir) = (self.origin[axis], self.dir[axis]);
+            let (min, max) = (aabb.min()[axis], aabb.max()[axis]);
+            if dir == 0.0 {
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+            let t1 = (min - origin) / dir;
+            let t2 = (max - origin) / dir;
+            t_min = t_min.max(t1.min(t2));
+            t_max = t_max.min(t1.max(t2));
+            if t_min > t_max {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+
+    /// Distance to the first point on the segment. Parallel overlapping segments count as a
+    /// miss.
+    pub fn intersect_segment(&self, segment: &Segment2D) -> Option<f32> {
+        let edge = segment.b - segment.a;
+        let denominator = cross(&self.dir, &edge);
+        if denominator == 0.0 {
+            return None;
+        }
+        let offset = segment.a - self.origin;
+        let t = cross(&offset, &edge) / denominator;
+        let u = cross(&offset, &self.dir) / denominator;
+        (t >= 0.0 && (0.0..=1.0).contains(&u)).then_some(t)
+    }
+}
+
+/// Line segment from `a` to `b`
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment2D {
+    pub a: Vector2<f32>,
+    pub b: Vector2<f32>,
+}
+
+impl Segment2D {
+    pub fn new(a: Vector2<f32>, b: Vector2<f32>) -> Self {
+        Self { a, b }
+    }
+
+    pub fn length(&self) -> f32 {
+        (self.b - self.a).norm()
+    }
+
+    pub fn aabb(&self) -> AABB {
+        AABB::new(self.a, self.b)
+    }
+
+    pub fn closest_point(&self, point: &Vector2<f32>) -> Vector2<f32> {
+        let edge = self.b - self.a;
+        let length_squared = edge.norm_squared();
+        if length_squared == 0.0 {
+            return self.a;
+        }
+        let t = ((point - self.a).dot(&edge) / length_squared).clamp(0.0, 1.0);
+        self.a + edge * t
+    }
+
+    pub fn distance_to_point(&self, point: &Vector2<f32>) -> f32 {
+        (self.closest_point(point) - point).norm()
+    }
+
+    /// Also true if the segments only touch or overlap while being collinear
+    pub fn intersects_segment(&self, other: &Segment2D) -> bool {
+        let d1 = cross(&(other.b - other.a), &(self.a - other.a));
+        let d2 = cross(&(other.b - other.a), &(self.b - other.a));
+        let d3 = cross(&(self.b - self.a), &(other.a - self.a));
+        let d4 = cross(&(self.b - self.a), &(other.b - self.a));
+        if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+            && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+        {
+            return true;
+        }
+        (d1 == 0.0 && other.aabb().contains_point(&self.a))
+            || (d2 == 0.0 && other.aabb().contains_point(&self.b))
+            || (d3 == 0.0 && self.aabb().contains_point(&other.a))
+            || (d4 == 0.0 && self.aabb().contains_point(&other.b))
+    }
+
+    /// Crossing point of two segments that aren't parallel
+    pub fn intersection(&self, other: &Segment2D) -> Option<Vector2<f32>> {
+        let ray = Ray2D::new(self.a, self.b - self.a);
+        ray.intersect_segment(other)
+            .filter(|t| *t <= 1.0)
+            .map(|t| ray.point_at(t))
+    }
+}
+
+fn cross(a: &Vector2<f32>, b: &Vector2<f32>) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+#[cfg(feature = "physics")]
+impl From<Ray2D> for rapier2d::prelude::Ray {
+    fn from(val: Ray2D) -> Self {
+        rapier2d::prelude::Ray::new(val.origin.into(), val.dir)
+    }
+}
+
+#[cfg(feature = "physics")]
+impl From<rapier2d::prelude::Ray> for Ray2D {
+    fn from(value: rapier2d::prelude::Ray) -> Self {
+        Self::new(value.origin.coords, value.dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        math::fixtures::{aabb, vector, CASES},
+        random::SceneRandom,
+    };
+
+    const EPSILON: f32 = 1e-3;
+
+    fn on_boundary(aabb: &AABB, point: &Vector2<f32>) -> bool {
+        aabb.expand(EPSILON).contains_point(point) && !aabb.expand(-EPSILON).contains_point(point)
+    }
+
+    #[test]
+    fn aabb_hits_lie_on_the_box() {
+        let mut random = SceneRandom::new(1);
+        let mut hits = 0;
+        for _ in 0..CASES {
+            let aabb = aabb(&mut random);
+            let ray = Ray2D::new(vector(&mut random), vector(&mut random));
+            let Some((enter, exit)) = ray.intersect_aabb(&aabb) else {
+                continue;
+            };
+            hits += 1;
+            assert!(0.0 <= enter && enter <= exit);
+            assert!(on_boundary(&aabb, &ray.point_at(exit)));
+            if aabb.contains_point(&ray.origin) {
+                assert_eq!(enter, 0.0);
+            } else {
+                assert!(on_boundary(&aabb, &ray.point_at(enter)));
+            }
+            let middle = ray.point_at((enter + exit) / 2.0);
+            assert!(aabb.expand(EPSILON).contains_point(&middle));
+        }
+        assert!(hits > CASES / 10);
+    }
+
+    #[test]
+    fn rays_towards_the_center_hit() {
+        let mut random = SceneRandom::new(2);
+        for _ in 0..CASES {
+            let aabb = aabb(&mut random);
+            let origin = vector(&mut random);
+            let towards = Ray2D::new(origin, aabb.center() - origin);
+            assert!(towards.intersect_aabb(&aabb).is_some());
+            if !aabb.contains_point(&origin) {
+                let away = Ray2D::new(origin, origin - aabb.center());
+                assert!(away.intersect_aabb(&aabb).is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn segment_hits_lie_on_the_segment() {
+        let mut random = SceneRandom::new(3);
+        let mut hits = 0;
+        for _ in 0..CASES {
+            let segment = Segment2D::new(vector(&mut random), vector(&mut random));
+            let ray = Ray2D::new(vector(&mut random), vector(&mut random));
+            if let Some(t) = ray.intersect_segment(&segment) {
+                hits += 1;
+                assert!(t >= 0.0);
+                assert!(segment.distance_to_point(&ray.point_at(t)) < EPSILON);
+            }
+        }
+        assert!(hits > CASES / 10);
+    }
+
+    #[test]
+    fn segment_intersection_is_symmetric() {
+        let mut random = SceneRandom::new(4);
+        for _ in 0..CASES {
+            let a = Segment2D::new(vector(&mut random), vector(&mut random));
+            let b = Segment2D::new(vector(&mut random), vector(&mut random));
+            assert_eq!(a.intersects_segment(&b), b.intersects_segment(&a));
+            match (a.intersection(&b), b.intersection(&a)) {
+                (Some(point), Some(other)) => {
+                    assert!((point - other).norm() < EPSILON);
+                    assert!(a.distance_to_point(&point) < EPSILON);
+                    assert!(b.distance_to_point(&point) < EPSILON);
+                    assert!(a.intersects_segment(&b));
+                }
+                (None, None) => (),
+                (a, b) => panic!("Asymmetric intersection {a:?} and {b:?}"),
+            }
+        }
+    }
+
+    #[cfg(feature = "physics")]
+    #[test]
+    fn matches_parry() {
+        use rapier2d::parry::{bounding_volume::Aabb, query::RayCast, shape::Segment};
+
+        let mut random = SceneRandom::new(5);
+        let mut hits = 0;
+        for _ in 0..CASES {
+            let ray = Ray2D::new(vector(&mut random), vector(&mut random));
+            let parry_ray = rapier2d::prelude::Ray::from(ray);
+
+            let aabb = aabb(&mut random);
+            let expected = Aabb::from(aabb).cast_local_ray(&parry_ray, f32::MAX, true);
+            match (ray.intersect_aabb(&aabb), expected) {
+                (Some((enter, _)), Some(toi)) => {
+                    hits += 1;
+                    assert!((enter - toi).abs() < EPSILON, "{enter} != {toi}");
+                }
+                (None, None) => (),
+                // Only rays grazing a corner may disagree
+                (hit, _) => {
+                    let grazed = [
+                        *aabb.min(),
+                        *aabb.max(),
+                        Vector2::new(aabb.min().x, aabb.max().y),
+                        Vector2::new(aabb.max().x, aabb.min().y),
+                    ]
+                    .iter()
+                    .any(|corner| distance_to_ray(&ray, corner) < EPSILON);
+                    assert!(grazed, "Disagreeing hit {hit:?} for {ray:?} and {aabb:?}");
+                }
+            }
+
+            let segment = Segment2D::new(vector(&mut random), vector(&mut random));
+            let expected = Segment::new(segment.a.into(), segment.b.into()).cast_local_ray(
+                &parry_ray,
+                f32::MAX,
+                true,
+            );
+            match (ray.intersect_segment(&segment), expected) {
+                (Some(t), Some(toi)) => {
+                    hits += 1;
+                    assert!((t - toi).abs() < EPSILON, "{t} != {toi}");
+                }
+                (None, None) => (),
+                // Only rays grazing an end of the segment may disagree
+                (hit, _) => {
+                    let grazed = distance_to_ray(&ray, &segment.a) < EPSILON
+                        || distance_to_ray(&ray, &segment.b) < EPSILON;
+                    assert!(
+                        grazed,
+                        "Disagreeing hit {hit:?} for {ray:?} and {segment:?}"
+                    );
+                }
+            }
+        }
+        assert!(hits > CASES / 10);
+    }
+
+    #[cfg(feature = "physics")]
+    fn distance_to_ray(ray: &Ray2D, point: &Vector2<f32>) -> f32 {
+        let t = ((point - ray.origin).dot(&ray.dir) / ray.dir.norm_squared()).max(0.0);
+        (ray.point_at(t) - point).norm()
+    }
+}
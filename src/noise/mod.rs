@@ -0,0 +1,3 @@
+mod noise;
+
+pub use noise::*;
@@ -0,0 +1,456 @@
+use crate::{graphics::SpriteBuilder, math::Vector2, random::SceneRandom};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NoiseKind {
+    Perlin,
+    /// Simplex noise, smoother and with fewer grid artifacts than [NoiseKind::Perlin]
+    Simplex,
+}
+
+/// Fractal brownian motion, sums octaves of noise with rising frequency and falling amplitude
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fbm {
+    pub octaves: u32,
+    /// Frequency multiplier from one octave to the next, usually `2.0`
+    pub lacunarity: f32,
+    /// Amplitude multiplier from one octave to the next, usually `0.5`
+    pub gain: f32,
+}
+
+/// Coherent noise for terrain, clouds, camera shake and other procedural content. The same
+/// seed gives the same noise on every platform, so it can be seeded from
+/// [SceneRandom](crate::random::SceneRandom). Samples are in the range `[-1, 1]`.
+#[derive(Clone, Debug)]
+pub struct Noise {
+    kind: NoiseKind,
+    seed: u64,
+    frequency: f32,
+    fbm: Option<Fbm>,
+    perm: Box<[u8; 512]>,
+}
+
+impl Noise {
+    pub fn new(kind: NoiseKind, seed: u64) -> Self {
+        let mut table: [u8; 256] = std::array::from_fn(|i| i as u8);
+        SceneRandom::new(seed).shuffle(&mut table);
+        let perm = Box::new(std::array::from_fn(|i| table[i & 255]));
+        Self {
+            kind,
+            seed,
+            frequency: 1.0,
+            fbm: None,
+            perm,
+        }
+    }
+
+    pub fn perlin(seed: u64) -> Self {
+        Self::new(NoiseKind::Perlin, seed)
+    }
+
+    pub fn simplex(seed: u64) -> Self {
+        Self::new(NoiseKind::Simplex, seed)
+    }
+
+    /// Scales the coordinates before sampling, larger values give smaller features
+    pub fn frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    pub fn fbm(mut self, octaves: u32, lacunarity: f32, gain: f32) -> Self {
+        self.fbm = Some(Fbm {
+            octaves: octaves.max(1),
+            lacunarity,
+            gain,
+        });
+        self
+    }
+
+    pub fn kind(&self) -> NoiseKind {
+        self.kind
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn sample1(&self, x: f32) -> f32 {
+        self.fractal(|noise, offset, frequency| {
+            let x = x * frequency + offset;
+            match noise.kind {
+                NoiseKind::Perlin => noise.perlin1(x),
+                NoiseKind::Simplex => noise.simplex1(x),
+            }
+        })
+    }
+
+    pub fn sample2(&self, x: f32, y: f32) -> f32 {
+        self.fractal(|noise, offset, frequency| {
+            let (x, y) = (x * frequency + offset, y * frequency + offset);
+            match noise.kind {
+                NoiseKind::Perlin => noise.perlin2(x, y),
+                NoiseKind::Simplex => noise.simplex2(x, y),
+            }
+        })
+    }
+
+    pub fn sample3(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.fractal(|noise, offset, frequency| {
+            let (x, y, z) = (
+                x * frequency + offset,
+                y * frequency + offset,
+                z * frequency + offset,
+            );
+            match noise.kind {
+                NoiseKind::Perlin => noise.perlin3(x, y, z),
+                NoiseKind::Simplex => noise.simplex3(x, y, z),
+            }
+        })
+    }
+
+    /// Samples the noise for every pixel, `offset` is the position of the top left pixel and
+    /// `scale` the distance between two pixels. `-1` maps to black and `1` to white.
+    pub fn bake(&self, size: Vector2<u32>, offset: Vector2<f32>, scale: f32) -> image::GrayImage {
+        image::GrayImage::from_fn(size.x, size.y, |x, y| {
+            let value = self.sample2(offset.x + x as f32 * scale, offset.y + y as f32 * scale);
+            image::Luma([((value * 0.5 + 0.5) * 255.0).round() as u8])
+        })
+    }
+
+    /// [Noise::bake] as a linear sprite, since the pixels are data and not colors
+    pub fn sprite(
+        &self,
+        size: Vector2<u32>,
+        offset: Vector2<f32>,
+        scale: f32,
+    ) -> SpriteBuilder<'static, image::RgbaImage> {
+        SpriteBuilder::image(image::DynamicImage::ImageLuma8(
+            self.bake(size, offset, scale),
+        ))
+        .format(wgpu::TextureFormat::Rgba8Unorm)
+    }
+
+    fn fractal(&self, sample: impl Fn(&Self, f32, f32) -> f32) -> f32 {
+        let Some(fbm) = self.fbm else {
+            return sample(self, 0.0, self.frequency);
+        };
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut total_amplitude = 0.0;
+        let mut frequency = self.frequency;
+        for octave in 0..fbm.octaves {
+            // Octaves are shifted against each other, otherwise they all line up at the origin
+            sum += sample(self, octave as f32 * 31.7, frequency) * amplitude;
+            total_amplitude += amplitude;
+            amplitude *= fbm.gain;
+            frequency *= fbm.lacunarity;
+        }
+        sum / total_amplitude
+    }
+
+    fn hash(&self, i: i32) -> usize {
+        self.perm[(i & 255) as usize] as usize
+    }
+
+    fn hash2(&self, i: i32, j: i32) -> usize {
+        self.perm[(i & 255) as usize + self.hash(j)] as usize
+    }
+
+    fn hash3(&self, i: i32, j: i32, k: i32) -> usize {
+        self.perm[(i & 255) as usize + self.hash2(j, k)] as usize
+    }
+
+    fn perlin1(&self, x: f32) -> f32 {
+        let i = x.floor();
+        let x = x - i;
+        let i = i as i32;
+        let a = grad1(self.hash(i), x);
+        let b = grad1(self.hash(i + 1), x - 1.0);
+        (lerp(a, b, fade(x)) * 0.25).clamp(-1.0, 1.0)
+    }
+
+    fn perlin2(&self, x: f32, y: f32) -> f32 {
+        let (i, j) = (x.floor(), y.floor());
+        let (x, y) = (x - i, y - j);
+        let (i, j) = (i as i32, j as i32);
+        let (u, v) = (fade(x), fade(y));
+        let a = lerp(
+            grad2(self.hash2(i, j), x, y),
+            grad2(self.hash2(i + 1, j), x - 1.0, y),
+            u,
+        );
+        let b = lerp(
+            grad2(self.hash2(i, j + 1), x, y - 1.0),
+            grad2(self.hash2(i + 1, j + 1), x - 1.0, y - 1.0),
+            u,
+        );
+        lerp(a, b, v).clamp(-1.0, 1.0)
+    }
+
+    fn perlin3(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (i, j, k) = (x.floor(), y.floor(), z.floor());
+        let (x, y, z) = (x - i, y - j, z - k);
+        let (i, j, k) = (i as i32, j as i32, k as i32);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+        let corner = |di: i32, dj: i32, dk: i32| {
+            grad3(
+                self.hash3(i + di, j + dj, k + dk),
+                x - di as f32,
+                y - dj as f32,
+                z - dk as f32,
+            )
+        };
+        let x0 = lerp(
+            lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+            lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+            v,
+        );
+        let x1 = lerp(
+            lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+            lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+            v,
+        );
+        lerp(x0, x1, w).clamp(-1.0, 1.0)
+    }
+
+    fn simplex1(&self, x: f32) -> f32 {
+        let i = x.floor() as i32;
+        let x0 = x - i as f32;
+        let x1 = x0 - 1.0;
+        let corner = |hash: usize, x: f32| {
+            let t = 1.0 - x * x;
+            let t = t * t;
+            t * t * grad1(hash, x)
+        };
+        ((corner(self.hash(i), x0) + corner(self.hash(i + 1), x1)) * 0.395).clamp(-1.0, 1.0)
+    }
+
+    fn simplex2(&self, x: f32, y: f32) -> f32 {
+        const F2: f32 = 0.36602540378; // (sqrt(3) - 1) / 2
+        const G2: f32 = 0.2113248654; // (3 - sqrt(3)) / 6
+
+        let s = (x + y) * F2;
+        let (i, j) = ((x + s).floor() as i32, (y + s).floor() as i32);
+        let t = (i + j) as f32 * G2;
+        let (x0, y0) = (x - (i as f32 - t), y - (j as f32 - t));
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let (x1, y1) = (x0 - i1 as f32 + G2, y0 - j1 as f32 + G2);
+        let (x2, y2) = (x0 - 1.0 + 2.0 * G2, y0 - 1.0 + 2.0 * G2);
+
+        let corner = |hash: usize, x: f32, y: f32| {
+            let t = 0.5 - x * x - y * y;
+            if t < 0.0 {
+                0.0
+            } else {
+                let t = t * t;
+                t * t * grad2(hash, x, y)
+            }
+        };
+        let n = corner(self.hash2(i, j), x0, y0)
+            + corner(self.hash2(i + i1, j + j1), x1, y1)
+            + corner(self.hash2(i + 1, j + 1), x2, y2);
+        (n * 70.0).clamp(-1.0, 1.0)
+    }
+
+    fn simplex3(&self, x: f32, y: f32, z: f32) -> f32 {
+        const F3: f32 = 1.0 / 3.0;
+        const G3: f32 = 1.0 / 6.0;
+
+        let s = (x + y + z) * F3;
+        let (i, j, k) = (
+            (x + s).floor() as i32,
+            (y + s).floor() as i32,
+            (z + s).floor() as i32,
+        );
+        let t = (i + j + k) as f32 * G3;
+        let (x0, y0, z0) = (x - (i as f32 - t), y - (j as f32 - t), z - (k as f32 - t));
+
+        // The simplex the point is in depends on the order of the offsets
+        let ((i1, j1, k1), (i2, j2, k2)) = if x0 >= y0 {
+            if y0 >= z0 {
+                ((1, 0, 0), (1, 1, 0))
+            } else if x0 >= z0 {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else if y0 < z0 {
+            ((0, 0, 1), (0, 1, 1))
+        } else if x0 < z0 {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        };
+
+        let corner = |di: i32, dj: i32, dk: i32, offset: f32| {
+            let (x, y, z) = (
+                x0 - di as f32 + offset,
+                y0 - dj as f32 + offset,
+                z0 - dk as f32 + offset,
+            );
+            let t = 0.6 - x * x - y * y - z * z;
+            if t < 0.0 {
+                0.0
+            } else {
+                let t = t * t;
+                t * t * grad3(self.hash3(i + di, j + dj, k + dk), x, y, z)
+            }
+        };
+        let n = corner(0, 0, 0, 0.0)
+            + corner(i1, j1, k1, G3)
+            + corner(i2, j2, k2, 2.0 * G3)
+            + corner(1, 1, 1, 3.0 * G3);
+        (n * 32.0).clamp(-1.0, 1.0)
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn grad1(hash: usize, x: f32) -> f32 {
+    let gradient = 1.0 + (hash & 7) as f32;
+    if hash & 8 == 0 {
+        gradient * x
+    } else {
+        -gradient * x
+    }
+}
+
+fn grad2(hash: usize, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+fn grad3(hash: usize, x: f32, y: f32, z: f32) -> f32 {
+    match hash & 15 {
+        0 | 12 => x + y,
+        1 | 14 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 | 13 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [NoiseKind; 2] = [NoiseKind::Perlin, NoiseKind::Simplex];
+
+    /// Points spread over many lattice cells, including negative coordinates
+    fn points() -> impl Iterator<Item = [f32; 3]> {
+        let mut random = SceneRandom::new(7);
+        (0..2000).map(move |_| {
+            [
+                random.gen_range(-100.0..100.0),
+                random.gen_range(-100.0..100.0),
+                random.gen_range(-100.0..100.0),
+            ]
+        })
+    }
+
+    fn samples(noise: &Noise) -> Vec<f32> {
+        points()
+            .flat_map(|[x, y, z]| {
+                [
+                    noise.sample1(x),
+                    noise.sample2(x, y),
+                    noise.sample3(x, y, z),
+                ]
+            })
+            .collect()
+    }
+
+    fn assert_in_range(values: &[f32]) {
+        assert!(values.iter().all(|value| (-1.0..=1.0).contains(value)));
+        let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        assert!(max - min > 0.5, "Noise is almost constant: {min}..{max}");
+    }
+
+    #[test]
+    fn output_in_range() {
+        for kind in KINDS {
+            assert_in_range(&samples(&Noise::new(kind, 1)));
+            assert_in_range(&samples(&Noise::new(kind, 2).frequency(3.7)));
+        }
+    }
+
+    #[test]
+    fn same_seed_same_values() {
+        for kind in KINDS {
+            let values = samples(&Noise::new(kind, 42));
+            assert_eq!(values, samples(&Noise::new(kind, 42)));
+            assert_ne!(values, samples(&Noise::new(kind, 43)));
+        }
+    }
+
+    #[test]
+    fn perlin_is_zero_on_the_lattice() {
+        let noise = Noise::perlin(5);
+        for i in -10..10 {
+            let i = i as f32;
+            assert_eq!(noise.sample1(i), 0.0);
+            assert_eq!(noise.sample2(i, -i), 0.0);
+            assert_eq!(noise.sample3(i, 3.0, i * 2.0), 0.0);
+        }
+    }
+
+    #[test]
+    fn fbm_is_bounded() {
+        for kind in KINDS {
+            for (octaves, lacunarity, gain) in [(1, 2.0, 0.5), (4, 2.0, 0.5), (8, 1.9, 0.8)] {
+                let fbm = Noise::new(kind, 3).fbm(octaves, lacunarity, gain);
+                let values = samples(&fbm);
+                assert_in_range(&values);
+                assert_eq!(
+                    values,
+                    samples(&Noise::new(kind, 3).fbm(octaves, lacunarity, gain))
+                );
+            }
+            // A single octave is the plain noise
+            assert_eq!(
+                samples(&Noise::new(kind, 3).fbm(1, 2.0, 0.5)),
+                samples(&Noise::new(kind, 3))
+            );
+            assert_ne!(
+                samples(&Noise::new(kind, 3).fbm(4, 2.0, 0.5)),
+                samples(&Noise::new(kind, 3))
+            );
+        }
+    }
+
+    #[test]
+    fn noise_is_continuous() {
+        for kind in KINDS {
+            let noise = Noise::new(kind, 9);
+            for [x, y, z] in points() {
+                let delta = 1e-3;
+                assert!((noise.sample2(x, y) - noise.sample2(x + delta, y)).abs() < 0.05);
+                assert!((noise.sample3(x, y, z) - noise.sample3(x, y, z + delta)).abs() < 0.05);
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{
+    context::Context,
+    ecs::{Component, EntityId, World, WorldExt},
+    physics::{CollisionType, EntityCollisionEvent, Physics, RapierCollisionEvent},
+};
+
+type CollisionHandler = Box<dyn Fn(&mut Context, EntityId, EntityId, EntityCollisionEvent)>;
+type HasComponent = fn(&World, EntityId) -> bool;
+
+fn has_component<C: Component>(world: &World, entity: EntityId) -> bool {
+    world.view::<C>().contains(entity)
+}
+
+/// Typed collision handlers registered with
+/// [SceneCreator::collision_handler](crate::scene::SceneCreator::collision_handler).
+/// They are called once per frame after all update systems ran.
+#[derive(Default)]
+pub struct CollisionHandlers {
+    handlers: Vec<(HasComponent, HasComponent, CollisionHandler)>,
+}
+
+impl CollisionHandlers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    pub fn register<A: Component, B: Component>(
+        &mut self,
+        handler: impl Fn(&mut Context, EntityId, EntityId, EntityCollisionEvent) + 'static,
+    ) {
+        self.handlers
+            .push((has_component::<A>, has_component::<B>, Box::new(handler)));
+    }
+
+    /// Calls every handler whose component pair matches the colliding entities in either order.
+    /// The entities are always passed in the order of the handler's components.
+    pub(crate) fn dispatch(&self, ctx: &mut Context) {
+        if self.handlers.is_empty() {
+            return;
+        }
+        for event in ctx.physics.take_routed_collisions() {
+            let Some(event) = entity_event(ctx.physics, event) else {
+                continue;
+            };
+            for (a, b, handler) in &self.handlers {
+                if a(ctx.world, event.entity1) && b(ctx.world, event.entity2) {
+                    (handler)(ctx, event.entity1, event.entity2, event);
+                } else if a(ctx.world, event.entity2) && b(ctx.world, event.entity1) {
+                    (handler)(ctx, event.entity2, event.entity1, event);
+                }
+            }
+        }
+    }
+}
+
+fn entity_event(physics: &Physics, event: RapierCollisionEvent) -> Option<EntityCollisionEvent> {
+    let collider1 = event.collider1();
+    let collider2 = event.collider2();
+    Some(EntityCollisionEvent {
+        collider1,
+        collider2,
+        entity1: *physics.entity_from_collider(&collider1)?,
+        entity2: *physics.entity_from_collider(&collider2)?,
+        collision_type: if event.started() {
+            CollisionType::Started
+        } else {
+            CollisionType::Stopped
+        },
+    })
+}
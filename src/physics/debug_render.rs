@@ -0,0 +1,160 @@
+use std::cell::RefCell;
+
+use rapier2d::pipeline::{
+    DebugColor, DebugRenderBackend, DebugRenderMode, DebugRenderObject, DebugRenderPipeline,
+    DebugRenderStyle,
+};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    context::Context,
+    ecs::{System, SystemPriority},
+    graphics::{Color, DebugDraw},
+    math::Point2,
+    physics::Physics,
+    scene::{Plugin, SceneCreator},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PhysicsDebugCategory {
+    RigidBody,
+    Collider,
+    ColliderAabb,
+    Joint,
+    Contact,
+}
+
+/// Draws the colliders, AABBs, contacts and joints of [Physics] with rapier's
+/// [DebugRenderPipeline] onto the [DebugDraw] overlay. Used as [Plugin] it renders every frame.
+pub struct PhysicsDebugRender {
+    pipeline: DebugRenderPipeline,
+    colors: FxHashMap<PhysicsDebugCategory, Color>,
+}
+
+impl Default for PhysicsDebugRender {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PhysicsDebugRender {
+    pub fn new() -> Self {
+        Self {
+            pipeline: DebugRenderPipeline::new(
+                DebugRenderStyle::default(),
+                DebugRenderMode::default(),
+            ),
+            colors: Default::default(),
+        }
+    }
+
+    pub fn colliders_only() -> Self {
+        Self::new().mode(DebugRenderMode::COLLIDER_SHAPES)
+    }
+
+    pub fn everything() -> Self {
+        Self::new().mode(DebugRenderMode::all())
+    }
+
+    pub fn mode(mut self, mode: DebugRenderMode) -> Self {
+        self.pipeline.mode = mode;
+        self
+    }
+
+    pub fn style(mut self, style: DebugRenderStyle) -> Self {
+        self.pipeline.style = style;
+        self
+    }
+
+    /// Replaces rapier's colors of a category with a single color
+    pub fn color(mut self, category: PhysicsDebugCategory, color: Color) -> Self {
+        self.colors.insert(category, color);
+        self
+    }
+
+    pub fn set_mode(&mut self, mode: DebugRenderMode) {
+        self.pipeline.mode = mode;
+    }
+
+    pub fn set_color(&mut self, category: PhysicsDebugCategory, color: Option<Color>) {
+        match color {
+            Some(color) => self.colors.insert(category, color),
+            None => self.colors.remove(&category),
+        };
+    }
+
+    pub fn render(&mut self, physics: &Physics, debug: &DebugDraw) {
+        let mut backend = DebugDrawBackend {
+            debug,
+            colors: &self.colors,
+        };
+        self.pipeline.render(
+            &mut backend,
+            physics.rigid_bodies(),
+            physics.colliders(),
+            physics.impulse_joints(),
+            physics.multibody_joints(),
+            physics.narrow_phase(),
+        );
+    }
+}
+
+impl Plugin for PhysicsDebugRender {
+    fn init<S: SceneCreator>(&mut self, scene: S) -> S {
+        let render = RefCell::new(std::mem::take(self));
+        scene.system(
+            System::update(move |ctx: &mut Context| {
+                render.borrow_mut().render(ctx.physics, ctx.debug)
+            })
+            .priority(SystemPriority::LAST),
+        )
+    }
+}
+
+struct DebugDrawBackend<'a> {
+    debug: &'a DebugDraw,
+    colors: &'a FxHashMap<PhysicsDebugCategory, Color>,
+}
+
+impl DebugRenderBackend for DebugDrawBackend<'_> {
+    fn draw_line(
+        &mut self,
+        object: DebugRenderObject,
+        a: Point2<f32>,
+        b: Point2<f32>,
+        color: DebugColor,
+    ) {
+        let category = match object {
+            DebugRenderObject::RigidBody(..) => PhysicsDebugCategory::RigidBody,
+            DebugRenderObject::Collider(..) => PhysicsDebugCategory::Collider,
+            DebugRenderObject::ColliderAabb(..) => PhysicsDebugCategory::ColliderAabb,
+            DebugRenderObject::ImpulseJoint(..) | DebugRenderObject::MultibodyJoint(..) => {
+                PhysicsDebugCategory::Joint
+            }
+            DebugRenderObject::ContactPair(..) => PhysicsDebugCategory::Contact,
+        };
+        let color = self
+            .colors
+            .get(&category)
+            .copied()
+            .unwrap_or_else(|| hsla_to_color(color));
+        self.debug.line(a.coords, b.coords, color);
+    }
+}
+
+/// Rapier colors are hue in degrees, saturation, lightness and alpha
+fn hsla_to_color([h, s, l, a]: DebugColor) -> Color {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h = (h / 60.0).rem_euclid(6.0);
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = l - c / 2.0;
+    Color::new(r + m, g + m, b + m, a)
+}
@@ -0,0 +1,35 @@
+use crate::{
+    math::Vector2,
+    physics::{FixedJointBuilder, PrismaticJointBuilder, RevoluteJointBuilder},
+};
+
+/// Sets the anchors of a joint builder relative to the two joined rigid bodies
+pub trait JointAnchors: Sized {
+    fn anchor1(self, anchor: Vector2<f32>) -> Self;
+    fn anchor2(self, anchor: Vector2<f32>) -> Self;
+    fn anchors(self, anchor1: Vector2<f32>, anchor2: Vector2<f32>) -> Self {
+        self.anchor1(anchor1).anchor2(anchor2)
+    }
+}
+
+macro_rules! impl_joint_anchors {
+    ($($builder:ty),*) => {
+        $(
+            impl JointAnchors for $builder {
+                fn anchor1(self, anchor: Vector2<f32>) -> Self {
+                    self.local_anchor1(anchor.into())
+                }
+
+                fn anchor2(self, anchor: Vector2<f32>) -> Self {
+                    self.local_anchor2(anchor.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_joint_anchors!(
+    FixedJointBuilder,
+    RevoluteJointBuilder,
+    PrismaticJointBuilder
+);
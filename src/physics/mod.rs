@@ -1,5 +1,13 @@
+#[cfg(feature = "debug-draw")]
+mod debug_render;
+mod collision_handler;
+mod joints;
 mod physics;
 
+#[cfg(feature = "debug-draw")]
+pub use debug_render::*;
+pub use collision_handler::*;
+pub use joints::*;
 pub use physics::*;
 pub use rapier2d;
 pub use rapier2d::control::{
@@ -6,7 +6,13 @@ use crate::{
     math::{Isometry2, Point2, Vector2},
     physics::{RapierCollisionEvent, RapierContactForceEvent},
 };
-use rapier2d::{crossbeam, parry::query::ShapeCastOptions, prelude::*};
+use parking_lot::Mutex;
+use rapier2d::{
+    control::{CharacterCollision, KinematicCharacterController},
+    crossbeam,
+    parry::query::{ShapeCastOptions, ShapeCastStatus},
+    prelude::*,
+};
 use rustc_hash::FxHashMap;
 
 type EventReceiver<T> = crossbeam::channel::Receiver<T>;
@@ -45,6 +51,41 @@ struct EventCollector {
     collision: EventReceiver<RapierCollisionEvent>,
     contact_force: EventReceiver<RapierContactForceEvent>,
     collector: ChannelEventCollector,
+    /// Copy of the collision events for the typed collision handlers
+    routed: Option<Mutex<Vec<RapierCollisionEvent>>>,
+}
+
+impl EventHandler for EventCollector {
+    fn handle_collision_event(
+        &self,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        event: RapierCollisionEvent,
+        contact_pair: Option<&ContactPair>,
+    ) {
+        if let Some(routed) = &self.routed {
+            routed.lock().push(event);
+        }
+        self.collector
+            .handle_collision_event(bodies, colliders, event, contact_pair);
+    }
+
+    fn handle_contact_force_event(
+        &self,
+        dt: Real,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        contact_pair: &ContactPair,
+        total_force_magnitude: Real,
+    ) {
+        self.collector.handle_contact_force_event(
+            dt,
+            bodies,
+            colliders,
+            contact_pair,
+            total_force_magnitude,
+        );
+    }
 }
 
 impl Default for EventCollector {
@@ -56,6 +97,7 @@ impl Default for EventCollector {
             collision,
             contact_force,
             collector,
+            routed: None,
         }
     }
 }
@@ -81,10 +123,115 @@ pub struct EntityContactForceEvent {
     pub max_force_magnitude: f32,
 }
 
+/// [QueryFilter] that can additionally exclude all colliders of an entity, e.g. the caster itself
+#[derive(Copy, Clone, Default)]
+pub struct EntityQueryFilter<'a> {
+    pub filter: QueryFilter<'a>,
+    pub exclude_entity: Option<EntityId>,
+}
+
+impl<'a> EntityQueryFilter<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn filter(mut self, filter: QueryFilter<'a>) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn exclude_entity(mut self, entity: EntityId) -> Self {
+        self.exclude_entity = Some(entity);
+        self
+    }
+}
+
+impl<'a> From<QueryFilter<'a>> for EntityQueryFilter<'a> {
+    fn from(filter: QueryFilter<'a>) -> Self {
+        Self {
+            filter,
+            exclude_entity: None,
+        }
+    }
+}
+
+impl From<EntityId> for EntityQueryFilter<'_> {
+    fn from(entity: EntityId) -> Self {
+        Self::new().exclude_entity(entity)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayHit {
+    pub entity: EntityId,
+    pub collider: ColliderHandle,
+    pub point: Point2<f32>,
+    pub normal: Vector2<f32>,
+    /// Distance from the origin of the ray
+    pub toi: f32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShapeHit {
+    pub entity: EntityId,
+    pub collider: ColliderHandle,
+    /// Contact point on the hit collider in world space
+    pub point: Point2<f32>,
+    /// Normal of the hit collider in world space
+    pub normal: Vector2<f32>,
+    /// Time until the shape hits the collider when moving with its velocity
+    pub toi: f32,
+    pub status: ShapeCastStatus,
+}
+
+/// How [Physics::update] advances the simulation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PhysicsStepMode {
+    /// One step with the frame time
+    #[default]
+    Variable,
+    /// Accumulates the frame time and steps with a constant delta of `1 / hz`. At most
+    /// `max_substeps` steps are done per frame, the remaining time is dropped.
+    Fixed { hz: u32, max_substeps: u32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicsConfig {
+    pub mode: PhysicsStepMode,
+}
+
+impl PhysicsConfig {
+    pub fn fixed(hz: u32, max_substeps: u32) -> Self {
+        Self {
+            mode: PhysicsStepMode::Fixed { hz, max_substeps },
+        }
+    }
+}
+
+/// Parameters of the simulation without its bodies, see [Physics::settings]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicsSettings {
+    pub gravity: Vector2<f32>,
+    pub time_scale: f32,
+    pub config: PhysicsConfig,
+    pub integration_parameters: IntegrationParameters,
+}
+
+/// Copy of the simulation state taken with [Physics::snapshot]
+#[derive(Clone)]
+pub struct PhysicsSnapshot {
+    physics: Physics,
+}
+
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Physics {
     pub time_scale: f32,
     pub gravity: Vector2<f32>,
+    pub config: PhysicsConfig,
+    accumulator: f32,
     bodies: RigidBodySet,
     colliders: ColliderSet,
     query_pipeline: QueryPipeline,
@@ -92,6 +239,7 @@ pub struct Physics {
     multibody_joints: MultibodyJointSet,
     collider_mapping: ColliderMapping,
     rigid_body_mapping: RigidBodyMapping,
+    entity_rigid_bodies: FxHashMap<EntityId, RigidBodyHandle>,
 
     integration_parameters: IntegrationParameters,
     islands: IslandManager,
@@ -113,6 +261,7 @@ impl Clone for Physics {
             colliders: self.colliders.clone(),
             collider_mapping: self.collider_mapping.clone(),
             rigid_body_mapping: self.rigid_body_mapping.clone(),
+            entity_rigid_bodies: self.entity_rigid_bodies.clone(),
             query_pipeline: self.query_pipeline.clone(),
             gravity: self.gravity,
             integration_parameters: self.integration_parameters,
@@ -125,6 +274,8 @@ impl Clone for Physics {
             physics_pipeline: Default::default(),
             collector: Default::default(),
             time_scale: self.time_scale,
+            config: self.config,
+            accumulator: self.accumulator,
         }
     }
 }
@@ -146,8 +297,11 @@ impl Physics {
             collector: Default::default(),
             collider_mapping: Default::default(),
             rigid_body_mapping: Default::default(),
+            entity_rigid_bodies: Default::default(),
             gravity: Vector2::new(0.0, 0.0),
             time_scale: 1.0,
+            config: PhysicsConfig::default(),
+            accumulator: 0.0,
         }
     }
 
@@ -171,6 +325,8 @@ impl Physics {
         let rigid_body_handle = self.bodies.insert(rigid_body.clone());
         self.rigid_body_mapping
             .insert(rigid_body_handle, *entity_handle);
+        self.entity_rigid_bodies
+            .insert(*entity_handle, rigid_body_handle);
         for collider in colliders {
             let collider_handle = self.colliders.insert_with_parent(
                 collider.clone(),
@@ -187,7 +343,7 @@ impl Physics {
         &mut self,
         handle: RigidBodyHandle,
     ) -> Option<(RigidBody, Vec<Collider>)> {
-        self.rigid_body_mapping.remove(&handle);
+        self.unmap_rigid_body(handle);
         if let Some(rigid_body) = self.bodies.remove(
             handle,
             &mut self.islands,
@@ -213,6 +369,14 @@ impl Physics {
         None
     }
 
+    fn unmap_rigid_body(&mut self, handle: RigidBodyHandle) {
+        if let Some(entity) = self.rigid_body_mapping.remove(&handle) {
+            if self.entity_rigid_bodies.get(&entity) == Some(&handle) {
+                self.entity_rigid_bodies.remove(&entity);
+            }
+        }
+    }
+
     pub(crate) fn remove_collider(&mut self, collider: ColliderHandle) -> Option<Collider> {
         self.collider_mapping.remove(&collider);
         if let Some(collider) =
@@ -224,6 +388,28 @@ impl Physics {
         None
     }
 
+    /// Removes the rigid bodies and colliders of all entities for which `keep` returns false
+    pub(crate) fn retain_entities(&mut self, keep: impl Fn(EntityId) -> bool) {
+        let rigid_bodies = self
+            .rigid_body_mapping
+            .iter()
+            .filter(|(_, entity)| !keep(**entity))
+            .map(|(handle, _)| *handle)
+            .collect::<Vec<_>>();
+        for handle in rigid_bodies {
+            self.remove_rigid_body(handle);
+        }
+        let colliders = self
+            .collider_mapping
+            .iter()
+            .filter(|(_, entity)| !keep(**entity))
+            .map(|(handle, _)| *handle)
+            .collect::<Vec<_>>();
+        for handle in colliders {
+            self.remove_collider(handle);
+        }
+    }
+
     pub(crate) fn attach_collider(
         &mut self,
         rigid_body_handle: RigidBodyHandle,
@@ -247,9 +433,67 @@ impl Physics {
     }
 
     pub fn step(&mut self, delta: f32) -> CollectedEvents {
+        self.clear_events();
+        self.step_raw(delta * self.time_scale);
+        self.events()
+    }
+
+    /// Steps `substeps` times with exactly `dt`, ignoring the time scale. The events of all
+    /// substeps are collected.
+    pub fn step_fixed(&mut self, dt: f32, substeps: u32) -> CollectedEvents {
+        self.clear_events();
+        for _ in 0..substeps {
+            self.step_raw(dt);
+        }
+        self.events()
+    }
+
+    /// Advances the simulation by the frame time as configured in [Physics::config]. With
+    /// [PhysicsStepMode::Fixed] the same input always yields the same simulation, independent
+    /// of the frame rate. For bit-identical results across platforms additionally enable the
+    /// `deterministic_physics` feature.
+    pub fn update(&mut self, delta: f32) -> CollectedEvents {
+        match self.config.mode {
+            PhysicsStepMode::Variable => self.step(delta),
+            PhysicsStepMode::Fixed { hz, max_substeps } => {
+                let fixed_delta = 1.0 / hz as f32;
+                self.accumulator += delta * self.time_scale;
+                let steps = (self.accumulator / fixed_delta) as u32;
+                if steps > max_substeps {
+                    self.accumulator %= fixed_delta;
+                } else {
+                    // Rounding can leave a tiny negative remainder
+                    self.accumulator = (self.accumulator - steps as f32 * fixed_delta).max(0.0);
+                }
+                self.step_fixed(fixed_delta, steps.min(max_substeps))
+            }
+        }
+    }
+
+    /// Leftover time of [PhysicsStepMode::Fixed] as a fraction of the fixed delta, used to
+    /// interpolate between the last two steps. Always in `[0, 1)` and `0.0` with
+    /// [PhysicsStepMode::Variable].
+    pub fn interpolation_alpha(&self) -> f32 {
+        // Largest f32 below 1.0
+        const MAX_ALPHA: f32 = 1.0 - f32::EPSILON / 2.0;
+        match self.config.mode {
+            PhysicsStepMode::Variable => 0.0,
+            PhysicsStepMode::Fixed { hz, .. } => (self.accumulator * hz as f32).min(MAX_ALPHA),
+        }
+    }
+
+    pub fn set_config(&mut self, config: PhysicsConfig) {
+        self.config = config;
+        self.accumulator = 0.0;
+    }
+
+    fn clear_events(&self) {
         while let Ok(_event) = self.collector.collision.try_recv() {}
         while let Ok(_event) = self.collector.contact_force.try_recv() {}
-        self.integration_parameters.dt = delta * self.time_scale;
+    }
+
+    fn step_raw(&mut self, dt: f32) {
+        self.integration_parameters.dt = dt;
         self.physics_pipeline.step(
             &self.gravity,
             &self.integration_parameters,
@@ -263,9 +507,66 @@ impl Physics {
             &mut self.ccd_solver,
             Some(&mut self.query_pipeline),
             &(),
-            &self.collector.collector,
+            &self.collector,
         );
-        self.events()
+    }
+
+    /// Keeps a copy of every collision event until [Physics::take_routed_collisions] is called
+    pub(crate) fn enable_collision_routing(&mut self) {
+        self.collector.routed.get_or_insert_with(Default::default);
+    }
+
+    pub(crate) fn take_routed_collisions(&mut self) -> Vec<RapierCollisionEvent> {
+        self.collector
+            .routed
+            .as_mut()
+            .map(|routed| std::mem::take(routed.get_mut()))
+            .unwrap_or_default()
+    }
+
+    pub fn settings(&self) -> PhysicsSettings {
+        PhysicsSettings {
+            gravity: self.gravity,
+            time_scale: self.time_scale,
+            config: self.config,
+            integration_parameters: self.integration_parameters,
+        }
+    }
+
+    pub fn apply_settings(&mut self, settings: &PhysicsSettings) {
+        self.gravity = settings.gravity;
+        self.time_scale = settings.time_scale;
+        self.config = settings.config;
+        self.integration_parameters = settings.integration_parameters;
+    }
+
+    pub fn snapshot(&self) -> PhysicsSnapshot {
+        PhysicsSnapshot {
+            physics: self.clone(),
+        }
+    }
+
+    /// Resets the simulation to a snapshot. All handles are the same as when the snapshot was
+    /// taken, so stepping afterwards gives the same results as the first time.
+    pub fn restore(&mut self, snapshot: &PhysicsSnapshot) {
+        let snapshot = snapshot.physics.clone();
+        self.time_scale = snapshot.time_scale;
+        self.config = snapshot.config;
+        self.accumulator = snapshot.accumulator;
+        self.gravity = snapshot.gravity;
+        self.bodies = snapshot.bodies;
+        self.colliders = snapshot.colliders;
+        self.query_pipeline = snapshot.query_pipeline;
+        self.impulse_joints = snapshot.impulse_joints;
+        self.multibody_joints = snapshot.multibody_joints;
+        self.collider_mapping = snapshot.collider_mapping;
+        self.rigid_body_mapping = snapshot.rigid_body_mapping;
+        self.entity_rigid_bodies = snapshot.entity_rigid_bodies;
+        self.integration_parameters = snapshot.integration_parameters;
+        self.islands = snapshot.islands;
+        self.broad_phase = snapshot.broad_phase;
+        self.narrow_phase = snapshot.narrow_phase;
+        self.ccd_solver = snapshot.ccd_solver;
     }
 
     pub fn events(&self) -> CollectedEvents {
@@ -278,6 +579,7 @@ impl Physics {
     pub(crate) fn remove_no_maintain_rigid_body(&mut self, component: &RigidBodyComponent) {
         match component.status {
             RigidBodyComponentStatus::Initialized { rigid_body_handle } => {
+                self.unmap_rigid_body(rigid_body_handle);
                 if let Some(rigid_body) = self.bodies.remove(
                     rigid_body_handle,
                     &mut self.islands,
@@ -324,6 +626,38 @@ impl Physics {
         self.impulse_joints.remove(joint, true)
     }
 
+    /// Joins the rigid bodies of two entities. Returns [None] if one of the entities has no
+    /// rigid body. The joints of a rigid body are removed together with the rigid body.
+    pub fn add_joint(
+        &mut self,
+        entity1: EntityId,
+        entity2: EntityId,
+        joint: impl Into<GenericJoint>,
+    ) -> Option<ImpulseJointHandle> {
+        let body_handle1 = self.rigid_body_handle(entity1)?;
+        let body_handle2 = self.rigid_body_handle(entity2)?;
+        Some(self.create_joint(body_handle1, body_handle2, joint))
+    }
+
+    /// All joints attached to the rigid body of an entity
+    pub fn joints_of(
+        &self,
+        entity: EntityId,
+    ) -> impl Iterator<Item = (ImpulseJointHandle, &ImpulseJoint)> {
+        self.rigid_body_handle(entity)
+            .into_iter()
+            .flat_map(|body_handle| self.impulse_joints.attached_joints(body_handle))
+            .map(|(_, _, handle, joint)| (handle, joint))
+    }
+
+    pub fn entity_from_rigid_body(&self, body_handle: &RigidBodyHandle) -> Option<&EntityId> {
+        self.rigid_body_mapping.get(body_handle)
+    }
+
+    pub fn rigid_body_handle(&self, entity: EntityId) -> Option<RigidBodyHandle> {
+        self.entity_rigid_bodies.get(&entity).copied()
+    }
+
     pub fn cast_ray(
         &self,
         ray: &Ray,
@@ -366,6 +700,29 @@ impl Physics {
         None
     }
 
+    /// Applies the impulses of a character with the given mass to the dynamic rigid bodies it
+    /// collided with
+    pub fn solve_character_collision_impulses<'a>(
+        &mut self,
+        controller: &KinematicCharacterController,
+        shape: &dyn Shape,
+        mass: f32,
+        collisions: impl IntoIterator<Item = &'a CharacterCollision>,
+        dt: f32,
+        filter: QueryFilter,
+    ) {
+        controller.solve_character_collision_impulses(
+            dt,
+            &mut self.bodies,
+            &self.colliders,
+            &self.query_pipeline,
+            shape,
+            mass,
+            collisions,
+            filter,
+        );
+    }
+
     pub fn cast_ray_and_get_normal(
         &self,
         ray: &Ray,
@@ -388,6 +745,109 @@ impl Physics {
         None
     }
 
+    fn with_entity_filter<R>(
+        &self,
+        filter: EntityQueryFilter,
+        query: impl FnOnce(QueryFilter) -> R,
+    ) -> R {
+        let Some(exclude) = filter.exclude_entity else {
+            return query(filter.filter);
+        };
+        let inner = filter.filter.predicate;
+        let predicate = |handle: ColliderHandle, collider: &Collider| {
+            self.entity_from_collider(&handle) != Some(&exclude)
+                && inner.map_or(true, |predicate| predicate(handle, collider))
+        };
+        query(QueryFilter {
+            predicate: Some(&predicate),
+            ..filter.filter
+        })
+    }
+
+    /// Closest hit of a ray. `direction` gets normalized, so the toi of the hit is the distance to
+    /// `origin`.
+    pub fn raycast<'a>(
+        &self,
+        origin: Point2<f32>,
+        direction: Vector2<f32>,
+        max_toi: f32,
+        filter: impl Into<EntityQueryFilter<'a>>,
+    ) -> Option<RayHit> {
+        let ray = Ray::new(origin, direction.normalize());
+        self.with_entity_filter(filter.into(), |filter| {
+            let (collider, intersection) = self.query_pipeline.cast_ray_and_get_normal(
+                &self.bodies,
+                &self.colliders,
+                &ray,
+                max_toi,
+                true,
+                filter,
+            )?;
+            Some(RayHit {
+                entity: *self.entity_from_collider(&collider)?,
+                collider,
+                point: ray.point_at(intersection.time_of_impact),
+                normal: intersection.normal,
+                toi: intersection.time_of_impact,
+            })
+        })
+    }
+
+    /// All hits of a ray sorted from near to far, see [Physics::raycast]
+    pub fn raycast_all<'a>(
+        &self,
+        origin: Point2<f32>,
+        direction: Vector2<f32>,
+        max_toi: f32,
+        filter: impl Into<EntityQueryFilter<'a>>,
+    ) -> Vec<RayHit> {
+        let ray = Ray::new(origin, direction.normalize());
+        let mut hits = vec![];
+        self.with_entity_filter(filter.into(), |filter| {
+            self.intersections_with_ray(
+                &ray,
+                max_toi,
+                true,
+                filter,
+                |entity, collider, intersection| {
+                    hits.push(RayHit {
+                        entity,
+                        collider,
+                        point: ray.point_at(intersection.time_of_impact),
+                        normal: intersection.normal,
+                        toi: intersection.time_of_impact,
+                    });
+                    true
+                },
+            )
+        });
+        hits.sort_by(|a, b| a.toi.total_cmp(&b.toi));
+        hits
+    }
+
+    pub fn shapecast<'a>(
+        &self,
+        shape: &dyn Shape,
+        position: &Isometry2<f32>,
+        velocity: &Vector2<f32>,
+        options: ShapeCastOptions,
+        filter: impl Into<EntityQueryFilter<'a>>,
+    ) -> Option<ShapeHit> {
+        self.with_entity_filter(filter.into(), |filter| {
+            let (entity, collider, hit) =
+                self.cast_shape(shape, position, velocity, options, filter)?;
+            let collider_position = self.colliders.get(collider)?.position();
+            Some(ShapeHit {
+                entity,
+                collider,
+                point: collider_position * hit.witness1,
+                normal: collider_position.rotation * hit.normal1.into_inner(),
+                toi: hit.time_of_impact,
+                status: hit.status,
+            })
+        })
+    }
+
     pub fn intersects_point(&self, collider_handle: ColliderHandle, point: Point2<f32>) -> bool {
         if let Some(collider) = self.collider(collider_handle) {
             return collider.shape().contains_point(collider.position(), &point);
@@ -539,6 +999,14 @@ impl Physics {
         &self.narrow_phase
     }
 
+    pub fn impulse_joints(&self) -> &ImpulseJointSet {
+        &self.impulse_joints
+    }
+
+    pub fn multibody_joints(&self) -> &MultibodyJointSet {
+        &self.multibody_joints
+    }
+
     pub fn joint(&self, joint: ImpulseJointHandle) -> Option<&ImpulseJoint> {
         self.impulse_joints.get(joint)
     }
@@ -563,3 +1031,277 @@ impl Physics {
         self.time_scale = time_scale;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::World;
+
+    fn add_box(physics: &mut Physics, world: &mut World, x: f32) -> (EntityId, RigidBodyHandle) {
+        let entity = world.add_entity(());
+        let handle = physics.add_rigid_body(
+            RigidBodyBuilder::dynamic()
+                .translation(Vector2::new(x, 0.0))
+                .build(),
+            vec![ColliderBuilder::cuboid(0.5, 0.5).build()],
+            &entity,
+        );
+        (entity, handle)
+    }
+
+    #[test]
+    fn rigid_body_handle_follows_entities() {
+        let mut world = World::new();
+        let mut physics = Physics::new();
+        let (a, handle_a) = add_box(&mut physics, &mut world, 0.0);
+        let (b, handle_b) = add_box(&mut physics, &mut world, 2.0);
+        assert_eq!(physics.rigid_body_handle(a), Some(handle_a));
+        assert_eq!(physics.rigid_body_handle(b), Some(handle_b));
+
+        physics
+            .add_joint(a, b, RevoluteJointBuilder::new())
+            .unwrap();
+        assert_eq!(physics.joints_of(a).count(), 1);
+        assert_eq!(physics.joints_of(b).count(), 1);
+
+        physics.remove_rigid_body(handle_a);
+        assert_eq!(physics.rigid_body_handle(a), None);
+        assert_eq!(physics.rigid_body_handle(b), Some(handle_b));
+        assert_eq!(physics.joints_of(b).count(), 0);
+        assert!(physics.add_joint(a, b, FixedJointBuilder::new()).is_none());
+    }
+
+    #[test]
+    fn restore_brings_back_rigid_body_handles() {
+        let mut world = World::new();
+        let mut physics = Physics::new();
+        let (entity, handle) = add_box(&mut physics, &mut world, 0.0);
+        let snapshot = physics.snapshot();
+        physics.retain_entities(|_| false);
+        assert_eq!(physics.rigid_body_handle(entity), None);
+        physics.restore(&snapshot);
+        assert_eq!(physics.rigid_body_handle(entity), Some(handle));
+    }
+
+    /// Boxes falling onto a floor and onto each other
+    fn pile(physics: &mut Physics, world: &mut World) -> Vec<RigidBodyHandle> {
+        physics.set_gravity(Vector2::new(0.0, -9.81));
+        let floor = world.add_entity(());
+        physics.add_collider(
+            &floor,
+            ColliderBuilder::cuboid(10.0, 0.5)
+                .translation(Vector2::new(0.0, -1.0))
+                .build(),
+        );
+        (0..12)
+            .map(|i| {
+                let entity = world.add_entity(());
+                physics.add_rigid_body(
+                    RigidBodyBuilder::dynamic()
+                        .translation(Vector2::new((i % 4) as f32 * 0.7, 1.0 + i as f32))
+                        .rotation(i as f32 * 0.3)
+                        .build(),
+                    vec![ColliderBuilder::cuboid(0.3, 0.3).build()],
+                    &entity,
+                )
+            })
+            .collect()
+    }
+
+    /// Bits of the positions and velocities of the bodies, equal only if the states are identical
+    fn state(physics: &Physics, bodies: &[RigidBodyHandle]) -> Vec<u32> {
+        bodies
+            .iter()
+            .flat_map(|handle| {
+                let body = physics.rigid_body(*handle).unwrap();
+                let position = body.position();
+                [
+                    position.translation.x,
+                    position.translation.y,
+                    position.rotation.re,
+                    position.rotation.im,
+                    body.linvel().x,
+                    body.linvel().y,
+                    body.angvel(),
+                ]
+            })
+            .map(f32::to_bits)
+            .collect()
+    }
+
+    #[test]
+    fn restored_snapshot_steps_identically() {
+        const STEPS: usize = 120;
+        let mut world = World::new();
+        let mut physics = Physics::new();
+        let bodies = pile(&mut physics, &mut world);
+        for _ in 0..30 {
+            physics.step(1.0 / 60.0);
+        }
+
+        let snapshot = physics.snapshot();
+        let mut first = Vec::with_capacity(STEPS);
+        for _ in 0..STEPS {
+            physics.step(1.0 / 60.0);
+            first.push(state(&physics, &bodies));
+        }
+
+        physics.restore(&snapshot);
+        for (step, expected) in first.iter().enumerate() {
+            physics.step(1.0 / 60.0);
+            assert_eq!(
+                &state(&physics, &bodies),
+                expected,
+                "Diverged at step {step}"
+            );
+        }
+    }
+
+    #[test]
+    fn same_input_steps_deterministically() {
+        let mut worlds = [World::new(), World::new()];
+        let mut simulations = [Physics::new(), Physics::new()];
+        let bodies = [
+            pile(&mut simulations[0], &mut worlds[0]),
+            pile(&mut simulations[1], &mut worlds[1]),
+        ];
+
+        for step in 0..1000 {
+            for (physics, bodies) in simulations.iter_mut().zip(&bodies) {
+                let body = physics.rigid_body_mut(bodies[step % bodies.len()]).unwrap();
+                body.apply_impulse(Vector2::new((step % 7) as f32 * 0.05 - 0.15, 0.2), true);
+                physics.step(1.0 / 60.0);
+            }
+            assert_eq!(
+                state(&simulations[0], &bodies[0]),
+                state(&simulations[1], &bodies[1]),
+                "Diverged at step {step}"
+            );
+        }
+    }
+
+    #[test]
+    fn fixed_mode_is_independent_of_the_frame_times() {
+        const HZ: u32 = 64;
+        // Multiples of 1 / 256 add up exactly, every sequence sums to 1000 fixed steps
+        let sequences: [&[f32]; 3] = [
+            &[1.0 / 64.0],
+            &[3.0 / 128.0, 1.0 / 128.0],
+            &[1.0 / 256.0, 7.0 / 256.0, 0.0, 5.0 / 256.0, 3.0 / 256.0],
+        ];
+        let mut results = Vec::new();
+        for frame_times in sequences {
+            let mut world = World::new();
+            let mut physics = Physics::new();
+            physics.set_config(PhysicsConfig::fixed(HZ, 8));
+            let bodies = pile(&mut physics, &mut world);
+            let frames_per_cycle = frame_times.len() as u32;
+            let steps_per_cycle = (frame_times.iter().sum::<f32>() * HZ as f32).round() as u32;
+            for delta in frame_times
+                .iter()
+                .cycle()
+                .take((1000 / steps_per_cycle * frames_per_cycle) as usize)
+            {
+                physics.update(*delta);
+            }
+            assert_eq!(physics.interpolation_alpha(), 0.0);
+            results.push(state(&physics, &bodies));
+        }
+
+        let mut world = World::new();
+        let mut stepped = Physics::new();
+        let bodies = pile(&mut stepped, &mut world);
+        stepped.step_fixed(1.0 / HZ as f32, 1000);
+        for result in &results {
+            assert_eq!(result, &state(&stepped, &bodies));
+        }
+    }
+
+    #[test]
+    fn fixed_mode_caps_substeps() {
+        let mut worlds = [World::new(), World::new()];
+        let mut updated = Physics::new();
+        updated.set_config(PhysicsConfig::fixed(64, 4));
+        let mut stepped = Physics::new();
+        let bodies = [
+            pile(&mut updated, &mut worlds[0]),
+            pile(&mut stepped, &mut worlds[1]),
+        ];
+
+        // A one second hitch only advances the simulation by 4 steps and drops the rest
+        updated.update(1.0);
+        stepped.step_fixed(1.0 / 64.0, 4);
+        assert_eq!(state(&updated, &bodies[0]), state(&stepped, &bodies[1]));
+        assert_eq!(updated.interpolation_alpha(), 0.0);
+
+        updated.update(0.5 / 64.0);
+        assert_eq!(updated.interpolation_alpha(), 0.5);
+        assert_eq!(state(&updated, &bodies[0]), state(&stepped, &bodies[1]));
+    }
+
+    #[test]
+    fn step_fixed_ignores_the_time_scale() {
+        let mut worlds = [World::new(), World::new()];
+        let mut scaled = Physics::new();
+        scaled.time_scale = 0.25;
+        let mut unscaled = Physics::new();
+        let bodies = [
+            pile(&mut scaled, &mut worlds[0]),
+            pile(&mut unscaled, &mut worlds[1]),
+        ];
+        scaled.step_fixed(1.0 / 60.0, 10);
+        for _ in 0..10 {
+            unscaled.step(1.0 / 60.0);
+        }
+        assert_eq!(state(&scaled, &bodies[0]), state(&unscaled, &bodies[1]));
+    }
+
+    #[test]
+    fn interpolation_alpha_stays_below_one() {
+        let mut physics = Physics::new();
+        assert_eq!(physics.interpolation_alpha(), 0.0);
+        physics.set_config(PhysicsConfig::fixed(60, 5));
+        let mut random = crate::random::SceneRandom::new(34);
+        for _ in 0..10_000 {
+            physics.time_scale = random.gen_range(0.0..3.0);
+            physics.update(random.gen_range(0.0..0.1));
+            let alpha = physics.interpolation_alpha();
+            assert!((0.0..1.0).contains(&alpha), "Alpha out of range: {alpha}");
+        }
+    }
+
+    #[test]
+    fn raycast_hits_are_sorted_by_toi() {
+        let mut world = World::new();
+        let mut physics = Physics::new();
+        let (far, _) = add_box(&mut physics, &mut world, 9.0);
+        let (near, _) = add_box(&mut physics, &mut world, 3.0);
+        let (middle, _) = add_box(&mut physics, &mut world, 6.0);
+        // Updates the query pipeline, nothing moves without gravity
+        physics.step(1.0 / 60.0);
+
+        let origin = Point2::new(0.0, 0.0);
+        let direction = Vector2::new(2.0, 0.0);
+        let hits = physics.raycast_all(origin, direction, 100.0, EntityQueryFilter::new());
+        assert_eq!(
+            hits.iter().map(|hit| hit.entity).collect::<Vec<_>>(),
+            vec![near, middle, far]
+        );
+        assert!(hits.windows(2).all(|pair| pair[0].toi <= pair[1].toi));
+        for (hit, expected) in hits.iter().zip([2.5, 5.5, 8.5]) {
+            assert!((hit.toi - expected).abs() < 1e-4);
+            assert!((hit.point - Point2::new(expected, 0.0)).norm() < 1e-4);
+            assert!((hit.normal - Vector2::new(-1.0, 0.0)).norm() < 1e-4);
+        }
+
+        let closest = physics
+            .raycast(origin, direction, 100.0, EntityQueryFilter::new())
+            .unwrap();
+        assert_eq!(closest.entity, near);
+        let behind = physics.raycast(origin, direction, 100.0, near).unwrap();
+        assert_eq!(behind.entity, middle);
+        assert!(physics
+            .raycast(origin, direction, 2.0, EntityQueryFilter::new())
+            .is_none());
+    }
+}
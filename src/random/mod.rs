@@ -1,9 +1,17 @@
+mod scene_random;
+
 pub use rand;
 pub use rand::random;
 use rand::{distributions::uniform, thread_rng, Rng};
+pub use scene_random::*;
+
+/// Uses the random generator of the thread, so results differ between runs. Use `ctx.random`
+/// for reproducible results.
 pub fn gen_range<T: uniform::SampleUniform, R: uniform::SampleRange<T>>(range: R) -> T {
     thread_rng().gen_range(range)
 }
+
+/// Not deterministic either, see [gen_range]
 pub fn gen_bool(p: f64) -> bool {
     thread_rng().gen_bool(p)
 }
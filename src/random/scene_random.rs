@@ -0,0 +1,96 @@
+use std::hash::{Hash, Hasher};
+
+use rand::{distributions::uniform, seq::SliceRandom, Rng, RngCore};
+use rustc_hash::FxHasher;
+
+/// Deterministic random generator of a scene, accessible as `ctx.random`. The same seed gives
+/// the same sequence on every platform, and the state is part of a serialized scene with
+/// `SceneSection::Random` so a loaded save continues it.
+/// Implements [RngCore], so everything from [rand] works with it as well.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SceneRandom {
+    seed: u64,
+    state: u64,
+}
+
+impl Default for SceneRandom {
+    fn default() -> Self {
+        Self::from_entropy()
+    }
+}
+
+impl SceneRandom {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, state: seed }
+    }
+
+    /// Random seed, which can be read with [SceneRandom::seed] to reproduce the run
+    pub fn from_entropy() -> Self {
+        Self::new(rand::random())
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn gen_range<T: uniform::SampleUniform, R: uniform::SampleRange<T>>(
+        &mut self,
+        range: R,
+    ) -> T {
+        Rng::gen_range(self, range)
+    }
+
+    pub fn gen_bool(&mut self, p: f64) -> bool {
+        Rng::gen_bool(self, p)
+    }
+
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        slice.shuffle(self);
+    }
+
+    /// [None] if the slice is empty
+    pub fn pick<'a, T>(&mut self, slice: &'a [T]) -> Option<&'a T> {
+        slice.choose(self)
+    }
+
+    /// Independent generator for `stream`, e.g. an entity or a system name. It only depends on
+    /// the seed and the stream, not on how many numbers were drawn before, so adding random
+    /// calls in one place doesn't change the numbers somewhere else.
+    pub fn fork(&self, stream: impl Hash) -> Self {
+        let mut hasher = FxHasher::default();
+        self.seed.hash(&mut hasher);
+        stream.hash(&mut hasher);
+        Self::new(Self::mix(hasher.finish()))
+    }
+
+    // SplitMix64, small and fast with good enough quality for games
+    fn mix(mut z: u64) -> u64 {
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl RngCore for SceneRandom {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        Self::mix(self.state)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use crate::{
+    context::{Context, RenderContext},
+    ecs::System,
+    graphics::{AssetBatch, AssetBatchHandle, Color, ColorInstance2D, Instance2D, RenderEncoder},
+    math::{Isometry2, Vector2},
+    scene::{Scene, SceneCreator},
+};
+
+type SceneFactory = Box<dyn FnOnce() -> Scene>;
+
+/// Scene that loads an [AssetBatch] while drawing a progress bar and then switches to the
+/// scene created by `next_scene`. The next scene is only created once all assets are loaded.
+pub struct LoadingScene {
+    batch: AssetBatch,
+    next_scene_id: u32,
+    next_scene: SceneFactory,
+    pub background: Color,
+    pub track: Color,
+    pub bar: Color,
+}
+
+impl LoadingScene {
+    const INSTANCES: &'static str = "shura_loading_bar";
+
+    pub fn new(
+        batch: AssetBatch,
+        next_scene_id: u32,
+        next_scene: impl FnOnce() -> Scene + 'static,
+    ) -> Self {
+        Self {
+            batch,
+            next_scene_id,
+            next_scene: Box::new(next_scene),
+            background: Color::BLACK,
+            track: Color::DARK_GRAY,
+            bar: Color::WHITE,
+        }
+    }
+
+    pub fn background(mut self, background: Color) -> Self {
+        self.background = background;
+        self
+    }
+
+    pub fn track(mut self, track: Color) -> Self {
+        self.track = track;
+        self
+    }
+
+    pub fn bar(mut self, bar: Color) -> Self {
+        self.bar = bar;
+        self
+    }
+}
+
+impl From<LoadingScene> for Scene {
+    fn from(loading: LoadingScene) -> Self {
+        let LoadingScene {
+            batch,
+            next_scene_id,
+            next_scene,
+            background,
+            track,
+            bar,
+        } = loading;
+        let handle: Rc<RefCell<Option<AssetBatchHandle>>> = Default::default();
+        let next_scene = RefCell::new(Some(next_scene));
+        let progress = Rc::new(Cell::new(0.0));
+
+        Scene::new()
+            .system(System::setup({
+                let handle = handle.clone();
+                move |ctx: &mut Context| {
+                    *handle.borrow_mut() = Some(batch.start(ctx.tasks));
+                }
+            }))
+            .system(System::update({
+                let progress = progress.clone();
+                move |ctx: &mut Context| {
+                    let handle = handle.borrow();
+                    let Some(handle) = handle.as_ref() else {
+                        return;
+                    };
+                    progress.set(handle.progress());
+                    if !handle.is_finished() {
+                        return;
+                    }
+                    if let Some(next_scene) = next_scene.borrow_mut().take() {
+                        ctx.scenes.add(next_scene_id, next_scene());
+                        ctx.scenes.set_next_active_scene(next_scene_id);
+                    }
+                }
+            }))
+            .system(System::render(
+                move |ctx: &RenderContext, encoder: &mut RenderEncoder| {
+                    let size = Vector2::new(0.6, 0.04);
+                    let filled = size.x * progress.get();
+                    let instances =
+                        ctx.assets
+                            .write_instances(LoadingScene::INSTANCES, false, |data| {
+                                data.push(ColorInstance2D::new(Isometry2::default(), size, track));
+                                data.push(Instance2D::new(
+                                    Isometry2::translation((filled - size.x) / 2.0, 0.0),
+                                    Vector2::new(filled, size.y),
+                                    bar,
+                                ));
+                            });
+                    encoder.renderer2d(Some(background)).draw_color(
+                        &instances,
+                        &ctx.default_assets.position_mesh,
+                        &ctx.default_assets.unit_camera.0,
+                    );
+                },
+            ))
+    }
+}
@@ -1,5 +1,11 @@
+mod loading_scene;
+mod plugin;
 mod scene;
 mod scene_manager;
+mod transition;
 
+pub use loading_scene::*;
+pub use plugin::*;
 pub use scene::*;
 pub use scene_manager::*;
+pub use transition::*;
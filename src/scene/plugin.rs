@@ -0,0 +1,71 @@
+use std::any::{Any, TypeId};
+
+use rustc_hash::FxHashMap;
+
+use crate::scene::Plugin;
+
+/// Identifies a [Plugin] by its type, used by [Plugin::dependencies]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PluginId {
+    id: TypeId,
+    name: &'static str,
+}
+
+impl PluginId {
+    pub fn of<P: Plugin>() -> Self {
+        Self {
+            id: TypeId::of::<P>(),
+            name: std::any::type_name::<P>(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// Resources of the plugins of a scene, one value per type. Accessible through
+/// [Context::plugin_data](crate::context::Context::plugin_data) and
+/// [RenderContext::plugin_data](crate::context::RenderContext::plugin_data).
+#[derive(Default)]
+pub struct PluginData {
+    data: FxHashMap<TypeId, Box<dyn Any>>,
+}
+
+impl PluginData {
+    pub fn insert<T: 'static>(&mut self, data: T) {
+        self.data.insert(TypeId::of::<T>(), Box::new(data));
+    }
+
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.data
+            .remove(&TypeId::of::<T>())
+            .map(|data| *data.downcast::<T>().unwrap())
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.data.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn try_get<T: 'static>(&self) -> Option<&T> {
+        self.data
+            .get(&TypeId::of::<T>())
+            .map(|data| data.downcast_ref::<T>().unwrap())
+    }
+
+    pub fn try_get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        self.data
+            .get_mut(&TypeId::of::<T>())
+            .map(|data| data.downcast_mut::<T>().unwrap())
+    }
+
+    pub fn get<T: 'static>(&self) -> &T {
+        self.try_get()
+            .unwrap_or_else(|| panic!("Plugin data {} does not exist!", std::any::type_name::<T>()))
+    }
+
+    pub fn get_mut<T: 'static>(&mut self) -> &mut T {
+        self.try_get_mut()
+            .unwrap_or_else(|| panic!("Plugin data {} does not exist!", std::any::type_name::<T>()))
+    }
+}
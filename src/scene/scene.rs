@@ -1,27 +1,63 @@
+use std::rc::Rc;
+
 use crate::{
-    ecs::{System, SystemManager, World},
+    context::Context,
+    ecs::{
+        EntityCommands, RenderPassConfig, SceneState, StateExt, System, SystemManager, World,
+    },
     graphics::{
-        CameraViewSelection, PerspectiveCamera3D, ScreenConfig, WorldCamera2D, WorldCamera3D,
-        WorldCameraScaling,
+        CameraViewSelection, Lights3D, PerspectiveCamera3D, ScreenConfig, Sky,
+        WorldCamera2D, WorldCamera3D, WorldCameraScaling,
     },
     math::Vector2,
+    random::SceneRandom,
+    scene::{PluginData, PluginId},
     tasks::TaskManager,
 };
 
 #[cfg(feature="physics")]
-use crate::physics::Physics;
+use crate::{
+    ecs::{Component, EntityId},
+    physics::{EntityCollisionEvent, Physics, PhysicsConfig},
+};
 
-pub trait Plugin {
+pub trait Plugin: 'static {
     fn init<S: SceneCreator>(&mut self, scene: S) -> S;
+    /// Runs once after all setup systems of the scene, e.g. to load assets
+    fn finish(&self, _ctx: &mut Context) {}
+    /// Runs after the end systems when the scene is removed or the app closes
+    fn cleanup(&self, _ctx: &mut Context) {}
+    /// Plugins that have to be added to the same scene, checked when the scene is added
+    fn dependencies(&self) -> Vec<PluginId> {
+        Vec::new()
+    }
 }
 
 pub trait SceneCreator {
     fn scene(&mut self) -> &mut Scene;
-    fn plugin(self, mut plugin: impl Plugin) -> Self
+    /// Panics if the plugin is already added to the scene
+    fn plugin<P: Plugin>(mut self, mut plugin: P) -> Self
+    where
+        Self: Sized,
+    {
+        self.scene()
+            .systems
+            .register_plugin(PluginId::of::<P>(), plugin.dependencies());
+        let mut scene = plugin.init(self);
+        let plugin = Rc::new(plugin);
+        let finish = plugin.clone();
+        let systems = &mut scene.scene().systems;
+        systems.register_plugin_finish(Box::new(move |ctx| finish.finish(ctx)));
+        systems.register_plugin_cleanup(Box::new(move |ctx, _| plugin.cleanup(ctx)));
+        scene
+    }
+    /// Stores a resource of a plugin, see [PluginData]
+    fn plugin_data<T: 'static>(mut self, data: T) -> Self
     where
         Self: Sized,
     {
-        plugin.init(self)
+        self.scene().plugin_data.insert(data);
+        self
     }
     fn system(mut self, system: System) -> Self
     where
@@ -30,27 +66,78 @@ pub trait SceneCreator {
         self.scene().systems.register_system(system);
         self
     }
+    /// Registers a named pass that [System::render_in] systems can render in. Cycles in the
+    /// ordering of the passes panic when the scene is added.
+    fn render_pass(mut self, pass: RenderPassConfig) -> Self
+    where
+        Self: Sized,
+    {
+        self.scene().systems.register_render_pass(pass);
+        self
+    }
+    /// Adds a [SceneState], its [System::on_enter] systems run before the first update
+    fn state<S: SceneState>(mut self, initial: S) -> Self
+    where
+        Self: Sized,
+    {
+        self.scene().world.insert_state(initial);
+        self
+    }
+    /// Seeds `ctx.random`, which gets a random seed otherwise
+    fn seed(mut self, seed: u64) -> Self
+    where
+        Self: Sized,
+    {
+        self.scene().random = SceneRandom::new(seed);
+        self
+    }
+    #[cfg(feature = "physics")]
+    fn physics_config(mut self, config: PhysicsConfig) -> Self
+    where
+        Self: Sized,
+    {
+        self.scene().physics.set_config(config);
+        self
+    }
+    /// Calls `handler` after the update systems for every collision between an entity with
+    /// component `A` and an entity with component `B`, no matter which collider rapier reported
+    /// first. All events stay available through [CollectedEvents](crate::physics::CollectedEvents).
+    #[cfg(feature = "physics")]
+    fn collision_handler<A: Component, B: Component>(
+        mut self,
+        handler: impl Fn(&mut Context, EntityId, EntityId, EntityCollisionEvent) + 'static,
+    ) -> Self
+    where
+        Self: Sized,
+    {
+        let scene = self.scene();
+        scene.physics.enable_collision_routing();
+        scene.systems.collision_handlers.register::<A, B>(handler);
+        self
+    }
 }
 
-#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 #[non_exhaustive]
 pub struct Scene {
     pub(crate) render_entities: bool,
     pub(crate) screen_config: ScreenConfig,
     pub(crate) world_camera2d: WorldCamera2D,
     pub(crate) world_camera3d: WorldCamera3D,
+    pub(crate) lights3d: Lights3D,
+    pub(crate) sky: Sky,
     pub(crate) world: World,
     #[cfg(feature="physics")]
     pub(crate) physics: Physics,
-    #[cfg_attr(feature = "serde", serde(skip))]
-    #[cfg_attr(feature = "serde", serde(default))]
     pub(crate) started: bool,
-    #[cfg_attr(feature = "serde", serde(skip))]
-    #[cfg_attr(feature = "serde", serde(default = "SystemManager::new"))]
     pub(crate) systems: SystemManager,
-    #[cfg_attr(feature = "serde", serde(skip))]
-    #[cfg_attr(feature = "serde", serde(default = "TaskManager::new"))]
+    pub(crate) plugin_data: PluginData,
     pub(crate) tasks: TaskManager,
+    pub(crate) random: SceneRandom,
+    #[cfg(feature = "net")]
+    pub(crate) net: crate::tasks::Net,
+    pub(crate) commands: EntityCommands,
+    #[cfg(feature = "animation")]
+    pub(crate) tweens: crate::animation::TweenManager,
 }
 
 impl Default for Scene {
@@ -67,15 +154,24 @@ impl Scene {
             // entities: EntityManager::new(),
             // groups: EntityGroupManager::new(),
             systems: SystemManager::new(),
+            plugin_data: PluginData::default(),
             screen_config: ScreenConfig::new(),
             render_entities: true,
             #[cfg(feature="physics")]
             physics: Physics::new(),
             tasks: TaskManager::new(),
+            random: SceneRandom::from_entropy(),
+            #[cfg(feature = "net")]
+            net: crate::tasks::Net::new(),
+            commands: EntityCommands::new(),
+            #[cfg(feature = "animation")]
+            tweens: crate::animation::TweenManager::new(),
             world_camera3d: WorldCamera3D::new(
                 window_size,
                 CameraViewSelection::PerspectiveCamera3D(PerspectiveCamera3D::default()),
             ),
+            lights3d: Lights3D::default(),
+            sky: Sky::default(),
 
             world_camera2d: WorldCamera2D::new(
                 window_size,
@@ -1,12 +1,33 @@
-use crate::scene::Scene;
+use crate::{
+    scene::{ActiveTransition, Scene, Transition},
+    time::Duration,
+};
 use rustc_hash::FxHashMap;
 use std::{cell::RefCell, rc::Rc};
 
+/// What happens to the scene underneath an overlay pushed with [SceneManager::push_overlay]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum OverlayMode {
+    /// The update systems of the scene underneath don't run until the overlay is popped
+    #[default]
+    PauseUnderneath,
+    /// The scene underneath keeps updating but doesn't receive any input
+    UpdateUnderneath,
+}
+
 pub struct SceneManager {
     pub(crate) scenes: FxHashMap<u32, Rc<RefCell<Scene>>>,
     next_active_scene_id: u32,
     active_scene_id: u32,
     scene_switched: Option<u32>,
+    scene_changed: bool,
+    transition: Option<ActiveTransition>,
+    finished_transition: Option<u32>,
+    /// Scenes underneath the active scene from the bottom up together with the mode of the
+    /// overlay on top of them
+    overlays: Vec<(u32, OverlayMode)>,
+    popped_overlays: Vec<u32>,
 }
 
 impl SceneManager {
@@ -16,6 +37,11 @@ impl SceneManager {
             active_scene_id,
             next_active_scene_id: active_scene_id,
             scene_switched: None,
+            scene_changed: false,
+            transition: None,
+            finished_transition: None,
+            overlays: Vec::new(),
+            popped_overlays: Vec::new(),
         };
         scenes.add(active_scene_id, scene);
         scenes
@@ -42,6 +68,102 @@ impl SceneManager {
         self.next_active_scene_id = next_active_scene_id;
     }
 
+    /// Switches to the scene like [SceneManager::set_next_active_scene] but keeps rendering the
+    /// current scene and blends between both for the duration of the transition. Only the new
+    /// scene gets updated, its switch systems run once the transition is over.
+    pub fn transition_to(&mut self, next_active_scene_id: u32, transition: Transition) {
+        self.set_next_active_scene(next_active_scene_id);
+        if next_active_scene_id == self.active_scene_id {
+            return;
+        }
+        self.transition = Some(ActiveTransition {
+            from: self.active_scene_id,
+            transition,
+            elapsed: Duration::ZERO,
+        });
+    }
+
+    /// Adds the scene and makes it the active scene on top of the current one. The scenes
+    /// underneath keep being rendered below it, so the overlay should not clear the screen.
+    /// Only the overlay receives input.
+    pub fn push_overlay(&mut self, id: u32, scene: impl Into<Scene>, mode: OverlayMode) {
+        self.add(id, scene);
+        self.overlays.push((self.next_active_scene_id, mode));
+        self.next_active_scene_id = id;
+    }
+
+    /// Removes the topmost overlay and activates the scene underneath it again. The end systems
+    /// of the overlay are called with [EndReason::Removed](crate::ecs::EndReason::Removed) at
+    /// the start of the next frame.
+    pub fn pop_overlay(&mut self) -> Option<u32> {
+        let (underneath, _) = self.overlays.pop()?;
+        let overlay = self.next_active_scene_id;
+        self.popped_overlays.push(overlay);
+        self.next_active_scene_id = underneath;
+        Some(overlay)
+    }
+
+    pub fn overlay_count(&self) -> usize {
+        self.overlays.len()
+    }
+
+    pub fn has_overlay(&self) -> bool {
+        !self.overlays.is_empty()
+    }
+
+    /// Scenes underneath the overlays from the bottom up, these are rendered before the
+    /// active scene
+    pub fn underneath(&self) -> impl DoubleEndedIterator<Item = u32> + '_ {
+        self.overlays.iter().map(|(id, _)| *id)
+    }
+
+    /// Scenes underneath the overlays whose update systems still run, from the bottom up
+    pub(crate) fn updated_underneath(&self) -> Vec<u32> {
+        let mut updated = self
+            .overlays
+            .iter()
+            .rev()
+            .take_while(|(_, mode)| *mode == OverlayMode::UpdateUnderneath)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        updated.reverse();
+        updated
+    }
+
+    pub(crate) fn take_popped_overlays(&mut self) -> Vec<u32> {
+        std::mem::take(&mut self.popped_overlays)
+    }
+
+    pub fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    /// Progress of the running transition between `0.0` and `1.0`
+    pub fn transition_progress(&self) -> Option<f32> {
+        self.transition
+            .as_ref()
+            .map(|transition| transition.progress())
+    }
+
+    pub(crate) fn active_transition(&self) -> Option<&ActiveTransition> {
+        self.transition.as_ref()
+    }
+
+    pub(crate) fn advance_transition(&mut self, delta: Duration) {
+        if let Some(transition) = &mut self.transition {
+            transition.elapsed += delta;
+            if transition.elapsed >= transition.transition.duration() {
+                self.finished_transition = Some(transition.from);
+                self.transition = None;
+            }
+        }
+    }
+
+    /// Whether the active scene is different from the last frame, regardless of a transition
+    pub(crate) fn scene_changed(&self) -> bool {
+        self.scene_changed
+    }
+
     pub fn scene_ids(&self) -> impl Iterator<Item = &u32> {
         self.scenes.keys()
     }
@@ -98,10 +220,13 @@ impl SceneManager {
 
     pub(crate) fn try_get_active_scene(&mut self) -> Option<Rc<RefCell<Scene>>> {
         if let Some(scene) = self.scenes.get(&self.next_active_scene_id) {
-            self.scene_switched = if self.active_scene_id != self.next_active_scene_id {
+            self.scene_changed = self.active_scene_id != self.next_active_scene_id;
+            self.scene_switched = if self.transition.is_some() {
+                None
+            } else if self.scene_changed {
                 Some(self.active_scene_id)
             } else {
-                None
+                self.finished_transition.take()
             };
             self.active_scene_id = self.next_active_scene_id;
             Some(scene.clone())
@@ -0,0 +1,75 @@
+use crate::{graphics::Color, time::Duration};
+
+/// Visual effect used by [SceneManager::transition_to](crate::scene::SceneManager::transition_to)
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transition {
+    /// Fades the old scene to `color` during the first half and the new scene in during the
+    /// second half
+    Fade {
+        duration: Duration,
+        color: Color,
+    },
+    Crossfade {
+        duration: Duration,
+    },
+    /// The new scene pushes the old one out to the left
+    SlideLeft {
+        duration: Duration,
+    },
+    /// The new scene pushes the old one out to the right
+    SlideRight {
+        duration: Duration,
+    },
+}
+
+impl Transition {
+    pub fn fade(duration: Duration) -> Self {
+        Self::Fade {
+            duration,
+            color: Color::BLACK,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        match *self {
+            Transition::Fade { duration, .. }
+            | Transition::Crossfade { duration }
+            | Transition::SlideLeft { duration }
+            | Transition::SlideRight { duration } => duration,
+        }
+    }
+
+    /// Mode in the transition shader
+    pub(crate) fn mode(&self) -> f32 {
+        match self {
+            Transition::Fade { .. } => 0.0,
+            Transition::Crossfade { .. } => 1.0,
+            Transition::SlideLeft { .. } => 2.0,
+            Transition::SlideRight { .. } => 3.0,
+        }
+    }
+
+    pub(crate) fn color(&self) -> Color {
+        match self {
+            Transition::Fade { color, .. } => *color,
+            _ => Color::TRANSPARENT,
+        }
+    }
+}
+
+pub(crate) struct ActiveTransition {
+    pub from: u32,
+    pub transition: Transition,
+    pub elapsed: Duration,
+}
+
+impl ActiveTransition {
+    pub fn progress(&self) -> f32 {
+        let duration = self.transition.duration().as_secs_f32();
+        if duration <= 0.0 {
+            return 1.0;
+        }
+        (self.elapsed.as_secs_f32() / duration).min(1.0)
+    }
+}
@@ -1,7 +1,9 @@
-mod group_serde;
+mod prefab;
+mod rebuild;
 mod scene_serde;
 
 pub use bincode;
-pub use group_serde::*;
+pub use prefab::*;
+pub use rebuild::*;
 pub use scene_serde::*;
 pub use serde::*;
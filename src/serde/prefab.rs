@@ -0,0 +1,293 @@
+use std::any::{Any, TypeId};
+
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    ecs::{EntityId, World},
+    graphics::AssetManager,
+    io::ResourceLoader,
+};
+
+/// Template of an entity that can be spawned by name from a [PrefabRegistry]. Usually a struct
+/// that holds all components of the entity.
+pub trait Prefab: DeserializeOwned + 'static {
+    /// Called on every fresh instance before it gets spawned
+    fn init(&mut self) {}
+    /// Adds the components to the world
+    fn spawn(self, world: &mut World) -> EntityId;
+}
+
+type SpawnPrefab =
+    fn(&mut World, &serde_json::Value, &mut dyn FnMut(&mut dyn Any)) -> Result<EntityId>;
+
+struct PrefabEntry {
+    type_id: TypeId,
+    type_name: &'static str,
+    template: serde_json::Value,
+    spawn: SpawnPrefab,
+}
+
+fn spawn_prefab<P: Prefab>(
+    world: &mut World,
+    template: &serde_json::Value,
+    modify: &mut dyn FnMut(&mut dyn Any),
+) -> Result<EntityId> {
+    let mut prefab = P::deserialize(template)?;
+    prefab.init();
+    (modify)(&mut prefab);
+    Ok(prefab.spawn(world))
+}
+
+/// Named entity templates stored as JSON. Every spawn deserializes a new instance.
+#[derive(Default)]
+pub struct PrefabRegistry {
+    prefabs: FxHashMap<String, PrefabEntry>,
+}
+
+impl PrefabRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `template` under `name`, an existing prefab with the same name is replaced
+    pub fn register<P: Prefab + Serialize>(&mut self, name: &str, template: &P) {
+        let template = serde_json::to_value(template)
+            .unwrap_or_else(|err| panic!("Cannot serialize prefab {name}: {err}"));
+        self.insert::<P>(name, template);
+    }
+
+    /// Registers a prefab from JSON. Fails if it can't be deserialized into `P`.
+    pub fn register_json<P: Prefab>(&mut self, name: &str, json: &[u8]) -> Result<()> {
+        let template: serde_json::Value = serde_json::from_slice(json)?;
+        P::deserialize(&template).map_err(|err| anyhow!("Invalid prefab {name}: {err}"))?;
+        self.insert::<P>(name, template);
+        Ok(())
+    }
+
+    /// Registers every `.json` file in the resource folder `dir` as prefab of type `P` named
+    /// after the file
+    pub fn load_folder<P: Prefab>(&mut self, assets: &AssetManager, dir: &str) -> Result<()> {
+        self.load_folder_from::<P>(&*assets.loader, dir)
+    }
+
+    /// Like [PrefabRegistry::load_folder] with any [ResourceLoader]
+    pub fn load_folder_from<P: Prefab>(
+        &mut self,
+        loader: &dyn ResourceLoader,
+        dir: &str,
+    ) -> Result<()> {
+        for path in loader.list(dir)? {
+            let Some(name) = path
+                .strip_suffix(".json")
+                .map(|name| name.rsplit('/').next().unwrap_or(name))
+            else {
+                continue;
+            };
+            let json = loader.load_bytes(&path)?;
+            self.register_json::<P>(name, &json)?;
+        }
+        Ok(())
+    }
+
+    fn insert<P: Prefab>(&mut self, name: &str, template: serde_json::Value) {
+        self.prefabs.insert(
+            name.to_owned(),
+            PrefabEntry {
+                type_id: TypeId::of::<P>(),
+                type_name: std::any::type_name::<P>(),
+                template,
+                spawn: spawn_prefab::<P>,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.prefabs.remove(name).is_some()
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.prefabs.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.prefabs.keys().map(|name| name.as_str())
+    }
+
+    /// Names of all prefabs of type `P`
+    pub fn names_of<P: Prefab>(&self) -> impl Iterator<Item = &str> {
+        self.prefabs
+            .iter()
+            .filter(|(_, entry)| entry.type_id == TypeId::of::<P>())
+            .map(|(name, _)| name.as_str())
+    }
+
+    pub fn template(&self, name: &str) -> Option<&serde_json::Value> {
+        self.prefabs.get(name).map(|entry| &entry.template)
+    }
+
+    pub fn spawn(&self, world: &mut World, name: &str) -> EntityId {
+        self.spawn_entry(world, name, &mut |_| {})
+    }
+
+    /// Spawns the prefab after modifying the fresh instance, `P` has to be the registered type
+    pub fn spawn_with<P: Prefab>(
+        &self,
+        world: &mut World,
+        name: &str,
+        modify: impl FnOnce(&mut P),
+    ) -> EntityId {
+        let entry = self.entry(name);
+        assert!(
+            entry.type_id == TypeId::of::<P>(),
+            "Prefab {name} is of type {} and not {}!",
+            entry.type_name,
+            std::any::type_name::<P>()
+        );
+        let mut modify = Some(modify);
+        self.spawn_entry(world, name, &mut |prefab| {
+            if let (Some(modify), Some(prefab)) = (modify.take(), prefab.downcast_mut::<P>()) {
+                (modify)(prefab);
+            }
+        })
+    }
+
+    fn entry(&self, name: &str) -> &PrefabEntry {
+        self.prefabs
+            .get(name)
+            .unwrap_or_else(|| panic!("Prefab {name} does not exist!"))
+    }
+
+    fn spawn_entry(
+        &self,
+        world: &mut World,
+        name: &str,
+        modify: &mut dyn FnMut(&mut dyn Any),
+    ) -> EntityId {
+        let entry = self.entry(name);
+        (entry.spawn)(world, &entry.template, modify)
+            .unwrap_or_else(|err| panic!("Cannot spawn prefab {name}: {err}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ecs::{Component, WorldExt},
+        io::NativeResourceLoader,
+    };
+
+    #[derive(Component, Clone, Debug, PartialEq, Serialize, serde::Deserialize)]
+    struct Position(f32, f32);
+
+    #[derive(Component, Clone, Debug, PartialEq, Serialize, serde::Deserialize)]
+    struct Health(u32);
+
+    #[derive(Clone, Debug, PartialEq, Serialize, serde::Deserialize)]
+    struct Enemy {
+        position: Position,
+        health: Health,
+    }
+
+    impl Prefab for Enemy {
+        fn init(&mut self) {
+            self.health.0 = self.health.0.max(1);
+        }
+
+        fn spawn(self, world: &mut World) -> EntityId {
+            world.add_entity((self.position, self.health))
+        }
+    }
+
+    fn goblin() -> Enemy {
+        Enemy {
+            position: Position(1.0, 2.0),
+            health: Health(0),
+        }
+    }
+
+    fn components(world: &World, entity: EntityId) -> (Position, Health) {
+        (
+            world.view::<Position>()[entity].clone(),
+            world.view::<Health>()[entity].clone(),
+        )
+    }
+
+    #[test]
+    fn saved_prefab_spawns_the_same_components() {
+        let mut registry = PrefabRegistry::new();
+        registry.register("goblin", &goblin());
+        let saved = serde_json::to_vec(registry.template("goblin").unwrap()).unwrap();
+        registry.register_json::<Enemy>("loaded", &saved).unwrap();
+
+        let mut world = World::new();
+        let original = registry.spawn(&mut world, "goblin");
+        let loaded = registry.spawn(&mut world, "loaded");
+        assert_ne!(original, loaded);
+        assert_eq!(
+            components(&world, original),
+            (Position(1.0, 2.0), Health(1))
+        );
+        assert_eq!(components(&world, original), components(&world, loaded));
+    }
+
+    #[test]
+    fn spawn_with_modifies_a_fresh_instance() {
+        let mut registry = PrefabRegistry::new();
+        registry.register("goblin", &goblin());
+
+        let mut world = World::new();
+        let moved = registry.spawn_with(&mut world, "goblin", |enemy: &mut Enemy| {
+            enemy.position = Position(5.0, 5.0)
+        });
+        let default = registry.spawn(&mut world, "goblin");
+        assert_eq!(world.view::<Position>()[moved], Position(5.0, 5.0));
+        assert_eq!(world.view::<Position>()[default], Position(1.0, 2.0));
+        assert_eq!(registry.names_of::<Enemy>().count(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn spawn_with_checks_the_type() {
+        let mut registry = PrefabRegistry::new();
+        registry.register("goblin", &goblin());
+        registry.spawn_with(&mut World::new(), "goblin", |_: &mut Position| {});
+    }
+
+    #[test]
+    fn invalid_json_is_rejected() {
+        let mut registry = PrefabRegistry::new();
+        assert!(registry
+            .register_json::<Enemy>("broken", br#"{"health": 3}"#)
+            .is_err());
+        assert!(!registry.exists("broken"));
+    }
+
+    #[test]
+    fn load_folder() {
+        let dir = std::env::temp_dir().join(format!("shura_prefabs_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("enemies")).unwrap();
+        std::fs::write(
+            dir.join("enemies/goblin.json"),
+            serde_json::to_vec(&goblin()).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(dir.join("enemies/readme.txt"), "not a prefab").unwrap();
+
+        let loader = NativeResourceLoader {
+            resource_dir: dir.clone(),
+        };
+        let mut registry = PrefabRegistry::new();
+        registry
+            .load_folder_from::<Enemy>(&loader, "enemies")
+            .unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(registry.names().collect::<Vec<_>>(), vec!["goblin"]);
+        let mut world = World::new();
+        let entity = registry.spawn(&mut world, "goblin");
+        assert_eq!(components(&world, entity), (Position(1.0, 2.0), Health(1)));
+    }
+}
@@ -0,0 +1,9 @@
+use crate::{context::Context, ecs::Component};
+
+/// Recreates state of a component that can't be serialized, like GPU resources, after the
+/// component has been deserialized. Such fields are skipped with `#[serde(skip)]` and need a
+/// [Default] value, the hook then rebuilds them from the serialized fields, e.g. from the key of
+/// an asset. Registered with [SerializedScene::rebuild](crate::serde::SerializedScene::rebuild).
+pub trait Rebuild: Component {
+    fn rebuild(&mut self, ctx: &mut Context);
+}
@@ -1,64 +1,224 @@
-use rustc_hash::FxHashMap;
-use std::ops::Deref;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{de::DeserializeOwned, Serialize};
+use shipyard::{IntoIter, IntoWithId};
+use std::{any::type_name, ops::Deref};
 
+#[cfg(feature = "log")]
+use crate::log::warn;
+#[cfg(feature = "physics")]
+use crate::physics::Physics;
 use crate::{
-    entity::{
-        ConstTypeId, Entities, EntityIdentifier, EntityManager, EntityScope, EntityType,
-        GroupedEntities, SingleEntity,
-    },
+    ecs::{Component, EntityId, System, SystemPriority, World, WorldExt},
+    graphics::{ScreenConfig, WorldCamera2D, WorldCamera3D},
+    random::SceneRandom,
     scene::{Scene, SceneCreator},
-    system::System,
+    serde::Rebuild,
 };
+/// State of a scene besides its entities that a [SceneSerializer] can include
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SceneSection {
+    WorldCamera2D,
+    WorldCamera3D,
+    /// Gravity, time scale and integration parameters, see
+    /// [PhysicsSettings](crate::physics::PhysicsSettings)
+    #[cfg(feature = "physics")]
+    PhysicsSettings,
+    /// The [ScreenConfig](crate::graphics::ScreenConfig) and whether entities are rendered
+    ScreenConfig,
+    /// State of the [SceneRandom](crate::random::SceneRandom), so the loaded scene continues
+    /// the same random sequence
+    Random,
+}
+
+impl SceneSection {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SceneSection::WorldCamera2D => "world_camera2d",
+            SceneSection::WorldCamera3D => "world_camera3d",
+            #[cfg(feature = "physics")]
+            SceneSection::PhysicsSettings => "physics_settings",
+            SceneSection::ScreenConfig => "screen_config",
+            SceneSection::Random => "random",
+        }
+    }
+}
 
+/// Serialized state of a scene by name. Sections a version of the game doesn't know are skipped
+/// when loading, so sections can be added without breaking older saves.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+pub(crate) struct SceneSections {
+    sections: FxHashMap<String, Vec<u8>>,
+}
+
+impl SceneSections {
+    pub fn insert<T: serde::Serialize>(
+        &mut self,
+        name: &str,
+        value: &T,
+    ) -> Result<(), Box<bincode::ErrorKind>> {
+        self.sections
+            .insert(name.to_owned(), bincode::serialize(value)?);
+        Ok(())
+    }
+
+    /// A section that cannot be decoded, e.g. because its layout changed since it was saved, is
+    /// skipped like a missing one
+    pub fn take<T: serde::de::DeserializeOwned>(&mut self, name: &str) -> Option<T> {
+        let data = self.sections.remove(name)?;
+        match bincode::deserialize(&data) {
+            Ok(value) => Some(value),
+            Err(_err) => {
+                #[cfg(feature = "log")]
+                warn!("Skipping scene section {name} that cannot be decoded: {_err}");
+                None
+            }
+        }
+    }
+}
+
+/// Entities and their serialized components by type name
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct SerializedWorld {
+    entities: Vec<EntityId>,
+    components: FxHashMap<String, Vec<u8>>,
+}
+
+/// Collects the components and sections of a scene, see
+/// [Context::serialize_scene](crate::context::Context::serialize_scene). Only entities with at
+/// least one serialized component are part of the save, the rigid bodies and colliders of all
+/// other entities are left out of the physics world.
 pub struct SceneSerializer<'a> {
-    entities: &'a EntityManager,
-    ser_entities: FxHashMap<ConstTypeId, Vec<u8>>,
+    world: &'a World,
+    #[cfg(feature = "physics")]
+    physics: &'a Physics,
+    world_camera2d: &'a WorldCamera2D,
+    world_camera3d: &'a WorldCamera3D,
+    screen_config: &'a ScreenConfig,
+    render_entities: bool,
+    random: &'a SceneRandom,
+    entities: FxHashSet<EntityId>,
+    components: FxHashMap<String, Vec<u8>>,
+    sections: Vec<SceneSection>,
+    error: Option<Box<bincode::ErrorKind>>,
 }
 
 impl<'a> SceneSerializer<'a> {
-    pub(crate) fn new(entities: &'a EntityManager) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        world: &'a World,
+        #[cfg(feature = "physics")] physics: &'a Physics,
+        world_camera2d: &'a WorldCamera2D,
+        world_camera3d: &'a WorldCamera3D,
+        screen_config: &'a ScreenConfig,
+        render_entities: bool,
+        random: &'a SceneRandom,
+    ) -> Self {
         Self {
-            entities,
-            ser_entities: Default::default(),
+            world,
+            #[cfg(feature = "physics")]
+            physics,
+            world_camera2d,
+            world_camera3d,
+            screen_config,
+            render_entities,
+            random,
+            entities: Default::default(),
+            components: Default::default(),
+            sections: Default::default(),
+            error: None,
         }
     }
 
-    pub(crate) fn finish(self) -> FxHashMap<ConstTypeId, Vec<u8>> {
-        self.ser_entities
+    pub(crate) fn finish(self) -> Result<Vec<u8>, Box<bincode::ErrorKind>> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        let mut sections = SceneSections::default();
+        for section in &self.sections {
+            match section {
+                SceneSection::WorldCamera2D => {
+                    sections.insert(section.name(), self.world_camera2d)?
+                }
+                SceneSection::WorldCamera3D => {
+                    sections.insert(section.name(), self.world_camera3d)?
+                }
+                #[cfg(feature = "physics")]
+                SceneSection::PhysicsSettings => {
+                    sections.insert(section.name(), &self.physics.settings())?
+                }
+                SceneSection::ScreenConfig => {
+                    sections.insert(section.name(), &(self.screen_config, self.render_entities))?
+                }
+                SceneSection::Random => sections.insert(section.name(), self.random)?,
+            }
+        }
+
+        #[cfg(feature = "physics")]
+        {
+            let mut physics = self.physics.clone();
+            physics.retain_entities(|entity| self.entities.contains(&entity));
+            sections.insert("physics", &physics)?;
+        }
+
+        let mut entities = self.entities.into_iter().collect::<Vec<_>>();
+        entities.sort_unstable_by_key(|entity| entity.index());
+        bincode::serialize(&(
+            sections,
+            SerializedWorld {
+                entities,
+                components: self.components,
+            },
+        ))
     }
 
-    pub fn serialize_entity_custom<ET: EntityType + serde::Serialize>(mut self) -> Self {
-        let ser = self.entities.serialize::<ET>();
-        self.ser_entities.insert(ET::Entity::IDENTIFIER, ser);
+    /// Also serializes a section of the scene state, which is restored before the first update
+    /// of the deserialized scene
+    pub fn serialize_section(mut self, section: SceneSection) -> Self {
+        if !self.sections.contains(&section) {
+            self.sections.push(section);
+        }
         self
     }
 
-    pub fn serialize_entity_single<E: EntityIdentifier + serde::Serialize>(self) -> Self
-    where
-        Self: Sized,
-    {
-        self.serialize_entity_custom::<SingleEntity<E>>()
+    pub fn serialize_world_camera2d(self) -> Self {
+        self.serialize_section(SceneSection::WorldCamera2D)
+    }
+
+    pub fn serialize_world_camera3d(self) -> Self {
+        self.serialize_section(SceneSection::WorldCamera3D)
+    }
+
+    #[cfg(feature = "physics")]
+    pub fn serialize_physics_settings(self) -> Self {
+        self.serialize_section(SceneSection::PhysicsSettings)
     }
 
-    pub fn serialize_entity<E: EntityIdentifier + serde::Serialize>(self) -> Self
-    where
-        Self: Sized,
-    {
-        self.serialize_entity_custom::<Entities<E>>()
+    pub fn serialize_screen_config(self) -> Self {
+        self.serialize_section(SceneSection::ScreenConfig)
     }
 
-    pub fn serialize_entity_grouped<E: EntityIdentifier + serde::Serialize>(self) -> Self
-    where
-        Self: Sized,
-    {
-        self.serialize_entity_custom::<GroupedEntities<Entities<E>>>()
+    pub fn serialize_random(self) -> Self {
+        self.serialize_section(SceneSection::Random)
     }
 
-    pub fn serialize_entity_grouped_single<E: EntityIdentifier + serde::Serialize>(self) -> Self
-    where
-        Self: Sized,
-    {
-        self.serialize_entity_custom::<GroupedEntities<SingleEntity<E>>>()
+    /// Serializes every component of type `C` together with its entity. It has to be
+    /// deserialized with [SerializedScene::deserialize_component].
+    pub fn serialize_component<C: Component + Serialize>(mut self) -> Self {
+        let world = self.world;
+        let view = world.view::<C>();
+        let components = view.iter().with_id().collect::<Vec<(EntityId, &C)>>();
+        self.entities
+            .extend(components.iter().map(|(entity, _)| *entity));
+        match bincode::serialize(&components) {
+            Ok(data) => {
+                self.components.insert(type_name::<C>().to_owned(), data);
+            }
+            Err(error) => {
+                self.error.get_or_insert(error);
+            }
+        }
+        self
     }
 }
 
@@ -66,92 +226,110 @@ pub struct SerializedScene {
     pub id: u32,
     pub scene: Scene,
     once: bool,
-    ser_entities: FxHashMap<ConstTypeId, Vec<u8>>,
+    components: FxHashMap<String, Vec<u8>>,
 }
 
 impl SerializedScene {
-    pub fn new<A: Deref<Target = [u8]>>(id: u32, scene: Option<A>) -> SerializedScene {
+    /// Fails if the save is corrupt, sections that cannot be decoded are skipped
+    pub fn new<A: Deref<Target = [u8]>>(
+        id: u32,
+        scene: Option<A>,
+    ) -> Result<SerializedScene, Box<bincode::ErrorKind>> {
         let once = scene.is_none();
-        let (scene, ser_entities): (Scene, FxHashMap<ConstTypeId, Vec<u8>>) =
-            if let Some(scene) = scene {
-                bincode::deserialize(&scene).unwrap()
-            } else {
-                (Scene::new(), Default::default())
-            };
-        Self {
+        let mut sections = SceneSections::default();
+        let mut world = SerializedWorld::default();
+        if let Some(scene) = scene {
+            (sections, world) = bincode::deserialize(&scene)?;
+        }
+
+        let mut scene = Scene::new();
+        // Entities keep their ids, so ids stored in components and the physics stay valid
+        for entity in world.entities {
+            scene.world.spawn(entity);
+        }
+        #[cfg(feature = "physics")]
+        if let Some(physics) = sections.take("physics") {
+            scene.physics = physics;
+        }
+        if let Some(camera) = sections.take(SceneSection::WorldCamera2D.name()) {
+            scene.world_camera2d = camera;
+        }
+        if let Some(camera) = sections.take(SceneSection::WorldCamera3D.name()) {
+            scene.world_camera3d = camera;
+        }
+        #[cfg(feature = "physics")]
+        if let Some(settings) = sections.take(SceneSection::PhysicsSettings.name()) {
+            scene.physics.apply_settings(&settings);
+        }
+        if let Some((screen_config, render_entities)) =
+            sections.take(SceneSection::ScreenConfig.name())
+        {
+            scene.screen_config = screen_config;
+            scene.render_entities = render_entities;
+        }
+        if let Some(random) = sections.take(SceneSection::Random.name()) {
+            scene.random = random;
+        }
+
+        Ok(Self {
             once,
             id,
             scene,
-            ser_entities,
-        }
+            components: world.components,
+        })
     }
 
-    pub fn deserialize_entity_custom<ET: EntityType + Default + serde::de::DeserializeOwned>(
+    /// Adds the components serialized with [SceneSerializer::serialize_component] to their
+    /// entities. Fails if they cannot be decoded, e.g. because the layout of `C` changed.
+    pub fn deserialize_component<C: Component + DeserializeOwned>(
         mut self,
-        scope: EntityScope,
-    ) -> Self {
-        let type_id = ET::Entity::IDENTIFIER;
-        if let Some(data) = self.ser_entities.remove(&type_id) {
-            self.scene
-                .entities
-                .register_entity(scope, bincode::deserialize::<ET>(&data).unwrap());
-        } else {
-            return self.entity_custom(ET::default(), scope);
+    ) -> Result<Self, Box<bincode::ErrorKind>> {
+        if let Some(data) = self.components.remove(type_name::<C>()) {
+            let components: Vec<(EntityId, C)> = bincode::deserialize(&data)?;
+            let entities = self.scene.world.entities();
+            let mut view = self.scene.world.view_mut::<C>();
+            for (entity, component) in components {
+                entities.add_component(entity, &mut view, component);
+            }
         }
-        self
-    }
-
-    pub fn deserialize_entity<E: EntityIdentifier + serde::de::DeserializeOwned>(self) -> Self
-    where
-        Self: Sized,
-    {
-        self.deserialize_entity_custom::<Entities<E>>(EntityScope::Scene)
-    }
-
-    pub fn deserialize_entity_single<E: EntityIdentifier + serde::de::DeserializeOwned>(
-        self,
-    ) -> Self
-    where
-        Self: Sized,
-    {
-        self.deserialize_entity_custom::<SingleEntity<E>>(EntityScope::Scene)
-    }
-
-    pub fn deserialize_entity_global<E: EntityIdentifier + serde::de::DeserializeOwned>(
-        self,
-    ) -> Self
-    where
-        Self: Sized,
-    {
-        self.deserialize_entity_custom::<Entities<E>>(EntityScope::Global)
+        Ok(self)
     }
 
-    pub fn deserialize_entity_single_global<E: EntityIdentifier + serde::de::DeserializeOwned>(
+    /// Deserializes `C` and rebuilds it, see [SerializedScene::rebuild]
+    pub fn deserialize_rebuild<C: Rebuild + DeserializeOwned>(
         self,
-    ) -> Self
-    where
-        Self: Sized,
-    {
-        self.deserialize_entity_custom::<SingleEntity<E>>(EntityScope::Global)
+    ) -> Result<Self, Box<bincode::ErrorKind>> {
+        Ok(self.deserialize_component::<C>()?.rebuild::<C>())
     }
 
-    pub fn deserialize_entity_grouped<E: EntityIdentifier + serde::de::DeserializeOwned>(
-        self,
-        scope: EntityScope,
-    ) -> Self
-    where
-        Self: Sized,
-    {
-        self.deserialize_entity_custom::<GroupedEntities<Entities<E>>>(scope)
-    }
-    pub fn deserialize_entity_grouped_single<E: EntityIdentifier + serde::de::DeserializeOwned>(
-        self,
-        scope: EntityScope,
-    ) -> Self
-    where
-        Self: Sized,
-    {
-        self.deserialize_entity_custom::<GroupedEntities<SingleEntity<E>>>(scope)
+    /// Calls [Rebuild::rebuild] during setup on every `C` that has been deserialized so far, so
+    /// it has to be called after [SerializedScene::deserialize_component]. Components spawned by
+    /// setup systems are left alone. The hooks run after the setup systems with the default
+    /// priority, so assets loaded there are available.
+    pub fn rebuild<C: Rebuild>(self) -> Self {
+        if self.once {
+            return self;
+        }
+        let entities: Vec<EntityId> = self
+            .scene
+            .world
+            .view::<C>()
+            .iter()
+            .with_id()
+            .map(|(entity, _)| entity)
+            .collect();
+        self.system(
+            System::setup(move |ctx| {
+                for entity in entities {
+                    let (Some(mut component),) = ctx.world.remove::<(C,)>(entity) else {
+                        continue;
+                    };
+                    component.rebuild(ctx);
+                    ctx.world.add_component(entity, (component,));
+                }
+            })
+            .priority(SystemPriority::AFTER),
+        )
     }
 
     pub fn system_once(self, system: System) -> Self {
@@ -167,7 +345,7 @@ impl SerializedScene {
 
     pub fn finish(self) -> Scene {
         assert!(
-            self.ser_entities.is_empty(),
+            self.components.is_empty(),
             "All components that were serialized should also be deserialized!"
         );
         self.scene
@@ -185,3 +363,141 @@ impl SceneCreator for SerializedScene {
         &mut self.scene
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graphics::WorldCameraScaling, math::Vector2};
+
+    #[derive(Component, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Health(u32);
+
+    #[derive(Component)]
+    struct Unsaved;
+
+    fn serialize(
+        scene: &Scene,
+        serialize: impl FnOnce(SceneSerializer) -> SceneSerializer,
+    ) -> Vec<u8> {
+        let serializer = SceneSerializer::new(
+            &scene.world,
+            #[cfg(feature = "physics")]
+            &scene.physics,
+            &scene.world_camera2d,
+            &scene.world_camera3d,
+            &scene.screen_config,
+            scene.render_entities,
+            &scene.random,
+        );
+        (serialize)(serializer).finish().unwrap()
+    }
+
+    #[test]
+    fn sections_survive_reload() {
+        let mut scene = Scene::new();
+        scene
+            .world_camera2d
+            .set_scaling(WorldCameraScaling::Max(5.0));
+        #[cfg(feature = "physics")]
+        {
+            scene.physics.gravity = Vector2::new(0.0, -9.81);
+        }
+
+        let data = serialize(&scene, |serializer| {
+            let serializer = serializer.serialize_world_camera2d().serialize_random();
+            #[cfg(feature = "physics")]
+            let serializer = serializer.serialize_physics_settings();
+            serializer
+        });
+        let loaded = SerializedScene::new(1, Some(data)).unwrap().finish();
+
+        assert_eq!(loaded.world_camera2d.fov(), scene.world_camera2d.fov());
+        assert_eq!(loaded.random, scene.random);
+        #[cfg(feature = "physics")]
+        assert_eq!(loaded.physics.gravity, Vector2::new(0.0, -9.81));
+    }
+
+    #[test]
+    fn sections_are_optional() {
+        let mut scene = Scene::new();
+        scene
+            .world_camera2d
+            .set_scaling(WorldCameraScaling::Max(5.0));
+        let data = serialize(&scene, |serializer| serializer);
+        let loaded = SerializedScene::new(1, Some(data)).unwrap().finish();
+        assert_eq!(
+            loaded.world_camera2d.fov(),
+            Scene::new().world_camera2d.fov()
+        );
+    }
+
+    #[test]
+    fn components_keep_their_entities() {
+        let mut scene = Scene::new();
+        let first = scene.world.add_entity((Health(3), Unsaved));
+        let second = scene.world.add_entity((Health(7),));
+        let unsaved = scene.world.add_entity((Unsaved,));
+
+        let data = serialize(&scene, |serializer| {
+            serializer.serialize_component::<Health>()
+        });
+        let loaded = SerializedScene::new(1, Some(data))
+            .unwrap()
+            .deserialize_component::<Health>()
+            .unwrap()
+            .finish();
+
+        let health = loaded.world.view::<Health>();
+        assert_eq!(health.len(), 2);
+        assert_eq!(health[first], Health(3));
+        assert_eq!(health[second], Health(7));
+        assert!(!loaded.world.is_alive(unsaved));
+        assert_eq!(loaded.world.view::<Unsaved>().len(), 0);
+    }
+
+    #[test]
+    fn undecodable_section_is_skipped() {
+        let mut scene = Scene::new();
+        scene
+            .world_camera2d
+            .set_scaling(WorldCameraScaling::Max(5.0));
+        let data = serialize(&scene, |serializer| serializer.serialize_random());
+        let (mut sections, world): (SceneSections, SerializedWorld) =
+            bincode::deserialize(&data).unwrap();
+        // A section that was saved with an older layout
+        sections
+            .sections
+            .insert(SceneSection::WorldCamera2D.name().to_owned(), vec![1, 2, 3]);
+        let data = bincode::serialize(&(sections, world)).unwrap();
+
+        let loaded = SerializedScene::new(1, Some(data)).unwrap().finish();
+        assert_eq!(
+            loaded.world_camera2d.fov(),
+            Scene::new().world_camera2d.fov()
+        );
+        assert_eq!(loaded.random, scene.random);
+    }
+
+    #[test]
+    fn corrupt_save_is_an_error() {
+        let mut scene = Scene::new();
+        scene.world.add_entity((Health(3),));
+        let data = serialize(&scene, |serializer| {
+            serializer.serialize_component::<Health>()
+        });
+        assert!(SerializedScene::new(1, Some(&data[..data.len() / 2])).is_err());
+        assert!(SerializedScene::new(1, Some(&[0xff; 16][..])).is_err());
+
+        // Health was saved with a different layout
+        let (sections, mut world): (SceneSections, SerializedWorld) =
+            bincode::deserialize(&data).unwrap();
+        world
+            .components
+            .insert(type_name::<Health>().to_owned(), vec![7]);
+        let data = bincode::serialize(&(sections, world)).unwrap();
+        assert!(SerializedScene::new(1, Some(data))
+            .unwrap()
+            .deserialize_component::<Health>()
+            .is_err());
+    }
+}
@@ -0,0 +1,5 @@
+mod spatial_grid;
+mod steering;
+
+pub use spatial_grid::*;
+pub use steering::*;
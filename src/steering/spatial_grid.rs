@@ -0,0 +1,154 @@
+use std::hash::Hash;
+
+use rustc_hash::FxHashMap;
+
+use crate::math::{Vector2, AABB};
+
+type Cell = (i32, i32);
+
+/// Hash grid for proximity queries of many moving points. Cells keep their allocation when
+/// they get cleared, so clearing and inserting all points each frame does not allocate once the
+/// grid is warmed up. Points can also be moved with [SpatialGrid::update], which only touches
+/// the grid when the point changes its cell.
+#[derive(Clone, Debug)]
+pub struct SpatialGrid<K> {
+    cell_size: f32,
+    cells: FxHashMap<Cell, Vec<(K, Vector2<f32>)>>,
+    entries: FxHashMap<K, Cell>,
+}
+
+impl<K: Copy + Eq + Hash> SpatialGrid<K> {
+    /// `cell_size` should be about the radius of the most common query
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "Cell size must be positive!");
+        Self {
+            cell_size,
+            cells: FxHashMap::default(),
+            entries: FxHashMap::default(),
+        }
+    }
+
+    fn cell(&self, position: Vector2<f32>) -> Cell {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Inserts a point or moves it if `key` is already in the grid
+    pub fn insert(&mut self, key: K, position: Vector2<f32>) {
+        if self.entries.contains_key(&key) {
+            self.update(key, position);
+            return;
+        }
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push((key, position));
+        self.entries.insert(key, cell);
+    }
+
+    /// Moves a point, returns false if `key` is not in the grid
+    pub fn update(&mut self, key: K, position: Vector2<f32>) -> bool {
+        let new_cell = self.cell(position);
+        let Some(old_cell) = self.entries.get_mut(&key) else {
+            return false;
+        };
+        let old = self.cells.get_mut(old_cell).unwrap();
+        let index = old.iter().position(|(k, _)| *k == key).unwrap();
+        if *old_cell == new_cell {
+            old[index].1 = position;
+        } else {
+            old.swap_remove(index);
+            *old_cell = new_cell;
+            self.cells
+                .entry(new_cell)
+                .or_default()
+                .push((key, position));
+        }
+        true
+    }
+
+    pub fn remove(&mut self, key: K) -> Option<Vector2<f32>> {
+        let cell = self.entries.remove(&key)?;
+        let points = self.cells.get_mut(&cell).unwrap();
+        let index = points.iter().position(|(k, _)| *k == key).unwrap();
+        Some(points.swap_remove(index).1)
+    }
+
+    pub fn position(&self, key: K) -> Option<Vector2<f32>> {
+        let cell = self.entries.get(&key)?;
+        self.cells[cell]
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, position)| *position)
+    }
+
+    pub fn contains(&self, key: K) -> bool {
+        self.entries.contains_key(&key)
+    }
+
+    /// Removes all points but keeps the memory of the cells
+    pub fn clear(&mut self) {
+        self.cells.values_mut().for_each(Vec::clear);
+        self.entries.clear();
+    }
+
+    /// Frees the cells that became empty, e.g. after the points moved to a different area
+    pub fn shrink(&mut self) {
+        self.cells.retain(|_, points| !points.is_empty());
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (K, Vector2<f32>)> + '_ {
+        self.cells.values().flatten().copied()
+    }
+
+    /// All points inside of `aabb`
+    pub fn query_aabb(&self, aabb: AABB) -> impl Iterator<Item = (K, Vector2<f32>)> + '_ {
+        let min = self.cell(*aabb.min());
+        let max = self.cell(*aabb.max());
+        (min.1..=max.1)
+            .flat_map(move |y| (min.0..=max.0).map(move |x| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |(_, position)| aabb.contains_point(position))
+    }
+
+    /// All points within `radius` of `center`, in no particular order
+    pub fn query_radius(
+        &self,
+        center: Vector2<f32>,
+        radius: f32,
+    ) -> impl Iterator<Item = (K, Vector2<f32>)> + '_ {
+        let half_extents = Vector2::new(radius, radius);
+        self.query_aabb(AABB::new(center - half_extents, center + half_extents))
+            .filter(move |(_, position)| (position - center).norm_squared() <= radius * radius)
+    }
+
+    /// Closest point to `center` within `radius`, excluding `exclude`
+    pub fn nearest(
+        &self,
+        center: Vector2<f32>,
+        radius: f32,
+        exclude: Option<K>,
+    ) -> Option<(K, Vector2<f32>)> {
+        self.query_radius(center, radius)
+            .filter(|(key, _)| Some(*key) != exclude)
+            .min_by(|(_, a), (_, b)| {
+                (a - center)
+                    .norm_squared()
+                    .total_cmp(&(b - center).norm_squared())
+            })
+    }
+}
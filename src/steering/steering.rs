@@ -0,0 +1,245 @@
+#[cfg(feature = "physics")]
+use crate::physics::{EntityQueryFilter, Physics};
+use crate::{
+    math::{Rotation2, Vector2},
+    random::gen_range,
+};
+
+/// Position and velocity of something that steers. All behaviors return the acceleration that
+/// turns the current velocity into the desired one, so they can be weighted, summed and
+/// clamped with [truncate] before being applied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SteeringAgent {
+    pub position: Vector2<f32>,
+    pub velocity: Vector2<f32>,
+    pub max_speed: f32,
+}
+
+/// Shortens `vector` to at most `max` length
+pub fn truncate(vector: Vector2<f32>, max: f32) -> Vector2<f32> {
+    let length = vector.norm();
+    if length > max && length > 0.0 {
+        vector * (max / length)
+    } else {
+        vector
+    }
+}
+
+impl SteeringAgent {
+    pub fn new(position: Vector2<f32>, velocity: Vector2<f32>, max_speed: f32) -> Self {
+        Self {
+            position,
+            velocity,
+            max_speed,
+        }
+    }
+
+    /// Acceleration towards `velocity`, limited to the max speed
+    pub fn steer_towards(&self, velocity: Vector2<f32>) -> Vector2<f32> {
+        truncate(velocity, self.max_speed) - self.velocity
+    }
+
+    fn towards(&self, target: Vector2<f32>, speed: f32) -> Vector2<f32> {
+        let offset = target - self.position;
+        let distance = offset.norm();
+        if distance <= f32::EPSILON {
+            return -self.velocity;
+        }
+        self.steer_towards(offset * (speed / distance))
+    }
+
+    /// Moves towards `target` at full speed
+    pub fn seek(&self, target: Vector2<f32>) -> Vector2<f32> {
+        self.towards(target, self.max_speed)
+    }
+
+    /// Moves away from `target` at full speed
+    pub fn flee(&self, target: Vector2<f32>) -> Vector2<f32> {
+        self.towards(2.0 * self.position - target, self.max_speed)
+    }
+
+    /// Like [seek](Self::seek), but slows down linearly inside of `slowing_radius` and stops on
+    /// `target`
+    pub fn arrive(&self, target: Vector2<f32>, slowing_radius: f32) -> Vector2<f32> {
+        let distance = (target - self.position).norm();
+        let speed = if distance < slowing_radius {
+            self.max_speed * distance / slowing_radius
+        } else {
+            self.max_speed
+        };
+        self.towards(target, speed)
+    }
+
+    /// Time until this agent would reach `position`, used to predict where a target will be
+    fn prediction(&self, position: Vector2<f32>) -> f32 {
+        if self.max_speed <= 0.0 {
+            return 0.0;
+        }
+        (position - self.position).norm() / self.max_speed
+    }
+
+    /// Seeks the position where `target` will be if it keeps its velocity
+    pub fn pursue(&self, target: &SteeringAgent) -> Vector2<f32> {
+        let time = self.prediction(target.position);
+        self.seek(target.position + target.velocity * time)
+    }
+
+    /// Flees from the position where `target` will be if it keeps its velocity
+    pub fn evade(&self, target: &SteeringAgent) -> Vector2<f32> {
+        let time = self.prediction(target.position);
+        self.flee(target.position + target.velocity * time)
+    }
+
+    /// Random but smooth movement, see [Wander]
+    pub fn wander(&self, wander: &mut Wander) -> Vector2<f32> {
+        wander.angle += gen_range(-wander.jitter..=wander.jitter);
+        let heading = if self.velocity.norm_squared() > f32::EPSILON {
+            self.velocity.normalize()
+        } else {
+            Vector2::x()
+        };
+        let circle = self.position + heading * wander.distance;
+        let target = circle + Rotation2::new(wander.angle) * heading * wander.radius;
+        self.seek(target)
+    }
+
+    /// Casts a ray `look_ahead` units along the velocity and steers along the normal of the
+    /// closest hit, stronger the closer the obstacle is
+    #[cfg(feature = "physics")]
+    pub fn avoid_obstacles<'a>(
+        &self,
+        physics: &Physics,
+        look_ahead: f32,
+        filter: impl Into<EntityQueryFilter<'a>>,
+    ) -> Vector2<f32> {
+        if self.velocity.norm_squared() <= f32::EPSILON {
+            return Vector2::zeros();
+        }
+        match physics.raycast(self.position.into(), self.velocity, look_ahead, filter) {
+            Some(hit) => {
+                let strength = 1.0 - hit.toi / look_ahead;
+                hit.normal * self.max_speed * strength
+            }
+            None => Vector2::zeros(),
+        }
+    }
+
+    /// Moves away from neighbours, weighted by how close they are
+    pub fn separation(&self, neighbours: impl IntoIterator<Item = Vector2<f32>>) -> Vector2<f32> {
+        let mut push = Vector2::zeros();
+        for neighbour in neighbours {
+            let offset = self.position - neighbour;
+            let distance_squared = offset.norm_squared();
+            if distance_squared > f32::EPSILON {
+                push += offset / distance_squared;
+            }
+        }
+        if push == Vector2::zeros() {
+            return push;
+        }
+        self.steer_towards(push.normalize() * self.max_speed)
+    }
+
+    /// Matches the average velocity of the neighbours
+    pub fn alignment(&self, neighbours: impl IntoIterator<Item = Vector2<f32>>) -> Vector2<f32> {
+        let (sum, count) = neighbours
+            .into_iter()
+            .fold((Vector2::zeros(), 0), |(sum, count), velocity| {
+                (sum + velocity, count + 1)
+            });
+        if count == 0 {
+            return Vector2::zeros();
+        }
+        self.steer_towards(sum / count as f32)
+    }
+
+    /// Moves towards the center of the neighbours
+    pub fn cohesion(&self, neighbours: impl IntoIterator<Item = Vector2<f32>>) -> Vector2<f32> {
+        let (sum, count) = neighbours
+            .into_iter()
+            .fold((Vector2::zeros(), 0), |(sum, count), position| {
+                (sum + position, count + 1)
+            });
+        if count == 0 {
+            return Vector2::zeros();
+        }
+        self.seek(sum / count as f32)
+    }
+}
+
+/// State of [SteeringAgent::wander]. The agent seeks a point on a circle in front of it that
+/// moves by up to `jitter` radians every call.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Wander {
+    pub radius: f32,
+    pub distance: f32,
+    pub jitter: f32,
+    pub angle: f32,
+}
+
+impl Wander {
+    pub fn new(radius: f32, distance: f32, jitter: f32) -> Self {
+        Self {
+            radius,
+            distance,
+            jitter,
+            angle: 0.0,
+        }
+    }
+}
+
+/// Weights of the three boid rules, neighbours are usually queried from a
+/// [SpatialGrid](crate::steering::SpatialGrid)
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Flocking {
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+    /// Only neighbours closer than this are avoided
+    pub separation_radius: f32,
+}
+
+impl Default for Flocking {
+    fn default() -> Self {
+        Self {
+            separation: 1.5,
+            alignment: 1.0,
+            cohesion: 1.0,
+            separation_radius: 1.0,
+        }
+    }
+}
+
+impl Flocking {
+    pub fn new(separation: f32, alignment: f32, cohesion: f32, separation_radius: f32) -> Self {
+        Self {
+            separation,
+            alignment,
+            cohesion,
+            separation_radius,
+        }
+    }
+
+    /// Weighted sum of separation, alignment and cohesion. `neighbours` must not contain the
+    /// agent itself.
+    pub fn steer(
+        &self,
+        agent: &SteeringAgent,
+        neighbours: impl IntoIterator<Item = SteeringAgent>,
+    ) -> Vector2<f32> {
+        let neighbours: Vec<SteeringAgent> = neighbours.into_iter().collect();
+        let radius_squared = self.separation_radius * self.separation_radius;
+        let close = neighbours
+            .iter()
+            .map(|neighbour| neighbour.position)
+            .filter(|position| (position - agent.position).norm_squared() < radius_squared);
+
+        agent.separation(close) * self.separation
+            + agent.alignment(neighbours.iter().map(|neighbour| neighbour.velocity))
+                * self.alignment
+            + agent.cohesion(neighbours.iter().map(|neighbour| neighbour.position)) * self.cohesion
+    }
+}
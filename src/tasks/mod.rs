@@ -1,3 +1,7 @@
+#[cfg(feature = "net")]
+mod net;
 mod task_manager;
 
+#[cfg(feature = "net")]
+pub use net::*;
 pub use task_manager::*;
@@ -0,0 +1,398 @@
+use std::{rc::Rc, sync::Arc};
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use parking_lot::Mutex;
+
+use crate::context::Context;
+
+type NetCallback = Box<dyn FnOnce(&mut Context) + Send + 'static>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WsState {
+    Connecting,
+    Open,
+    /// Closed by either side, with the error if the connection failed
+    Closed(Option<String>),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+enum WsCommand {
+    Send(Vec<u8>),
+    Close,
+}
+
+struct WsInner {
+    state: Arc<Mutex<WsState>>,
+    incoming: Receiver<Vec<u8>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    outgoing: Sender<WsCommand>,
+    #[cfg(target_arch = "wasm32")]
+    socket: Option<web_sys::WebSocket>,
+    #[cfg(target_arch = "wasm32")]
+    pending: Rc<std::cell::RefCell<Vec<Vec<u8>>>>,
+    #[cfg(target_arch = "wasm32")]
+    _callbacks: Vec<
+        wasm_bindgen_futures::wasm_bindgen::closure::Closure<
+            dyn FnMut(wasm_bindgen_futures::wasm_bindgen::JsValue),
+        >,
+    >,
+}
+
+/// WebSocket connection opened with [Net::connect_ws]. Messages are queued until the connection
+/// is open, text messages are received as their UTF-8 bytes.
+#[derive(Clone)]
+pub struct WsHandle {
+    inner: Rc<WsInner>,
+}
+
+impl WsHandle {
+    pub fn state(&self) -> WsState {
+        self.inner.state.lock().clone()
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.state() == WsState::Open
+    }
+
+    pub fn send(&self, data: impl Into<Vec<u8>>) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let _ = self.inner.outgoing.send(WsCommand::Send(data.into()));
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let data = data.into();
+            match &self.inner.socket {
+                Some(socket) if self.is_open() => {
+                    let _ = socket.send_with_u8_array(&data);
+                }
+                Some(_) if self.state() == WsState::Connecting => {
+                    self.inner.pending.borrow_mut().push(data)
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Next received message, never blocks
+    pub fn try_recv(&self) -> Option<Vec<u8>> {
+        self.inner.incoming.try_recv().ok()
+    }
+
+    pub fn close(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let _ = self.inner.outgoing.send(WsCommand::Close);
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        if let Some(socket) = &self.inner.socket {
+            let _ = socket.close();
+        }
+    }
+}
+
+/// WebSockets and HTTP requests of a scene, accessible as `ctx.net`. Callbacks are called on
+/// the main thread before the update systems, all connections are closed when the scene ends.
+pub struct Net {
+    sockets: Vec<WsHandle>,
+    receiver: Rc<Receiver<NetCallback>>,
+    sender: Sender<NetCallback>,
+}
+
+impl Default for Net {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Net {
+    pub(crate) fn new() -> Self {
+        let (sender, receiver) = unbounded();
+        Self {
+            sockets: Vec::new(),
+            receiver: Rc::new(receiver),
+            sender,
+        }
+    }
+
+    pub fn connect_ws(&mut self, url: &str) -> WsHandle {
+        let state = Arc::new(Mutex::new(WsState::Connecting));
+        let (incoming_sender, incoming) = unbounded();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let inner = {
+            let (outgoing, commands) = unbounded();
+            let url = url.to_owned();
+            let thread_state = state.clone();
+            std::thread::spawn(move || run_ws(&url, &thread_state, incoming_sender, commands));
+            WsInner {
+                state,
+                incoming,
+                outgoing,
+            }
+        };
+
+        #[cfg(target_arch = "wasm32")]
+        let inner = connect_web_ws(url, state, incoming_sender, incoming);
+
+        self.sockets
+            .retain(|socket| !matches!(socket.state(), WsState::Closed(_)));
+        let handle = WsHandle {
+            inner: Rc::new(inner),
+        };
+        self.sockets.push(handle.clone());
+        handle
+    }
+
+    /// Downloads `url` and calls `callback` with the body
+    pub fn http_get(
+        &self,
+        url: &str,
+        callback: impl FnOnce(&mut Context, anyhow::Result<Vec<u8>>) + Send + 'static,
+    ) {
+        let url = url.to_owned();
+        let sender = self.sender.clone();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::spawn(move || {
+            let result = (|| {
+                let response = reqwest_blocking::blocking::get(url)?.error_for_status()?;
+                Ok::<_, anyhow::Error>(response.bytes()?.to_vec())
+            })();
+            let _ = sender.send(Box::new(move |ctx| (callback)(ctx, result)));
+        });
+
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = async {
+                let response = reqwest::get(url).await?.error_for_status()?;
+                Ok::<_, anyhow::Error>(response.bytes().await?.to_vec())
+            }
+            .await;
+            let _ = sender.send(Box::new(move |ctx| (callback)(ctx, result)));
+        });
+    }
+
+    pub(crate) fn receiver(&self) -> Rc<Receiver<NetCallback>> {
+        self.receiver.clone()
+    }
+}
+
+impl Drop for Net {
+    fn drop(&mut self) {
+        for socket in &self.sockets {
+            socket.close();
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn run_ws(
+    url: &str,
+    state: &Mutex<WsState>,
+    incoming: Sender<Vec<u8>>,
+    commands: Receiver<WsCommand>,
+) {
+    use crossbeam_channel::TryRecvError;
+    use tungstenite::{stream::MaybeTlsStream, Message};
+
+    let mut socket = match tungstenite::connect(url) {
+        Ok((socket, _)) => socket,
+        Err(err) => {
+            *state.lock() = WsState::Closed(Some(err.to_string()));
+            return;
+        }
+    };
+    // Reads time out so that queued messages get sent
+    let timeout = Some(std::time::Duration::from_millis(5));
+    let _ = match socket.get_mut() {
+        MaybeTlsStream::Plain(stream) => stream.set_read_timeout(timeout),
+        MaybeTlsStream::Rustls(stream) => stream.sock.set_read_timeout(timeout),
+        _ => Ok(()),
+    };
+    *state.lock() = WsState::Open;
+
+    let error = loop {
+        match commands.try_recv() {
+            Ok(WsCommand::Send(data)) => match socket.send(Message::Binary(data)) {
+                Ok(_) => continue,
+                Err(err) => break Some(err.to_string()),
+            },
+            Ok(WsCommand::Close) | Err(TryRecvError::Disconnected) => {
+                let _ = socket.close(None);
+                let _ = socket.flush();
+                break None;
+            }
+            Err(TryRecvError::Empty) => (),
+        }
+
+        match socket.read() {
+            Ok(Message::Binary(data)) => {
+                let _ = incoming.send(data);
+            }
+            Ok(Message::Text(text)) => {
+                let _ = incoming.send(text.into_bytes());
+            }
+            Ok(Message::Close(_)) => break None,
+            Ok(_) => (),
+            Err(tungstenite::Error::Io(err))
+                if matches!(
+                    err.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) => {}
+            Err(tungstenite::Error::ConnectionClosed) => break None,
+            Err(err) => break Some(err.to_string()),
+        }
+    };
+    *state.lock() = WsState::Closed(error);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn connect_web_ws(
+    url: &str,
+    state: Arc<Mutex<WsState>>,
+    incoming_sender: Sender<Vec<u8>>,
+    incoming: Receiver<Vec<u8>>,
+) -> WsInner {
+    use wasm_bindgen_futures::{
+        js_sys,
+        wasm_bindgen::{closure::Closure, JsCast, JsValue},
+    };
+
+    let pending: Rc<std::cell::RefCell<Vec<Vec<u8>>>> = Default::default();
+    let socket = match web_sys::WebSocket::new(url) {
+        Ok(socket) => socket,
+        Err(err) => {
+            *state.lock() = WsState::Closed(err.as_string());
+            return WsInner {
+                state,
+                incoming,
+                socket: None,
+                pending,
+                _callbacks: Vec::new(),
+            };
+        }
+    };
+    socket.set_binary_type(web_sys::BinaryType::Arraybuffer);
+
+    let on_open = {
+        let state = state.clone();
+        let pending = pending.clone();
+        let socket = socket.clone();
+        Closure::<dyn FnMut(JsValue)>::new(move |_| {
+            *state.lock() = WsState::Open;
+            for data in pending.borrow_mut().drain(..) {
+                let _ = socket.send_with_u8_array(&data);
+            }
+        })
+    };
+    let on_message = Closure::<dyn FnMut(JsValue)>::new(move |event: JsValue| {
+        let data = event.unchecked_into::<web_sys::MessageEvent>().data();
+        let data = match data.dyn_into::<js_sys::ArrayBuffer>() {
+            Ok(buffer) => js_sys::Uint8Array::new(&buffer).to_vec(),
+            Err(data) => data.as_string().unwrap_or_default().into_bytes(),
+        };
+        let _ = incoming_sender.send(data);
+    });
+    let on_close = {
+        let state = state.clone();
+        Closure::<dyn FnMut(JsValue)>::new(move |_| {
+            let mut state = state.lock();
+            if !matches!(*state, WsState::Closed(_)) {
+                *state = WsState::Closed(None);
+            }
+        })
+    };
+    let on_error = {
+        let state = state.clone();
+        Closure::<dyn FnMut(JsValue)>::new(move |_| {
+            *state.lock() = WsState::Closed(Some("WebSocket error".to_owned()));
+        })
+    };
+    socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+    socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+    socket.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+    WsInner {
+        state,
+        incoming,
+        socket: Some(socket),
+        pending,
+        _callbacks: vec![on_open, on_message, on_close, on_error],
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use std::{
+        net::TcpListener,
+        time::{Duration, Instant},
+    };
+
+    use tungstenite::Message;
+
+    use super::*;
+
+    fn wait_for<T>(mut poll: impl FnMut() -> Option<T>) -> T {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            if let Some(value) = poll() {
+                return value;
+            }
+            assert!(Instant::now() < deadline, "Timed out!");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut socket = tungstenite::accept(stream).unwrap();
+            let message = socket.read().unwrap();
+            socket.send(Message::Text("pong".to_owned())).unwrap();
+            // Wait for the close of the client
+            while socket.read().is_ok() {}
+            message
+        });
+
+        let mut net = Net::new();
+        let socket = net.connect_ws(&format!("ws://127.0.0.1:{port}"));
+        socket.send(b"ping".to_vec());
+        assert_eq!(wait_for(|| socket.try_recv()), b"pong");
+        assert!(socket.is_open());
+
+        socket.close();
+        assert_eq!(
+            wait_for(|| match socket.state() {
+                WsState::Closed(error) => Some(error),
+                _ => None,
+            }),
+            None
+        );
+        assert_eq!(server.join().unwrap(), Message::Binary(b"ping".to_vec()));
+    }
+
+    #[test]
+    fn refused_connection() {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let mut net = Net::new();
+        let socket = net.connect_ws(&format!("ws://127.0.0.1:{port}"));
+        let error = wait_for(|| match socket.state() {
+            WsState::Closed(error) => Some(error),
+            _ => None,
+        });
+        assert!(error.is_some());
+        assert_eq!(socket.try_recv(), None);
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use owned_ttf_parser::AsFaceRef;
 use rustc_hash::FxHashMap;
 
@@ -27,130 +29,320 @@ impl FontBuilder {
     }
 }
 
+/// Keeps a cached glyph on its layer for as long as it is held, see [GlyphSlots::lease].
+pub(super) type GlyphLease = Arc<()>;
+
+/// Face index and glyph of a cached glyph
+pub(super) type GlyphKey = (usize, rusttype::GlyphId);
+
+#[derive(Clone)]
+struct CachedGlyph {
+    index: SpriteArrayIndex,
+    tex_scaling: Vector2<f32>,
+    last_used: u64,
+    lease: GlyphLease,
+}
+
+/// Bookkeeping of which glyph lives on which layer. A layer is only handed to another glyph if
+/// its glyph wasn't used since the last [GlyphSlots::next_generation] and no [Text](super::Text)
+/// holds a lease on it, since written instances keep pointing at their layer.
+#[derive(Default)]
+pub(super) struct GlyphSlots {
+    glyphs: FxHashMap<GlyphKey, CachedGlyph>,
+    allocated: u32,
+    generation: u64,
+}
+
+impl GlyphSlots {
+    fn get(&mut self, id: GlyphKey) -> Option<(SpriteArrayIndex, Vector2<f32>)> {
+        let glyph = self.glyphs.get_mut(&id)?;
+        glyph.last_used = self.generation;
+        Some((glyph.index, glyph.tex_scaling))
+    }
+
+    fn insert(&mut self, id: GlyphKey, index: SpriteArrayIndex, tex_scaling: Vector2<f32>) {
+        self.glyphs.insert(
+            id,
+            CachedGlyph {
+                index,
+                tex_scaling,
+                last_used: self.generation,
+                lease: GlyphLease::default(),
+            },
+        );
+    }
+
+    /// A free layer below `capacity` or the layer of the least recently used glyph that can be
+    /// evicted.
+    fn allocate(&mut self, capacity: u32) -> Option<SpriteArrayIndex> {
+        if self.allocated < capacity {
+            let index = self.allocated;
+            self.allocated += 1;
+            return Some(index);
+        }
+
+        let (id, index) = self
+            .glyphs
+            .iter()
+            .filter(|(_, glyph)| {
+                glyph.last_used != self.generation && Arc::strong_count(&glyph.lease) == 1
+            })
+            .min_by_key(|(_, glyph)| glyph.last_used)
+            .map(|(id, glyph)| (*id, glyph.index))?;
+        self.glyphs.remove(&id);
+        Some(index)
+    }
+
+    pub(super) fn lease(&self, id: GlyphKey) -> Option<GlyphLease> {
+        self.glyphs.get(&id).map(|glyph| glyph.lease.clone())
+    }
+
+    fn next_generation(&mut self) {
+        self.generation += 1;
+    }
+}
+
+/// Glyphs are rasterized on first use into their own layer of a sprite array. The array grows
+/// when it is full and evicts the least recently used glyphs once the layer limit is reached.
+pub(super) struct GlyphCache {
+    sprite_array: SpriteArray,
+    glyph_size: Vector2<u32>,
+    pub(super) slots: GlyphSlots,
+    buffer: Vec<u8>,
+}
+
+impl GlyphCache {
+    const INITIAL_CAPACITY: u32 = 64;
+
+    fn new(gpu: &Gpu, glyph_size: Vector2<u32>) -> Self {
+        let capacity = Self::INITIAL_CAPACITY.min(gpu.device.limits().max_texture_array_layers);
+        Self {
+            sprite_array: Self::create_sprite_array(gpu, glyph_size, capacity),
+            glyph_size,
+            slots: GlyphSlots::default(),
+            buffer: Vec::with_capacity((glyph_size.x * glyph_size.y) as usize),
+        }
+    }
+
+    fn create_sprite_array(gpu: &Gpu, glyph_size: Vector2<u32>, capacity: u32) -> SpriteArray {
+        gpu.create_sprite_array(
+            SpriteArrayBuilder::empty(glyph_size, Vector2::new(capacity, 1))
+                .sampler(wgpu::SamplerDescriptor {
+                    label: Some("glyph_cache_sampler"),
+                    address_mode_u: wgpu::AddressMode::ClampToEdge,
+                    address_mode_v: wgpu::AddressMode::ClampToEdge,
+                    address_mode_w: wgpu::AddressMode::ClampToEdge,
+                    mag_filter: wgpu::FilterMode::Linear,
+                    min_filter: wgpu::FilterMode::Linear,
+                    ..Default::default()
+                })
+                .label(Some("glyph_cache"))
+                .format(wgpu::TextureFormat::R8Unorm),
+        )
+    }
+
+    /// Glyphs used since the last call cannot be evicted.
+    pub(super) fn next_generation(&mut self) {
+        self.slots.next_generation();
+    }
+
+    pub(super) fn glyph(
+        &mut self,
+        gpu: &Gpu,
+        font: &rusttype::Font,
+        id: GlyphKey,
+    ) -> Option<(SpriteArrayIndex, Vector2<f32>)> {
+        if let Some(glyph) = self.slots.get(id) {
+            return Some(glyph);
+        }
+
+        let positioned = font
+            .glyph(id.1)
+            .scaled(rusttype::Scale::uniform(Font::RES))
+            .positioned(rusttype::Point { x: 0.0, y: 0.0 });
+        let bb = positioned.pixel_bounding_box()?;
+        let index = self.allocate(gpu)?;
+
+        let size = Vector2::new(
+            (bb.width() as u32).min(self.glyph_size.x),
+            (bb.height() as u32).min(self.glyph_size.y),
+        );
+        let glyph_size = self.glyph_size;
+        self.buffer.clear();
+        self.buffer
+            .resize((glyph_size.x * glyph_size.y) as usize, 0);
+        positioned.draw(|x, y, a| {
+            if x < size.x && y < size.y {
+                self.buffer[(y * glyph_size.x + x) as usize] = (a * 255.0) as u8;
+            }
+        });
+        self.sprite_array
+            .write(gpu, index, glyph_size, 1, &self.buffer);
+
+        let tex_scaling = size.cast::<f32>().component_div(&glyph_size.cast::<f32>());
+        self.slots.insert(id, index, tex_scaling);
+        Some((index, tex_scaling))
+    }
+
+    fn allocate(&mut self, gpu: &Gpu) -> Option<SpriteArrayIndex> {
+        let capacity = self.sprite_array.len();
+        let max_capacity = gpu.device.limits().max_texture_array_layers;
+        if self.slots.allocated >= capacity && capacity < max_capacity {
+            self.grow(gpu, (capacity * 2).min(max_capacity));
+        }
+        self.slots.allocate(self.sprite_array.len())
+    }
+
+    fn grow(&mut self, gpu: &Gpu, capacity: u32) {
+        let sprite_array = Self::create_sprite_array(gpu, self.glyph_size, capacity);
+        let mut encoder = gpu
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("glyph_cache_grow"),
+            });
+        encoder.copy_texture_to_texture(
+            self.sprite_array.texture().as_image_copy(),
+            sprite_array.texture().as_image_copy(),
+            wgpu::Extent3d {
+                width: self.glyph_size.x,
+                height: self.glyph_size.y,
+                depth_or_array_layers: self.slots.allocated,
+            },
+        );
+        gpu.queue.submit(Some(encoder.finish()));
+        self.sprite_array = sprite_array;
+    }
+}
+
+/// One or more font faces that share a glyph cache, so a [Text](super::Text) can mix them. Spans
+/// pick their face with [TextSpan::font](super::TextSpan::font).
 pub struct Font {
-    pub(super) sprite_array: SpriteArray,
-    pub(super) index_map: FxHashMap<rusttype::GlyphId, (SpriteArrayIndex, Vector2<f32>)>,
-    pub(super) font: rusttype::Font<'static>,
+    pub(super) faces: Vec<rusttype::Font<'static>>,
+    pub(super) cache: GlyphCache,
 }
+
 impl Font {
-    const RES: f32 = 400.0;
+    pub(super) const RES: f32 = 400.0;
 
     pub fn sprite_array(&self) -> &SpriteArray {
-        &self.sprite_array
+        &self.cache.sprite_array
+    }
+
+    pub fn cached_glyphs(&self) -> usize {
+        self.cache.slots.glyphs.len()
+    }
+
+    pub fn faces(&self) -> usize {
+        self.faces.len()
     }
 
     pub fn new(gpu: &Gpu, builder: FontBuilder) -> Self {
-        let scale = rusttype::Scale::uniform(Self::RES);
-        let font = match builder {
-            FontBuilder::Ref(bytes) => rusttype::Font::try_from_bytes(bytes).unwrap(),
-            FontBuilder::Owned(bytes) => rusttype::Font::try_from_vec(bytes).unwrap(),
-        };
-        let face_ref = match &font {
-            rusttype::Font::Ref(f) => f,
-            rusttype::Font::Owned(f) => f.as_face_ref(),
-        };
-
-        macro_rules! glyphs {
-            ($face_ref: expr) => {{
-                let mut used_indices = std::collections::BTreeSet::new();
-                $face_ref
-                    .tables()
-                    .cmap
-                    .iter()
-                    .flat_map(|c| c.subtables)
-                    .filter(|s| s.is_unicode())
-                    .flat_map(move |subtable| {
-                        let mut pairs = Vec::new();
-                        subtable.codepoints(|c| {
-                            if let Ok(ch) = char::try_from(c) {
-                                if let Some(idx) = subtable.glyph_index(c).filter(|i| i.0 > 0) {
-                                    if used_indices.insert(idx.0) {
-                                        pairs.push((rusttype::GlyphId(idx.0), ch));
-                                    }
-                                }
-                            }
-                        });
-                        pairs
-                    })
-            }};
-        }
+        Self::with_faces(gpu, vec![builder])
+    }
 
-        let mut amount = 0;
-        let mut size = Vector2::default();
-        let glyphs = glyphs!(face_ref);
-        for (id, _char) in glyphs {
-            if !_char.is_ascii() {
-                continue;
-            }
-            let glyph = font.glyph(id);
-            let scaled = glyph.scaled(scale);
-            let positioned = scaled.positioned(rusttype::Point { x: 0.0, y: 0.0 });
-
-            if let Some(bb) = positioned.pixel_bounding_box() {
-                amount += 1;
-                if bb.width() > size.x {
-                    size.x = bb.width();
-                }
-                if bb.height() > size.y {
-                    size.y = bb.height();
-                }
+    /// Face `i` is selected by spans with `font: Some(i)`, the first face is the default.
+    pub fn with_faces(gpu: &Gpu, builders: Vec<FontBuilder>) -> Self {
+        assert!(!builders.is_empty(), "A font needs at least one face!");
+        let faces: Vec<rusttype::Font<'static>> = builders
+            .into_iter()
+            .map(|builder| match builder {
+                FontBuilder::Ref(bytes) => rusttype::Font::try_from_bytes(bytes).unwrap(),
+                FontBuilder::Owned(bytes) => rusttype::Font::try_from_vec(bytes).unwrap(),
+            })
+            .collect();
+
+        let glyph_size = faces
+            .iter()
+            .map(|font| {
+                let face_ref = match font {
+                    rusttype::Font::Ref(f) => f,
+                    rusttype::Font::Owned(f) => f.as_face_ref(),
+                };
+                let bb = face_ref.global_bounding_box();
+                let scale = Self::RES / face_ref.units_per_em() as f32;
+                Vector2::new(
+                    ((bb.x_max as f32 - bb.x_min as f32) * scale).ceil() as u32,
+                    ((bb.y_max as f32 - bb.y_min as f32) * scale).ceil() as u32,
+                )
+            })
+            .fold(Vector2::new(1, 1), |size, face| size.sup(&face));
+
+        let mut cache = GlyphCache::new(gpu, glyph_size);
+        for (index, font) in faces.iter().enumerate() {
+            for c in ' '..='~' {
+                cache.glyph(gpu, font, (index, font.glyph(c).id()));
             }
         }
 
-        let desc = SpriteArrayBuilder::empty(
-            Vector2::new(size.x as u32, Self::RES as u32),
-            Vector2::new(amount as u32, 1),
-        )
-        .sampler(wgpu::SamplerDescriptor {
-            label: Some("wgpu-text Cache Texture Sampler"),
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            ..Default::default()
-        })
-        .format(wgpu::TextureFormat::R8Unorm);
-
-        let mut sprite_array = gpu.create_sprite_array(desc);
-        let mut index_map = FxHashMap::default();
-
-        let glyphs = glyphs!(face_ref);
-        let mut buffer: Vec<u8> = Vec::with_capacity((size.x * size.y) as usize);
-        let mut counter = 0;
-        for (id, _char) in glyphs {
-            if !_char.is_ascii() {
-                continue;
-            }
+        Self { faces, cache }
+    }
 
-            let glyph = font.glyph(id);
-            let scaled = glyph.scaled(scale);
-            let positioned = scaled.positioned(rusttype::Point { x: 0.0, y: 0.0 });
-
-            if let Some(bb) = positioned.pixel_bounding_box() {
-                positioned.draw(|_x, _y, a| {
-                    buffer.push((a * 255.0) as u8);
-                });
-                let ratio = Vector2::new(bb.width(), bb.height())
-                    .cast::<f32>()
-                    .component_div(&size.cast::<f32>());
-                index_map.insert(id, (counter, ratio));
-                sprite_array.write(
-                    gpu,
-                    counter,
-                    Vector2::new(bb.width() as u32, bb.height() as u32),
-                    1,
-                    &buffer,
-                );
-                buffer.clear();
-                counter += 1;
-            }
+    /// Face of a span, the first face if the index is out of range
+    pub(super) fn face_index(&self, font: Option<usize>) -> usize {
+        font.filter(|font| *font < self.faces.len()).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(id: u16) -> GlyphKey {
+        (0, rusttype::GlyphId(id))
+    }
+
+    fn fill(slots: &mut GlyphSlots, capacity: u32, ids: std::ops::Range<u16>) {
+        for id in ids {
+            let index = slots.allocate(capacity).unwrap();
+            slots.insert(key(id), index, Vector2::new(1.0, 1.0));
         }
+    }
 
-        Self {
-            sprite_array,
-            index_map,
-            font,
+    fn layer(slots: &mut GlyphSlots, id: u16) -> Option<SpriteArrayIndex> {
+        slots.get(key(id)).map(|(index, _)| index)
+    }
+
+    #[test]
+    fn eviction_keeps_leased_glyphs() {
+        const CAPACITY: u32 = 4;
+        let mut slots = GlyphSlots::default();
+        fill(&mut slots, CAPACITY, 0..4);
+        let text: Vec<GlyphLease> = (0..2).map(|id| slots.lease(key(id)).unwrap()).collect();
+        let before: Vec<_> = (0..2).map(|id| layer(&mut slots, id)).collect();
+
+        slots.next_generation();
+        fill(&mut slots, CAPACITY, 4..6);
+        assert_eq!(
+            (0..2).map(|id| layer(&mut slots, id)).collect::<Vec<_>>(),
+            before
+        );
+        assert_eq!(layer(&mut slots, 2), None);
+        assert_eq!(layer(&mut slots, 3), None);
+        assert_eq!(slots.allocate(CAPACITY), None);
+
+        drop(text);
+        slots.next_generation();
+        assert!(slots.allocate(CAPACITY).is_some());
+    }
+
+    #[test]
+    fn glyphs_of_the_current_generation_are_not_evicted() {
+        let mut slots = GlyphSlots::default();
+        fill(&mut slots, 2, 0..2);
+        assert_eq!(slots.allocate(2), None);
+        slots.next_generation();
+        assert!(slots.allocate(2).is_some());
+    }
+
+    #[test]
+    fn faces_cache_their_glyphs_separately() {
+        let mut slots = GlyphSlots::default();
+        for face in 0..2 {
+            let index = slots.allocate(4).unwrap();
+            slots.insert((face, rusttype::GlyphId(7)), index, Vector2::new(1.0, 1.0));
         }
+        assert_eq!(slots.get((0, rusttype::GlyphId(7))).unwrap().0, 0);
+        assert_eq!(slots.get((1, rusttype::GlyphId(7))).unwrap().0, 1);
+        assert!(slots.get((2, rusttype::GlyphId(7))).is_none());
     }
 }
@@ -1,5 +1,7 @@
 mod font;
+mod span;
 mod text;
 
 pub use font::*;
+pub use span::*;
 pub use text::*;
@@ -0,0 +1,155 @@
+use crate::graphics::Color;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextSpan {
+    pub text: String,
+    /// Falls back to the color of the section.
+    pub color: Option<Color>,
+    /// Falls back to the size of the section.
+    pub size: Option<f32>,
+    /// Face of the [Font](crate::text::Font), falls back to its first face.
+    pub font: Option<usize>,
+    pub bold: bool,
+}
+
+impl TextSpan {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn font(mut self, font: usize) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.bold = bold;
+        self
+    }
+
+    /// Parses inline tags like `Press <red>A</red> to <b>jump</b>`. Colors can be named or given
+    /// as `<#rrggbb>` / `<#rrggbbaa>` and are closed with their own name or `</>`. Unknown tags
+    /// are kept as text.
+    pub fn parse_markup(markup: &str) -> Vec<TextSpan> {
+        let mut spans: Vec<TextSpan> = Vec::new();
+        let mut colors: Vec<(String, Color)> = Vec::new();
+        let mut bold = 0;
+        let mut text = String::new();
+        let mut rest = markup;
+
+        while let Some(start) = rest.find('<') {
+            text.push_str(&rest[..start]);
+            let tag_str = &rest[start..];
+            let Some(end) = tag_str.find('>') else {
+                rest = tag_str;
+                break;
+            };
+            let tag = &tag_str[1..end];
+            let (closing, name) = match tag.strip_prefix('/') {
+                Some(name) => (true, name),
+                None => (false, tag),
+            };
+
+            let known = if name == "b" {
+                if closing {
+                    if bold > 0 {
+                        Self::flush(&mut spans, &mut text, &colors, bold);
+                        bold -= 1;
+                    }
+                } else {
+                    Self::flush(&mut spans, &mut text, &colors, bold);
+                    bold += 1;
+                }
+                true
+            } else if closing {
+                let position = if name.is_empty() {
+                    colors.len().checked_sub(1)
+                } else {
+                    colors.iter().rposition(|(color, _)| color == name)
+                };
+                if let Some(position) = position {
+                    Self::flush(&mut spans, &mut text, &colors, bold);
+                    colors.remove(position);
+                }
+                position.is_some() || name.is_empty()
+            } else if let Some(color) = Self::parse_color(name) {
+                Self::flush(&mut spans, &mut text, &colors, bold);
+                colors.push((name.to_owned(), color));
+                true
+            } else {
+                false
+            };
+
+            if !known {
+                text.push_str(&tag_str[..=end]);
+            }
+            rest = &tag_str[end + 1..];
+        }
+        text.push_str(rest);
+        Self::flush(&mut spans, &mut text, &colors, bold);
+        spans
+    }
+
+    fn flush(spans: &mut Vec<TextSpan>, text: &mut String, colors: &[(String, Color)], bold: u32) {
+        if text.is_empty() {
+            return;
+        }
+        spans.push(TextSpan {
+            text: std::mem::take(text),
+            color: colors.last().map(|(_, color)| *color),
+            size: None,
+            font: None,
+            bold: bold > 0,
+        });
+    }
+
+    fn parse_color(name: &str) -> Option<Color> {
+        if let Some(hex) = name.strip_prefix('#') {
+            if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+                return None;
+            }
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+            return Some(Color::new_rgba(
+                channel(0)?,
+                channel(2)?,
+                channel(4)?,
+                alpha,
+            ));
+        }
+
+        Some(match name.to_ascii_lowercase().as_str() {
+            "black" => Color::BLACK,
+            "white" => Color::WHITE,
+            "red" => Color::RED,
+            "lime" => Color::LIME,
+            "green" => Color::GREEN,
+            "blue" => Color::BLUE,
+            "navy" => Color::NAVY,
+            "cyan" => Color::CYAN,
+            "yellow" => Color::YELLOW,
+            "gold" => Color::GOLD,
+            "orange" => Color::ORANGE,
+            "pink" => Color::PINK,
+            "purple" => Color::PURPLE,
+            "brown" => Color::BROWN,
+            "silver" => Color::SILVER,
+            "gray" => Color::GRAY,
+            _ => return None,
+        })
+    }
+}
@@ -1,27 +1,9 @@
 use crate::{
-    graphics::{Color, Gpu, Index, Instance, Instance2D, Mesh, SpriteArrayIndex, Vertex},
+    graphics::{Color, Gpu, Instance, Instance2D, InstanceBuffer, SpriteArrayIndex},
     math::{Isometry2, Vector2},
-    text::Font,
+    text::{Font, GlyphKey, GlyphLease, TextSpan},
 };
-
-#[repr(C)]
-#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct TextVertex2D {
-    pub pos: Vector2<f32>,
-    pub tex: Vector2<f32>,
-    pub color: Color,
-    pub sprite: u32,
-}
-
-impl Vertex for TextVertex2D {
-    const ATTRIBUTES: &'static [wgpu::VertexFormat] = &[
-        wgpu::VertexFormat::Float32x2,
-        wgpu::VertexFormat::Float32x2,
-        wgpu::VertexFormat::Float32x4,
-        wgpu::VertexFormat::Uint32,
-    ];
-}
+use rustc_hash::FxHashSet;
 
 pub enum TextAlignment {
     Start,
@@ -32,7 +14,11 @@ pub enum TextAlignment {
 pub struct TextSection<S: AsRef<str>> {
     pub color: Color,
     pub text: S,
+    /// Replaces `text` when not empty.
+    pub spans: Vec<TextSpan>,
     pub size: f32,
+    /// Wraps lines at whitespace once they get wider than this.
+    pub max_width: Option<f32>,
     pub offset: Isometry2<f32>,
     pub rotation_axis: Vector2<f32>,
     pub horizontal_alignment: TextAlignment,
@@ -44,7 +30,9 @@ impl Default for TextSection<&str> {
         Self {
             color: Color::BLACK,
             text: "",
+            spans: Vec::new(),
             size: 1.0,
+            max_width: None,
             offset: Isometry2::default(),
             horizontal_alignment: TextAlignment::Start,
             vertical_alignment: TextAlignment::Start,
@@ -58,7 +46,9 @@ impl Default for TextSection<String> {
         Self {
             color: Color::BLACK,
             text: String::from(""),
+            spans: Vec::new(),
             size: 1.0,
+            max_width: None,
             offset: Isometry2::default(),
             horizontal_alignment: TextAlignment::Start,
             vertical_alignment: TextAlignment::Start,
@@ -67,176 +57,202 @@ impl Default for TextSection<String> {
     }
 }
 
+struct TextRun<'a> {
+    text: &'a str,
+    color: Color,
+    size: f32,
+    font: usize,
+    bold: bool,
+}
+
+struct LineGlyph<'a> {
+    glyph: rusttype::PositionedGlyph<'a>,
+    run: usize,
+}
+
+#[derive(Default)]
+struct Line<'a> {
+    glyphs: Vec<LineGlyph<'a>>,
+    size: f32,
+    break_at: Option<usize>,
+}
+
 impl<S: AsRef<str>> TextSection<S> {
+    const BOLD_OFFSET: f32 = 0.04;
+
+    fn runs(&self, font: &Font) -> Vec<TextRun> {
+        if self.spans.is_empty() {
+            return vec![TextRun {
+                text: self.text.as_ref(),
+                color: self.color,
+                size: self.size,
+                font: 0,
+                bold: false,
+            }];
+        }
+        self.spans
+            .iter()
+            .map(|span| TextRun {
+                text: &span.text,
+                color: span.color.unwrap_or(self.color),
+                size: span.size.unwrap_or(self.size),
+                font: font.face_index(span.font),
+                bold: span.bold,
+            })
+            .collect()
+    }
+
+    fn compute_lines<'a>(&self, faces: &[rusttype::Font<'a>], runs: &[TextRun]) -> Vec<Line<'a>> {
+        let mut lines = vec![Line::default()];
+        let mut caret = 0.0;
+        for (run_index, run) in runs.iter().enumerate() {
+            let font = &faces[run.font];
+            let scaling = rusttype::Scale::uniform(run.size);
+            for (i, text) in run.text.split('\n').enumerate() {
+                let text = text.strip_suffix('\r').unwrap_or(text);
+                if i > 0 {
+                    lines.push(Line::default());
+                    caret = 0.0;
+                }
+                let line = lines.last_mut().unwrap();
+                line.size = line.size.max(run.size);
+
+                let mut last = None;
+                for c in text.chars() {
+                    let glyph = font.glyph(c).scaled(scaling);
+                    let id = glyph.id();
+                    if let Some(last) = last {
+                        caret += font.pair_kerning(scaling, last, id);
+                    }
+                    last = Some(id);
+                    let advance = glyph.h_metrics().advance_width;
+
+                    let line = lines.last_mut().unwrap();
+                    if let (Some(max_width), Some(break_at)) = (self.max_width, line.break_at) {
+                        if !c.is_whitespace() && caret + advance > max_width && break_at > 0 {
+                            let mut wrapped = line.glyphs.split_off(break_at);
+                            line.break_at = None;
+                            let shift = wrapped
+                                .first()
+                                .map(|g| g.glyph.position().x)
+                                .unwrap_or(caret);
+                            let mut size = run.size;
+                            for g in &mut wrapped {
+                                let x = g.glyph.position().x - shift;
+                                g.glyph = g
+                                    .glyph
+                                    .unpositioned()
+                                    .clone()
+                                    .positioned(rusttype::point(x, 0.0));
+                                size = size.max(runs[g.run].size);
+                            }
+                            caret -= shift;
+                            lines.push(Line {
+                                glyphs: wrapped,
+                                size,
+                                break_at: None,
+                            });
+                        }
+                    }
+
+                    let line = lines.last_mut().unwrap();
+                    line.glyphs.push(LineGlyph {
+                        glyph: glyph.positioned(rusttype::point(caret, 0.0)),
+                        run: run_index,
+                    });
+                    if c.is_whitespace() {
+                        line.break_at = Some(line.glyphs.len());
+                    }
+                    caret += advance;
+                }
+            }
+        }
+        lines
+    }
+
     fn compute_layout(
-        font: &Font,
+        gpu: &Gpu,
+        font: &mut Font,
         sections: &[TextSection<S>],
         mut letter: impl FnMut(FormattedGlyph<S>),
     ) {
+        font.cache.next_generation();
+        let metrics = font.faces[0].v_metrics(rusttype::Scale::uniform(1.0));
+        let gap = metrics.descent.abs() + metrics.line_gap;
+
         for section in sections {
-            let text = section.text.as_ref();
-            if text.is_empty() {
+            let runs = section.runs(font);
+            if runs.iter().all(|run| run.text.is_empty()) {
                 continue;
             }
 
-            let scaling = rusttype::Scale::uniform(section.size);
-            let metrics = font.font.v_metrics(scaling);
-            let mut off_y = 0.0;
-            for line in text.lines() {
-                let glyphs = font
-                    .font
-                    .layout(line, scaling, rusttype::Point::default())
-                    .collect::<Vec<rusttype::PositionedGlyph>>();
+            let Font { faces, cache } = &mut *font;
+            let lines = section.compute_lines(faces, &runs);
+            let height = lines.iter().map(|line| line.size).sum::<f32>()
+                + lines[..lines.len() - 1]
+                    .iter()
+                    .map(|line| line.size * gap)
+                    .sum::<f32>();
+            let block = match section.vertical_alignment {
+                TextAlignment::Start => height,
+                TextAlignment::Center => height / 2.0,
+                TextAlignment::End => 0.0,
+            };
 
-                let horizontal = match section.horizontal_alignment {
-                    TextAlignment::Start => 0.0,
-                    TextAlignment::Center => {
-                        let mut max = 0.0;
-                        for glyph in glyphs.iter().rev() {
-                            if let Some(bb) = glyph.unpositioned().exact_bounding_box() {
-                                max = glyph.position().x + bb.max.x;
-                                break;
-                            }
-                        }
-                        max / 2.0
+            let mut top = 0.0;
+            for line in &lines {
+                let mut width = 0.0;
+                for g in line.glyphs.iter().rev() {
+                    if let Some(bb) = g.glyph.unpositioned().exact_bounding_box() {
+                        width = g.glyph.position().x + bb.max.x;
+                        break;
                     }
-                    TextAlignment::End => {
-                        let mut max = 0.0;
-                        for glyph in glyphs.iter().rev() {
-                            if let Some(bb) = glyph.unpositioned().exact_bounding_box() {
-                                max = glyph.position().x + bb.max.x;
-                                break;
-                            }
-                        }
-                        max
-                    }
-                };
-                let vertical = match section.vertical_alignment {
+                }
+                let horizontal = match section.horizontal_alignment {
                     TextAlignment::Start => 0.0,
-                    TextAlignment::Center => section.size / 2.0,
-                    TextAlignment::End => section.size,
+                    TextAlignment::Center => width / 2.0,
+                    TextAlignment::End => width,
                 };
+                let baseline = block - top - line.size;
 
-                for glyph in &glyphs {
-                    if let Some(bb) = glyph.unpositioned().exact_bounding_box() {
-                        if let Some((id, scaling)) = font.index_map.get(&glyph.id()) {
+                for g in &line.glyphs {
+                    if let Some(bb) = g.glyph.unpositioned().exact_bounding_box() {
+                        let run = &runs[g.run];
+                        let key = (run.font, g.glyph.id());
+                        if let Some((id, scaling)) = cache.glyph(gpu, &faces[run.font], key) {
                             let size = Vector2::new(bb.width(), bb.height());
-                            let offset = Vector2::new(-horizontal, -bb.max.y - vertical - off_y);
-                            let bottom_left =
-                                Vector2::new(glyph.position().x, glyph.position().y) + offset;
+                            let bottom_left = Vector2::new(
+                                g.glyph.position().x - horizontal,
+                                baseline - bb.max.y,
+                            );
                             letter(FormattedGlyph {
                                 size,
                                 bottom_left,
                                 section,
-                                tex_scaling: *scaling,
-                                id: *id,
-                            })
+                                color: run.color,
+                                tex_scaling: scaling,
+                                glyph: key,
+                                id,
+                            });
+                            if run.bold {
+                                letter(FormattedGlyph {
+                                    size,
+                                    bottom_left: bottom_left
+                                        + Vector2::new(run.size * Self::BOLD_OFFSET, 0.0),
+                                    section,
+                                    color: run.color,
+                                    tex_scaling: scaling,
+                                    glyph: key,
+                                    id,
+                                });
+                            }
                         }
                     }
                 }
 
-                off_y += section.size + metrics.descent.abs() + metrics.line_gap;
-            }
-        }
-    }
-}
-
-pub struct TextMesh {
-    mesh: Mesh<TextVertex2D>,
-}
-
-impl TextMesh {
-    pub fn new<S: AsRef<str>>(gpu: &Gpu, font: &Font, sections: &[TextSection<S>]) -> Self {
-        let builder = Self::compute_vertices(font, sections);
-        let mesh = gpu.create_mesh(&builder);
-        Self { mesh }
-    }
-
-    pub fn compute_vertices<S: AsRef<str>>(
-        font: &Font,
-        sections: &[TextSection<S>],
-    ) -> (Vec<TextVertex2D>, Vec<Index>) {
-        let mut vertices: Vec<TextVertex2D> = vec![];
-        let mut indices: Vec<Index> = vec![];
-
-        fn compute_modifed_vertices(
-            vertices: &mut [TextVertex2D],
-            offset: Isometry2<f32>,
-            rotation_axis: Vector2<f32>,
-        ) {
-            let angle = offset.rotation.angle();
-            if angle != 0.0 {
-                for v in vertices.iter_mut() {
-                    let delta = v.pos - rotation_axis;
-                    v.pos = rotation_axis + offset.rotation * delta;
-                    v.pos = offset.rotation * v.pos;
-                }
-            }
-
-            if offset.translation.vector != Vector2::zeros() {
-                for v in vertices.iter_mut() {
-                    v.pos += offset.translation.vector;
-                }
+                top += line.size + line.size * gap;
             }
         }
-        TextSection::compute_layout(font, sections, |letter| {
-            let base_index = vertices.len() as u32;
-            let top_right = letter.bottom_left + letter.size;
-            let bottom_right = letter.bottom_left + Vector2::new(letter.size.x, 0.0);
-            let top_left = letter.bottom_left + Vector2::new(0.0, letter.size.y);
-
-            vertices.extend([
-                TextVertex2D {
-                    pos: top_left,
-                    tex: Vector2::new(0.0, 0.0),
-                    color: letter.section.color,
-                    sprite: letter.id,
-                },
-                TextVertex2D {
-                    pos: letter.bottom_left,
-                    tex: Vector2::new(0.0, letter.tex_scaling.y),
-                    color: letter.section.color,
-                    sprite: letter.id,
-                },
-                TextVertex2D {
-                    pos: bottom_right,
-                    tex: Vector2::new(letter.tex_scaling.x, letter.tex_scaling.y),
-                    color: letter.section.color,
-                    sprite: letter.id,
-                },
-                TextVertex2D {
-                    pos: top_right,
-                    tex: Vector2::new(letter.tex_scaling.x, 0.0),
-                    color: letter.section.color,
-                    sprite: letter.id,
-                },
-            ]);
-            let offset = vertices.len() - 4;
-            compute_modifed_vertices(
-                &mut vertices[offset..],
-                letter.section.offset,
-                letter.section.rotation_axis,
-            );
-            indices.extend([
-                base_index,
-                base_index + 1,
-                base_index + 2,
-                base_index + 2,
-                base_index + 3,
-                base_index,
-            ]);
-        });
-
-        (vertices, indices)
-    }
-
-    pub fn write<S: AsRef<str>>(&mut self, gpu: &Gpu, font: &Font, sections: &[TextSection<S>]) {
-        let builder = Self::compute_vertices(font, sections);
-        self.mesh.write(gpu, &builder);
-    }
-
-    pub fn mesh(&self) -> &Mesh<TextVertex2D> {
-        &self.mesh
     }
 }
 
@@ -261,14 +277,59 @@ pub struct LetterData {
     pub index: SpriteArrayIndex,
 }
 
-pub trait CreateTextCollection {
-    fn text<S: AsRef<str>>(font: &Font, sections: &[TextSection<S>]) -> Self;
+pub struct Text {
+    instances: InstanceBuffer<TextInstance2D>,
+    // Keeps the glyphs of the instances from being evicted from the cache
+    glyphs: Vec<GlyphLease>,
 }
 
-impl CreateTextCollection for TextInstanceCollection2D {
-    fn text<S: AsRef<str>>(font: &Font, sections: &[TextSection<S>]) -> Self {
-        let mut instances = vec![];
-        TextSection::compute_layout(font, sections, |letter| {
+impl Text {
+    pub fn new<S: AsRef<str>>(gpu: &Gpu, font: &mut Font, sections: &[TextSection<S>]) -> Self {
+        let mut instances = Vec::new();
+        let mut glyphs = Vec::new();
+        Self::compute_leased_instances(gpu, font, sections, &mut instances, &mut glyphs);
+        Self {
+            instances: InstanceBuffer::new(gpu, &instances),
+            glyphs,
+        }
+    }
+
+    /// Instances computed here point at layers of the glyph cache, which can be given to other
+    /// glyphs once the cache is full. Use a [Text] to keep them valid for as long as they are
+    /// drawn.
+    pub fn compute_instances<S: AsRef<str>>(
+        gpu: &Gpu,
+        font: &mut Font,
+        sections: &[TextSection<S>],
+        instances: &mut TextInstanceCollection2D,
+    ) {
+        Self::push_instances(gpu, font, sections, instances, |_| {});
+    }
+
+    fn compute_leased_instances<S: AsRef<str>>(
+        gpu: &Gpu,
+        font: &mut Font,
+        sections: &[TextSection<S>],
+        instances: &mut TextInstanceCollection2D,
+        glyphs: &mut Vec<GlyphLease>,
+    ) {
+        let mut used = FxHashSet::default();
+        Self::push_instances(gpu, font, sections, instances, |glyph| {
+            used.insert(glyph);
+        });
+        glyphs.clear();
+        glyphs.extend(used.into_iter().filter_map(|id| font.cache.slots.lease(id)));
+    }
+
+    fn push_instances<S: AsRef<str>>(
+        gpu: &Gpu,
+        font: &mut Font,
+        sections: &[TextSection<S>],
+        instances: &mut TextInstanceCollection2D,
+        mut used: impl FnMut(GlyphKey),
+    ) {
+        TextSection::compute_layout(gpu, font, sections, |letter| {
+            used(letter.glyph);
             let rotation = letter.section.offset.rotation;
             let rotation_axis = letter.section.rotation_axis;
             let mut pos = letter.bottom_left + letter.size / 2.0;
@@ -283,12 +344,28 @@ impl CreateTextCollection for TextInstanceCollection2D {
                 letter.size,
                 LetterData {
                     tex_scaling: letter.tex_scaling,
-                    color: letter.section.color,
+                    color: letter.color,
                     index: letter.id,
                 },
             ));
         });
-        instances
+    }
+
+    pub fn write<S: AsRef<str>>(
+        &mut self,
+        gpu: &Gpu,
+        font: &mut Font,
+        sections: &[TextSection<S>],
+    ) {
+        let mut instances = std::mem::take(&mut self.instances.data);
+        instances.clear();
+        Self::compute_leased_instances(gpu, font, sections, &mut instances, &mut self.glyphs);
+        self.instances.write(gpu, &instances);
+        self.instances.data = instances;
+    }
+
+    pub fn instances(&self) -> &InstanceBuffer<TextInstance2D> {
+        &self.instances
     }
 }
 
@@ -297,5 +374,7 @@ struct FormattedGlyph<'a, S: AsRef<str>> {
     tex_scaling: Vector2<f32>,
     bottom_left: Vector2<f32>,
     section: &'a TextSection<S>,
+    color: Color,
+    glyph: GlyphKey,
     id: u32,
 }
@@ -0,0 +1,9 @@
+mod nav_grid;
+#[cfg(feature = "tiled")]
+mod tiled;
+mod tilemap;
+
+pub use nav_grid::*;
+#[cfg(feature = "tiled")]
+pub use tiled::*;
+pub use tilemap::*;
@@ -0,0 +1,542 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+#[cfg(feature = "physics")]
+use crate::{
+    math::Isometry2,
+    physics::{Cuboid, Physics, QueryFilter},
+};
+use crate::{
+    math::{Point2, Vector2, AABB},
+    tilemap::{TileId, TileMap},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+struct OpenCell {
+    estimate: f32,
+    index: u32,
+}
+
+impl Eq for OpenCell {}
+
+impl Ord for OpenCell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, so that the BinaryHeap pops the lowest estimate first
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for OpenCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Grid of walkable cells for A* pathfinding. Cell (0, 0) is the bottom left cell, matching the
+/// tiles of a [TileMap].
+#[derive(Clone, Debug)]
+pub struct NavGrid {
+    size: Vector2<u32>,
+    cell_size: Vector2<f32>,
+    position: Vector2<f32>,
+    walkable: Vec<u64>,
+    diagonal: bool,
+    smoothing: bool,
+    max_explored: usize,
+}
+
+impl NavGrid {
+    pub const DEFAULT_MAX_EXPLORED: usize = 100_000;
+    const ORTHOGONAL: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+    const DIAGONAL: [(i32, i32); 4] = [(1, 1), (-1, 1), (1, -1), (-1, -1)];
+
+    /// Grid with all cells walkable
+    pub fn new(size: Vector2<u32>, cell_size: Vector2<f32>) -> Self {
+        let cells = (size.x * size.y) as usize;
+        let mut walkable = vec![u64::MAX; cells.div_ceil(64)];
+        if cells % 64 != 0 {
+            *walkable.last_mut().unwrap() = (1 << (cells % 64)) - 1;
+        }
+        Self {
+            size,
+            cell_size,
+            position: Vector2::zeros(),
+            walkable,
+            diagonal: true,
+            smoothing: true,
+            max_explored: Self::DEFAULT_MAX_EXPLORED,
+        }
+    }
+
+    /// Samples `walkable` at the center of every cell
+    pub fn from_fn(
+        size: Vector2<u32>,
+        cell_size: Vector2<f32>,
+        position: Vector2<f32>,
+        walkable: impl FnMut(Vector2<f32>) -> bool,
+    ) -> Self {
+        let mut grid = Self::new(size, cell_size).with_position(position);
+        let aabb = grid.aabb();
+        grid.update_region(&aabb, walkable);
+        grid
+    }
+
+    /// One cell per tile, tiles for which `walkable` returns false block the path
+    pub fn from_tilemap(tilemap: &TileMap, walkable: impl Fn(TileId) -> bool) -> Self {
+        let size = tilemap.size();
+        let mut grid = Self::new(size, tilemap.tile_size()).with_position(tilemap.position());
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let cell = Vector2::new(x, y);
+                grid.set_walkable(cell, walkable(tilemap.tile(x, y)));
+            }
+        }
+        grid
+    }
+
+    /// Cells that intersect a collider matching `filter` block the path
+    #[cfg(feature = "physics")]
+    pub fn from_colliders(
+        physics: &Physics,
+        size: Vector2<u32>,
+        cell_size: Vector2<f32>,
+        position: Vector2<f32>,
+        filter: QueryFilter,
+    ) -> Self {
+        let mut grid = Self::new(size, cell_size).with_position(position);
+        let aabb = grid.aabb();
+        grid.update_colliders(physics, &aabb, filter);
+        grid
+    }
+
+    pub fn with_position(mut self, position: Vector2<f32>) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Allows moving diagonally, but never past the corner of a blocked cell. Enabled by default.
+    pub fn with_diagonal(mut self, diagonal: bool) -> Self {
+        self.diagonal = diagonal;
+        self
+    }
+
+    /// Removes all points of a path that can be skipped by walking in a straight line.
+    /// Enabled by default.
+    pub fn with_smoothing(mut self, smoothing: bool) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Amount of cells a search may explore before giving up and returning `None`
+    pub fn with_max_explored(mut self, max_explored: usize) -> Self {
+        self.max_explored = max_explored;
+        self
+    }
+
+    fn index(&self, cell: Vector2<u32>) -> usize {
+        assert!(
+            cell.x < self.size.x && cell.y < self.size.y,
+            "Cell ({}, {}) is out of bounds!",
+            cell.x,
+            cell.y
+        );
+        (cell.y * self.size.x + cell.x) as usize
+    }
+
+    fn cell_of(&self, index: usize) -> Vector2<u32> {
+        Vector2::new(index as u32 % self.size.x, index as u32 / self.size.x)
+    }
+
+    fn walkable_at(&self, x: i32, y: i32) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as u32) < self.size.x
+            && (y as u32) < self.size.y
+            && self.is_walkable(Vector2::new(x as u32, y as u32))
+    }
+
+    pub fn is_walkable(&self, cell: Vector2<u32>) -> bool {
+        let index = self.index(cell);
+        self.walkable[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Changes a single cell, e.g. when a wall gets destroyed. Paths are searched on demand, so
+    /// nothing has to be rebuilt.
+    pub fn set_walkable(&mut self, cell: Vector2<u32>, walkable: bool) {
+        let index = self.index(cell);
+        if walkable {
+            self.walkable[index / 64] |= 1 << (index % 64);
+        } else {
+            self.walkable[index / 64] &= !(1 << (index % 64));
+        }
+    }
+
+    /// Samples `walkable` again only for the cells whose center lies in `region`
+    pub fn update_region(&mut self, region: &AABB, mut walkable: impl FnMut(Vector2<f32>) -> bool) {
+        let Some((min, max)) = self.cells_in(region) else {
+            return;
+        };
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let cell = Vector2::new(x, y);
+                let center = self.cell_center(cell);
+                if region.contains_point(&center) {
+                    self.set_walkable(cell, walkable(center));
+                }
+            }
+        }
+    }
+
+    /// Tests the cells whose center lies in `region` against the colliders again
+    #[cfg(feature = "physics")]
+    pub fn update_colliders(&mut self, physics: &Physics, region: &AABB, filter: QueryFilter) {
+        // Slightly smaller than the cell, so that colliders only touching it do not block it
+        let cell_shape = Cuboid::new(self.cell_size * 0.49);
+        self.update_region(region, |center| {
+            physics
+                .intersection_with_shape(
+                    &Isometry2::translation(center.x, center.y),
+                    &cell_shape,
+                    filter,
+                )
+                .is_none()
+        });
+    }
+
+    fn cells_in(&self, region: &AABB) -> Option<(Vector2<u32>, Vector2<u32>)> {
+        let min = (region.min() - self.position).component_div(&self.cell_size);
+        let max = (region.max() - self.position).component_div(&self.cell_size);
+        if max.x < 0.0 || max.y < 0.0 || min.x >= self.size.x as f32 || min.y >= self.size.y as f32
+        {
+            return None;
+        }
+        let clamp = |value: f32, size: u32| (value.max(0.0) as u32).min(size - 1);
+        Some((
+            Vector2::new(clamp(min.x, self.size.x), clamp(min.y, self.size.y)),
+            Vector2::new(clamp(max.x, self.size.x), clamp(max.y, self.size.y)),
+        ))
+    }
+
+    pub fn cell_center(&self, cell: Vector2<u32>) -> Vector2<f32> {
+        self.position
+            + Vector2::new(cell.x as f32 + 0.5, cell.y as f32 + 0.5).component_mul(&self.cell_size)
+    }
+
+    /// Returns the cell at a world position
+    pub fn cell_at(&self, position: Vector2<f32>) -> Option<Vector2<u32>> {
+        let cell = (position - self.position).component_div(&self.cell_size);
+        if cell.x < 0.0 || cell.y < 0.0 {
+            return None;
+        }
+        let cell = Vector2::new(cell.x as u32, cell.y as u32);
+        (cell.x < self.size.x && cell.y < self.size.y).then_some(cell)
+    }
+
+    pub fn size(&self) -> Vector2<u32> {
+        self.size
+    }
+
+    pub fn cell_size(&self) -> Vector2<f32> {
+        self.cell_size
+    }
+
+    pub fn position(&self) -> Vector2<f32> {
+        self.position
+    }
+
+    pub fn aabb(&self) -> AABB {
+        AABB::new(
+            self.position,
+            self.position + self.size.cast::<f32>().component_mul(&self.cell_size),
+        )
+    }
+
+    /// Octile distance in cells, or the manhattan distance without diagonal movement
+    fn heuristic(&self, from: Vector2<u32>, to: Vector2<u32>) -> f32 {
+        let dx = from.x.abs_diff(to.x) as f32;
+        let dy = from.y.abs_diff(to.y) as f32;
+        if self.diagonal {
+            dx.max(dy) + (std::f32::consts::SQRT_2 - 1.0) * dx.min(dy)
+        } else {
+            dx + dy
+        }
+    }
+
+    /// Path from `start` to `goal` in world space, starting and ending with the given points.
+    /// Returns `None` if either point is outside of the grid or blocked, if there is no path or
+    /// if the search explored more than [with_max_explored](Self::with_max_explored) cells.
+    pub fn find_path(&self, start: Point2<f32>, goal: Point2<f32>) -> Option<Vec<Point2<f32>>> {
+        let start_cell = self.cell_at(start.coords)?;
+        let goal_cell = self.cell_at(goal.coords)?;
+        let cells = self.find_cells(start_cell, goal_cell)?;
+        if cells.len() == 1 {
+            return Some(vec![start, goal]);
+        }
+
+        let mut path: Vec<Point2<f32>> = cells
+            .into_iter()
+            .map(|cell| self.cell_center(cell).into())
+            .collect();
+        let last = path.len() - 1;
+        path[0] = start;
+        path[last] = goal;
+        if self.smoothing {
+            path = self.smooth(&path);
+        }
+        Some(path)
+    }
+
+    /// A* search on the cells of the grid, returns every cell of the path including `start`
+    /// and `goal`
+    pub fn find_cells(&self, start: Vector2<u32>, goal: Vector2<u32>) -> Option<Vec<Vector2<u32>>> {
+        if !self.is_walkable(start) || !self.is_walkable(goal) {
+            return None;
+        }
+        let cells = (self.size.x * self.size.y) as usize;
+        let start_index = self.index(start);
+        let goal_index = self.index(goal);
+        let mut costs = vec![f32::INFINITY; cells];
+        let mut parents = vec![u32::MAX; cells];
+        let mut closed = vec![false; cells];
+        let mut open = BinaryHeap::new();
+        let mut explored = 0;
+
+        costs[start_index] = 0.0;
+        open.push(OpenCell {
+            estimate: self.heuristic(start, goal),
+            index: start_index as u32,
+        });
+        while let Some(OpenCell { index, .. }) = open.pop() {
+            let index = index as usize;
+            if closed[index] {
+                continue;
+            }
+            if index == goal_index {
+                let mut path = vec![goal];
+                let mut current = index;
+                while current != start_index {
+                    current = parents[current] as usize;
+                    path.push(self.cell_of(current));
+                }
+                path.reverse();
+                return Some(path);
+            }
+            closed[index] = true;
+            explored += 1;
+            if explored > self.max_explored {
+                return None;
+            }
+
+            let cell = self.cell_of(index);
+            let (x, y) = (cell.x as i32, cell.y as i32);
+            let orthogonal = Self::ORTHOGONAL.iter().map(|offset| (*offset, 1.0));
+            let diagonal = Self::DIAGONAL
+                .iter()
+                .filter(|_| self.diagonal)
+                // Both neighbours must be free, so that the path never cuts a corner
+                .filter(|(dx, dy)| self.walkable_at(x + dx, y) && self.walkable_at(x, y + dy))
+                .map(|offset| (*offset, std::f32::consts::SQRT_2));
+            for ((dx, dy), step) in orthogonal.chain(diagonal) {
+                if !self.walkable_at(x + dx, y + dy) {
+                    continue;
+                }
+                let neighbour = Vector2::new((x + dx) as u32, (y + dy) as u32);
+                let neighbour_index = self.index(neighbour);
+                let cost = costs[index] + step;
+                if closed[neighbour_index] || cost >= costs[neighbour_index] {
+                    continue;
+                }
+                costs[neighbour_index] = cost;
+                parents[neighbour_index] = index as u32;
+                open.push(OpenCell {
+                    estimate: cost + self.heuristic(neighbour, goal),
+                    index: neighbour_index as u32,
+                });
+            }
+        }
+        None
+    }
+
+    /// String pulling: keeps only the points where a straight line to the next point would
+    /// cross a blocked cell
+    fn smooth(&self, path: &[Point2<f32>]) -> Vec<Point2<f32>> {
+        let mut smoothed = vec![path[0]];
+        let mut anchor = path[0];
+        for i in 2..path.len() {
+            if !self.line_of_sight(anchor.coords, path[i].coords) {
+                anchor = path[i - 1];
+                smoothed.push(anchor);
+            }
+        }
+        smoothed.push(path[path.len() - 1]);
+        smoothed
+    }
+
+    /// Walks all cells that the line from `from` to `to` touches
+    pub fn line_of_sight(&self, from: Vector2<f32>, to: Vector2<f32>) -> bool {
+        let from = (from - self.position).component_div(&self.cell_size);
+        let to = (to - self.position).component_div(&self.cell_size);
+        let direction = to - from;
+        let mut x = from.x.floor() as i32;
+        let mut y = from.y.floor() as i32;
+        let end_x = to.x.floor() as i32;
+        let end_y = to.y.floor() as i32;
+        let step_x = if direction.x > 0.0 { 1 } else { -1 };
+        let step_y = if direction.y > 0.0 { 1 } else { -1 };
+        let delta_x = (1.0 / direction.x).abs();
+        let delta_y = (1.0 / direction.y).abs();
+        let mut next_x = if direction.x == 0.0 {
+            f32::INFINITY
+        } else {
+            ((x + (step_x > 0) as i32) as f32 - from.x) / direction.x
+        };
+        let mut next_y = if direction.y == 0.0 {
+            f32::INFINITY
+        } else {
+            ((y + (step_y > 0) as i32) as f32 - from.y) / direction.y
+        };
+
+        let steps = x.abs_diff(end_x) + y.abs_diff(end_y);
+        for _ in 0..=steps {
+            if !self.walkable_at(x, y) {
+                return false;
+            }
+            if x == end_x && y == end_y {
+                return true;
+            }
+            if (next_x - next_y).abs() < 1e-6 {
+                // Passing exactly through a corner, which is only allowed if both sides are free
+                if !self.walkable_at(x + step_x, y) || !self.walkable_at(x, y + step_y) {
+                    return false;
+                }
+                x += step_x;
+                y += step_y;
+                next_x += delta_x;
+                next_y += delta_y;
+            } else if next_x < next_y {
+                x += step_x;
+                next_x += delta_x;
+            } else {
+                y += step_y;
+                next_y += delta_y;
+            }
+        }
+        x == end_x && y == end_y && self.walkable_at(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::SceneRandom;
+
+    /// The first row is the top of the grid, `#` blocks a cell
+    fn grid(rows: &[&str]) -> NavGrid {
+        let size = Vector2::new(rows[0].len() as u32, rows.len() as u32);
+        let mut grid = NavGrid::new(size, Vector2::new(1.0, 1.0));
+        for (y, row) in rows.iter().rev().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                grid.set_walkable(Vector2::new(x as u32, y as u32), cell != '#');
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn blocked_path() {
+        let grid = grid(&["..#..", "..#..", "..#.."]);
+        let (start, goal) = (Vector2::new(0, 0), Vector2::new(4, 2));
+        assert!(grid.find_cells(start, goal).is_none());
+        assert!(grid
+            .find_path(Point2::new(0.5, 0.5), Point2::new(4.5, 2.5))
+            .is_none());
+        assert!(grid.find_cells(start, Vector2::new(2, 1)).is_none());
+
+        let mut open = grid.clone();
+        open.set_walkable(Vector2::new(2, 1), true);
+        assert!(open.find_cells(start, goal).is_some());
+    }
+
+    #[test]
+    fn diagonals_do_not_cut_corners() {
+        let open = grid(&["...", "...", "..."]);
+        assert_eq!(
+            open.find_cells(Vector2::new(0, 0), Vector2::new(2, 2)),
+            Some(vec![
+                Vector2::new(0, 0),
+                Vector2::new(1, 1),
+                Vector2::new(2, 2)
+            ])
+        );
+
+        let corner = grid(&["..", ".#"]);
+        assert_eq!(
+            corner.find_cells(Vector2::new(0, 0), Vector2::new(1, 1)),
+            Some(vec![
+                Vector2::new(0, 0),
+                Vector2::new(0, 1),
+                Vector2::new(1, 1)
+            ])
+        );
+        assert!(!corner.line_of_sight(Vector2::new(0.5, 0.5), Vector2::new(1.5, 1.5)));
+        assert!(corner.line_of_sight(Vector2::new(0.5, 0.5), Vector2::new(0.5, 1.5)));
+
+        let closed = grid(&["#.", ".#"]);
+        assert!(closed
+            .find_cells(Vector2::new(0, 0), Vector2::new(1, 1))
+            .is_none());
+    }
+
+    #[test]
+    fn max_explored() {
+        let size = Vector2::new(200, 200);
+        let (start, goal) = (Vector2::new(0, 0), Vector2::new(199, 199));
+        let grid = NavGrid::new(size, Vector2::new(1.0, 1.0)).with_diagonal(false);
+        assert!(grid.find_cells(start, goal).is_some());
+        assert!(grid
+            .clone()
+            .with_max_explored(100)
+            .find_cells(start, goal)
+            .is_none());
+    }
+
+    #[test]
+    fn smoothed_paths_avoid_blocked_cells() {
+        const SIZE: u32 = 32;
+        let mut random = SceneRandom::new(90);
+        let mut paths = 0;
+        for _ in 0..100 {
+            let mut grid = NavGrid::new(Vector2::new(SIZE, SIZE), Vector2::new(1.0, 1.0));
+            for y in 0..SIZE {
+                for x in 0..SIZE {
+                    grid.set_walkable(Vector2::new(x, y), random.gen_bool(0.75));
+                }
+            }
+            let mut point = || {
+                Point2::new(
+                    random.gen_range(0.0..SIZE as f32),
+                    random.gen_range(0.0..SIZE as f32),
+                )
+            };
+            let (start, goal) = (point(), point());
+            let Some(path) = grid.find_path(start, goal) else {
+                continue;
+            };
+            paths += 1;
+            assert_eq!(path.first(), Some(&start));
+            assert_eq!(path.last(), Some(&goal));
+            for segment in path.windows(2) {
+                let (from, to) = (segment[0].coords, segment[1].coords);
+                assert!(grid.line_of_sight(from, to));
+                for i in 0..=1000 {
+                    let point = from.lerp(&to, i as f32 / 1000.0);
+                    let cell = grid.cell_at(point).unwrap();
+                    assert!(
+                        grid.is_walkable(cell),
+                        "Path from {from:?} to {to:?} passes the blocked cell {cell:?}"
+                    );
+                }
+            }
+        }
+        assert!(paths > 10);
+    }
+}
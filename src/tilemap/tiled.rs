@@ -0,0 +1,763 @@
+use std::{io::Read, str::FromStr};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use quick_xml::events::{BytesStart, Event};
+use rustc_hash::FxHashMap;
+
+#[cfg(feature = "physics")]
+use crate::physics::ColliderBuilder;
+use crate::{
+    graphics::{AssetKey, Gpu, LayerMismatch, SpriteArrayBuilder},
+    math::{Isometry2, Point2, Rotation2, Vector2},
+    tilemap::{TileId, TileMap, TileMapBuilder},
+};
+
+const FLIPPED_HORIZONTALLY: u32 = 0x80000000;
+const FLIPPED_VERTICALLY: u32 = 0x40000000;
+const FLIPPED_DIAGONALLY: u32 = 0x20000000;
+const ROTATED_HEXAGONAL: u32 = 0x10000000;
+const FLAGS: u32 =
+    FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TiledProperty {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    /// Id of the referenced object, 0 if none
+    Object(u32),
+    Class(TiledProperties),
+}
+
+pub type TiledProperties = FxHashMap<String, TiledProperty>;
+
+/// Shape of a [TiledObject] relative to its position in world units
+#[derive(Clone, Debug, PartialEq)]
+pub enum TiledShape {
+    /// Extends from the position to the right and down like in Tiled
+    Rect(Vector2<f32>),
+    /// Bounding box of the ellipse, extends like [TiledShape::Rect]
+    Ellipse(Vector2<f32>),
+    Point,
+    Polygon(Vec<Point2<f32>>),
+    Polyline(Vec<Point2<f32>>),
+    /// Tile object, extends from the position to the right and up like in Tiled
+    Tile {
+        id: TileId,
+        size: Vector2<f32>,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    pub class: String,
+    pub visible: bool,
+    pub position: Vector2<f32>,
+    /// Counter clockwise in radians
+    pub rotation: f32,
+    pub shape: TiledShape,
+    pub properties: TiledProperties,
+}
+
+impl TiledObject {
+    pub fn isometry(&self) -> Isometry2<f32> {
+        Isometry2::new(self.position, self.rotation)
+    }
+
+    /// Center of the shape in world space
+    pub fn center(&self) -> Vector2<f32> {
+        let offset = match &self.shape {
+            TiledShape::Rect(size) | TiledShape::Ellipse(size) => {
+                Vector2::new(size.x, -size.y) / 2.0
+            }
+            TiledShape::Tile { size, .. } => size / 2.0,
+            TiledShape::Point => Vector2::zeros(),
+            TiledShape::Polygon(points) | TiledShape::Polyline(points) => {
+                points.iter().map(|p| p.coords).sum::<Vector2<f32>>() / points.len().max(1) as f32
+            }
+        };
+        self.position + Rotation2::new(self.rotation) * offset
+    }
+
+    /// Converts the shape into a collider. Concave polygons get decomposed into convex parts.
+    /// Points don't have a collider.
+    #[cfg(feature = "physics")]
+    pub fn collider(&self) -> Option<ColliderBuilder> {
+        let centered = Isometry2::new(self.center(), self.rotation);
+        let collider = match &self.shape {
+            TiledShape::Rect(size) | TiledShape::Tile { size, .. } => {
+                ColliderBuilder::cuboid(size.x / 2.0, size.y / 2.0).position(centered)
+            }
+            TiledShape::Ellipse(size) if (size.x - size.y).abs() < f32::EPSILON => {
+                ColliderBuilder::ball(size.x / 2.0).position(centered)
+            }
+            TiledShape::Ellipse(size) => {
+                const RESOLUTION: usize = 24;
+                let points = (0..RESOLUTION)
+                    .map(|i| {
+                        let angle = i as f32 / RESOLUTION as f32 * std::f32::consts::TAU;
+                        Point2::new(angle.cos() * size.x, angle.sin() * size.y) / 2.0
+                    })
+                    .collect::<Vec<_>>();
+                ColliderBuilder::convex_hull(&points)?.position(centered)
+            }
+            TiledShape::Point => return None,
+            TiledShape::Polygon(points) => {
+                let len = points.len() as u32;
+                let indices = (0..len).map(|i| [i, (i + 1) % len]).collect::<Vec<_>>();
+                ColliderBuilder::convex_decomposition(points, &indices).position(self.isometry())
+            }
+            TiledShape::Polyline(points) => {
+                ColliderBuilder::polyline(points.clone(), None).position(self.isometry())
+            }
+        };
+        Some(collider)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledObjectGroup {
+    pub name: String,
+    pub visible: bool,
+    pub properties: TiledProperties,
+    pub objects: Vec<TiledObject>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledTileLayer {
+    pub name: String,
+    pub visible: bool,
+    pub properties: TiledProperties,
+    /// Row major tiles starting at the bottom left, see [TileMapBuilder::tiles]
+    pub tiles: Vec<TileId>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledTileset {
+    pub name: String,
+    pub first_id: TileId,
+    pub tile_count: u32,
+    /// Size of a tile in pixels
+    pub tile_size: Vector2<u32>,
+}
+
+/// Orthogonal map loaded from a Tiled `.tmx` file. One tile is one world unit wide and the
+/// bottom left corner of the map is at the origin. The [TileId]s are the global tile ids of
+/// Tiled without the flip flags, so all tilesets share one
+/// [SpriteArray](crate::graphics::SpriteArray), see [TiledMap::sprite_array].
+pub struct TiledMap {
+    /// Size in tiles. Infinite maps are cropped to the area that contains chunks.
+    pub size: Vector2<u32>,
+    /// Size of a tile in world units
+    pub tile_size: Vector2<f32>,
+    /// Tile in the top left corner in Tiled's coordinates. Only non zero for infinite maps.
+    pub origin: Vector2<i32>,
+    pub properties: TiledProperties,
+    pub layers: Vec<TiledTileLayer>,
+    pub object_groups: Vec<TiledObjectGroup>,
+    pub tilesets: Vec<TiledTileset>,
+    tile_properties: FxHashMap<TileId, TiledProperties>,
+    tile_images: Vec<Option<image::DynamicImage>>,
+    tile_pixels: Vector2<u32>,
+}
+
+impl TiledMap {
+    pub fn layer(&self, name: &str) -> Option<&TiledTileLayer> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    pub fn object_group(&self, name: &str) -> Option<&TiledObjectGroup> {
+        self.object_groups.iter().find(|group| group.name == name)
+    }
+
+    pub fn objects(&self) -> impl Iterator<Item = &TiledObject> {
+        self.object_groups
+            .iter()
+            .flat_map(|group| group.objects.iter())
+    }
+
+    pub fn tile_properties(&self, id: TileId) -> Option<&TiledProperties> {
+        self.tile_properties.get(&id)
+    }
+
+    /// Builder for the given layer whose tiles index into `sprite_array`
+    pub fn builder(&self, layer: &TiledTileLayer, sprite_array: AssetKey) -> TileMapBuilder {
+        TileMapBuilder::new(self.size, self.tile_size, sprite_array).tiles(layer.tiles.clone())
+    }
+
+    /// One [TileMap] per visible tile layer in draw order
+    pub fn tilemaps(&self, gpu: &Gpu, sprite_array: AssetKey) -> Vec<TileMap> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.visible)
+            .map(|layer| gpu.create_tilemap(self.builder(layer, sprite_array)))
+            .collect()
+    }
+
+    /// Sprite array with one layer per tile id of all tilesets. Tiles of different sizes get
+    /// resized to the largest one.
+    pub fn sprite_array(&self) -> Result<SpriteArrayBuilder<'static, image::RgbaImage>> {
+        let images = self
+            .tile_images
+            .iter()
+            .map(|image| {
+                image.clone().unwrap_or_else(|| {
+                    image::DynamicImage::new_rgba8(self.tile_pixels.x, self.tile_pixels.y)
+                })
+            })
+            .collect::<Vec<_>>();
+        SpriteArrayBuilder::images_with(&images, LayerMismatch::Resize)
+    }
+}
+
+impl TileMapBuilder {
+    /// Loads a map from a resource. Tilesets and images are loaded relative to the map.
+    pub fn tiled_resource(path: &str) -> Result<TiledMap> {
+        let resources = crate::app::global_resources();
+        let tmx = resources.load_bytes(path)?;
+        let dir = parent_dir(path);
+        Self::tiled(&tmx, |source| resources.load_bytes(&join_path(dir, source)))
+    }
+
+    /// Parses an orthogonal Tiled map. `resolver` loads external tilesets and images by the path
+    /// relative to the map. CSV, base64, zlib and gzip encoded layers as well as infinite maps
+    /// are supported. Flipped tiles are loaded without their flip.
+    pub fn tiled(
+        tmx: &[u8],
+        mut resolver: impl FnMut(&str) -> Result<Vec<u8>>,
+    ) -> Result<TiledMap> {
+        let map = XmlNode::parse(tmx).context("Invalid Tiled map!")?;
+        if map.tag != "map" {
+            bail!("Expected <map> but found <{}>!", map.tag);
+        }
+        let orientation = map.attr("orientation").unwrap_or("orthogonal");
+        if orientation != "orthogonal" {
+            bail!("Only orthogonal maps are supported, found {orientation}!");
+        }
+        let infinite = map.attr_or("infinite", 0u32)? != 0;
+        let map_size = Vector2::new(map.attr_or("width", 0u32)?, map.attr_or("height", 0u32)?);
+        let tile_pixels = Vector2::new(
+            map.required::<u32>("tilewidth")?,
+            map.required::<u32>("tileheight")?,
+        );
+
+        let mut tilesets = vec![];
+        let mut tile_images = vec![];
+        let mut tile_properties = FxHashMap::default();
+        for node in map.children("tileset") {
+            let first_id = node.required::<TileId>("firstgid")?;
+            let tileset = if let Some(source) = node.attr("source") {
+                let tsx =
+                    resolver(source).with_context(|| format!("Cannot load tileset {source}!"))?;
+                let tsx =
+                    XmlNode::parse(&tsx).with_context(|| format!("Invalid tileset {source}!"))?;
+                parse_tileset(
+                    &tsx,
+                    first_id,
+                    parent_dir(source),
+                    &mut resolver,
+                    &mut tile_images,
+                    &mut tile_properties,
+                )?
+            } else {
+                parse_tileset(
+                    node,
+                    first_id,
+                    "",
+                    &mut resolver,
+                    &mut tile_images,
+                    &mut tile_properties,
+                )?
+            };
+            tilesets.push(tileset);
+        }
+
+        let mut raw_layers = vec![];
+        let mut raw_groups = vec![];
+        collect_layers(&map, true, &mut raw_layers, &mut raw_groups)?;
+
+        let (origin, size) = if infinite {
+            let chunks = raw_layers.iter().flat_map(|layer| layer.chunks.iter());
+            let mut min = Vector2::new(i32::MAX, i32::MAX);
+            let mut max = Vector2::new(i32::MIN, i32::MIN);
+            for chunk in chunks {
+                min = min.inf(&chunk.position);
+                max = max.sup(&(chunk.position + chunk.size.cast::<i32>()));
+            }
+            if min.x > max.x {
+                (Vector2::zeros(), Vector2::zeros())
+            } else {
+                (min, (max - min).map(|v| v as u32))
+            }
+        } else {
+            (Vector2::zeros(), map_size)
+        };
+
+        let layers = raw_layers
+            .into_iter()
+            .map(|layer| {
+                let mut tiles = vec![TileMap::EMPTY; (size.x * size.y) as usize];
+                for chunk in &layer.chunks {
+                    for (i, gid) in chunk.tiles.iter().enumerate() {
+                        let tiled = chunk.position
+                            + Vector2::new(
+                                i as i32 % chunk.size.x as i32,
+                                i as i32 / chunk.size.x as i32,
+                            )
+                            - origin;
+                        if tiled.x < 0
+                            || tiled.y < 0
+                            || tiled.x >= size.x as i32
+                            || tiled.y >= size.y as i32
+                        {
+                            continue;
+                        }
+                        let y = size.y - 1 - tiled.y as u32;
+                        tiles[(y * size.x + tiled.x as u32) as usize] = gid & !FLAGS;
+                    }
+                }
+                TiledTileLayer {
+                    name: layer.name,
+                    visible: layer.visible,
+                    properties: layer.properties,
+                    tiles,
+                }
+            })
+            .collect();
+
+        let pixels_per_unit = tile_pixels.x as f32;
+        let top = (origin.y + size.y as i32) as f32 * tile_pixels.y as f32;
+        let left = origin.x as f32 * tile_pixels.x as f32;
+        let to_world =
+            |pixel: Vector2<f32>| Vector2::new(pixel.x - left, top - pixel.y) / pixels_per_unit;
+        let object_groups = raw_groups
+            .into_iter()
+            .map(|(group, visible)| parse_object_group(group, visible, pixels_per_unit, &to_world))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(TiledMap {
+            size,
+            tile_size: tile_pixels.cast::<f32>() / pixels_per_unit,
+            origin,
+            properties: parse_properties(&map)?,
+            layers,
+            object_groups,
+            tilesets,
+            tile_properties,
+            tile_images,
+            tile_pixels,
+        })
+    }
+}
+
+struct RawChunk {
+    position: Vector2<i32>,
+    size: Vector2<u32>,
+    tiles: Vec<u32>,
+}
+
+struct RawLayer {
+    name: String,
+    visible: bool,
+    properties: TiledProperties,
+    chunks: Vec<RawChunk>,
+}
+
+fn collect_layers<'a>(
+    node: &'a XmlNode,
+    parent_visible: bool,
+    layers: &mut Vec<RawLayer>,
+    groups: &mut Vec<(&'a XmlNode, bool)>,
+) -> Result<()> {
+    for child in &node.children {
+        let visible = parent_visible && child.attr_or("visible", 1u32)? != 0;
+        match child.tag.as_str() {
+            "layer" => {
+                let data = child
+                    .child("data")
+                    .with_context(|| format!("Layer {} has no data!", child.name()))?;
+                let mut chunks = data
+                    .children("chunk")
+                    .map(|chunk| {
+                        Ok(RawChunk {
+                            position: Vector2::new(
+                                chunk.required::<i32>("x")?,
+                                chunk.required::<i32>("y")?,
+                            ),
+                            size: Vector2::new(
+                                chunk.required::<u32>("width")?,
+                                chunk.required::<u32>("height")?,
+                            ),
+                            tiles: decode_tiles(data, chunk)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                if chunks.is_empty() {
+                    chunks.push(RawChunk {
+                        position: Vector2::zeros(),
+                        size: Vector2::new(
+                            child.required::<u32>("width")?,
+                            child.required::<u32>("height")?,
+                        ),
+                        tiles: decode_tiles(data, data)?,
+                    });
+                }
+                for chunk in &chunks {
+                    if chunk.tiles.len() != (chunk.size.x * chunk.size.y) as usize {
+                        bail!("Layer {} has the wrong amount of tiles!", child.name());
+                    }
+                }
+                layers.push(RawLayer {
+                    name: child.name().to_owned(),
+                    visible,
+                    properties: parse_properties(child)?,
+                    chunks,
+                });
+            }
+            "objectgroup" => groups.push((child, visible)),
+            "group" => collect_layers(child, visible, layers, groups)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn decode_tiles(data: &XmlNode, node: &XmlNode) -> Result<Vec<u32>> {
+    match data.attr("encoding") {
+        None => node
+            .children("tile")
+            .map(|tile| tile.attr_or("gid", 0u32))
+            .collect(),
+        Some("csv") => node
+            .text
+            .split(',')
+            .map(str::trim)
+            .filter(|gid| !gid.is_empty())
+            .map(|gid| {
+                gid.parse::<u32>()
+                    .map_err(|_| anyhow!("Invalid tile id {gid}!"))
+            })
+            .collect(),
+        Some("base64") => {
+            let text = node
+                .text
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>();
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(text)
+                .context("Invalid base64 tile data!")?;
+            let bytes = match data.attr("compression") {
+                None => bytes,
+                Some("zlib") => {
+                    let mut decoded = vec![];
+                    flate2::read::ZlibDecoder::new(&bytes[..]).read_to_end(&mut decoded)?;
+                    decoded
+                }
+                Some("gzip") => {
+                    let mut decoded = vec![];
+                    flate2::read::GzDecoder::new(&bytes[..]).read_to_end(&mut decoded)?;
+                    decoded
+                }
+                Some(compression) => bail!("Unsupported compression {compression}!"),
+            };
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+                .collect())
+        }
+        Some(encoding) => bail!("Unsupported encoding {encoding}!"),
+    }
+}
+
+fn parse_tileset(
+    tileset: &XmlNode,
+    first_id: TileId,
+    dir: &str,
+    resolver: &mut impl FnMut(&str) -> Result<Vec<u8>>,
+    images: &mut Vec<Option<image::DynamicImage>>,
+    properties: &mut FxHashMap<TileId, TiledProperties>,
+) -> Result<TiledTileset> {
+    let mut load_image = |source: &str| -> Result<image::DynamicImage> {
+        let bytes = resolver(&join_path(dir, source))
+            .with_context(|| format!("Cannot load tileset image {source}!"))?;
+        image::load_from_memory(&bytes)
+            .with_context(|| format!("Cannot decode tileset image {source}!"))
+    };
+    let mut set_image = |id: u32, image: image::DynamicImage| {
+        let index = (first_id + id - 1) as usize;
+        if images.len() <= index {
+            images.resize(index + 1, None);
+        }
+        images[index] = Some(image);
+    };
+
+    let tile_size = Vector2::new(
+        tileset.required::<u32>("tilewidth")?,
+        tileset.required::<u32>("tileheight")?,
+    );
+    let mut tile_count = tileset.attr_or("tilecount", 0u32)?;
+    if let Some(image) = tileset.child("image") {
+        let source = image.required::<String>("source")?;
+        let sheet = load_image(&source)?;
+        let spacing = tileset.attr_or("spacing", 0u32)?;
+        let margin = tileset.attr_or("margin", 0u32)?;
+        let columns = match tileset.attr_or("columns", 0u32)? {
+            0 => (sheet.width().saturating_sub(margin) + spacing) / (tile_size.x + spacing),
+            columns => columns,
+        };
+        if tile_count == 0 {
+            let rows = (sheet.height().saturating_sub(margin) + spacing) / (tile_size.y + spacing);
+            tile_count = columns * rows;
+        }
+        for id in 0..tile_count {
+            let x = margin + (id % columns.max(1)) * (tile_size.x + spacing);
+            let y = margin + (id / columns.max(1)) * (tile_size.y + spacing);
+            if x + tile_size.x <= sheet.width() && y + tile_size.y <= sheet.height() {
+                set_image(id, sheet.crop_imm(x, y, tile_size.x, tile_size.y));
+            }
+        }
+    }
+
+    for tile in tileset.children("tile") {
+        let id = tile.required::<u32>("id")?;
+        if let Some(image) = tile.child("image") {
+            let source = image.required::<String>("source")?;
+            set_image(id, load_image(&source)?);
+            tile_count = tile_count.max(id + 1);
+        }
+        let tile_properties = parse_properties(tile)?;
+        if !tile_properties.is_empty() {
+            properties.insert(first_id + id, tile_properties);
+        }
+    }
+
+    Ok(TiledTileset {
+        name: tileset.name().to_owned(),
+        first_id,
+        tile_count,
+        tile_size,
+    })
+}
+
+fn parse_object_group(
+    group: &XmlNode,
+    visible: bool,
+    pixels_per_unit: f32,
+    to_world: &impl Fn(Vector2<f32>) -> Vector2<f32>,
+) -> Result<TiledObjectGroup> {
+    let parse_points = |points: &str| {
+        points
+            .split_whitespace()
+            .map(|point| {
+                let (x, y) = point
+                    .split_once(',')
+                    .with_context(|| format!("Invalid point {point}!"))?;
+                let x = x
+                    .parse::<f32>()
+                    .map_err(|_| anyhow!("Invalid point {point}!"))?;
+                let y = y
+                    .parse::<f32>()
+                    .map_err(|_| anyhow!("Invalid point {point}!"))?;
+                Ok(Point2::new(x, -y) / pixels_per_unit)
+            })
+            .collect::<Result<Vec<_>>>()
+    };
+
+    let objects = group
+        .children("object")
+        .map(|object| {
+            let size = Vector2::new(
+                object.attr_or("width", 0.0f32)?,
+                object.attr_or("height", 0.0f32)?,
+            ) / pixels_per_unit;
+            let gid = object.attr_or("gid", 0u32)? & !FLAGS;
+            let shape = if gid != 0 {
+                TiledShape::Tile { id: gid, size }
+            } else if object.child("ellipse").is_some() {
+                TiledShape::Ellipse(size)
+            } else if object.child("point").is_some() {
+                TiledShape::Point
+            } else if let Some(polygon) = object.child("polygon") {
+                TiledShape::Polygon(parse_points(polygon.attr("points").unwrap_or_default())?)
+            } else if let Some(polyline) = object.child("polyline") {
+                TiledShape::Polyline(parse_points(polyline.attr("points").unwrap_or_default())?)
+            } else {
+                TiledShape::Rect(size)
+            };
+            Ok(TiledObject {
+                id: object.attr_or("id", 0u32)?,
+                name: object.name().to_owned(),
+                class: object
+                    .attr("class")
+                    .or_else(|| object.attr("type"))
+                    .unwrap_or_default()
+                    .to_owned(),
+                visible: object.attr_or("visible", 1u32)? != 0,
+                position: to_world(Vector2::new(
+                    object.attr_or("x", 0.0f32)?,
+                    object.attr_or("y", 0.0f32)?,
+                )),
+                rotation: -object.attr_or("rotation", 0.0f32)?.to_radians(),
+                shape,
+                properties: parse_properties(object)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(TiledObjectGroup {
+        name: group.name().to_owned(),
+        visible,
+        properties: parse_properties(group)?,
+        objects,
+    })
+}
+
+fn parse_properties(node: &XmlNode) -> Result<TiledProperties> {
+    let Some(properties) = node.child("properties") else {
+        return Ok(Default::default());
+    };
+    properties
+        .children("property")
+        .map(|property| {
+            let name = property.required::<String>("name")?;
+            // Multiline strings are stored as text instead of an attribute
+            let value = property.attr("value").unwrap_or(&property.text);
+            let invalid = || anyhow!("Invalid value {value} for property {name}!");
+            let value = match property.attr("type").unwrap_or("string") {
+                "int" => TiledProperty::Int(value.parse().map_err(|_| invalid())?),
+                "float" => TiledProperty::Float(value.parse().map_err(|_| invalid())?),
+                "bool" => TiledProperty::Bool(value.parse().map_err(|_| invalid())?),
+                "object" => TiledProperty::Object(value.parse().map_err(|_| invalid())?),
+                "class" => TiledProperty::Class(parse_properties(property)?),
+                _ => TiledProperty::String(value.to_owned()),
+            };
+            Ok((name, value))
+        })
+        .collect()
+}
+
+fn parent_dir(path: &str) -> &str {
+    path.rsplit_once('/')
+        .map(|(dir, _)| dir)
+        .unwrap_or_default()
+}
+
+fn join_path(dir: &str, path: &str) -> String {
+    let mut parts: Vec<&str> = dir.split('/').filter(|part| !part.is_empty()).collect();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." if parts.last().is_some_and(|last| *last != "..") => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+#[derive(Default)]
+struct XmlNode {
+    tag: String,
+    attributes: FxHashMap<String, String>,
+    children: Vec<XmlNode>,
+    text: String,
+}
+
+impl XmlNode {
+    fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut reader = quick_xml::Reader::from_reader(bytes);
+        let mut stack = vec![XmlNode::default()];
+        loop {
+            match reader.read_event()? {
+                Event::Start(element) => stack.push(Self::element(&element)?),
+                Event::Empty(element) => {
+                    let node = Self::element(&element)?;
+                    stack.last_mut().unwrap().children.push(node);
+                }
+                Event::End(_) => {
+                    if stack.len() < 2 {
+                        bail!("Unexpected closing tag!");
+                    }
+                    let node = stack.pop().unwrap();
+                    stack.last_mut().unwrap().children.push(node);
+                }
+                Event::Text(text) => stack.last_mut().unwrap().text.push_str(&text.unescape()?),
+                Event::CData(text) => stack
+                    .last_mut()
+                    .unwrap()
+                    .text
+                    .push_str(std::str::from_utf8(&text)?),
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        if stack.len() != 1 {
+            bail!("Unclosed tag <{}>!", stack.last().unwrap().tag);
+        }
+        stack
+            .pop()
+            .unwrap()
+            .children
+            .into_iter()
+            .next()
+            .context("Empty document!")
+    }
+
+    fn element(element: &BytesStart) -> Result<Self> {
+        let mut attributes = FxHashMap::default();
+        for attribute in element.attributes() {
+            let attribute = attribute?;
+            attributes.insert(
+                String::from_utf8_lossy(attribute.key.as_ref()).into_owned(),
+                attribute.unescape_value()?.into_owned(),
+            );
+        }
+        Ok(Self {
+            tag: String::from_utf8_lossy(element.name().as_ref()).into_owned(),
+            attributes,
+            children: vec![],
+            text: String::new(),
+        })
+    }
+
+    /// Value of the "name" attribute
+    fn name(&self) -> &str {
+        self.attr("name").unwrap_or_default()
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    fn attr_or<T: FromStr>(&self, name: &str, default: T) -> Result<T> {
+        match self.attr(name) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| anyhow!("Invalid value {value} for {name} in <{}>!", self.tag)),
+            None => Ok(default),
+        }
+    }
+
+    fn required<T: FromStr>(&self, name: &str) -> Result<T> {
+        let value = self
+            .attr(name)
+            .with_context(|| format!("Missing {name} in <{}>!", self.tag))?;
+        value
+            .parse()
+            .map_err(|_| anyhow!("Invalid value {value} for {name} in <{}>!", self.tag))
+    }
+
+    fn child(&self, name: &str) -> Option<&XmlNode> {
+        self.children.iter().find(|child| child.tag == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlNode> {
+        self.children.iter().filter(move |child| child.tag == name)
+    }
+}
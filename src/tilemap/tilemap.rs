@@ -0,0 +1,309 @@
+#[cfg(feature = "physics")]
+use crate::{
+    ecs::{ColliderComponent, EntityId, World},
+    physics::ColliderBuilder,
+};
+use crate::{
+    graphics::{AssetKey, Gpu, InstanceBuffer, SpriteArrayInstance2D},
+    math::{Isometry2, Vector2, AABB},
+};
+
+/// Index into the [SpriteArray](crate::graphics::SpriteArray) of a [TileMap] plus one.
+/// [TileMap::EMPTY] tiles are not drawn.
+pub type TileId = u32;
+
+struct TileChunk {
+    instances: InstanceBuffer<SpriteArrayInstance2D>,
+    aabb: AABB,
+    dirty: bool,
+}
+
+/// Grid of tiles that gets drawn in chunks of [TileMap::CHUNK_SIZE] x [TileMap::CHUNK_SIZE]
+/// tiles. Tile (0, 0) is the bottom left tile.
+pub struct TileMap {
+    size: Vector2<u32>,
+    tile_size: Vector2<f32>,
+    position: Vector2<f32>,
+    sprite_array: AssetKey,
+    tiles: Vec<TileId>,
+    chunks: Vec<TileChunk>,
+    chunk_amount: Vector2<u32>,
+}
+
+/// Describes the initial state of a [TileMap]
+#[derive(Clone, Debug)]
+pub struct TileMapBuilder {
+    pub size: Vector2<u32>,
+    pub tile_size: Vector2<f32>,
+    pub position: Vector2<f32>,
+    pub sprite_array: AssetKey,
+    /// Row major tiles starting at the bottom left, all [TileMap::EMPTY] if empty
+    pub tiles: Vec<TileId>,
+}
+
+impl TileMapBuilder {
+    pub fn new(size: Vector2<u32>, tile_size: Vector2<f32>, sprite_array: AssetKey) -> Self {
+        Self {
+            size,
+            tile_size,
+            position: Vector2::zeros(),
+            sprite_array,
+            tiles: vec![],
+        }
+    }
+
+    pub fn position(mut self, position: Vector2<f32>) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn tile_size(mut self, tile_size: Vector2<f32>) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    pub fn sprite_array(mut self, sprite_array: AssetKey) -> Self {
+        self.sprite_array = sprite_array;
+        self
+    }
+
+    pub fn tiles(mut self, tiles: Vec<TileId>) -> Self {
+        self.tiles = tiles;
+        self
+    }
+}
+
+impl TileMap {
+    pub const EMPTY: TileId = 0;
+    pub const CHUNK_SIZE: u32 = 32;
+
+    pub fn new(gpu: &Gpu, builder: TileMapBuilder) -> Self {
+        let size = builder.size;
+        let tiles = if builder.tiles.is_empty() {
+            vec![Self::EMPTY; (size.x * size.y) as usize]
+        } else {
+            assert_eq!(
+                builder.tiles.len(),
+                (size.x * size.y) as usize,
+                "Amount of tiles does not match the size of the TileMap!"
+            );
+            builder.tiles
+        };
+        let chunk_amount = Vector2::new(
+            size.x.div_ceil(Self::CHUNK_SIZE),
+            size.y.div_ceil(Self::CHUNK_SIZE),
+        );
+        let mut tilemap = Self {
+            size,
+            tile_size: builder.tile_size,
+            position: builder.position,
+            sprite_array: builder.sprite_array,
+            tiles,
+            chunks: Vec::new(),
+            chunk_amount,
+        };
+        tilemap.chunks = (0..chunk_amount.x * chunk_amount.y)
+            .map(|_| TileChunk {
+                instances: InstanceBuffer::empty(gpu, 0),
+                aabb: AABB::default(),
+                dirty: true,
+            })
+            .collect();
+        tilemap.update_chunk_bounds();
+        tilemap
+    }
+
+    /// Moves the bottom left corner of the map
+    pub fn with_position(mut self, position: Vector2<f32>) -> Self {
+        self.set_position(position);
+        self
+    }
+
+    pub fn set_position(&mut self, position: Vector2<f32>) {
+        self.position = position;
+        self.update_chunk_bounds();
+        self.chunks.iter_mut().for_each(|chunk| chunk.dirty = true);
+    }
+
+    fn update_chunk_bounds(&mut self) {
+        for (i, chunk) in self.chunks.iter_mut().enumerate() {
+            let chunk_pos = Vector2::new(
+                i as u32 % self.chunk_amount.x,
+                i as u32 / self.chunk_amount.x,
+            ) * Self::CHUNK_SIZE;
+            let end =
+                (chunk_pos + Vector2::new(Self::CHUNK_SIZE, Self::CHUNK_SIZE)).inf(&self.size);
+            chunk.aabb = AABB::new(
+                self.position + chunk_pos.cast::<f32>().component_mul(&self.tile_size),
+                self.position + end.cast::<f32>().component_mul(&self.tile_size),
+            );
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        assert!(
+            x < self.size.x && y < self.size.y,
+            "Tile ({x}, {y}) is out of bounds!"
+        );
+        (y * self.size.x + x) as usize
+    }
+
+    fn chunk_index(&self, x: u32, y: u32) -> usize {
+        ((y / Self::CHUNK_SIZE) * self.chunk_amount.x + x / Self::CHUNK_SIZE) as usize
+    }
+
+    pub fn tile(&self, x: u32, y: u32) -> TileId {
+        self.tiles[self.index(x, y)]
+    }
+
+    /// Changes a tile and marks its chunk to be rebuilt on the next [TileMap::update]
+    pub fn set_tile(&mut self, x: u32, y: u32, id: TileId) {
+        let index = self.index(x, y);
+        if self.tiles[index] != id {
+            self.tiles[index] = id;
+            let chunk = self.chunk_index(x, y);
+            self.chunks[chunk].dirty = true;
+        }
+    }
+
+    pub fn fill(&mut self, id: TileId) {
+        self.tiles.fill(id);
+        self.chunks.iter_mut().for_each(|chunk| chunk.dirty = true);
+    }
+
+    /// Rebuilds the instances of all chunks whose tiles changed
+    pub fn update(&mut self, gpu: &Gpu) {
+        let mut instances = Vec::new();
+        for i in 0..self.chunks.len() {
+            if !self.chunks[i].dirty {
+                continue;
+            }
+            let start = Vector2::new(
+                i as u32 % self.chunk_amount.x,
+                i as u32 / self.chunk_amount.x,
+            ) * Self::CHUNK_SIZE;
+            let end = (start + Vector2::new(Self::CHUNK_SIZE, Self::CHUNK_SIZE)).inf(&self.size);
+            instances.clear();
+            for y in start.y..end.y {
+                for x in start.x..end.x {
+                    let id = self.tiles[self.index(x, y)];
+                    if id != Self::EMPTY {
+                        instances.push(SpriteArrayInstance2D::new(
+                            Isometry2::new(self.tile_center(x, y), 0.0),
+                            self.tile_size,
+                            id - 1,
+                        ));
+                    }
+                }
+            }
+            let chunk = &mut self.chunks[i];
+            chunk.instances.write(gpu, &instances);
+            chunk.dirty = false;
+        }
+    }
+
+    pub fn tile_center(&self, x: u32, y: u32) -> Vector2<f32> {
+        self.position + Vector2::new(x as f32 + 0.5, y as f32 + 0.5).component_mul(&self.tile_size)
+    }
+
+    /// Returns the tile at a world position
+    pub fn tile_at(&self, position: Vector2<f32>) -> Option<Vector2<u32>> {
+        let tile = (position - self.position).component_div(&self.tile_size);
+        if tile.x < 0.0 || tile.y < 0.0 {
+            return None;
+        }
+        let tile = Vector2::new(tile.x as u32, tile.y as u32);
+        (tile.x < self.size.x && tile.y < self.size.y).then_some(tile)
+    }
+
+    /// Instances of all chunks that intersect `view`
+    pub fn visible_chunks(
+        &self,
+        view: &AABB,
+    ) -> impl Iterator<Item = &InstanceBuffer<SpriteArrayInstance2D>> {
+        self.chunks
+            .iter()
+            .filter(move |chunk| chunk.aabb.intersects(view))
+            .map(|chunk| &chunk.instances)
+    }
+
+    pub fn size(&self) -> Vector2<u32> {
+        self.size
+    }
+
+    pub fn tile_size(&self) -> Vector2<f32> {
+        self.tile_size
+    }
+
+    pub fn position(&self) -> Vector2<f32> {
+        self.position
+    }
+
+    pub fn sprite_array(&self) -> AssetKey {
+        self.sprite_array
+    }
+
+    pub fn aabb(&self) -> AABB {
+        AABB::new(
+            self.position,
+            self.position + self.size.cast::<f32>().component_mul(&self.tile_size),
+        )
+    }
+
+    /// Merges the solid tiles into as few rectangles as possible. Returns the bottom left tile
+    /// and the amount of tiles of each rectangle.
+    pub fn solid_rects(&self, solid: impl Fn(TileId) -> bool) -> Vec<(Vector2<u32>, Vector2<u32>)> {
+        let mut visited = vec![false; self.tiles.len()];
+        let free = |visited: &[bool], x: u32, y: u32| {
+            let index = self.index(x, y);
+            !visited[index] && solid(self.tiles[index])
+        };
+
+        let mut rects = vec![];
+        for y in 0..self.size.y {
+            for x in 0..self.size.x {
+                if !free(&visited, x, y) {
+                    continue;
+                }
+                let mut width = 1;
+                while x + width < self.size.x && free(&visited, x + width, y) {
+                    width += 1;
+                }
+                let mut height = 1;
+                while y + height < self.size.y
+                    && (x..x + width).all(|x| free(&visited, x, y + height))
+                {
+                    height += 1;
+                }
+                for y in y..y + height {
+                    for x in x..x + width {
+                        visited[self.index(x, y)] = true;
+                    }
+                }
+                rects.push((Vector2::new(x, y), Vector2::new(width, height)));
+            }
+        }
+        rects
+    }
+
+    /// Adds one cuboid [ColliderComponent] per merged rectangle of solid tiles
+    #[cfg(feature = "physics")]
+    pub fn generate_colliders(
+        &self,
+        world: &mut World,
+        solid: impl Fn(TileId) -> bool,
+    ) -> Vec<EntityId> {
+        self.solid_rects(solid)
+            .into_iter()
+            .map(|(start, amount)| {
+                let half_extents = amount.cast::<f32>().component_mul(&self.tile_size) / 2.0;
+                let center = self.position
+                    + start.cast::<f32>().component_mul(&self.tile_size)
+                    + half_extents;
+                world.add_entity((ColliderComponent::new(
+                    ColliderBuilder::cuboid(half_extents.x, half_extents.y).translation(center),
+                ),))
+            })
+            .collect()
+    }
+}
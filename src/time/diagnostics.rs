@@ -0,0 +1,368 @@
+use std::collections::VecDeque;
+
+use parking_lot::Mutex;
+
+#[cfg(feature = "log")]
+use crate::log::{error, info};
+use crate::{
+    ecs::{Component, World, WorldExt},
+    graphics::RenderStats,
+    time::{Duration, Instant, RecordConfig, Recorder, Recording},
+};
+
+/// CPU time of one system over the last [Diagnostics::TIMING_WINDOW]
+#[derive(Clone, Copy, Debug)]
+pub struct SystemTiming {
+    pub name: &'static str,
+    pub calls: usize,
+    pub min: Duration,
+    pub avg: Duration,
+    pub max: Duration,
+}
+
+struct SystemSamples {
+    name: &'static str,
+    samples: VecDeque<(Instant, Duration)>,
+}
+
+/// Performance overlay and measurements, accessible as `ctx.diagnostics`. Systems are only
+/// timed while the overlay is shown, otherwise every system only pays for one branch.
+pub struct Diagnostics {
+    shown: bool,
+    frame_times: VecDeque<Duration>,
+    render_stats: RenderStats,
+    adapter: Option<String>,
+    gpu_timings: Vec<(String, Duration)>,
+    systems: Mutex<Vec<SystemSamples>>,
+    components: Vec<(&'static str, fn(&World) -> usize)>,
+    component_counts: Vec<(&'static str, usize)>,
+    recorder: Option<Recorder>,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Diagnostics {
+    pub const FRAME_HISTORY: usize = 120;
+    pub const TIMING_WINDOW: Duration = Duration::from_secs(1);
+
+    pub fn new() -> Self {
+        Self {
+            shown: false,
+            frame_times: VecDeque::with_capacity(Self::FRAME_HISTORY),
+            render_stats: RenderStats::default(),
+            adapter: None,
+            gpu_timings: Vec::new(),
+            systems: Mutex::new(Vec::new()),
+            components: Vec::new(),
+            component_counts: Vec::new(),
+            recorder: None,
+        }
+    }
+
+    /// Shows the overlay and starts timing the systems. The overlay is drawn with egui when
+    /// the `gui` feature is enabled, otherwise with [DebugDraw](crate::graphics::DebugDraw)
+    /// text, which needs a font set with
+    /// [DebugDraw::set_font](crate::graphics::DebugDraw::set_font).
+    pub fn show(&mut self, shown: bool) {
+        self.shown = shown;
+        if !shown {
+            self.systems.lock().clear();
+        }
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.shown
+    }
+
+    /// Counts the entities with the component `C` while the overlay is shown
+    pub fn track_component<C: Component>(&mut self) {
+        let name = std::any::type_name::<C>();
+        if !self.components.iter().any(|(n, _)| *n == name) {
+            self.components
+                .push((name, |world| world.view::<C>().len()));
+        }
+    }
+
+    /// Frame times of the last [Diagnostics::FRAME_HISTORY] frames, oldest first
+    pub fn frame_times(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.frame_times.iter().copied()
+    }
+
+    /// Draw calls and instances of the last rendered frame
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_stats
+    }
+
+    /// GPU time per render pass, see [Gpu::pass_timings](crate::graphics::Gpu::pass_timings)
+    pub fn gpu_timings(&self) -> &[(String, Duration)] {
+        &self.gpu_timings
+    }
+
+    pub fn component_counts(&self) -> &[(&'static str, usize)] {
+        &self.component_counts
+    }
+
+    /// Timings of every system that ran during the last [Diagnostics::TIMING_WINDOW], in the
+    /// order they first ran
+    pub fn system_timings(&self) -> Vec<SystemTiming> {
+        let systems = self.systems.lock();
+        systems
+            .iter()
+            .filter(|system| !system.samples.is_empty())
+            .map(|system| {
+                let durations = system.samples.iter().map(|(_, duration)| *duration);
+                let total: Duration = durations.clone().sum();
+                SystemTiming {
+                    name: system.name,
+                    calls: system.samples.len(),
+                    min: durations.clone().min().unwrap_or_default(),
+                    avg: total / system.samples.len() as u32,
+                    max: durations.max().unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+
+    /// Starts recording frame statistics as CSV, which is written to `path` of the
+    /// [StorageLoader](crate::io::StorageLoader) once [Diagnostics::stop_recording] is called or
+    /// the app closes. Frames before this call, like a warmup period, are not recorded. A
+    /// running recording is stopped first.
+    pub fn record_to(&mut self, path: impl Into<String>, config: RecordConfig) {
+        self.stop_recording();
+        self.recorder = Some(Recorder::new(path.into(), config));
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Stops the recording, logs its [FrameSummary](crate::time::FrameSummary) and stores it
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        let recording = self.recorder.take()?.finish();
+        #[cfg(feature = "log")]
+        info!("Recording {}: {}", recording.path, recording.summary);
+        if let Err(_err) = recording.store(&*crate::app::global_storage()) {
+            #[cfg(feature = "log")]
+            error!("Failed to store recording {}: {_err}", recording.path);
+        }
+        Some(recording)
+    }
+
+    /// Start of a system measured with [Diagnostics::stop], [None] while the overlay is hidden
+    pub(crate) fn start(&self) -> Option<Instant> {
+        self.shown.then(Instant::now)
+    }
+
+    pub(crate) fn stop(&self, name: &'static str, start: Option<Instant>) {
+        let Some(start) = start else {
+            return;
+        };
+        let duration = start.elapsed();
+        let mut systems = self.systems.lock();
+        let index = match systems.iter().position(|system| system.name == name) {
+            Some(index) => index,
+            None => {
+                systems.push(SystemSamples {
+                    name,
+                    samples: VecDeque::new(),
+                });
+                systems.len() - 1
+            }
+        };
+        systems[index].samples.push_back((start, duration));
+    }
+
+    pub(crate) fn frame(
+        &mut self,
+        delta: Duration,
+        render_stats: RenderStats,
+        gpu_timings: Vec<(String, Duration)>,
+    ) {
+        if self.frame_times.len() == Self::FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(delta);
+        self.render_stats = render_stats;
+        self.gpu_timings = gpu_timings;
+
+        if self.shown {
+            let now = Instant::now();
+            for system in self.systems.get_mut() {
+                while system
+                    .samples
+                    .front()
+                    .is_some_and(|(start, _)| now.duration_since(*start) > Self::TIMING_WINDOW)
+                {
+                    system.samples.pop_front();
+                }
+            }
+        }
+    }
+
+    pub(crate) fn set_adapter(&mut self, info: &wgpu::AdapterInfo) {
+        self.adapter = Some(format!("{} ({:?})", info.name, info.backend));
+    }
+
+    pub(crate) fn count_components(&mut self, world: &World) {
+        if !self.shown {
+            return;
+        }
+        self.component_counts.clear();
+        for (name, count) in &self.components {
+            self.component_counts.push((name, (count)(world)));
+        }
+    }
+
+    pub(crate) fn record(&mut self, world: &World) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(
+                world,
+                self.frame_times.back().copied().unwrap_or_default(),
+                self.render_stats,
+                &self.gpu_timings,
+            );
+        }
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let frame = self.frame_times.back().copied().unwrap_or_default();
+        lines.push(format!(
+            "{:.0} fps, {:.2} ms",
+            1.0 / frame.as_secs_f64().max(f64::EPSILON),
+            frame.as_secs_f64() * 1000.0
+        ));
+        lines.push(format!(
+            "{} draw calls, {} instances",
+            self.render_stats.draw_calls, self.render_stats.instances
+        ));
+        if let Some(adapter) = &self.adapter {
+            lines.push(adapter.clone());
+        }
+        for (name, count) in &self.component_counts {
+            lines.push(format!("{}: {count}", short_name(name)));
+        }
+        for (pass, duration) in &self.gpu_timings {
+            lines.push(format!(
+                "GPU {pass}: {:.3} ms",
+                duration.as_secs_f64() * 1000.0
+            ));
+        }
+        for timing in self.system_timings() {
+            lines.push(format!(
+                "{}: {:.3} / {:.3} / {:.3} ms",
+                short_name(timing.name),
+                timing.min.as_secs_f64() * 1000.0,
+                timing.avg.as_secs_f64() * 1000.0,
+                timing.max.as_secs_f64() * 1000.0
+            ));
+        }
+        lines
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn gui(&self, gui: &crate::gui::GuiContext) {
+        if !self.shown {
+            return;
+        }
+        egui::Window::new("Diagnostics")
+            .default_pos(egui::pos2(10.0, 10.0))
+            .show(gui, |ui| {
+                let max = self
+                    .frame_times()
+                    .max()
+                    .unwrap_or_default()
+                    .as_secs_f32()
+                    .max(1.0 / 60.0);
+                let (rect, _) =
+                    ui.allocate_exact_size(egui::vec2(240.0, 60.0), egui::Sense::hover());
+                let painter = ui.painter_at(rect);
+                painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(128));
+                let step = rect.width() / (Self::FRAME_HISTORY - 1) as f32;
+                let points = self
+                    .frame_times()
+                    .enumerate()
+                    .map(|(i, time)| {
+                        egui::pos2(
+                            rect.left() + i as f32 * step,
+                            rect.bottom() - time.as_secs_f32() / max * rect.height(),
+                        )
+                    })
+                    .collect();
+                painter.add(egui::Shape::line(
+                    points,
+                    egui::Stroke::new(1.0, egui::Color32::LIGHT_GREEN),
+                ));
+
+                let lines = self.lines();
+                let (summary, systems) = lines.split_at(
+                    2 + self.adapter.is_some() as usize
+                        + self.component_counts.len()
+                        + self.gpu_timings.len(),
+                );
+                for line in summary {
+                    ui.label(line);
+                }
+                ui.separator();
+                ui.label("System min / avg / max");
+                for line in systems {
+                    ui.monospace(line);
+                }
+            });
+    }
+
+    #[cfg(all(feature = "debug-draw", feature = "text", not(feature = "gui")))]
+    pub(crate) fn debug_draw(
+        &self,
+        debug: &crate::graphics::DebugDraw,
+        camera: &crate::graphics::Camera2D,
+    ) {
+        use crate::{graphics::Color, math::Vector2};
+
+        if !self.shown {
+            return;
+        }
+        let fov = camera.fov();
+        let top_left = camera.translation() + Vector2::new(-fov.x, fov.y) * 0.95;
+        let line_height = fov.y * 0.05;
+
+        let max = self
+            .frame_times()
+            .max()
+            .unwrap_or_default()
+            .as_secs_f32()
+            .max(1.0 / 60.0);
+        let width = fov.x * 0.6;
+        let height = line_height * 3.0;
+        let step = width / (Self::FRAME_HISTORY - 1) as f32;
+        let bottom = top_left.y - height;
+        let mut last = None;
+        for (i, time) in self.frame_times().enumerate() {
+            let point = Vector2::new(
+                top_left.x + i as f32 * step,
+                bottom + time.as_secs_f32() / max * height,
+            );
+            if let Some(last) = last {
+                debug.line(last, point, Color::GREEN);
+            }
+            last = Some(point);
+        }
+
+        for (i, line) in self.lines().into_iter().enumerate() {
+            debug.text(
+                Vector2::new(top_left.x, bottom - line_height * (i + 1) as f32),
+                line,
+            );
+        }
+    }
+}
+
+/// Strips the module path of a type or function name
+fn short_name(name: &str) -> &str {
+    let name = name.trim_end_matches("::{{closure}}");
+    name.rsplit("::").next().unwrap_or(name)
+}
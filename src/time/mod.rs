@@ -1,4 +1,8 @@
+mod diagnostics;
+mod recorder;
 mod time_manager;
 
+pub use diagnostics::*;
 pub use instant::*;
+pub use recorder::*;
 pub use time_manager::*;
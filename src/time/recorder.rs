@@ -0,0 +1,196 @@
+use std::fmt::Write;
+
+use crate::{
+    ecs::{Component, World, WorldExt},
+    graphics::RenderStats,
+    io::StorageLoader,
+    time::Duration,
+};
+
+/// Column of a recording started with [Diagnostics::record_to](crate::time::Diagnostics::record_to)
+#[derive(Clone, Copy, Debug)]
+pub enum RecordColumn {
+    /// Frame time in milliseconds
+    FrameTime,
+    Fps,
+    DrawCalls,
+    Instances,
+    /// Sum of all [Diagnostics::gpu_timings](crate::time::Diagnostics::gpu_timings) in
+    /// milliseconds
+    GpuTime,
+    EntityCount(&'static str, fn(&World) -> usize),
+}
+
+impl RecordColumn {
+    /// Amount of entities with the component `C`
+    pub fn entity_count<C: Component>() -> Self {
+        let name = std::any::type_name::<C>();
+        Self::EntityCount(name.rsplit("::").next().unwrap_or(name), |world| {
+            world.view::<C>().len()
+        })
+    }
+
+    fn header(&self) -> &'static str {
+        match self {
+            RecordColumn::FrameTime => "frame_time_ms",
+            RecordColumn::Fps => "fps",
+            RecordColumn::DrawCalls => "draw_calls",
+            RecordColumn::Instances => "instances",
+            RecordColumn::GpuTime => "gpu_time_ms",
+            RecordColumn::EntityCount(name, _) => name,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordConfig {
+    /// Only every n-th frame is written as a row, the summary includes every frame
+    pub every_n_frames: u64,
+    pub columns: Vec<RecordColumn>,
+}
+
+impl Default for RecordConfig {
+    fn default() -> Self {
+        Self {
+            every_n_frames: 1,
+            columns: vec![
+                RecordColumn::FrameTime,
+                RecordColumn::DrawCalls,
+                RecordColumn::Instances,
+            ],
+        }
+    }
+}
+
+/// Frame time statistics of a recording
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameSummary {
+    pub frames: usize,
+    pub mean: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl FrameSummary {
+    fn new(mut frame_times: Vec<Duration>) -> Self {
+        if frame_times.is_empty() {
+            return Self::default();
+        }
+        frame_times.sort_unstable();
+        let percentile = |p: f64| {
+            let index = ((frame_times.len() - 1) as f64 * p).round() as usize;
+            frame_times[index]
+        };
+        Self {
+            frames: frame_times.len(),
+            mean: frame_times.iter().sum::<Duration>() / frame_times.len() as u32,
+            p95: percentile(0.95),
+            p99: percentile(0.99),
+            max: *frame_times.last().unwrap(),
+        }
+    }
+}
+
+impl std::fmt::Display for FrameSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} frames, mean: {:.2} ms, p95: {:.2} ms, p99: {:.2} ms, max: {:.2} ms",
+            self.frames,
+            self.mean.as_secs_f64() * 1000.0,
+            self.p95.as_secs_f64() * 1000.0,
+            self.p99.as_secs_f64() * 1000.0,
+            self.max.as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/// Finished recording, returned by
+/// [Diagnostics::stop_recording](crate::time::Diagnostics::stop_recording)
+#[derive(Clone, Debug)]
+pub struct Recording {
+    pub path: String,
+    pub csv: String,
+    pub summary: FrameSummary,
+}
+
+impl Recording {
+    pub fn store(&self, storage: &dyn StorageLoader) -> anyhow::Result<()> {
+        storage.store(&self.path, &self.csv)
+    }
+}
+
+pub(crate) struct Recorder {
+    path: String,
+    config: RecordConfig,
+    frame: u64,
+    csv: String,
+    frame_times: Vec<Duration>,
+}
+
+impl Recorder {
+    pub fn new(path: String, config: RecordConfig) -> Self {
+        assert!(config.every_n_frames > 0, "Cannot record every 0th frame!");
+        let mut csv = config
+            .columns
+            .iter()
+            .map(|column| column.header())
+            .collect::<Vec<_>>()
+            .join(",");
+        csv.push('\n');
+        Self {
+            path,
+            config,
+            frame: 0,
+            csv,
+            frame_times: Vec::new(),
+        }
+    }
+
+    pub fn record(
+        &mut self,
+        world: &World,
+        frame_time: Duration,
+        render_stats: RenderStats,
+        gpu_timings: &[(String, Duration)],
+    ) {
+        self.frame_times.push(frame_time);
+        self.frame += 1;
+        if (self.frame - 1) % self.config.every_n_frames != 0 {
+            return;
+        }
+
+        for (i, column) in self.config.columns.iter().enumerate() {
+            if i > 0 {
+                self.csv.push(',');
+            }
+            let _ = match column {
+                RecordColumn::FrameTime => {
+                    write!(self.csv, "{:.3}", frame_time.as_secs_f64() * 1000.0)
+                }
+                RecordColumn::Fps => write!(
+                    self.csv,
+                    "{:.1}",
+                    1.0 / frame_time.as_secs_f64().max(f64::EPSILON)
+                ),
+                RecordColumn::DrawCalls => write!(self.csv, "{}", render_stats.draw_calls),
+                RecordColumn::Instances => write!(self.csv, "{}", render_stats.instances),
+                RecordColumn::GpuTime => {
+                    let total: Duration = gpu_timings.iter().map(|(_, duration)| *duration).sum();
+                    write!(self.csv, "{:.3}", total.as_secs_f64() * 1000.0)
+                }
+                RecordColumn::EntityCount(_, count) => write!(self.csv, "{}", (count)(world)),
+            };
+        }
+        self.csv.push('\n');
+    }
+
+    pub fn finish(self) -> Recording {
+        Recording {
+            path: self.path,
+            csv: self.csv,
+            summary: FrameSummary::new(self.frame_times),
+        }
+    }
+}
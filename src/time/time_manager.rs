@@ -1,6 +1,9 @@
+use crate::ecs::tick_interval;
 pub use crate::time::{Duration, Instant};
 #[cfg(feature = "log")]
 use log::info;
+use rustc_hash::FxHashMap;
+use std::cell::{Cell, RefCell};
 
 pub struct TimeManager {
     delta_time: Duration,
@@ -12,10 +15,13 @@ pub struct TimeManager {
     total_frames: u64,
     fps_counter: u32,
     fps: u32,
+    fixed_delta: Cell<Duration>,
+    alphas: RefCell<FxHashMap<Duration, f32>>,
 }
 
 impl TimeManager {
     pub const MAX_FRAME_TIME: Duration = Duration::from_millis(50);
+    pub const MAX_FIXED_TICKS: u32 = 5;
     pub(crate) fn new() -> Self {
         let now = Instant::now();
         let elapsed = now.elapsed();
@@ -29,6 +35,8 @@ impl TimeManager {
             total_frames: 0,
             fps_counter: 0,
             fps: 0,
+            fixed_delta: Cell::new(Duration::ZERO),
+            alphas: Default::default(),
         }
     }
 
@@ -66,6 +74,30 @@ impl TimeManager {
         self.last_time = self.total_time;
     }
 
+    /// Advances the time by exactly `delta` instead of measuring it
+    pub(crate) fn tick_by(&mut self, delta: Duration) {
+        self.total_time += delta;
+        self.update_time = self.start_time + self.total_time;
+        self.delta_time = delta;
+        self.fps_counter += 1;
+        self.total_frames += 1;
+        if self.total_time > self.fps_time + Duration::from_secs(1) {
+            self.fps = self.fps_counter;
+            self.fps_time = self.total_time;
+            self.fps_counter = 0;
+        }
+        self.last_time = self.total_time;
+    }
+
+    /// Tick interval of the fixed update system that runs or ran last
+    pub(crate) fn set_fixed_delta(&self, fixed_delta: Duration) {
+        self.fixed_delta.set(fixed_delta);
+    }
+
+    pub(crate) fn set_alpha(&self, fixed_delta: Duration, alpha: f32) {
+        self.alphas.borrow_mut().insert(fixed_delta, alpha);
+    }
+
     pub const fn start(&self) -> Instant {
         self.start_time
     }
@@ -101,4 +133,59 @@ impl TimeManager {
     pub const fn frames_since_last_seconds(&self) -> u32 {
         self.fps_counter
     }
+
+    pub fn fixed_delta(&self) -> f32 {
+        self.fixed_delta.get().as_secs_f32()
+    }
+
+    pub fn fixed_delta_duration(&self) -> Duration {
+        self.fixed_delta.get()
+    }
+
+    /// Progress between the last two fixed steps in the range [0, 1). Use this to interpolate
+    /// between the previous and the current state when rendering. Inside a fixed update system
+    /// this is the alpha of its own tick rate, afterwards the alpha of the fixed update system
+    /// that ran last. Rendering and the world camera use the latter, with several tick rates
+    /// use [TimeManager::alpha_of] instead.
+    pub fn alpha(&self) -> f32 {
+        self.alpha_of_interval(self.fixed_delta.get())
+    }
+
+    /// Alpha of the fixed update systems with the given tick rate, `0.0` if none has run yet
+    pub fn alpha_of(&self, tick_rate: u32) -> f32 {
+        self.alpha_of_interval(tick_interval(tick_rate))
+    }
+
+    fn alpha_of_interval(&self, fixed_delta: Duration) -> f32 {
+        self.alphas
+            .borrow()
+            .get(&fixed_delta)
+            .copied()
+            .unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alpha_per_tick_rate() {
+        let time = TimeManager::new();
+        assert_eq!(time.alpha(), 0.0);
+
+        time.set_fixed_delta(tick_interval(50));
+        time.set_alpha(tick_interval(50), 0.25);
+        time.set_fixed_delta(tick_interval(20));
+        time.set_alpha(tick_interval(20), 0.75);
+        assert_eq!(time.alpha_of(50), 0.25);
+        assert_eq!(time.alpha_of(20), 0.75);
+        assert_eq!(time.alpha_of(60), 0.0);
+        // The fixed update system that ran last
+        assert_eq!(time.alpha(), 0.75);
+        assert_eq!(time.fixed_delta_duration(), Duration::from_millis(50));
+
+        time.set_fixed_delta(tick_interval(50));
+        assert_eq!(time.alpha(), 0.25);
+    }
 }
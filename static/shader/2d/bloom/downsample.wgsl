@@ -0,0 +1,41 @@
+@group(0) @binding(0)
+var<uniform> u_camera: mat4x4<f32>;
+
+@group(1) @binding(0)
+var u_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var u_sampler: sampler;
+
+@group(2) @binding(0)
+var<uniform> u_params: vec4<f32>;
+
+struct VertexInput {
+    @location(0) v_position: vec2<f32>,
+    @location(1) v_tex: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = u_camera * vec4<f32>(model.v_position, 0.0, 1.0);
+    out.tex = model.v_tex;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(u_diffuse));
+    let a = textureSample(u_diffuse, u_sampler, in.tex + texel * vec2<f32>(-1.0, -1.0)).rgb;
+    let b = textureSample(u_diffuse, u_sampler, in.tex + texel * vec2<f32>(1.0, -1.0)).rgb;
+    let c = textureSample(u_diffuse, u_sampler, in.tex + texel * vec2<f32>(-1.0, 1.0)).rgb;
+    let d = textureSample(u_diffuse, u_sampler, in.tex + texel * vec2<f32>(1.0, 1.0)).rgb;
+    let e = textureSample(u_diffuse, u_sampler, in.tex).rgb;
+    return vec4<f32>((a + b + c + d) * 0.125 + e * 0.5, 1.0);
+}
@@ -0,0 +1,42 @@
+// x: threshold, y: soft knee
+@group(0) @binding(0)
+var<uniform> u_camera: mat4x4<f32>;
+
+@group(1) @binding(0)
+var u_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var u_sampler: sampler;
+
+@group(2) @binding(0)
+var<uniform> u_params: vec4<f32>;
+
+struct VertexInput {
+    @location(0) v_position: vec2<f32>,
+    @location(1) v_tex: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = u_camera * vec4<f32>(model.v_position, 0.0, 1.0);
+    out.tex = model.v_tex;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(u_diffuse, u_sampler, in.tex).rgb;
+    let brightness = max(color.r, max(color.g, color.b));
+    let knee = u_params.x * u_params.y + 0.00001;
+    var soft = clamp(brightness - u_params.x + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee);
+    let contribution = max(soft, brightness - u_params.x) / max(brightness, 0.00001);
+    return vec4<f32>(color * contribution, 1.0);
+}
@@ -0,0 +1,24 @@
+@group(0) @binding(0)
+var u_diffuse: texture_2d<f32>;
+@group(0) @binding(1)
+var u_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex: vec2<f32>,
+}
+
+// Fullscreen triangle
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let tex = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    out.clip_position = vec4<f32>(tex.x * 2.0 - 1.0, 1.0 - tex.y * 2.0, 0.0, 1.0);
+    out.tex = tex;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(u_diffuse, u_sampler, in.tex);
+}
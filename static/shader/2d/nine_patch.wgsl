@@ -0,0 +1,59 @@
+@group(0) @binding(0)
+var<uniform> u_camera: mat4x4<f32>;
+
+@group(1) @binding(0)
+var u_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var u_sampler: sampler;
+
+struct VertexInput {
+    @location(0) v_cell: vec2<f32>,
+}
+
+struct InstanceInput {
+    @location(1) i_translation: vec2<f32>,
+    @location(2) i_scale_rotation: vec4<f32>,
+    @location(3) i_border: vec4<f32>,
+    @location(4) i_tex_border: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex: vec2<f32>,
+}
+
+fn axis_position(cell: u32, size: f32, start: f32, end: f32) -> f32 {
+    let half = size / 2.0;
+    let factor = min(1.0, size / max(start + end, 0.0001));
+    var positions = array<f32, 4>(-half, -half + start * factor, half - end * factor, half);
+    return positions[cell];
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    let m = instance.i_scale_rotation;
+    let scale = vec2<f32>(length(vec2<f32>(m.x, m.z)), length(vec2<f32>(m.y, m.w)));
+    let cell = vec2<u32>(u32(model.v_cell.x + 0.5), u32(model.v_cell.y + 0.5));
+
+    let local = vec2<f32>(
+        axis_position(cell.x, scale.x, instance.i_border.x, instance.i_border.y),
+        axis_position(cell.y, scale.y, instance.i_border.z, instance.i_border.w),
+    );
+    let pos = (local / scale) * mat2x2<f32>(m.xy, m.zw) + instance.i_translation;
+    out.clip_position = u_camera * vec4<f32>(pos, 0.0, 1.0);
+
+    var tex_u = array<f32, 4>(0.0, instance.i_tex_border.x, 1.0 - instance.i_tex_border.y, 1.0);
+    var tex_v = array<f32, 4>(1.0, 1.0 - instance.i_tex_border.z, instance.i_tex_border.w, 0.0);
+    out.tex = vec2<f32>(tex_u[cell.x], tex_v[cell.y]);
+
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(u_diffuse, u_sampler, in.tex);
+}
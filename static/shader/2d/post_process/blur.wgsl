@@ -0,0 +1,46 @@
+@group(0) @binding(0)
+var<uniform> u_camera: mat4x4<f32>;
+
+@group(1) @binding(0)
+var u_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var u_sampler: sampler;
+
+// xy: direction, z: radius in pixels
+@group(2) @binding(0)
+var<uniform> u_params: vec4<f32>;
+
+struct VertexInput {
+    @location(0) v_position: vec2<f32>,
+    @location(1) v_tex: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = u_camera * vec4<f32>(model.v_position, 0.0, 1.0);
+    out.tex = model.v_tex;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var weights = array<f32, 5>(0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+    let texel = 1.0 / vec2<f32>(textureDimensions(u_diffuse));
+    let step = u_params.xy * texel * u_params.z / 4.0;
+
+    var color = textureSample(u_diffuse, u_sampler, in.tex) * weights[0];
+    for (var i = 1; i < 5; i++) {
+        let offset = step * f32(i);
+        color += textureSample(u_diffuse, u_sampler, in.tex + offset) * weights[i];
+        color += textureSample(u_diffuse, u_sampler, in.tex - offset) * weights[i];
+    }
+    return color;
+}
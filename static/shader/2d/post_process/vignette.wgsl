@@ -0,0 +1,40 @@
+@group(0) @binding(0)
+var<uniform> u_camera: mat4x4<f32>;
+
+@group(1) @binding(0)
+var u_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var u_sampler: sampler;
+
+// x: intensity, y: radius, z: softness
+@group(2) @binding(0)
+var<uniform> u_params: vec4<f32>;
+
+struct VertexInput {
+    @location(0) v_position: vec2<f32>,
+    @location(1) v_tex: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = u_camera * vec4<f32>(model.v_position, 0.0, 1.0);
+    out.tex = model.v_tex;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(u_diffuse, u_sampler, in.tex);
+    let dist = distance(in.tex, vec2<f32>(0.5, 0.5));
+    let vignette = smoothstep(u_params.y, u_params.y - u_params.z, dist);
+    let factor = mix(1.0, vignette, u_params.x);
+    return vec4<f32>(color.rgb * factor, color.a);
+}
@@ -0,0 +1,91 @@
+const MAX_LIGHTS: u32 = 16;
+
+struct PointLight {
+    position: vec2<f32>,
+    height: f32,
+    radius: f32,
+    color: vec4<f32>,
+    falloff: f32,
+}
+
+struct Lights {
+    lights: array<PointLight, MAX_LIGHTS>,
+    ambient: vec4<f32>,
+    count: u32,
+}
+
+@group(0) @binding(0)
+var<uniform> u_camera: mat4x4<f32>;
+
+@group(1) @binding(0)
+var u_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var u_sampler: sampler;
+
+@group(2) @binding(0)
+var u_normal: texture_2d<f32>;
+@group(2) @binding(1)
+var u_normal_sampler: sampler;
+
+@group(3) @binding(0)
+var<uniform> u_lights: Lights;
+
+struct VertexInput {
+    @location(0) v_position: vec2<f32>,
+    @location(1) v_tex: vec2<f32>,
+}
+
+struct InstanceInput {
+    @location(2) i_translation: vec2<f32>,
+    @location(3) i_scale_rotation: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex: vec2<f32>,
+    @location(1) world_position: vec2<f32>,
+    // Inverse transpose of the instance matrix to bring the normals into world space
+    @location(2) normal_matrix: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    let m = mat2x2<f32>(instance.i_scale_rotation.xy, instance.i_scale_rotation.zw);
+    let pos = model.v_position * m + instance.i_translation;
+    out.clip_position = u_camera * vec4<f32>(pos, 0.0, 1.0);
+    out.tex = model.v_tex;
+    out.world_position = pos;
+    let s = instance.i_scale_rotation;
+    out.normal_matrix = vec4<f32>(s.w, -s.y, -s.z, s.x) / determinant(m);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let diffuse = textureSample(u_diffuse, u_sampler, in.tex);
+    let encoded = textureSample(u_normal, u_normal_sampler, in.tex).xyz * 2.0 - 1.0;
+    let n = in.normal_matrix;
+    let normal = normalize(vec3<f32>(
+        n.x * encoded.x + n.z * encoded.y,
+        n.y * encoded.x + n.w * encoded.y,
+        encoded.z
+    ));
+
+    var light = u_lights.ambient.rgb;
+    for (var i: u32 = 0; i < min(u_lights.count, MAX_LIGHTS); i = i + 1) {
+        let l = u_lights.lights[i];
+        let delta = vec3<f32>(l.position - in.world_position, l.height);
+        let distance = length(delta.xy);
+        if distance >= l.radius {
+            continue;
+        }
+        let lambert = max(dot(normal, normalize(delta)), 0.0);
+        let attenuation = pow(1.0 - distance / l.radius, l.falloff);
+        light += l.color.rgb * l.color.a * lambert * attenuation;
+    }
+    return vec4<f32>(diffuse.rgb * light, diffuse.a);
+}
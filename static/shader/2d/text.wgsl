@@ -24,7 +24,7 @@ struct VertexOutput {
     @location(0) tex: vec2<f32>,
     @location(1) color: vec4<f32>,
     @location(2) index: u32,
-}-
+}
 
 @vertex
 fn vs_main(
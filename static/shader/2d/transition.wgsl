@@ -0,0 +1,64 @@
+@group(0) @binding(0)
+var<uniform> u_camera: mat4x4<f32>;
+
+@group(1) @binding(0)
+var u_from: texture_2d<f32>;
+@group(1) @binding(1)
+var u_from_sampler: sampler;
+
+@group(2) @binding(0)
+var u_to: texture_2d<f32>;
+@group(2) @binding(1)
+var u_to_sampler: sampler;
+
+// [0] x: progress, y: mode (0: fade, 1: crossfade, 2: slide left, 3: slide right)
+// [1] fade color
+@group(3) @binding(0)
+var<uniform> u_params: array<vec4<f32>, 2>;
+
+struct VertexInput {
+    @location(0) v_position: vec2<f32>,
+    @location(1) v_tex: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = u_camera * vec4<f32>(model.v_position, 0.0, 1.0);
+    out.tex = model.v_tex;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let progress = u_params[0].x;
+    let mode = u_params[0].y;
+    let from_color = textureSample(u_from, u_from_sampler, in.tex);
+    let to_color = textureSample(u_to, u_to_sampler, in.tex);
+
+    if mode < 0.5 {
+        let color = u_params[1];
+        if progress < 0.5 {
+            return mix(from_color, color, progress * 2.0);
+        }
+        return mix(color, to_color, progress * 2.0 - 1.0);
+    } else if mode < 1.5 {
+        return mix(from_color, to_color, progress);
+    }
+
+    let offset = select(-progress, progress, mode < 2.5);
+    let tex = vec2<f32>(in.tex.x + offset, in.tex.y);
+    let shifted_from = textureSample(u_from, u_from_sampler, fract(tex));
+    let shifted_to = textureSample(u_to, u_to_sampler, fract(tex));
+    if tex.x >= 0.0 && tex.x < 1.0 {
+        return shifted_from;
+    }
+    return shifted_to;
+}
@@ -0,0 +1,62 @@
+@group(0) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+
+@group(1) @binding(0)
+var u_diffuse: texture_2d<f32>;
+@group(1) @binding(1)
+var u_sampler: sampler;
+
+struct VertexInput {
+    @location(0) v_position: vec2<f32>,
+    @location(1) v_tex: vec2<f32>,
+}
+
+struct InstanceInput {
+    @location(2) i_position: vec3<f32>,
+    @location(3) i_cylindrical: u32,
+    @location(4) i_size: vec2<f32>,
+    @location(5) i_uv_offset: vec2<f32>,
+    @location(6) i_uv_scale: vec2<f32>,
+    @location(7) i_color: vec4<f32>,
+    @location(8) i_alpha_cutoff: f32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) alpha_cutoff: f32,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    // The first two rows of the view projection point along the right and up axis of the
+    // view, because the projection only scales them
+    var right = normalize(vec3<f32>(camera[0][0], camera[1][0], camera[2][0]));
+    var up = normalize(vec3<f32>(camera[0][1], camera[1][1], camera[2][1]));
+    if instance.i_cylindrical != 0u {
+        right = normalize(vec3<f32>(right.x, 0.0, right.z));
+        up = vec3<f32>(0.0, 1.0, 0.0);
+    }
+
+    let offset = model.v_position * instance.i_size;
+    let world_position = instance.i_position + right * offset.x + up * offset.y;
+    var out: VertexOutput;
+    out.clip_position = camera * vec4<f32>(world_position, 1.0);
+    out.tex = model.v_tex * instance.i_uv_scale + instance.i_uv_offset;
+    out.color = instance.i_color;
+    out.alpha_cutoff = instance.i_alpha_cutoff;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(u_diffuse, u_sampler, in.tex) * in.color;
+    if color.a < in.alpha_cutoff {
+        discard;
+    }
+    return color;
+}
@@ -0,0 +1,124 @@
+@group(0) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) normal: vec3<f32>,
+}
+struct InstanceInput {
+    @location(3) instance_matrix_0: vec4<f32>,
+    @location(4) instance_matrix_1: vec4<f32>,
+    @location(5) instance_matrix_2: vec4<f32>,
+    @location(6) instance_matrix_3: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+    @location(1) world_position: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    let instance_matrix = mat4x4<f32>(
+        instance.instance_matrix_0,
+        instance.instance_matrix_1,
+        instance.instance_matrix_2,
+        instance.instance_matrix_3,
+    );
+    // The cofactor matrix is the inverse transpose scaled by the determinant, so it keeps
+    // normals perpendicular to the surface under non uniform scaling
+    let c0 = instance_matrix[0].xyz;
+    let c1 = instance_matrix[1].xyz;
+    let c2 = instance_matrix[2].xyz;
+    let cofactor = mat3x3<f32>(cross(c1, c2), cross(c2, c0), cross(c0, c1));
+    let handedness = sign(dot(c0, cross(c1, c2)));
+
+    let world_position = instance_matrix * vec4<f32>(model.position, 1.0);
+    var out: VertexOutput;
+    out.tex_coords = model.tex_coords;
+    out.world_position = world_position.xyz;
+    out.normal = cofactor * model.normal * handedness;
+    out.clip_position = camera * world_position;
+    return out;
+}
+
+// Fragment shader
+
+const MAX_POINT_LIGHTS: u32 = 16u;
+const SHININESS: f32 = 32.0;
+
+struct DirectionalLight {
+    direction: vec3<f32>,
+    intensity: f32,
+    color: vec4<f32>,
+}
+
+struct PointLight {
+    position: vec3<f32>,
+    range: f32,
+    color: vec4<f32>,
+    intensity: f32,
+}
+
+struct Lights {
+    directional: DirectionalLight,
+    points: array<PointLight, MAX_POINT_LIGHTS>,
+    ambient: vec4<f32>,
+    camera_position: vec3<f32>,
+    count: u32,
+}
+
+@group(1) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(1)@binding(1)
+var s_diffuse: sampler;
+
+@group(2) @binding(0)
+var<uniform> lights: Lights;
+
+// Blinn-Phong diffuse and specular term of a light coming from `light_dir`
+fn blinn_phong(normal: vec3<f32>, view_dir: vec3<f32>, light_dir: vec3<f32>) -> vec2<f32> {
+    let diffuse = max(dot(normal, light_dir), 0.0);
+    let half_dir = normalize(light_dir + view_dir);
+    let specular = select(0.0, pow(max(dot(normal, half_dir), 0.0), SHININESS), diffuse > 0.0);
+    return vec2<f32>(diffuse, specular);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let albedo = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    let normal = normalize(in.normal);
+    let view_dir = normalize(lights.camera_position - in.world_position);
+
+    var diffuse = lights.ambient.rgb;
+    var specular = vec3<f32>(0.0);
+
+    let directional = lights.directional;
+    if directional.intensity > 0.0 {
+        let terms = blinn_phong(normal, view_dir, normalize(-directional.direction));
+        let radiance = directional.color.rgb * directional.intensity;
+        diffuse += radiance * terms.x;
+        specular += radiance * terms.y;
+    }
+
+    for (var i = 0u; i < min(lights.count, MAX_POINT_LIGHTS); i++) {
+        let light = lights.points[i];
+        let to_light = light.position - in.world_position;
+        let dist = length(to_light);
+        let attenuation = pow(clamp(1.0 - dist / light.range, 0.0, 1.0), 2.0);
+        if attenuation > 0.0 {
+            let terms = blinn_phong(normal, view_dir, to_light / dist);
+            let radiance = light.color.rgb * light.intensity * attenuation;
+            diffuse += radiance * terms.x;
+            specular += radiance * terms.y;
+        }
+    }
+
+    return vec4<f32>(albedo.rgb * diffuse + specular, albedo.a);
+}
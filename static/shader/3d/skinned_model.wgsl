@@ -0,0 +1,58 @@
+@group(0) @binding(0)
+var<uniform> camera: mat4x4<f32>;
+
+// Must match MAX_JOINTS in src/graphics/skinned_model.rs
+@group(2) @binding(0)
+var<uniform> joints: array<mat4x4<f32>, 128>;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) tex_coords: vec2<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) joints: vec4<u32>,
+    @location(4) weights: vec4<f32>,
+}
+struct InstanceInput {
+    @location(5) instance_matrix_0: vec4<f32>,
+    @location(6) instance_matrix_1: vec4<f32>,
+    @location(7) instance_matrix_2: vec4<f32>,
+    @location(8) instance_matrix_3: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    let instance_matrix = mat4x4<f32>(
+        instance.instance_matrix_0,
+        instance.instance_matrix_1,
+        instance.instance_matrix_2,
+        instance.instance_matrix_3,
+    );
+    let skin_matrix = joints[model.joints.x] * model.weights.x
+        + joints[model.joints.y] * model.weights.y
+        + joints[model.joints.z] * model.weights.z
+        + joints[model.joints.w] * model.weights.w;
+    var out: VertexOutput;
+    out.tex_coords = model.tex_coords;
+    out.clip_position = camera * instance_matrix * skin_matrix * vec4<f32>(model.position, 1.0);
+    return out;
+}
+
+// Fragment shader
+
+@group(1) @binding(0)
+var t_diffuse: texture_2d<f32>;
+@group(1)@binding(1)
+var s_diffuse: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+}
@@ -0,0 +1,36 @@
+// Inverse of the view projection without the camera translation
+@group(0) @binding(0)
+var<uniform> inverse_view_proj: mat4x4<f32>;
+
+@group(1) @binding(0)
+var t_sky: texture_cube<f32>;
+@group(1) @binding(1)
+var s_sky: sampler;
+
+struct VertexInput {
+    @location(0) v_position: vec2<f32>,
+    @location(1) v_tex: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc: vec2<f32>,
+}
+
+@vertex
+fn vs_main(
+    model: VertexInput,
+) -> VertexOutput {
+    // Scales the unit quad to the whole screen at the far plane
+    let ndc = model.v_position * 2.0;
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(ndc, 1.0, 1.0);
+    out.ndc = ndc;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let world = inverse_view_proj * vec4<f32>(in.ndc, 1.0, 1.0);
+    return textureSample(t_sky, s_sky, world.xyz / world.w);
+}